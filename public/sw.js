@@ -0,0 +1,55 @@
+// Keeps the app working offline. Every file the app loads is cached as it's fetched, and the
+// page sends the ones it loaded before the worker took over. The page itself is fetched from
+// the network first so updates come through, and bundle files, whose names change with their
+// contents, from the cache first.
+const CACHE = "spreadsheet-v1";
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(caches.open(CACHE).then((cache) => cache.add("/")));
+    self.skipWaiting();
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches.keys()
+            .then((keys) => Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))))
+            .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener("message", (event) => {
+    if (event.data?.type === "cache") {
+        event.waitUntil(
+            caches.open(CACHE).then((cache) =>
+                Promise.all(event.data.urls.map((url) => cache.add(url).catch(() => {})))
+            )
+        );
+    }
+});
+
+const fetchAndCache = async (request) => {
+    const response = await fetch(request);
+    if (response.ok) {
+        const cache = await caches.open(CACHE);
+        await cache.put(request, response.clone());
+    }
+    return response;
+};
+
+self.addEventListener("fetch", (event) => {
+    const request = event.request;
+    if (request.method !== "GET" || new URL(request.url).origin !== self.location.origin) {
+        return;
+    }
+    if (request.mode === "navigate") {
+        event.respondWith(
+            fetchAndCache(request).catch(async () =>
+                (await caches.match(request)) ?? (await caches.match("/"))
+            )
+        );
+        return;
+    }
+    event.respondWith(
+        caches.match(request).then((cached) => cached ?? fetchAndCache(request))
+    );
+});
@@ -1,2 +1,44 @@
+pub mod autocomplete;
+pub mod autosave;
+pub mod chart;
+pub mod chart_export;
+pub mod clipboard;
+pub mod conditional_format;
+pub mod csv_import;
+pub mod cycle_banner;
+pub mod dialog;
+pub mod edit_history;
+pub mod evaluate_formula;
+pub mod files;
+pub mod filter;
+pub mod flash_fill;
+pub mod go_to;
+pub mod google_sheets;
 pub mod grid;
 pub mod header;
+pub mod json_import;
+#[cfg(feature = "desktop")]
+pub mod native_files;
+pub mod number_format;
+pub mod ods;
+pub mod offline;
+pub mod outline;
+pub mod paste_special;
+pub mod presence;
+pub mod print;
+pub mod properties;
+pub mod protected_ranges;
+pub mod read_only;
+pub mod recent_files;
+pub mod reference_highlight;
+pub mod remote_data;
+pub mod search;
+pub mod server_files;
+pub mod sheet_tabs;
+pub mod side_panel;
+pub mod solver;
+pub mod statistics;
+pub mod table;
+pub mod trace;
+pub mod validation;
+pub mod versions;
@@ -0,0 +1,174 @@
+use dioxus::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::engine::{
+    editing,
+    parser::{self, FunctionHint},
+};
+
+pub enum AutocompleteKey {
+    Ignored,
+    Handled,
+    Completed(String),
+}
+
+/// Function-name suggestions for a formula input, tracking its caret through the DOM
+#[derive(Clone, Copy, PartialEq)]
+pub struct Autocomplete {
+    input_id: &'static str,
+    caret: Signal<usize>,
+    selected: Signal<usize>,
+    dismissed: Signal<bool>,
+}
+
+pub fn use_autocomplete(input_id: &'static str) -> Autocomplete {
+    Autocomplete {
+        input_id,
+        caret: use_signal(|| 0),
+        selected: use_signal(|| 0),
+        dismissed: use_signal(|| false),
+    }
+}
+
+impl Autocomplete {
+    pub fn track_caret(&mut self, content: &str) {
+        if let Some(caret) = get_caret(self.input_id) {
+            self.caret.set(utf16_to_byte_offset(content, caret));
+        }
+    }
+    pub fn on_input(&mut self, content: &str) {
+        self.track_caret(content);
+        self.selected.set(0);
+        self.dismissed.set(false);
+    }
+
+    pub fn suggestions(&self, content: &str) -> Vec<FunctionHint> {
+        if (self.dismissed)() {
+            return vec![];
+        }
+        match editing::function_prefix_at(content, (self.caret)()) {
+            Some((_, prefix)) => parser::matching_functions(prefix),
+            None => vec![],
+        }
+    }
+
+    pub fn handle_key(&mut self, key: &Key, content: &str) -> AutocompleteKey {
+        let suggestions = self.suggestions(content);
+        if suggestions.is_empty() {
+            return AutocompleteKey::Ignored;
+        }
+        let count = suggestions.len();
+        let selected = (self.selected)().min(count - 1);
+        match key {
+            Key::ArrowDown => {
+                self.selected.set((selected + 1) % count);
+                AutocompleteKey::Handled
+            }
+            Key::ArrowUp => {
+                self.selected.set((selected + count - 1) % count);
+                AutocompleteKey::Handled
+            }
+            Key::Tab => match self.complete(content, suggestions[selected].name) {
+                Some(completed) => AutocompleteKey::Completed(completed),
+                None => AutocompleteKey::Ignored,
+            },
+            Key::Escape => {
+                self.dismissed.set(true);
+                AutocompleteKey::Handled
+            }
+            _ => AutocompleteKey::Ignored,
+        }
+    }
+
+    /// Writes the completion straight into the DOM input so the caret lands after `name(`
+    pub fn complete(&mut self, content: &str, name: &str) -> Option<String> {
+        let (completed, caret) = editing::complete_function(content, (self.caret)(), name)?;
+        set_input_value(self.input_id, &completed, byte_to_utf16_offset(&completed, caret));
+        self.caret.set(caret);
+        self.selected.set(0);
+        Some(completed)
+    }
+}
+
+#[component]
+pub fn AutocompleteDropdown(
+    autocomplete: Autocomplete,
+    suggestions: Vec<FunctionHint>,
+    on_pick: EventHandler<&'static str>,
+) -> Element {
+    let selected = (autocomplete.selected)().min(suggestions.len().saturating_sub(1));
+
+    rsx! {
+        if !suggestions.is_empty() {
+            div {
+                class: "autocomplete-dropdown",
+                for (i, hint) in suggestions.into_iter().enumerate() {
+                    div {
+                        class: if i == selected { "autocomplete-item autocomplete-item-selected" } else { "autocomplete-item" },
+                        // Mouse down instead of click so the input doesn't lose focus first
+                        onmousedown: move |evt| {
+                            evt.prevent_default();
+                            on_pick.call(hint.name);
+                        },
+                        span { class: "autocomplete-signature", "{hint.signature}" }
+                        span { class: "autocomplete-description", "{hint.description}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// F4 while editing: cycles the absolute markers of the reference under the caret
+pub fn cycle_reference_at_caret(input_id: &str, content: &str) -> Option<String> {
+    let caret = utf16_to_byte_offset(content, get_caret(input_id)?);
+    let (cycled, caret) = editing::cycle_absolute_reference(content, caret)?;
+    set_input_value(input_id, &cycled, byte_to_utf16_offset(&cycled, caret));
+    Some(cycled)
+}
+
+pub fn move_caret_to_end(input_id: &str) {
+    if let Some(input) = get_input(input_id) {
+        let end = input.value().encode_utf16().count() as u32;
+        let _ = input.set_selection_range(end, end);
+    }
+}
+
+pub fn get_caret(input_id: &str) -> Option<usize> {
+    let caret = get_input(input_id)?.selection_start().ok()??;
+    Some(caret as usize)
+}
+
+pub fn set_input_value(input_id: &str, value: &str, caret: usize) {
+    if let Some(input) = get_input(input_id) {
+        input.set_value(value);
+        let _ = input.set_selection_range(caret as u32, caret as u32);
+    }
+}
+
+fn get_input(input_id: &str) -> Option<web_sys::HtmlInputElement> {
+    web_sys::window()?
+        .document()?
+        .get_element_by_id(input_id)?
+        .dyn_into()
+        .ok()
+}
+
+// The DOM counts caret positions in UTF-16 code units, Rust strings in bytes
+pub fn utf16_to_byte_offset(content: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in content.char_indices() {
+        if units >= utf16_offset {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    content.len()
+}
+
+pub fn byte_to_utf16_offset(content: &str, byte_offset: usize) -> usize {
+    content[..byte_offset.min(content.len())]
+        .chars()
+        .map(char::len_utf16)
+        .sum()
+}
@@ -0,0 +1,191 @@
+use dioxus::prelude::*;
+
+use crate::model::{
+    command::GridCommand,
+    file::{read_workbook, SessionFile},
+    grid::Grid,
+};
+
+/// How long after the last change the workbook is saved
+const AUTOSAVE_DELAY_MS: u32 = 1000;
+
+/// Defines `autosaved(action, key, value)`, which loads, saves or deletes a value of the
+/// autosave store in IndexedDB and resolves to what was loaded. Everything the app keeps
+/// between visits goes through IndexedDB, which works offline. Browsers that don't allow
+/// storage get null back for everything.
+const AUTOSAVE_STORE_JS: &str = r#"
+    const autosaved = async (action, key, value) => {
+        try {
+            const db = await new Promise((resolve, reject) => {
+                const request = indexedDB.open("spreadsheet", 1);
+                request.onupgradeneeded = () => request.result.createObjectStore("autosave");
+                request.onsuccess = () => resolve(request.result);
+                request.onerror = () => reject(request.error);
+            });
+            const store = db
+                .transaction("autosave", action === "load" ? "readonly" : "readwrite")
+                .objectStore("autosave");
+            const request = action === "load" ? store.get(key)
+                : action === "save" ? store.put(value, key)
+                : store.delete(key);
+            const result = await new Promise((resolve, reject) => {
+                request.onsuccess = () => resolve(request.result);
+                request.onerror = () => reject(request.error);
+            });
+            db.close();
+            return action === "load" ? result ?? null : null;
+        } catch {
+            return null;
+        }
+    };
+"#;
+
+/// Loads, saves or deletes the autosaved workbook, returning what was loaded
+const AUTOSAVE_JS: &str = r#"
+    const [action, text] = await dioxus.recv();
+    return await autosaved(action, "workbook", text);
+"#;
+
+/// Keeps the session with the grid's scroll position, again when the page is left since
+/// scrolling alone doesn't change the workbook
+const SAVE_SESSION_JS: &str = r#"
+    const session = await dioxus.recv();
+    const save = () => {
+        const container = document.getElementById("scroll-container");
+        if (container) {
+            session.scroll = [container.scrollLeft, container.scrollTop];
+        }
+        return autosaved("save", "session", JSON.stringify(session));
+    };
+    await save();
+    window.onpagehide = save;
+"#;
+
+const LOAD_SESSION_JS: &str = r#"
+    const text = await autosaved("load", "session");
+    try {
+        return JSON.parse(text);
+    } catch {
+        return null;
+    }
+"#;
+
+const DISCARD_SESSION_JS: &str = r#"
+    await autosaved("delete", "session");
+"#;
+
+/// Runs a script with `autosaved` defined, for anything else kept between visits
+pub fn autosave_eval(script: &str) -> document::Eval {
+    document::eval(&format!("{}{}", AUTOSAVE_STORE_JS, script))
+}
+
+/// Scrolls the grid once it's drawn the restored sheet
+const SCROLL_JS: &str = r#"
+    const [left, top] = await dioxus.recv();
+    requestAnimationFrame(() => {
+        const container = document.getElementById("scroll-container");
+        if (container) {
+            container.scrollLeft = left;
+            container.scrollTop = top;
+        }
+    });
+"#;
+
+async fn autosave(action: &str, text: String) -> Option<String> {
+    let eval = autosave_eval(AUTOSAVE_JS);
+    let _ = eval.send((action, text));
+    eval.join::<Option<String>>().await.ok().flatten()
+}
+
+/// Saves the workbook to browser storage a moment after each change, and on launch offers
+/// to bring back what was saved last time, along with the sheet, selections and scroll
+/// position. Nothing is saved until that's been answered, so the empty workbook doesn't take
+/// its place.
+#[component]
+pub fn Autosave(mut grid: Signal<Grid>) -> Element {
+    let mut saved = use_signal(|| None::<String>);
+    let mut enabled = use_signal(|| false);
+    let mut last_saved = use_signal(String::new);
+    let mut generation = use_signal(|| 0u32);
+
+    use_future(move || async move {
+        let text = autosave("load", String::new()).await;
+        match text {
+            Some(text) => saved.set(Some(text)),
+            None => enabled.set(true),
+        }
+    });
+
+    use_effect(move || {
+        grid.read();
+        if !*enabled.peek() {
+            return;
+        }
+        let current = *generation.peek() + 1;
+        generation.set(current);
+        spawn(async move {
+            let wait = format!("await new Promise((resolve) => setTimeout(resolve, {AUTOSAVE_DELAY_MS}));");
+            let _ = document::eval(&wait).await;
+            if *generation.peek() != current {
+                return;
+            }
+            let eval = autosave_eval(SAVE_SESSION_JS);
+            let _ = eval.send(grid.peek().session_file());
+            let _ = eval.await;
+            let text = grid.peek().save_workbook();
+            if *last_saved.peek() != text {
+                last_saved.set(text.clone());
+                autosave("save", text).await;
+            }
+        });
+    });
+
+    let Some(text) = saved() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "autosave-banner",
+
+            span { "Restore the workbook from your last session?" }
+            button {
+                onclick: move |_| {
+                    let text = text.clone();
+                    spawn(async move {
+                        match read_workbook(&text) {
+                            Ok(workbook) => grid.write().apply(GridCommand::OpenWorkbook(workbook)),
+                            Err(e) => error!("{e}"),
+                        }
+                        let session = autosave_eval(LOAD_SESSION_JS)
+                            .join::<Option<SessionFile>>()
+                            .await
+                            .ok()
+                            .flatten();
+                        if let Some(session) = session {
+                            let scroll = session.scroll;
+                            grid.write().apply(GridCommand::RestoreSession(session));
+                            let eval = document::eval(SCROLL_JS);
+                            let _ = eval.send(scroll);
+                            let _ = eval.await;
+                        }
+                        saved.set(None);
+                        enabled.set(true);
+                    });
+                },
+                "Restore"
+            }
+            button {
+                onclick: move |_| {
+                    spawn(async move {
+                        autosave("discard", String::new()).await;
+                    });
+                    let _ = autosave_eval(DISCARD_SESSION_JS);
+                    saved.set(None);
+                    enabled.set(true);
+                },
+                "Discard autosave"
+            }
+        }
+    }
+}
@@ -0,0 +1,895 @@
+use std::{f64::consts::PI, rc::Rc};
+
+use dioxus::{
+    html::{geometry::ClientPoint, input_data::MouseButton},
+    prelude::*,
+};
+
+use crate::{
+    components::{
+        chart_export::{chart_element_id, export_chart, ExportFormat},
+        dialog::{close_dialog, Dialog},
+        grid::grid_point,
+    },
+    engine::{
+        chart::{linear_fit, moving_average, Axis},
+        number_format::FormatCode,
+    },
+    model::{
+        chart::{
+            AxisOptions, Chart, ChartAxis, ChartData, ChartId, ChartKind, ChartSeries, Trendline,
+            CHART_KINDS,
+        },
+        command::GridCommand,
+        format::NumberFormat,
+        grid::{column_index_to_letter, coords_to_cell_address, Grid},
+    },
+};
+
+const SERIES_COLORS: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#9c755f",
+];
+
+fn series_color(index: usize) -> &'static str {
+    SERIES_COLORS[index % SERIES_COLORS.len()]
+}
+
+/// A chart being dragged somewhere else, by how far the mouse has gone since grabbing it
+#[derive(Clone, Copy, PartialEq)]
+struct ChartDrag {
+    id: ChartId,
+    start: ClientPoint,
+    dx: i32,
+    dy: i32,
+}
+
+/// The active sheet's charts, floating over the grid at their anchor cells. They're drawn
+/// from the grid as it is, so they follow whatever recalculates.
+#[component]
+pub fn Charts(grid: Signal<Grid>, dialog: Signal<Option<Dialog>>) -> Element {
+    let mut dragging = use_signal(|| None::<ChartDrag>);
+    let mut menu = use_signal(|| None::<(ChartId, ClientPoint)>);
+    let charts = grid.read().sheet().charts.clone();
+
+    rsx! {
+        for chart in charts {
+            {
+                let id = chart.id;
+                let rect = grid.read().get_cell_rect(chart.anchor);
+                let (dx, dy) = match dragging() {
+                    Some(drag) if drag.id == id => (drag.dx, drag.dy),
+                    _ => (0, 0),
+                };
+                let data = grid.read().sheet().chart_data(&chart);
+                rsx! {
+                    div {
+                        class: "chart",
+                        style: "left: {rect.x + dx}px; top: {rect.y + dy}px; width: {chart.width}px; height: {chart.height}px;",
+                        onmousedown: move |evt| {
+                            evt.stop_propagation();
+                            evt.prevent_default();
+                            if evt.trigger_button() == Some(MouseButton::Secondary) {
+                                return;
+                            }
+                            let start = grid_point(&grid.read(), evt.client_coordinates());
+                            dragging.set(Some(ChartDrag { id, start, dx: 0, dy: 0 }));
+                        },
+                        ondoubleclick: move |_| dialog.set(Some(Dialog::Chart(id))),
+                        oncontextmenu: move |evt| {
+                            evt.prevent_default();
+                            menu.set(Some((id, grid_point(&grid.read(), evt.client_coordinates()))));
+                        },
+
+                        ChartSvg { chart: chart.clone(), data }
+                        button {
+                            class: "chart-edit",
+                            "tooltip-text": "Edit chart",
+                            onmousedown: move |evt| evt.stop_propagation(),
+                            onclick: move |_| dialog.set(Some(Dialog::Chart(id))),
+                            lucide_dioxus::Pencil { size: 14 }
+                        }
+                    }
+                }
+            }
+        }
+        // Catches the mouse anywhere while a chart is dragged, dropping it on the cell under
+        // its new top-left corner
+        if dragging().is_some() {
+            div {
+                class: "chart-drag-overlay",
+                onmousemove: move |evt| {
+                    if let Some(mut drag) = dragging() {
+                        let point = grid_point(&grid.read(), evt.client_coordinates());
+                        drag.dx = (point.x - drag.start.x).round() as i32;
+                        drag.dy = (point.y - drag.start.y).round() as i32;
+                        dragging.set(Some(drag));
+                    }
+                },
+                onmouseup: move |_| {
+                    let Some(drag) = dragging.take() else {
+                        return;
+                    };
+                    let chart = grid.read().sheet().get_chart(drag.id).cloned();
+                    if let Some(mut chart) = chart.filter(|_| (drag.dx, drag.dy) != (0, 0)) {
+                        let rect = grid.read().get_cell_rect(chart.anchor);
+                        chart.anchor = grid.read().get_cell_at(rect.x + drag.dx, rect.y + drag.dy);
+                        grid.write().apply(GridCommand::SetChart(chart));
+                    }
+                },
+            }
+        }
+        if let Some((id, point)) = menu() {
+            ChartMenu { grid, dialog, menu, id, point }
+        }
+    }
+}
+
+#[component]
+fn ChartMenu(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    menu: Signal<Option<(ChartId, ClientPoint)>>,
+    id: ChartId,
+    point: ClientPoint,
+) -> Element {
+    let mut export = move |format: ExportFormat| {
+        let title = grid
+            .read()
+            .sheet()
+            .get_chart(id)
+            .map(|chart| chart.title.clone())
+            .unwrap_or_default();
+        spawn(export_chart(id, title, format));
+        menu.set(None);
+    };
+
+    rsx! {
+        div {
+            class: "context-menu-backdrop",
+            onmousedown: move |_| menu.set(None),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                menu.set(None);
+            },
+        }
+        div {
+            class: "context-menu",
+            style: "left: {point.x}px; top: {point.y}px;",
+            button {
+                onclick: move |_| {
+                    menu.set(None);
+                    dialog.set(Some(Dialog::Chart(id)));
+                },
+                "Edit chart"
+            }
+            button {
+                onclick: move |_| export(ExportFormat::Svg),
+                "Download as SVG"
+            }
+            button {
+                onclick: move |_| export(ExportFormat::Png),
+                "Download as PNG"
+            }
+        }
+    }
+}
+
+/// Room around the plot for the title, axis labels and legend
+struct Margins {
+    top: f64,
+    right: f64,
+    bottom: f64,
+    left: f64,
+}
+
+#[component]
+fn ChartSvg(chart: Chart, data: ChartData) -> Element {
+    let (width, height) = (chart.width as f64, chart.height as f64);
+    let legend = chart.kind == ChartKind::Pie || data.series.len() > 1;
+    let secondary = chart.kind != ChartKind::Pie && data.series.iter().any(|s| s.secondary);
+    let legend_width = if legend { 120.0 } else { 0.0 };
+    let margins = Margins {
+        top: if chart.title.is_empty() { 16.0 } else { 36.0 },
+        right: f64::max(legend_width, 16.0) + if secondary { 48.0 } else { 0.0 },
+        bottom: if chart.x_title.is_empty() { 32.0 } else { 48.0 },
+        left: if chart.y_title.is_empty() { 48.0 } else { 64.0 },
+    };
+    let plot_width = (width - margins.left - margins.right).max(1.0);
+    let plot_height = (height - margins.top - margins.bottom).max(1.0);
+    let legend_entries = match chart.kind {
+        ChartKind::Pie => data.categories.clone(),
+        _ => data
+            .series
+            .iter()
+            .map(|series| match series.secondary {
+                true => format!("{} (right)", series.name),
+                false => series.name.clone(),
+            })
+            .collect(),
+    };
+
+    rsx! {
+        svg {
+            id: "{chart_element_id(chart.id)}",
+            width: "{width}",
+            height: "{height}",
+
+            if !chart.title.is_empty() {
+                text {
+                    class: "chart-title",
+                    x: "{width / 2.0}",
+                    y: "22",
+                    text_anchor: "middle",
+                    "{chart.title}"
+                }
+            }
+            g {
+                transform: "translate({margins.left}, {margins.top})",
+                {
+                    match chart.kind {
+                        ChartKind::Pie => rsx! { PiePlot { data, width: plot_width, height: plot_height } },
+                        _ => rsx! { XyPlot { chart: chart.clone(), data, width: plot_width, height: plot_height } },
+                    }
+                }
+            }
+            if chart.kind != ChartKind::Pie && !chart.x_title.is_empty() {
+                text {
+                    class: "chart-axis-title",
+                    x: "{margins.left + plot_width / 2.0}",
+                    y: "{height - 8.0}",
+                    text_anchor: "middle",
+                    "{chart.x_title}"
+                }
+            }
+            if chart.kind != ChartKind::Pie && !chart.y_title.is_empty() {
+                text {
+                    class: "chart-axis-title",
+                    transform: "translate(16, {margins.top + plot_height / 2.0}) rotate(-90)",
+                    text_anchor: "middle",
+                    "{chart.y_title}"
+                }
+            }
+            if legend {
+                g {
+                    transform: "translate({width - legend_width + 12.0}, {margins.top})",
+                    for (index, name) in legend_entries.into_iter().enumerate() {
+                        rect {
+                            x: "0",
+                            y: "{index as f64 * 18.0}",
+                            width: "10",
+                            height: "10",
+                            fill: series_color(index),
+                        }
+                        text {
+                            class: "chart-label",
+                            x: "16",
+                            y: "{index as f64 * 18.0 + 9.0}",
+                            "{name}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Bars, lines or points against value axes, categories or x values along the bottom. Series
+/// on the secondary axis are scaled to it, labeled on the right.
+#[component]
+fn XyPlot(chart: Chart, data: ChartData, width: f64, height: f64) -> Element {
+    let kind = chart.kind;
+    let value_axis = |secondary: bool, options: &AxisOptions| {
+        let values = data
+            .series
+            .iter()
+            .filter(|series| series.secondary == secondary)
+            .flat_map(|series| series.values.iter().flatten().copied())
+            .collect::<Vec<_>>();
+        let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        // Bars grow from zero, so it has to be on the axis
+        let axis = match (values.is_empty(), kind) {
+            (true, _) => Axis::new(0.0, 0.0),
+            (false, ChartKind::Bar) => Axis::new(low.min(0.0), high.max(0.0)),
+            (false, _) => Axis::new(low, high),
+        };
+        axis.bounded(options.min, options.max)
+    };
+    let y_axis = value_axis(false, &chart.y_axis);
+    let secondary_axis = data
+        .series
+        .iter()
+        .any(|series| series.secondary)
+        .then(|| value_axis(true, &chart.secondary_axis));
+    let axis_of = |secondary: bool| match (secondary, &secondary_axis) {
+        (true, Some(axis)) => axis,
+        _ => &y_axis,
+    };
+    let y = |secondary: bool, value: f64| height - axis_of(secondary).fraction(value) * height;
+    let zero = |secondary: bool| {
+        let axis = axis_of(secondary);
+        y(secondary, 0.0f64.clamp(axis.min, axis.max))
+    };
+
+    // Scatter plots put each point at its x value, the others space categories evenly. Both
+    // place points by a position along the x axis, category positions being their index.
+    let x_values = data
+        .categories
+        .iter()
+        .map(|category| category.parse::<f64>().ok())
+        .collect::<Vec<_>>();
+    let x_axis = (kind == ChartKind::Scatter).then(|| {
+        let known = x_values.iter().flatten().copied();
+        let low = known.clone().fold(f64::INFINITY, f64::min);
+        let high = known.fold(f64::NEG_INFINITY, f64::max);
+        let axis = match low.is_finite() {
+            true => Axis::new(low, high),
+            false => Axis::new(0.0, 0.0),
+        };
+        axis.bounded(chart.x_axis.min, chart.x_axis.max)
+    });
+    let slot = width / data.categories.len().max(1) as f64;
+    let position = |index: usize| -> Option<f64> {
+        match &x_axis {
+            Some(_) => x_values[index],
+            None => Some(index as f64),
+        }
+    };
+    let to_x = |position: f64| match &x_axis {
+        Some(axis) => axis.fraction(position) * width,
+        None => slot * (position + 0.5),
+    };
+    let x = |index: usize| position(index).map(to_x);
+
+    let bar_width = slot * 0.8 / data.series.len().max(1) as f64;
+    let bars = match kind {
+        ChartKind::Bar => data
+            .series
+            .iter()
+            .enumerate()
+            .flat_map(|(s, series)| {
+                let zero = zero(series.secondary);
+                series.values.iter().enumerate().filter_map(move |(i, value)| {
+                    let top = y(series.secondary, (*value)?);
+                    let left = slot * (i as f64 + 0.1) + bar_width * s as f64;
+                    Some((s, left, top.min(zero), (top - zero).abs()))
+                })
+            })
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let lines = match kind {
+        ChartKind::Line => data
+            .series
+            .iter()
+            .enumerate()
+            .map(|(s, series)| {
+                let points = series
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| Some((x(i)?, y(series.secondary, (*value)?))));
+                (s, line_path(points))
+            })
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let points = match kind {
+        ChartKind::Line | ChartKind::Scatter => data
+            .series
+            .iter()
+            .enumerate()
+            .flat_map(|(s, series)| {
+                series
+                    .values
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(i, value)| Some((s, i, series.secondary, (*value)?)))
+            })
+            .filter_map(|(s, i, secondary, value)| Some((s, x(i)?, y(secondary, value))))
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let trendlines = data
+        .series
+        .iter()
+        .enumerate()
+        .filter_map(|(s, series)| {
+            let d = match series.trendline? {
+                // Drawn across the points it was fitted to
+                Trendline::Linear => {
+                    let fitted = series
+                        .values
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, value)| Some((position(i)?, (*value)?)))
+                        .collect::<Vec<_>>();
+                    let (slope, intercept) = linear_fit(&fitted)?;
+                    let first = fitted.iter().map(|(x, _)| *x).fold(f64::INFINITY, f64::min);
+                    let last = fitted.iter().map(|(x, _)| *x).fold(f64::NEG_INFINITY, f64::max);
+                    line_path([first, last].into_iter().map(|position| {
+                        Some((to_x(position), y(series.secondary, slope * position + intercept)))
+                    }))
+                }
+                Trendline::MovingAverage(period) => line_path(
+                    moving_average(&series.values, period)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, value)| Some((x(i)?, y(series.secondary, value?)))),
+                ),
+            };
+            Some((s, d))
+        })
+        .collect::<Vec<_>>();
+
+    let x_labels = match &x_axis {
+        Some(axis) => axis
+            .ticks
+            .iter()
+            .map(|tick| (axis.fraction(*tick) * width, axis_label(&chart.x_axis, *tick)))
+            .collect::<Vec<_>>(),
+        None => data
+            .categories
+            .iter()
+            .enumerate()
+            .map(|(i, category)| (slot * (i as f64 + 0.5), category.clone()))
+            .collect(),
+    };
+    let y_labels = y_axis
+        .ticks
+        .iter()
+        .map(|tick| (y(false, *tick), axis_label(&chart.y_axis, *tick)))
+        .collect::<Vec<_>>();
+    let secondary_labels = secondary_axis
+        .iter()
+        .flat_map(|axis| axis.ticks.iter())
+        .map(|tick| (y(true, *tick), axis_label(&chart.secondary_axis, *tick)))
+        .collect::<Vec<_>>();
+    let clip = format!("chart-plot-{}", chart.id);
+    let baseline = zero(false);
+
+    rsx! {
+        defs {
+            clipPath {
+                id: "{clip}",
+                rect { x: "0", y: "0", width: "{width}", height: "{height}" }
+            }
+        }
+        for (y, label) in y_labels {
+            line {
+                class: "chart-gridline",
+                x1: "0",
+                x2: "{width}",
+                y1: "{y}",
+                y2: "{y}",
+            }
+            text {
+                class: "chart-label",
+                x: "-6",
+                y: "{y}",
+                text_anchor: "end",
+                dominant_baseline: "middle",
+                "{label}"
+            }
+        }
+        for (y, label) in secondary_labels {
+            text {
+                class: "chart-label",
+                x: "{width + 6.0}",
+                y: "{y}",
+                dominant_baseline: "middle",
+                "{label}"
+            }
+        }
+        line { class: "chart-axis", x1: "0", x2: "{width}", y1: "{baseline}", y2: "{baseline}" }
+        for (x, label) in x_labels {
+            text {
+                class: "chart-label",
+                x: "{x}",
+                y: "{height + 16.0}",
+                text_anchor: "middle",
+                "{label}"
+            }
+        }
+        // Values outside a fixed axis range are cut off at the plot's edges
+        g {
+            clip_path: "url(#{clip})",
+            for (s, left, top, bar_height) in bars {
+                rect {
+                    x: "{left}",
+                    y: "{top}",
+                    width: "{bar_width}",
+                    height: "{bar_height}",
+                    fill: series_color(s),
+                }
+            }
+            for (s, d) in lines {
+                path {
+                    d: "{d}",
+                    fill: "none",
+                    stroke: series_color(s),
+                    stroke_width: "2",
+                }
+            }
+            for (s, x, y) in points {
+                circle { cx: "{x}", cy: "{y}", r: "3", fill: series_color(s) }
+            }
+            for (s, d) in trendlines {
+                path {
+                    class: "chart-trendline",
+                    d: "{d}",
+                    stroke: series_color(s),
+                }
+            }
+        }
+    }
+}
+
+/// A line through points, broken where one is missing
+fn line_path(points: impl Iterator<Item = Option<(f64, f64)>>) -> String {
+    let mut d = String::new();
+    let mut pen_down = false;
+    for point in points {
+        match point {
+            Some((x, y)) => {
+                let command = if pen_down { "L" } else { "M" };
+                d.push_str(&format!("{} {} {} ", command, x, y));
+                pen_down = true;
+            }
+            None => pen_down = false,
+        }
+    }
+    d
+}
+
+/// The first series' positive values as slices of a circle
+#[component]
+fn PiePlot(data: ChartData, width: f64, height: f64) -> Element {
+    let values = data
+        .series
+        .first()
+        .map(|series| {
+            series
+                .values
+                .iter()
+                .map(|value| value.filter(|value| *value > 0.0).unwrap_or(0.0))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let total = values.iter().sum::<f64>();
+    let radius = width.min(height) / 2.0;
+    let (cx, cy) = (width / 2.0, height / 2.0);
+    let point = |angle: f64| (cx + radius * angle.sin(), cy - radius * angle.cos());
+
+    let mut slices = Vec::new();
+    let mut start = 0.0;
+    for (index, value) in values.iter().enumerate() {
+        if *value == 0.0 {
+            continue;
+        }
+        let sweep = value / total * 2.0 * PI;
+        let d = match sweep >= 2.0 * PI - 1e-9 {
+            // An arc can't end where it starts, so a whole circle is two halves
+            true => format!(
+                "M {cx} {} A {radius} {radius} 0 1 1 {cx} {} A {radius} {radius} 0 1 1 {cx} {} Z",
+                cy - radius,
+                cy + radius,
+                cy - radius
+            ),
+            false => {
+                let (x1, y1) = point(start);
+                let (x2, y2) = point(start + sweep);
+                let large = if sweep > PI { 1 } else { 0 };
+                format!("M {cx} {cy} L {x1} {y1} A {radius} {radius} 0 {large} 1 {x2} {y2} Z")
+            }
+        };
+        slices.push((index, d));
+        start += sweep;
+    }
+
+    rsx! {
+        for (index, d) in slices {
+            path { class: "chart-slice", d: "{d}", fill: series_color(index) }
+        }
+    }
+}
+
+/// A tick's value without the float noise of stepping by fractions, in the axis' format
+fn axis_label(options: &AxisOptions, value: f64) -> String {
+    let rounded = (value * 1e9).round() / 1e9;
+    options.format.apply(&format!("{}", rounded + 0.0))
+}
+
+#[component]
+pub fn ChartDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    original: Chart,
+) -> Element {
+    let id = original.id;
+    let mut range = use_signal(|| {
+        format!(
+            "{}:{}",
+            coords_to_cell_address(original.range.top_left()),
+            coords_to_cell_address(original.range.bottom_right())
+        )
+    });
+    let mut chart = use_signal(|| original);
+
+    // The chart as edited, None while the range can't be read on the active sheet
+    let edited = move || -> Option<Chart> {
+        let grid = grid.read();
+        let (sheet, range) = grid.resolve_range(range().trim())?;
+        if sheet != grid.view.active_sheet {
+            return None;
+        }
+        let mut chart = chart();
+        chart.range = range;
+        Some(chart)
+    };
+    // Every column past the first can be a series, named as the chart would name it
+    let columns = edited()
+        .map(|chart| {
+            let (first, last) = (chart.range.top_left().column, chart.range.bottom_right().column);
+            let all = Chart {
+                series: (first + 1..=last).map(ChartSeries::new).collect(),
+                ..chart
+            };
+            let names = grid.read().sheet().chart_data(&all).series;
+            all.series
+                .iter()
+                .zip(names)
+                .map(|(series, data)| {
+                    (series.column, format!("{}: {}", column_index_to_letter(series.column), data.name))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    rsx! {
+        div {
+            class: "chart-dialog",
+
+            label {
+                "Type"
+                select {
+                    value: "{CHART_KINDS.iter().position(|(kind, _)| *kind == chart().kind).unwrap_or(0)}",
+                    onchange: move |evt| {
+                        if let Some((kind, _)) = evt.value().parse::<usize>().ok().and_then(|i| CHART_KINDS.get(i)) {
+                            chart.write().kind = *kind;
+                        }
+                    },
+                    for (index, (_, name)) in CHART_KINDS.iter().enumerate() {
+                        option { value: "{index}", "{name}" }
+                    }
+                }
+            }
+            label {
+                "Title"
+                input {
+                    value: "{chart().title}",
+                    oninput: move |evt| chart.write().title = evt.value(),
+                }
+            }
+            div {
+                class: "chart-dialog-row",
+                label {
+                    "Horizontal axis title"
+                    input {
+                        value: "{chart().x_title}",
+                        oninput: move |evt| chart.write().x_title = evt.value(),
+                    }
+                }
+                label {
+                    "Vertical axis title"
+                    input {
+                        value: "{chart().y_title}",
+                        oninput: move |evt| chart.write().y_title = evt.value(),
+                    }
+                }
+            }
+            label {
+                "Data range"
+                input {
+                    value: "{range}",
+                    oninput: move |evt| range.set(evt.value()),
+                }
+            }
+            div { class: "dialog-message", "Series" }
+            for (column, name) in columns {
+                {
+                    let included = chart().series.iter().find(|series| series.column == column).copied();
+                    let trendline = included.and_then(|series| series.trendline);
+                    let mut edit_series = move |edit: &dyn Fn(&mut ChartSeries)| {
+                        if let Some(series) = chart.write().series.iter_mut().find(|series| series.column == column) {
+                            edit(series);
+                        }
+                    };
+                    rsx! {
+                        div {
+                            class: "chart-dialog-series",
+                            label {
+                                class: "chart-dialog-check",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: included.is_some(),
+                                    onchange: move |evt| {
+                                        let series = &mut chart.write().series;
+                                        series.retain(|series| series.column != column);
+                                        if evt.checked() {
+                                            series.push(ChartSeries::new(column));
+                                            series.sort_by_key(|series| series.column);
+                                        }
+                                    },
+                                }
+                                "{name}"
+                            }
+                            if included.is_some() && chart().kind != ChartKind::Pie {
+                                label {
+                                    class: "chart-dialog-check",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: included.is_some_and(|series| series.secondary),
+                                        onchange: move |evt| edit_series(&|series: &mut ChartSeries| series.secondary = evt.checked()),
+                                    }
+                                    "Right axis"
+                                }
+                                select {
+                                    "tooltip-text": "Trendline",
+                                    value: match trendline {
+                                        None => "none",
+                                        Some(Trendline::Linear) => "linear",
+                                        Some(Trendline::MovingAverage(_)) => "average",
+                                    },
+                                    onchange: move |evt| {
+                                        let trendline = match evt.value().as_str() {
+                                            "linear" => Some(Trendline::Linear),
+                                            "average" => Some(Trendline::MovingAverage(2)),
+                                            _ => None,
+                                        };
+                                        edit_series(&|series: &mut ChartSeries| series.trendline = trendline);
+                                    },
+                                    option { value: "none", "No trendline" }
+                                    option { value: "linear", "Linear trendline" }
+                                    option { value: "average", "Moving average" }
+                                }
+                                if let Some(Trendline::MovingAverage(period)) = trendline {
+                                    input {
+                                        class: "chart-dialog-period",
+                                        r#type: "number",
+                                        min: "2",
+                                        "tooltip-text": "Period",
+                                        value: "{period}",
+                                        oninput: move |evt| if let Ok(period) = evt.value().parse::<usize>() {
+                                            edit_series(&|series: &mut ChartSeries| series.trendline = Some(Trendline::MovingAverage(period.max(2))));
+                                        },
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if chart().kind != ChartKind::Pie {
+                div { class: "dialog-message", "Axes. Leave a bound blank to fit it to the values." }
+                if chart().kind == ChartKind::Scatter {
+                    AxisFields { chart, axis: ChartAxis::X, label: "Horizontal" }
+                }
+                AxisFields { chart, axis: ChartAxis::Y, label: "Vertical" }
+                if chart().series.iter().any(|series| series.secondary) {
+                    AxisFields { chart, axis: ChartAxis::Secondary, label: "Right" }
+                }
+            }
+            div {
+                class: "chart-dialog-row",
+                label {
+                    "Width"
+                    input {
+                        r#type: "number",
+                        value: "{chart().width}",
+                        oninput: move |evt| if let Ok(width) = evt.value().parse::<i32>() {
+                            chart.write().width = width.max(120);
+                        },
+                    }
+                }
+                label {
+                    "Height"
+                    input {
+                        r#type: "number",
+                        value: "{chart().height}",
+                        oninput: move |evt| if let Ok(height) = evt.value().parse::<i32>() {
+                            chart.write().height = height.max(90);
+                        },
+                    }
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| {
+                    grid.write().apply(GridCommand::DeleteChart(id));
+                    close_dialog(dialog, scroll_container);
+                },
+                "Delete"
+            }
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: edited().is_none(),
+                onclick: move |_| {
+                    if let Some(chart) = edited() {
+                        grid.write().apply(GridCommand::SetChart(chart));
+                        close_dialog(dialog, scroll_container);
+                    }
+                },
+                "OK"
+            }
+        }
+    }
+}
+
+/// Bounds and label format of one of a chart's value axes
+#[component]
+fn AxisFields(chart: Signal<Chart>, axis: ChartAxis, label: &'static str) -> Element {
+    let options = chart().axis(axis).clone();
+    let bound_text = |bound: Option<f64>| bound.map(|bound| bound.to_string()).unwrap_or_default();
+    let code = match &options.format {
+        NumberFormat::Custom(code) => code.clone(),
+        _ => String::new(),
+    };
+    // Blank goes back to fitting the values, anything else has to be a number
+    let parse_bound = |text: String| -> Option<Option<f64>> {
+        match text.trim() {
+            "" => Some(None),
+            text => text.parse().ok().map(Some),
+        }
+    };
+
+    rsx! {
+        div {
+            class: "chart-dialog-row",
+            span { class: "chart-dialog-axis", "{label}" }
+            label {
+                "Minimum"
+                input {
+                    value: "{bound_text(options.min)}",
+                    placeholder: "Auto",
+                    oninput: move |evt| if let Some(min) = parse_bound(evt.value()) {
+                        chart.write().axis_mut(axis).min = min;
+                    },
+                }
+            }
+            label {
+                "Maximum"
+                input {
+                    value: "{bound_text(options.max)}",
+                    placeholder: "Auto",
+                    oninput: move |evt| if let Some(max) = parse_bound(evt.value()) {
+                        chart.write().axis_mut(axis).max = max;
+                    },
+                }
+            }
+            label {
+                "Format"
+                input {
+                    value: "{code}",
+                    placeholder: "General, or like 0.0%",
+                    oninput: move |evt| {
+                        let code = evt.value();
+                        let format = match code.trim() {
+                            "" => Some(NumberFormat::General),
+                            code => FormatCode::parse(code).map(|_| NumberFormat::Custom(code.to_string())),
+                        };
+                        if let Some(format) = format {
+                            chart.write().axis_mut(axis).format = format;
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use dioxus::prelude::*;
+
+use crate::model::chart::ChartId;
+
+/// Copies a chart's SVG with the styles the page gives it written onto each element, so it
+/// looks the same outside the page, then downloads it as it is or drawn onto a canvas
+const EXPORT_CHART_JS: &str = r#"
+    const [id, name, png] = await dioxus.recv();
+    const source = document.getElementById(id);
+    if (!source) {
+        return;
+    }
+    const copy = source.cloneNode(true);
+    const properties = [
+        "fill", "stroke", "stroke-width", "stroke-dasharray", "font-family", "font-size",
+        "font-weight", "text-anchor", "dominant-baseline",
+    ];
+    const originals = [source, ...source.querySelectorAll("*")];
+    [copy, ...copy.querySelectorAll("*")].forEach((element, i) => {
+        const style = getComputedStyle(originals[i]);
+        element.setAttribute("style", properties
+            .map((property) => `${property}: ${style.getPropertyValue(property)}`)
+            .join("; "));
+        element.removeAttribute("class");
+    });
+    copy.setAttribute("xmlns", "http://www.w3.org/2000/svg");
+    copy.removeAttribute("id");
+    const background = document.createElementNS("http://www.w3.org/2000/svg", "rect");
+    background.setAttribute("width", "100%");
+    background.setAttribute("height", "100%");
+    background.setAttribute("fill", "white");
+    copy.insertBefore(background, copy.firstChild);
+    const svg = new XMLSerializer().serializeToString(copy);
+
+    const download = (blob, extension) => {
+        const url = URL.createObjectURL(blob);
+        const anchor = document.createElement("a");
+        anchor.href = url;
+        anchor.download = `${name}.${extension}`;
+        anchor.click();
+        URL.revokeObjectURL(url);
+    };
+    if (!png) {
+        download(new Blob([svg], { type: "image/svg+xml" }), "svg");
+        return;
+    }
+    // Twice the size, so it stays sharp in documents
+    const scale = 2;
+    const image = new Image();
+    image.src = "data:image/svg+xml;charset=utf-8," + encodeURIComponent(svg);
+    await image.decode();
+    const canvas = new OffscreenCanvas(source.width.baseVal.value * scale, source.height.baseVal.value * scale);
+    const context = canvas.getContext("2d");
+    context.scale(scale, scale);
+    context.drawImage(image, 0, 0);
+    download(await canvas.convertToBlob({ type: "image/png" }), "png");
+"#;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Svg,
+    Png,
+}
+
+/// The id of a chart's `svg` element, to find it by when exporting
+pub fn chart_element_id(id: ChartId) -> String {
+    format!("chart-svg-{}", id)
+}
+
+/// Downloads a chart as a standalone image named after its title
+pub async fn export_chart(id: ChartId, title: String, format: ExportFormat) {
+    let name = title
+        .chars()
+        .filter(|c| !r#"\/:*?"<>|"#.contains(*c))
+        .collect::<String>();
+    let name = match name.trim() {
+        "" => "chart".to_string(),
+        name => name.to_string(),
+    };
+    let eval = document::eval(EXPORT_CHART_JS);
+    let _ = eval.send((chart_element_id(id), name, format == ExportFormat::Png));
+    let _ = eval.await;
+}
@@ -0,0 +1,60 @@
+use dioxus::prelude::*;
+
+const WRITE_CLIPBOARD_JS: &str = r#"
+    const [text, html] = await dioxus.recv();
+    try {
+        await navigator.clipboard.write([new ClipboardItem({
+            "text/plain": new Blob([text], { type: "text/plain" }),
+            "text/html": new Blob([html], { type: "text/html" }),
+        })]);
+    } catch {
+        await navigator.clipboard.writeText(text);
+    }
+"#;
+
+const READ_CLIPBOARD_JS: &str = r#"
+    try {
+        let text = "";
+        let html = null;
+        for (const item of await navigator.clipboard.read()) {
+            if (item.types.includes("text/plain")) {
+                text = await (await item.getType("text/plain")).text();
+            }
+            if (item.types.includes("text/html")) {
+                html = await (await item.getType("text/html")).text();
+            }
+        }
+        return [text, html];
+    } catch {
+        try {
+            return [await navigator.clipboard.readText(), null];
+        } catch {
+            return null;
+        }
+    }
+"#;
+
+/// Puts copied cells on the system clipboard as plain text and an HTML table,
+/// the formats other spreadsheets paste from
+pub async fn write_system_clipboard(text: String, html: String) {
+    let eval = document::eval(WRITE_CLIPBOARD_JS);
+    let _ = eval.send((text, html));
+    let _ = eval.await;
+}
+
+/// Puts plain text alone on the system clipboard, so it pastes as written even where
+/// HTML would be preferred
+pub async fn write_system_clipboard_text(text: String) {
+    let eval = document::eval("await navigator.clipboard.writeText(await dioxus.recv());");
+    let _ = eval.send(text);
+    let _ = eval.await;
+}
+
+/// Plain text and HTML on the system clipboard, None if the browser doesn't allow reading it
+pub async fn read_system_clipboard() -> Option<(String, Option<String>)> {
+    document::eval(READ_CLIPBOARD_JS)
+        .join::<Option<(String, Option<String>)>>()
+        .await
+        .ok()
+        .flatten()
+}
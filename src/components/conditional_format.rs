@@ -0,0 +1,225 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        conditional::{IconSet, Rule},
+        grid::Grid,
+    },
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum RuleKind {
+    TwoColorScale,
+    ThreeColorScale,
+    DataBar,
+    IconSet,
+}
+
+#[component]
+pub fn ConditionalFormatDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut kind = use_signal(|| RuleKind::TwoColorScale);
+    let mut min_color = use_signal(|| "#f8696b".to_string());
+    let mut mid_color = use_signal(|| "#ffeb84".to_string());
+    let mut max_color = use_signal(|| "#63be7b".to_string());
+    let mut bar_color = use_signal(|| "#638ec6".to_string());
+    let mut show_value = use_signal(|| true);
+    let mut icons = use_signal(|| IconSet::Arrows);
+    let mut thresholds = use_signal(|| [33.0, 67.0]);
+    let mut percentiles = use_signal(|| true);
+
+    let range = grid.read().get_selection_address();
+    let rule_count = grid.read().sheet().conditional_formats.len();
+
+    rsx! {
+        div {
+            class: "conditional-format",
+
+            div { class: "dialog-message", "Applies to {range}" }
+            select {
+                value: match kind() {
+                    RuleKind::TwoColorScale => "two",
+                    RuleKind::ThreeColorScale => "three",
+                    RuleKind::DataBar => "bar",
+                    RuleKind::IconSet => "icons",
+                },
+                onchange: move |evt| {
+                    kind.set(match evt.value().as_str() {
+                        "three" => RuleKind::ThreeColorScale,
+                        "bar" => RuleKind::DataBar,
+                        "icons" => RuleKind::IconSet,
+                        _ => RuleKind::TwoColorScale,
+                    });
+                },
+                option { value: "two", "2-color scale" }
+                option { value: "three", "3-color scale" }
+                option { value: "bar", "Data bar" }
+                option { value: "icons", "Icon set" }
+            }
+            if kind() == RuleKind::IconSet {
+                div {
+                    class: "conditional-format-colors",
+                    label {
+                        "Icons"
+                        select {
+                            value: match icons() {
+                                IconSet::Arrows => "arrows",
+                                IconSet::TrafficLights => "lights",
+                                IconSet::Flags => "flags",
+                            },
+                            onchange: move |evt| {
+                                icons.set(match evt.value().as_str() {
+                                    "lights" => IconSet::TrafficLights,
+                                    "flags" => IconSet::Flags,
+                                    _ => IconSet::Arrows,
+                                });
+                            },
+                            option { value: "arrows", "Arrows" }
+                            option { value: "lights", "Traffic lights" }
+                            option { value: "flags", "Flags" }
+                        }
+                    }
+                    label {
+                        "Thresholds"
+                        select {
+                            value: if percentiles() { "percentiles" } else { "values" },
+                            onchange: move |evt| percentiles.set(evt.value() == "percentiles"),
+                            option { value: "percentiles", "Percentiles" }
+                            option { value: "values", "Values" }
+                        }
+                    }
+                    for (i, caption) in ["Middle from", "Highest from"].into_iter().enumerate() {
+                        label {
+                            "{caption}"
+                            input {
+                                class: "conditional-format-threshold",
+                                r#type: "number",
+                                value: "{thresholds.read()[i]}",
+                                oninput: move |evt| {
+                                    if let Ok(value) = evt.value().parse::<f64>() {
+                                        thresholds.write()[i] = value;
+                                    }
+                                },
+                            }
+                        }
+                    }
+                }
+            } else if kind() == RuleKind::DataBar {
+                div {
+                    class: "conditional-format-colors",
+                    label {
+                        "Bar"
+                        input {
+                            r#type: "color",
+                            value: "{bar_color}",
+                            oninput: move |evt| bar_color.set(evt.value()),
+                        }
+                    }
+                    label {
+                        "Show value"
+                        input {
+                            r#type: "checkbox",
+                            checked: show_value(),
+                            onchange: move |evt| show_value.set(evt.checked()),
+                        }
+                    }
+                }
+            } else {
+                div {
+                    class: "conditional-format-colors",
+                    label {
+                        "Lowest"
+                        input {
+                            r#type: "color",
+                            value: "{min_color}",
+                            oninput: move |evt| min_color.set(evt.value()),
+                        }
+                    }
+                    if kind() == RuleKind::ThreeColorScale {
+                        label {
+                            "Middle"
+                            input {
+                                r#type: "color",
+                                value: "{mid_color}",
+                                oninput: move |evt| mid_color.set(evt.value()),
+                            }
+                        }
+                    }
+                    label {
+                        "Highest"
+                        input {
+                            r#type: "color",
+                            value: "{max_color}",
+                            oninput: move |evt| max_color.set(evt.value()),
+                        }
+                    }
+                }
+            }
+            if rule_count > 0 {
+                div { class: "dialog-message", "This sheet has {rule_count} rule(s)" }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                "tooltip-text": "Remove the rules covering the selection",
+                onclick: move |_| grid.write().apply(GridCommand::ClearConditionalFormats),
+                "Clear rules"
+            }
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                onclick: move |_| {
+                    let rule = match kind() {
+                        RuleKind::IconSet => Rule::IconSet {
+                            icons: icons(),
+                            thresholds: thresholds(),
+                            percentiles: percentiles(),
+                        },
+                        RuleKind::DataBar => Rule::DataBar {
+                            color: bar_color(),
+                            show_value: show_value(),
+                        },
+                        kind => Rule::ColorScale {
+                            min_color: min_color(),
+                            mid_color: (kind == RuleKind::ThreeColorScale).then(|| mid_color.cloned()),
+                            max_color: max_color(),
+                        },
+                    };
+                    grid.write().apply(GridCommand::AddConditionalFormat(rule));
+                    close_dialog(dialog, scroll_container);
+                },
+                "Add"
+            }
+        }
+    }
+}
+
+/// An icon set's icon for a cell, drawn ahead of its value
+#[component]
+pub fn ConditionalIcon(icons: IconSet, level: usize) -> Element {
+    let class = format!("cell-icon cell-icon-level-{}", level);
+    rsx! {
+        span {
+            class,
+            match (icons, level) {
+                (IconSet::Arrows, 0) => rsx! { lucide_dioxus::ArrowDown { size: 12 } },
+                (IconSet::Arrows, 1) => rsx! { lucide_dioxus::ArrowRight { size: 12 } },
+                (IconSet::Arrows, _) => rsx! { lucide_dioxus::ArrowUp { size: 12 } },
+                (IconSet::TrafficLights, _) => rsx! {
+                    span { class: "cell-icon-light", lucide_dioxus::Circle { size: 12 } }
+                },
+                (IconSet::Flags, _) => rsx! { lucide_dioxus::Flag { size: 12 } },
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::{collections::HashMap, rc::Rc};
+
+use dioxus::{core::spawn_forever, prelude::*};
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    engine::csv::{self, ColumnType, CsvRecords, DELIMITERS, ENCODINGS},
+    model::{
+        command::GridCommand,
+        grid::{column_index_to_letter, Coords, Grid},
+    },
+};
+
+/// Rows of the file shown before importing it
+const PREVIEW_ROWS: usize = 5;
+
+/// Rows of a file imported straight away that its header and column types are told from
+const TYPED_ROWS: usize = 100;
+
+/// Where an import's top-left cell goes
+#[derive(Clone, Copy, PartialEq)]
+pub enum Destination {
+    TopLeft,
+    CurrentCell,
+    NewSheet,
+}
+
+impl Destination {
+    /// The cell to import at, and whether on a new sheet
+    pub fn place(self, grid: &Grid) -> (Coords, bool) {
+        match self {
+            Destination::TopLeft => (Coords { row: 0, column: 0 }, false),
+            Destination::CurrentCell => (grid.current_cell(), false),
+            Destination::NewSheet => (Coords { row: 0, column: 0 }, true),
+        }
+    }
+}
+
+/// Imports a file at A1 straight away if its encoding and delimiter are clear, opening the
+/// import dialog to ask about them otherwise. `.tsv` files are split by tabs.
+pub fn import_csv(
+    mut grid: Signal<Grid>,
+    mut dialog: Signal<Option<Dialog>>,
+    name: &str,
+    bytes: Vec<u8>,
+) {
+    let delimiter = name.to_lowercase().ends_with(".tsv").then_some('\t');
+    let Some(records) = csv::sniff(&bytes, delimiter) else {
+        dialog.set(Some(Dialog::ImportCsv(bytes.into())));
+        return;
+    };
+    // The rest is only read as it's written
+    let sample = records.preview(TYPED_ROWS);
+    let header = csv::detect_header(&sample);
+    let column_count = sample
+        .iter()
+        .map(|fields| fields.len())
+        .max()
+        .unwrap_or_default();
+    let types = (0..column_count)
+        .map(|column| csv::detect_column_type(&sample, column, header))
+        .collect();
+    grid.write().apply(GridCommand::ImportCsv {
+        rows: records.into(),
+        at: Coords { row: 0, column: 0 },
+        new_sheet: false,
+        header,
+        types,
+    });
+    continue_import(grid);
+}
+
+/// Writes the rest of an import a batch at a time, letting the page draw and take input in
+/// between. Outlives the dialog that started it.
+pub fn continue_import(mut grid: Signal<Grid>) {
+    spawn_forever(async move {
+        while grid.read().view.import.is_some() {
+            let _ = document::eval("await new Promise((resolve) => setTimeout(resolve));").await;
+            grid.write().apply(GridCommand::ContinueImport);
+        }
+    });
+}
+
+/// How far an import has got, with a button to stop it
+#[component]
+pub fn ImportProgress(grid: Signal<Grid>) -> Element {
+    let Some((written, progress)) = grid
+        .read()
+        .view
+        .import
+        .as_ref()
+        .map(|import| (import.rows_written(), import.progress()))
+    else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "import-progress",
+
+            span { "Importing row {written}" }
+            progress { value: "{progress}", max: "1" }
+            button {
+                onclick: move |_| grid.write().apply(GridCommand::CancelImport),
+                "Cancel"
+            }
+        }
+    }
+}
+
+/// The "Import to" picker of the import dialogs
+#[component]
+pub fn DestinationSelect(mut destination: Signal<Destination>) -> Element {
+    rsx! {
+        label {
+            "Import to"
+            select {
+                onchange: move |evt| destination.set(match evt.value().as_str() {
+                    "current" => Destination::CurrentCell,
+                    "sheet" => Destination::NewSheet,
+                    _ => Destination::TopLeft,
+                }),
+                option { value: "top-left", selected: destination() == Destination::TopLeft, "Cell A1" }
+                option { value: "current", selected: destination() == Destination::CurrentCell, "The current cell" }
+                option { value: "sheet", selected: destination() == Destination::NewSheet, "A new sheet" }
+            }
+        }
+    }
+}
+
+/// Picks how to read a CSV file and where to put it, previewing the first rows
+#[component]
+pub fn CsvImportDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    bytes: Rc<[u8]>,
+) -> Element {
+    let mut encoding = use_signal(|| csv::detect_encoding(&bytes).0);
+    let text = csv::decode(&bytes, encoding());
+    let mut delimiter = use_signal(|| csv::detect_delimiter(&text));
+    let mut header = use_signal(|| csv::detect_header(&csv::parse_csv(&text, delimiter())));
+    // Types picked in the preview, the rest detected
+    let mut types = use_signal(HashMap::<usize, ColumnType>::new);
+    let destination = use_signal(|| Destination::TopLeft);
+
+    let rows = csv::parse_csv(&text, delimiter());
+    let column_count = rows.iter().map(|fields| fields.len()).max().unwrap_or_default();
+    let column_types = (0..column_count)
+        .map(|column| match types.read().get(&column) {
+            Some(column_type) => *column_type,
+            None => csv::detect_column_type(&rows, column, header()),
+        })
+        .collect::<Vec<_>>();
+    let letters = (0..column_count)
+        .map(|column| column_index_to_letter(column as i32))
+        .collect::<Vec<_>>();
+    let preview = rows.iter().take(PREVIEW_ROWS).cloned().collect::<Vec<_>>();
+    let row_count = rows.len();
+    let command = {
+        let column_types = column_types.clone();
+        move || {
+            let (at, new_sheet) = destination().place(&grid.read());
+            GridCommand::ImportCsv {
+                rows: CsvRecords::new(text.clone(), delimiter()).into(),
+                at,
+                new_sheet,
+                header: header(),
+                types: column_types.clone(),
+            }
+        }
+    };
+
+    rsx! {
+        div {
+            class: "csv-import",
+
+            div {
+                class: "csv-import-options",
+                label {
+                    "Encoding"
+                    select {
+                        onchange: move |evt| {
+                            if let Some((e, _)) = ENCODINGS.get(evt.value().parse::<usize>().unwrap_or_default()) {
+                                encoding.set(*e);
+                            }
+                        },
+                        for (i, (e, name)) in ENCODINGS.iter().enumerate() {
+                            option {
+                                value: "{i}",
+                                selected: encoding() == *e,
+                                "{name}"
+                            }
+                        }
+                    }
+                }
+                label {
+                    "Delimiter"
+                    select {
+                        onchange: move |evt| {
+                            if let Some((d, _)) = DELIMITERS.get(evt.value().parse::<usize>().unwrap_or_default()) {
+                                delimiter.set(*d);
+                                types.write().clear();
+                            }
+                        },
+                        for (i, (d, name)) in DELIMITERS.iter().enumerate() {
+                            option {
+                                value: "{i}",
+                                selected: delimiter() == *d,
+                                "{name}"
+                            }
+                        }
+                    }
+                }
+                DestinationSelect { destination }
+                label {
+                    class: "csv-import-check",
+                    input {
+                        r#type: "checkbox",
+                        checked: header(),
+                        onchange: move |evt| header.set(evt.checked()),
+                    }
+                    "First row is a header"
+                }
+            }
+            div {
+                class: "csv-import-preview",
+                table {
+                    thead {
+                        tr {
+                            for (column, letter) in letters.into_iter().enumerate() {
+                                th {
+                                    span { "{letter}" }
+                                    select {
+                                        onchange: move |evt| {
+                                            let column_type = match evt.value().as_str() {
+                                                "number" => ColumnType::Number,
+                                                _ => ColumnType::Text,
+                                            };
+                                            types.write().insert(column, column_type);
+                                        },
+                                        option { value: "text", selected: column_types[column] == ColumnType::Text, "Text" }
+                                        option { value: "number", selected: column_types[column] == ColumnType::Number, "Number" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    tbody {
+                        for (i, fields) in preview.into_iter().enumerate() {
+                            tr {
+                                class: if header() && i == 0 { "csv-import-header" },
+                                for field in fields {
+                                    td { "{field}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            span {
+                class: "csv-import-count",
+                if row_count == 1 { "1 row" } else { "{row_count} rows" }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: row_count == 0,
+                onclick: move |_| {
+                    grid.write().apply(command());
+                    continue_import(grid);
+                    close_dialog(dialog, scroll_container);
+                },
+                "Import"
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use dioxus::prelude::*;
+
+use crate::{
+    components::side_panel::CellLink,
+    model::{command::GridCommand, grid::Grid},
+};
+
+#[component]
+pub fn CycleBanner(grid: Signal<Grid>) -> Element {
+    let Some(path) = grid.read().view.circular_reference.clone() else {
+        return rsx! {};
+    };
+    let last = path.len().saturating_sub(1);
+
+    rsx! {
+        div {
+            class: "cycle-banner",
+
+            span { "Circular reference: " }
+            for (i, cell_key) in path.into_iter().enumerate() {
+                CellLink { grid, cell_key }
+                if i < last {
+                    span { " → " }
+                }
+            }
+            button {
+                "tooltip-text": "Dismiss",
+                onclick: move |_| grid.write().apply(GridCommand::DismissCircularReference),
+                lucide_dioxus::X { size: 14 }
+            }
+        }
+    }
+}
+
+/// Why the last change didn't go through, as the sheet is protected
+#[component]
+pub fn ProtectionBanner(grid: Signal<Grid>) -> Element {
+    let Some(message) = grid.read().view.protection_message.clone() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "cycle-banner",
+
+            lucide_dioxus::Lock { size: 14 }
+            span { "{message}" }
+            button {
+                "tooltip-text": "Dismiss",
+                onclick: move |_| grid.write().apply(GridCommand::DismissProtectionMessage),
+                lucide_dioxus::X { size: 14 }
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::rc::Rc;
+
+use dioxus::{core::spawn_forever, prelude::*};
+
+use crate::{
+    components::{
+        chart::ChartDialog,
+        conditional_format::ConditionalFormatDialog,
+        csv_import::CsvImportDialog,
+        edit_history::EditHistoryDialog,
+        evaluate_formula::EvaluateFormulaDialog,
+        files::NewWorkbookDialog,
+        flash_fill::FlashFillDialog,
+        go_to::GoToDialog,
+        google_sheets::GoogleSheetsDialog,
+        json_import::JsonImportDialog,
+        number_format::CustomFormatDialog,
+        outline::SubtotalsDialog,
+        paste_special::PasteSpecialDialog,
+        print::PrintDialog,
+        properties::PropertiesDialog,
+        protected_ranges::ProtectedRangesDialog,
+        server_files::ServerFilesDialog,
+        sheet_tabs::DeleteSheetDialog,
+        solver::SolverDialog,
+        statistics::{DescriptiveStatisticsDialog, HistogramDialog},
+        table::TableDialog,
+        validation::ValidationDialog,
+        versions::VersionDiffDialog,
+    },
+    model::{
+        chart::ChartId,
+        grid::{Coords, Grid},
+        snapshot::CellChange,
+        workbook::SheetId,
+    },
+};
+
+static DIALOG_CSS: Asset = asset!("/assets/dialog.css");
+
+#[derive(Clone, PartialEq)]
+pub enum Dialog {
+    EvaluateFormula,
+    DeleteSheet(SheetId),
+    PasteSpecial,
+    FlashFill,
+    GoTo,
+    CustomFormat,
+    ConditionalFormat,
+    Table,
+    DataValidation,
+    Solver,
+    DescriptiveStatistics,
+    Histogram,
+    Chart(ChartId),
+    Subtotals,
+    /// Holds the file being imported
+    ImportCsv(Rc<[u8]>),
+    NewWorkbook,
+    GoogleSheets,
+    /// Holds the JSON being imported, None to ask for a URL to fetch it from
+    ImportJson(Option<Rc<str>>),
+    Print,
+    ServerFiles,
+    /// Holds the cell whose changes are listed
+    EditHistory(Coords),
+    /// Holds the cells that differ between the versions compared
+    VersionDiff(Rc<[CellChange]>),
+    Properties,
+    ProtectedRanges,
+}
+
+impl Dialog {
+    fn title(&self) -> &'static str {
+        match self {
+            Dialog::EvaluateFormula => "Evaluate Formula",
+            Dialog::DeleteSheet(_) => "Delete Sheet",
+            Dialog::PasteSpecial => "Paste Special",
+            Dialog::FlashFill => "Flash Fill",
+            Dialog::GoTo => "Go To",
+            Dialog::CustomFormat => "Custom Number Format",
+            Dialog::ConditionalFormat => "Conditional Formatting",
+            Dialog::Table => "Format as Table",
+            Dialog::DataValidation => "Data Validation",
+            Dialog::Solver => "Solver",
+            Dialog::DescriptiveStatistics => "Descriptive Statistics",
+            Dialog::Histogram => "Histogram",
+            Dialog::Chart(_) => "Chart",
+            Dialog::Subtotals => "Subtotals",
+            Dialog::ImportCsv(_) => "Import CSV",
+            Dialog::NewWorkbook => "Unsaved Changes",
+            Dialog::GoogleSheets => "Import from Google Sheets",
+            Dialog::ImportJson(_) => "Import JSON",
+            Dialog::Print => "Print",
+            Dialog::ServerFiles => "Workbooks on a Server",
+            Dialog::EditHistory(_) => "Edit History",
+            Dialog::VersionDiff(_) => "Changes Between Versions",
+            Dialog::Properties => "Document Properties",
+            Dialog::ProtectedRanges => "Protected Ranges",
+        }
+    }
+}
+
+#[component]
+pub fn DialogHost(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let Some(current) = dialog() else {
+        return rsx! {
+            document::Stylesheet { href: DIALOG_CSS }
+        };
+    };
+
+    rsx! {
+        document::Stylesheet { href: DIALOG_CSS }
+        div {
+            class: "dialog-backdrop",
+            onclick: move |_| close_dialog(dialog, scroll_container),
+
+            div {
+                class: "dialog",
+                onclick: move |evt| evt.stop_propagation(),
+                onkeydown: move |evt| {
+                    evt.stop_propagation();
+                    if evt.key() == Key::Escape {
+                        close_dialog(dialog, scroll_container);
+                    }
+                },
+
+                div {
+                    class: "dialog-title",
+                    span { "{current.title()}" }
+                    button {
+                        "tooltip-text": "Close",
+                        onclick: move |_| close_dialog(dialog, scroll_container),
+                        lucide_dioxus::X { size: 16 }
+                    }
+                }
+                {
+                    match current.clone() {
+                        Dialog::EvaluateFormula => rsx! { EvaluateFormulaDialog { grid } },
+                        Dialog::DeleteSheet(sheet) => rsx! {
+                            DeleteSheetDialog { grid, dialog, scroll_container, sheet }
+                        },
+                        Dialog::PasteSpecial => rsx! {
+                            PasteSpecialDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::FlashFill => rsx! {
+                            FlashFillDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::GoTo => rsx! {
+                            GoToDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::CustomFormat => rsx! {
+                            CustomFormatDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::ConditionalFormat => rsx! {
+                            ConditionalFormatDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::Table => rsx! {
+                            TableDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::DataValidation => rsx! {
+                            ValidationDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::Solver => rsx! {
+                            SolverDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::DescriptiveStatistics => rsx! {
+                            DescriptiveStatisticsDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::Histogram => rsx! {
+                            HistogramDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::Chart(id) => match grid.read().sheet().get_chart(id).cloned() {
+                            Some(original) => rsx! {
+                                ChartDialog { grid, dialog, scroll_container, original }
+                            },
+                            None => rsx! {},
+                        },
+                        Dialog::Subtotals => rsx! {
+                            SubtotalsDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::ImportCsv(bytes) => rsx! {
+                            CsvImportDialog { grid, dialog, scroll_container, bytes }
+                        },
+                        Dialog::NewWorkbook => rsx! {
+                            NewWorkbookDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::GoogleSheets => rsx! {
+                            GoogleSheetsDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::ImportJson(text) => rsx! {
+                            JsonImportDialog { grid, dialog, scroll_container, text }
+                        },
+                        Dialog::Print => rsx! {
+                            PrintDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::ServerFiles => rsx! {
+                            ServerFilesDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::EditHistory(coords) => rsx! {
+                            EditHistoryDialog { grid, dialog, scroll_container, coords }
+                        },
+                        Dialog::VersionDiff(changes) => rsx! {
+                            VersionDiffDialog { dialog, scroll_container, changes }
+                        },
+                        Dialog::Properties => rsx! {
+                            PropertiesDialog { grid, dialog, scroll_container }
+                        },
+                        Dialog::ProtectedRanges => rsx! {
+                            ProtectedRangesDialog { grid, dialog, scroll_container }
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn close_dialog(
+    mut dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) {
+    dialog.set(None);
+    if let Some(container) = scroll_container() {
+        spawn_forever(async move {
+            let _ = container.set_focus(true).await;
+        });
+    }
+}
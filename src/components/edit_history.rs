@@ -0,0 +1,78 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    engine::date,
+    model::{
+        command::GridCommand,
+        grid::{coords_to_cell_address, Coords, Grid},
+    },
+};
+
+/// The changes made to a cell, latest first, each with the content before it to restore
+#[component]
+pub fn EditHistoryDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    coords: Coords,
+) -> Element {
+    let edits = grid.read().get_edit_history(coords);
+
+    rsx! {
+        div {
+            class: "edit-history",
+            div {
+                class: "dialog-message",
+                "Changes to {coords_to_cell_address(coords)}, latest first"
+            }
+            if edits.is_empty() {
+                div { class: "dialog-message", "The cell hasn't been changed." }
+            } else {
+                div {
+                    class: "edit-history-list",
+                    for edit in edits {
+                        {
+                            let restored = edit.old.clone();
+                            rsx! {
+                                div {
+                                    class: "edit-history-row",
+                                    span { class: "edit-history-date", "{date::format_timestamp(edit.timestamp)}" }
+                                    span { class: "edit-history-content", title: "{edit.old}", "{shown(&edit.old)}" }
+                                    lucide_dioxus::ArrowRight { size: 14 }
+                                    span { class: "edit-history-content", title: "{edit.new}", "{shown(&edit.new)}" }
+                                    button {
+                                        "tooltip-text": "Restore what it held before",
+                                        onclick: move |_| {
+                                            let content = restored.clone();
+                                            grid.write().apply(GridCommand::SetCellContent { coords, content });
+                                            close_dialog(dialog, scroll_container);
+                                        },
+                                        "Restore"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Close"
+            }
+        }
+    }
+}
+
+/// Content as listed, blanks standing out as such
+fn shown(content: &str) -> &str {
+    match content.is_empty() {
+        true => "(blank)",
+        false => content,
+    }
+}
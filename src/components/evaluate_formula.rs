@@ -0,0 +1,81 @@
+use dioxus::prelude::*;
+
+use crate::{engine::parser, model::grid::Grid};
+
+#[component]
+pub fn EvaluateFormulaDialog(grid: Signal<Grid>) -> Element {
+    // Snapshot of the cell when the dialog was opened
+    let address = use_signal(|| grid.read().get_current_cell_address());
+    let content = use_signal(|| grid.read().get_current_cell_content());
+    let sheet = use_signal(|| grid.read().view.active_sheet);
+    let mut step = use_signal(|| 0usize);
+
+    if !content.read().starts_with('=') {
+        return rsx! {
+            div { class: "dialog-message", "{address} doesn't contain a formula" }
+        };
+    }
+
+    if let Some(function) = parser::standalone_function(&content.read()) {
+        return rsx! {
+            div {
+                class: "dialog-message",
+                "{address}: {content}. {function} is calculated as a whole, with no steps to show."
+            }
+        };
+    }
+
+    let grid_read = grid.read();
+    let cell_ref_resolver = grid_read.workbook.resolver(sheet());
+    let outline = parser::formula_outline(&content.read());
+    let steps = parser::evaluation_steps(&content.read(), &cell_ref_resolver);
+    let (outline, steps) = match (outline, steps) {
+        (Ok(outline), Ok(steps)) => (outline, steps),
+        (Err(e), _) | (_, Err(e)) => {
+            return rsx! {
+                div { class: "dialog-message", "{address}: {e}" }
+            };
+        }
+    };
+    let last_step = steps.len() - 1;
+    let current_step = step().min(last_step);
+
+    rsx! {
+        div {
+            class: "evaluate-formula",
+
+            div { class: "dialog-message", "{address}: {content}" }
+            div {
+                class: "evaluate-formula-panes",
+
+                div {
+                    class: "evaluate-formula-outline",
+                    for (depth, label) in outline {
+                        div { style: "padding-left: {depth * 12}px;", "{label}" }
+                    }
+                }
+                div {
+                    class: "evaluate-formula-steps",
+                    for (i, expression) in steps.into_iter().take(current_step + 1).enumerate() {
+                        div {
+                            class: if i == current_step { "evaluate-step evaluate-step-current" } else { "evaluate-step" },
+                            "{expression}"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "dialog-buttons",
+                button {
+                    disabled: current_step == last_step,
+                    onclick: move |_| step += 1,
+                    "Evaluate"
+                }
+                button {
+                    onclick: move |_| step.set(0),
+                    "Restart"
+                }
+            }
+        }
+    }
+}
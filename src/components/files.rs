@@ -0,0 +1,317 @@
+use std::rc::Rc;
+
+use dioxus::{html::HasFileData, prelude::*};
+#[cfg(not(feature = "desktop"))]
+use wasm_bindgen::JsCast;
+
+#[cfg(feature = "desktop")]
+use crate::components::native_files;
+use crate::{
+    components::{
+        csv_import::import_csv,
+        dialog::{close_dialog, Dialog},
+        ods::import_ods,
+        recent_files::remember_file,
+    },
+    model::{
+        command::GridCommand,
+        file::{read_workbook, FILE_EXTENSION},
+        grid::Grid,
+    },
+};
+
+/// The hidden input files are picked with where the File System Access API isn't supported
+pub const FILE_INPUT_ID: &str = "file-input";
+
+pub const ACCEPTED_FILES: &str = ".rsheet,.csv,.tsv,.txt,.ods,.json";
+
+/// Picks a file to open, keeping the handle of a workbook so saving writes back to it.
+/// Returns the file's name and bytes, "unsupported" without the File System Access API, or
+/// null if nothing was picked.
+#[cfg(not(feature = "desktop"))]
+const OPEN_JS: &str = r#"
+    if (!window.showOpenFilePicker) {
+        return "unsupported";
+    }
+    try {
+        const [handle] = await showOpenFilePicker({
+            types: [{
+                description: "Spreadsheets",
+                accept: { "application/octet-stream": [".rsheet", ".csv", ".tsv", ".txt", ".ods", ".json"] },
+            }],
+        });
+        const file = await handle.getFile();
+        window.workbookFileHandle = file.name.toLowerCase().endsWith(".rsheet") ? handle : null;
+        await window.recentFiles?.add(file.name, handle, null).catch(() => {});
+        return [file.name, Array.from(new Uint8Array(await file.arrayBuffer()))];
+    } catch {
+        return null;
+    }
+"#;
+
+/// Writes the workbook over the file it came from or was last saved to, asking where to save
+/// it the first time and for Save As. Browsers without the File System Access API download it
+/// instead. Returns whether it was saved.
+#[cfg(not(feature = "desktop"))]
+const SAVE_JS: &str = r#"
+    const [text, saveAs, name] = await dioxus.recv();
+    if (!window.showSaveFilePicker) {
+        const url = URL.createObjectURL(new Blob([text], { type: "application/json" }));
+        const anchor = document.createElement("a");
+        anchor.href = url;
+        anchor.download = name;
+        anchor.click();
+        URL.revokeObjectURL(url);
+        await window.recentFiles?.add(name, null, new TextEncoder().encode(text)).catch(() => {});
+        return true;
+    }
+    try {
+        if (saveAs || !window.workbookFileHandle) {
+            window.workbookFileHandle = await showSaveFilePicker({
+                suggestedName: name,
+                types: [{
+                    description: "Workbook",
+                    accept: { "application/json": [".rsheet"] },
+                }],
+            });
+        }
+        const writable = await window.workbookFileHandle.createWritable();
+        await writable.write(text);
+        await writable.close();
+        const handle = window.workbookFileHandle;
+        await window.recentFiles?.add(handle.name, handle, null).catch(() => {});
+        return true;
+    } catch {
+        return false;
+    }
+"#;
+
+#[cfg(not(feature = "desktop"))]
+const DOWNLOAD_JS: &str = r#"
+    const [bytes, type, name] = await dioxus.recv();
+    const url = URL.createObjectURL(new Blob([new Uint8Array(bytes)], { type }));
+    const anchor = document.createElement("a");
+    anchor.href = url;
+    anchor.download = name;
+    anchor.click();
+    URL.revokeObjectURL(url);
+"#;
+
+/// Opens a workbook file in place of the current one, or imports a CSV, TSV, ODS or JSON
+/// file, going by the name's extension
+pub async fn open_file(
+    mut grid: Signal<Grid>,
+    mut dialog: Signal<Option<Dialog>>,
+    name: &str,
+    bytes: Vec<u8>,
+) {
+    let lowercase = name.to_lowercase();
+    if lowercase.ends_with(&format!(".{FILE_EXTENSION}")) {
+        match read_workbook(&String::from_utf8_lossy(&bytes)) {
+            Ok(workbook) => grid.write().apply(GridCommand::OpenWorkbook(workbook)),
+            Err(e) => error!("{e}"),
+        }
+    } else if lowercase.ends_with(".ods") {
+        import_ods(grid, bytes).await;
+    } else if lowercase.ends_with(".json") {
+        let text = String::from_utf8_lossy(&bytes).into();
+        dialog.set(Some(Dialog::ImportJson(Some(text))));
+    } else {
+        import_csv(grid, dialog, name, bytes);
+    }
+}
+
+/// Opens a file picked with the file input or dropped, which there's no handle to save back to
+pub async fn open_uploaded_file(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    name: String,
+    bytes: Vec<u8>,
+) {
+    forget_file_handle();
+    remember_file(name.clone(), bytes.clone()).await;
+    open_file(grid, dialog, &name, bytes).await;
+}
+
+/// Asks for a file to open, through the File System Access API where there is one and the
+/// file input otherwise
+#[cfg(not(feature = "desktop"))]
+pub async fn pick_file(grid: Signal<Grid>, dialog: Signal<Option<Dialog>>) {
+    let picked = document::eval(OPEN_JS).await;
+    match picked {
+        Ok(serde_json::Value::String(unsupported)) if unsupported == "unsupported" => {
+            click_file_input()
+        }
+        Ok(picked) => {
+            if let Ok((name, bytes)) = serde_json::from_value::<(String, Vec<u8>)>(picked) {
+                open_file(grid, dialog, &name, bytes).await;
+            }
+        }
+        Err(e) => error!("{e:?}"),
+    }
+}
+
+/// Asks for a file to open with the system's dialog
+#[cfg(feature = "desktop")]
+pub async fn pick_file(grid: Signal<Grid>, dialog: Signal<Option<Dialog>>) {
+    if let Some((name, bytes)) = native_files::pick_file().await {
+        remember_file(name.clone(), bytes.clone()).await;
+        open_file(grid, dialog, &name, bytes).await;
+    }
+}
+
+/// Forgets the file saving writes back to
+#[cfg(not(feature = "desktop"))]
+fn forget_file_handle() {
+    let _ = document::eval("window.workbookFileHandle = null;");
+}
+
+#[cfg(feature = "desktop")]
+fn forget_file_handle() {
+    native_files::forget_workbook_path();
+}
+
+#[cfg(not(feature = "desktop"))]
+fn click_file_input() {
+    if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+        if let Some(element) = document.get_element_by_id(FILE_INPUT_ID) {
+            if let Some(input) = element.dyn_ref::<web_sys::HtmlInputElement>() {
+                input.click();
+            }
+        }
+    }
+}
+
+/// Saves the workbook as a `.rsheet` file, over the one it was opened from or last saved to
+/// unless `save_as` (Ctrl+S, Ctrl+Shift+S)
+#[cfg(not(feature = "desktop"))]
+pub async fn save_workbook(mut grid: Signal<Grid>, save_as: bool) {
+    let text = grid.read().save_workbook();
+    let eval = document::eval(SAVE_JS);
+    let _ = eval.send((text, save_as, format!("workbook.{FILE_EXTENSION}")));
+    if matches!(eval.join::<bool>().await, Ok(true)) {
+        grid.write().apply(GridCommand::MarkSaved);
+    }
+}
+
+#[cfg(feature = "desktop")]
+pub async fn save_workbook(mut grid: Signal<Grid>, save_as: bool) {
+    let text = grid.read().save_workbook();
+    let name = format!("workbook.{FILE_EXTENSION}");
+    if let Some(name) = native_files::save_workbook(&text, save_as, &name).await {
+        remember_file(name, text.into_bytes()).await;
+        grid.write().apply(GridCommand::MarkSaved);
+    }
+}
+
+/// Saves an exported file, downloading it in the browser
+#[cfg(not(feature = "desktop"))]
+pub async fn save_export(bytes: Vec<u8>, mime_type: &str, name: &str) {
+    let eval = document::eval(DOWNLOAD_JS);
+    let _ = eval.send((bytes, mime_type, name));
+    let _ = eval.await;
+}
+
+/// Saves an exported file where the system's dialog says
+#[cfg(feature = "desktop")]
+pub async fn save_export(bytes: Vec<u8>, _mime_type: &str, name: &str) {
+    native_files::save_file(bytes, name).await;
+}
+
+/// Starts an empty workbook, first asking whether to discard unsaved changes
+pub fn new_workbook(mut grid: Signal<Grid>, mut dialog: Signal<Option<Dialog>>) {
+    if grid.read().view.has_unsaved_changes {
+        dialog.set(Some(Dialog::NewWorkbook));
+        return;
+    }
+    forget_file_handle();
+    grid.write().apply(GridCommand::NewWorkbook);
+}
+
+/// Has the browser ask before leaving the page while there are unsaved changes
+pub fn use_unsaved_changes_warning(grid: Signal<Grid>) {
+    let has_unsaved_changes = use_memo(move || grid.read().view.has_unsaved_changes);
+    use_effect(move || {
+        let handler = match has_unsaved_changes() {
+            true => "(event) => event.preventDefault()",
+            false => "null",
+        };
+        let _ = document::eval(&format!("window.onbeforeunload = {handler};"));
+    });
+}
+
+#[component]
+pub fn NewWorkbookDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    rsx! {
+        div {
+            class: "dialog-message",
+            "The workbook has unsaved changes. Discard them and start a new workbook?"
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                onclick: move |_| {
+                    forget_file_handle();
+                    grid.write().apply(GridCommand::NewWorkbook);
+                    close_dialog(dialog, scroll_container);
+                },
+                "Discard changes"
+            }
+        }
+    }
+}
+
+/// Covers the page while files are dragged over the grid, opening the first one dropped
+#[component]
+pub fn FileDropOverlay(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    dropping: Signal<bool>,
+) -> Element {
+    if !dropping() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div {
+            class: "file-drop-overlay",
+            ondragover: move |evt| evt.prevent_default(),
+            ondragleave: move |_| dropping.set(false),
+            ondrop: move |evt| {
+                evt.prevent_default();
+                dropping.set(false);
+                let Some(file) = evt.files().into_iter().next() else {
+                    return;
+                };
+                let name = file.name();
+                let is_accepted = ACCEPTED_FILES
+                    .split(',')
+                    .any(|extension| name.to_lowercase().ends_with(extension));
+                if !is_accepted {
+                    error!("Can't open {name}, only {ACCEPTED_FILES} files");
+                    return;
+                }
+                spawn(async move {
+                    match file.read_bytes().await {
+                        Ok(bytes) => open_uploaded_file(grid, dialog, name, bytes.to_vec()).await,
+                        Err(e) => error!("{e:?}"),
+                    }
+                });
+            },
+
+            div {
+                class: "file-drop-message",
+                lucide_dioxus::FileUp { size: 40 }
+                span { "Drop to open a workbook, or import CSV, TSV, ODS or JSON" }
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeSet;
+
+use dioxus::{html::geometry::ClientPoint, prelude::*};
+
+use crate::{
+    components::grid::grid_point,
+    model::{
+        command::GridCommand,
+        filter::{ColumnFilter, Condition},
+        grid::Grid,
+    },
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum ConditionKind {
+    GreaterThan,
+    LessThan,
+    Contains,
+}
+
+/// The dropdown button on a filtered column's header cell
+#[component]
+pub fn FilterButton(
+    grid: Signal<Grid>,
+    filter_menu: Signal<Option<(i32, ClientPoint)>>,
+    column: i32,
+    active: bool,
+) -> Element {
+    rsx! {
+        button {
+            class: if active { "filter-button filter-button-active" } else { "filter-button" },
+            "tooltip-text": "Filter",
+            onmousedown: move |evt| evt.stop_propagation(),
+            ondoubleclick: move |evt| evt.stop_propagation(),
+            onclick: move |evt| filter_menu.set(Some((column, grid_point(&grid.read(), evt.client_coordinates())))),
+            if active {
+                lucide_dioxus::ListFilter { size: 12 }
+            } else {
+                lucide_dioxus::ChevronDown { size: 12 }
+            }
+        }
+    }
+}
+
+/// Picks which rows show by a column's values: a checklist of them, or a condition
+#[component]
+pub fn FilterMenu(
+    grid: Signal<Grid>,
+    filter_menu: Signal<Option<(i32, ClientPoint)>>,
+    column: i32,
+    point: ClientPoint,
+) -> Element {
+    let values = use_signal(|| grid.read().sheet().filter_values(column));
+    let current = use_signal(|| {
+        grid.read()
+            .sheet()
+            .auto_filter
+            .as_ref()
+            .and_then(|filter| filter.filters.get(&column).cloned())
+    });
+    let mut checked = use_signal(|| match current() {
+        Some(ColumnFilter::Values(checked)) => checked,
+        _ => values().into_iter().collect::<BTreeSet<_>>(),
+    });
+    let mut kind = use_signal(|| match current() {
+        Some(ColumnFilter::Condition(Condition::GreaterThan(_))) => Some(ConditionKind::GreaterThan),
+        Some(ColumnFilter::Condition(Condition::LessThan(_))) => Some(ConditionKind::LessThan),
+        Some(ColumnFilter::Condition(Condition::Contains(_))) => Some(ConditionKind::Contains),
+        _ => None,
+    });
+    let mut operand = use_signal(|| match current() {
+        Some(ColumnFilter::Condition(Condition::GreaterThan(bound)))
+        | Some(ColumnFilter::Condition(Condition::LessThan(bound))) => bound.to_string(),
+        Some(ColumnFilter::Condition(Condition::Contains(text))) => text,
+        _ => String::new(),
+    });
+
+    // None if the condition's number can't be read
+    let filter = move || -> Option<Option<ColumnFilter>> {
+        let condition = match kind() {
+            Some(ConditionKind::GreaterThan) => Condition::GreaterThan(operand().trim().parse().ok()?),
+            Some(ConditionKind::LessThan) => Condition::LessThan(operand().trim().parse().ok()?),
+            Some(ConditionKind::Contains) => Condition::Contains(operand()),
+            None if checked().len() == values().len() => return Some(None),
+            None => return Some(Some(ColumnFilter::Values(checked()))),
+        };
+        Some(Some(ColumnFilter::Condition(condition)))
+    };
+    let all_checked = checked().len() == values().len();
+
+    rsx! {
+        div {
+            class: "context-menu-backdrop",
+            onmousedown: move |_| filter_menu.set(None),
+        }
+        div {
+            class: "context-menu filter-menu",
+            style: "left: {point.x}px; top: {point.y}px;",
+
+            select {
+                value: match kind() {
+                    Some(ConditionKind::GreaterThan) => "greater",
+                    Some(ConditionKind::LessThan) => "less",
+                    Some(ConditionKind::Contains) => "contains",
+                    None => "values",
+                },
+                onchange: move |evt| {
+                    kind.set(match evt.value().as_str() {
+                        "greater" => Some(ConditionKind::GreaterThan),
+                        "less" => Some(ConditionKind::LessThan),
+                        "contains" => Some(ConditionKind::Contains),
+                        _ => None,
+                    });
+                },
+                option { value: "values", "Values" }
+                option { value: "greater", "Greater than" }
+                option { value: "less", "Less than" }
+                option { value: "contains", "Contains" }
+            }
+            if kind().is_some() {
+                input {
+                    value: "{operand}",
+                    oninput: move |evt| operand.set(evt.value()),
+                }
+            } else {
+                div {
+                    class: "filter-values",
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: all_checked,
+                            onchange: move |evt| {
+                                checked.set(match evt.checked() {
+                                    true => values().into_iter().collect(),
+                                    false => BTreeSet::new(),
+                                });
+                            },
+                        }
+                        "(Select all)"
+                    }
+                    for value in values() {
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: checked().contains(&value),
+                                onchange: {
+                                    let value = value.clone();
+                                    move |evt: FormEvent| {
+                                        match evt.checked() {
+                                            true => checked.write().insert(value.clone()),
+                                            false => checked.write().remove(&value),
+                                        };
+                                    }
+                                },
+                            }
+                            if value.is_empty() { "(Blanks)" } else { "{value}" }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "filter-menu-buttons",
+                button {
+                    disabled: current().is_none(),
+                    onclick: move |_| {
+                        grid.write().apply(GridCommand::SetColumnFilter { column, filter: None });
+                        filter_menu.set(None);
+                    },
+                    "Clear filter"
+                }
+                button {
+                    disabled: filter().is_none(),
+                    onclick: move |_| {
+                        if let Some(filter) = filter() {
+                            grid.write().apply(GridCommand::SetColumnFilter { column, filter });
+                            filter_menu.set(None);
+                        }
+                    },
+                    "OK"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        grid::{coords_to_cell_address, Grid},
+    },
+};
+
+#[component]
+pub fn FlashFillDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let preview = grid.read().get_flash_fill();
+
+    if preview.is_empty() {
+        return rsx! {
+            div {
+                class: "dialog-message",
+                "Type an example or two next to a column of data, then flash fill from below them"
+            }
+        };
+    }
+
+    rsx! {
+        div {
+            class: "flash-fill-preview",
+            for (coords, content) in preview {
+                div {
+                    class: "flash-fill-row",
+                    span { class: "flash-fill-address", "{coords_to_cell_address(coords)}" }
+                    span { "{content}" }
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                onclick: move |_| {
+                    grid.write().apply(GridCommand::FlashFill);
+                    close_dialog(dialog, scroll_container);
+                },
+                "Fill"
+            }
+        }
+    }
+}
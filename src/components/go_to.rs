@@ -0,0 +1,89 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        grid::{Grid, SpecialCells},
+    },
+};
+
+#[component]
+pub fn GoToDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut reference = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+
+    let mut go_to = move || {
+        let resolved = grid.read().resolve_range(&reference.read());
+        match resolved {
+            Some((sheet, range)) => {
+                grid.write().apply(GridCommand::GoToRange { sheet, range });
+                close_dialog(dialog, scroll_container);
+            }
+            None => {
+                let text = reference.read().clone();
+                message.set(Some(format!("\"{}\" isn't a cell or range", text)));
+            }
+        }
+    };
+    let mut go_to_special = move |kind: SpecialCells| {
+        let cells = grid.read().get_special_cells(kind);
+        if cells.is_empty() {
+            message.set(Some("No cells were found".to_string()));
+            return;
+        }
+        grid.write().apply(GridCommand::SelectCells(cells));
+        close_dialog(dialog, scroll_container);
+    };
+
+    rsx! {
+        div {
+            class: "go-to",
+
+            input {
+                placeholder: "A1, B2:C5 or Sheet2!A1",
+                value: "{reference}",
+                onmounted: move |elem| async move {
+                    let _ = elem.data().set_focus(true).await;
+                },
+                oninput: move |evt| {
+                    reference.set(evt.value());
+                    message.set(None);
+                },
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Enter {
+                        go_to();
+                    }
+                },
+            }
+            if let Some(message) = message() {
+                div { class: "dialog-message", "{message}" }
+            }
+            div { class: "dialog-message", "Go To Special, within the selection" }
+            div {
+                class: "dialog-buttons go-to-special",
+                button { onclick: move |_| go_to_special(SpecialCells::Formulas), "Formulas" }
+                button { onclick: move |_| go_to_special(SpecialCells::Constants), "Constants" }
+                button { onclick: move |_| go_to_special(SpecialCells::Errors), "Errors" }
+                button { onclick: move |_| go_to_special(SpecialCells::Blanks), "Blanks" }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                onclick: move |_| go_to(),
+                "Go"
+            }
+        }
+    }
+}
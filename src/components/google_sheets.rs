@@ -0,0 +1,140 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        file::SheetFile,
+        google_sheets,
+        grid::Grid,
+    },
+};
+
+/// Fetches a URL's text, returning whether the response was a success along with it, or null
+/// if it couldn't be reached
+const FETCH_JS: &str = r#"
+    const url = await dioxus.recv();
+    try {
+        const response = await fetch(url);
+        return [response.ok, await response.text()];
+    } catch {
+        return null;
+    }
+"#;
+
+async fn fetch_text(url: String) -> Result<(bool, String), String> {
+    let eval = document::eval(FETCH_JS);
+    let _ = eval.send(url);
+    match eval.join::<Option<(bool, String)>>().await {
+        Ok(Some(response)) => Ok(response),
+        _ => Err("Google Sheets couldn't be reached".to_string()),
+    }
+}
+
+/// The sheets a link points to: through the Sheets API with formats if there's a key, and as
+/// the values of a CSV download otherwise
+async fn fetch_sheets(link: String, key: String) -> Result<Vec<SheetFile>, String> {
+    if key.trim().is_empty() {
+        let url = google_sheets::csv_url(&link).map_err(|e| e.to_string())?;
+        let (ok, text) = fetch_text(url).await?;
+        // Sheets that aren't shared answer with a sign-in page
+        if !ok || text.trim_start().starts_with('<') {
+            return Err(
+                "The sheet couldn't be downloaded. Publish it to the web, share it with anyone who has the link, or use an API key."
+                    .to_string(),
+            );
+        }
+        return Ok(vec![google_sheets::read_csv(&text)]);
+    }
+    let url = google_sheets::api_url(&link, &key).map_err(|e| e.to_string())?;
+    let (_, text) = fetch_text(url).await?;
+    google_sheets::read_api_response(&text, google_sheets::sheet_gid(&link))
+        .map_err(|e| e.to_string())
+}
+
+#[component]
+pub fn GoogleSheetsDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut link = use_signal(String::new);
+    let mut key = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+    let mut importing = use_signal(|| false);
+
+    let mut import = move || {
+        if importing() {
+            return;
+        }
+        importing.set(true);
+        message.set(None);
+        spawn(async move {
+            match fetch_sheets(link(), key()).await {
+                Ok(sheets) => {
+                    grid.write().apply(GridCommand::AddSheets(sheets));
+                    close_dialog(dialog, scroll_container);
+                }
+                Err(e) => message.set(Some(e)),
+            }
+            importing.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "url-import",
+
+            div {
+                class: "dialog-message",
+                "A link to a sheet published to the web or shared with anyone who has the link. With a Sheets API key, formats come along with the values."
+            }
+            input {
+                placeholder: "https://docs.google.com/spreadsheets/d/…",
+                value: "{link}",
+                onmounted: move |elem| async move {
+                    let _ = elem.data().set_focus(true).await;
+                },
+                oninput: move |evt| {
+                    link.set(evt.value());
+                    message.set(None);
+                },
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Enter {
+                        import();
+                    }
+                },
+            }
+            input {
+                placeholder: "API key (optional)",
+                value: "{key}",
+                oninput: move |evt| {
+                    key.set(evt.value());
+                    message.set(None);
+                },
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Enter {
+                        import();
+                    }
+                },
+            }
+            if let Some(message) = message() {
+                div { class: "dialog-message", "{message}" }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: importing() || link.read().trim().is_empty(),
+                onclick: move |_| import(),
+                if importing() { "Importing…" } else { "Import" }
+            }
+        }
+    }
+}
@@ -1,153 +1,238 @@
-use std::rc::Rc;
+use std::{collections::BTreeSet, rc::Rc};
 
-use dioxus::{core::spawn_forever, prelude::*};
-use petgraph::{
-    algo,
-    visit::{self, Dfs},
+use dioxus::{
+    core::spawn_forever,
+    html::{geometry::ClientPoint, input_data::MouseButton},
+    prelude::*,
 };
-use tracing::info;
+use wasm_bindgen::JsCast;
 
 use crate::{
-    engine::parser::{self, FormulaError},
-    model::grid::{column_index_to_letter, cell_address_to_coords, Cell, Coords, Grid},
+    components::{
+        autocomplete::{
+            cycle_reference_at_caret, move_caret_to_end, use_autocomplete, AutocompleteDropdown,
+            AutocompleteKey,
+        },
+        chart::Charts,
+        clipboard::{read_system_clipboard, write_system_clipboard},
+        conditional_format::ConditionalIcon,
+        dialog::Dialog,
+        files::save_workbook,
+        filter::{FilterButton, FilterMenu},
+        outline::{outline_style, OutlineToggles},
+        presence::CollaboratorCursors,
+        reference_highlight::ReferenceHighlights,
+        trace::TraceArrows,
+        validation::{ValidationButton, ValidationList, ValidationPrompt},
+    },
+    model::{
+        clipboard,
+        command::{GridCommand, MoveDirection},
+        format::{FontStyle, HorizontalAlign, DEFAULT_FONT_SIZE},
+        grid::{column_index_to_letter, Coords, Grid},
+        outline::OutlineDirection,
+        validation::ValidationRule,
+        viewport::Viewport,
+        workbook::{Sheet, MIN_COLUMN_WIDTH, MIN_ROW_HEIGHT},
+    },
 };
 
-pub fn update_cell_display(mut grid: Signal<Grid>, coords: Coords) {
-    let mut cycle: bool = false;
-    let dependants: Vec<_>;
-    info!("Called for {:?}", coords);
-    {
-        info!("Entered brackets");
-        let mut grid_write = grid.write();
-        let Some(content) = grid_write.cells_map.get(&coords).map(|c| c.content.clone()) else {
-            return;
-        };
-        grid_write.remove_cell_dependencies(coords);
-        let display_value: String;
-        if content.starts_with('=') {
-            if let Some(target_coords) = cell_address_to_coords(content.split_at(1).1) {
-                display_value = match grid_write.get_cell_value_by_address(content.split_at(1).1) {
-                    Ok(value) => value.to_string(),
-                    Err(err) => err.to_string(),
-                };
-                grid_write
-                    .cells_dep_graph
-                    .add_edge(target_coords, coords, ());
-            } else {
-                let cell_ref_resolver =
-                    |ref_str: &str| grid_write.get_cell_value_by_address(ref_str);
-                display_value = match parser::calculate(&content, &cell_ref_resolver) {
-                    Ok((val, deps)) => {
-                        deps.into_iter().for_each(|dep| {
-                            if let Some(dep_coords) = cell_address_to_coords(&dep) {
-                                grid_write.cells_dep_graph.add_edge(dep_coords, coords, ());
-                            }
-                        });
-                        val.to_string()
-                    }
-                    Err(e) => e.to_string(),
-                };
-            }
-            if is_node_in_cycle(&grid_write.cells_dep_graph, coords) {
-                cycle = true;
-            }
-        } else {
-            display_value = content;
-        }
-        grid_write.cells_map.get_mut(&coords).unwrap().display_value = match cycle {
-            false => display_value,
-            true => FormulaError::CircularReference.to_string(),
-        };
-        dependants = grid_write.get_cell_dependants(coords);
-    }
-    if !cycle {
-        dependants.into_iter().for_each(|dependant| {
-            update_cell_display(grid, dependant);
-        });
-    } else {
-        let nodes = get_connected_nodes(&grid.read().cells_dep_graph, coords);
-        nodes.iter().for_each(|node| {
-            grid.write().cells_map.get_mut(node).unwrap().display_value =
-                FormulaError::CircularReference.to_string();
-        });
-        info!("Connected nodes: {:?}", nodes);
-    }
-}
-
-fn is_node_in_cycle<G>(graph: G, node: G::NodeId) -> bool
-where
-    G: visit::IntoNeighbors + visit::Visitable,
-{
-    let mut space = algo::DfsSpace::new(&graph);
-    for neighbour in graph.neighbors(node) {
-        if algo::has_path_connecting(graph, neighbour, node, Some(&mut space)) {
-            return true;
-        }
-    }
-    false
-}
-
-fn get_connected_nodes<G>(graph: G, node: G::NodeId) -> <G as petgraph::visit::Visitable>::Map
-where
-    G: visit::IntoNeighbors + visit::Visitable,
-{
-    let mut dfs = Dfs::new(&graph, node);
-    while dfs.next(&graph).is_some() {}
-    dfs.discovered
-}
-
 static GRID_CSS: Asset = asset!("/assets/grid.css");
 
 #[component]
 pub fn GridDisplay(
     grid: Signal<Grid>,
     scroll_container: Signal<Option<Rc<MountedData>>>,
+    dialog: Signal<Option<Dialog>>,
 ) -> Element {
+    let mut resizing = use_signal(|| None::<Resize>);
+    let mut selecting = use_signal(|| false);
+    let mut filling = use_signal(|| None::<Coords>);
+    let mut moving = use_signal(|| None::<MoveDrag>);
+    let filter_menu = use_signal(|| None::<(i32, ClientPoint)>);
+    let list_menu = use_signal(|| None::<(Coords, ClientPoint)>);
+    let cell_menu = use_signal(|| None::<(Coords, ClientPoint)>);
+    let mut viewport = use_signal(Viewport::default);
+
+    // Keeps the moving corner of the selection in view however it got moved, mouse scrolling
+    // staying free until it moves again
+    let selection_end = use_memo(move || grid.read().view.selection().active_range().extent);
+    use_effect(move || scroll_into_view(&grid.peek(), selection_end()));
+    // Zooming shows more or less of the grid without any scrolling to say so
+    let zoom = use_memo(move || grid.read().view.zoom);
+    use_effect(move || {
+        zoom();
+        if let Some(shown) = read_viewport(&grid.peek()) {
+            viewport.set(shown);
+        }
+    });
+
     rsx! {
         document::Stylesheet { href: GRID_CSS }
         div {
+            id: "scroll-container",
             class: "scroll-container",
             tabindex: "0",
 
+            onmousemove: move |evt| {
+                if let Some(mut resize) = resizing() {
+                    resize.drag_to(grid_point(&grid.read(), evt.client_coordinates()));
+                    resizing.set(Some(resize));
+                }
+            },
+            onmouseup: move |_| {
+                selecting.set(false);
+                if let Some(resize) = resizing.take() {
+                    grid.write().apply(resize.command());
+                }
+                if let Some(target) = filling.take() {
+                    grid.write().apply(GridCommand::FillTo(target));
+                }
+                if let Some(target) = moving.take().and_then(|drag| drag.target(&grid.read())) {
+                    grid.write().apply(GridCommand::MoveRange(target));
+                }
+            },
+            onmouseleave: move |_| {
+                selecting.set(false);
+                resizing.set(None);
+                filling.set(None);
+                moving.set(None);
+            },
+
             onmounted: move |elem| async move {
                 scroll_container.set(Some(elem.data()));
+                if let Some(shown) = read_viewport(&grid.read()) {
+                    viewport.set(shown);
+                }
                 let _ = elem.data().set_focus(true).await;
             },
+            onscroll: move |_| {
+                if let Some(shown) = read_viewport(&grid.read()) {
+                    viewport.set(shown);
+                }
+            },
+            onresize: move |_| {
+                if let Some(shown) = read_viewport(&grid.read()) {
+                    viewport.set(shown);
+                }
+            },
 
             onkeydown: move |evt| {
                 evt.prevent_default();
                 match evt.key() {
-                    Key::ArrowDown => {
-                        grid.write().current_cell_down_one();
-                    }
-                    Key::ArrowUp => {
-                        grid.write().current_cell_up_one();
+                    Key::ArrowDown | Key::ArrowUp | Key::ArrowLeft | Key::ArrowRight => {
+                        let direction = match evt.key() {
+                            Key::ArrowDown => MoveDirection::Down,
+                            Key::ArrowUp => MoveDirection::Up,
+                            Key::ArrowLeft => MoveDirection::Left,
+                            _ => MoveDirection::Right,
+                        };
+                        let command = match (evt.modifiers().ctrl(), evt.modifiers().shift()) {
+                            (true, true) => GridCommand::ExtendSelectionToEdge(direction),
+                            (true, false) => GridCommand::JumpSelection(direction),
+                            (false, true) => GridCommand::ExtendSelectionTowards(direction),
+                            (false, false) => GridCommand::MoveSelection(direction),
+                        };
+                        grid.write().apply(command);
                     }
-                    Key::ArrowLeft => {
-                        grid.write().current_cell_left_one();
+                    Key::PageDown | Key::PageUp => {
+                        let down = evt.key() == Key::PageDown;
+                        let from = grid.read().current_cell();
+                        let height = viewport_height(&grid.read());
+                        if let Some(height) = height {
+                            grid.write().apply(GridCommand::MovePage { down, height });
+                        }
+                        // The view pages along with the current cell
+                        let to = grid.read().current_cell();
+                        let distance = grid.read().get_cell_rect(to).y - grid.read().get_cell_rect(from).y;
+                        scroll_by(&grid.read(), 0, distance);
                     }
-                    Key::ArrowRight => {
-                        grid.write().current_cell_right_one();
+                    Key::Home if evt.modifiers().ctrl() => {
+                        grid.write().apply(GridCommand::SelectCell(Coords { row: 0, column: 0 }));
                     }
+                    Key::End if evt.modifiers().ctrl() => grid.write().apply(GridCommand::GoToLastCell),
                     Key::Enter => {
                         if evt.modifiers().shift() {
-                            grid.write().current_cell_up_one();
+                            grid.write().apply(GridCommand::MoveSelection(MoveDirection::Up));
                         } else {
-                            grid.write().current_cell_down_one();
+                            grid.write().apply(GridCommand::MoveSelection(MoveDirection::Down));
                         }
                     }
                     Key::Tab => {
                         if evt.modifiers().shift() {
-                            grid.write().current_cell_left_one();
+                            grid.write().apply(GridCommand::MoveSelection(MoveDirection::Left));
                         } else {
-                            grid.write().current_cell_right_one();
+                            grid.write().apply(GridCommand::MoveSelection(MoveDirection::Right));
+                        }
+                    }
+                    Key::F2 => {
+                        grid.write().apply(GridCommand::StartEditing { initial_content: None });
+                    }
+                    Key::Escape => {
+                        grid.write().apply(GridCommand::ClearClipboard);
+                    }
+                    Key::Delete => grid.write().apply(GridCommand::ClearContents),
+                    Key::Character(c) if c == " " && evt.modifiers().ctrl() => {
+                        grid.write().apply(GridCommand::SelectEntireColumns);
+                    }
+                    Key::Character(c) if c == " " && evt.modifiers().shift() => {
+                        grid.write().apply(GridCommand::SelectEntireRows);
+                    }
+                    Key::Backspace => {
+                        grid.write().apply(GridCommand::StartEditing { initial_content: Some(String::new()) });
+                    }
+                    // AltGr arrives as Ctrl+Alt, and types characters rather than shortcuts
+                    Key::Character(c) if evt.modifiers().ctrl() && !evt.modifiers().alt() => match c.to_lowercase().as_str() {
+                        "f" if grid.read().view.search_query.is_none() => {
+                            grid.write().apply(GridCommand::SetSearchQuery(Some(String::new())));
+                        }
+                        "c" | "x" => {
+                            let command = match c.eq_ignore_ascii_case("x") {
+                                true => GridCommand::Cut,
+                                false => GridCommand::Copy,
+                            };
+                            grid.write().apply(command);
+                            if let Some(copied) = grid.read().view.clipboard.clone() {
+                                spawn(write_system_clipboard(copied.to_tsv(), copied.to_html()));
+                            }
                         }
+                        "v" if evt.modifiers().shift() => dialog.set(Some(Dialog::PasteSpecial)),
+                        "e" => dialog.set(Some(Dialog::FlashFill)),
+                        "a" => grid.write().apply(GridCommand::SelectAll),
+                        "b" => grid.write().apply(GridCommand::ToggleFontStyle(FontStyle::Bold)),
+                        "i" => grid.write().apply(GridCommand::ToggleFontStyle(FontStyle::Italic)),
+                        "u" => grid.write().apply(GridCommand::ToggleFontStyle(FontStyle::Underline)),
+                        "z" if evt.modifiers().shift() => grid.write().apply(GridCommand::Redo),
+                        "z" => grid.write().apply(GridCommand::Undo),
+                        "y" => grid.write().apply(GridCommand::Redo),
+                        "g" => dialog.set(Some(Dialog::GoTo)),
+                        "p" => dialog.set(Some(Dialog::Print)),
+                        "s" => {
+                            spawn(save_workbook(grid, evt.modifiers().shift()));
+                        }
+                        "v" => {
+                            spawn(async move {
+                                // Our own copy pastes formulas, anything else pastes as values
+                                let external = read_system_clipboard().await.filter(|(text, _)| {
+                                    !text.is_empty()
+                                        && !grid.read().view.clipboard.as_ref()
+                                            .is_some_and(|copied| copied.matches_text(text))
+                                });
+                                match external {
+                                    Some((text, html)) => {
+                                        let rows = clipboard::parse_external(&text, html.as_deref());
+                                        grid.write().apply(GridCommand::PasteRows(rows));
+                                    }
+                                    None => grid.write().apply(GridCommand::Paste),
+                                }
+                            });
+                        }
+                        _ => {}
                     }
-                    Key::Character(c) => if c.len() == 1 {
-                        let previous_value = grid.write().get_current_cell_content().clone();
-                        grid.write().previous_content = previous_value;
-                        grid.write().get_mut_current_cell().content = c;
-                        grid.write().is_editing_cell = true;
+                    // Whatever the key typed, however many bytes or characters: accents finished
+                    // after a dead key, emoji, other scripts
+                    Key::Character(c) if !c.is_empty() && !c.chars().any(char::is_control) && !evt.modifiers().meta() => {
+                        grid.write().apply(GridCommand::StartEditing { initial_content: Some(c) });
                     }
                     _ => {}
                 }
@@ -157,33 +242,52 @@ pub fn GridDisplay(
                 let grid_read = grid.read();
                 let grid_template_columns = format!(
                     "{}px {}",
-                    grid_read.base_header_column_width,
-                    grid_read
-                        .column_widths
-                        .iter()
-                        .map(|width| format!("{}px", width))
+                    grid_read.view.base_header_column_width,
+                    (0..grid_read.sheet().column_widths.len())
+                        .map(|column| format!("{}px", grid_read.sheet().displayed_column_width(column)))
                         .collect::<Vec<_>>()
                         .join(" ")
                     );
                 let grid_template_rows = format!(
                     "{}px {}",
-                    grid_read.base_header_row_height,
-                    grid_read
-                        .row_heights
-                        .iter()
-                        .map(|height| format!("{}px", height))
+                    grid_read.view.base_header_row_height,
+                    (0..grid_read.sheet().row_heights.len())
+                        .map(|row| format!("{}px", grid_read.sheet().displayed_row_height(row)))
                         .collect::<Vec<_>>()
                         .join(" ")
                     );
                 rsx! {
                     div {
                         class: "grid",
-                        style: "grid-template-columns: {grid_template_columns}; grid-template-rows: {grid_template_rows};",
+                        style: "grid-template-columns: {grid_template_columns}; grid-template-rows: {grid_template_rows}; zoom: {grid_read.view.zoom_factor()};",
 
                         CornerCell { grid }
-                        HeaderRow { grid }
-                        HeaderColumn { grid }
-                        GridCells { grid, scroll_container }
+                        HeaderRow { grid, viewport, resizing, selecting }
+                        HeaderColumn { grid, viewport, resizing, selecting }
+                        GridCells { grid, scroll_container, viewport, selecting, filling, moving, filter_menu, list_menu, cell_menu }
+                        SelectionRange { grid }
+                        if !grid.read().view.read_only {
+                            MoveHandles { grid, moving }
+                            FillHandle { grid, filling }
+                        }
+                        FillPreview { grid, filling }
+                        MovePreview { grid, moving }
+                        CopiedRange { grid }
+                        CollaboratorCursors { grid }
+                        TraceArrows { grid }
+                        ReferenceHighlights { grid }
+                        ResizePreview { grid, resizing }
+                        Charts { grid, dialog }
+                        if let Some((column, point)) = filter_menu() {
+                            FilterMenu { grid, filter_menu, column, point }
+                        }
+                        ValidationPrompt { grid }
+                        if let Some((coords, point)) = list_menu() {
+                            ValidationList { grid, list_menu, coords, point }
+                        }
+                        if let Some((coords, point)) = cell_menu() {
+                            CellMenu { dialog, cell_menu, coords, point }
+                        }
                     }
                 }
             }
@@ -202,55 +306,578 @@ fn CornerCell(grid: Signal<Grid>) -> Element {
 }
 
 #[component]
-fn HeaderRow(grid: Signal<Grid>) -> Element {
+fn HeaderRow(
+    grid: Signal<Grid>,
+    viewport: Signal<Viewport>,
+    resizing: Signal<Option<Resize>>,
+    selecting: Signal<bool>,
+) -> Element {
+    let selected = grid.read().get_selected_columns();
+    let last_row = grid.read().sheet().row_heights.len() as i32 - 1;
+    let mut menu = use_signal(|| None::<(i32, ClientPoint)>);
+    // Only the headers of the columns drawn, leaving out collapsed groups' columns
+    let visible = use_memo(move || grid.read().visible_columns(viewport()));
+    let frozen = grid.read().view.frozen_panes().unwrap_or(Coords { row: 0, column: 0 });
+    let shown_columns = visible()
+        .map(|col| col as i32)
+        .chain(0..frozen.column)
+        .filter(|col| !grid.read().sheet().is_column_hidden(*col))
+        .collect::<BTreeSet<_>>();
+
     rsx! {
-        for col in 0..grid.read().column_widths.len() as i32 {
+        for col in shown_columns {
             div {
-                class: "column-header header-cell",
-                style: "grid-row: 1; grid-column: {col + 2};",
+                class: if selected.contains(&col) { "column-header header-cell header-selected" } else { "column-header header-cell" },
+                style: "grid-row: 1; grid-column: {col + 2};{frozen_column_header_style(&grid.read(), col)}{outline_style(&grid.read(), OutlineDirection::Columns, col)}",
+                onmousedown: move |evt| {
+                    evt.prevent_default();
+                    // A right click keeps the selection, for the menu to sort it
+                    if evt.trigger_button() == Some(MouseButton::Secondary) {
+                        return;
+                    }
+                    if evt.modifiers().shift() {
+                        grid.write().apply(GridCommand::ExtendSelection(Coords { row: last_row, column: col }));
+                    } else {
+                        grid.write().apply(GridCommand::SelectColumn(col));
+                    }
+                    selecting.set(true);
+                },
+                onmouseenter: move |_| {
+                    if selecting() {
+                        grid.write().apply(GridCommand::ExtendSelection(Coords { row: last_row, column: col }));
+                    }
+                },
+                oncontextmenu: move |evt| {
+                    evt.prevent_default();
+                    menu.set(Some((col, grid_point(&grid.read(), evt.client_coordinates()))));
+                },
                 "{column_index_to_letter(col)}"
+                OutlineToggles { grid, direction: OutlineDirection::Columns, index: col }
+                if !grid.read().view.read_only {
+                    div {
+                        class: "column-resize-handle",
+                        onmousedown: move |evt| {
+                            evt.stop_propagation();
+                            evt.prevent_default();
+                            let width = grid.read().sheet().column_widths[col as usize];
+                            let edge = ResizeEdge::Column(col as usize);
+                            resizing.set(Some(Resize::new(edge, grid_point(&grid.read(), evt.client_coordinates()), width)));
+                        },
+                        ondoubleclick: move |evt| {
+                            evt.stop_propagation();
+                            let width = fit_column_width(&grid.read(), col as usize);
+                            if let Some(width) = width {
+                                grid.write().apply(GridCommand::ResizeColumn { column: col as usize, width });
+                            }
+                        },
+                    }
+                }
+            }
+        }
+        if let Some((column, point)) = menu() {
+            ColumnMenu { grid, menu, column, point }
+        }
+    }
+}
+
+/// Sorts by a column from its header's context menu
+#[component]
+fn ColumnMenu(
+    grid: Signal<Grid>,
+    menu: Signal<Option<(i32, ClientPoint)>>,
+    column: i32,
+    point: ClientPoint,
+) -> Element {
+    let mut sort = move |descending: bool| {
+        grid.write().apply(GridCommand::SortRange { column, descending });
+        menu.set(None);
+    };
+
+    rsx! {
+        div {
+            class: "context-menu-backdrop",
+            onmousedown: move |_| menu.set(None),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                menu.set(None);
+            },
+        }
+        div {
+            class: "context-menu",
+            style: "left: {point.x}px; top: {point.y}px;",
+            button {
+                disabled: grid.read().get_sort_range().is_none(),
+                onclick: move |_| sort(false),
+                "Sort A to Z"
+            }
+            button {
+                disabled: grid.read().get_sort_range().is_none(),
+                onclick: move |_| sort(true),
+                "Sort Z to A"
+            }
+        }
+    }
+}
+
+#[component]
+fn CellMenu(
+    dialog: Signal<Option<Dialog>>,
+    cell_menu: Signal<Option<(Coords, ClientPoint)>>,
+    coords: Coords,
+    point: ClientPoint,
+) -> Element {
+    rsx! {
+        div {
+            class: "context-menu-backdrop",
+            onmousedown: move |_| cell_menu.set(None),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                cell_menu.set(None);
+            },
+        }
+        div {
+            class: "context-menu",
+            style: "left: {point.x}px; top: {point.y}px;",
+            button {
+                onclick: move |_| {
+                    cell_menu.set(None);
+                    dialog.set(Some(Dialog::EditHistory(coords)));
+                },
+                "Show edit history"
             }
         }
     }
 }
 
 #[component]
-fn HeaderColumn(grid: Signal<Grid>) -> Element {
+fn HeaderColumn(
+    grid: Signal<Grid>,
+    viewport: Signal<Viewport>,
+    resizing: Signal<Option<Resize>>,
+    selecting: Signal<bool>,
+) -> Element {
+    let selected = grid.read().get_selected_rows();
+    let last_column = grid.read().sheet().column_widths.len() as i32 - 1;
+    // Only the headers of the rows drawn, as for the cells
+    let visible = use_memo(move || grid.read().visible_rows(viewport()));
+    let frozen = grid.read().view.frozen_panes().unwrap_or(Coords { row: 0, column: 0 });
+    let shown_rows = visible()
+        .chain(0..frozen.row as usize)
+        .filter(|row| !grid.read().sheet().is_row_hidden(*row as i32))
+        .collect::<BTreeSet<_>>();
+
     rsx! {
-        for row in 0..grid.read().row_heights.len() {
+        for row in shown_rows {
             div {
-                class: "row-header header-cell",
-                style: "grid-row: {row + 2}; grid-column: 1;",
+                class: if selected.contains(&(row as i32)) { "row-header header-cell header-selected" } else { "row-header header-cell" },
+                style: "grid-row: {row + 2}; grid-column: 1;{frozen_row_header_style(&grid.read(), row as i32)}{outline_style(&grid.read(), OutlineDirection::Rows, row as i32)}",
+                onmousedown: move |evt| {
+                    evt.prevent_default();
+                    if evt.modifiers().shift() {
+                        grid.write().apply(GridCommand::ExtendSelection(Coords { row: row as i32, column: last_column }));
+                    } else {
+                        grid.write().apply(GridCommand::SelectRow(row as i32));
+                    }
+                    selecting.set(true);
+                },
+                onmouseenter: move |_| {
+                    if selecting() {
+                        grid.write().apply(GridCommand::ExtendSelection(Coords { row: row as i32, column: last_column }));
+                    }
+                },
                 "{row + 1}"
+                OutlineToggles { grid, direction: OutlineDirection::Rows, index: row as i32 }
+                if !grid.read().view.read_only {
+                    div {
+                        class: "row-resize-handle",
+                        onmousedown: move |evt| {
+                            evt.stop_propagation();
+                            evt.prevent_default();
+                            let height = grid.read().sheet().row_heights[row];
+                            let edge = ResizeEdge::Row(row);
+                            resizing.set(Some(Resize::new(edge, grid_point(&grid.read(), evt.client_coordinates()), height)));
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Frozen cells stick below the headers, those frozen both ways above the rest
+fn frozen_cell_style(grid: &Grid, coords: Coords) -> String {
+    let (left, top) = grid.get_frozen_offsets(coords);
+    let z_index = match (left, top) {
+        (None, None) => return String::new(),
+        (Some(_), Some(_)) => 2,
+        _ => 1,
+    };
+    format!(
+        " position: sticky;{}{} z-index: {z_index};",
+        left.map(|x| format!(" left: {x}px;")).unwrap_or_default(),
+        top.map(|y| format!(" top: {y}px;")).unwrap_or_default(),
+    )
+}
+
+/// Borders around the selected ranges
+#[component]
+fn SelectionRange(grid: Signal<Grid>) -> Element {
+    let grid_read = grid.read();
+    let selection = grid_read.view.selection();
+    if selection.is_single_cell() {
+        return rsx! {};
+    }
+    let rects = selection
+        .ranges
+        .iter()
+        .map(|range| {
+            let first = grid_read.get_cell_rect(range.top_left());
+            let last = grid_read.get_cell_rect(range.bottom_right());
+            let width = last.x + last.width - first.x;
+            let height = last.y + last.height - first.y;
+            (first.x, first.y, width, height)
+        })
+        .collect::<Vec<_>>();
+
+    rsx! {
+        for (left, top, width, height) in rects {
+            div {
+                class: "selection-range",
+                style: "left: {left}px; top: {top}px; width: {width}px; height: {height}px;",
+            }
+        }
+    }
+}
+
+/// Square on the active range's corner, dragging it fills the cells it's dragged over
+#[component]
+fn FillHandle(grid: Signal<Grid>, filling: Signal<Option<Coords>>) -> Element {
+    let grid_read = grid.read();
+    if grid_read.view.is_editing_cell {
+        return rsx! {};
+    }
+    let corner = grid_read.view.selection().active_range().bottom_right();
+    let rect = grid_read.get_cell_rect(corner);
+    let (x, y) = (rect.x + rect.width, rect.y + rect.height);
+
+    rsx! {
+        div {
+            class: "fill-handle",
+            style: "left: {x}px; top: {y}px;",
+            onmousedown: move |evt| {
+                evt.stop_propagation();
+                evt.prevent_default();
+                filling.set(Some(corner));
+            },
+        }
+    }
+}
+
+/// Outline of the cells a fill handle drag will fill
+#[component]
+fn FillPreview(grid: Signal<Grid>, filling: Signal<Option<Coords>>) -> Element {
+    let grid_read = grid.read();
+    let Some((range, _)) = filling().and_then(|target| grid_read.get_fill_range(target)) else {
+        return rsx! {};
+    };
+    let first = grid_read.get_cell_rect(range.top_left());
+    let last = grid_read.get_cell_rect(range.bottom_right());
+    let width = last.x + last.width - first.x;
+    let height = last.y + last.height - first.y;
+
+    rsx! {
+        div {
+            class: "fill-preview",
+            style: "left: {first.x}px; top: {first.y}px; width: {width}px; height: {height}px;",
+        }
+    }
+}
+
+/// A drag of the active range's border. The range moves by how far the mouse has gone since
+/// entering its first cell, which is next to the border grabbed.
+#[derive(Clone, Copy, PartialEq)]
+struct MoveDrag {
+    from: Option<Coords>,
+    to: Option<Coords>,
+}
+
+impl MoveDrag {
+    fn enter(&mut self, coords: Coords) {
+        self.from = self.from.or(Some(coords));
+        self.to = Some(coords);
+    }
+
+    /// Where the range's top-left is dragged to, if it's been dragged anywhere
+    fn target(&self, grid: &Grid) -> Option<Coords> {
+        let (from, to) = (self.from?, self.to?);
+        if from == to {
+            return None;
+        }
+        let top_left = grid.view.selection().active_range().top_left();
+        Some(Coords {
+            row: top_left.row + to.row - from.row,
+            column: top_left.column + to.column - from.column,
+        })
+    }
+}
+
+/// Strips along the active range's border to grab it by and drag it somewhere else
+#[component]
+fn MoveHandles(grid: Signal<Grid>, moving: Signal<Option<MoveDrag>>) -> Element {
+    let grid_read = grid.read();
+    if grid_read.view.is_editing_cell {
+        return rsx! {};
+    }
+    let range = grid_read.view.selection().active_range();
+    let first = grid_read.get_cell_rect(range.top_left());
+    let last = grid_read.get_cell_rect(range.bottom_right());
+    let width = last.x + last.width - first.x;
+    let height = last.y + last.height - first.y;
+    let strips = [
+        (first.x, first.y, width, 0),
+        (first.x, first.y + height, width, 0),
+        (first.x, first.y, 0, height),
+        (first.x + width, first.y, 0, height),
+    ];
+
+    rsx! {
+        for (left, top, width, height) in strips {
+            div {
+                class: "move-handle",
+                style: "left: {left}px; top: {top}px; width: {width}px; height: {height}px;",
+                onmousedown: move |evt| {
+                    evt.stop_propagation();
+                    evt.prevent_default();
+                    moving.set(Some(MoveDrag { from: None, to: None }));
+                },
             }
         }
     }
 }
 
+/// Ghost of the active range where a border drag will drop it
+#[component]
+fn MovePreview(grid: Signal<Grid>, moving: Signal<Option<MoveDrag>>) -> Element {
+    let grid_read = grid.read();
+    let Some(target) = moving().and_then(|drag| drag.target(&grid_read)) else {
+        return rsx! {};
+    };
+    let range = grid_read.get_move_range(target);
+    let first = grid_read.get_cell_rect(range.top_left());
+    let last = grid_read.get_cell_rect(range.bottom_right());
+    let width = last.x + last.width - first.x;
+    let height = last.y + last.height - first.y;
+
+    rsx! {
+        div {
+            class: "move-preview",
+            style: "left: {first.x}px; top: {first.y}px; width: {width}px; height: {height}px;",
+        }
+    }
+}
+
+/// Dashed border around the copied cells while they can be pasted
+#[component]
+fn CopiedRange(grid: Signal<Grid>) -> Element {
+    let grid_read = grid.read();
+    let Some(clipboard) = grid_read.view.clipboard.as_ref() else {
+        return rsx! {};
+    };
+    if clipboard.source.sheet != grid_read.view.active_sheet {
+        return rsx! {};
+    }
+    let first = grid_read.get_cell_rect(clipboard.source.coords);
+    let last = grid_read.get_cell_rect(Coords {
+        row: clipboard.source.coords.row + clipboard.rows - 1,
+        column: clipboard.source.coords.column + clipboard.columns - 1,
+    });
+    let width = last.x + last.width - first.x;
+    let height = last.y + last.height - first.y;
+
+    rsx! {
+        div {
+            class: "copied-range",
+            style: "left: {first.x}px; top: {first.y}px; width: {width}px; height: {height}px;",
+        }
+    }
+}
+
+// Headers of frozen rows and columns stick along with their cells, above the other headers
+fn frozen_column_header_style(grid: &Grid, column: i32) -> String {
+    match grid.get_frozen_offsets(Coords { row: 0, column }) {
+        (Some(x), _) => format!(" left: {x}px; z-index: 4;"),
+        _ => String::new(),
+    }
+}
+fn frozen_row_header_style(grid: &Grid, row: i32) -> String {
+    match grid.get_frozen_offsets(Coords { row, column: 0 }) {
+        (_, Some(y)) => format!(" top: {y}px; z-index: 4;"),
+        _ => String::new(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ResizeEdge {
+    Column(usize),
+    Row(usize),
+}
+
+/// A header edge being dragged. The sheet only changes once the mouse is released.
+#[derive(Clone, Copy, PartialEq)]
+struct Resize {
+    edge: ResizeEdge,
+    start: f64,
+    original_size: i32,
+    size: i32,
+}
+
+impl Resize {
+    fn new(edge: ResizeEdge, point: ClientPoint, size: i32) -> Self {
+        let mut resize = Resize {
+            edge,
+            start: 0.0,
+            original_size: size,
+            size,
+        };
+        resize.start = resize.position(point);
+        resize
+    }
+
+    fn position(&self, point: ClientPoint) -> f64 {
+        match self.edge {
+            ResizeEdge::Column(_) => point.x,
+            ResizeEdge::Row(_) => point.y,
+        }
+    }
+
+    fn drag_to(&mut self, point: ClientPoint) {
+        let min_size = match self.edge {
+            ResizeEdge::Column(_) => MIN_COLUMN_WIDTH,
+            ResizeEdge::Row(_) => MIN_ROW_HEIGHT,
+        };
+        let delta = (self.position(point) - self.start).round() as i32;
+        self.size = (self.original_size + delta).max(min_size);
+    }
+
+    fn command(&self) -> GridCommand {
+        match self.edge {
+            ResizeEdge::Column(column) => GridCommand::ResizeColumn {
+                column,
+                width: self.size,
+            },
+            ResizeEdge::Row(row) => GridCommand::ResizeRow {
+                row,
+                height: self.size,
+            },
+        }
+    }
+}
+
+/// Line showing where the dragged edge will end up
+#[component]
+fn ResizePreview(grid: Signal<Grid>, resizing: Signal<Option<Resize>>) -> Element {
+    let Some(resize) = resizing() else {
+        return rsx! {};
+    };
+    let (class, style) = match resize.edge {
+        ResizeEdge::Column(column) => {
+            let rect = grid.read().get_cell_rect(Coords { row: 0, column: column as i32 });
+            (
+                "resize-preview resize-preview-column",
+                format!("left: {}px;", rect.x + resize.size),
+            )
+        }
+        ResizeEdge::Row(row) => {
+            let rect = grid.read().get_cell_rect(Coords { row: row as i32, column: 0 });
+            (
+                "resize-preview resize-preview-row",
+                format!("top: {}px;", rect.y + resize.size),
+            )
+        }
+    };
+
+    rsx! {
+        div { class, style }
+    }
+}
+
 #[component]
-fn GridCells(grid: Signal<Grid>, scroll_container: Signal<Option<Rc<MountedData>>>) -> Element {
+fn GridCells(
+    grid: Signal<Grid>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    viewport: Signal<Viewport>,
+    selecting: Signal<bool>,
+    filling: Signal<Option<Coords>>,
+    moving: Signal<Option<MoveDrag>>,
+    filter_menu: Signal<Option<(i32, ClientPoint)>>,
+    list_menu: Signal<Option<(Coords, ClientPoint)>>,
+    cell_menu: Signal<Option<(Coords, ClientPoint)>>,
+) -> Element {
+    let table_styles = grid.read().sheet().table_styles();
+    let conditional_styles = grid.read().sheet().conditional_styles();
+    let invalid_cells = grid.read().get_invalid_cells();
+    // Only what's scrolled into view is drawn, changing as the view moves past whole cells
+    let visible = use_memo(move || grid.read().visible_cells(viewport()));
+    let frozen = grid.read().view.frozen_panes().unwrap_or(Coords { row: 0, column: 0 });
+    // Filtered out rows and collapsed groups aren't drawn at all
+    let shown_rows = visible()
+        .rows
+        .chain(0..frozen.row as usize)
+        .filter(|row| !grid.read().sheet().is_row_hidden(*row as i32))
+        .collect::<BTreeSet<_>>();
+    let shown_columns = visible()
+        .columns
+        .chain(0..frozen.column as usize)
+        .filter(|col| !grid.read().sheet().is_column_hidden(*col as i32))
+        .collect::<BTreeSet<_>>();
+
     rsx! {
-        for row in 0..grid.read().row_heights.len() {
-            for col in 0..grid.read().column_widths.len() {
+        for row in shown_rows {
+            for col in shown_columns.clone() {
                 {
                     let grid_read = grid.read();
+                    let sheet = grid_read.sheet();
+                    let current_cell = grid_read.current_cell();
+                    let selection = grid_read.view.selection();
                     let coords = Coords { row: row as i32, column: col as i32 };
-                    let cell = grid_read.cells_map.get(&coords);
+                    let cell = sheet.cells_map.get(&coords);
 
-                    let display_value = cell.map(|c| c.display_value.as_str()).unwrap_or_default();
-                    let sci_noatation = match display_value.parse::<f64>() {
-                        Ok(val) => format!("{:.2e}", val),
-                        Err(_) => display_value.to_string(),
+                    let display_value = cell
+                        .map(|c| c.format.number_format.apply(&c.display_value))
+                        .unwrap_or_default();
+                    let diagnostic = cell
+                        .and_then(|c| c.diagnostic.as_ref())
+                        .map(|d| d.to_string())
+                        .unwrap_or_default();
+                    // Numbers are told apart by the value, not by how its format shows it
+                    let number = cell.and_then(|c| c.display_value.parse::<f64>().ok());
+                    let is_text = number.is_none();
+                    let sci_noatation = match number {
+                        Some(val) => format!("{:.2e}", val),
+                        None => display_value.to_string(),
+                    };
+                    // 5 - border + padding size on each side
+                    let text_space = (sheet.column_widths[col] - 5 * 2) as f64;
+                    let font = cell.map(|c| c.format.font()).unwrap_or_default();
+                    let fits = |text: &str| {
+                        measure_text_width(text, &font).is_none_or(|width| width <= text_space)
+                    };
+                    // Text too wide for its column runs on over the empty cells to its right
+                    let can_run_on = cell.is_none_or(|c| {
+                        c.format.horizontal_align.is_none_or(|align| align == HorizontalAlign::Left)
+                            && c.format.rotation == 0
+                            && !c.format.vertical_text
+                    });
+                    let text_style = cell.map(|c| c.format.text_style()).unwrap_or_default();
+                    let overflow_width = match is_text && can_run_on && !fits(&display_value) {
+                        true => measure_text_width(&display_value, &font)
+                            .map(|width| overflow_width(sheet, coords, width)),
+                        false => None,
                     };
-                    // Number of characters that can fit in the cell
-                    // 5 - border + padding size
-                    // 7 - font size (idk, works)
-                    // NOTE: change this after implementing changable font & border size
-                    let char_space = (grid_read.column_widths[col] - 5 * 2) / 7;
 
-                    let is_selected = grid_read.current_cell == coords;
-                    let top_is_selected = grid_read.current_cell == Coords { row: row as i32 - 1 , column: col as i32 };
-                    let left_is_selected = grid_read.current_cell == Coords { row: row as i32, column: col as i32 - 1 };
+                    let is_selected = current_cell == coords;
+                    let top_is_selected = current_cell == Coords { row: row as i32 - 1 , column: col as i32 };
+                    let left_is_selected = current_cell == Coords { row: row as i32, column: col as i32 - 1 };
 
                     let cell_class =
                         if is_selected { "cell cell-selected" }
@@ -258,29 +885,125 @@ fn GridCells(grid: Signal<Grid>, scroll_container: Signal<Option<Rc<MountedData>
                         else if left_is_selected { "cell cell-selected-left" }
                         else { "cell" };
 
-                    let is_editing = grid_read.is_editing_cell && is_selected;
+                    let cell_class = match cell.is_some_and(|c| grid_read.is_search_match(c)) {
+                        true => format!("{cell_class} cell-search-match"),
+                        false => cell_class.to_string(),
+                    };
+                    let cell_class = match !is_selected && selection.contains(coords) {
+                        true => format!("{cell_class} cell-in-selection"),
+                        false => cell_class,
+                    };
+                    let cell_class = match overflow_width {
+                        Some(_) => format!("{cell_class} cell-overflowing"),
+                        None => cell_class,
+                    };
+                    let cell_class = match invalid_cells.contains(&coords) {
+                        true => format!("{cell_class} cell-invalid"),
+                        false => cell_class,
+                    };
+                    let has_comments = sheet.has_open_comments(coords);
+                    let cell_class = match has_comments {
+                        true => format!("{cell_class} cell-commented"),
+                        false => cell_class,
+                    };
+
+                    let is_editing = grid_read.view.is_editing_cell && is_selected;
+                    let has_list = is_selected && !is_editing && matches!(
+                        sheet.validation_at(coords).map(|validation| &validation.rule),
+                        Some(ValidationRule::List(_))
+                    );
+                    let frozen_style = frozen_cell_style(&grid_read, coords);
+                    let table_style = table_styles.get(&coords).map(|style| style.to_style()).unwrap_or_default();
+                    // The table draws under the cell's own format
+                    let format_style = cell
+                        .map(|c| c.format.alignment_style(&c.display_value) + &table_style + &c.format.to_style())
+                        .unwrap_or(table_style);
+                    // A custom code's section color, like [Red] for negatives, wins over the text color
+                    let format_style = match cell.and_then(|c| c.format.number_format.color(&c.display_value)) {
+                        Some(color) => format!("{format_style}color: {color};"),
+                        None => format_style,
+                    };
+                    let hides_value = conditional_styles.get(&coords).is_some_and(|style| style.hide_value);
+                    let icon = conditional_styles.get(&coords).and_then(|style| style.icon);
+                    // Whether the header cell's column is filtered, if it gets a filter button
+                    let filter_button = sheet
+                        .auto_filter
+                        .as_ref()
+                        .filter(|filter| filter.header_row() == coords.row && filter.columns().contains(&coords.column))
+                        .map(|filter| filter.filters.contains_key(&coords.column));
+                    let format_style = match conditional_styles.get(&coords) {
+                        Some(style) => format_style + &style.to_style(),
+                        None => format_style,
+                    };
 
                     rsx! {
                         div {
                             class: "{cell_class}",
-                            style: "grid-row: {row + 2}; grid-column: {col + 2};",
-                            onclick: move |_| {
-                                grid.write().current_cell = Coords { row: row as i32, column: col as i32};
+                            style: "grid-row: {row + 2}; grid-column: {col + 2};{frozen_style}{format_style}",
+                            title: "{diagnostic}",
+                            onmousedown: move |evt| {
+                                // A right click in the selection keeps it
+                                if evt.trigger_button() == Some(MouseButton::Secondary)
+                                    && grid.read().view.selection().contains(coords)
+                                {
+                                    return;
+                                }
+                                if evt.modifiers().shift() {
+                                    grid.write().apply(GridCommand::ExtendSelection(coords));
+                                } else if evt.modifiers().ctrl() {
+                                    grid.write().apply(GridCommand::AddToSelection(coords));
+                                } else {
+                                    grid.write().apply(GridCommand::SelectCell(coords));
+                                }
+                                selecting.set(true);
+                            },
+                            onmouseenter: move |_| {
+                                if selecting() {
+                                    grid.write().apply(GridCommand::ExtendSelection(coords));
+                                }
+                                if filling().is_some() {
+                                    filling.set(Some(coords));
+                                }
+                                if let Some(mut drag) = moving() {
+                                    drag.enter(coords);
+                                    moving.set(Some(drag));
+                                }
                             },
                             ondoubleclick: move |_| {
-                                let previous_value = grid.write().get_current_cell_content().clone();
-                                grid.write().previous_content = previous_value;
-                                grid.write().is_editing_cell = true;
+                                grid.write().apply(GridCommand::StartEditing { initial_content: None });
                             },
-                            if !is_editing {
-                                if display_value.is_empty() || display_value.len() as i32 <= char_space {
-                                    "{display_value}"
-                                } else if sci_noatation.len() as i32 <= char_space {
-                                    "{sci_noatation}"
+                            oncontextmenu: move |evt| {
+                                evt.prevent_default();
+                                cell_menu.set(Some((coords, grid_point(&grid.read(), evt.client_coordinates()))));
+                            },
+                            if let (false, Some((icons, level))) = (is_editing, icon) {
+                                ConditionalIcon { icons, level }
+                            }
+                            if !is_editing && !hides_value {
+                                if let Some(width) = overflow_width {
+                                    span {
+                                        class: "cell-overflow-text",
+                                        style: "max-width: {width}px;",
+                                        "{display_value}"
+                                    }
+                                } else if is_text || fits(&display_value) {
+                                    // Text that can't run on is cut off, never replaced
+                                    span { class: "cell-text", style: "{text_style}", "{display_value}" }
+                                } else if fits(&sci_noatation) {
+                                    span { class: "cell-text", style: "{text_style}", "{sci_noatation}" }
                                 } else {
                                     "###"
                                 }
                             }
+                            if let Some(active) = filter_button {
+                                FilterButton { grid, filter_menu, column: coords.column, active }
+                            }
+                            if has_list {
+                                ValidationButton { grid, list_menu, coords }
+                            }
+                            if has_comments {
+                                div { class: "comment-indicator" }
+                            }
                         }
                         if is_editing {
                             InputCell { grid, scroll_container, coords, row, col }
@@ -300,46 +1023,73 @@ fn InputCell(
     row: usize,
     col: usize,
 ) -> Element {
+    let mut autocomplete = use_autocomplete("input-cell");
+    let suggestions = autocomplete.suggestions(&grid.read().get_current_cell_content());
+    let frozen_style = frozen_cell_style(&grid.read(), coords);
+    let format_style = grid
+        .read()
+        .get_current_cell()
+        .map(|c| c.format.to_style())
+        .unwrap_or_default();
+
     rsx! {
         input {
+            id: "input-cell",
             class: "input-cell",
-            style: "grid-row: {row + 2}; grid-column: {col + 2};",
+            style: "grid-row: {row + 2}; grid-column: {col + 2};{frozen_style}{format_style}",
             value: "{grid.read().get_current_cell_content()}",
             onmounted: move |elem| async move {
                 let _ = elem.data().set_focus(true).await;
+                move_caret_to_end("input-cell");
             },
             oninput: move |evt| {
-                let mut grid_write = grid.write();
-                let cell = grid_write.cells_map.entry(coords).or_insert(Cell::new());
-                cell.content = evt.value();
+                grid.write().apply(GridCommand::EditContent { coords, content: evt.value() });
+                autocomplete.on_input(&evt.value());
+            },
+            onclick: move |_| {
+                autocomplete.track_caret(&grid.read().get_current_cell_content());
+            },
+            onkeyup: move |_| {
+                autocomplete.track_caret(&grid.read().get_current_cell_content());
             },
             onblur: move |_| {
-                if grid.write().is_editing_cell {
-                    update_cell_display(grid, coords);
+                if grid.read().view.is_editing_cell {
+                    grid.write().apply(GridCommand::CommitEdit(coords));
                 }
-                grid.write().is_editing_cell = false;
             },
             onkeydown: move |evt| {
                 evt.stop_propagation();
+                let content = grid.read().get_current_cell_content();
+                match autocomplete.handle_key(&evt.key(), &content) {
+                    AutocompleteKey::Completed(completed) => {
+                        evt.prevent_default();
+                        grid.write().apply(GridCommand::EditContent { coords, content: completed });
+                        return;
+                    }
+                    AutocompleteKey::Handled => {
+                        evt.prevent_default();
+                        return;
+                    }
+                    AutocompleteKey::Ignored => {}
+                }
                 match evt.key() {
+                    Key::F4 => {
+                        evt.prevent_default();
+                        if let Some(cycled) = cycle_reference_at_caret("input-cell", &content) {
+                            grid.write().apply(GridCommand::EditContent { coords, content: cycled });
+                        }
+                    }
                     Key::Enter | Key::Tab => {
                         evt.prevent_default();
-                        grid.write().is_editing_cell = false;
-                        update_cell_display(grid, coords);
+                        grid.write().apply(GridCommand::CommitEdit(coords));
 
-                        if evt.key() == Key::Enter {
-                            if evt.modifiers().shift() {
-                                grid.write().current_cell_up_one();
-                            }
-                            else {
-                                grid.write().current_cell_down_one();
-                            }
-                        } else if evt.modifiers().shift() {
-                            grid.write().current_cell_left_one();
-                        }
-                        else {
-                            grid.write().current_cell_right_one();
-                        }
+                        let direction = match (evt.key() == Key::Enter, evt.modifiers().shift()) {
+                            (true, true) => MoveDirection::Up,
+                            (true, false) => MoveDirection::Down,
+                            (false, true) => MoveDirection::Left,
+                            (false, false) => MoveDirection::Right,
+                        };
+                        grid.write().apply(GridCommand::MoveSelection(direction));
 
                         if let Some(container) = scroll_container() {
                             spawn_forever(async move {
@@ -349,10 +1099,7 @@ fn InputCell(
                     }
                     Key::Escape => {
                         evt.prevent_default();
-                        let previous_content = grid.write().previous_content.clone();
-                        grid.write().cells_map.entry(coords).or_insert(Cell::new()).content = previous_content;
-                        grid.write().is_editing_cell = false;
-                        update_cell_display(grid, coords);
+                        grid.write().apply(GridCommand::CancelEdit(coords));
 
                         if let Some(container) = scroll_container() {
                             spawn_forever(async move {
@@ -364,5 +1111,195 @@ fn InputCell(
                 }
             }
         }
+        div {
+            class: "autocomplete-anchor",
+            style: "grid-row: {row + 2}; grid-column: {col + 2};",
+            AutocompleteDropdown {
+                autocomplete,
+                suggestions,
+                on_pick: move |name: &'static str| {
+                    let content = grid.read().get_current_cell_content();
+                    if let Some(completed) = autocomplete.complete(&content, name) {
+                        grid.write().apply(GridCommand::EditContent { coords, content: completed });
+                    }
+                },
+            }
+        }
+    }
+}
+
+fn scroll_container_element() -> Option<web_sys::Element> {
+    web_sys::window()?
+        .document()?
+        .get_element_by_id("scroll-container")
+}
+
+/// Where a mouse event happened in the grid's own pixels, which the zoom level scales on screen
+pub fn grid_point(grid: &Grid, point: ClientPoint) -> ClientPoint {
+    point / grid.view.zoom_factor()
+}
+
+/// Scroll container pixels in the grid's own, unzoomed pixels
+fn unzoomed(grid: &Grid, pixels: i32) -> i32 {
+    (pixels as f64 / grid.view.zoom_factor()).round() as i32
+}
+
+/// Grid pixels as the scroll container shows them at the zoom level
+fn zoomed(grid: &Grid, pixels: i32) -> i32 {
+    (pixels as f64 * grid.view.zoom_factor()).round() as i32
+}
+
+/// Which part of the grid the scroll container shows
+fn read_viewport(grid: &Grid) -> Option<Viewport> {
+    let container = scroll_container_element()?;
+    Some(Viewport {
+        left: unzoomed(grid, container.scroll_left()),
+        top: unzoomed(grid, container.scroll_top()),
+        width: unzoomed(grid, container.client_width()),
+        height: unzoomed(grid, container.client_height()),
+    })
+}
+
+/// Width and height of the headers and frozen panes staying over the scrolled area's corner
+fn sticky_size(grid: &Grid) -> (i32, i32) {
+    let frozen = grid.view.frozen_panes().unwrap_or(Coords { row: 0, column: 0 });
+    let rect = grid.get_cell_rect(frozen);
+    (rect.x, rect.y)
+}
+
+/// Height in pixels of the rows scrolling past under the headers and frozen rows
+fn viewport_height(grid: &Grid) -> Option<i32> {
+    let container = scroll_container_element()?;
+    Some(unzoomed(grid, container.client_height()) - sticky_size(grid).1)
+}
+
+fn scroll_by(grid: &Grid, left: i32, top: i32) {
+    if let Some(container) = scroll_container_element() {
+        container.set_scroll_left(container.scroll_left() + zoomed(grid, left));
+        container.set_scroll_top(container.scroll_top() + zoomed(grid, top));
+    }
+}
+
+/// Scrolls the grid just enough to show a cell clear of the headers and frozen panes
+fn scroll_into_view(grid: &Grid, coords: Coords) {
+    let Some(container) = scroll_container_element() else {
+        return;
+    };
+    let Some(shown) = read_viewport(grid) else {
+        return;
+    };
+    let rect = grid.get_cell_rect(coords);
+    let (sticky_width, sticky_height) = sticky_size(grid);
+    let frozen = grid.view.frozen_panes().unwrap_or(Coords { row: 0, column: 0 });
+    // Frozen cells are always in view
+    if coords.column >= frozen.column {
+        if rect.x - sticky_width < shown.left {
+            container.set_scroll_left(zoomed(grid, rect.x - sticky_width));
+        } else if rect.x + rect.width > shown.left + shown.width {
+            container.set_scroll_left(zoomed(grid, rect.x + rect.width - shown.width));
+        }
     }
+    if coords.row >= frozen.row {
+        if rect.y - sticky_height < shown.top {
+            container.set_scroll_top(zoomed(grid, rect.y - sticky_height));
+        } else if rect.y + rect.height > shown.top + shown.height {
+            container.set_scroll_top(zoomed(grid, rect.y + rect.height - shown.height));
+        }
+    }
+}
+
+/// Width the text of the cell at `coords` can take running on to its right, up to the first
+/// cell with a value or as far as `needed`
+fn overflow_width(sheet: &Sheet, coords: Coords, needed: f64) -> i32 {
+    let column = coords.column as usize;
+    // 5 - border + padding size on each side
+    let mut width = sheet.column_widths[column] - 5 * 2;
+    for next in column + 1..sheet.column_widths.len() {
+        let next_coords = Coords {
+            row: coords.row,
+            column: next as i32,
+        };
+        let is_empty = sheet
+            .cells_map
+            .get(&next_coords)
+            .is_none_or(|cell| cell.display_value.is_empty());
+        if width as f64 >= needed || !is_empty {
+            break;
+        }
+        width += sheet.displayed_column_width(next);
+    }
+    width
+}
+
+/// Height that shows the turned text of the row's cells in full, or None if none is taller
+/// than the row already is
+pub fn fit_row_height(grid: &Grid, row: usize) -> Option<i32> {
+    let sheet = grid.sheet();
+    let tallest = sheet
+        .cells_map
+        .iter()
+        .filter(|(coords, cell)| {
+            coords.row as usize == row
+                && !cell.display_value.is_empty()
+                && (cell.format.rotation != 0 || cell.format.vertical_text)
+        })
+        .filter_map(|(_, cell)| {
+            let shown = cell.format.number_format.apply(&cell.display_value);
+            let size = cell.format.font_size.unwrap_or(DEFAULT_FONT_SIZE) as f64;
+            if cell.format.vertical_text {
+                // A line height per letter
+                return Some(shown.chars().count() as f64 * size * 1.2);
+            }
+            let width = measure_text_width(&shown, &cell.format.font())?;
+            let angle = (cell.format.rotation as f64).to_radians();
+            Some(width * angle.sin().abs() + size * angle.cos().abs())
+        })
+        .reduce(f64::max)?;
+    // 5 - border + padding size on each side
+    let height = tallest.ceil() as i32 + 5 * 2;
+    (height > *sheet.row_heights.get(row)?).then_some(height)
+}
+
+/// Width that shows every value of the column in full, or None if it's empty
+fn fit_column_width(grid: &Grid, column: usize) -> Option<i32> {
+    let widest = grid
+        .sheet()
+        .cells_map
+        .iter()
+        .filter(|(coords, cell)| {
+            coords.column as usize == column && !cell.display_value.is_empty()
+        })
+        .filter_map(|(_, cell)| {
+            let shown = cell.format.number_format.apply(&cell.display_value);
+            measure_text_width(&shown, &cell.format.font())
+        })
+        .reduce(f64::max)?;
+    // 5 - border + padding size on each side, plus a pixel so the text isn't clipped
+    Some(widest.ceil() as i32 + 5 * 2 + 1)
+}
+
+thread_local! {
+    static MEASURE_CONTEXT: Option<web_sys::CanvasRenderingContext2d> = measure_context();
+}
+
+/// Rendered width of `text` in a CSS `font`, measured on an offscreen canvas
+fn measure_text_width(text: &str, font: &str) -> Option<f64> {
+    MEASURE_CONTEXT.with(|context| {
+        let context = context.as_ref()?;
+        context.set_font(font);
+        context.measure_text(text).ok().map(|m| m.width())
+    })
+}
+
+fn measure_context() -> Option<web_sys::CanvasRenderingContext2d> {
+    web_sys::window()?
+        .document()?
+        .create_element("canvas")
+        .ok()?
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .ok()?
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .ok()
 }
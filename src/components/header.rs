@@ -1,68 +1,106 @@
-use std::rc::Rc;
-use wasm_bindgen::JsCast;
+use std::{collections::BTreeSet, rc::Rc};
 
 use dioxus::{core::spawn_forever, prelude::*};
 
 use crate::{
-    components::grid::update_cell_display,
-    model::grid::{cell_address_to_coords, Cell, Coords, Grid},
+    components::{
+        autocomplete::{
+            cycle_reference_at_caret, use_autocomplete, AutocompleteDropdown, AutocompleteKey,
+        },
+        clipboard::{write_system_clipboard, write_system_clipboard_text},
+        dialog::Dialog,
+        files::{
+            new_workbook, open_uploaded_file, pick_file, save_export, save_workbook,
+            ACCEPTED_FILES, FILE_INPUT_ID,
+        },
+        grid::fit_row_height,
+        ods::export_ods,
+        offline::OfflineIndicator,
+        read_only::ReadOnlyToggle,
+        recent_files::RecentFilesMenu,
+        side_panel::Panel,
+    },
+    engine::{cleaning::CLEANINGS, csv, date::DateFormat},
+    model::{
+        chart::ChartKind,
+        clipboard,
+        command::GridCommand,
+        export::ExportContent,
+        format::{
+            normalize_hex_color, ColorTarget, FontStyle, HorizontalAlign, NumberFormat,
+            VerticalAlign, DEFAULT_FONT_SIZE, FONT_FAMILIES, FONT_SIZES, PALETTE,
+        },
+        grid::Grid,
+        outline::OutlineDirection,
+    },
 };
 
 static HEADER_CSS: Asset = asset!("/assets/header.css");
 
 #[component]
-pub fn Header(grid: Signal<Grid>, scroll_container: Signal<Option<Rc<MountedData>>>) -> Element {
+pub fn Header(
+    grid: Signal<Grid>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    side_panel: Signal<Option<Panel>>,
+    dialog: Signal<Option<Dialog>>,
+) -> Element {
     rsx! {
         document::Stylesheet { href: HEADER_CSS }
         div {
             class: "header",
 
-            FileToolbar { grid },
-            FormattingToolbar {},
+            FileToolbar { grid, dialog },
+            if !grid.read().view.read_only {
+                FormattingToolbar { grid, side_panel, dialog }
+            }
             FormulaBar { grid, scroll_container }
         }
     }
 }
 
 #[component]
-fn FileToolbar(grid: Signal<Grid>) -> Element {
+fn FileToolbar(grid: Signal<Grid>, mut dialog: Signal<Option<Dialog>>) -> Element {
     rsx! {
         div {
             class: "file-toolbar",
 
             button {
-                "tooltip-text": "Export to CSV",
+                "tooltip-text": "New workbook",
+                onclick: move |_| new_workbook(grid, dialog),
+                lucide_dioxus::FilePlus { size: 22 }
+            }
+            button {
+                "tooltip-text": "Save workbook (Ctrl+S)",
                 onclick: move |_| {
-                    let csv = export_to_csv(grid);
-
-                    let array = js_sys::Array::new();
-                    array.push(&wasm_bindgen::JsValue::from_str(&csv));
-                    let blob = web_sys::Blob::new_with_str_sequence(&array).unwrap();
-                    let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
-
-                    let document = web_sys::window().unwrap().document().unwrap();
-                    let anchor: web_sys::HtmlAnchorElement = document.create_element("a").unwrap().dyn_into().unwrap();
-
-                    anchor.set_href(&url);
-                    anchor.set_download("export.csv");
-                    anchor.click();
-
-                    web_sys::Url::revoke_object_url(&url).unwrap();
+                    spawn(save_workbook(grid, false));
                 },
-                lucide_dioxus::Save { size: 22 }
+                lucide_dioxus::FileDown { size: 22 }
+            }
+            button {
+                "tooltip-text": "Save workbook as (Ctrl+Shift+S)",
+                onclick: move |_| {
+                    spawn(save_workbook(grid, true));
+                },
+                lucide_dioxus::FileOutput { size: 22 }
+            }
+            ExportMenu { grid }
+            button {
+                "tooltip-text": "Print or save as PDF (Ctrl+P)",
+                onclick: move |_| dialog.set(Some(Dialog::Print)),
+                lucide_dioxus::Printer { size: 22 }
             }
 
             input {
                 r#type: "file",
-                accept: ".csv",
-                id: "csv-import",
+                accept: ACCEPTED_FILES,
+                id: FILE_INPUT_ID,
                 style: "display: none;",
                 onchange: move |evt| {
                     spawn(async move {
                         if let Some(file) = evt.files().first() {
-                            match file.read_string().await {
-                                Ok(csv_text) => {
-                                    import_csv(grid, &csv_text);
+                            match file.read_bytes().await {
+                                Ok(bytes) => {
+                                    open_uploaded_file(grid, dialog, file.name(), bytes.to_vec()).await
                                 }
                                 Err(e) => {
                                     error!("{e:?}");
@@ -73,27 +111,593 @@ fn FileToolbar(grid: Signal<Grid>) -> Element {
                 }
             }
             button {
-                "tooltip-text": "Import CSV",
+                "tooltip-text": "Open a workbook, or import CSV, TSV, ODS or JSON",
                 onclick: move |_| {
-                    if let Some(document) = web_sys::window().and_then(|w| w.document()) {
-                        if let Some(element) = document.get_element_by_id("csv-import") {
-                            if let Some(input) = element.dyn_ref::<web_sys::HtmlInputElement>() {
-                                input.click();
-                            }
-                        }
-                    }
+                    spawn(pick_file(grid, dialog));
                 },
                 lucide_dioxus::FolderOpen { size: 22 }
             }
+            button {
+                "tooltip-text": "Import from Google Sheets",
+                onclick: move |_| dialog.set(Some(Dialog::GoogleSheets)),
+                lucide_dioxus::Sheet { size: 22 }
+            }
+            button {
+                "tooltip-text": "Import JSON from a URL",
+                onclick: move |_| dialog.set(Some(Dialog::ImportJson(None))),
+                lucide_dioxus::Braces { size: 22 }
+            }
+            button {
+                "tooltip-text": "Open or save workbooks on a server",
+                onclick: move |_| dialog.set(Some(Dialog::ServerFiles)),
+                lucide_dioxus::Cloud { size: 22 }
+            }
+            button {
+                "tooltip-text": "Document properties",
+                onclick: move |_| dialog.set(Some(Dialog::Properties)),
+                lucide_dioxus::FileText { size: 22 }
+            }
+            RecentFilesMenu { grid, dialog }
+            ReadOnlyToggle { grid }
+            OfflineIndicator {}
         }
     }
 }
 
 #[component]
-fn FormattingToolbar() -> Element {
+fn FormattingToolbar(
+    grid: Signal<Grid>,
+    side_panel: Signal<Option<Panel>>,
+    mut dialog: Signal<Option<Dialog>>,
+) -> Element {
+    let format = grid
+        .read()
+        .get_current_cell()
+        .map(|c| c.format.clone())
+        .unwrap_or_default();
+    let font_styles = [
+        (FontStyle::Bold, "Bold (Ctrl+B)", rsx! { lucide_dioxus::Bold { size: 18 } }),
+        (FontStyle::Italic, "Italic (Ctrl+I)", rsx! { lucide_dioxus::Italic { size: 18 } }),
+        (FontStyle::Underline, "Underline (Ctrl+U)", rsx! { lucide_dioxus::Underline { size: 18 } }),
+        (FontStyle::Strikethrough, "Strikethrough", rsx! { lucide_dioxus::Strikethrough { size: 18 } }),
+    ];
+    let horizontal_aligns = [
+        (HorizontalAlign::Left, "Align left", rsx! { lucide_dioxus::AlignHorizontalJustifyStart { size: 18 } }),
+        (HorizontalAlign::Center, "Center", rsx! { lucide_dioxus::AlignHorizontalJustifyCenter { size: 18 } }),
+        (HorizontalAlign::Right, "Align right", rsx! { lucide_dioxus::AlignHorizontalJustifyEnd { size: 18 } }),
+    ];
+    let horizontal_align = format.horizontal_align;
+    let vertical_aligns = [
+        (VerticalAlign::Top, "Align top", rsx! { lucide_dioxus::AlignVerticalJustifyStart { size: 18 } }),
+        (VerticalAlign::Middle, "Align middle", rsx! { lucide_dioxus::AlignVerticalJustifyCenter { size: 18 } }),
+        (VerticalAlign::Bottom, "Align bottom", rsx! { lucide_dioxus::AlignVerticalJustifyEnd { size: 18 } }),
+    ];
+
     rsx! {
         div {
             class: "formatting-toolbar",
+
+            button {
+                "tooltip-text": "Undo (Ctrl+Z)",
+                disabled: !grid.read().view.undo_history.can_undo(),
+                onclick: move |_| grid.write().apply(GridCommand::Undo),
+                lucide_dioxus::Undo2 { size: 18 }
+            }
+            button {
+                "tooltip-text": "Redo (Ctrl+Y)",
+                disabled: !grid.read().view.undo_history.can_redo(),
+                onclick: move |_| grid.write().apply(GridCommand::Redo),
+                lucide_dioxus::Redo2 { size: 18 }
+            }
+            div { class: "toolbar-separator" }
+
+            select {
+                class: "number-format-select",
+                "tooltip-text": "Font",
+                value: format.font_family.clone().unwrap_or_default(),
+                onchange: move |evt| {
+                    let family = Some(evt.value()).filter(|family| !family.is_empty());
+                    grid.write().apply(GridCommand::SetFontFamily(family));
+                },
+                option { value: "", "Default font" }
+                for family in FONT_FAMILIES {
+                    option { value: family, style: "font-family: \"{family}\";", "{family}" }
+                }
+            }
+            select {
+                class: "number-format-select",
+                "tooltip-text": "Font size",
+                value: format.font_size.unwrap_or(DEFAULT_FONT_SIZE).to_string(),
+                onchange: move |evt| {
+                    let size = evt.value().parse::<u32>().ok().filter(|size| *size != DEFAULT_FONT_SIZE);
+                    grid.write().apply(GridCommand::SetFontSize(size));
+                },
+                for size in FONT_SIZES {
+                    option { value: size.to_string(), "{size}" }
+                }
+            }
+            div { class: "toolbar-separator" }
+            for (style, tooltip, icon) in font_styles {
+                button {
+                    class: if format.has(style) { "toolbar-toggle-on" },
+                    "tooltip-text": tooltip,
+                    onclick: move |_| grid.write().apply(GridCommand::ToggleFontStyle(style)),
+                    {icon}
+                }
+            }
+            ColorPicker { grid, target: ColorTarget::Text }
+            ColorPicker { grid, target: ColorTarget::Background }
+            div { class: "toolbar-separator" }
+            for (align, tooltip, icon) in horizontal_aligns {
+                button {
+                    class: if horizontal_align == Some(align) { "toolbar-toggle-on" },
+                    "tooltip-text": tooltip,
+                    // Clicking the alignment in use goes back to aligning by value
+                    onclick: move |_| {
+                        let align = (horizontal_align != Some(align)).then_some(align);
+                        grid.write().apply(GridCommand::SetHorizontalAlign(align));
+                    },
+                    {icon}
+                }
+            }
+            for (align, tooltip, icon) in vertical_aligns {
+                button {
+                    class: if format.vertical_align == align { "toolbar-toggle-on" },
+                    "tooltip-text": tooltip,
+                    onclick: move |_| grid.write().apply(GridCommand::SetVerticalAlign(align)),
+                    {icon}
+                }
+            }
+            select {
+                class: "number-format-select",
+                "tooltip-text": "Text orientation",
+                value: match (format.vertical_text, format.rotation) {
+                    (true, _) => "vertical".to_string(),
+                    (false, rotation) => rotation.to_string(),
+                },
+                onchange: move |evt| {
+                    let (rotation, vertical) = match evt.value().as_str() {
+                        "vertical" => (0, true),
+                        value => (value.parse::<i16>().unwrap_or_default(), false),
+                    };
+                    grid.write().apply(GridCommand::SetTextOrientation { rotation, vertical });
+                    // Rows grow to fit the turned text, like headers angled over narrow columns
+                    let rows = grid
+                        .read()
+                        .view
+                        .selection()
+                        .cells()
+                        .into_iter()
+                        .map(|coords| coords.row as usize)
+                        .collect::<BTreeSet<_>>();
+                    for row in rows {
+                        let height = fit_row_height(&grid.read(), row);
+                        if let Some(height) = height {
+                            grid.write().apply(GridCommand::ResizeRow { row, height });
+                        }
+                    }
+                },
+                option { value: "0", "Horizontal" }
+                option { value: "45", "Angle up" }
+                option { value: "-45", "Angle down" }
+                option { value: "90", "Rotate up" }
+                option { value: "-90", "Rotate down" }
+                option { value: "vertical", "Vertical text" }
+            }
+            div { class: "toolbar-separator" }
+            select {
+                class: "number-format-select",
+                "tooltip-text": "Number format",
+                value: match format.number_format {
+                    NumberFormat::General => "general",
+                    NumberFormat::Number(_) => "number",
+                    NumberFormat::Currency(_) => "currency",
+                    NumberFormat::Percent(_) => "percent",
+                    NumberFormat::Date(DateFormat::Iso) => "date-iso",
+                    NumberFormat::Date(DateFormat::Short) => "date-short",
+                    NumberFormat::Date(DateFormat::Long) => "date-long",
+                    NumberFormat::Date(DateFormat::Time) => "time",
+                    NumberFormat::Date(DateFormat::Duration) => "duration",
+                    NumberFormat::Custom(_) => "custom",
+                },
+                onchange: move |evt| {
+                    let number_format = match evt.value().as_str() {
+                        "number" => NumberFormat::Number(2),
+                        "currency" => NumberFormat::Currency(2),
+                        "percent" => NumberFormat::Percent(0),
+                        "date-iso" => NumberFormat::Date(DateFormat::Iso),
+                        "date-short" => NumberFormat::Date(DateFormat::Short),
+                        "date-long" => NumberFormat::Date(DateFormat::Long),
+                        "time" => NumberFormat::Date(DateFormat::Time),
+                        "duration" => NumberFormat::Date(DateFormat::Duration),
+                        "custom" => {
+                            dialog.set(Some(Dialog::CustomFormat));
+                            return;
+                        }
+                        _ => NumberFormat::General,
+                    };
+                    grid.write().apply(GridCommand::SetNumberFormat(number_format));
+                },
+                option { value: "general", "General" }
+                option { value: "number", "Number" }
+                option { value: "currency", "Currency" }
+                option { value: "percent", "Percent" }
+                option { value: "date-iso", "Date (ISO)" }
+                option { value: "date-short", "Short date" }
+                option { value: "date-long", "Long date" }
+                option { value: "time", "Time" }
+                option { value: "duration", "Duration" }
+                option { value: "custom", "Custom…" }
+            }
+            button {
+                class: "decimals-button",
+                "tooltip-text": "Increase decimals",
+                onclick: move |_| grid.write().apply(GridCommand::ChangeDecimals(1)),
+                ".0+"
+            }
+            button {
+                class: "decimals-button",
+                "tooltip-text": "Decrease decimals",
+                onclick: move |_| grid.write().apply(GridCommand::ChangeDecimals(-1)),
+                ".0−"
+            }
+            div { class: "toolbar-separator" }
+
+            button {
+                "tooltip-text": "Trace precedents",
+                onclick: move |_| grid.write().apply(GridCommand::TracePrecedents),
+                lucide_dioxus::ArrowDownToDot { size: 18 }
+            }
+            button {
+                "tooltip-text": "Trace dependents",
+                onclick: move |_| grid.write().apply(GridCommand::TraceDependants),
+                lucide_dioxus::ArrowUpFromDot { size: 18 }
+            }
+            button {
+                "tooltip-text": "Remove arrows",
+                onclick: move |_| grid.write().apply(GridCommand::ClearTraceArrows),
+                lucide_dioxus::Eraser { size: 18 }
+            }
+            if grid.read().view.frozen_panes().is_some() {
+                button {
+                    "tooltip-text": "Unfreeze panes",
+                    onclick: move |_| grid.write().apply(GridCommand::UnfreezePanes),
+                    lucide_dioxus::Snowflake { size: 18 }
+                }
+            } else {
+                button {
+                    "tooltip-text": "Freeze panes",
+                    onclick: move |_| grid.write().apply(GridCommand::FreezePanes),
+                    lucide_dioxus::Snowflake { size: 18 }
+                }
+            }
+            if format.unlocked {
+                button {
+                    "tooltip-text": "Lock cells",
+                    onclick: move |_| grid.write().apply(GridCommand::SetCellsLocked(true)),
+                    lucide_dioxus::LockOpen { size: 18 }
+                }
+            } else {
+                button {
+                    "tooltip-text": "Unlock cells",
+                    onclick: move |_| grid.write().apply(GridCommand::SetCellsLocked(false)),
+                    lucide_dioxus::Lock { size: 18 }
+                }
+            }
+            if grid.read().sheet().protected {
+                button {
+                    class: "toolbar-toggle-on",
+                    "tooltip-text": "Unprotect sheet",
+                    onclick: move |_| grid.write().apply(GridCommand::SetSheetProtection(false)),
+                    lucide_dioxus::ShieldCheck { size: 18 }
+                }
+            } else {
+                button {
+                    "tooltip-text": "Protect sheet",
+                    onclick: move |_| grid.write().apply(GridCommand::SetSheetProtection(true)),
+                    lucide_dioxus::Shield { size: 18 }
+                }
+            }
+            button {
+                "tooltip-text": "Protected ranges",
+                onclick: move |_| dialog.set(Some(Dialog::ProtectedRanges)),
+                lucide_dioxus::ShieldPlus { size: 18 }
+            }
+            button {
+                "tooltip-text": "Dependencies",
+                onclick: move |_| toggle_panel(side_panel, Panel::Dependencies),
+                lucide_dioxus::Network { size: 18 }
+            }
+            button {
+                "tooltip-text": "Watch window",
+                onclick: move |_| toggle_panel(side_panel, Panel::Watch),
+                lucide_dioxus::Eye { size: 18 }
+            }
+            button {
+                "tooltip-text": "Recalculation profiler",
+                onclick: move |_| toggle_panel(side_panel, Panel::Profiler),
+                lucide_dioxus::Gauge { size: 18 }
+            }
+            button {
+                "tooltip-text": "Data sources",
+                onclick: move |_| toggle_panel(side_panel, Panel::DataSources),
+                lucide_dioxus::DatabaseZap { size: 18 }
+            }
+            button {
+                "tooltip-text": "Comments",
+                onclick: move |_| toggle_panel(side_panel, Panel::Comments),
+                lucide_dioxus::MessageSquare { size: 18 }
+            }
+            button {
+                "tooltip-text": "Versions",
+                onclick: move |_| toggle_panel(side_panel, Panel::Versions),
+                lucide_dioxus::History { size: 18 }
+            }
+            button {
+                "tooltip-text": "Paste special",
+                onclick: move |_| dialog.set(Some(Dialog::PasteSpecial)),
+                lucide_dioxus::ClipboardPaste { size: 18 }
+            }
+            button {
+                "tooltip-text": "Sort ascending",
+                onclick: move |_| {
+                    let column = grid.read().current_cell().column;
+                    grid.write().apply(GridCommand::SortRange { column, descending: false });
+                },
+                lucide_dioxus::ArrowUpNarrowWide { size: 18 }
+            }
+            button {
+                "tooltip-text": "Sort descending",
+                onclick: move |_| {
+                    let column = grid.read().current_cell().column;
+                    grid.write().apply(GridCommand::SortRange { column, descending: true });
+                },
+                lucide_dioxus::ArrowDownWideNarrow { size: 18 }
+            }
+            button {
+                "tooltip-text": if grid.read().sheet().auto_filter.is_some() { "Remove filter" } else { "Filter" },
+                onclick: move |_| grid.write().apply(GridCommand::ToggleAutoFilter),
+                lucide_dioxus::ListFilter { size: 18 }
+            }
+            CleanDataMenu { grid }
+            OutlineMenu { grid, dialog }
+            button {
+                "tooltip-text": "Format as table",
+                onclick: move |_| dialog.set(Some(Dialog::Table)),
+                lucide_dioxus::Table { size: 18 }
+            }
+            button {
+                "tooltip-text": "Data validation",
+                onclick: move |_| dialog.set(Some(Dialog::DataValidation)),
+                lucide_dioxus::ListChecks { size: 18 }
+            }
+            button {
+                "tooltip-text": "Conditional formatting",
+                onclick: move |_| dialog.set(Some(Dialog::ConditionalFormat)),
+                lucide_dioxus::Palette { size: 18 }
+            }
+            button {
+                "tooltip-text": "Flash fill",
+                onclick: move |_| dialog.set(Some(Dialog::FlashFill)),
+                lucide_dioxus::Sparkles { size: 18 }
+            }
+            button {
+                "tooltip-text": "Insert chart",
+                onclick: move |_| {
+                    let count = grid.read().sheet().charts.len();
+                    grid.write().apply(GridCommand::CreateChart(ChartKind::Bar));
+                    // The new chart is the last one, if there was anything to chart
+                    let created = grid.read().sheet().charts.last().map(|chart| chart.id);
+                    if let Some(id) = created.filter(|_| grid.read().sheet().charts.len() > count) {
+                        dialog.set(Some(Dialog::Chart(id)));
+                    }
+                },
+                lucide_dioxus::ChartLine { size: 18 }
+            }
+            button {
+                "tooltip-text": "Descriptive statistics",
+                onclick: move |_| dialog.set(Some(Dialog::DescriptiveStatistics)),
+                lucide_dioxus::Sigma { size: 18 }
+            }
+            button {
+                "tooltip-text": "Histogram",
+                onclick: move |_| dialog.set(Some(Dialog::Histogram)),
+                lucide_dioxus::ChartColumn { size: 18 }
+            }
+            button {
+                "tooltip-text": "Solver",
+                onclick: move |_| dialog.set(Some(Dialog::Solver)),
+                lucide_dioxus::Target { size: 18 }
+            }
+            button {
+                "tooltip-text": "Evaluate formula",
+                onclick: move |_| dialog.set(Some(Dialog::EvaluateFormula)),
+                lucide_dioxus::Calculator { size: 18 }
+            }
+        }
+    }
+}
+
+/// Toolbar button dropping down the cleanups that can be run over the selection
+#[component]
+fn CleanDataMenu(grid: Signal<Grid>) -> Element {
+    let mut open = use_signal(|| false);
+
+    rsx! {
+        div {
+            class: "clean-data",
+
+            button {
+                "tooltip-text": "Clean data",
+                onclick: move |_| open.toggle(),
+                lucide_dioxus::WandSparkles { size: 18 }
+            }
+            if open() {
+                div {
+                    class: "clean-data-dropdown",
+
+                    for (cleaning, label) in CLEANINGS {
+                        button {
+                            onclick: move |_| {
+                                grid.write().apply(GridCommand::CleanData(cleaning));
+                                open.set(false);
+                            },
+                            "{label}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Toolbar button dropping down grouping of the selected rows or columns, the subtotals
+/// tool, and buttons showing each level of the outlines there are
+#[component]
+fn OutlineMenu(grid: Signal<Grid>, dialog: Signal<Option<Dialog>>) -> Element {
+    let mut open = use_signal(|| false);
+    let mut apply = move |command: GridCommand| {
+        grid.write().apply(command);
+        open.set(false);
+    };
+    let levels = [OutlineDirection::Rows, OutlineDirection::Columns].map(|direction| {
+        (direction, grid.read().sheet().outline(direction).max_level())
+    });
+
+    rsx! {
+        div {
+            class: "clean-data",
+
+            button {
+                "tooltip-text": "Group and outline",
+                onclick: move |_| open.toggle(),
+                lucide_dioxus::ListTree { size: 18 }
+            }
+            if open() {
+                div {
+                    class: "clean-data-dropdown",
+
+                    button {
+                        onclick: move |_| apply(GridCommand::Group(OutlineDirection::Rows)),
+                        "Group rows"
+                    }
+                    button {
+                        onclick: move |_| apply(GridCommand::Group(OutlineDirection::Columns)),
+                        "Group columns"
+                    }
+                    button {
+                        onclick: move |_| apply(GridCommand::Ungroup(OutlineDirection::Rows)),
+                        "Ungroup rows"
+                    }
+                    button {
+                        onclick: move |_| apply(GridCommand::Ungroup(OutlineDirection::Columns)),
+                        "Ungroup columns"
+                    }
+                    button {
+                        onclick: move |_| {
+                            open.set(false);
+                            dialog.set(Some(Dialog::Subtotals));
+                        },
+                        "Subtotals…"
+                    }
+                    for (direction, max_level) in levels {
+                        if max_level > 0 {
+                            div {
+                                class: "outline-levels",
+                                span {
+                                    if direction == OutlineDirection::Rows { "Row levels" } else { "Column levels" }
+                                }
+                                // One past the deepest level shows everything
+                                for level in 1..=max_level + 1 {
+                                    button {
+                                        onclick: move |_| apply(GridCommand::ShowOutlineLevel { direction, level }),
+                                        "{level}"
+                                    }
+                                }
+                            }
+                            button {
+                                onclick: move |_| apply(GridCommand::ClearOutline(direction)),
+                                if direction == OutlineDirection::Rows { "Clear row outline" } else { "Clear column outline" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Toolbar button dropping down a palette and a hex input, coloring the selection's text or
+/// background
+#[component]
+fn ColorPicker(grid: Signal<Grid>, target: ColorTarget) -> Element {
+    let mut open = use_signal(|| false);
+    let mut custom = use_signal(String::new);
+    let current = grid
+        .read()
+        .get_current_cell()
+        .and_then(|c| c.format.color(target).map(str::to_string))
+        .unwrap_or_else(|| "transparent".to_string());
+    let tooltip = match target {
+        ColorTarget::Text => "Text color",
+        ColorTarget::Background => "Fill color",
+    };
+    let mut apply = move |color: Option<String>| {
+        grid.write().apply(GridCommand::SetColor { target, color });
+        open.set(false);
+    };
+
+    rsx! {
+        div {
+            class: "color-picker",
+
+            button {
+                "tooltip-text": tooltip,
+                onclick: move |_| open.toggle(),
+                if target == ColorTarget::Text {
+                    lucide_dioxus::Baseline { size: 18 }
+                } else {
+                    lucide_dioxus::PaintBucket { size: 18 }
+                }
+                div { class: "color-picker-bar", style: "background-color: {current};" }
+            }
+            if open() {
+                div {
+                    class: "color-picker-dropdown",
+
+                    div {
+                        class: "color-picker-palette",
+                        for color in PALETTE {
+                            button {
+                                class: "color-swatch",
+                                style: "background-color: {color};",
+                                title: color,
+                                onclick: move |_| apply(Some(color.to_string())),
+                            }
+                        }
+                    }
+                    button {
+                        class: "color-picker-automatic",
+                        onclick: move |_| apply(None),
+                        "Automatic"
+                    }
+                    input {
+                        class: "color-picker-hex",
+                        placeholder: "#rrggbb",
+                        value: "{custom}",
+                        oninput: move |evt| custom.set(evt.value()),
+                        onkeydown: move |evt| {
+                            evt.stop_propagation();
+                            if evt.key() == Key::Enter {
+                                let color = normalize_hex_color(&custom.read());
+                                if let Some(color) = color {
+                                    apply(Some(color));
+                                }
+                            }
+                        },
+                    }
+                }
+            }
         }
     }
 }
@@ -123,7 +727,7 @@ fn CellAddressInput(
     rsx! {
         input {
             class: "cell-address-input header-input",
-            value: "{grid.read().get_current_cell_address()}",
+            value: "{grid.read().get_selection_address()}",
             onfocus: move |_| {
                 previous_address.set(grid.read().get_current_cell_address());
             },
@@ -134,10 +738,10 @@ fn CellAddressInput(
                 // evt.stop_propagation();
                 match evt.key() {
                     Key::Enter => {
-                        if let Some(new_coords) = cell_address_to_coords(&value.read()) {
-                            grid.write().current_cell = new_coords;
-                        } else {
-                            grid.write().current_cell = cell_address_to_coords(&previous_address.read()).unwrap();
+                        let target = grid.read().resolve_address(&value.read())
+                            .or_else(|| grid.read().resolve_address(&previous_address.read()));
+                        if let Some(key) = target {
+                            grid.write().apply(GridCommand::GoToCell(key));
                         }
                         if let Some(container) = scroll_container() {
                             spawn_forever(async move {
@@ -146,7 +750,10 @@ fn CellAddressInput(
                         }
                     }
                     Key::Escape => {
-                        grid.write().current_cell = cell_address_to_coords(&previous_address.read()).unwrap();
+                        let previous = grid.read().resolve_address(&previous_address.read());
+                        if let Some(previous) = previous {
+                            grid.write().apply(GridCommand::GoToCell(previous));
+                        }
                         if let Some(container) = scroll_container() {
                             spawn_forever(async move {
                                 let _ = container.set_focus(true).await;
@@ -162,106 +769,258 @@ fn CellAddressInput(
 
 #[component]
 fn FormulaInput(grid: Signal<Grid>, scroll_container: Signal<Option<Rc<MountedData>>>) -> Element {
-    let mut previous_value = use_signal(String::new);
+    let mut autocomplete = use_autocomplete("formula-input");
+    let suggestions = autocomplete.suggestions(&grid.read().get_current_cell_content());
+
+    let diagnostic = grid
+        .read()
+        .get_current_cell()
+        .and_then(|c| c.diagnostic.clone());
+    let diagnostic_message = diagnostic.as_ref().map(|d| d.to_string()).unwrap_or_default();
+    let diagnostic_overlay = {
+        let content = grid.read().get_current_cell_content();
+        diagnostic.map(|diagnostic| {
+            let position = diagnostic.position.min(content.len());
+            let (before, after) = content.split_at(position);
+            let marked = after.chars().next().map(String::from).unwrap_or_else(|| " ".to_string());
+            rsx! {
+                div {
+                    class: "formula-diagnostic",
+                    span { "{before}" }
+                    span { class: "formula-diagnostic-mark", "{marked}" }
+                }
+            }
+        })
+    };
 
     rsx! {
-        input {
-            class: "formula-input header-input",
-            value: "{grid.read().get_current_cell_content()}",
-            onfocus: move |_| {
-                previous_value.set(grid.read().get_current_cell_content());
-            },
-            oninput: move |evt| {
-                let coords = grid.read().current_cell;
-                let mut grid_write = grid.write();
-                let cell = grid_write.cells_map.entry(coords).or_insert(Cell::new());
-                cell.content = evt.value();
-                cell.display_value = evt.value();
-            },
-            onkeydown: move |evt| {
-                // evt.stop_propagation();
-                match evt.key() {
-                    Key::Enter => {
-                        evt.prevent_default();
-                        let coords = grid.read().current_cell;
-                        update_cell_display(grid, coords);
-                        if let Some(container) = scroll_container() {
-                            spawn_forever(async move {
-                                let _ = container.set_focus(true).await;
-                            });
+        div {
+            class: "formula-input-wrapper",
+
+            input {
+                id: "formula-input",
+                class: "formula-input header-input",
+                value: "{grid.read().get_current_cell_content()}",
+                title: "{diagnostic_message}",
+                readonly: grid.read().view.read_only,
+                onfocus: move |_| {
+                    grid.write().apply(GridCommand::SetFormulaBarEditing(true));
+                },
+                onblur: move |_| {
+                    grid.write().apply(GridCommand::SetFormulaBarEditing(false));
+                },
+                oninput: move |evt| {
+                    let coords = grid.read().current_cell();
+                    grid.write().apply(GridCommand::EditContent { coords, content: evt.value() });
+                    autocomplete.on_input(&evt.value());
+                },
+                onclick: move |_| {
+                    autocomplete.track_caret(&grid.read().get_current_cell_content());
+                },
+                onkeyup: move |_| {
+                    autocomplete.track_caret(&grid.read().get_current_cell_content());
+                },
+                onkeydown: move |evt| {
+                    // evt.stop_propagation();
+                    let content = grid.read().get_current_cell_content();
+                    match autocomplete.handle_key(&evt.key(), &content) {
+                        AutocompleteKey::Completed(completed) => {
+                            evt.prevent_default();
+                            let coords = grid.read().current_cell();
+                            grid.write().apply(GridCommand::EditContent { coords, content: completed });
+                            return;
                         }
+                        AutocompleteKey::Handled => {
+                            evt.prevent_default();
+                            return;
+                        }
+                        AutocompleteKey::Ignored => {}
                     }
-                    Key::Escape => {
-                        evt.prevent_default();
-                        let coords = grid.read().current_cell;
-                        let previous_content = grid.write().previous_content.clone();
-                        grid.write().cells_map.entry(coords).or_insert(Cell::new()).content = previous_content;
-                        update_cell_display(grid, coords);
+                    match evt.key() {
+                        Key::F4 => {
+                            evt.prevent_default();
+                            if let Some(cycled) = cycle_reference_at_caret("formula-input", &content) {
+                                let coords = grid.read().current_cell();
+                                grid.write().apply(GridCommand::EditContent { coords, content: cycled });
+                            }
+                        }
+                        Key::Enter => {
+                            evt.prevent_default();
+                            let coords = grid.read().current_cell();
+                            grid.write().apply(GridCommand::CommitEdit(coords));
+                            if let Some(container) = scroll_container() {
+                                spawn_forever(async move {
+                                    let _ = container.set_focus(true).await;
+                                });
+                            }
+                        }
+                        Key::Escape => {
+                            evt.prevent_default();
+                            let coords = grid.read().current_cell();
+                            grid.write().apply(GridCommand::CancelEdit(coords));
 
-                        if let Some(container) = scroll_container() {
-                            spawn_forever(async move {
-                                let _ = container.set_focus(true).await;
-                            });
+                            if let Some(container) = scroll_container() {
+                                spawn_forever(async move {
+                                    let _ = container.set_focus(true).await;
+                                });
+                            }
                         }
+                        _ => {}
                     }
-                    _ => {}
                 }
             }
+            {diagnostic_overlay}
+            AutocompleteDropdown {
+                autocomplete,
+                suggestions,
+                on_pick: move |name: &'static str| {
+                    let content = grid.read().get_current_cell_content();
+                    if let Some(completed) = autocomplete.complete(&content, name) {
+                        let coords = grid.read().current_cell();
+                        grid.write().apply(GridCommand::EditContent { coords, content: completed });
+                    }
+                },
+            }
         }
     }
 }
 
-fn export_to_csv(grid: Signal<Grid>) -> String {
-    let row_count = grid
-        .read()
-        .cells_map
-        .keys()
-        .map(|c| c.row)
-        .max()
-        .unwrap_or(0);
-    let col_count = grid
-        .read()
-        .cells_map
-        .keys()
-        .map(|c| c.column)
-        .max()
-        .unwrap_or(0);
-
-    let mut lines = Vec::new();
-    for row in 0..=row_count {
-        let mut cells = Vec::new();
-        for col in 0..=col_count {
-            let coords = Coords { row, column: col };
-            let content = grid
-                .read()
-                .cells_map
-                .get(&coords)
-                .map(|c| c.content.clone())
-                .unwrap_or_default();
-            cells.push(content);
+fn toggle_panel(mut side_panel: Signal<Option<Panel>>, panel: Panel) {
+    if side_panel() == Some(panel) {
+        side_panel.set(None);
+    } else {
+        side_panel.set(Some(panel));
+    }
+}
+
+/// Toolbar button dropping down the formats the sheet or the selection downloads as, writing
+/// either the computed values or the formulas to import back. ODS takes the whole workbook.
+#[component]
+fn ExportMenu(grid: Signal<Grid>) -> Element {
+    let mut open = use_signal(|| false);
+    let mut content = use_signal(|| ExportContent::Values);
+    let mut selection_only = use_signal(|| false);
+    let rows = move || {
+        let grid = grid.read();
+        match selection_only() {
+            true => grid.export_selection(content()),
+            false => grid.export_rows(content()),
+        }
+    };
+    let formats = [
+        ("Export to CSV", ',', "export.csv", "text/csv"),
+        ("Export to TSV", '\t', "export.tsv", "text/tab-separated-values"),
+    ];
+
+    rsx! {
+        div {
+            class: "clean-data",
+
+            button {
+                "tooltip-text": "Export",
+                onclick: move |_| open.toggle(),
+                lucide_dioxus::Save { size: 22 }
+            }
+            if open() {
+                div {
+                    class: "clean-data-dropdown",
+
+                    label {
+                        class: "export-content",
+                        input {
+                            r#type: "radio",
+                            name: "export-content",
+                            checked: content() == ExportContent::Values,
+                            onchange: move |_| content.set(ExportContent::Values),
+                        }
+                        "Values"
+                    }
+                    label {
+                        class: "export-content",
+                        input {
+                            r#type: "radio",
+                            name: "export-content",
+                            checked: content() == ExportContent::Formulas,
+                            onchange: move |_| content.set(ExportContent::Formulas),
+                        }
+                        "Formulas"
+                    }
+                    label {
+                        class: "export-content",
+                        input {
+                            r#type: "checkbox",
+                            checked: selection_only(),
+                            onchange: move |evt| selection_only.set(evt.checked()),
+                        }
+                        "Selection only"
+                    }
+                    for (label, delimiter, file_name, mime_type) in formats {
+                        button {
+                            onclick: move |_| {
+                                download_text(csv::write_delimited(&rows(), delimiter), mime_type, file_name);
+                                open.set(false);
+                            },
+                            "{label}"
+                        }
+                    }
+                    button {
+                        onclick: move |_| {
+                            let table = grid.read().export_html(selection_only());
+                            let title = clipboard::escape_html(&grid.read().sheet().name);
+                            let html = format!("<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title></head><body>{table}</body></html>");
+                            download_text(html, "text/html", "export.html");
+                            open.set(false);
+                        },
+                        "Export to HTML"
+                    }
+                    button {
+                        onclick: move |_| {
+                            spawn(export_ods(grid));
+                            open.set(false);
+                        },
+                        "Export to ODS"
+                    }
+                    button {
+                        onclick: move |_| {
+                            let rows = rows();
+                            // Values paste into documents with their formatting
+                            let html = match content() {
+                                ExportContent::Values => grid.read().export_html(selection_only()),
+                                ExportContent::Formulas => clipboard::to_html_table(&rows),
+                            };
+                            spawn(write_system_clipboard(csv::write_tsv(&rows), html));
+                            open.set(false);
+                        },
+                        "Copy to clipboard"
+                    }
+                    button {
+                        onclick: move |_| {
+                            let markdown = clipboard::to_markdown_table(&rows());
+                            spawn(write_system_clipboard_text(markdown));
+                            open.set(false);
+                        },
+                        "Copy as Markdown"
+                    }
+                    button {
+                        onclick: move |_| {
+                            let html = match content() {
+                                ExportContent::Values => grid.read().export_html(selection_only()),
+                                ExportContent::Formulas => clipboard::to_html_table(&rows()),
+                            };
+                            // The markup as text, to paste as source into editors
+                            spawn(write_system_clipboard(html.clone(), html));
+                            open.set(false);
+                        },
+                        "Copy as HTML"
+                    }
+                }
+            }
         }
-        lines.push(cells.join(","));
     }
-    lines.join("\n")
 }
 
-fn import_csv(mut grid: Signal<Grid>, csv_text: &str) {
-    let mut coords_list: Vec<Coords> = Vec::new();
-    csv_text.lines().enumerate().for_each(|(row, line)| {
-        line.split(',').enumerate().for_each(|(col, content)| {
-            let coords = Coords {
-                row: row as i32,
-                column: col as i32,
-            };
-            coords_list.push(coords);
-            grid.write()
-                .cells_map
-                .entry(coords)
-                .or_insert(Cell::new())
-                .content = content.to_string();
-        });
-    });
-    coords_list.into_iter().for_each(|coords| {
-        update_cell_display(grid, coords);
-    });
+/// Downloads an export in the browser, or saves it where the system's dialog says on the
+/// desktop
+fn download_text(text: String, mime_type: &'static str, file_name: &'static str) {
+    spawn(save_export(text.into_bytes(), mime_type, file_name));
 }
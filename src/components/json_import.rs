@@ -0,0 +1,197 @@
+use std::{collections::HashSet, rc::Rc};
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::{
+        csv_import::{continue_import, Destination, DestinationSelect},
+        dialog::{close_dialog, Dialog},
+        remote_data::fetch_text,
+    },
+    engine::{csv, json},
+    model::{command::GridCommand, grid::Grid},
+};
+
+/// Records shown before importing them
+const PREVIEW_ROWS: usize = 5;
+
+/// Picks which fields of a JSON array of objects become columns and where they go,
+/// previewing the first records. Without `text` it first asks for a URL to fetch it from.
+#[component]
+pub fn JsonImportDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    text: Option<Rc<str>>,
+) -> Element {
+    let mut loaded = use_signal(|| text.clone());
+    let mut url = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+    let mut fetching = use_signal(|| false);
+    // Fields left out, so every field is in until it's unticked
+    let mut excluded = use_signal(HashSet::<String>::new);
+    let destination = use_signal(|| Destination::NewSheet);
+
+    let mut fetch = move || {
+        if fetching() {
+            return;
+        }
+        fetching.set(true);
+        message.set(None);
+        spawn(async move {
+            match fetch_text(url().trim().to_string()).await {
+                Some(text) => loaded.set(Some(text.into())),
+                None => message.set(Some("The URL couldn't be fetched".to_string())),
+            }
+            fetching.set(false);
+        });
+    };
+
+    let Some(text) = loaded() else {
+        return rsx! {
+            div {
+                class: "url-import",
+
+                div { class: "dialog-message", "The URL of a JSON array of objects" }
+                input {
+                    placeholder: "https://example.com/data.json",
+                    value: "{url}",
+                    onmounted: move |elem| async move {
+                        let _ = elem.data().set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        url.set(evt.value());
+                        message.set(None);
+                    },
+                    onkeydown: move |evt| {
+                        if evt.key() == Key::Enter {
+                            fetch();
+                        }
+                    },
+                }
+                if let Some(message) = message() {
+                    div { class: "dialog-message", "{message}" }
+                }
+            }
+            div {
+                class: "dialog-buttons",
+                button {
+                    onclick: move |_| close_dialog(dialog, scroll_container),
+                    "Cancel"
+                }
+                button {
+                    disabled: fetching() || url.read().trim().is_empty(),
+                    onclick: move |_| fetch(),
+                    if fetching() { "Fetching…" } else { "Fetch" }
+                }
+            }
+        };
+    };
+
+    let table = match json::read_records(&text) {
+        Ok(table) => table,
+        Err(e) => {
+            return rsx! {
+                div { class: "dialog-message", "{e}" }
+                div {
+                    class: "dialog-buttons",
+                    button {
+                        onclick: move |_| close_dialog(dialog, scroll_container),
+                        "Close"
+                    }
+                }
+            };
+        }
+    };
+    let fields = table
+        .fields
+        .iter()
+        .filter(|field| !excluded.read().contains(*field))
+        .cloned()
+        .collect::<Vec<_>>();
+    let rows = table.rows(&fields);
+    let preview = rows.iter().take(PREVIEW_ROWS + 1).cloned().collect::<Vec<_>>();
+    let record_count = table.records.len();
+    let command = move || {
+        let (at, new_sheet) = destination().place(&grid.read());
+        let types = (0..fields.len())
+            .map(|column| csv::detect_column_type(&rows, column, true))
+            .collect();
+        GridCommand::ImportCsv {
+            rows: rows.clone().into(),
+            at,
+            new_sheet,
+            header: true,
+            types,
+        }
+    };
+
+    rsx! {
+        div {
+            class: "csv-import",
+
+            div {
+                class: "csv-import-options",
+                DestinationSelect { destination }
+            }
+            div {
+                class: "json-import-fields",
+                for field in table.fields.clone() {
+                    label {
+                        class: "csv-import-check",
+                        input {
+                            r#type: "checkbox",
+                            checked: !excluded.read().contains(&field),
+                            onchange: {
+                                let field = field.clone();
+                                move |evt: FormEvent| {
+                                    if evt.checked() {
+                                        excluded.write().remove(&field);
+                                    } else {
+                                        excluded.write().insert(field.clone());
+                                    }
+                                }
+                            },
+                        }
+                        "{field}"
+                    }
+                }
+            }
+            div {
+                class: "csv-import-preview",
+                table {
+                    tbody {
+                        for (i, fields) in preview.into_iter().enumerate() {
+                            tr {
+                                class: if i == 0 { "csv-import-header" },
+                                for field in fields {
+                                    td { "{field}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            span {
+                class: "csv-import-count",
+                if record_count == 1 { "1 record" } else { "{record_count} records" }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: excluded.read().len() == table.fields.len(),
+                onclick: move |_| {
+                    grid.write().apply(command());
+                    continue_import(grid);
+                    close_dialog(dialog, scroll_container);
+                },
+                "Import"
+            }
+        }
+    }
+}
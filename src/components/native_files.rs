@@ -0,0 +1,64 @@
+use std::{path::PathBuf, sync::Mutex};
+
+use dioxus::prelude::*;
+
+/// The workbook file saving writes back to, as the browser keeps the file's handle
+static WORKBOOK_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+const OPEN_EXTENSIONS: [&str; 6] = ["rsheet", "csv", "tsv", "txt", "ods", "json"];
+
+/// Asks for a file to open, returning its name and bytes. A workbook's path is kept so saving
+/// writes back to it.
+pub async fn pick_file() -> Option<(String, Vec<u8>)> {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("Spreadsheets", &OPEN_EXTENSIONS)
+        .pick_file()
+        .await?;
+    let name = file.file_name();
+    let bytes = file.read().await;
+    let path = file.path().to_path_buf();
+    *WORKBOOK_PATH.lock().unwrap() = name.to_lowercase().ends_with(".rsheet").then_some(path);
+    Some((name, bytes))
+}
+
+/// Forgets the file saving writes back to
+pub fn forget_workbook_path() {
+    *WORKBOOK_PATH.lock().unwrap() = None;
+}
+
+/// Writes the workbook over the file it came from or was last saved to, asking where to save
+/// it the first time and for Save As. Returns the name it was saved under, None if it wasn't.
+pub async fn save_workbook(text: &str, save_as: bool, name: &str) -> Option<String> {
+    let kept = WORKBOOK_PATH.lock().unwrap().clone();
+    let path = match kept.filter(|_| !save_as) {
+        Some(path) => path,
+        None => ask_save_path(name, "Workbook", "rsheet").await?,
+    };
+    if let Err(e) = std::fs::write(&path, text) {
+        error!("{e}");
+        return None;
+    }
+    let name = path.file_name()?.to_string_lossy().to_string();
+    *WORKBOOK_PATH.lock().unwrap() = Some(path);
+    Some(name)
+}
+
+/// Asks where to save an export and writes it there
+pub async fn save_file(bytes: Vec<u8>, name: &str) {
+    let extension = name.rsplit_once('.').map(|(_, extension)| extension).unwrap_or("");
+    let Some(path) = ask_save_path(name, "Export", extension).await else {
+        return;
+    };
+    if let Err(e) = std::fs::write(path, bytes) {
+        error!("{e}");
+    }
+}
+
+async fn ask_save_path(name: &str, description: &str, extension: &str) -> Option<PathBuf> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_file_name(name)
+        .add_filter(description, &[extension])
+        .save_file()
+        .await?;
+    Some(file.path().to_path_buf())
+}
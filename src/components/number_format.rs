@@ -0,0 +1,109 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    engine::number_format::FormatCode,
+    model::{command::GridCommand, format::NumberFormat, grid::Grid},
+};
+
+/// Codes to start from, most are the built-in formats spelled out
+const EXAMPLE_CODES: [&str; 5] = [
+    "#,##0.00",
+    "#,##0.00;[Red](#,##0.00)",
+    "0.0%",
+    "$#,##0;[Red]-$#,##0;\"-\"",
+    "000000",
+];
+
+#[component]
+pub fn CustomFormatDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut code = use_signal(|| {
+        match grid.read().get_current_cell().map(|c| &c.format.number_format) {
+            Some(NumberFormat::Custom(code)) => code.clone(),
+            _ => String::new(),
+        }
+    });
+    let parsed = FormatCode::parse(&code.read());
+    let sample = grid
+        .read()
+        .get_current_cell()
+        .map(|c| c.display_value.clone())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "-1234.5".to_string());
+
+    let mut apply = move || {
+        let text = code.read().clone();
+        if FormatCode::parse(&text).is_none() {
+            return;
+        }
+        grid.write()
+            .apply(GridCommand::SetNumberFormat(NumberFormat::Custom(text)));
+        close_dialog(dialog, scroll_container);
+    };
+
+    rsx! {
+        div {
+            class: "custom-format",
+
+            input {
+                placeholder: "#,##0.00;[Red](#,##0.00)",
+                value: "{code}",
+                onmounted: move |elem| async move {
+                    let _ = elem.data().set_focus(true).await;
+                },
+                oninput: move |evt| code.set(evt.value()),
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Enter {
+                        apply();
+                    }
+                },
+            }
+            match parsed {
+                Some(parsed) => {
+                    let formatted = parsed.format(&sample);
+                    let style = formatted
+                        .color
+                        .map(|color| format!("color: {};", color))
+                        .unwrap_or_default();
+                    rsx! {
+                        div {
+                            class: "dialog-message",
+                            "{sample} shows as "
+                            span { class: "custom-format-preview", style, "{formatted.text}" }
+                        }
+                    }
+                }
+                None => rsx! {
+                    div { class: "dialog-message", "This format code can't be read" }
+                },
+            }
+            div {
+                class: "custom-format-examples",
+                for example in EXAMPLE_CODES {
+                    button {
+                        onclick: move |_| code.set(example.to_string()),
+                        "{example}"
+                    }
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: FormatCode::parse(&code.read()).is_none(),
+                onclick: move |_| apply(),
+                "Apply"
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use dioxus::prelude::*;
+
+use crate::{
+    components::files::save_export,
+    engine::{
+        ods::{self, ODS_MIME_TYPE},
+        zip::{self, Compression},
+    },
+    model::{command::GridCommand, grid::Grid},
+};
+
+/// Inflates a ZIP entry's raw DEFLATE data into text
+const INFLATE_JS: &str = r#"
+    const bytes = new Uint8Array(await dioxus.recv());
+    const stream = new Blob([bytes]).stream().pipeThrough(new DecompressionStream("deflate-raw"));
+    try {
+        return await new Response(stream).text();
+    } catch {
+        return null;
+    }
+"#;
+
+/// Adds the sheets of an OpenDocument spreadsheet to the workbook
+pub async fn import_ods(mut grid: Signal<Grid>, bytes: Vec<u8>) {
+    let Some(entry) = zip::find_entry(&bytes, "content.xml") else {
+        error!("Not an OpenDocument spreadsheet");
+        return;
+    };
+    let xml = match entry.compression {
+        Compression::Stored => String::from_utf8(entry.data.to_vec()).ok(),
+        Compression::Deflated => {
+            let eval = document::eval(INFLATE_JS);
+            let _ = eval.send(entry.data.to_vec());
+            eval.join::<Option<String>>().await.ok().flatten()
+        }
+    };
+    let Some(xml) = xml else {
+        error!("Couldn't read the spreadsheet's content");
+        return;
+    };
+    let (row_count, column_count) = {
+        let grid = grid.read();
+        (
+            grid.sheet().row_heights.len(),
+            grid.sheet().column_widths.len(),
+        )
+    };
+    let tables = ods::read_content(&xml, row_count, column_count);
+    grid.write().apply(GridCommand::ImportSheets(tables));
+}
+
+/// Exports every sheet as an OpenDocument spreadsheet
+pub async fn export_ods(grid: Signal<Grid>) {
+    let bytes = {
+        let grid = grid.read();
+        ods::write_ods(&grid.export_sheets(), &grid.workbook.properties)
+    };
+    save_export(bytes, ODS_MIME_TYPE, "export.ods").await;
+}
@@ -0,0 +1,65 @@
+use dioxus::prelude::*;
+
+/// Registers the service worker that caches the app for offline use, then hands it the files
+/// loaded before it took over so it caches those too
+const REGISTER_JS: &str = r#"
+    if (!("serviceWorker" in navigator)) {
+        return;
+    }
+    try {
+        await navigator.serviceWorker.register("/sw.js");
+        const registration = await navigator.serviceWorker.ready;
+        const urls = performance
+            .getEntriesByType("resource")
+            .map((entry) => entry.name)
+            .filter((url) => new URL(url).origin === location.origin);
+        registration.active?.postMessage({ type: "cache", urls: [location.pathname, ...urls] });
+    } catch (error) {
+        console.warn("Offline support unavailable", error);
+    }
+"#;
+
+const IS_ONLINE_JS: &str = "return navigator.onLine;";
+
+/// Waits for the connection to be lost or come back, returning whether it's online
+const CONNECTION_CHANGE_JS: &str = r#"
+    await new Promise((resolve) => {
+        window.addEventListener("online", resolve, { once: true });
+        window.addEventListener("offline", resolve, { once: true });
+    });
+    return navigator.onLine;
+"#;
+
+/// Makes the app installable and usable offline
+pub fn use_service_worker() {
+    use_hook(|| {
+        let _ = document::eval(REGISTER_JS);
+    });
+}
+
+/// Shows when the browser has no connection. Everything but formulas fetching URLs keeps
+/// working, with workbooks autosaved in the browser.
+#[component]
+pub fn OfflineIndicator() -> Element {
+    let mut online = use_signal(|| true);
+    use_future(move || async move {
+        if let Ok(value) = document::eval(IS_ONLINE_JS).join::<bool>().await {
+            online.set(value);
+        }
+        while let Ok(value) = document::eval(CONNECTION_CHANGE_JS).join::<bool>().await {
+            online.set(value);
+        }
+    });
+
+    if online() {
+        return rsx! {};
+    }
+    rsx! {
+        div {
+            class: "offline-indicator",
+            "tooltip-text": "No connection. Changes are kept in this browser, formulas fetching URLs can't refresh.",
+            lucide_dioxus::WifiOff { size: 18 }
+            "Offline"
+        }
+    }
+}
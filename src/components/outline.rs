@@ -0,0 +1,201 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        grid::{column_index_to_letter, Coords, Grid},
+        outline::OutlineDirection,
+    },
+};
+
+/// SUBTOTAL's functions the tool offers, by their number
+const SUBTOTAL_FUNCTIONS: [(u8, &str); 4] = [(9, "Sum"), (1, "Average"), (4, "Max"), (5, "Min")];
+
+/// The +/- buttons on a row or column header for the groups that end next to it
+#[component]
+pub fn OutlineToggles(grid: Signal<Grid>, direction: OutlineDirection, index: i32) -> Element {
+    let grid_read = grid.read();
+    let sheet = grid_read.sheet();
+    let count = match direction {
+        OutlineDirection::Rows => sheet.row_heights.len(),
+        OutlineDirection::Columns => sheet.column_widths.len(),
+    };
+    let outline = sheet.outline(direction);
+    let toggles = outline
+        .groups_toggled_at(index, count as i32)
+        .into_iter()
+        .map(|group| (group, outline.is_collapsed(&group)))
+        .collect::<Vec<_>>();
+    if toggles.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div {
+            class: match direction {
+                OutlineDirection::Rows => "outline-toggles outline-toggles-rows",
+                OutlineDirection::Columns => "outline-toggles outline-toggles-columns",
+            },
+            for (group, collapsed) in toggles {
+                button {
+                    "tooltip-text": if collapsed { "Expand group" } else { "Collapse group" },
+                    // Not selecting the row or column as well
+                    onmousedown: move |evt| evt.stop_propagation(),
+                    onclick: move |_| {
+                        grid.write().apply(GridCommand::ToggleOutlineGroup {
+                            direction,
+                            level: group.level,
+                            start: group.start,
+                        });
+                    },
+                    if collapsed { "+" } else { "−" }
+                }
+            }
+        }
+    }
+}
+
+/// A bar along a header for each level of groups it's in
+pub fn outline_style(grid: &Grid, direction: OutlineDirection, index: i32) -> String {
+    let level = grid.sheet().outline(direction).level(index);
+    if level == 0 {
+        return String::new();
+    }
+    let depth = level as i32 * 3;
+    match direction {
+        OutlineDirection::Rows => {
+            format!(" box-shadow: inset {depth}px 0 0 var(--header-separator-fx);")
+        }
+        OutlineDirection::Columns => {
+            format!(" box-shadow: inset 0 {depth}px 0 var(--header-separator-fx);")
+        }
+    }
+}
+
+/// Picks the column whose changes start a new subtotal, the function and the columns to
+/// total for the range sorting would pick
+#[component]
+pub fn SubtotalsDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let range = use_signal(|| grid.read().get_sort_range());
+    let mut key_column = use_signal(|| range().map(|range| range.top_left().column));
+    let mut function = use_signal(|| 9);
+    // The last column is usually the one with the numbers
+    let mut columns = use_signal(|| {
+        range()
+            .map(|range| vec![range.bottom_right().column])
+            .unwrap_or_default()
+    });
+
+    let Some(range) = range() else {
+        return rsx! {
+            div {
+                class: "dialog-message",
+                "Select the data to total, sorted by the column to group it by"
+            }
+        };
+    };
+    // Named by the header above the data, if there is one
+    let names = {
+        let grid = grid.read();
+        let header = range.top_left().row - 1;
+        (range.top_left().column..=range.bottom_right().column)
+            .map(|column| {
+                let name = grid
+                    .sheet()
+                    .cells_map
+                    .get(&Coords {
+                        row: header,
+                        column,
+                    })
+                    .map(|cell| cell.display_value.clone())
+                    .filter(|name| !name.is_empty() && name.parse::<f64>().is_err());
+                let name = name
+                    .unwrap_or_else(|| format!("Column {}", column_index_to_letter(column)));
+                (column, name)
+            })
+            .collect::<Vec<_>>()
+    };
+    let command = move || -> Option<GridCommand> {
+        (!columns.read().is_empty()).then_some(GridCommand::Subtotals {
+            range,
+            key_column: key_column()?,
+            function: function(),
+            columns: columns(),
+        })
+    };
+
+    rsx! {
+        div {
+            class: "analysis",
+
+            label {
+                "At each change in"
+                select {
+                    onchange: move |evt| key_column.set(evt.value().parse().ok()),
+                    for (column, name) in names.clone() {
+                        option {
+                            value: "{column}",
+                            selected: key_column() == Some(column),
+                            "{name}"
+                        }
+                    }
+                }
+            }
+            label {
+                "Use function"
+                select {
+                    onchange: move |evt| function.set(evt.value().parse().unwrap_or(9)),
+                    for (number, name) in SUBTOTAL_FUNCTIONS {
+                        option {
+                            value: "{number}",
+                            selected: function() == number,
+                            "{name}"
+                        }
+                    }
+                }
+            }
+            span { "Add subtotal to" }
+            for (column, name) in names {
+                label {
+                    class: "analysis-check",
+                    input {
+                        r#type: "checkbox",
+                        checked: columns.read().contains(&column),
+                        onchange: move |evt| {
+                            columns.write().retain(|c| *c != column);
+                            if evt.checked() {
+                                columns.write().push(column);
+                                columns.write().sort();
+                            }
+                        },
+                    }
+                    "{name}"
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: command().is_none(),
+                onclick: move |_| {
+                    if let Some(command) = command() {
+                        grid.write().apply(command);
+                        close_dialog(dialog, scroll_container);
+                    }
+                },
+                "OK"
+            }
+        }
+    }
+}
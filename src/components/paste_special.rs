@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        clipboard::{PasteContent, PasteOptions},
+        command::GridCommand,
+        grid::Grid,
+    },
+};
+
+#[component]
+pub fn PasteSpecialDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut options = use_signal(PasteOptions::default);
+
+    if grid.read().view.clipboard.is_none() {
+        return rsx! {
+            div { class: "dialog-message", "Copy some cells first" }
+        };
+    }
+
+    rsx! {
+        div {
+            class: "paste-special",
+
+            label {
+                input {
+                    r#type: "radio",
+                    name: "paste-content",
+                    checked: options().content == PasteContent::All,
+                    onchange: move |_| options.write().content = PasteContent::All,
+                }
+                "All"
+            }
+            label {
+                input {
+                    r#type: "radio",
+                    name: "paste-content",
+                    checked: options().content == PasteContent::Formulas,
+                    onchange: move |_| options.write().content = PasteContent::Formulas,
+                }
+                "Formulas"
+            }
+            label {
+                input {
+                    r#type: "radio",
+                    name: "paste-content",
+                    checked: options().content == PasteContent::Values,
+                    onchange: move |_| options.write().content = PasteContent::Values,
+                }
+                "Values only"
+            }
+            label {
+                input {
+                    r#type: "radio",
+                    name: "paste-content",
+                    checked: options().content == PasteContent::Formats,
+                    onchange: move |_| options.write().content = PasteContent::Formats,
+                }
+                "Formats only"
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: options().skip_blanks,
+                    onchange: move |evt| options.write().skip_blanks = evt.checked(),
+                }
+                "Skip blanks"
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: options().transpose,
+                    onchange: move |evt| options.write().transpose = evt.checked(),
+                }
+                "Transpose"
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                onclick: move |_| {
+                    grid.write().apply(GridCommand::PasteSpecial(options()));
+                    close_dialog(dialog, scroll_container);
+                },
+                "Paste"
+            }
+        }
+    }
+}
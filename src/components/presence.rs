@@ -0,0 +1,105 @@
+use dioxus::prelude::*;
+
+use crate::model::{command::GridCommand, grid::Grid, presence::Presence};
+
+/// How often presence is sent when the selection stays put, and others' checked for
+const PRESENCE_INTERVAL_MS: u32 = 2000;
+
+/// Sends where this session is, returning where the others are, or null if the server
+/// couldn't be reached or refused it
+const PRESENCE_JS: &str = r#"
+    const [url, token, presence] = await dioxus.recv();
+    try {
+        const response = await fetch(url, {
+            method: "PUT",
+            headers: { Authorization: `Bearer ${token}`, "Content-Type": "application/json" },
+            body: JSON.stringify(presence),
+        });
+        return response.ok ? await response.json() : null;
+    } catch {
+        return null;
+    }
+"#;
+
+/// Tells the server where this session is and shows where the others are
+async fn exchange_presence(mut grid: Signal<Grid>) {
+    let (Some(presence), Some(collaboration)) = (
+        grid.peek().presence(),
+        grid.peek().view.collaboration.clone(),
+    ) else {
+        return;
+    };
+    let eval = document::eval(PRESENCE_JS);
+    let _ = eval.send((
+        collaboration.connection.presence_url(&collaboration.workbook),
+        &collaboration.connection.token,
+        presence,
+    ));
+    if let Ok(Some(collaborators)) = eval.join::<Option<Vec<Presence>>>().await {
+        // The workbook may have stopped being shared in the meantime
+        if grid.peek().view.collaboration.as_ref() == Some(&collaboration) {
+            grid.write()
+                .apply(GridCommand::SetCollaborators(collaborators));
+        }
+    }
+}
+
+/// While a stored workbook is shared, sends the selection as soon as it changes and every
+/// couple of seconds, keeping the others' up to date
+pub fn use_presence(grid: Signal<Grid>) {
+    let presence = use_memo(move || grid.read().presence());
+    use_effect(move || {
+        if presence().is_some() {
+            spawn(exchange_presence(grid));
+        }
+    });
+    use_future(move || async move {
+        let wait = format!(
+            "await new Promise((resolve) => setTimeout(resolve, {PRESENCE_INTERVAL_MS}));"
+        );
+        loop {
+            let _ = document::eval(&wait).await;
+            exchange_presence(grid).await;
+        }
+    });
+}
+
+/// Each collaborator's selection on the active sheet in their color, named at its corner
+#[component]
+pub fn CollaboratorCursors(grid: Signal<Grid>) -> Element {
+    let grid_read = grid.read();
+    let cursors = grid_read
+        .collaborators_on_sheet()
+        .into_iter()
+        .flat_map(|presence| {
+            let color = presence.color();
+            presence
+                .ranges
+                .iter()
+                .enumerate()
+                .map(|(index, range)| {
+                    let first = grid_read.get_cell_rect(range.top_left());
+                    let last = grid_read.get_cell_rect(range.bottom_right());
+                    let width = last.x + last.width - first.x;
+                    let height = last.y + last.height - first.y;
+                    // Only the active range carries the name, if they gave one
+                    let name = (index + 1 == presence.ranges.len() && !presence.name.is_empty())
+                        .then(|| presence.name.clone());
+                    (first.x, first.y, width, height, color, name)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    rsx! {
+        for (left, top, width, height, color, name) in cursors {
+            div {
+                class: "collaborator-cursor",
+                style: "left: {left}px; top: {top}px; width: {width}px; height: {height}px; --collaborator-color: var(--reference-color-{color});",
+                if let Some(name) = name {
+                    span { class: "collaborator-name", "{name}" }
+                }
+            }
+        }
+    }
+}
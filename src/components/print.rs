@@ -0,0 +1,279 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        grid::{coords_to_cell_address, Grid},
+        print::{HeaderFooter, Orientation, HEADER_FOOTER_TOKENS, PAGE_SIZES, SCALINGS},
+        view::CellRange,
+    },
+};
+
+/// Prints a document from a hidden frame, which goes once the print dialog closes. Browsers
+/// save it as a PDF from there.
+const PRINT_JS: &str = r#"
+    const html = await dioxus.recv();
+    const frame = document.createElement("iframe");
+    frame.style.cssText = "position: fixed; width: 0; height: 0; border: 0;";
+    frame.onload = () => {
+        frame.contentWindow.onafterprint = () => frame.remove();
+        frame.contentWindow.focus();
+        frame.contentWindow.print();
+    };
+    frame.srcdoc = html;
+    document.body.appendChild(frame);
+"#;
+
+pub fn print_document(html: String) {
+    let eval = document::eval(PRINT_JS);
+    let _ = eval.send(html);
+}
+
+/// Today in the user's locale, for headers and footers
+fn today() -> String {
+    js_sys::Date::new_0()
+        .to_locale_date_string("default", &wasm_bindgen::JsValue::UNDEFINED)
+        .into()
+}
+
+fn range_address(range: CellRange) -> String {
+    format!(
+        "{}:{}",
+        coords_to_cell_address(range.top_left()),
+        coords_to_cell_address(range.bottom_right())
+    )
+}
+
+/// Inputs for the left, center and right text of a header or footer
+#[component]
+fn HeaderFooterInputs(
+    label: &'static str,
+    value: HeaderFooter,
+    onchange: EventHandler<HeaderFooter>,
+) -> Element {
+    let parts = [
+        ("Left", value.left.clone()),
+        ("Center", value.center.clone()),
+        ("Right", value.right.clone()),
+    ];
+    rsx! {
+        div { class: "dialog-message", "{label}" }
+        div {
+            class: "print-header-footer",
+            for (index, (placeholder, text)) in parts.into_iter().enumerate() {
+                input {
+                    placeholder,
+                    value: "{text}",
+                    oninput: {
+                        let value = value.clone();
+                        move |evt: FormEvent| {
+                            let mut changed = value.clone();
+                            match index {
+                                0 => changed.left = evt.value(),
+                                1 => changed.center = evt.value(),
+                                _ => changed.right = evt.value(),
+                            }
+                            onchange.call(changed);
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn PrintDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut settings = use_signal(|| grid.read().sheet().print_settings.clone());
+    let mut print_area = use_signal(|| {
+        let area = settings.read().print_area;
+        area.map(range_address).unwrap_or_default()
+    });
+    let date = use_hook(today);
+    let layout = use_memo(move || grid.read().print_layout(&settings.read()));
+    let preview = use_memo(move || grid.read().print_html(&settings.read(), &date));
+    // Blank for the cells in use, and left as it was while it's not a range of the sheet
+    let mut set_print_area = move |text: String| {
+        let text = text.trim().to_string();
+        let range = match text.is_empty() {
+            true => Some(None),
+            false => grid
+                .read()
+                .resolve_range(&text)
+                .filter(|(sheet, _)| *sheet == grid.read().view.active_sheet)
+                .map(|(_, range)| Some(range)),
+        };
+        if let Some(range) = range {
+            settings.write().print_area = range;
+        }
+        print_area.set(text);
+    };
+
+    let print = move |_| {
+        grid.write().apply(GridCommand::SetPrintSettings(settings()));
+        print_document(preview());
+        close_dialog(dialog, scroll_container);
+    };
+    let margins = settings().margins;
+    let margin_inputs = [
+        ("Top", margins.top),
+        ("Right", margins.right),
+        ("Bottom", margins.bottom),
+        ("Left", margins.left),
+    ];
+    let tokens = HEADER_FOOTER_TOKENS.join(" ");
+    let page_count = layout.read().pages.len();
+    let scale = (layout.read().scale * 100.0).round();
+
+    rsx! {
+        div {
+            class: "print",
+
+            div {
+                class: "print-options",
+                label {
+                    "Print area"
+                    div {
+                        class: "print-area",
+                        input {
+                            placeholder: "Cells in use",
+                            value: "{print_area}",
+                            oninput: move |evt| set_print_area(evt.value()),
+                        }
+                        button {
+                            onclick: move |_| {
+                                let range = grid.read().view.selection().active_range();
+                                set_print_area(range_address(range));
+                            },
+                            "Selection"
+                        }
+                    }
+                }
+                label {
+                    "Page size"
+                    select {
+                        value: "{PAGE_SIZES.iter().position(|(size, _)| *size == settings().page_size).unwrap_or(0)}",
+                        onchange: move |evt| {
+                            if let Some((size, _)) = evt.value().parse::<usize>().ok().and_then(|i| PAGE_SIZES.get(i)) {
+                                settings.write().page_size = *size;
+                            }
+                        },
+                        for (index, (_, name)) in PAGE_SIZES.iter().enumerate() {
+                            option { value: "{index}", "{name}" }
+                        }
+                    }
+                }
+                label {
+                    "Orientation"
+                    select {
+                        value: if settings().orientation == Orientation::Landscape { "landscape" } else { "portrait" },
+                        onchange: move |evt| {
+                            settings.write().orientation = match evt.value().as_str() {
+                                "landscape" => Orientation::Landscape,
+                                _ => Orientation::Portrait,
+                            };
+                        },
+                        option { value: "portrait", "Portrait" }
+                        option { value: "landscape", "Landscape" }
+                    }
+                }
+                label {
+                    "Scaling"
+                    select {
+                        value: "{SCALINGS.iter().position(|(scaling, _)| *scaling == settings().scaling).unwrap_or(0)}",
+                        onchange: move |evt| {
+                            if let Some((scaling, _)) = evt.value().parse::<usize>().ok().and_then(|i| SCALINGS.get(i)) {
+                                settings.write().scaling = *scaling;
+                            }
+                        },
+                        for (index, (_, name)) in SCALINGS.iter().enumerate() {
+                            option { value: "{index}", "{name}" }
+                        }
+                    }
+                }
+                div { class: "dialog-message", "Margins (mm)" }
+                div {
+                    class: "print-margins",
+                    for (side, (name, value)) in margin_inputs.into_iter().enumerate() {
+                        label {
+                            "{name}"
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "1",
+                                value: "{value}",
+                                oninput: move |evt| {
+                                    let Some(value) = evt.value().parse::<f64>().ok().filter(|v| *v >= 0.0) else {
+                                        return;
+                                    };
+                                    let mut settings = settings.write();
+                                    match side {
+                                        0 => settings.margins.top = value,
+                                        1 => settings.margins.right = value,
+                                        2 => settings.margins.bottom = value,
+                                        _ => settings.margins.left = value,
+                                    }
+                                },
+                            }
+                        }
+                    }
+                }
+                label {
+                    "Rows to repeat at the top of each page"
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        value: "{settings().repeat_rows}",
+                        oninput: move |evt| {
+                            if let Ok(rows) = evt.value().parse::<u32>() {
+                                settings.write().repeat_rows = rows;
+                            }
+                        },
+                    }
+                }
+                HeaderFooterInputs {
+                    label: "Header",
+                    value: settings().header,
+                    onchange: move |header| settings.write().header = header,
+                }
+                HeaderFooterInputs {
+                    label: "Footer",
+                    value: settings().footer,
+                    onchange: move |footer| settings.write().footer = footer,
+                }
+                div {
+                    class: "print-count",
+                    "Tokens: {tokens}"
+                }
+                div {
+                    class: "print-count",
+                    if page_count == 1 { "1 page" } else { "{page_count} pages" }
+                    if scale < 100.0 { ", at {scale}%" }
+                }
+            }
+            iframe {
+                class: "print-preview",
+                srcdoc: "{preview}",
+            }
+        }
+        div { class: "dialog-message", "Choose \"Save as PDF\" as the printer to get a PDF." }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                onclick: print,
+                "Print"
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{command::GridCommand, grid::Grid, properties::DocumentProperties},
+};
+
+/// The workbook's title, author, description and custom properties. Formulas show them with
+/// `INFO("name")`.
+#[component]
+pub fn PropertiesDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut properties = use_signal(|| grid.read().workbook.properties.clone());
+    // Custom properties as edited, in order, blank names dropped on saving
+    let mut custom = use_signal(|| {
+        properties
+            .peek()
+            .custom
+            .clone()
+            .into_iter()
+            .collect::<Vec<_>>()
+    });
+
+    let save = move |_| {
+        let mut saved: DocumentProperties = properties();
+        saved.custom = custom()
+            .into_iter()
+            .map(|(name, value)| (name.trim().to_string(), value))
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+        grid.write().apply(GridCommand::SetProperties(saved));
+        close_dialog(dialog, scroll_container);
+    };
+
+    rsx! {
+        div {
+            class: "properties",
+            label {
+                "Title"
+                input {
+                    value: "{properties.read().title}",
+                    oninput: move |evt| properties.write().title = evt.value(),
+                }
+            }
+            label {
+                "Author"
+                input {
+                    value: "{properties.read().author}",
+                    oninput: move |evt| properties.write().author = evt.value(),
+                }
+            }
+            label {
+                "Description"
+                textarea {
+                    value: "{properties.read().description}",
+                    oninput: move |evt| properties.write().description = evt.value(),
+                }
+            }
+            div { class: "dialog-message", "Custom properties" }
+            for (index, (name, value)) in custom().into_iter().enumerate() {
+                div {
+                    class: "properties-custom",
+                    input {
+                        placeholder: "Name",
+                        value: "{name}",
+                        oninput: move |evt| custom.write()[index].0 = evt.value(),
+                    }
+                    input {
+                        placeholder: "Value",
+                        value: "{value}",
+                        oninput: move |evt| custom.write()[index].1 = evt.value(),
+                    }
+                    button {
+                        "tooltip-text": "Remove",
+                        onclick: move |_| {
+                            custom.write().remove(index);
+                        },
+                        lucide_dioxus::X { size: 14 }
+                    }
+                }
+            }
+            div {
+                class: "properties-custom",
+                button {
+                    onclick: move |_| custom.write().push((String::new(), String::new())),
+                    "Add property"
+                }
+            }
+            div {
+                class: "dialog-message",
+                "Formulas show a property with INFO, like =INFO(\"title\")."
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                onclick: save,
+                "Save"
+            }
+        }
+    }
+}
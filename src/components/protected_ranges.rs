@@ -0,0 +1,95 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        grid::{coords_to_cell_address, Grid},
+    },
+};
+
+/// The active sheet's ranges that stay editable, or stay locked, once it's protected, and
+/// adding one for the selection
+#[component]
+pub fn ProtectedRangesDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut editable = use_signal(|| true);
+    let mut description = use_signal(String::new);
+    let grid_read = grid.read();
+    let protected = grid_read.sheet().protected;
+    let ranges = grid_read.sheet().protected_ranges.clone();
+    let active_range = grid_read.view.selection().active_range();
+    let selection = format!(
+        "{}:{}",
+        coords_to_cell_address(active_range.top_left()),
+        coords_to_cell_address(active_range.bottom_right())
+    );
+
+    rsx! {
+        div {
+            class: "protected-ranges",
+
+            if protected {
+                div {
+                    class: "dialog-message",
+                    "Unprotect the sheet to change its ranges."
+                }
+            }
+            if ranges.is_empty() {
+                div { class: "dialog-message", "No ranges yet. Cells go by their own lock." }
+            }
+            for (index, range) in ranges.into_iter().enumerate() {
+                div {
+                    class: "protected-range",
+                    span { class: "protected-range-address", "{range.address()}" }
+                    span { if range.editable { "Editable" } else { "Protected" } }
+                    span { class: "protected-range-description", "{range.description}" }
+                    button {
+                        "tooltip-text": "Remove",
+                        disabled: protected,
+                        onclick: move |_| grid.write().apply(GridCommand::RemoveProtectedRange(index)),
+                        lucide_dioxus::X { size: 14 }
+                    }
+                }
+            }
+            div {
+                class: "protected-range",
+                span { class: "protected-range-address", "{selection}" }
+                select {
+                    value: if editable() { "editable" } else { "protected" },
+                    onchange: move |evt| editable.set(evt.value() == "editable"),
+                    option { value: "editable", "Editable" }
+                    option { value: "protected", "Protected" }
+                }
+                input {
+                    placeholder: "Description (optional)",
+                    value: "{description}",
+                    oninput: move |evt| description.set(evt.value()),
+                }
+                button {
+                    disabled: protected,
+                    onclick: move |_| {
+                        grid.write().apply(GridCommand::AddProtectedRange {
+                            editable: editable(),
+                            description: description(),
+                        });
+                        description.set(String::new());
+                    },
+                    "Add"
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Close"
+            }
+        }
+    }
+}
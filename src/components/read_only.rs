@@ -0,0 +1,41 @@
+use dioxus::prelude::*;
+
+use crate::model::{command::GridCommand, grid::Grid};
+
+/// Links to the app with this query parameter open it read-only
+const READ_ONLY_PARAMETER: &str = "view";
+
+const HAS_PARAMETER_JS: &str = r#"
+    const name = await dioxus.recv();
+    return new URLSearchParams(location.search).has(name);
+"#;
+
+/// Starts the workbook read-only when the app's URL asks for it
+pub fn use_read_only_parameter(mut grid: Signal<Grid>) {
+    use_future(move || async move {
+        let eval = document::eval(HAS_PARAMETER_JS);
+        let _ = eval.send(READ_ONLY_PARAMETER);
+        if let Ok(true) = eval.join::<bool>().await {
+            grid.write().apply(GridCommand::SetReadOnly(true));
+        }
+    });
+}
+
+/// Switches between viewing the workbook read-only and editing it
+#[component]
+pub fn ReadOnlyToggle(grid: Signal<Grid>) -> Element {
+    let read_only = grid.read().view.read_only;
+
+    rsx! {
+        button {
+            class: if read_only { "toolbar-toggle-on" },
+            "tooltip-text": if read_only { "Viewing only, click to edit" } else { "View only" },
+            onclick: move |_| grid.write().apply(GridCommand::SetReadOnly(!read_only)),
+            if read_only {
+                lucide_dioxus::BookLock { size: 22 }
+            } else {
+                lucide_dioxus::BookOpen { size: 22 }
+            }
+        }
+    }
+}
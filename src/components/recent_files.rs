@@ -0,0 +1,188 @@
+use dioxus::prelude::*;
+
+use crate::{
+    components::{dialog::Dialog, files::open_file},
+    model::grid::Grid,
+};
+
+/// Installs `window.recentFiles`, the files last opened or saved, newest first, kept in
+/// IndexedDB. Files picked or saved through the File System Access API are kept as their
+/// handles, others as a copy of their bytes.
+const RECENT_FILES_JS: &str = r#"
+    const MAX_RECENT_FILES = 8;
+    const withList = async (mode, change) => {
+        const db = await new Promise((resolve, reject) => {
+            const request = indexedDB.open("spreadsheet-recent", 1);
+            request.onupgradeneeded = () => request.result.createObjectStore("files");
+            request.onsuccess = () => resolve(request.result);
+            request.onerror = () => reject(request.error);
+        });
+        const run = (make) => new Promise((resolve, reject) => {
+            const request = make(db.transaction("files", mode).objectStore("files"));
+            request.onsuccess = () => resolve(request.result);
+            request.onerror = () => reject(request.error);
+        });
+        try {
+            const files = (await run((store) => store.get("list"))) ?? [];
+            const changed = await change(files);
+            if (changed) {
+                await run((store) => store.put(changed, "list"));
+            }
+            return changed ?? files;
+        } finally {
+            db.close();
+        }
+    };
+    const isSame = async (a, b) => a.handle && b.handle
+        ? await a.handle.isSameEntry(b.handle)
+        : !a.handle && !b.handle && a.name === b.name;
+    const toFront = async (files, entry) => {
+        const kept = [];
+        for (const file of files) {
+            if (!(await isSame(file, entry))) {
+                kept.push(file);
+            }
+        }
+        return [entry, ...kept].slice(0, MAX_RECENT_FILES);
+    };
+
+    window.recentFiles = {
+        add: (name, handle, bytes) => withList("readwrite", (files) => toFront(files, { name, handle, bytes })),
+        list: async () => (await withList("readonly", () => null)).map((file) => file.name),
+        clear: () => withList("readwrite", () => []),
+        // The file's name and bytes, or null if it's gone or access to it was refused
+        open: async (index) => {
+            let opened = null;
+            await withList("readwrite", async (files) => {
+                const file = files[index];
+                if (!file) {
+                    return null;
+                }
+                if (file.handle) {
+                    if (await file.handle.requestPermission({ mode: "readwrite" }) !== "granted") {
+                        return null;
+                    }
+                    const contents = await file.handle.getFile();
+                    opened = [contents.name, new Uint8Array(await contents.arrayBuffer())];
+                    window.workbookFileHandle = contents.name.toLowerCase().endsWith(".rsheet")
+                        ? file.handle
+                        : null;
+                } else {
+                    opened = [file.name, file.bytes];
+                    window.workbookFileHandle = null;
+                }
+                return toFront(files, file);
+            });
+            return opened && [opened[0], Array.from(opened[1])];
+        },
+    };
+"#;
+
+const LIST_JS: &str = r#"
+    try {
+        return await window.recentFiles.list();
+    } catch {
+        return [];
+    }
+"#;
+
+const OPEN_RECENT_JS: &str = r#"
+    const index = await dioxus.recv();
+    try {
+        return await window.recentFiles.open(index);
+    } catch {
+        return null;
+    }
+"#;
+
+const REMEMBER_JS: &str = r#"
+    const [name, bytes] = await dioxus.recv();
+    try {
+        await window.recentFiles.add(name, null, new Uint8Array(bytes));
+    } catch {
+        // Storage isn't allowed
+    }
+"#;
+
+const CLEAR_JS: &str = r#"
+    try {
+        await window.recentFiles.clear();
+    } catch {
+        // Storage isn't allowed
+    }
+"#;
+
+/// Keeps a copy of a file opened without a handle to it, from the file input or dropped
+pub async fn remember_file(name: String, bytes: Vec<u8>) {
+    let eval = document::eval(REMEMBER_JS);
+    let _ = eval.send((name, bytes));
+    let _ = eval.await;
+}
+
+/// Toolbar button dropping down the files last opened or saved, to open again with a click
+#[component]
+pub fn RecentFilesMenu(grid: Signal<Grid>, dialog: Signal<Option<Dialog>>) -> Element {
+    let mut open = use_signal(|| false);
+    let mut names = use_signal(Vec::<String>::new);
+    use_hook(|| {
+        let _ = document::eval(RECENT_FILES_JS);
+    });
+
+    rsx! {
+        div {
+            class: "clean-data",
+
+            button {
+                "tooltip-text": "Recent files",
+                onclick: move |_| {
+                    open.toggle();
+                    if open() {
+                        spawn(async move {
+                            let listed = document::eval(LIST_JS).join::<Vec<String>>().await;
+                            names.set(listed.unwrap_or_default());
+                        });
+                    }
+                },
+                lucide_dioxus::History { size: 22 }
+            }
+            if open() {
+                div {
+                    class: "clean-data-dropdown",
+
+                    if names.read().is_empty() {
+                        span { class: "recent-files-empty", "No recent files" }
+                    }
+                    for (index, name) in names().into_iter().enumerate() {
+                        button {
+                            onclick: move |_| {
+                                open.set(false);
+                                spawn(async move {
+                                    let eval = document::eval(OPEN_RECENT_JS);
+                                    let _ = eval.send(index);
+                                    let opened = eval.join::<Option<(String, Vec<u8>)>>().await;
+                                    match opened {
+                                        Ok(Some((name, bytes))) => open_file(grid, dialog, &name, bytes).await,
+                                        _ => error!("The file couldn't be opened"),
+                                    }
+                                });
+                            },
+                            "{name}"
+                        }
+                    }
+                    if !names.read().is_empty() {
+                        button {
+                            onclick: move |_| {
+                                open.set(false);
+                                names.set(Vec::new());
+                                spawn(async move {
+                                    let _ = document::eval(CLEAR_JS).await;
+                                });
+                            },
+                            "Clear recent files"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
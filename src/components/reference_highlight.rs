@@ -0,0 +1,62 @@
+use dioxus::prelude::*;
+
+use crate::{
+    engine::editing,
+    model::grid::{cell_address_to_coords, Grid},
+};
+
+const REFERENCE_COLORS: usize = 6;
+
+/// Colored borders around the active sheet's cells referenced by the formula being edited
+#[component]
+pub fn ReferenceHighlights(grid: Signal<Grid>) -> Element {
+    let grid_read = grid.read();
+    if !grid_read.view.is_editing_cell && !grid_read.view.is_editing_formula_bar {
+        return rsx! {};
+    }
+
+    let content = grid_read.get_current_cell_content();
+    let mut addresses: Vec<(String, String)> = Vec::new();
+    let highlights = editing::find_references(&content)
+        .into_iter()
+        .filter_map(|span| {
+            if let Some(sheet) = &span.sheet {
+                let on_active_sheet = grid_read
+                    .workbook
+                    .get_sheet_by_name(sheet)
+                    .is_some_and(|sheet| sheet.id == grid_read.view.active_sheet);
+                if !on_active_sheet {
+                    return None;
+                }
+            }
+            let first = cell_address_to_coords(&span.first.replace('$', ""))?;
+            let last = cell_address_to_coords(&span.last.replace('$', ""))?;
+            // The same reference typed twice keeps its color
+            let key = (span.first.to_uppercase(), span.last.to_uppercase());
+            let color = match addresses.iter().position(|a| *a == key) {
+                Some(index) => index,
+                None => {
+                    addresses.push(key);
+                    addresses.len() - 1
+                }
+            } % REFERENCE_COLORS;
+
+            let first_rect = grid_read.get_cell_rect(first.min(last));
+            let last_rect = grid_read.get_cell_rect(first.max(last));
+            let left = first_rect.x.min(last_rect.x);
+            let top = first_rect.y.min(last_rect.y);
+            let right = (first_rect.x + first_rect.width).max(last_rect.x + last_rect.width);
+            let bottom = (first_rect.y + first_rect.height).max(last_rect.y + last_rect.height);
+            Some((left, top, right - left, bottom - top, color))
+        })
+        .collect::<Vec<_>>();
+
+    rsx! {
+        for (left, top, width, height, color) in highlights {
+            div {
+                class: "reference-highlight",
+                style: "left: {left}px; top: {top}px; width: {width}px; height: {height}px; border-color: var(--reference-color-{color});",
+            }
+        }
+    }
+}
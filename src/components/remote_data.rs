@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use dioxus::prelude::*;
+
+use crate::model::{command::GridCommand, grid::Grid};
+
+/// Fetches a URL's text, null if it can't be reached or the server refuses it
+const FETCH_JS: &str = r#"
+    const url = await dioxus.recv();
+    try {
+        const response = await fetch(url);
+        return response.ok ? await response.text() : null;
+    } catch {
+        return null;
+    }
+"#;
+
+/// A URL's text, None if it can't be reached or the server refuses it
+pub async fn fetch_text(url: String) -> Option<String> {
+    let eval = document::eval(FETCH_JS);
+    let _ = eval.send(url);
+    eval.join::<Option<String>>().await.ok().flatten()
+}
+
+/// Fetches the URLs formulas like IMPORTCSV wait on, handing each response to the grid to
+/// recalculate them with, and refreshes the ones on a schedule when it comes round
+pub fn use_remote_data(mut grid: Signal<Grid>) {
+    let mut fetching = use_signal(HashSet::<String>::new);
+    let pending = use_memo(move || grid.read().view.pending_urls());
+    use_effect(move || {
+        for url in pending() {
+            if !fetching.write().insert(url.clone()) {
+                continue;
+            }
+            spawn(async move {
+                let text = fetch_text(url.clone()).await;
+                fetching.write().remove(&url);
+                grid.write()
+                    .apply(GridCommand::ReceiveRemoteData { url, text });
+            });
+        }
+    });
+
+    // Minutes since launch, each scheduled source refreshed on multiples of its interval
+    use_future(move || async move {
+        let mut minutes = 0u32;
+        loop {
+            let _ = document::eval("await new Promise((resolve) => setTimeout(resolve, 60000));")
+                .await;
+            minutes += 1;
+            let due = grid
+                .peek()
+                .workbook
+                .refresh_intervals
+                .iter()
+                .filter(|(_, interval)| **interval > 0 && minutes.is_multiple_of(**interval))
+                .map(|(url, _)| url.clone())
+                .collect::<Vec<_>>();
+            for url in due {
+                grid.write().apply(GridCommand::RefreshData(Some(url)));
+            }
+        }
+    });
+}
@@ -0,0 +1,76 @@
+use std::rc::Rc;
+
+use dioxus::{core::spawn_forever, prelude::*};
+
+use crate::{
+    components::side_panel::Panel,
+    model::{
+        command::GridCommand,
+        grid::{Grid, SearchStep},
+    },
+};
+
+static SEARCH_CSS: Asset = asset!("/assets/search.css");
+
+#[component]
+pub fn SearchBar(
+    grid: Signal<Grid>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    side_panel: Signal<Option<Panel>>,
+) -> Element {
+    let search_query = grid.read().view.search_query.clone();
+    let match_count = grid.read().get_search_matches().len();
+
+    rsx! {
+        document::Stylesheet { href: SEARCH_CSS }
+        if let Some(query) = search_query {
+            div {
+                class: "search-bar",
+
+                input {
+                    class: "search-input",
+                    placeholder: "Find",
+                    value: "{query}",
+                    onmounted: move |elem| async move {
+                        let _ = elem.data().set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        grid.write().apply(GridCommand::SetSearchQuery(Some(evt.value())));
+                    },
+                    onkeydown: move |evt| {
+                        evt.stop_propagation();
+                        match evt.key() {
+                            Key::Enter => {
+                                evt.prevent_default();
+                                if evt.modifiers().shift() {
+                                    grid.write().apply(GridCommand::Search(SearchStep::Backward));
+                                } else {
+                                    grid.write().apply(GridCommand::Search(SearchStep::Forward));
+                                }
+                            }
+                            Key::Escape => {
+                                evt.prevent_default();
+                                grid.write().apply(GridCommand::SetSearchQuery(None));
+                                if let Some(container) = scroll_container() {
+                                    spawn_forever(async move {
+                                        let _ = container.set_focus(true).await;
+                                    });
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                span {
+                    class: "search-count",
+                    "{match_count} found"
+                }
+                button {
+                    "tooltip-text": "Find all",
+                    onclick: move |_| side_panel.set(Some(Panel::FindResults)),
+                    lucide_dioxus::List { size: 16 }
+                }
+            }
+        }
+    }
+}
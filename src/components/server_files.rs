@@ -0,0 +1,251 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::{
+        autosave::autosave_eval,
+        dialog::{close_dialog, Dialog},
+    },
+    engine::date,
+    model::{
+        command::GridCommand,
+        file::read_workbook,
+        grid::Grid,
+        presence::Collaboration,
+        server::{is_valid_workbook_name, ServerConnection, WorkbookEntry},
+    },
+};
+
+/// Sends a request to the server with the token, returning the response's status and text, or
+/// null if the server couldn't be reached
+const REQUEST_JS: &str = r#"
+    const [method, url, token, body] = await dioxus.recv();
+    try {
+        const response = await fetch(url, {
+            method,
+            headers: { Authorization: `Bearer ${token}` },
+            body: body ?? undefined,
+        });
+        return [response.status, await response.text()];
+    } catch {
+        return null;
+    }
+"#;
+
+const LOAD_CONNECTION_JS: &str = r#"
+    try {
+        return JSON.parse(await autosaved("load", "server"));
+    } catch {
+        return null;
+    }
+"#;
+
+const SAVE_CONNECTION_JS: &str = r#"
+    await autosaved("save", "server", JSON.stringify(await dioxus.recv()));
+"#;
+
+/// The response's text, or what went wrong
+async fn request(
+    connection: &ServerConnection,
+    method: &str,
+    name: Option<&str>,
+    body: Option<String>,
+) -> Result<String, String> {
+    let eval = document::eval(REQUEST_JS);
+    let _ = eval.send((method, connection.api_url(name), &connection.token, body));
+    match eval.join::<Option<(u16, String)>>().await {
+        Ok(Some((200..=299, text))) => Ok(text),
+        Ok(Some((401, _))) => Err("The server refused the token.".to_string()),
+        Ok(Some((404, _))) => Err("There's no workbook by that name on the server.".to_string()),
+        Ok(Some((503, _))) => Err("The server has no token set, so it keeps no workbooks.".to_string()),
+        Ok(Some((status, _))) => Err(format!("The server answered with an error ({}).", status)),
+        _ => Err("The server couldn't be reached.".to_string()),
+    }
+}
+
+/// Shares a stored workbook with whoever else opens it, keeping this session's id if it was
+/// already sharing
+fn collaborate(mut grid: Signal<Grid>, connection: ServerConnection, workbook: String) {
+    let id = grid
+        .peek()
+        .view
+        .collaboration
+        .as_ref()
+        .map(|collaboration| collaboration.id.clone())
+        .unwrap_or_else(|| format!("{:016x}", (js_sys::Math::random() * 1e16) as u64));
+    grid.write()
+        .apply(GridCommand::SetCollaboration(Some(Collaboration {
+            connection,
+            workbook,
+            id,
+        })));
+}
+
+/// Opens and saves workbooks kept on a server, from wherever the app is used
+#[component]
+pub fn ServerFilesDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut connection = use_signal(ServerConnection::default);
+    let mut workbooks = use_signal(|| None::<Vec<WorkbookEntry>>);
+    let mut name = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+    let mut busy = use_signal(|| false);
+
+    // Lists the workbooks, keeping the connection for next time once it works
+    let mut list = move || {
+        busy.set(true);
+        message.set(None);
+        spawn(async move {
+            let listed = request(&connection(), "GET", None, None)
+                .await
+                .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()));
+            match listed {
+                Ok(listed) => {
+                    workbooks.set(Some(listed));
+                    let eval = autosave_eval(SAVE_CONNECTION_JS);
+                    let _ = eval.send(connection());
+                }
+                Err(e) => message.set(Some(e)),
+            }
+            busy.set(false);
+        });
+    };
+    use_future(move || async move {
+        let saved = autosave_eval(LOAD_CONNECTION_JS)
+            .join::<Option<ServerConnection>>()
+            .await;
+        if let Ok(Some(saved)) = saved {
+            connection.set(saved);
+            list();
+        }
+    });
+
+    let mut open = move |opened: String| {
+        busy.set(true);
+        message.set(None);
+        spawn(async move {
+            let workbook = request(&connection(), "GET", Some(&opened), None)
+                .await
+                .and_then(|text| read_workbook(&text).map_err(|e| e.to_string()));
+            busy.set(false);
+            match workbook {
+                Ok(workbook) => {
+                    grid.write().apply(GridCommand::OpenWorkbook(workbook));
+                    collaborate(grid, connection(), opened);
+                    close_dialog(dialog, scroll_container);
+                }
+                Err(e) => message.set(Some(e)),
+            }
+        });
+    };
+    let save = move |_| {
+        busy.set(true);
+        message.set(None);
+        spawn(async move {
+            let text = grid.read().save_workbook();
+            let saved = request(&connection(), "PUT", Some(&name()), Some(text)).await;
+            busy.set(false);
+            match saved {
+                Ok(_) => {
+                    grid.write().apply(GridCommand::MarkSaved);
+                    collaborate(grid, connection(), name());
+                    close_dialog(dialog, scroll_container);
+                }
+                Err(e) => message.set(Some(e)),
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            class: "url-import",
+
+            div {
+                class: "dialog-message",
+                "Workbooks kept on a server open wherever the app is used. The server lets in requests with the token it's been given. Others with a workbook open see where you are in it under your name."
+            }
+            input {
+                placeholder: "Server address (blank for this site)",
+                value: "{connection().url}",
+                oninput: move |evt| connection.write().url = evt.value(),
+            }
+            input {
+                placeholder: "Your name",
+                value: "{connection().user_name}",
+                oninput: move |evt| connection.write().user_name = evt.value(),
+            }
+            div {
+                class: "server-files-row",
+                input {
+                    r#type: "password",
+                    placeholder: "Token",
+                    value: "{connection().token}",
+                    oninput: move |evt| connection.write().token = evt.value(),
+                    onkeydown: move |evt| {
+                        if evt.key() == Key::Enter {
+                            list();
+                        }
+                    },
+                }
+                button {
+                    disabled: busy(),
+                    onclick: move |_| list(),
+                    "Connect"
+                }
+            }
+            if let Some(workbooks) = workbooks() {
+                div {
+                    class: "server-files-list",
+                    if workbooks.is_empty() {
+                        div { class: "dialog-message", "No workbooks on the server yet." }
+                    }
+                    for workbook in workbooks {
+                        div {
+                            class: "server-files-row",
+                            span { "{workbook.name}" }
+                            span {
+                                class: "server-files-date",
+                                "{date::format_date(workbook.modified as i64 / 86_400)}"
+                            }
+                            button {
+                                disabled: busy(),
+                                onclick: {
+                                    let opened = workbook.name.clone();
+                                    move |_| open(opened.clone())
+                                },
+                                "Open"
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "server-files-row",
+                    input {
+                        placeholder: "Name to save the workbook under",
+                        value: "{name}",
+                        oninput: move |evt| name.set(evt.value()),
+                    }
+                    button {
+                        disabled: busy() || !is_valid_workbook_name(&name()),
+                        onclick: save,
+                        "Save"
+                    }
+                }
+            }
+            if let Some(message) = message() {
+                div { class: "dialog-message", "{message}" }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Close"
+            }
+        }
+    }
+}
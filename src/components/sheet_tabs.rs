@@ -0,0 +1,232 @@
+use std::rc::Rc;
+
+use dioxus::{core::spawn_forever, prelude::*};
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{command::GridCommand, grid::Grid, view::ZOOM_LEVELS, workbook::SheetId},
+};
+
+static SHEET_TABS_CSS: Asset = asset!("/assets/sheet_tabs.css");
+
+#[component]
+pub fn SheetTabs(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut renaming = use_signal(|| None::<SheetId>);
+    let mut dragged = use_signal(|| None::<SheetId>);
+
+    let active_sheet = grid.read().view.active_sheet;
+    let sheets = grid
+        .read()
+        .workbook
+        .sheets
+        .iter()
+        .map(|sheet| (sheet.id, sheet.name.clone()))
+        .collect::<Vec<_>>();
+    let read_only = grid.read().view.read_only;
+    let can_delete = sheets.len() > 1 && !read_only;
+    let zoom = grid.read().view.zoom;
+
+    rsx! {
+        document::Stylesheet { href: SHEET_TABS_CSS }
+        div {
+            class: "sheet-tabs",
+
+            for (index, (sheet, name)) in sheets.into_iter().enumerate() {
+                if renaming() == Some(sheet) {
+                    SheetNameInput { grid, scroll_container, renaming, sheet, name }
+                } else {
+                    div {
+                        class: if sheet == active_sheet { "sheet-tab sheet-tab-active" } else { "sheet-tab" },
+                        draggable: if read_only { "false" } else { "true" },
+                        onclick: move |_| {
+                            grid.write().apply(GridCommand::ActivateSheet(sheet));
+                            focus_grid(scroll_container);
+                        },
+                        ondoubleclick: move |_| {
+                            if !read_only {
+                                renaming.set(Some(sheet));
+                            }
+                        },
+                        ondragstart: move |_| dragged.set(Some(sheet)),
+                        ondragover: move |evt| evt.prevent_default(),
+                        ondrop: move |evt| {
+                            evt.prevent_default();
+                            if let Some(dropped) = dragged() {
+                                grid.write().apply(GridCommand::MoveSheet { sheet: dropped, to_index: index });
+                            }
+                            dragged.set(None);
+                        },
+                        ondragend: move |_| dragged.set(None),
+
+                        span { "{name}" }
+                        if can_delete {
+                            button {
+                                "tooltip-text": "Delete sheet",
+                                onclick: move |evt| {
+                                    evt.stop_propagation();
+                                    dialog.set(Some(Dialog::DeleteSheet(sheet)));
+                                },
+                                lucide_dioxus::X { size: 12 }
+                            }
+                        }
+                    }
+                }
+            }
+            if !read_only {
+                button {
+                    "tooltip-text": "Add sheet",
+                    onclick: move |_| {
+                        grid.write().apply(GridCommand::AddSheet);
+                        focus_grid(scroll_container);
+                    },
+                    lucide_dioxus::Plus { size: 16 }
+                }
+            }
+            select {
+                class: "zoom-select",
+                "tooltip-text": "Zoom",
+                value: "{zoom}",
+                onchange: move |evt| {
+                    if let Ok(zoom) = evt.value().parse() {
+                        grid.write().apply(GridCommand::SetZoom(zoom));
+                    }
+                    focus_grid(scroll_container);
+                },
+                for level in ZOOM_LEVELS {
+                    option { value: "{level}", "{level}%" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SheetNameInput(
+    grid: Signal<Grid>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    renaming: Signal<Option<SheetId>>,
+    sheet: SheetId,
+    name: String,
+) -> Element {
+    let mut value = use_signal(|| name.clone());
+    let is_valid = grid.read().workbook.is_valid_sheet_name(&value.read(), sheet);
+
+    let mut finish = move |commit: bool| {
+        if renaming() != Some(sheet) {
+            return;
+        }
+        if commit && is_valid {
+            grid.write().apply(GridCommand::RenameSheet {
+                sheet,
+                name: value(),
+            });
+        }
+        renaming.set(None);
+        focus_grid(scroll_container);
+    };
+
+    rsx! {
+        input {
+            class: if is_valid { "sheet-tab-input" } else { "sheet-tab-input sheet-tab-input-invalid" },
+            value: "{value}",
+            title: if is_valid { "" } else { "Sheet names must be unique and can't contain ' or !" },
+            onmounted: move |elem| async move {
+                let _ = elem.data().set_focus(true).await;
+            },
+            oninput: move |evt| value.set(evt.value()),
+            onkeydown: move |evt| {
+                evt.stop_propagation();
+                match evt.key() {
+                    Key::Enter => finish(true),
+                    Key::Escape => finish(false),
+                    _ => {}
+                }
+            },
+            onblur: move |_| finish(true),
+        }
+    }
+}
+
+/// Asks which way references to the sheet should be broken before deleting it
+#[component]
+pub fn DeleteSheetDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    sheet: SheetId,
+) -> Element {
+    let grid_read = grid.read();
+    let Some(name) = grid_read.workbook.get_sheet(sheet).map(|s| s.name.clone()) else {
+        return rsx! {};
+    };
+    let references = grid_read
+        .workbook
+        .get_inbound_references(sheet)
+        .into_iter()
+        .map(|key| grid_read.workbook.format_reference(key, sheet))
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div { class: "dialog-message", "Delete sheet \"{name}\"? Undo (Ctrl+Z) brings it back." }
+        if !references.is_empty() {
+            div {
+                class: "dialog-message",
+                "{references.len()} cell(s) on other sheets reference it:"
+            }
+            div {
+                class: "delete-sheet-references",
+                for reference in references.iter() {
+                    div { "{reference}" }
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            if references.is_empty() {
+                button {
+                    onclick: move |_| delete_sheet(grid, dialog, scroll_container, sheet, false),
+                    "Delete"
+                }
+            } else {
+                button {
+                    onclick: move |_| delete_sheet(grid, dialog, scroll_container, sheet, true),
+                    "Replace with values"
+                }
+                button {
+                    onclick: move |_| delete_sheet(grid, dialog, scroll_container, sheet, false),
+                    "Replace with #REF!"
+                }
+            }
+        }
+    }
+}
+
+fn delete_sheet(
+    mut grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    sheet: SheetId,
+    replace_with_values: bool,
+) {
+    grid.write().apply(GridCommand::DeleteSheet {
+        sheet,
+        replace_with_values,
+    });
+    close_dialog(dialog, scroll_container);
+}
+
+fn focus_grid(scroll_container: Signal<Option<Rc<MountedData>>>) {
+    if let Some(container) = scroll_container() {
+        spawn_forever(async move {
+            let _ = container.set_focus(true).await;
+        });
+    }
+}
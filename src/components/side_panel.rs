@@ -0,0 +1,497 @@
+use dioxus::prelude::*;
+
+use crate::{
+    components::{autosave::autosave_eval, dialog::Dialog, versions::VersionsPanel},
+    engine::{date, remote::RemoteData},
+    model::{
+        command::GridCommand,
+        comment::Comment,
+        data_source::REFRESH_INTERVALS,
+        grid::{coords_to_cell_address, Grid},
+        workbook::CellKey,
+    },
+};
+
+static SIDE_PANEL_CSS: Asset = asset!("/assets/side_panel.css");
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Panel {
+    Dependencies,
+    Watch,
+    Profiler,
+    FindResults,
+    DataSources,
+    Comments,
+    Versions,
+}
+
+impl Panel {
+    fn title(&self) -> &'static str {
+        match self {
+            Panel::Dependencies => "Dependencies",
+            Panel::Watch => "Watch",
+            Panel::Profiler => "Recalculation profiler",
+            Panel::FindResults => "Find all",
+            Panel::DataSources => "Data sources",
+            Panel::Comments => "Comments",
+            Panel::Versions => "Versions",
+        }
+    }
+}
+
+#[component]
+pub fn SidePanel(
+    grid: Signal<Grid>,
+    side_panel: Signal<Option<Panel>>,
+    dialog: Signal<Option<Dialog>>,
+) -> Element {
+    let Some(panel) = side_panel() else {
+        return rsx! {
+            document::Stylesheet { href: SIDE_PANEL_CSS }
+        };
+    };
+
+    rsx! {
+        document::Stylesheet { href: SIDE_PANEL_CSS }
+        div {
+            class: "side-panel",
+
+            div {
+                class: "side-panel-title",
+                span { "{panel.title()}" }
+                button {
+                    "tooltip-text": "Close",
+                    onclick: move |_| side_panel.set(None),
+                    lucide_dioxus::X { size: 16 }
+                }
+            }
+            {
+                match panel {
+                    Panel::Dependencies => rsx! { DependenciesPanel { grid } },
+                    Panel::Watch => rsx! { WatchPanel { grid } },
+                    Panel::Profiler => rsx! { ProfilerPanel { grid } },
+                    Panel::FindResults => rsx! { FindResultsPanel { grid } },
+                    Panel::DataSources => rsx! { DataSourcesPanel { grid } },
+                    Panel::Comments => rsx! { CommentsPanel { grid } },
+                    Panel::Versions => rsx! { VersionsPanel { grid, dialog } },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn DependenciesPanel(grid: Signal<Grid>) -> Element {
+    let grid_read = grid.read();
+    let workbook = &grid_read.workbook;
+    let key = grid_read.current_key();
+    let sections = [
+        ("Direct precedents", workbook.get_cell_precedents(key)),
+        ("All precedents", workbook.get_transitive_precedents(key)),
+        ("Direct dependents", workbook.get_cell_dependants(key)),
+        ("All dependents", workbook.get_transitive_dependants(key)),
+    ];
+
+    rsx! {
+        div {
+            class: "side-panel-subtitle",
+            "{grid_read.get_current_cell_address()}"
+        }
+        for (title, cells) in sections {
+            div {
+                class: "side-panel-section",
+                div { class: "side-panel-section-title", "{title}" }
+                if cells.is_empty() {
+                    div { class: "side-panel-empty", "None" }
+                }
+                for cell_key in cells {
+                    CellLink { grid, cell_key }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn WatchPanel(grid: Signal<Grid>) -> Element {
+    let mut address = use_signal(String::new);
+    let watched_cells = grid.read().view.watched_cells.clone();
+
+    rsx! {
+        div {
+            class: "side-panel-row",
+            input {
+                class: "side-panel-input",
+                placeholder: "Cell address",
+                value: "{address}",
+                oninput: move |evt| address.set(evt.value()),
+                onkeydown: move |evt| {
+                    evt.stop_propagation();
+                    if evt.key() == Key::Enter {
+                        let key = grid.read().resolve_address(&address.read());
+                        if let Some(key) = key {
+                            grid.write().apply(GridCommand::WatchCell(key));
+                            address.set(String::new());
+                        }
+                    }
+                }
+            }
+            button {
+                "tooltip-text": "Watch selected cell",
+                onclick: move |_| {
+                    let key = grid.read().current_key();
+                    grid.write().apply(GridCommand::WatchCell(key));
+                },
+                lucide_dioxus::Plus { size: 16 }
+            }
+        }
+        if watched_cells.is_empty() {
+            div { class: "side-panel-empty", "No watched cells" }
+        }
+        for cell_key in watched_cells {
+            {
+                let grid_read = grid.read();
+                let cell = grid_read.workbook.get_cell(cell_key);
+                let content = cell.map(|c| c.content.clone()).unwrap_or_default();
+                let value = cell.map(|c| c.display_value.clone()).unwrap_or_default();
+                rsx! {
+                    div {
+                        class: "watch-entry",
+                        CellLink { grid, cell_key }
+                        span { class: "watch-content", title: "{content}", "{content}" }
+                        span { class: "watch-value", "{value}" }
+                        button {
+                            "tooltip-text": "Stop watching",
+                            onclick: move |_| grid.write().apply(GridCommand::UnwatchCell(cell_key)),
+                            lucide_dioxus::X { size: 14 }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+const PROFILER_HOTSPOT_COUNT: usize = 20;
+
+#[component]
+fn ProfilerPanel(grid: Signal<Grid>) -> Element {
+    let is_recording = grid.read().view.profile.is_some();
+    let hotspots = grid.read().view.get_recalc_hotspots();
+
+    rsx! {
+        div {
+            class: "side-panel-row",
+            button {
+                "tooltip-text": if is_recording { "Stop recording" } else { "Start recording" },
+                onclick: move |_| grid.write().apply(GridCommand::SetProfiling(!is_recording)),
+                if is_recording {
+                    lucide_dioxus::Square { size: 16 }
+                } else {
+                    lucide_dioxus::Circle { size: 16 }
+                }
+            }
+            button {
+                "tooltip-text": "Reset",
+                onclick: move |_| grid.write().apply(GridCommand::ResetProfile),
+                lucide_dioxus::RotateCcw { size: 16 }
+            }
+            span {
+                if is_recording { "Recording" } else { "Not recording" }
+            }
+        }
+        if hotspots.is_empty() {
+            div { class: "side-panel-empty", "No recalculations recorded" }
+        } else {
+            div {
+                class: "profiler-entry side-panel-section-title",
+                span { "Cell" }
+                span { "Count" }
+                span { "Total ms" }
+                span { "Avg ms" }
+            }
+        }
+        for (cell_key, stats) in hotspots.into_iter().take(PROFILER_HOTSPOT_COUNT) {
+            div {
+                class: "profiler-entry",
+                CellLink { grid, cell_key }
+                span { "{stats.count}" }
+                span { "{stats.total_ms:.2}" }
+                span { "{stats.total_ms / stats.count as f64:.2}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn FindResultsPanel(grid: Signal<Grid>) -> Element {
+    // Starts from whatever the find bar is looking for, then lives on its own
+    let mut query = use_signal(|| grid.read().view.search_query.clone().unwrap_or_default());
+    let results = grid.read().find_all(&query.read());
+
+    rsx! {
+        div {
+            class: "side-panel-row",
+            input {
+                class: "side-panel-input",
+                placeholder: "Find",
+                value: "{query}",
+                oninput: move |evt| query.set(evt.value()),
+                onkeydown: move |evt| evt.stop_propagation(),
+            }
+        }
+        if !query.read().is_empty() {
+            div { class: "side-panel-subtitle", "{results.len()} found" }
+        }
+        for cell_key in results {
+            {
+                let grid_read = grid.read();
+                let cell = grid_read.workbook.get_cell(cell_key);
+                let content = cell.map(|c| c.content.clone()).unwrap_or_default();
+                let value = cell.map(|c| c.display_value.clone()).unwrap_or_default();
+                rsx! {
+                    div {
+                        class: "find-result",
+                        CellLink { grid, cell_key }
+                        span { class: "watch-content", title: "{content}", "{content}" }
+                        span { class: "watch-value", "{value}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The URLs formulas like IMPORTCSV fetch from, each with the cells it fills and how often
+/// it's refreshed
+#[component]
+fn DataSourcesPanel(grid: Signal<Grid>) -> Element {
+    let sources = grid.read().data_sources();
+    let no_sources = sources.is_empty();
+
+    rsx! {
+        div {
+            class: "side-panel-row",
+            button {
+                "tooltip-text": "Refresh all",
+                disabled: no_sources,
+                onclick: move |_| grid.write().apply(GridCommand::RefreshData(None)),
+                lucide_dioxus::RefreshCw { size: 16 }
+            }
+            span { "Refresh all" }
+        }
+        if no_sources {
+            div { class: "side-panel-empty", "No formulas fetch data, like IMPORTCSV or WEBSERVICE" }
+        }
+        for source in sources {
+            {
+                let status = match &source.data {
+                    None | Some(RemoteData::Pending) => "Loading…",
+                    Some(RemoteData::Loaded(_)) => "Loaded",
+                    Some(RemoteData::Refreshing(_)) => "Refreshing…",
+                    Some(RemoteData::Failed) => "Couldn't be fetched",
+                };
+                let url = source.url.clone();
+                let refresh_url = source.url.clone();
+                rsx! {
+                    div {
+                        class: "side-panel-section data-source",
+                        div {
+                            class: "side-panel-row",
+                            span { class: "watch-content", title: "{source.url}", "{source.url}" }
+                            button {
+                                "tooltip-text": "Refresh",
+                                onclick: move |_| {
+                                    grid.write().apply(GridCommand::RefreshData(Some(refresh_url.clone())));
+                                },
+                                lucide_dioxus::RefreshCw { size: 14 }
+                            }
+                        }
+                        div { class: "side-panel-subtitle", "{status}" }
+                        for (cell_key, range) in source.ranges {
+                            div {
+                                class: "side-panel-row",
+                                CellLink { grid, cell_key }
+                                if !range.is_single_cell() {
+                                    span { "to {coords_to_cell_address(range.bottom_right())}" }
+                                }
+                            }
+                        }
+                        select {
+                            class: "side-panel-input",
+                            value: source.refresh_interval.map(|minutes| minutes.to_string()).unwrap_or_default(),
+                            onchange: move |evt| {
+                                let minutes = evt.value().parse::<u32>().ok();
+                                grid.write().apply(GridCommand::SetRefreshInterval { url: url.clone(), minutes });
+                            },
+                            option { value: "", "Refresh when asked" }
+                            for minutes in REFRESH_INTERVALS {
+                                option {
+                                    value: minutes.to_string(),
+                                    if minutes < 60 {
+                                        "Every {minutes} min"
+                                    } else {
+                                        "Every {minutes / 60} h"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+const LOAD_AUTHOR_JS: &str = r#"return (await autosaved("load", "author")) ?? "";"#;
+
+const SAVE_AUTHOR_JS: &str = r#"await autosaved("save", "author", await dioxus.recv());"#;
+
+/// The selected cell's comments, to reply to, resolve or delete, and every sheet's open threads
+#[component]
+fn CommentsPanel(grid: Signal<Grid>) -> Element {
+    let mut author = use_signal(String::new);
+    let mut text = use_signal(String::new);
+    use_future(move || async move {
+        if let Ok(saved) = autosave_eval(LOAD_AUTHOR_JS).join::<String>().await {
+            // Collaborators already know this session by a name
+            let shared_name = grid
+                .peek()
+                .view
+                .collaboration
+                .as_ref()
+                .map(|collaboration| collaboration.connection.user_name.clone());
+            match saved.is_empty() {
+                true => author.set(shared_name.unwrap_or_default()),
+                false => author.set(saved),
+            }
+        }
+    });
+
+    let grid_read = grid.read();
+    let coords = grid_read.current_cell();
+    let thread = grid_read.sheet().comment_thread_at(coords).cloned();
+    let open_threads = grid_read
+        .get_open_comment_threads()
+        .into_iter()
+        .map(|(cell_key, thread)| {
+            let replies = match thread.comments.len() - 1 {
+                0 => String::new(),
+                1 => "1 reply".to_string(),
+                replies => format!("{replies} replies"),
+            };
+            (cell_key, thread.comments[0].text.clone(), replies)
+        })
+        .collect::<Vec<_>>();
+    let mut add_comment = move || {
+        let comment = Comment {
+            author: match author().trim() {
+                "" => "Anonymous".to_string(),
+                name => name.to_string(),
+            },
+            timestamp: date::now_seconds(),
+            text: text(),
+        };
+        grid.write().apply(GridCommand::AddComment { coords, comment });
+        text.set(String::new());
+        let eval = autosave_eval(SAVE_AUTHOR_JS);
+        let _ = eval.send(author());
+    };
+
+    rsx! {
+        div {
+            class: "side-panel-subtitle",
+            "{grid_read.get_current_cell_address()}"
+        }
+        if let Some(thread) = &thread {
+            for comment in thread.comments.iter() {
+                div {
+                    class: "comment",
+                    div {
+                        class: "side-panel-subtitle",
+                        "{comment.author} · {comment.written_at()}"
+                    }
+                    div { class: "comment-text", "{comment.text}" }
+                }
+            }
+            div {
+                class: "side-panel-row",
+                if thread.resolved {
+                    span { class: "side-panel-empty", "Resolved" }
+                    button {
+                        "tooltip-text": "Reopen",
+                        onclick: move |_| grid.write().apply(GridCommand::SetCommentResolved { coords, resolved: false }),
+                        lucide_dioxus::RotateCcw { size: 16 }
+                    }
+                } else {
+                    button {
+                        "tooltip-text": "Resolve",
+                        onclick: move |_| grid.write().apply(GridCommand::SetCommentResolved { coords, resolved: true }),
+                        lucide_dioxus::Check { size: 16 }
+                    }
+                }
+                button {
+                    "tooltip-text": "Delete thread",
+                    onclick: move |_| grid.write().apply(GridCommand::DeleteCommentThread(coords)),
+                    lucide_dioxus::Trash2 { size: 16 }
+                }
+            }
+        }
+        input {
+            class: "side-panel-input",
+            placeholder: "Your name",
+            value: "{author}",
+            oninput: move |evt| author.set(evt.value()),
+            onkeydown: move |evt| evt.stop_propagation(),
+        }
+        textarea {
+            class: "side-panel-input comment-input",
+            placeholder: if thread.is_some() { "Reply" } else { "Comment on this cell" },
+            value: "{text}",
+            oninput: move |evt| text.set(evt.value()),
+            onkeydown: move |evt| {
+                evt.stop_propagation();
+                // Enter sends, Shift+Enter starts a new line
+                if evt.key() == Key::Enter && !evt.modifiers().shift() {
+                    evt.prevent_default();
+                    add_comment();
+                }
+            },
+        }
+        div {
+            class: "side-panel-row",
+            button {
+                disabled: text.read().trim().is_empty(),
+                onclick: move |_| add_comment(),
+                if thread.is_some() { "Reply" } else { "Comment" }
+            }
+        }
+        div {
+            class: "side-panel-section",
+            div { class: "side-panel-section-title", "Open comments" }
+            if open_threads.is_empty() {
+                div { class: "side-panel-empty", "None" }
+            }
+            for (cell_key, first, replies) in open_threads {
+                div {
+                    class: "find-result",
+                    CellLink { grid, cell_key }
+                    span { class: "watch-content", title: "{first}", "{first}" }
+                    span { class: "watch-value", "{replies}" }
+                }
+            }
+        }
+    }
+}
+
+/// A cell's address, with its sheet if that isn't the active one. Clicking it goes there.
+#[component]
+pub fn CellLink(grid: Signal<Grid>, cell_key: CellKey) -> Element {
+    rsx! {
+        span {
+            class: "cell-link",
+            onclick: move |_| grid.write().apply(GridCommand::GoToCell(cell_key)),
+            "{grid.read().get_cell_reference(cell_key)}"
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    engine::solver::Relation,
+    model::{
+        command::GridCommand,
+        grid::{Coords, Grid},
+        solver::{SolverConstraint, SolverOutcome, SolverProblem},
+    },
+};
+
+/// A constraint as typed: cell, relation and bound
+type ConstraintRow = (String, Relation, String);
+
+#[component]
+pub fn SolverDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut objective = use_signal(|| grid.read().get_current_cell_address());
+    let mut maximize = use_signal(|| true);
+    let mut variables = use_signal(String::new);
+    let mut constraints = use_signal(Vec::<ConstraintRow>::new);
+    // Only this dialog's runs, not whatever an earlier one left
+    let mut outcome = use_signal(|| None);
+
+    // Addresses are on the active sheet. None while any of them can't be read.
+    let problem = move || -> Option<SolverProblem> {
+        let grid = grid.read();
+        let cell = |address: &str| -> Option<Coords> {
+            let key = grid.resolve_address(address)?;
+            (key.sheet == grid.view.active_sheet).then_some(key.coords)
+        };
+        let (sheet, variables) = grid.resolve_range(variables().trim())?;
+        if sheet != grid.view.active_sheet {
+            return None;
+        }
+        Some(SolverProblem {
+            objective: cell(&objective())?,
+            maximize: maximize(),
+            variables,
+            constraints: constraints()
+                .iter()
+                .map(|(address, relation, bound)| {
+                    Some(SolverConstraint {
+                        cell: cell(address)?,
+                        relation: *relation,
+                        bound: bound.trim().parse().ok()?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?,
+        })
+    };
+    let message = match outcome() {
+        Some(SolverOutcome::Solved { objective }) => Some(format!(
+            "Solved. The variable cells hold the solution, the objective is {}.",
+            objective
+        )),
+        Some(SolverOutcome::Infeasible) => {
+            Some("No values meet every constraint.".to_string())
+        }
+        Some(SolverOutcome::Unbounded) => {
+            Some("The objective keeps improving without limit. Add constraints to bound it.".to_string())
+        }
+        Some(SolverOutcome::NotLinear) => {
+            Some("The objective or a constraint isn't linear in the variable cells.".to_string())
+        }
+        Some(SolverOutcome::InvalidValue) => {
+            Some("The objective or a constraint cell doesn't hold a number.".to_string())
+        }
+        None => None,
+    };
+
+    rsx! {
+        div {
+            class: "solver",
+
+            div {
+                class: "solver-row",
+                label {
+                    "Objective cell"
+                    input {
+                        value: "{objective}",
+                        oninput: move |evt| objective.set(evt.value()),
+                    }
+                }
+                label {
+                    "Goal"
+                    select {
+                        value: if maximize() { "max" } else { "min" },
+                        onchange: move |evt| maximize.set(evt.value() == "max"),
+                        option { value: "max", "Maximize" }
+                        option { value: "min", "Minimize" }
+                    }
+                }
+            }
+            label {
+                "Variable cells"
+                input {
+                    placeholder: "A1:A5",
+                    value: "{variables}",
+                    oninput: move |evt| variables.set(evt.value()),
+                }
+            }
+            div { class: "dialog-message", "Constraints. Variable cells are kept at 0 or more." }
+            for (index, (address, relation, bound)) in constraints().into_iter().enumerate() {
+                div {
+                    class: "solver-row",
+                    input {
+                        class: "solver-cell",
+                        placeholder: "Cell",
+                        value: "{address}",
+                        oninput: move |evt| constraints.write()[index].0 = evt.value(),
+                    }
+                    select {
+                        value: match relation {
+                            Relation::AtMost => "at-most",
+                            Relation::AtLeast => "at-least",
+                            Relation::EqualTo => "equal",
+                        },
+                        onchange: move |evt| {
+                            constraints.write()[index].1 = match evt.value().as_str() {
+                                "at-least" => Relation::AtLeast,
+                                "equal" => Relation::EqualTo,
+                                _ => Relation::AtMost,
+                            };
+                        },
+                        option { value: "at-most", "<=" }
+                        option { value: "at-least", ">=" }
+                        option { value: "equal", "=" }
+                    }
+                    input {
+                        class: "solver-bound",
+                        r#type: "number",
+                        value: "{bound}",
+                        oninput: move |evt| constraints.write()[index].2 = evt.value(),
+                    }
+                    button {
+                        "tooltip-text": "Remove",
+                        onclick: move |_| {
+                            constraints.write().remove(index);
+                        },
+                        lucide_dioxus::X { size: 14 }
+                    }
+                }
+            }
+            button {
+                class: "solver-add",
+                onclick: move |_| constraints.write().push((String::new(), Relation::AtMost, String::new())),
+                "Add constraint"
+            }
+            if let Some(message) = message {
+                div { class: "dialog-message", "{message}" }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Close"
+            }
+            button {
+                disabled: problem().is_none(),
+                onclick: move |_| {
+                    if let Some(problem) = problem() {
+                        grid.write().apply(GridCommand::Solve(problem));
+                        outcome.set(grid.read().view.solver_outcome.clone());
+                    }
+                },
+                "Solve"
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        grid::{coords_to_cell_address, Coords, Grid},
+    },
+};
+
+#[component]
+pub fn DescriptiveStatisticsDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut input = use_signal(|| grid.read().get_selection_address());
+    // Past the selection, leaving a column between
+    let mut output = use_signal(|| {
+        let range = grid.read().view.selection().active_range();
+        coords_to_cell_address(Coords {
+            row: range.top_left().row,
+            column: range.bottom_right().column + 2,
+        })
+    });
+    let mut labels = use_signal(|| true);
+
+    // None while either address can't be read on the active sheet
+    let command = move || -> Option<GridCommand> {
+        let grid = grid.read();
+        let (sheet, input) = grid.resolve_range(input().trim())?;
+        let output = grid.resolve_address(&output())?;
+        (sheet == grid.view.active_sheet && output.sheet == sheet).then_some(
+            GridCommand::DescribeStatistics {
+                input,
+                output: output.coords,
+                labels: labels(),
+            },
+        )
+    };
+
+    rsx! {
+        div {
+            class: "analysis",
+
+            label {
+                "Input range"
+                input {
+                    value: "{input}",
+                    oninput: move |evt| input.set(evt.value()),
+                }
+            }
+            label {
+                class: "analysis-check",
+                input {
+                    r#type: "checkbox",
+                    checked: labels(),
+                    onchange: move |evt| labels.set(evt.checked()),
+                }
+                "Labels in first row"
+            }
+            label {
+                "Output to"
+                input {
+                    value: "{output}",
+                    oninput: move |evt| output.set(evt.value()),
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: command().is_none(),
+                onclick: move |_| {
+                    if let Some(command) = command() {
+                        grid.write().apply(command);
+                        close_dialog(dialog, scroll_container);
+                    }
+                },
+                "OK"
+            }
+        }
+    }
+}
+
+#[component]
+pub fn HistogramDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let mut input = use_signal(|| grid.read().get_selection_address());
+    let mut output = use_signal(|| {
+        let range = grid.read().view.selection().active_range();
+        coords_to_cell_address(Coords {
+            row: range.top_left().row,
+            column: range.bottom_right().column + 2,
+        })
+    });
+    let mut bin_width = use_signal(String::new);
+
+    // None while an address or the width can't be read. A blank width is picked.
+    let command = move || -> Option<GridCommand> {
+        let grid = grid.read();
+        let (sheet, input) = grid.resolve_range(input().trim())?;
+        let output = grid.resolve_address(&output())?;
+        let bin_width = match bin_width().trim() {
+            "" => None,
+            width => Some(width.parse::<f64>().ok().filter(|width| *width > 0.0)?),
+        };
+        (sheet == grid.view.active_sheet && output.sheet == sheet).then_some(
+            GridCommand::Histogram {
+                input,
+                output: output.coords,
+                bin_width,
+            },
+        )
+    };
+
+    rsx! {
+        div {
+            class: "analysis",
+
+            label {
+                "Input range"
+                input {
+                    value: "{input}",
+                    oninput: move |evt| input.set(evt.value()),
+                }
+            }
+            label {
+                "Bin width"
+                input {
+                    r#type: "number",
+                    placeholder: "Automatic",
+                    value: "{bin_width}",
+                    oninput: move |evt| bin_width.set(evt.value()),
+                }
+            }
+            label {
+                "Output to"
+                input {
+                    value: "{output}",
+                    oninput: move |evt| output.set(evt.value()),
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: command().is_none(),
+                onclick: move |_| {
+                    if let Some(command) = command() {
+                        grid.write().apply(command);
+                        close_dialog(dialog, scroll_container);
+                    }
+                },
+                "OK"
+            }
+        }
+    }
+}
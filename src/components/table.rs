@@ -0,0 +1,91 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::dialog::{close_dialog, Dialog},
+    model::{
+        command::GridCommand,
+        grid::Grid,
+        table::{TableStyle, TABLE_STYLES},
+    },
+};
+
+#[component]
+pub fn TableDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    let table = {
+        let grid = grid.read();
+        let sheet = grid.sheet();
+        sheet
+            .table_at(grid.current_cell())
+            .map(|index| sheet.tables[index].clone())
+    };
+
+    let Some(table) = table else {
+        // Outside a table the styles make one
+        return rsx! {
+            div { class: "dialog-message", "The first row of the selection becomes the header" }
+            TableStylePicker {
+                current: None,
+                onpick: move |style| {
+                    grid.write().apply(GridCommand::CreateTable(style));
+                    close_dialog(dialog, scroll_container);
+                },
+            }
+        };
+    };
+
+    rsx! {
+        TableStylePicker {
+            current: Some(table.style),
+            onpick: move |style| grid.write().apply(GridCommand::SetTableStyle(style)),
+        }
+        label {
+            class: "table-totals",
+            input {
+                r#type: "checkbox",
+                checked: table.totals_row,
+                onchange: move |_| grid.write().apply(GridCommand::ToggleTotalsRow),
+            }
+            "Totals row"
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| {
+                    grid.write().apply(GridCommand::RemoveTable);
+                    close_dialog(dialog, scroll_container);
+                },
+                "Convert to range"
+            }
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Done"
+            }
+        }
+    }
+}
+
+#[component]
+fn TableStylePicker(current: Option<TableStyle>, onpick: EventHandler<TableStyle>) -> Element {
+    rsx! {
+        div {
+            class: "table-styles",
+            for style in TABLE_STYLES {
+                button {
+                    class: if current == Some(style) { "table-style table-style-current" } else { "table-style" },
+                    "tooltip-text": "{style:?}",
+                    onclick: move |_| onpick.call(style),
+                    div { class: "table-style-header", style: "background-color: {style.accent()};" }
+                    div { class: "table-style-band" }
+                    div { class: "table-style-band", style: "background-color: {style.band()};" }
+                    div { class: "table-style-band" }
+                }
+            }
+        }
+    }
+}
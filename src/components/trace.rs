@@ -0,0 +1,58 @@
+use dioxus::prelude::*;
+
+use crate::model::grid::Grid;
+
+#[component]
+pub fn TraceArrows(grid: Signal<Grid>) -> Element {
+    let grid_read = grid.read();
+    let sheet = grid_read.sheet();
+    let view = &grid_read.view;
+    let width = view.base_header_column_width + sheet.column_widths.iter().sum::<i32>();
+    let height = view.base_header_row_height + sheet.row_heights.iter().sum::<i32>();
+    // Only arrows between cells of the active sheet, cross-sheet ones have nowhere to point
+    let arrows = view
+        .trace_arrows
+        .iter()
+        .filter(|(from, to)| from.sheet == sheet.id && to.sheet == sheet.id)
+        .map(|(from, to)| {
+            let (x1, y1) = grid_read.get_cell_rect(from.coords).center();
+            let (x2, y2) = grid_read.get_cell_rect(to.coords).center();
+            (x1, y1, x2, y2)
+        })
+        .collect::<Vec<_>>();
+
+    if arrows.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        svg {
+            class: "trace-arrows",
+            width: "{width}",
+            height: "{height}",
+
+            defs {
+                marker {
+                    id: "trace-arrowhead",
+                    view_box: "0 0 10 10",
+                    ref_x: "10",
+                    ref_y: "5",
+                    marker_width: "8",
+                    marker_height: "8",
+                    orient: "auto-start-reverse",
+                    path { d: "M 0 0 L 10 5 L 0 10 z" }
+                }
+            }
+            for (x1, y1, x2, y2) in arrows {
+                circle { cx: "{x1}", cy: "{y1}", r: "3" }
+                line {
+                    x1: "{x1}",
+                    y1: "{y1}",
+                    x2: "{x2}",
+                    y2: "{y2}",
+                    marker_end: "url(#trace-arrowhead)",
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::rc::Rc;
+
+use dioxus::{html::geometry::ClientPoint, prelude::*};
+
+use crate::{
+    components::{
+        dialog::{close_dialog, Dialog},
+        grid::grid_point,
+    },
+    engine::date::{self, DateFormat},
+    model::{
+        command::GridCommand,
+        grid::{Coords, Grid},
+        validation::{AlertStyle, ValidationRule},
+    },
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum RuleKind {
+    List,
+    Number,
+    Date,
+    Formula,
+}
+
+#[component]
+pub fn ValidationDialog(
+    grid: Signal<Grid>,
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+) -> Element {
+    // Starts from the rule the current cell already has, if any
+    let existing = use_signal(|| {
+        let grid = grid.read();
+        grid.sheet().validation_at(grid.current_cell()).cloned()
+    });
+    let rule = existing().map(|validation| validation.rule);
+    let bound = |bound: Option<f64>| bound.map(|bound| bound.to_string()).unwrap_or_default();
+    let date_bound = |bound: Option<f64>| {
+        bound
+            .and_then(|serial| DateFormat::Iso.apply(&serial.to_string()))
+            .unwrap_or_default()
+    };
+    let mut kind = use_signal(|| match &rule {
+        Some(ValidationRule::Number { .. }) => RuleKind::Number,
+        Some(ValidationRule::Date { .. }) => RuleKind::Date,
+        Some(ValidationRule::Formula(_)) => RuleKind::Formula,
+        _ => RuleKind::List,
+    });
+    let mut list = use_signal(|| match &rule {
+        Some(ValidationRule::List(values)) => values.join(", "),
+        _ => String::new(),
+    });
+    let mut low = use_signal(|| match &rule {
+        Some(ValidationRule::Number { min, .. }) => bound(*min),
+        Some(ValidationRule::Date { start, .. }) => date_bound(*start),
+        _ => String::new(),
+    });
+    let mut high = use_signal(|| match &rule {
+        Some(ValidationRule::Number { max, .. }) => bound(*max),
+        Some(ValidationRule::Date { end, .. }) => date_bound(*end),
+        _ => String::new(),
+    });
+    let mut formula = use_signal(|| match &rule {
+        Some(ValidationRule::Formula(formula)) => formula.clone(),
+        _ => "=".to_string(),
+    });
+    let mut alert = use_signal(|| {
+        existing()
+            .map(|validation| validation.alert)
+            .unwrap_or(AlertStyle::Stop)
+    });
+    let mut error_message = use_signal(|| {
+        existing()
+            .and_then(|validation| validation.error_message)
+            .unwrap_or_default()
+    });
+    let mut prompt = use_signal(|| {
+        existing()
+            .and_then(|validation| validation.prompt)
+            .unwrap_or_default()
+    });
+
+    let range = grid.read().get_selection_address();
+    // None while a bound can't be read. Empty bounds leave that side open.
+    let rule = move || -> Option<ValidationRule> {
+        let read = |text: String, parse: fn(&str) -> Option<f64>| match text.trim() {
+            "" => Some(None),
+            text => parse(text).map(Some),
+        };
+        let number = |text: &str| text.parse::<f64>().ok();
+        Some(match kind() {
+            RuleKind::List => ValidationRule::List(
+                list()
+                    .split(',')
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect(),
+            ),
+            RuleKind::Number => ValidationRule::Number {
+                min: read(low(), number)?,
+                max: read(high(), number)?,
+            },
+            RuleKind::Date => ValidationRule::Date {
+                start: read(low(), date::serial_of)?,
+                end: read(high(), date::serial_of)?,
+            },
+            RuleKind::Formula => ValidationRule::Formula(formula()),
+        })
+    };
+    let bound_type = if kind() == RuleKind::Date { "date" } else { "number" };
+
+    rsx! {
+        div {
+            class: "validation",
+
+            div { class: "dialog-message", "Applies to {range}" }
+            select {
+                value: match kind() {
+                    RuleKind::List => "list",
+                    RuleKind::Number => "number",
+                    RuleKind::Date => "date",
+                    RuleKind::Formula => "formula",
+                },
+                onchange: move |evt| {
+                    kind.set(match evt.value().as_str() {
+                        "number" => RuleKind::Number,
+                        "date" => RuleKind::Date,
+                        "formula" => RuleKind::Formula,
+                        _ => RuleKind::List,
+                    });
+                    low.set(String::new());
+                    high.set(String::new());
+                },
+                option { value: "list", "List of values" }
+                option { value: "number", "Number between" }
+                option { value: "date", "Date between" }
+                option { value: "formula", "Custom formula" }
+            }
+            match kind() {
+                RuleKind::List => rsx! {
+                    input {
+                        placeholder: "Values, separated by commas",
+                        value: "{list}",
+                        oninput: move |evt| list.set(evt.value()),
+                    }
+                },
+                RuleKind::Number | RuleKind::Date => rsx! {
+                    div {
+                        class: "validation-bounds",
+                        label {
+                            "From"
+                            input {
+                                r#type: bound_type,
+                                value: "{low}",
+                                oninput: move |evt| low.set(evt.value()),
+                            }
+                        }
+                        label {
+                            "To"
+                            input {
+                                r#type: bound_type,
+                                value: "{high}",
+                                oninput: move |evt| high.set(evt.value()),
+                            }
+                        }
+                    }
+                },
+                RuleKind::Formula => rsx! {
+                    input {
+                        value: "{formula}",
+                        oninput: move |evt| formula.set(evt.value()),
+                    }
+                    div {
+                        class: "dialog-message",
+                        "Entries pass when the formula isn't 0. It's written for the first cell of the range."
+                    }
+                },
+            }
+            label {
+                "Prompt when a cell is selected"
+                input {
+                    placeholder: "None",
+                    value: "{prompt}",
+                    oninput: move |evt| prompt.set(evt.value()),
+                }
+            }
+            label {
+                "On invalid entries"
+                select {
+                    value: match alert() {
+                        AlertStyle::Stop => "stop",
+                        AlertStyle::Warning => "warning",
+                        AlertStyle::Information => "information",
+                    },
+                    onchange: move |evt| {
+                        alert.set(match evt.value().as_str() {
+                            "warning" => AlertStyle::Warning,
+                            "information" => AlertStyle::Information,
+                            _ => AlertStyle::Stop,
+                        });
+                    },
+                    option { value: "stop", "Stop: reject the entry" }
+                    option { value: "warning", "Warning: keep it unless undone" }
+                    option { value: "information", "Information: keep it" }
+                }
+            }
+            label {
+                "Error message"
+                input {
+                    placeholder: "That value isn't allowed in …",
+                    value: "{error_message}",
+                    oninput: move |evt| error_message.set(evt.value()),
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                "tooltip-text": "Remove the rules covering the selection",
+                onclick: move |_| {
+                    grid.write().apply(GridCommand::ClearValidation);
+                    close_dialog(dialog, scroll_container);
+                },
+                "Clear"
+            }
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Cancel"
+            }
+            button {
+                disabled: rule().is_none(),
+                onclick: move |_| {
+                    if let Some(rule) = rule() {
+                        // Blank messages leave the default
+                        let message = |text: String| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+                        grid.write().apply(GridCommand::SetValidation {
+                            rule,
+                            alert: alert(),
+                            error_message: message(error_message()),
+                            prompt: message(prompt()),
+                        });
+                        close_dialog(dialog, scroll_container);
+                    }
+                },
+                "Apply"
+            }
+        }
+    }
+}
+
+/// The dropdown arrow beside a list-validated current cell
+#[component]
+pub fn ValidationButton(
+    grid: Signal<Grid>,
+    list_menu: Signal<Option<(Coords, ClientPoint)>>,
+    coords: Coords,
+) -> Element {
+    rsx! {
+        button {
+            class: "validation-button",
+            onmousedown: move |evt| evt.stop_propagation(),
+            ondoubleclick: move |evt| evt.stop_propagation(),
+            onclick: move |evt| list_menu.set(Some((coords, grid_point(&grid.read(), evt.client_coordinates())))),
+            lucide_dioxus::ChevronDown { size: 12 }
+        }
+    }
+}
+
+/// Picks one of a list rule's values for its cell
+#[component]
+pub fn ValidationList(
+    grid: Signal<Grid>,
+    list_menu: Signal<Option<(Coords, ClientPoint)>>,
+    coords: Coords,
+    point: ClientPoint,
+) -> Element {
+    let values = match grid.read().sheet().validation_at(coords).map(|v| &v.rule) {
+        Some(ValidationRule::List(values)) => values.clone(),
+        _ => Vec::new(),
+    };
+
+    rsx! {
+        div {
+            class: "context-menu-backdrop",
+            onmousedown: move |_| list_menu.set(None),
+        }
+        div {
+            class: "context-menu validation-list",
+            style: "left: {point.x}px; top: {point.y}px;",
+            for value in values {
+                button {
+                    onclick: {
+                        let value = value.clone();
+                        move |_| {
+                            grid.write().apply(GridCommand::SetCellContent { coords, content: value.clone() });
+                            list_menu.set(None);
+                        }
+                    },
+                    "{value}"
+                }
+            }
+        }
+    }
+}
+
+/// Tells about an entry that broke its cell's rule. A warned entry can be taken back.
+#[component]
+pub fn ValidationAlertBanner(grid: Signal<Grid>) -> Element {
+    let Some(alert) = grid.read().view.validation_alert.clone() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "cycle-banner validation-alert",
+
+            match alert.style {
+                AlertStyle::Stop => rsx! { lucide_dioxus::Ban { size: 14 } },
+                AlertStyle::Warning => rsx! { lucide_dioxus::TriangleAlert { size: 14 } },
+                AlertStyle::Information => rsx! { lucide_dioxus::Info { size: 14 } },
+            }
+            span { "{alert.message}" }
+            if alert.style == AlertStyle::Warning {
+                button {
+                    onclick: move |_| grid.write().apply(GridCommand::TakeBackEntry),
+                    "Undo entry"
+                }
+                button {
+                    onclick: move |_| grid.write().apply(GridCommand::DismissValidationAlert),
+                    "Keep"
+                }
+            } else {
+                button {
+                    "tooltip-text": "Dismiss",
+                    onclick: move |_| grid.write().apply(GridCommand::DismissValidationAlert),
+                    lucide_dioxus::X { size: 14 }
+                }
+            }
+        }
+    }
+}
+
+/// The prompt of the current cell's rule, under the cell
+#[component]
+pub fn ValidationPrompt(grid: Signal<Grid>) -> Element {
+    let grid_read = grid.read();
+    let current = grid_read.current_cell();
+    let prompt = grid_read
+        .sheet()
+        .validation_at(current)
+        .and_then(|validation| validation.prompt.clone());
+    let Some(prompt) = prompt else {
+        return rsx! {};
+    };
+    let rect = grid_read.get_cell_rect(current);
+    let (left, top) = (rect.x + rect.width / 2, rect.y + rect.height + 4);
+
+    rsx! {
+        div {
+            class: "validation-prompt",
+            style: "left: {left}px; top: {top}px;",
+            "{prompt}"
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    components::{
+        autosave::autosave_eval,
+        dialog::{close_dialog, Dialog},
+    },
+    engine::date,
+    model::{
+        command::GridCommand,
+        file::{read_workbook, WorkbookFile},
+        grid::Grid,
+        snapshot::{diff_workbooks, CellChange, ChangeKind, Snapshot},
+    },
+};
+
+const LOAD_SNAPSHOTS_JS: &str = r#"
+    try {
+        return JSON.parse(await autosaved("load", "snapshots")) ?? [];
+    } catch {
+        return [];
+    }
+"#;
+
+const SAVE_SNAPSHOTS_JS: &str = r#"
+    await autosaved("save", "snapshots", JSON.stringify(await dioxus.recv()));
+"#;
+
+/// Compared against the snapshots, the workbook as it is now
+const CURRENT: usize = usize::MAX;
+
+/// Snapshots kept in the browser, latest first
+async fn load_snapshots() -> Vec<Snapshot> {
+    autosave_eval(LOAD_SNAPSHOTS_JS)
+        .join::<Vec<Snapshot>>()
+        .await
+        .unwrap_or_default()
+}
+
+fn save_snapshots(snapshots: Vec<Snapshot>) {
+    let eval = autosave_eval(SAVE_SNAPSHOTS_JS);
+    let _ = eval.send(snapshots);
+}
+
+/// Named snapshots of the workbook kept in the browser, to compare two of, or go back to one
+#[component]
+pub fn VersionsPanel(grid: Signal<Grid>, dialog: Signal<Option<Dialog>>) -> Element {
+    let mut snapshots = use_signal(Vec::<Snapshot>::new);
+    let mut name = use_signal(String::new);
+    let mut from = use_signal(|| 0usize);
+    let mut to = use_signal(|| CURRENT);
+    use_future(move || async move {
+        snapshots.set(load_snapshots().await);
+    });
+
+    let mut take_snapshot = move || {
+        let snapshot = Snapshot {
+            name: match name().trim() {
+                "" => format!("Version {}", snapshots.read().len() + 1),
+                name => name.to_string(),
+            },
+            timestamp: date::now_seconds(),
+            workbook: grid.read().save_workbook(),
+        };
+        snapshots.write().insert(0, snapshot);
+        save_snapshots(snapshots());
+        name.set(String::new());
+        // The new one moves the others down
+        if from() != CURRENT {
+            from += 1;
+        }
+        if to() != CURRENT {
+            to += 1;
+        }
+    };
+    let version = move |index: usize| -> Option<WorkbookFile> {
+        match index {
+            CURRENT => Some(grid.read().workbook_file()),
+            index => read_workbook(&snapshots.read().get(index)?.workbook).ok(),
+        }
+    };
+    let compare = move |_| {
+        let (Some(old), Some(new)) = (version(from()), version(to())) else {
+            return;
+        };
+        let changes = diff_workbooks(&old, &new);
+        dialog.set(Some(Dialog::VersionDiff(changes.into())));
+    };
+
+    rsx! {
+        div {
+            class: "side-panel-row",
+            input {
+                class: "side-panel-input",
+                placeholder: "Snapshot name",
+                value: "{name}",
+                oninput: move |evt| name.set(evt.value()),
+                onkeydown: move |evt| {
+                    evt.stop_propagation();
+                    if evt.key() == Key::Enter {
+                        take_snapshot();
+                    }
+                },
+            }
+            button {
+                "tooltip-text": "Take a snapshot",
+                onclick: move |_| take_snapshot(),
+                lucide_dioxus::Camera { size: 16 }
+            }
+        }
+        if snapshots.read().is_empty() {
+            div { class: "side-panel-empty", "No snapshots yet" }
+        }
+        for (index, snapshot) in snapshots().into_iter().enumerate() {
+            div {
+                class: "side-panel-section data-source",
+                div {
+                    class: "side-panel-row",
+                    span { class: "watch-content", title: "{snapshot.name}", "{snapshot.name}" }
+                    button {
+                        "tooltip-text": "Restore",
+                        onclick: move |_| {
+                            let file = snapshots.read().get(index).and_then(|snapshot| read_workbook(&snapshot.workbook).ok());
+                            if let Some(file) = file {
+                                grid.write().apply(GridCommand::RestoreVersion(file));
+                            }
+                        },
+                        lucide_dioxus::RotateCcw { size: 14 }
+                    }
+                    button {
+                        "tooltip-text": "Delete",
+                        onclick: move |_| {
+                            snapshots.write().remove(index);
+                            save_snapshots(snapshots());
+                            from.set(0);
+                            to.set(CURRENT);
+                        },
+                        lucide_dioxus::Trash2 { size: 14 }
+                    }
+                }
+                div { class: "side-panel-subtitle", "{date::format_timestamp(snapshot.timestamp)}" }
+            }
+        }
+        if !snapshots.read().is_empty() {
+            div {
+                class: "side-panel-section",
+                div { class: "side-panel-section-title", "Compare" }
+                VersionSelect { snapshots, selected: from }
+                VersionSelect { snapshots, selected: to }
+                button {
+                    disabled: from() == to(),
+                    onclick: compare,
+                    "Show changes"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn VersionSelect(snapshots: Signal<Vec<Snapshot>>, selected: Signal<usize>) -> Element {
+    rsx! {
+        select {
+            class: "side-panel-input",
+            onchange: move |evt| {
+                if let Ok(index) = evt.value().parse() {
+                    selected.set(index);
+                }
+            },
+            option { value: "{CURRENT}", selected: selected() == CURRENT, "Current workbook" }
+            for (index, snapshot) in snapshots.read().iter().enumerate() {
+                option {
+                    value: "{index}",
+                    selected: selected() == index,
+                    "{snapshot.name}"
+                }
+            }
+        }
+    }
+}
+
+/// The cells that differ between two versions, added, removed and changed ones set apart
+#[component]
+pub fn VersionDiffDialog(
+    dialog: Signal<Option<Dialog>>,
+    scroll_container: Signal<Option<Rc<MountedData>>>,
+    changes: Rc<[CellChange]>,
+) -> Element {
+    let summary = match changes.len() {
+        0 => "No cells differ.".to_string(),
+        1 => "1 cell differs.".to_string(),
+        count => format!("{count} cells differ."),
+    };
+
+    rsx! {
+        div {
+            class: "version-diff",
+            div { class: "dialog-message", "{summary}" }
+            div {
+                class: "version-diff-list",
+                for change in changes.iter() {
+                    div {
+                        class: match change.kind() {
+                            ChangeKind::Added => "version-diff-row version-diff-added",
+                            ChangeKind::Removed => "version-diff-row version-diff-removed",
+                            ChangeKind::Changed => "version-diff-row version-diff-changed",
+                        },
+                        span { class: "version-diff-address", "{change.sheet}!{change.address()}" }
+                        span { class: "version-diff-old", title: "{change.old}", "{change.old}" }
+                        span { class: "version-diff-new", title: "{change.new}", "{change.new}" }
+                    }
+                }
+            }
+        }
+        div {
+            class: "dialog-buttons",
+            button {
+                onclick: move |_| close_dialog(dialog, scroll_container),
+                "Close"
+            }
+        }
+    }
+}
@@ -1 +1,18 @@
+pub mod chart;
+pub mod cleaning;
+pub mod csv;
+pub mod date;
+pub mod editing;
+pub mod fill;
+pub mod flash_fill;
+pub mod json;
+pub mod number_format;
+pub mod ods;
 pub mod parser;
+pub mod query;
+pub mod recalc;
+pub mod remote;
+pub mod solver;
+pub mod sort;
+pub mod statistics;
+pub mod zip;
@@ -0,0 +1,126 @@
+use crate::engine::statistics::round_width;
+
+/// About how many gridlines a value axis gets
+const TICKS: f64 = 5.0;
+
+/// A value axis spanning round numbers from `min` to `max`, a gridline at each tick
+#[derive(Clone, Debug, PartialEq)]
+pub struct Axis {
+    pub min: f64,
+    pub max: f64,
+    pub ticks: Vec<f64>,
+}
+
+impl Axis {
+    /// An axis taking in every value between `low` and `high`, widened around a single value
+    pub fn new(low: f64, high: f64) -> Self {
+        let (low, high) = match high - low {
+            spread if spread > 0.0 => (low, high),
+            _ if low == 0.0 => (0.0, 1.0),
+            _ => (low - low.abs() / 2.0, high + high.abs() / 2.0),
+        };
+        let step = round_width((high - low) / TICKS);
+        let min = (low / step).floor() * step;
+        let max = (high / step).ceil() * step;
+        let count = ((max - min) / step).round() as usize;
+        Axis {
+            min,
+            max,
+            // Multiplying rather than adding up keeps ticks like 0.3 from drifting
+            ticks: (0..=count).map(|i| min + i as f64 * step).collect(),
+        }
+    }
+
+    /// The axis with its ends moved to `min` or `max` where they're given, ticks staying on
+    /// round steps between them. Ends that cross are left as they were.
+    pub fn bounded(self, min: Option<f64>, max: Option<f64>) -> Self {
+        let (min, max) = (min.unwrap_or(self.min), max.unwrap_or(self.max));
+        if max <= min || (min, max) == (self.min, self.max) {
+            return self;
+        }
+        let step = round_width((max - min) / TICKS);
+        // Allowing for a little float error, so an end on a step still gets its tick
+        let first = (min / step - 1e-9).ceil() as i64;
+        let last = (max / step + 1e-9).floor() as i64;
+        Axis {
+            min,
+            max,
+            ticks: (first..=last).map(|i| i as f64 * step).collect(),
+        }
+    }
+
+    /// How far along the axis a value is, 0 at `min` and 1 at `max`
+    pub fn fraction(&self, value: f64) -> f64 {
+        (value - self.min) / (self.max - self.min)
+    }
+}
+
+/// The slope and intercept of the least squares line through points, None without two
+/// different x values
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let spread = points
+        .iter()
+        .map(|(x, _)| (x - mean_x).powi(2))
+        .sum::<f64>();
+    if points.len() < 2 || spread == 0.0 {
+        return None;
+    }
+    let covariance = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+    let slope = covariance / spread;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+/// The mean of each value and the `period - 1` before it, None until there are that many or
+/// where any of them is missing
+pub fn moving_average(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    let period = period.max(1);
+    (0..values.len())
+        .map(|end| {
+            let window = values.get((end + 1).checked_sub(period)?..=end)?;
+            let sum = window.iter().copied().sum::<Option<f64>>()?;
+            Some(sum / period as f64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::chart::*;
+
+    #[test]
+    fn axes_span_round_numbers() {
+        let axis = Axis::new(3.0, 47.0);
+        assert_eq!((axis.min, axis.max), (0.0, 50.0));
+        assert_eq!(axis.ticks, [0.0, 10.0, 20.0, 30.0, 40.0, 50.0]);
+        assert_eq!(axis.fraction(25.0), 0.5);
+
+        let negative = Axis::new(-12.0, 7.0);
+        assert_eq!((negative.min, negative.max), (-15.0, 10.0));
+
+        let flat = Axis::new(0.0, 0.0);
+        assert_eq!((flat.min, flat.max), (0.0, 1.0));
+
+        let bounded = axis.bounded(Some(12.0), None);
+        assert_eq!((bounded.min, bounded.max), (12.0, 50.0));
+        assert_eq!(bounded.ticks, [20.0, 30.0, 40.0, 50.0]);
+    }
+
+    #[test]
+    fn fits_trendlines() {
+        let (slope, intercept) = linear_fit(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]).unwrap();
+        assert!((slope - 2.0).abs() < 1e-9 && (intercept - 1.0).abs() < 1e-9);
+        assert_eq!(linear_fit(&[(1.0, 1.0), (1.0, 2.0)]), None);
+
+        let values = [Some(1.0), Some(3.0), Some(5.0), None, Some(7.0)];
+        assert_eq!(
+            moving_average(&values, 2),
+            [None, Some(2.0), Some(4.0), None, None]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+/// Tidying applied to text entered in cells
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cleaning {
+    /// Drops spaces at either end and squeezes runs of them inside to one, like TRIM
+    TrimWhitespace,
+    UpperCase,
+    LowerCase,
+    /// Capitalizes the first letter of each word and lowercases the rest, like PROPER
+    ProperCase,
+    /// Drops control characters like tabs, line breaks and zero-width spaces
+    RemoveNonPrintable,
+    /// Reads text like `1,234.50`, `$12`, `(7)` or `45%` as the number it shows
+    TextToNumbers,
+}
+
+pub const CLEANINGS: [(Cleaning, &str); 6] = [
+    (Cleaning::TrimWhitespace, "Trim whitespace"),
+    (Cleaning::UpperCase, "UPPERCASE"),
+    (Cleaning::LowerCase, "lowercase"),
+    (Cleaning::ProperCase, "Proper Case"),
+    (
+        Cleaning::RemoveNonPrintable,
+        "Remove non-printable characters",
+    ),
+    (Cleaning::TextToNumbers, "Convert text to numbers"),
+];
+
+impl Cleaning {
+    /// The cleaned up text, the same text if there's nothing to clean
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Cleaning::TrimWhitespace => text
+                .split(' ')
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+            Cleaning::UpperCase => text.to_uppercase(),
+            Cleaning::LowerCase => text.to_lowercase(),
+            Cleaning::ProperCase => proper_case(text),
+            Cleaning::RemoveNonPrintable => text
+                .chars()
+                .filter(|c| !c.is_control() && !matches!(c, '\u{200b}'..='\u{200d}' | '\u{feff}'))
+                .collect(),
+            // Numbers already are, whatever their trailing zeros
+            Cleaning::TextToNumbers if text.parse::<f64>().is_ok() => text.to_string(),
+            Cleaning::TextToNumbers => match number_in(text) {
+                Some(number) => number.to_string(),
+                None => text.to_string(),
+            },
+        }
+    }
+}
+
+fn proper_case(text: &str) -> String {
+    let mut proper = String::with_capacity(text.len());
+    let mut after_letter = false;
+    for c in text.chars() {
+        match after_letter {
+            true => proper.extend(c.to_lowercase()),
+            false => proper.extend(c.to_uppercase()),
+        }
+        after_letter = c.is_alphabetic();
+    }
+    proper
+}
+
+fn number_in(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (text, negative) = match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(inner) => (inner.trim(), true),
+        None => (text, false),
+    };
+    let (text, percent) = match text.strip_suffix('%') {
+        Some(inner) => (inner.trim_end(), true),
+        None => (text, false),
+    };
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(inner) => (-1.0, inner),
+        None => (1.0, text),
+    };
+    let text = text.strip_prefix('$').unwrap_or(text).replace(',', "");
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let number = text.parse::<f64>().ok()? * sign;
+    let number = if negative { -number } else { number };
+    Some(if percent { number / 100.0 } else { number })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::cleaning::*;
+
+    #[test]
+    fn cleans_text() {
+        assert_eq!(Cleaning::TrimWhitespace.apply("  a   b c "), "a b c");
+        assert_eq!(
+            Cleaning::ProperCase.apply("mARY o'neil-SMITH"),
+            "Mary O'Neil-Smith"
+        );
+        assert_eq!(Cleaning::RemoveNonPrintable.apply("a\tb\u{200b}c\n"), "abc");
+        assert_eq!(Cleaning::TextToNumbers.apply(" 1,234.50 "), "1234.5");
+        assert_eq!(Cleaning::TextToNumbers.apply("($12)"), "-12");
+        assert_eq!(Cleaning::TextToNumbers.apply("45%"), "0.45");
+        assert_eq!(Cleaning::TextToNumbers.apply("12 apples"), "12 apples");
+    }
+}
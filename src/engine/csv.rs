@@ -0,0 +1,429 @@
+/// Writes rows of fields split by `delimiter` the way RFC 4180 has CSV: fields with the
+/// delimiter, a quote or a line break are quoted, quotes in them doubled, and records end
+/// with CRLF
+pub fn write_delimited(rows: &[Vec<String>], delimiter: char) -> String {
+    rows.iter()
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|field| quote_field(field, delimiter))
+                .collect::<Vec<_>>()
+                .join(delimiter.encode_utf8(&mut [0; 4]))
+        })
+        .map(|record| record + "\r\n")
+        .collect()
+}
+
+/// Tab separated values, as spreadsheets put them on the clipboard
+pub fn write_tsv(rows: &[Vec<String>]) -> String {
+    write_delimited(rows, '\t')
+}
+
+fn quote_field(field: &str, delimiter: char) -> String {
+    match field.contains([delimiter, '"', '\r', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+/// The delimiters the import dialog offers, with their names
+pub const DELIMITERS: [(char, &str); 4] = [
+    (',', "Comma"),
+    (';', "Semicolon"),
+    ('\t', "Tab"),
+    ('|', "Pipe"),
+];
+
+/// Lines of a file looked at to tell its delimiter
+const SNIFFED_LINES: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+/// The encodings the import dialog offers, with their names
+pub const ENCODINGS: [(Encoding, &str); 4] = [
+    (Encoding::Utf8, "UTF-8"),
+    (Encoding::Utf16Le, "UTF-16 LE"),
+    (Encoding::Utf16Be, "UTF-16 BE"),
+    (Encoding::Latin1, "Latin-1"),
+];
+
+/// How an imported column's fields are taken
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnType {
+    /// Kept as they are, aligned left like text
+    Text,
+    /// Read as numbers, ignoring thousands separators and currency signs
+    Number,
+}
+
+/// Reads CSV (RFC 4180) into rows of fields split by `delimiter`. Quoted fields may hold
+/// delimiters, doubled quotes and line breaks. Records may end with CRLF or just LF, and a
+/// final line break doesn't start another row.
+pub fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut rest = text;
+    while let Some((fields, read)) = read_record(rest, delimiter) {
+        rows.push(fields);
+        rest = &rest[read..];
+    }
+    rows
+}
+
+/// The first record of `text`, with how many bytes of it it took up. None once there's
+/// nothing but an empty field left.
+fn read_record(text: &str, delimiter: char) -> Option<(Vec<String>, usize)> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        match (quoted, c) {
+            (true, '"') if next == Some('"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            // Quotes only open a field at its start, elsewhere they're kept as they are
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, c) if c == delimiter => fields.push(std::mem::take(&mut field)),
+            (false, '\r') if next == Some('\n') => {}
+            (false, '\n') => {
+                fields.push(field);
+                return Some((fields, i + 1));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if field.is_empty() && fields.is_empty() {
+        return None;
+    }
+    fields.push(field);
+    Some((fields, text.len()))
+}
+
+/// The records of CSV text read one at a time, so an import only parses as far as it's
+/// written
+#[derive(Clone, Debug)]
+pub struct CsvRecords {
+    text: String,
+    delimiter: char,
+    /// Where the next record starts in `text`
+    position: usize,
+}
+
+impl CsvRecords {
+    pub fn new(text: String, delimiter: char) -> Self {
+        CsvRecords {
+            text,
+            delimiter,
+            position: 0,
+        }
+    }
+
+    /// The first records not read yet, leaving them to read
+    pub fn preview(&self, count: usize) -> Vec<Vec<String>> {
+        let mut rest = &self.text[self.position..];
+        std::iter::from_fn(|| {
+            let (fields, read) = read_record(rest, self.delimiter)?;
+            rest = &rest[read..];
+            Some(fields)
+        })
+        .take(count)
+        .collect()
+    }
+
+    /// How much of the text has been read, from 0 to 1
+    pub fn progress(&self) -> f64 {
+        match self.text.is_empty() {
+            true => 1.0,
+            false => self.position as f64 / self.text.len() as f64,
+        }
+    }
+}
+
+impl Iterator for CsvRecords {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        let (fields, read) = read_record(&self.text[self.position..], self.delimiter)?;
+        self.position += read;
+        Some(fields)
+    }
+}
+
+/// A file's encoding, and whether that's certain: a byte order mark or valid UTF-8 are,
+/// UTF-16 told by its zero bytes or Latin-1 for anything else are guesses. The zero bytes are
+/// looked for first, as ASCII in UTF-16 is valid UTF-8 too.
+pub fn detect_encoding(bytes: &[u8]) -> (Encoding, bool) {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return (Encoding::Utf8, true);
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return (Encoding::Utf16Le, true);
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return (Encoding::Utf16Be, true);
+    }
+    // ASCII text in UTF-16 has every other byte zero
+    let zeros_at = |parity: usize| {
+        bytes
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|byte| **byte == 0)
+            .count()
+    };
+    let half = bytes.len() / 2;
+    match (zeros_at(0), zeros_at(1)) {
+        (_, odd) if half > 0 && odd * 2 > half => (Encoding::Utf16Le, false),
+        (even, _) if half > 0 && even * 2 > half => (Encoding::Utf16Be, false),
+        _ if std::str::from_utf8(bytes).is_ok() => (Encoding::Utf8, true),
+        _ => (Encoding::Latin1, false),
+    }
+}
+
+/// A file's text, without a byte order mark. What isn't valid in the encoding becomes �.
+pub fn decode(bytes: &[u8], encoding: Encoding) -> String {
+    let utf16 = |unit: fn([u8; 2]) -> u16, bom: [u8; 2]| {
+        let bytes = bytes.strip_prefix(&bom).unwrap_or(bytes);
+        let units = bytes
+            .chunks_exact(2)
+            .map(|pair| unit([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    };
+    match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+        Encoding::Utf16Le => utf16(u16::from_le_bytes, [0xFF, 0xFE]),
+        Encoding::Utf16Be => utf16(u16::from_be_bytes, [0xFE, 0xFF]),
+        Encoding::Latin1 => bytes.iter().map(|byte| *byte as char).collect(),
+    }
+}
+
+/// The delimiter splitting each of the first lines into the same number of fields, more
+/// than one, and into the most fields if several do. None if none does or it's a tie.
+pub fn sniff_delimiter(text: &str) -> Option<char> {
+    let sample = text
+        .lines()
+        .take(SNIFFED_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let cut = text.lines().nth(SNIFFED_LINES).is_some();
+    let consistent = DELIMITERS
+        .iter()
+        .filter_map(|(delimiter, _)| {
+            let mut rows = parse_csv(&sample, *delimiter);
+            // The last line may end in the middle of a quoted field
+            if cut {
+                rows.pop();
+            }
+            let first = rows.first()?.len();
+            let same = rows.iter().all(|fields| fields.len() == first);
+            (first > 1 && same).then_some((*delimiter, first))
+        })
+        .collect::<Vec<_>>();
+    let most = consistent.iter().map(|(_, fields)| *fields).max()?;
+    match consistent
+        .iter()
+        .filter(|(_, fields)| *fields == most)
+        .collect::<Vec<_>>()[..]
+    {
+        [(delimiter, _)] => Some(*delimiter),
+        _ => None,
+    }
+}
+
+/// Whether the first row looks like column names: no numbers or blanks in it, over at
+/// least one more row
+pub fn detect_header(rows: &[Vec<String>]) -> bool {
+    rows.len() > 1
+        && rows[0]
+            .iter()
+            .all(|field| !field.trim().is_empty() && parse_number(field).is_none())
+}
+
+/// The records of a file whose encoding and delimiter leave no doubt, None for the import
+/// dialog to ask about them. A delimiter the file's type implies is taken as it is.
+pub fn sniff(bytes: &[u8], delimiter: Option<char>) -> Option<CsvRecords> {
+    let (encoding, certain) = detect_encoding(bytes);
+    if !certain {
+        return None;
+    }
+    let text = decode(bytes, encoding);
+    let delimiter = delimiter.or_else(|| sniff_delimiter(&text))?;
+    Some(CsvRecords::new(text, delimiter))
+}
+
+/// The delimiter sniffed, or the one in the first line the most times outside quotes
+pub fn detect_delimiter(text: &str) -> char {
+    if let Some(delimiter) = sniff_delimiter(text) {
+        return delimiter;
+    }
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut quoted = false;
+    let mut counts = [0; DELIMITERS.len()];
+    for c in first_line.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if let Some(i) = DELIMITERS.iter().position(|(d, _)| *d == c && !quoted) {
+            counts[i] += 1;
+        }
+    }
+    // Ties go to the first, a comma if there are none
+    let most = counts.iter().copied().max().unwrap_or_default();
+    let i = counts
+        .iter()
+        .position(|count| *count == most)
+        .unwrap_or_default();
+    DELIMITERS[i].0
+}
+
+/// Number if every field of a column but the header reads as one, blanks aside
+pub fn detect_column_type(rows: &[Vec<String>], column: usize, header: bool) -> ColumnType {
+    let mut fields = rows
+        .iter()
+        .skip(header as usize)
+        .filter_map(|fields| fields.get(column))
+        .filter(|field| !field.trim().is_empty())
+        .peekable();
+    match fields.peek().is_some() && fields.all(|field| parse_number(field).is_some()) {
+        true => ColumnType::Number,
+        false => ColumnType::Text,
+    }
+}
+
+/// A field as a cell's content: numbers written plainly, anything that doesn't read as
+/// one in a number column kept as it is
+pub fn convert_field(field: &str, column_type: ColumnType) -> String {
+    match column_type {
+        ColumnType::Number => parse_number(field)
+            .map(|number| number.to_string())
+            .unwrap_or_else(|| field.to_string()),
+        ColumnType::Text => field.to_string(),
+    }
+}
+
+fn parse_number(field: &str) -> Option<f64> {
+    let cleaned = field
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ',' | '$' | '€' | '£' | ' '))
+        .collect::<String>();
+    cleaned
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::csv::*;
+
+    #[test]
+    fn quoted_fields_round_trip() {
+        let rows = vec![
+            vec!["Name".to_string(), "Note".to_string()],
+            vec![
+                "Smith, Jo".to_string(),
+                "Said \"hi\"\nthen left".to_string(),
+            ],
+            vec!["".to_string(), "=SUM(A1,A2)".to_string()],
+        ];
+        let csv = write_delimited(&rows, ',');
+        assert_eq!(
+            csv,
+            "Name,Note\r\n\"Smith, Jo\",\"Said \"\"hi\"\"\nthen left\"\r\n,\"=SUM(A1,A2)\"\r\n"
+        );
+        assert_eq!(parse_csv(&csv, ','), rows);
+
+        let tsv = write_tsv(&rows);
+        assert!(tsv.starts_with("Name\tNote\r\nSmith, Jo\t\"Said"));
+        assert_eq!(parse_csv(&tsv, '\t'), rows);
+    }
+
+    #[test]
+    fn reads_line_feeds_and_a_missing_final_break() {
+        assert_eq!(
+            parse_csv("a,b\nc,\"d\"\"\"", ','),
+            [vec!["a", "b"], vec!["c", "d\""]]
+        );
+        assert_eq!(parse_csv("a,\n\n", ','), [vec!["a", ""], vec![""]]);
+        assert!(parse_csv("", ',').is_empty());
+    }
+
+    #[test]
+    fn records_are_read_as_they_are_needed() {
+        let text = "a,\"b\nc\"\r\nd,e\n\nf";
+        let mut records = CsvRecords::new(text.to_string(), ',');
+        assert_eq!(records.preview(1), [vec!["a", "b\nc"]]);
+        assert_eq!(
+            records.next(),
+            Some(vec!["a".to_string(), "b\nc".to_string()])
+        );
+        assert_eq!(records.preview(5), [vec!["d", "e"], vec![""], vec!["f"]]);
+        assert!(records.progress() > 0.0 && records.progress() < 1.0);
+        assert_eq!(records.collect::<Vec<_>>(), parse_csv(text, ',')[1..]);
+    }
+
+    #[test]
+    fn detects_delimiters_and_number_columns() {
+        assert_eq!(detect_delimiter("\"a;b\",c,d\n1;2"), ',');
+        assert_eq!(detect_delimiter("a;b;c"), ';');
+        assert_eq!(detect_delimiter("a\tb"), '\t');
+
+        let rows = parse_csv("Name;Price\nTea;\"1,200.50\"\nCake;\n", ';');
+        assert_eq!(detect_column_type(&rows, 0, true), ColumnType::Text);
+        assert_eq!(detect_column_type(&rows, 1, true), ColumnType::Number);
+        assert_eq!(detect_column_type(&rows, 1, false), ColumnType::Text);
+        assert_eq!(convert_field("$1,200.50", ColumnType::Number), "1200.5");
+        assert_eq!(convert_field("007", ColumnType::Text), "007");
+    }
+
+    #[test]
+    fn sniffs_encodings_and_delimiters() {
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBFa,b"), (Encoding::Utf8, true));
+        assert_eq!(decode(b"\xEF\xBB\xBFa,b", Encoding::Utf8), "a,b");
+        let utf16 = [0xFF, 0xFE, b'a', 0, b';', 0, 0xE9, 0];
+        assert_eq!(detect_encoding(&utf16), (Encoding::Utf16Le, true));
+        assert_eq!(decode(&utf16, Encoding::Utf16Le), "a;é");
+        assert_eq!(detect_encoding(&utf16[2..]), (Encoding::Utf16Le, false));
+        // Plain ASCII without a byte order mark, valid UTF-8 as well
+        let ascii_le = [b'a', 0, b',', 0, b'1', 0];
+        assert_eq!(detect_encoding(&ascii_le), (Encoding::Utf16Le, false));
+        assert_eq!(decode(&ascii_le, Encoding::Utf16Le), "a,1");
+        let ascii_be = [0, b'a', 0, b',', 0, b'1'];
+        assert_eq!(detect_encoding(&ascii_be), (Encoding::Utf16Be, false));
+        assert_eq!(decode(&ascii_be, Encoding::Utf16Be), "a,1");
+        assert_eq!(detect_encoding(b"a,1"), (Encoding::Utf8, true));
+        assert_eq!(detect_encoding(b"caf\xE9,1"), (Encoding::Latin1, false));
+        assert_eq!(decode(b"caf\xE9", Encoding::Latin1), "café");
+
+        assert_eq!(sniff_delimiter("a;b;c,d\n1;2;3,4\n"), Some(';'));
+        assert_eq!(sniff_delimiter("a\tb\n\"1\t2\"\t3"), Some('\t'));
+        // Both split every line in two
+        assert_eq!(sniff_delimiter("a;b,c\nd;e,f"), None);
+        assert_eq!(sniff_delimiter("just one column\n2"), None);
+        assert_eq!(
+            sniff(b"Name|Age\nJo|7", None).map(|records| records.count()),
+            Some(2)
+        );
+        assert!(sniff(b"caf\xE9|1\nthe|2", None).is_none());
+        // A single column is ambiguous unless the file's type says how it's split
+        assert!(sniff(b"a\nb", None).is_none());
+        assert_eq!(
+            sniff(b"a\nb", Some('\t')).map(|records| records.count()),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+/// Day 0 of date serials, 1899-12-30 as in other spreadsheets, in days since 1970-01-01
+const SERIAL_EPOCH: i64 = -25569;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Ways of showing a date serial: days since 1899-12-30, with the time of day as the fraction
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DateFormat {
+    /// `2024-03-05`
+    Iso,
+    /// The locale's short date, like `3/5/24`
+    Short,
+    /// The locale's long date, like `March 5, 2024`
+    Long,
+    /// `14:30:00`, the time of day alone
+    Time,
+    /// `50:15:00`, hours counting past a day
+    Duration,
+}
+
+impl DateFormat {
+    /// None for values that aren't dates
+    pub fn apply(self, value: &str) -> Option<String> {
+        let serial = serial_of(value)?;
+        // To the second, so 0.99999 doesn't show as 23:59:59 on one side and a day on the other
+        let seconds = (serial * SECONDS_PER_DAY as f64).round() as i64;
+        let days = seconds.div_euclid(SECONDS_PER_DAY) + SERIAL_EPOCH;
+        let time = seconds.rem_euclid(SECONDS_PER_DAY);
+        Some(match self {
+            DateFormat::Iso => format_date(days),
+            DateFormat::Short => locale_date(days, "short"),
+            DateFormat::Long => locale_date(days, "long"),
+            DateFormat::Time => format_time(time as u64),
+            DateFormat::Duration => {
+                let sign = if seconds < 0 { "-" } else { "" };
+                format!("{}{}", sign, format_time(seconds.unsigned_abs()))
+            }
+        })
+    }
+}
+
+/// A value as a date serial. Numbers already are one and `YYYY-MM-DD` text is read as a date.
+pub fn serial_of(value: &str) -> Option<f64> {
+    match value.parse::<f64>() {
+        Ok(number) => number.is_finite().then_some(number),
+        Err(_) => parse_date(value.trim()).map(|days| (days - SERIAL_EPOCH) as f64),
+    }
+}
+
+/// `HH:MM:SS`, the hours not wrapping at a day
+fn format_time(seconds: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// The browser's way of writing the date for the user's locale, ISO outside the browser
+fn locale_date(days: i64, style: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let millis = days as f64 * SECONDS_PER_DAY as f64 * 1000.0;
+        let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(millis));
+        let options = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&options, &"dateStyle".into(), &style.into());
+        // Serials have no time zone, so the date is read back in UTC like it was made
+        let _ = js_sys::Reflect::set(&options, &"timeZone".into(), &"UTC".into());
+        date.to_locale_date_string("default", &options).into()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = style;
+        format_date(days)
+    }
+}
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD` date
+pub fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let (year, month, day) = (
+        year.parse::<i64>().ok()?,
+        month.parse::<i64>().ok()?,
+        day.parse::<i64>().ok()?,
+    );
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Rejects dates like 2023-02-30 instead of rolling them over
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+pub fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A time in seconds since 1970-01-01 as its date and UTC time, like `2024-03-05 14:30 UTC`
+pub fn format_timestamp(seconds: u64) -> String {
+    let time = seconds % SECONDS_PER_DAY as u64;
+    format!(
+        "{} {:02}:{:02} UTC",
+        format_date((seconds / SECONDS_PER_DAY as u64) as i64),
+        time / 3600,
+        time % 3600 / 60
+    )
+}
+
+/// Seconds since 1970-01-01, from the browser's clock in the browser
+pub fn now_seconds() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    return (js_sys::Date::now() / 1000.0) as u64;
+    #[cfg(not(target_arch = "wasm32"))]
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// Conversions between the proleptic Gregorian calendar and days since 1970-01-01, after
+// Howard Hinnant's `days_from_civil` and `civil_from_days`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::date::*;
+
+    #[test]
+    fn formats_serials() {
+        assert_eq!(
+            DateFormat::Iso.apply("45356").as_deref(),
+            Some("2024-03-05")
+        );
+        assert_eq!(
+            DateFormat::Iso.apply("2024-03-05").as_deref(),
+            Some("2024-03-05")
+        );
+        assert_eq!(
+            DateFormat::Time.apply("45356.6041667").as_deref(),
+            Some("14:30:00")
+        );
+        assert_eq!(
+            DateFormat::Duration.apply("2.09375").as_deref(),
+            Some("50:15:00")
+        );
+        assert_eq!(
+            DateFormat::Duration.apply("-1e300").as_deref(),
+            Some("-2562047788015215:30:08")
+        );
+        assert_eq!(
+            DateFormat::Short.apply("45356").as_deref(),
+            Some("2024-03-05")
+        );
+        assert_eq!(DateFormat::Iso.apply("soon"), None);
+        assert_eq!(serial_of("1899-12-31"), Some(1.0));
+    }
+}
@@ -0,0 +1,372 @@
+use crate::model::grid::{
+    cell_address_to_coords, column_index_to_letter, coords_to_cell_address, Coords,
+};
+
+const EXPRESSION_STARTS: &str = "=(,+-*/^";
+
+/// The function name being typed just before `caret`, as (byte offset of its start, prefix)
+pub fn function_prefix_at(content: &str, caret: usize) -> Option<(usize, &str)> {
+    if !content.starts_with('=') {
+        return None;
+    }
+    let before = content.get(..caret)?;
+    let start = before.rfind(|c: char| !c.is_ascii_alphabetic())? + 1;
+    let prefix = &before[start..];
+    if prefix.is_empty() {
+        return None;
+    }
+    let preceding = before[..start].trim_end().chars().last()?;
+    EXPRESSION_STARTS
+        .contains(preceding)
+        .then_some((start, prefix))
+}
+
+/// Replaces the function prefix before `caret` with `name(`, returning the new content and caret
+pub fn complete_function(content: &str, caret: usize, name: &str) -> Option<(String, usize)> {
+    let (start, _) = function_prefix_at(content, caret)?;
+    let completed = format!("{}{}({}", &content[..start], name, &content[caret..]);
+    Some((completed, start + name.len() + 1))
+}
+
+/// A cell or range reference inside a formula, located by byte offsets.
+/// `start` includes the sheet prefix (`Sheet2!`), `cell_start` is where the cell part begins.
+#[derive(Debug, PartialEq)]
+pub struct ReferenceSpan {
+    pub start: usize,
+    pub cell_start: usize,
+    pub end: usize,
+    pub sheet: Option<String>,
+    pub first: String,
+    pub last: String,
+}
+
+impl ReferenceSpan {
+    pub fn is_range(&self) -> bool {
+        self.end - self.cell_start != self.first.len()
+    }
+}
+
+/// Cell and range references in a formula, found by scanning so incomplete formulas still work
+pub fn find_references(content: &str) -> Vec<ReferenceSpan> {
+    let mut references = Vec::new();
+    if !content.starts_with('=') {
+        return references;
+    }
+    let bytes = content.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        let previous = bytes[i - 1];
+        if previous.is_ascii_alphanumeric() || b"$_'!".contains(&previous) {
+            i += 1;
+            continue;
+        }
+        let (sheet, cell_start) = match sheet_prefix_end(bytes, i) {
+            Some(cell_start) => {
+                let name = content[i..cell_start - 1].trim_matches('\'');
+                (Some(name.to_string()), cell_start)
+            }
+            None => (None, i),
+        };
+        let Some(first_end) = cell_ref_end(bytes, cell_start) else {
+            i += 1;
+            continue;
+        };
+        let mut end = first_end;
+        if bytes.get(end) == Some(&b':') {
+            if let Some(last_end) = cell_ref_end(bytes, end + 1) {
+                end = last_end;
+            }
+        }
+        let is_reference = match bytes.get(end) {
+            Some(next) => !next.is_ascii_alphanumeric() && *next != b'(' && *next != b'$',
+            None => true,
+        };
+        if is_reference {
+            let first = content[cell_start..first_end].to_string();
+            let last = match end == first_end {
+                true => first.clone(),
+                false => content[first_end + 1..end].to_string(),
+            };
+            references.push(ReferenceSpan {
+                start: i,
+                cell_start,
+                end,
+                sheet,
+                first,
+                last,
+            });
+        }
+        i = end;
+    }
+    references
+}
+
+/// Rebuilds `content` with each reference replaced by what `replacement` returns for it,
+/// or left as is for None
+pub fn replace_references(
+    content: &str,
+    mut replacement: impl FnMut(&ReferenceSpan) -> Option<String>,
+) -> String {
+    let mut result = String::new();
+    let mut copied_until = 0;
+    for span in find_references(content) {
+        if let Some(replaced) = replacement(&span) {
+            result.push_str(&content[copied_until..span.start]);
+            result.push_str(&replaced);
+            copied_until = span.end;
+        }
+    }
+    result.push_str(&content[copied_until..]);
+    result
+}
+
+/// End of a `Sheet2!` or `'My sheet'!` prefix starting at `start`, just past the `!`
+fn sheet_prefix_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let name_end = match bytes[start] {
+        b'\'' => start + 1 + bytes[start + 1..].iter().position(|b| *b == b'\'')? + 1,
+        _ => {
+            let mut i = start;
+            while bytes
+                .get(i)
+                .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_')
+            {
+                i += 1;
+            }
+            i
+        }
+    };
+    (name_end > start && bytes.get(name_end) == Some(&b'!')).then_some(name_end + 1)
+}
+
+/// End of a `$A$1`-style reference starting at `start`
+fn cell_ref_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start;
+    let skip_dollar = |i: &mut usize| {
+        if bytes.get(*i) == Some(&b'$') {
+            *i += 1;
+        }
+    };
+    skip_dollar(&mut i);
+    let letters_start = i;
+    while bytes.get(i).is_some_and(|b| b.is_ascii_alphabetic()) {
+        i += 1;
+    }
+    if i == letters_start {
+        return None;
+    }
+    skip_dollar(&mut i);
+    let digits_start = i;
+    while bytes.get(i).is_some_and(|b| b.is_ascii_digit()) {
+        i += 1;
+    }
+    (i > digits_start).then_some(i)
+}
+
+/// Cycles the reference around `caret` through A1 → $A$1 → A$1 → $A1 → A1,
+/// returning the new content and a caret placed after the reference
+pub fn cycle_absolute_reference(content: &str, caret: usize) -> Option<(String, usize)> {
+    let span = find_references(content)
+        .into_iter()
+        .find(|span| span.start <= caret && caret <= span.end)?;
+    let cycled = match span.is_range() {
+        false => cycle_cell_ref(&span.first),
+        true => format!(
+            "{}:{}",
+            cycle_cell_ref(&span.first),
+            cycle_cell_ref(&span.last)
+        ),
+    };
+    let new_content = format!(
+        "{}{}{}",
+        &content[..span.cell_start],
+        cycled,
+        &content[span.end..]
+    );
+    Some((new_content, span.cell_start + cycled.len()))
+}
+
+/// Moves the relative parts of every reference by an offset, the way copying a formula does.
+/// `$` anchored parts stay put, references pushed off the sheet become `#REF!`.
+pub fn shift_references(content: &str, rows: i32, columns: i32) -> String {
+    replace_references(content, |span| {
+        Some(match shift_span(span, rows, columns, true) {
+            Some(cells) => format!("{}{}", &content[span.start..span.cell_start], cells),
+            None => "#REF!".to_string(),
+        })
+    })
+}
+
+/// References of a formula pasted transposed from `from` to `to`. Relative references keep
+/// pointing at the same neighbour with rows and columns swapped, anchored ones shift as usual.
+pub fn transpose_references(content: &str, from: Coords, to: Coords) -> String {
+    let transpose = |cell_ref: &String| {
+        if cell_ref.contains('$') {
+            return shift_cell_ref(cell_ref, to.row - from.row, to.column - from.column, true);
+        }
+        let coords = cell_address_to_coords(cell_ref)?;
+        let transposed = Coords {
+            row: to.row + coords.column - from.column,
+            column: to.column + coords.row - from.row,
+        };
+        (transposed.row >= 0 && transposed.column >= 0).then(|| coords_to_cell_address(transposed))
+    };
+    replace_references(content, |span| {
+        let transposed = match span.is_range() {
+            false => transpose(&span.first),
+            true => transpose(&span.first)
+                .zip(transpose(&span.last))
+                .map(|(first, last)| format!("{}:{}", first, last)),
+        };
+        Some(match transposed {
+            Some(cells) => format!("{}{}", &content[span.start..span.cell_start], cells),
+            None => "#REF!".to_string(),
+        })
+    })
+}
+
+/// Cell part of a reference (`A1` or `A1:B2`) moved by an offset, None if it falls off the sheet.
+/// Moving cells drags `$` anchored parts along, copying doesn't.
+pub fn shift_span(
+    span: &ReferenceSpan,
+    rows: i32,
+    columns: i32,
+    keep_anchored: bool,
+) -> Option<String> {
+    match span.is_range() {
+        false => shift_cell_ref(&span.first, rows, columns, keep_anchored),
+        true => shift_cell_ref(&span.first, rows, columns, keep_anchored)
+            .zip(shift_cell_ref(&span.last, rows, columns, keep_anchored))
+            .map(|(first, last)| format!("{}:{}", first, last)),
+    }
+}
+
+fn shift_cell_ref(cell_ref: &str, rows: i32, columns: i32, keep_anchored: bool) -> Option<String> {
+    let column_absolute = cell_ref.starts_with('$');
+    let row_absolute = cell_ref[1..].contains('$');
+    let unmarked = cell_ref.replace('$', "");
+    let coords = cell_address_to_coords(&unmarked)?;
+    let (column, row) = unmarked.split_at(unmarked.find(|c: char| c.is_ascii_digit())?);
+
+    let column = match (column_absolute && keep_anchored) || columns == 0 {
+        true => column.to_string(),
+        false if coords.column + columns < 0 => return None,
+        false => column_index_to_letter(coords.column + columns),
+    };
+    let row = match (row_absolute && keep_anchored) || rows == 0 {
+        true => row.to_string(),
+        false if coords.row + rows < 0 => return None,
+        false => (coords.row + rows + 1).to_string(),
+    };
+    Some(format!(
+        "{}{}{}{}",
+        if column_absolute { "$" } else { "" },
+        column,
+        if row_absolute { "$" } else { "" },
+        row
+    ))
+}
+
+fn cycle_cell_ref(cell_ref: &str) -> String {
+    let column_absolute = cell_ref.starts_with('$');
+    let unmarked = cell_ref.replace('$', "");
+    let row_start = unmarked
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(unmarked.len());
+    let row_absolute = cell_ref[1..].contains('$');
+    let (column, row) = unmarked.split_at(row_start);
+    match (column_absolute, row_absolute) {
+        (false, false) => format!("${}${}", column, row),
+        (true, true) => format!("{}${}", column, row),
+        (false, true) => format!("${}{}", column, row),
+        (true, false) => format!("{}{}", column, row),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::editing::*;
+
+    #[test]
+    fn function_prefix() {
+        assert_eq!(function_prefix_at("=su", 3), Some((1, "su")));
+        assert_eq!(function_prefix_at("=1 + a", 6), Some((5, "a")));
+        assert_eq!(function_prefix_at("=sum(1, av", 10), Some((8, "av")));
+        assert_eq!(function_prefix_at("=a1", 3), None);
+        assert_eq!(function_prefix_at("sum", 3), None);
+        assert_eq!(function_prefix_at("=", 1), None);
+    }
+
+    #[test]
+    fn references() {
+        let spans = find_references("=sum(a1:$B$2, c3) + d4");
+        let addresses = spans
+            .iter()
+            .map(|span| (span.first.as_str(), span.last.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(addresses, vec![("a1", "$B$2"), ("c3", "c3"), ("d4", "d4")]);
+        assert_eq!((spans[0].start, spans[0].end), (5, 12));
+
+        assert!(find_references("=log10(2)").is_empty());
+        assert!(find_references("a1").is_empty());
+        assert_eq!(find_references("=a1+").len(), 1);
+
+        let spans = find_references("=Sheet2!a1 + 'My sheet'!b2:c3");
+        let sheets = spans
+            .iter()
+            .map(|span| span.sheet.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(sheets, vec![Some("Sheet2"), Some("My sheet")]);
+        assert_eq!((spans[1].start, spans[1].cell_start), (13, 24));
+        let replaced = replace_references("=Sheet2!a1 + b2", |span| {
+            span.sheet.as_ref().map(|_| "#REF!".to_string())
+        });
+        assert_eq!(replaced, "=#REF! + b2");
+    }
+
+    #[test]
+    fn absolute_reference_cycling() {
+        let mut content = "=a1 + 2".to_string();
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            (content, _) = cycle_absolute_reference(&content, 2).unwrap();
+            seen.push(content.clone());
+        }
+        assert_eq!(seen, vec!["=$a$1 + 2", "=a$1 + 2", "=$a1 + 2", "=a1 + 2"]);
+
+        assert_eq!(
+            cycle_absolute_reference("=sum(a1:b2)", 7),
+            Some(("=sum($a$1:$b$2)".to_string(), 14))
+        );
+        assert_eq!(cycle_absolute_reference("=1 + 2", 3), None);
+        assert_eq!(
+            cycle_absolute_reference("=Sheet2!a1", 3),
+            Some(("=Sheet2!$a$1".to_string(), 12))
+        );
+    }
+
+    #[test]
+    fn reference_shifting() {
+        assert_eq!(
+            shift_references("=a1 + $b$2 + c$3 * $d4", 2, 1),
+            "=B3 + $b$2 + D$3 * $d6"
+        );
+        assert_eq!(
+            shift_references("=sum(Sheet2!a1:b2)", 1, 0),
+            "=sum(Sheet2!a2:b3)"
+        );
+        assert_eq!(shift_references("=a1 + 1", -1, 0), "=#REF! + 1");
+        assert_eq!(shift_references("=log10(2)", 5, 5), "=log10(2)");
+    }
+
+    #[test]
+    fn function_completion() {
+        assert_eq!(
+            complete_function("=1 + su", 7, "SUM"),
+            Some(("=1 + SUM(".to_string(), 9))
+        );
+        assert_eq!(
+            complete_function("=av * 2", 3, "AVG"),
+            Some(("=AVG( * 2".to_string(), 5))
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use crate::engine::{
+    date::{format_date, parse_date},
+    editing,
+};
+
+static WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+static WEEKDAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+static MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+static MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+type Series = Box<dyn Fn(usize) -> String>;
+
+/// Contents of `count` cells continuing `seeds` along a row or column, `seeds` ordered in the
+/// direction of the fill. `step` is the (rows, columns) offset between neighbouring cells,
+/// (1, 0) filling down, (0, -1) filling left.
+///
+/// Seeds forming a series (numbers, dates, weekdays, months, numbered text) continue it.
+/// Anything else repeats, formulas shifting their relative references by how far each copy
+/// lands from its seed.
+pub fn extend(seeds: &[String], count: usize, step: (i32, i32)) -> Vec<String> {
+    if seeds.is_empty() {
+        return Vec::new();
+    }
+    if let Some(series) = detect_series(seeds) {
+        return (seeds.len()..seeds.len() + count).map(series).collect();
+    }
+    (0..count)
+        .map(|i| {
+            let seed = &seeds[i % seeds.len()];
+            let distance = (seeds.len() + i - i % seeds.len()) as i32;
+            match seed.starts_with('=') {
+                true => editing::shift_references(seed, distance * step.0, distance * step.1),
+                false => seed.clone(),
+            }
+        })
+        .collect()
+}
+
+/// The value at any position of the series the seeds start, the seeds being positions 0..n
+fn detect_series(seeds: &[String]) -> Option<Series> {
+    if seeds.iter().any(|seed| seed.starts_with('=')) {
+        return None;
+    }
+    let seeds = seeds.iter().map(|seed| seed.trim()).collect::<Vec<_>>();
+    linear_series(&seeds)
+        .or_else(|| date_series(&seeds))
+        .or_else(|| named_series(&seeds))
+        .or_else(|| numbered_text_series(&seeds))
+}
+
+/// Two or more numbers continue along their least-squares line, a single one just repeats
+fn linear_series(seeds: &[&str]) -> Option<Series> {
+    if seeds.len() < 2 {
+        return None;
+    }
+    let values = seeds
+        .iter()
+        .map(|seed| seed.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let n = values.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, y) in values.iter().enumerate() {
+        covariance += (x as f64 - mean_x) * (y - mean_y);
+        variance += (x as f64 - mean_x).powi(2);
+    }
+    let slope = covariance / variance;
+    let intercept = mean_y - slope * mean_x;
+    Some(Box::new(move |position| {
+        // Rounded so steps like 0.1 don't show floating point noise
+        let value = intercept + slope * position as f64;
+        ((value * 1e10).round() / 1e10).to_string()
+    }))
+}
+
+/// `YYYY-MM-DD` dates, a day apart or as far apart as the seeds are
+fn date_series(seeds: &[&str]) -> Option<Series> {
+    let days = seeds
+        .iter()
+        .map(|seed| parse_date(seed))
+        .collect::<Option<Vec<_>>>()?;
+    let step = constant_step(&days)?;
+    let start = days[0];
+    Some(Box::new(move |position| {
+        format_date(start + step * position as i64)
+    }))
+}
+
+/// Weekday and month names, short or long, keeping the seeds' capitalization
+fn named_series(seeds: &[&str]) -> Option<Series> {
+    let names = [
+        &WEEKDAYS[..],
+        &WEEKDAY_NAMES[..],
+        &MONTHS[..],
+        &MONTH_NAMES[..],
+    ]
+    .into_iter()
+    .find(|names| {
+        seeds
+            .iter()
+            .all(|seed| names.iter().any(|name| name.eq_ignore_ascii_case(seed)))
+    })?;
+    let indices = seeds
+        .iter()
+        .filter_map(|seed| {
+            names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(seed))
+        })
+        .map(|index| index as i64)
+        .collect::<Vec<_>>();
+    let cycle = names.len() as i64;
+    // Nov, Jan steps by 2 rather than -10, so the steps are compared going around the cycle
+    let mut unwrapped = vec![indices[0]];
+    for pair in indices.windows(2) {
+        let previous = unwrapped[unwrapped.len() - 1];
+        unwrapped.push(previous + (pair[1] - pair[0]).rem_euclid(cycle));
+    }
+    let step = constant_step(&unwrapped)?;
+    let start = indices[0];
+    let first = seeds[0];
+    let uppercase = first.len() > 1 && first.chars().all(|c| c.is_ascii_uppercase());
+    let lowercase = first.chars().all(|c| c.is_ascii_lowercase());
+    Some(Box::new(move |position| {
+        let name = names[(start + step * position as i64).rem_euclid(cycle) as usize];
+        match (uppercase, lowercase) {
+            (true, _) => name.to_uppercase(),
+            (_, true) => name.to_lowercase(),
+            _ => name.to_string(),
+        }
+    }))
+}
+
+/// Text ending in a number, like `Item 1` or `Q1`, counts the number along
+fn numbered_text_series(seeds: &[&str]) -> Option<Series> {
+    let parts = seeds
+        .iter()
+        .map(|seed| split_trailing_number(seed))
+        .collect::<Option<Vec<_>>>()?;
+    let prefix = parts[0].0.to_string();
+    if parts.iter().any(|(other, _)| *other != prefix) {
+        return None;
+    }
+    let numbers = parts
+        .iter()
+        .map(|(_, number)| number.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let step = constant_step(&numbers)?;
+    let start = numbers[0];
+    // Keeps zero padding like `Item 007`
+    let width = match parts[0].1.starts_with('0') {
+        true => parts[0].1.len(),
+        false => 0,
+    };
+    Some(Box::new(move |position| {
+        let number = start + step * position as i64;
+        format!("{}{:0width$}", prefix, number, width = width)
+    }))
+}
+
+fn split_trailing_number(text: &str) -> Option<(&str, &str)> {
+    let prefix = text.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = &text[prefix.len()..];
+    (!prefix.is_empty() && !number.is_empty()).then_some((prefix, number))
+}
+
+/// The difference between consecutive values, 1 for a single value, None if it varies
+fn constant_step(values: &[i64]) -> Option<i64> {
+    let step = match values {
+        [first, second, ..] => second - first,
+        _ => 1,
+    };
+    values
+        .windows(2)
+        .all(|pair| pair[1] - pair[0] == step)
+        .then_some(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::fill::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn repeats_seeds_and_shifts_formulas() {
+        assert_eq!(
+            extend(&strings(&["a", "=b1 + $c$1"]), 3, (1, 0)),
+            strings(&["a", "=b3 + $c$1", "a"])
+        );
+        assert_eq!(
+            extend(&strings(&["=b2"]), 2, (0, -1)),
+            strings(&["=A2", "=#REF!"])
+        );
+    }
+
+    #[test]
+    fn continues_series() {
+        assert_eq!(
+            extend(&strings(&["1", "2"]), 2, (1, 0)),
+            strings(&["3", "4"])
+        );
+        assert_eq!(
+            extend(&strings(&["1", "3", "4"]), 1, (1, 0)),
+            strings(&["5.6666666667"])
+        );
+        assert_eq!(
+            extend(&strings(&["0.1", "0.2"]), 1, (1, 0)),
+            strings(&["0.3"])
+        );
+        assert_eq!(extend(&strings(&["7"]), 2, (1, 0)), strings(&["7", "7"]));
+
+        assert_eq!(
+            extend(&strings(&["Sat"]), 2, (1, 0)),
+            strings(&["Sun", "Mon"])
+        );
+        assert_eq!(
+            extend(&strings(&["JAN", "MAR"]), 2, (0, 1)),
+            strings(&["MAY", "JUL"])
+        );
+        assert_eq!(
+            extend(&strings(&["november"]), 2, (1, 0)),
+            strings(&["december", "january"])
+        );
+        assert_eq!(
+            extend(&strings(&["2024-02-28"]), 2, (1, 0)),
+            strings(&["2024-02-29", "2024-03-01"])
+        );
+        assert_eq!(
+            extend(&strings(&["Item 08", "Item 10"]), 1, (1, 0)),
+            strings(&["Item 12"])
+        );
+        // Not a series, so it repeats
+        assert_eq!(
+            extend(&strings(&["Mon", "x"]), 1, (1, 0)),
+            strings(&["Mon"])
+        );
+    }
+}
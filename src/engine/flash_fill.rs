@@ -0,0 +1,213 @@
+/// A text transformation learned from examples, built from the words of the source text
+/// (runs of letters and digits) and literal text between them
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Piece {
+    Word {
+        index: WordIndex,
+        case: Case,
+        /// Only the word's first character, as in initials
+        initial: bool,
+    },
+    Literal(String),
+}
+
+/// Counting from the end lets "the last word" work for sources with varying word counts
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WordIndex {
+    FromStart(usize),
+    FromEnd(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Case {
+    Keep,
+    Upper,
+    Lower,
+    Title,
+}
+
+impl Case {
+    fn apply(self, text: &str) -> String {
+        match self {
+            Case::Keep => text.to_string(),
+            Case::Upper => text.to_uppercase(),
+            Case::Lower => text.to_lowercase(),
+            Case::Title => {
+                let mut chars = text.chars();
+                match chars.next() {
+                    Some(first) => first
+                        .to_uppercase()
+                        .chain(chars.flat_map(char::to_lowercase))
+                        .collect(),
+                    None => String::new(),
+                }
+            }
+        }
+    }
+}
+
+impl Pattern {
+    /// The transformed text, None if the source doesn't have the words the pattern takes
+    pub fn apply(&self, source: &str) -> Option<String> {
+        let words = words(source);
+        let mut output = String::new();
+        for piece in &self.pieces {
+            output.push_str(&piece.apply(&words)?);
+        }
+        Some(output)
+    }
+}
+
+impl Piece {
+    fn apply(&self, words: &[&str]) -> Option<String> {
+        match self {
+            Piece::Word {
+                index,
+                case,
+                initial,
+            } => {
+                let word = match *index {
+                    WordIndex::FromStart(i) => *words.get(i)?,
+                    WordIndex::FromEnd(i) => *words.get(words.len().checked_sub(i + 1)?)?,
+                };
+                let word = match *initial {
+                    true => &word[..word.chars().next()?.len_utf8()],
+                    false => word,
+                };
+                Some(case.apply(word))
+            }
+            Piece::Literal(text) => Some(text.clone()),
+        }
+    }
+}
+
+/// Infers the pattern turning every example's source into its output. The first example
+/// shapes the pattern, the others pick between the ways of producing it.
+pub fn infer(examples: &[(String, String)]) -> Option<Pattern> {
+    let (source, output) = examples.first()?;
+    let words = words(source);
+    let mut segments = Vec::new();
+    for segment in segments_of(output) {
+        segments.push(candidates(segment, &words));
+    }
+    let mut pieces = Vec::new();
+    search(&segments, examples, &mut pieces).then_some(Pattern { pieces })
+}
+
+/// Depth-first over the candidates for each output segment, dropping a branch as soon as
+/// its output stops being a prefix of some example's
+fn search(segments: &[Vec<Piece>], examples: &[(String, String)], pieces: &mut Vec<Piece>) -> bool {
+    let Some((candidates, rest)) = segments.split_first() else {
+        return examples.iter().all(|(source, output)| {
+            Pattern {
+                pieces: pieces.clone(),
+            }
+            .apply(source)
+            .is_some_and(|result| result == *output)
+        });
+    };
+    for candidate in candidates {
+        pieces.push(candidate.clone());
+        let consistent = examples.iter().all(|(source, output)| {
+            Pattern {
+                pieces: pieces.clone(),
+            }
+            .apply(source)
+            .is_some_and(|result| output.starts_with(&result))
+        });
+        if consistent && search(rest, examples, pieces) {
+            return true;
+        }
+        pieces.pop();
+    }
+    false
+}
+
+/// The ways of producing one segment of the output, most plausible first
+fn candidates(segment: &str, words: &[&str]) -> Vec<Piece> {
+    let mut candidates = Vec::new();
+    if segment.chars().next().is_some_and(char::is_alphanumeric) {
+        let count = words.len();
+        let indices = (0..count)
+            .map(WordIndex::FromStart)
+            .chain((0..count).map(WordIndex::FromEnd));
+        for index in indices {
+            for case in [Case::Keep, Case::Title, Case::Upper, Case::Lower] {
+                for initial in [false, true] {
+                    let piece = Piece::Word {
+                        index,
+                        case,
+                        initial,
+                    };
+                    if piece.apply(words).as_deref() == Some(segment) {
+                        candidates.push(piece);
+                    }
+                }
+            }
+        }
+    }
+    candidates.push(Piece::Literal(segment.to_string()));
+    candidates
+}
+
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Splits text into alternating runs of word and non-word characters
+fn segments_of(text: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        let is_word = c.is_alphanumeric();
+        if previous.is_some_and(|previous| previous != is_word) {
+            segments.push(&text[start..i]);
+            start = i;
+        }
+        previous = Some(is_word);
+    }
+    if start < text.len() {
+        segments.push(&text[start..]);
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::flash_fill::*;
+
+    fn examples(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(source, output)| (source.to_string(), output.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn infers_from_examples() {
+        let pattern = infer(&examples(&[("Doe, John", "John")])).unwrap();
+        assert_eq!(pattern.apply("Smith, Jane").as_deref(), Some("Jane"));
+
+        let pattern = infer(&examples(&[("john doe", "J. Doe")])).unwrap();
+        assert_eq!(pattern.apply("jane roe").as_deref(), Some("J. Roe"));
+
+        // A second example settles whether it's the second or the last word
+        let pattern = infer(&examples(&[
+            ("Ann Lee", "Lee"),
+            ("Mary Ann Smith", "Smith"),
+        ]))
+        .unwrap();
+        assert_eq!(pattern.apply("Bo Jo Ko").as_deref(), Some("Ko"));
+
+        let pattern = infer(&examples(&[("jo@mail.com", "mail")])).unwrap();
+        assert_eq!(pattern.apply("x").as_deref(), None);
+    }
+}
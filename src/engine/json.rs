@@ -0,0 +1,148 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+/// Records of a JSON array of objects, each flattened to its fields' paths and values, with
+/// every path any record has in the order they first appear
+#[derive(Debug, Default, PartialEq)]
+pub struct JsonTable {
+    pub fields: Vec<String>,
+    pub records: Vec<Vec<(String, String)>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JsonImportError {
+    Invalid(String),
+    /// Valid JSON without an array of objects in it
+    NoRecords,
+}
+
+impl fmt::Display for JsonImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonImportError::Invalid(error) => write!(f, "Not JSON: {}", error),
+            JsonImportError::NoRecords => write!(f, "The JSON has no array of objects to import"),
+        }
+    }
+}
+
+/// Reads the records of a JSON array of objects. An object holding such an array, as APIs
+/// often wrap their results, is read for the first one it holds. Nested objects are
+/// flattened into `parent.child` paths, and arrays kept as JSON text.
+pub fn read_records(text: &str) -> Result<JsonTable, JsonImportError> {
+    let value: Value =
+        serde_json::from_str(text).map_err(|error| JsonImportError::Invalid(error.to_string()))?;
+    let records = find_records(&value).ok_or(JsonImportError::NoRecords)?;
+
+    let mut table = JsonTable::default();
+    for record in records {
+        let mut fields = Vec::new();
+        flatten(record, "", &mut fields);
+        for (path, _) in &fields {
+            if !table.fields.contains(path) {
+                table.fields.push(path.clone());
+            }
+        }
+        table.records.push(fields);
+    }
+    Ok(table)
+}
+
+/// The first array of objects, the value itself or one of its fields
+fn find_records(value: &Value) -> Option<Vec<&Map<String, Value>>> {
+    records_of(value).or_else(|| value.as_object()?.values().find_map(records_of))
+}
+
+/// The objects of an array that has any
+fn records_of(value: &Value) -> Option<Vec<&Map<String, Value>>> {
+    let records = value
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_object)
+        .collect::<Vec<_>>();
+    (!records.is_empty()).then_some(records)
+}
+
+fn flatten(object: &Map<String, Value>, prefix: &str, fields: &mut Vec<(String, String)>) {
+    for (key, value) in object {
+        let path = match prefix.is_empty() {
+            true => key.clone(),
+            false => format!("{}.{}", prefix, key),
+        };
+        match value {
+            Value::Object(child) => flatten(child, &path, fields),
+            Value::Null => fields.push((path, String::new())),
+            Value::Bool(boolean) => fields.push((path, boolean.to_string().to_uppercase())),
+            Value::Number(number) => fields.push((path, number.to_string())),
+            Value::String(text) => fields.push((path, text.clone())),
+            Value::Array(_) => fields.push((path, value.to_string())),
+        }
+    }
+}
+
+impl JsonTable {
+    /// Rows of the chosen fields' values, under a header row of their paths
+    pub fn rows(&self, fields: &[String]) -> Vec<Vec<String>> {
+        let values = self.records.iter().map(|record| {
+            fields
+                .iter()
+                .map(|field| {
+                    record
+                        .iter()
+                        .find(|(path, _)| path == field)
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>()
+        });
+        std::iter::once(fields.to_vec()).chain(values).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::json::*;
+
+    #[test]
+    fn records_flatten_nested_fields_by_path() {
+        let text = r#"{"count": 2, "results": [
+            {"name": "Ada", "address": {"city": "London", "zip": null}, "tags": ["a", "b"]},
+            {"name": "Grace", "active": true, "age": 85}
+        ]}"#;
+        let table = read_records(text).unwrap();
+        assert_eq!(
+            table.fields,
+            vec![
+                "name",
+                "address.city",
+                "address.zip",
+                "tags",
+                "active",
+                "age"
+            ]
+        );
+        let fields = ["name", "age", "address.city", "active"].map(String::from);
+        assert_eq!(
+            table.rows(&fields),
+            vec![
+                vec!["name", "age", "address.city", "active"],
+                vec!["Ada", "", "London", ""],
+                vec!["Grace", "85", "", "TRUE"],
+            ]
+        );
+        assert_eq!(
+            read_records(r#"[{"tags": ["a", "b"]}]"#).unwrap().records,
+            vec![vec![("tags".to_string(), r#"["a","b"]"#.to_string())]]
+        );
+    }
+
+    #[test]
+    fn json_without_records_is_refused() {
+        assert_eq!(read_records(r#"{"a": 1}"#), Err(JsonImportError::NoRecords));
+        assert_eq!(read_records("[1, 2]"), Err(JsonImportError::NoRecords));
+        assert!(matches!(
+            read_records("{"),
+            Err(JsonImportError::Invalid(_))
+        ));
+    }
+}
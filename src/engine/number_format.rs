@@ -0,0 +1,293 @@
+/// An Excel-style number format code like `#,##0.00;[Red](#,##0.00)` or `0.0%`.
+///
+/// Up to four `;`-separated sections format positive numbers, negative numbers, zero and
+/// text. With one section it formats every number, with two the first also formats zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatCode {
+    sections: Vec<Section>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Section {
+    color: Option<&'static str>,
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Number(NumberPattern),
+    /// `@`, the text being formatted
+    Text,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct NumberPattern {
+    /// Integer digits always shown, `0`s before the decimal point
+    min_integer_digits: usize,
+    /// `0`s after the decimal point
+    min_decimals: usize,
+    /// `0`s and `#`s after the decimal point
+    max_decimals: usize,
+    has_point: bool,
+    thousands: bool,
+    /// Each `%` multiplies by 100
+    percent_scale: i32,
+}
+
+/// The formatted text and the color its section asks for, if any
+#[derive(Clone, Debug, PartialEq)]
+pub struct Formatted {
+    pub text: String,
+    pub color: Option<&'static str>,
+}
+
+const COLORS: [(&str, &str); 8] = [
+    ("black", "#000000"),
+    ("blue", "#0000ff"),
+    ("cyan", "#00ffff"),
+    ("green", "#008000"),
+    ("magenta", "#ff00ff"),
+    ("red", "#ff0000"),
+    ("white", "#ffffff"),
+    ("yellow", "#ffff00"),
+];
+
+impl FormatCode {
+    /// None if the code can't be read: unclosed quotes or brackets, unknown colors, more than
+    /// four sections or more than one number in a section
+    pub fn parse(code: &str) -> Option<Self> {
+        let mut sections = Vec::new();
+        let mut section = Section::default();
+        let mut literal = String::new();
+        let mut number: Option<NumberPattern> = None;
+        let mut chars = code.chars().peekable();
+        let mut after_point = false;
+
+        while let Some(c) = chars.next() {
+            match c {
+                ';' => {
+                    finish_section(&mut section, &mut literal, &mut number);
+                    sections.push(std::mem::take(&mut section));
+                    after_point = false;
+                }
+                '"' => {
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '"' {
+                            closed = true;
+                            break;
+                        }
+                        literal.push(c);
+                    }
+                    if !closed {
+                        return None;
+                    }
+                }
+                '\\' => literal.push(chars.next()?),
+                '[' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next()? {
+                            ']' => break,
+                            c => name.push(c),
+                        }
+                    }
+                    let (_, color) = COLORS
+                        .iter()
+                        .find(|(known, _)| known.eq_ignore_ascii_case(&name))?;
+                    section.color = Some(color);
+                }
+                '@' => {
+                    flush_literal(&mut section, &mut literal);
+                    section.parts.push(Part::Text);
+                }
+                '0' | '#' | '?' | '.' | ',' => {
+                    // A comma only groups thousands between digit placeholders
+                    if c == ',' && number.is_none() {
+                        literal.push(c);
+                        continue;
+                    }
+                    if number.is_none() {
+                        if section.parts.iter().any(|p| matches!(p, Part::Number(_))) {
+                            return None;
+                        }
+                        flush_literal(&mut section, &mut literal);
+                        number = Some(NumberPattern::default());
+                        after_point = false;
+                    }
+                    let pattern = number.as_mut()?;
+                    match c {
+                        '.' if !after_point => {
+                            after_point = true;
+                            pattern.has_point = true;
+                        }
+                        '.' => literal.push(c),
+                        ',' => {
+                            let next_is_digit =
+                                matches!(chars.peek(), Some('0') | Some('#') | Some('?'));
+                            if next_is_digit && !after_point {
+                                pattern.thousands = true;
+                            }
+                        }
+                        '0' if after_point => {
+                            pattern.min_decimals = pattern.max_decimals + 1;
+                            pattern.max_decimals += 1;
+                        }
+                        _ if after_point => pattern.max_decimals += 1,
+                        '0' => pattern.min_integer_digits += 1,
+                        _ => {}
+                    }
+                }
+                c => {
+                    close_number(&mut section, &mut number);
+                    literal.push(c);
+                }
+            }
+        }
+        finish_section(&mut section, &mut literal, &mut number);
+        sections.push(section);
+        if sections.len() > 4 {
+            return None;
+        }
+        // A percent sign scales the number wherever it sits in the section
+        for section in &mut sections {
+            let percents = section
+                .parts
+                .iter()
+                .map(|part| match part {
+                    Part::Literal(text) => text.matches('%').count() as i32,
+                    _ => 0,
+                })
+                .sum::<i32>();
+            for part in &mut section.parts {
+                if let Part::Number(pattern) = part {
+                    pattern.percent_scale = percents;
+                }
+            }
+        }
+        Some(FormatCode { sections })
+    }
+
+    /// Formats a cell's value, numbers by their section and anything else by the text
+    /// section if there is one
+    pub fn format(&self, value: &str) -> Formatted {
+        let Ok(number) = value.parse::<f64>() else {
+            return match self.sections.get(3) {
+                Some(section) => section.render(value, None),
+                None => Formatted {
+                    text: value.to_string(),
+                    color: None,
+                },
+            };
+        };
+        let count = self.sections.len();
+        let (section, signed) = match count {
+            1 => (&self.sections[0], number),
+            _ if number < 0.0 => (&self.sections[1], -number),
+            _ if number == 0.0 && count > 2 => (&self.sections[2], number),
+            _ => (&self.sections[0], number),
+        };
+        section.render(value, Some(signed))
+    }
+}
+
+impl Section {
+    fn render(&self, text: &str, number: Option<f64>) -> Formatted {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => output.push_str(literal),
+                Part::Text => output.push_str(text),
+                Part::Number(pattern) => match number {
+                    Some(number) => output.push_str(&pattern.render(number)),
+                    None => output.push_str(text),
+                },
+            }
+        }
+        Formatted {
+            text: output,
+            color: self.color,
+        }
+    }
+}
+
+impl NumberPattern {
+    fn render(&self, number: f64) -> String {
+        let scaled = number * 100f64.powi(self.percent_scale);
+        let text = format!("{:.*}", self.max_decimals, scaled.abs());
+        let (integer, fraction) = match text.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (text.as_str(), ""),
+        };
+        let fraction = fraction.trim_end_matches('0');
+        let fraction = format!("{:0<width$}", fraction, width = self.min_decimals);
+
+        let integer = integer.trim_start_matches('0');
+        let integer = format!("{:0>width$}", integer, width = self.min_integer_digits);
+        let mut grouped = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if self.thousands && i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        if self.has_point && (!fraction.is_empty() || self.max_decimals == 0) {
+            grouped.push('.');
+            grouped.push_str(&fraction);
+        }
+        let is_zero = !text.chars().any(|c| c.is_ascii_digit() && c != '0');
+        match scaled < 0.0 && !is_zero {
+            true => format!("-{}", grouped),
+            false => grouped,
+        }
+    }
+}
+
+fn flush_literal(section: &mut Section, literal: &mut String) {
+    if !literal.is_empty() {
+        section.parts.push(Part::Literal(std::mem::take(literal)));
+    }
+}
+
+fn close_number(section: &mut Section, number: &mut Option<NumberPattern>) {
+    if let Some(pattern) = number.take() {
+        section.parts.push(Part::Number(pattern));
+    }
+}
+
+fn finish_section(section: &mut Section, literal: &mut String, number: &mut Option<NumberPattern>) {
+    close_number(section, number);
+    flush_literal(section, literal);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::number_format::*;
+
+    fn format(code: &str, value: &str) -> (String, Option<&'static str>) {
+        let formatted = FormatCode::parse(code).unwrap().format(value);
+        (formatted.text, formatted.color)
+    }
+
+    #[test]
+    fn formats_by_section() {
+        let code = "#,##0.00;[Red](#,##0.00);\"zero\";\"<\"@\">\"";
+        assert_eq!(format(code, "1234.5"), ("1,234.50".to_string(), None));
+        assert_eq!(
+            format(code, "-1234.5"),
+            ("(1,234.50)".to_string(), Some("#ff0000"))
+        );
+        assert_eq!(format(code, "0"), ("zero".to_string(), None));
+        assert_eq!(format(code, "abc"), ("<abc>".to_string(), None));
+
+        assert_eq!(format("0.0%", "0.1234").0, "12.3%");
+        assert_eq!(format("#.##", "0.5").0, ".5");
+        assert_eq!(format("0.0#", "2").0, "2.0");
+        assert_eq!(format("000", "-7").0, "-007");
+        assert_eq!(format("$#,##0", "1234567").0, "$1,234,567");
+
+        assert_eq!(FormatCode::parse("0.0\"unclosed"), None);
+        assert_eq!(FormatCode::parse("[Purple]0"), None);
+    }
+}
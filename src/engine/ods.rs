@@ -0,0 +1,510 @@
+use crate::{
+    engine::{editing, zip},
+    model::{properties::DocumentProperties, workbook::quote_sheet_name},
+};
+
+pub const ODS_MIME_TYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+/// The most characters a cell's text is read to, as in Excel. Spaces written as a count
+/// past it are left out.
+const MAX_CELL_TEXT: usize = 32_767;
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+ <manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>
+ <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+ <manifest:file-entry manifest:full-path="meta.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+const CONTENT_START: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:of="urn:oasis:names:tc:opendocument:xmlns:of:1.2" office:version="1.2"><office:body><office:spreadsheet>"#;
+
+const CONTENT_END: &str = "</office:spreadsheet></office:body></office:document-content>";
+
+const META_START: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-meta xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0" xmlns:dc="http://purl.org/dc/elements/1.1/" office:version="1.2"><office:meta><meta:generator>RustSpreadsheets</meta:generator>"#;
+
+const META_END: &str = "</office:meta></office:document-meta>";
+
+/// A sheet of an OpenDocument spreadsheet, its cells as they'd be typed here
+#[derive(Debug, PartialEq)]
+pub struct OdsTable {
+    pub name: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A sheet to write, each cell's content along with the value it shows
+pub struct OdsSheet {
+    pub name: String,
+    pub rows: Vec<Vec<(String, String)>>,
+}
+
+/// An OpenDocument spreadsheet of the sheets, formulas written in OpenFormula with the
+/// values they last calculated, and the workbook's properties in its metadata
+pub fn write_ods(sheets: &[OdsSheet], properties: &DocumentProperties) -> Vec<u8> {
+    let mut content = CONTENT_START.to_string();
+    for sheet in sheets {
+        content.push_str(&format!(
+            r#"<table:table table:name="{}">"#,
+            escape_xml(&sheet.name)
+        ));
+        for row in &sheet.rows {
+            content.push_str("<table:table-row>");
+            for (cell_content, value) in row {
+                content.push_str(&write_cell(cell_content, value));
+            }
+            content.push_str("</table:table-row>");
+        }
+        content.push_str("</table:table>");
+    }
+    content.push_str(CONTENT_END);
+
+    zip::write_zip(&[
+        ("mimetype", ODS_MIME_TYPE.as_bytes()),
+        ("META-INF/manifest.xml", MANIFEST.as_bytes()),
+        ("content.xml", content.as_bytes()),
+        ("meta.xml", write_meta(properties).as_bytes()),
+    ])
+}
+
+fn write_meta(properties: &DocumentProperties) -> String {
+    let mut meta = META_START.to_string();
+    let fields = [
+        ("dc:title", &properties.title),
+        ("meta:initial-creator", &properties.author),
+        ("dc:creator", &properties.author),
+        ("dc:description", &properties.description),
+    ];
+    for (tag, value) in fields {
+        if !value.is_empty() {
+            meta.push_str(&format!("<{tag}>{}</{tag}>", escape_xml(value)));
+        }
+    }
+    for (name, value) in &properties.custom {
+        meta.push_str(&format!(
+            r#"<meta:user-defined meta:name="{}">{}</meta:user-defined>"#,
+            escape_xml(name),
+            escape_xml(value)
+        ));
+    }
+    meta.push_str(META_END);
+    meta
+}
+
+fn write_cell(content: &str, value: &str) -> String {
+    if content.is_empty() {
+        return "<table:table-cell/>".to_string();
+    }
+    let formula = match content.starts_with('=') {
+        true => format!(
+            r#" table:formula="{}""#,
+            escape_xml(&to_open_formula(content))
+        ),
+        false => String::new(),
+    };
+    let value_type = match value.parse::<f64>() {
+        Ok(number) => format!(r#"office:value-type="float" office:value="{}""#, number),
+        Err(_) => r#"office:value-type="string""#.to_string(),
+    };
+    format!(
+        "<table:table-cell{} {}><text:p>{}</text:p></table:table-cell>",
+        formula,
+        value_type,
+        escape_xml(value)
+    )
+}
+
+/// A formula in OpenFormula, as `table:formula` holds it: `=A1+Sheet2!B2:C3` becomes
+/// `of:=[.A1]+[$Sheet2.B2:.C3]`, and arguments are split by semicolons
+pub fn to_open_formula(content: &str) -> String {
+    let replaced = editing::replace_references(content, |span| {
+        let sheet = span
+            .sheet
+            .as_deref()
+            .map(|name| format!("${}", quote_sheet_name(name)))
+            .unwrap_or_default();
+        Some(match span.is_range() {
+            true => format!("[{}.{}:.{}]", sheet, span.first, span.last),
+            false => format!("[{}.{}]", sheet, span.first),
+        })
+    });
+    format!("of:{}", outside_quotes(&replaced, ',', ';'))
+}
+
+/// A formula read from OpenFormula, or None if it's in another syntax
+pub fn from_open_formula(formula: &str) -> Option<String> {
+    let formula = formula.strip_prefix("of:").unwrap_or(formula);
+    if !formula.starts_with('=') {
+        return None;
+    }
+    let mut content = String::new();
+    let mut rest = formula;
+    while let Some(start) = rest.find('[') {
+        let end = start + rest[start..].find(']')?;
+        content.push_str(&rest[..start]);
+        let parts = rest[start + 1..end]
+            .split(':')
+            .map(|part| {
+                let (sheet, cell) = part.rsplit_once('.')?;
+                let sheet = sheet.trim_start_matches('$').trim_matches('\'');
+                Some((sheet, cell))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let (sheet, first) = parts.first()?;
+        if !sheet.is_empty() {
+            content.push_str(&format!("{}!", quote_sheet_name(sheet)));
+        }
+        content.push_str(first);
+        if let Some((_, last)) = parts.get(1) {
+            content.push_str(&format!(":{}", last));
+        }
+        rest = &rest[end + 1..];
+    }
+    content.push_str(rest);
+    Some(outside_quotes(&content, ';', ','))
+}
+
+/// Replaces a character everywhere but inside quoted names or text
+fn outside_quotes(text: &str, from: char, to: char) -> String {
+    let mut quote = None;
+    text.chars()
+        .map(|c| match (quote, c) {
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                c
+            }
+            (Some(open), c) if c == open => {
+                quote = None;
+                c
+            }
+            (None, c) if c == from => to,
+            (_, c) => c,
+        })
+        .collect()
+}
+
+/// The tables of a spreadsheet's `content.xml`, without the empty rows and cells that
+/// pad them out. Rows and cells past the sheet's `row_count` and `column_count` are left
+/// out, however many times they're repeated.
+pub fn read_content(xml: &str, row_count: usize, column_count: usize) -> Vec<OdsTable> {
+    let mut tables = Vec::new();
+    let mut table: Option<OdsTable> = None;
+    // Rows of the current table, and empty ones waiting on one with something in it
+    let mut empty_rows: usize = 0;
+    let mut row: Option<(Vec<String>, usize)> = None;
+    let mut empty_cells: usize = 0;
+    let mut cell: Option<CellReader> = None;
+
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        if let Some(cell) = cell.as_mut().filter(|cell| cell.paragraphs > 0) {
+            cell.text.push_str(&unescape_xml(&rest[..start]));
+        }
+        let Some(end) = rest[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        let tag = Tag::parse(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+
+        match (tag.name, tag.kind) {
+            ("table:table", TagKind::Open) => {
+                table = Some(OdsTable {
+                    name: tag.attribute("table:name").unwrap_or_default(),
+                    rows: Vec::new(),
+                });
+                empty_rows = 0;
+            }
+            ("table:table", TagKind::Close) => tables.extend(table.take()),
+            ("table:table-row", TagKind::Open | TagKind::Empty) => {
+                row = Some((Vec::new(), tag.repeat("table:number-rows-repeated")));
+                empty_cells = 0;
+                if tag.kind == TagKind::Empty {
+                    let repeat = row.take().map_or(0, |(_, repeat)| repeat);
+                    empty_rows = empty_rows.saturating_add(repeat);
+                }
+            }
+            ("table:table-row", TagKind::Close) => {
+                let (Some(table), Some((cells, repeat))) = (table.as_mut(), row.take()) else {
+                    continue;
+                };
+                if cells.is_empty() {
+                    empty_rows = empty_rows.saturating_add(repeat);
+                    continue;
+                }
+                let room = row_count.saturating_sub(table.rows.len());
+                let padding = empty_rows.min(room);
+                table.rows.extend(std::iter::repeat_n(Vec::new(), padding));
+                table
+                    .rows
+                    .extend(std::iter::repeat_n(cells, repeat.min(room - padding)));
+                empty_rows = 0;
+            }
+            ("table:table-cell" | "table:covered-table-cell", kind) => {
+                if kind == TagKind::Close {
+                    if let (Some(reader), Some((cells, _))) = (cell.take(), row.as_mut()) {
+                        reader.finish(cells, &mut empty_cells, column_count);
+                    }
+                    continue;
+                }
+                let reader = CellReader::new(&tag);
+                match kind {
+                    TagKind::Open => cell = Some(reader),
+                    _ => {
+                        if let Some((cells, _)) = row.as_mut() {
+                            reader.finish(cells, &mut empty_cells, column_count);
+                        }
+                    }
+                }
+            }
+            ("text:p", TagKind::Open) => {
+                if let Some(cell) = cell.as_mut() {
+                    if cell.paragraphs > 0 {
+                        cell.text.push('\n');
+                    }
+                    cell.paragraphs += 1;
+                }
+            }
+            ("text:s", _) => {
+                if let Some(cell) = cell.as_mut() {
+                    let room = MAX_CELL_TEXT.saturating_sub(cell.text.len());
+                    let spaces = tag.repeat("text:c").min(room);
+                    cell.text.push_str(&" ".repeat(spaces));
+                }
+            }
+            ("text:tab", _) => cell.iter_mut().for_each(|cell| cell.text.push('\t')),
+            ("text:line-break", _) => cell.iter_mut().for_each(|cell| cell.text.push('\n')),
+            _ => {}
+        }
+    }
+    tables
+}
+
+/// A cell being read, its text gathered from its paragraphs
+struct CellReader {
+    formula: Option<String>,
+    value: Option<String>,
+    repeat: usize,
+    text: String,
+    paragraphs: usize,
+}
+
+impl CellReader {
+    fn new(tag: &Tag) -> Self {
+        let value = match tag.attribute("office:value-type").as_deref() {
+            Some("float" | "percentage" | "currency") => tag.attribute("office:value"),
+            Some("date") => tag.attribute("office:date-value"),
+            Some("boolean") => tag
+                .attribute("office:boolean-value")
+                .map(|value| value.to_uppercase()),
+            _ => None,
+        };
+        CellReader {
+            formula: tag.attribute("table:formula"),
+            value,
+            repeat: tag.repeat("table:number-columns-repeated"),
+            text: String::new(),
+            paragraphs: 0,
+        }
+    }
+
+    /// Adds the cell to its row, empty ones only once something comes after them, up to
+    /// `column_count` cells
+    fn finish(self, cells: &mut Vec<String>, empty_cells: &mut usize, column_count: usize) {
+        let content = self
+            .formula
+            .as_deref()
+            .and_then(from_open_formula)
+            .or(self.value)
+            .unwrap_or(self.text);
+        if content.is_empty() {
+            *empty_cells = empty_cells.saturating_add(self.repeat);
+            return;
+        }
+        let room = column_count.saturating_sub(cells.len());
+        let padding = (*empty_cells).min(room);
+        cells.extend(std::iter::repeat_n(String::new(), padding));
+        cells.extend(std::iter::repeat_n(
+            content,
+            self.repeat.min(room - padding),
+        ));
+        *empty_cells = 0;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TagKind {
+    Open,
+    Close,
+    /// Self-closing, like `<table:table-cell/>`
+    Empty,
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    kind: TagKind,
+    attributes: &'a str,
+}
+
+impl<'a> Tag<'a> {
+    fn parse(inside: &'a str) -> Self {
+        let (kind, inside) = match (inside.strip_prefix('/'), inside.strip_suffix('/')) {
+            (Some(inside), _) => (TagKind::Close, inside),
+            (None, Some(inside)) => (TagKind::Empty, inside),
+            (None, None) => (TagKind::Open, inside),
+        };
+        let (name, attributes) = inside
+            .split_once(char::is_whitespace)
+            .unwrap_or((inside, ""));
+        Tag {
+            name,
+            kind,
+            attributes,
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<String> {
+        let mut rest = self.attributes;
+        while let Some(equals) = rest.find('=') {
+            let key = rest[..equals].trim();
+            let after = rest[equals + 1..].trim_start();
+            let quote = after.chars().next()?;
+            let end = after[1..].find(quote)? + 1;
+            if key == name {
+                return Some(unescape_xml(&after[1..end]));
+            }
+            rest = &after[end + 1..];
+        }
+        None
+    }
+
+    fn repeat(&self, name: &str) -> usize {
+        self.attribute(name)
+            .and_then(|repeat| repeat.parse().ok())
+            .unwrap_or(1)
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape_xml(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find(';').map(|end| start + end) else {
+            break;
+        };
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|decimal| decimal.parse()))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => result.push(c),
+            None => result.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::ods::*;
+
+    #[test]
+    fn formulas_translate_both_ways() {
+        let content = "=SUM(A1:B2,'My Sheet'!C3)*$D$4";
+        let formula = to_open_formula(content);
+        assert_eq!(formula, "of:=SUM([.A1:.B2];[$'My Sheet'.C3])*[.$D$4]");
+        assert_eq!(from_open_formula(&formula).as_deref(), Some(content));
+        assert_eq!(from_open_formula("msoxl:A1"), None);
+    }
+
+    #[test]
+    fn written_spreadsheets_read_back() {
+        let sheet = OdsSheet {
+            name: "Costs & fees".to_string(),
+            rows: vec![
+                vec![
+                    ("Item".to_string(), "Item".to_string()),
+                    (String::new(), String::new()),
+                    ("<b>".to_string(), "<b>".to_string()),
+                ],
+                vec![
+                    ("2".to_string(), "2".to_string()),
+                    ("=A2*3".to_string(), "6".to_string()),
+                ],
+            ],
+        };
+        let mut properties = DocumentProperties {
+            title: "Q1 <draft>".to_string(),
+            ..Default::default()
+        };
+        properties
+            .custom
+            .insert("Owner".to_string(), "Finance".to_string());
+        let archive = write_ods(&[sheet], &properties);
+        let meta = zip::find_entry(&archive, "meta.xml").unwrap();
+        let meta = std::str::from_utf8(meta.data).unwrap();
+        assert!(meta.contains("<dc:title>Q1 &lt;draft&gt;</dc:title>"));
+        assert!(
+            meta.contains(r#"<meta:user-defined meta:name="Owner">Finance</meta:user-defined>"#)
+        );
+        assert!(!meta.contains("dc:creator"));
+
+        let entry = zip::find_entry(&archive, "content.xml").unwrap();
+        let tables = read_content(std::str::from_utf8(entry.data).unwrap(), 100, 26);
+        assert_eq!(
+            tables,
+            [OdsTable {
+                name: "Costs & fees".to_string(),
+                rows: vec![vec!["Item", "", "<b>"], vec!["2", "=A2*3"]]
+                    .into_iter()
+                    .map(|row| row.into_iter().map(String::from).collect())
+                    .collect(),
+            }]
+        );
+    }
+
+    #[test]
+    fn repeats_expand_and_padding_is_dropped() {
+        let xml = r#"<table:table table:name="S"><table:table-row><table:table-cell table:number-columns-repeated="2" office:value-type="float" office:value="1"><text:p>1</text:p></table:table-cell><table:table-cell table:number-columns-repeated="1020"/></table:table-row><table:table-row table:number-rows-repeated="2"><table:table-cell/></table:table-row><table:table-row><table:table-cell office:value-type="string"><text:p>a<text:s text:c="2"/>b</text:p><text:p>c</text:p></table:table-cell></table:table-row><table:table-row table:number-rows-repeated="1048570"><table:table-cell table:number-columns-repeated="1024"/></table:table-row></table:table>"#;
+        let table = &read_content(xml, 100, 26)[0];
+        assert_eq!(table.rows.len(), 4);
+        assert_eq!(table.rows[0], ["1", "1"]);
+        assert!(table.rows[1].is_empty());
+        assert_eq!(table.rows[3], ["a  b\nc"]);
+    }
+
+    #[test]
+    fn repeats_stop_at_the_sheets_edges() {
+        let xml = r#"<table:table table:name="S"><table:table-row table:number-rows-repeated="1000000000"/><table:table-row table:number-rows-repeated="1000000000"><table:table-cell table:number-columns-repeated="1000000000"/><table:table-cell table:number-columns-repeated="1000000000" office:value-type="float" office:value="1"><text:p>1</text:p></table:table-cell></table:table-row></table:table><table:table table:name="T"><table:table-row><table:table-cell table:number-columns-repeated="3" office:value-type="string"><text:p>a<text:s text:c="1000000000"/>b</text:p></table:table-cell></table:table-row></table:table>"#;
+        let tables = read_content(xml, 100, 26);
+        assert_eq!(tables[0].rows.len(), 100);
+        assert!(tables[0].rows.iter().all(|row| row.is_empty()));
+        assert_eq!(tables[1].rows[0].len(), 3);
+        assert_eq!(tables[1].rows[0][0].len(), MAX_CELL_TEXT + 1);
+
+        let xml = r#"<table:table table:name="S"><table:table-row table:number-rows-repeated="10000"><table:table-cell table:number-columns-repeated="10000" office:value-type="float" office:value="1"><text:p>1</text:p></table:table-cell></table:table-row></table:table>"#;
+        let table = &read_content(xml, 100, 26)[0];
+        assert_eq!(table.rows.len(), 100);
+        assert_eq!(table.rows[99], vec!["1"; 26]);
+    }
+}
@@ -1,6 +1,11 @@
 use std::{collections::HashMap, fmt};
 
-use pest::{iterators::Pairs, pratt_parser::PrattParser, Parser};
+use pest::{
+    error::{Error, ErrorVariant, InputLocation},
+    iterators::Pairs,
+    pratt_parser::PrattParser,
+    Parser,
+};
 use pest_derive::Parser;
 
 use crate::model::grid::GetCellValueError;
@@ -8,34 +13,142 @@ use crate::model::grid::GetCellValueError;
 #[derive(Debug, PartialEq)]
 pub enum FormulaError {
     ParsingError,
+    SyntaxError(ParseDiagnostic),
     DivBy0,
     UnknownFunction,
     CircularReference,
+    InvalidReference,
+    /// A QUERY that can't be read or asks for columns it can't have
+    InvalidQuery,
+    /// A returned table with cells in its way
+    Spill,
+    /// A formula whose data is still being fetched
+    Busy,
+    /// A formula whose data couldn't be fetched
+    Connect,
+    /// A value asked for that there isn't, like a document property never set
+    NotAvailable,
+    /// A function returning text or a table, which only works as the whole formula, used
+    /// inside another
+    Standalone,
 }
 
 impl fmt::Display for FormulaError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             FormulaError::ParsingError => write!(f, "#NAME?"),
+            FormulaError::SyntaxError(_) => write!(f, "#NAME?"),
             FormulaError::DivBy0 => write!(f, "#DIV/0!"),
             FormulaError::UnknownFunction => write!(f, "#NAME?"),
             FormulaError::CircularReference => write!(f, "#REF!"),
+            FormulaError::InvalidReference => write!(f, "#REF!"),
+            FormulaError::InvalidQuery => write!(f, "#VALUE!"),
+            FormulaError::Spill => write!(f, "#SPILL!"),
+            FormulaError::Busy => write!(f, "#BUSY!"),
+            FormulaError::Connect => write!(f, "#CONNECT!"),
+            FormulaError::NotAvailable => write!(f, "#N/A"),
+            FormulaError::Standalone => write!(f, "#VALUE!"),
         }
     }
 }
 
+/// Where and why a formula failed to parse.
+/// `position` is the byte offset into the formula, including the leading `=`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseDiagnostic {
+    pub position: usize,
+    pub expected: Vec<String>,
+}
+
+impl From<&Error<Rule>> for ParseDiagnostic {
+    fn from(err: &Error<Rule>) -> Self {
+        let position = match err.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+        let mut expected: Vec<String> = Vec::new();
+        if let ErrorVariant::ParsingError { positives, .. } = &err.variant {
+            for rule in positives {
+                let description = describe_rule(rule).to_string();
+                if !expected.contains(&description) {
+                    expected.push(description);
+                }
+            }
+        }
+        ParseDiagnostic { position, expected }
+    }
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.expected.is_empty() {
+            write!(f, "Unexpected character at column {}", self.position + 1)
+        } else {
+            write!(
+                f,
+                "Expected {} at column {}",
+                self.expected.join(", "),
+                self.position + 1
+            )
+        }
+    }
+}
+
+fn describe_rule(rule: &Rule) -> &'static str {
+    match rule {
+        Rule::expr => "expression",
+        Rule::number => "number",
+        Rule::cell_ref => "cell reference",
+        Rule::ref_error => "#REF!",
+        Rule::func => "function",
+        Rule::func_name => "function name",
+        Rule::func_args => "function arguments",
+        Rule::neg | Rule::sub => "-",
+        Rule::add => "+",
+        Rule::mul => "*",
+        Rule::div => "/",
+        Rule::pow => "^",
+        Rule::EOI => "end of formula",
+        _ => "token",
+    }
+}
+
+/// Looks up the cells formulas refer to
+pub trait CellResolver {
+    fn value(&self, reference: &str) -> Result<f64, GetCellValueError>;
+
+    /// Whether the referenced cell is in a row a filter hides, for SUBTOTAL to skip it
+    fn is_filtered(&self, _reference: &str) -> bool {
+        false
+    }
+
+    /// Whether the referenced cell is in a row hidden by collapsing an outline group, for
+    /// SUBTOTAL's 101 to 109 to skip it
+    fn is_hidden(&self, _reference: &str) -> bool {
+        false
+    }
+}
+
+impl<F: Fn(&str) -> Result<f64, GetCellValueError>> CellResolver for F {
+    fn value(&self, reference: &str) -> Result<f64, GetCellValueError> {
+        self(reference)
+    }
+}
+
 pub fn calculate(
     input: &str,
-    cell_ref_resolver: &impl Fn(&str) -> Result<f64, GetCellValueError>,
+    cell_ref_resolver: &impl CellResolver,
 ) -> Result<(f64, Vec<String>), FormulaError> {
-    match CellFormulaParser::parse(Rule::formula, input) {
-        Ok(mut pairs) => {
-            let expr = parse_expr(pairs.next().unwrap().into_inner());
-            let evaluated_expr = eval_expr(&expr, cell_ref_resolver)?;
-            Ok((evaluated_expr, eval_deps(&expr)))
-        }
-        Err(_) => Err(FormulaError::ParsingError),
-    }
+    let expr = parse_formula(input)?;
+    let evaluated_expr = eval_expr(&expr, cell_ref_resolver)?;
+    Ok((evaluated_expr, eval_deps(&expr)))
+}
+
+/// The references a formula makes, whether or not it evaluates. None if it doesn't parse.
+pub fn references(input: &str) -> Vec<String> {
+    parse_formula(input)
+        .map(|expr| eval_deps(&expr))
+        .unwrap_or_default()
 }
 
 #[derive(Parser)]
@@ -44,6 +157,21 @@ struct CellFormulaParser;
 
 type SpreadsheetFunction = fn(&[f64]) -> Result<f64, FormulaError>;
 
+struct FunctionDefinition {
+    /// None for functions calculated as a whole formula rather than by the parser, since
+    /// their arguments or results aren't numbers
+    func: Option<SpreadsheetFunction>,
+    signature: &'static str,
+    description: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionHint {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+}
+
 lazy_static::lazy_static! {
     static ref PRATT_PARSER: PrattParser<Rule> = {
         use pest::pratt_parser::{Assoc::*, Op};
@@ -54,14 +182,63 @@ lazy_static::lazy_static! {
             .op(Op::infix(pow, Right))
             .op(Op::prefix(neg))
     };
-    static ref FUNCTION_REGISTRY: HashMap<&'static str, SpreadsheetFunction> = {
+    static ref FUNCTION_REGISTRY: HashMap<&'static str, FunctionDefinition> = {
         let mut m = HashMap::new();
-        m.insert("SUM", sum as SpreadsheetFunction);
-        m.insert("AVG", avg as SpreadsheetFunction);
+        m.insert("SUM", FunctionDefinition {
+            func: Some(sum),
+            signature: "SUM(value, ...)",
+            description: "Adds up its arguments",
+        });
+        m.insert("AVG", FunctionDefinition {
+            func: Some(avg),
+            signature: "AVG(value, ...)",
+            description: "Arithmetic mean of its arguments",
+        });
+        m.insert("SUBTOTAL", FunctionDefinition {
+            func: Some(subtotal),
+            signature: "SUBTOTAL(function, value, ...)",
+            description: "Average (1), max (4), min (5) or sum (9) of the values in rows filters leave showing",
+        });
+        m.insert("INFO", FunctionDefinition {
+            func: None,
+            signature: "INFO(\"name\")",
+            description: "A property of the workbook, such as its title or author, as the whole formula",
+        });
+        m.insert("IMPORTCSV", FunctionDefinition {
+            func: None,
+            signature: "IMPORTCSV(url)",
+            description: "Table of the CSV file at a URL, as the whole formula",
+        });
+        m.insert("WEBSERVICE", FunctionDefinition {
+            func: None,
+            signature: "WEBSERVICE(url)",
+            description: "Text a URL responds with, as the whole formula",
+        });
+        m.insert("QUERY", FunctionDefinition {
+            func: None,
+            signature: "QUERY(range, \"query\")",
+            description: "Table of a range's rows picked, grouped and sorted by a query, as the whole formula",
+        });
         m
     };
 }
 
+/// Registered functions whose name starts with `prefix` (case insensitive), sorted by name
+pub fn matching_functions(prefix: &str) -> Vec<FunctionHint> {
+    let prefix = prefix.to_uppercase();
+    let mut hints = FUNCTION_REGISTRY
+        .iter()
+        .filter(|(name, _)| name.starts_with(&prefix))
+        .map(|(name, definition)| FunctionHint {
+            name,
+            signature: definition.signature,
+            description: definition.description,
+        })
+        .collect::<Vec<_>>();
+    hints.sort_by_key(|hint| hint.name);
+    hints
+}
+
 fn sum(args: &[f64]) -> Result<f64, FormulaError> {
     Ok(args.iter().sum())
 }
@@ -73,10 +250,44 @@ fn avg(args: &[f64]) -> Result<f64, FormulaError> {
     Ok(args.iter().sum::<f64>() / args.len() as f64)
 }
 
-#[derive(Debug)]
+/// The first argument picks the function, as in Excel. Rows a filter hides are always left
+/// out, and 101 to 109 leave out those in collapsed outline groups too.
+fn subtotal(args: &[f64]) -> Result<f64, FormulaError> {
+    let (function, values) = args.split_first().ok_or(FormulaError::UnknownFunction)?;
+    let extreme = |pick: fn(f64, f64) -> f64| values.iter().copied().reduce(pick).unwrap_or(0.0);
+    match *function as i64 % 100 {
+        1 => avg(values),
+        4 => Ok(extreme(f64::max)),
+        5 => Ok(extreme(f64::min)),
+        9 => sum(values),
+        _ => Err(FormulaError::UnknownFunction),
+    }
+}
+
+/// SUBTOTAL's references to hidden rows, which it leaves out
+fn filtered_args(name: &str, args: &[Expr], cell_ref_resolver: &impl CellResolver) -> Vec<usize> {
+    if !name.eq_ignore_ascii_case("SUBTOTAL") {
+        return Vec::new();
+    }
+    let skips_hidden = args
+        .first()
+        .and_then(|function| eval_expr(function, cell_ref_resolver).ok())
+        .is_some_and(|function| function >= 100.0);
+    let is_skipped = |reference: &str| {
+        cell_ref_resolver.is_filtered(reference)
+            || (skips_hidden && cell_ref_resolver.is_hidden(reference))
+    };
+    (1..args.len())
+        .filter(|i| matches!(&args[*i], Expr::CellRef(cr) if is_skipped(cr)))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
 enum Expr {
     Number(f64),
     CellRef(String),
+    /// A reference whose target no longer exists, e.g. a cell on a deleted sheet
+    RefError,
     // Range(String, String),
     BinaryOp {
         op: BinOp,
@@ -95,7 +306,7 @@ enum Expr {
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum BinOp {
     Add,
     Sub,
@@ -104,7 +315,7 @@ enum BinOp {
     Pow,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum UnOp {
     Neg,
 }
@@ -114,6 +325,7 @@ fn parse_expr(pairs: Pairs<Rule>) -> Expr {
         .map_primary(|primary| match primary.as_rule() {
             Rule::number => Expr::Number(primary.as_str().parse().unwrap()),
             Rule::cell_ref => Expr::CellRef(primary.as_str().to_string()),
+            Rule::ref_error => Expr::RefError,
             Rule::func => {
                 let mut inner = primary.into_inner();
                 let name = inner.next().unwrap().as_str().to_string();
@@ -157,17 +369,11 @@ fn parse_expr(pairs: Pairs<Rule>) -> Expr {
         .parse(pairs)
 }
 
-fn eval_expr(
-    expr: &Expr,
-    cell_ref_resolver: &impl Fn(&str) ->  Result<f64, GetCellValueError>,
-) -> Result<f64, FormulaError> {
+fn eval_expr(expr: &Expr, cell_ref_resolver: &impl CellResolver) -> Result<f64, FormulaError> {
     match expr {
         Expr::Number(n) => Ok(*n),
-        Expr::CellRef(cr) => match cell_ref_resolver(cr) {
-            Ok(value) => Ok(value),
-            Err(GetCellValueError::CircularReference) => Err(FormulaError::CircularReference),
-            Err(_) => Err(FormulaError::ParsingError),
-        },
+        Expr::CellRef(cr) => resolve_cell_ref(cr, cell_ref_resolver),
+        Expr::RefError => Err(FormulaError::InvalidReference),
         // Expr::Range(_c1, _c2) => unimplemented!(),
         Expr::BinaryOp { op, lhs, rhs } => {
             let lval = self::eval_expr(lhs, cell_ref_resolver)?;
@@ -179,19 +385,72 @@ fn eval_expr(
             Ok(eval_unary_op(op, val))
         }
         Expr::Function { name, args } => {
+            let filtered = filtered_args(name, args, cell_ref_resolver);
             let args = args
                 .iter()
-                .map(|arg| eval_expr(arg, cell_ref_resolver))
+                .enumerate()
+                .filter(|(i, _)| !filtered.contains(i))
+                .map(|(_, arg)| eval_expr(arg, cell_ref_resolver))
                 .collect::<Result<Vec<f64>, FormulaError>>()?;
-            let func = FUNCTION_REGISTRY
-                .get(name.to_uppercase().as_str())
-                .ok_or(FormulaError::UnknownFunction)?;
-
-            func(&args)
+            call_function(name, &args)
         }
     }
 }
 
+fn call_function(name: &str, args: &[f64]) -> Result<f64, FormulaError> {
+    let definition = FUNCTION_REGISTRY
+        .get(name.to_uppercase().as_str())
+        .ok_or(FormulaError::UnknownFunction)?;
+    let func = definition.func.ok_or(FormulaError::Standalone)?;
+    func(args)
+}
+
+/// The text argument of a function calculated as the whole formula
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextArgument {
+    /// A quoted string, its quotes doubled inside
+    Literal(String),
+    /// A cell reference, for the text the cell shows
+    Reference(String),
+}
+
+/// Reads an argument as a quoted string or a cell reference, None if it's anything else
+pub fn parse_text_argument(argument: &str) -> Option<TextArgument> {
+    let argument = argument.trim();
+    if let Some(text) = argument
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return Some(TextArgument::Literal(text.replace("\"\"", "\"")));
+    }
+    let reference = CellFormulaParser::parse(Rule::cell_ref, argument).ok()?;
+    (reference.as_str() == argument).then(|| TextArgument::Reference(argument.to_string()))
+}
+
+/// The registered function a formula is a call to, if it's one calculated as the whole
+/// formula rather than step by step
+pub fn standalone_function(content: &str) -> Option<&'static str> {
+    let content = content.trim().strip_prefix('=')?.trim_start();
+    let (name, _) = content.split_once('(')?;
+    let name = name.trim_end().to_uppercase();
+    FUNCTION_REGISTRY
+        .get_key_value(name.as_str())
+        .filter(|(_, definition)| definition.func.is_none())
+        .map(|(name, _)| *name)
+}
+
+fn resolve_cell_ref(
+    cell_ref: &str,
+    cell_ref_resolver: &impl CellResolver,
+) -> Result<f64, FormulaError> {
+    match cell_ref_resolver.value(cell_ref) {
+        Ok(value) => Ok(value),
+        Err(GetCellValueError::CircularReference) => Err(FormulaError::CircularReference),
+        Err(GetCellValueError::UnknownSheet) => Err(FormulaError::InvalidReference),
+        Err(_) => Err(FormulaError::ParsingError),
+    }
+}
+
 fn eval_binary_op(op: &BinOp, lhs: f64, rhs: f64) -> Result<f64, FormulaError> {
     match op {
         BinOp::Add => Ok(lhs + rhs),
@@ -213,9 +472,196 @@ fn eval_unary_op(op: &UnOp, operand: f64) -> f64 {
     }
 }
 
+/// Successive forms of a formula as its sub-expressions are evaluated one at a time,
+/// leftmost innermost first. The last step is the result, or the error that stopped evaluation.
+pub fn evaluation_steps(
+    input: &str,
+    cell_ref_resolver: &impl CellResolver,
+) -> Result<Vec<String>, FormulaError> {
+    let mut expr = parse_formula(input)?;
+    let mut steps = vec![expr.to_string()];
+    loop {
+        match reduce_expr(&expr, cell_ref_resolver) {
+            Ok(Some(reduced)) => {
+                expr = reduced;
+                steps.push(expr.to_string());
+            }
+            Ok(None) => return Ok(steps),
+            Err(e) => {
+                steps.push(e.to_string());
+                return Ok(steps);
+            }
+        }
+    }
+}
+
+/// Syntax tree of a formula as (depth, label) pairs in depth-first order
+pub fn formula_outline(input: &str) -> Result<Vec<(usize, String)>, FormulaError> {
+    let expr = parse_formula(input)?;
+    let mut outline = Vec::new();
+    outline_expr(&expr, 0, &mut outline);
+    Ok(outline)
+}
+
+fn parse_formula(input: &str) -> Result<Expr, FormulaError> {
+    match CellFormulaParser::parse(Rule::formula, input) {
+        Ok(mut pairs) => Ok(parse_expr(pairs.next().unwrap().into_inner())),
+        Err(err) => Err(FormulaError::SyntaxError(ParseDiagnostic::from(&err))),
+    }
+}
+
+/// Evaluates the leftmost innermost reducible part of `expr`, or returns None if it's already a number
+fn reduce_expr(
+    expr: &Expr,
+    cell_ref_resolver: &impl CellResolver,
+) -> Result<Option<Expr>, FormulaError> {
+    match expr {
+        Expr::Number(_) => Ok(None),
+        Expr::CellRef(cr) => Ok(Some(Expr::Number(resolve_cell_ref(cr, cell_ref_resolver)?))),
+        Expr::RefError => Err(FormulaError::InvalidReference),
+        Expr::BinaryOp { op, lhs, rhs } => {
+            if let Some(lhs) = reduce_expr(lhs, cell_ref_resolver)? {
+                return Ok(Some(Expr::BinaryOp {
+                    op: *op,
+                    lhs: Box::new(lhs),
+                    rhs: rhs.clone(),
+                }));
+            }
+            if let Some(rhs) = reduce_expr(rhs, cell_ref_resolver)? {
+                return Ok(Some(Expr::BinaryOp {
+                    op: *op,
+                    lhs: lhs.clone(),
+                    rhs: Box::new(rhs),
+                }));
+            }
+            let (Expr::Number(lval), Expr::Number(rval)) = (lhs.as_ref(), rhs.as_ref()) else {
+                unreachable!("Irreducible operands are numbers");
+            };
+            Ok(Some(Expr::Number(eval_binary_op(op, *lval, *rval)?)))
+        }
+        Expr::UnaryOp { op, operand } => {
+            if let Some(operand) = reduce_expr(operand, cell_ref_resolver)? {
+                return Ok(Some(Expr::UnaryOp {
+                    op: *op,
+                    operand: Box::new(operand),
+                }));
+            }
+            let Expr::Number(val) = operand.as_ref() else {
+                unreachable!("Irreducible operand is a number");
+            };
+            Ok(Some(Expr::Number(eval_unary_op(op, *val))))
+        }
+        Expr::Function { name, args } => {
+            let filtered = filtered_args(name, args, cell_ref_resolver);
+            if !filtered.is_empty() {
+                let args = args
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !filtered.contains(i))
+                    .map(|(_, arg)| arg.clone())
+                    .collect();
+                return Ok(Some(Expr::Function {
+                    name: name.clone(),
+                    args,
+                }));
+            }
+            for (i, arg) in args.iter().enumerate() {
+                if let Some(reduced) = reduce_expr(arg, cell_ref_resolver)? {
+                    let mut args = args.clone();
+                    args[i] = reduced;
+                    return Ok(Some(Expr::Function {
+                        name: name.clone(),
+                        args,
+                    }));
+                }
+            }
+            let values = args
+                .iter()
+                .map(|arg| match arg {
+                    Expr::Number(n) => *n,
+                    _ => unreachable!("Irreducible arguments are numbers"),
+                })
+                .collect::<Vec<f64>>();
+            Ok(Some(Expr::Number(call_function(name, &values)?)))
+        }
+    }
+}
+
+fn outline_expr(expr: &Expr, depth: usize, outline: &mut Vec<(usize, String)>) {
+    match expr {
+        Expr::Number(n) => outline.push((depth, n.to_string())),
+        Expr::CellRef(cr) => outline.push((depth, cr.to_uppercase())),
+        Expr::RefError => outline.push((depth, FormulaError::InvalidReference.to_string())),
+        Expr::BinaryOp { op, lhs, rhs } => {
+            outline.push((depth, op.to_string()));
+            outline_expr(lhs, depth + 1, outline);
+            outline_expr(rhs, depth + 1, outline);
+        }
+        Expr::UnaryOp { op, operand } => {
+            outline.push((depth, op.to_string()));
+            outline_expr(operand, depth + 1, outline);
+        }
+        Expr::Function { name, args } => {
+            outline.push((depth, format!("{}()", name.to_uppercase())));
+            args.iter()
+                .for_each(|arg| outline_expr(arg, depth + 1, outline));
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::CellRef(cr) => write!(f, "{}", cr.to_uppercase()),
+            Expr::RefError => write!(f, "{}", FormulaError::InvalidReference),
+            Expr::BinaryOp { op, lhs, rhs } => {
+                write_operand(f, lhs)?;
+                write!(f, " {} ", op)?;
+                write_operand(f, rhs)
+            }
+            Expr::UnaryOp { op, operand } => {
+                write!(f, "{}", op)?;
+                write_operand(f, operand)
+            }
+            Expr::Function { name, args } => {
+                let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+                write!(f, "{}({})", name.to_uppercase(), args.join(", "))
+            }
+        }
+    }
+}
+
+fn write_operand(f: &mut fmt::Formatter, operand: &Expr) -> fmt::Result {
+    match operand {
+        Expr::BinaryOp { .. } => write!(f, "({})", operand),
+        _ => write!(f, "{}", operand),
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BinOp::Add => write!(f, "+"),
+            BinOp::Sub => write!(f, "-"),
+            BinOp::Mul => write!(f, "*"),
+            BinOp::Div => write!(f, "/"),
+            BinOp::Pow => write!(f, "^"),
+        }
+    }
+}
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnOp::Neg => write!(f, "-"),
+        }
+    }
+}
+
 fn eval_deps(expr: &Expr) -> Vec<String> {
     match expr {
-        Expr::Number(_) => vec![],
+        Expr::Number(_) | Expr::RefError => vec![],
         Expr::CellRef(cr) => vec![cr.to_uppercase()],
         Expr::BinaryOp { lhs, rhs, .. } => {
             eval_deps(lhs).into_iter().chain(eval_deps(rhs)).collect()
@@ -228,6 +674,7 @@ fn eval_deps(expr: &Expr) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use crate::engine::parser::*;
+    use crate::model::grid::GetCellValueError;
     use pest::Parser;
 
     #[test]
@@ -240,9 +687,126 @@ mod tests {
         assert!(CellFormulaParser::parse(Rule::formula, "= a1 + -B2 / 9").is_ok());
         assert!(CellFormulaParser::parse(Rule::formula, "= SUM(1,a1,-3)").is_ok());
         assert!(CellFormulaParser::parse(Rule::formula, "= sum()").is_ok());
+        assert!(CellFormulaParser::parse(Rule::formula, "= Sheet2!a1 + 'My sheet'!$B$2").is_ok());
+        assert!(CellFormulaParser::parse(Rule::formula, "= #REF! + 1").is_ok());
         assert!(CellFormulaParser::parse(Rule::formula, "= a1:b3").is_err());
     }
 
+    #[test]
+    fn syntax_error_position() {
+        let resolver = |_: &str| -> Result<f64, GetCellValueError> { Ok(0.0) };
+        match calculate("= 3 +", &resolver) {
+            Err(FormulaError::SyntaxError(diagnostic)) => assert_eq!(diagnostic.position, 5),
+            other => panic!("expected syntax error, got {:?}", other),
+        }
+        match calculate("= a1:b3", &resolver) {
+            Err(FormulaError::SyntaxError(diagnostic)) => {
+                assert_eq!(diagnostic.position, 4);
+                assert!(diagnostic.expected.contains(&"end of formula".to_string()));
+            }
+            other => panic!("expected syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluation_steps_reduce_one_at_a_time() {
+        let resolver = |cell_ref: &str| -> Result<f64, GetCellValueError> {
+            match cell_ref.to_uppercase().as_str() {
+                "A1" => Ok(2.0),
+                _ => Ok(0.0),
+            }
+        };
+        assert_eq!(
+            evaluation_steps("= a1 * (3 + 1) - sum(1, 2)", &resolver),
+            Ok(vec![
+                "(A1 * (3 + 1)) - SUM(1, 2)".to_string(),
+                "(2 * (3 + 1)) - SUM(1, 2)".to_string(),
+                "(2 * 4) - SUM(1, 2)".to_string(),
+                "8 - SUM(1, 2)".to_string(),
+                "8 - 3".to_string(),
+                "5".to_string(),
+            ])
+        );
+        assert_eq!(
+            evaluation_steps("= 1 / 0", &resolver),
+            Ok(vec!["1 / 0".to_string(), "#DIV/0!".to_string()])
+        );
+    }
+
+    struct FilteringResolver;
+
+    impl CellResolver for FilteringResolver {
+        fn value(&self, cell_ref: &str) -> Result<f64, GetCellValueError> {
+            match cell_ref.to_uppercase().as_str() {
+                "A1" => Ok(1.0),
+                "A2" => Ok(2.0),
+                "A3" => Ok(4.0),
+                _ => Ok(0.0),
+            }
+        }
+
+        fn is_filtered(&self, cell_ref: &str) -> bool {
+            cell_ref.eq_ignore_ascii_case("A2")
+        }
+
+        fn is_hidden(&self, cell_ref: &str) -> bool {
+            cell_ref.eq_ignore_ascii_case("A3")
+        }
+    }
+
+    #[test]
+    fn subtotal_skips_filtered_rows() {
+        let resolver = FilteringResolver;
+        assert_eq!(
+            calculate("=SUBTOTAL(9,A1,A2,A3)", &resolver).map(|r| r.0),
+            Ok(5.0)
+        );
+        assert_eq!(
+            calculate("=SUBTOTAL(1,A1,A2,A3)", &resolver).map(|r| r.0),
+            Ok(2.5)
+        );
+        // Rows of collapsed groups are only left out by 101 to 109
+        assert_eq!(
+            calculate("=SUBTOTAL(101,A1,A2,A3)", &resolver).map(|r| r.0),
+            Ok(1.0)
+        );
+        assert_eq!(
+            calculate("=SUBTOTAL(109,A1,A2,A3)", &resolver).map(|r| r.0),
+            Ok(1.0)
+        );
+        assert_eq!(calculate("=SUM(A1,A2,A3)", &resolver).map(|r| r.0), Ok(7.0));
+        assert_eq!(
+            evaluation_steps("=SUBTOTAL(9,A1,A2)", &resolver),
+            Ok(vec![
+                "SUBTOTAL(9, A1, A2)".to_string(),
+                "SUBTOTAL(9, A1)".to_string(),
+                "SUBTOTAL(9, 1)".to_string(),
+                "1".to_string(),
+            ])
+        );
+        assert_eq!(
+            calculate("=SUBTOTAL(3,A1)", &resolver),
+            Err(FormulaError::UnknownFunction)
+        );
+    }
+
+    #[test]
+    fn standalone_functions_are_listed_but_not_nested() {
+        let resolver = |_: &str| -> Result<f64, GetCellValueError> { Ok(0.0) };
+        assert!(matching_functions("qu")
+            .iter()
+            .any(|hint| hint.name == "QUERY"));
+        assert_eq!(
+            standalone_function(r#"= query(A1:B4, "select A")"#),
+            Some("QUERY")
+        );
+        assert_eq!(standalone_function("=SUM(A1)"), None);
+        assert_eq!(
+            calculate("=SUM(QUERY(A1))", &resolver),
+            Err(FormulaError::Standalone)
+        );
+    }
+
     /* fn mock_cell_ref_resolver(cell_ref: &str) -> Option<f64> {
         match cell_ref {
             "a1" | "A1" => Some(1.0),
@@ -0,0 +1,419 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+    engine::sort,
+    model::grid::{cell_address_to_coords, column_index_to_letter},
+};
+
+/// The arguments of a `=QUERY(range, "query")` formula, which the formula parser doesn't
+/// handle since it returns a table rather than a number
+#[derive(Debug, PartialEq)]
+pub struct QueryCall {
+    pub range: String,
+    pub query: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    Syntax,
+    /// A column outside the queried range
+    UnknownColumn(String),
+    /// A column selected or ordered by next to aggregates without being grouped by
+    NotGrouped(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryError::Syntax => write!(f, "The query can't be read"),
+            QueryError::UnknownColumn(column) => {
+                write!(f, "Column {} isn't in the queried range", column)
+            }
+            QueryError::NotGrouped(column) => {
+                write!(
+                    f,
+                    "Column {} has to be grouped by next to aggregates",
+                    column
+                )
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Aggregate {
+    Sum,
+    Avg,
+    Count,
+    Min,
+    Max,
+}
+
+/// A selected or ordered by value: a column of the range, by index into its rows
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Item {
+    Column(usize),
+    Aggregate(Aggregate, usize),
+}
+
+#[derive(Debug, Default)]
+struct Query {
+    /// None for `select *`
+    select: Option<Vec<Item>>,
+    group_by: Vec<usize>,
+    /// Items with whether they're descending
+    order_by: Vec<(Item, bool)>,
+    limit: Option<usize>,
+}
+
+/// Reads a formula as a QUERY call, None if it's anything else
+pub fn parse_call(content: &str) -> Option<QueryCall> {
+    let content = content.trim().strip_prefix('=')?.trim_start();
+    let name = content.get(..6)?;
+    if !name.eq_ignore_ascii_case("QUERY(") {
+        return None;
+    }
+    let arguments = content[6..].trim_end().strip_suffix(')')?;
+    let (range, query) = arguments.split_once(',')?;
+    let query = query.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some(QueryCall {
+        range: range.trim().to_string(),
+        query: query.replace("\"\"", "\""),
+    })
+}
+
+/// Runs a query over the values of a range whose first row is headers and whose first column
+/// is `first_column`. The result starts with a header row too.
+///
+/// Understands `select` with columns by their letters or `sum`, `avg`, `count`, `min` and
+/// `max` of them, then optionally `group by`, `order by` (with `asc` or `desc`) and `limit`.
+pub fn run(
+    query: &str,
+    first_column: i32,
+    rows: &[Vec<String>],
+) -> Result<Vec<Vec<String>>, QueryError> {
+    let Some((headers, data)) = rows.split_first() else {
+        return Ok(Vec::new());
+    };
+    let query = Parser::new(query, first_column, headers.len())?.query()?;
+    let select = query
+        .select
+        .clone()
+        .unwrap_or_else(|| (0..headers.len()).map(Item::Column).collect());
+    let aggregated = select
+        .iter()
+        .chain(query.order_by.iter().map(|(item, _)| item))
+        .any(|item| matches!(item, Item::Aggregate(..)));
+    if aggregated || !query.group_by.is_empty() {
+        let ungrouped = select
+            .iter()
+            .chain(query.order_by.iter().map(|(item, _)| item))
+            .find(|item| matches!(item, Item::Column(c) if !query.group_by.contains(c)));
+        if let Some(Item::Column(column)) = ungrouped {
+            return Err(QueryError::NotGrouped(letter_of(*column, first_column)));
+        }
+    }
+
+    // Rows left blank at the bottom of a generous range don't count
+    let data = data
+        .iter()
+        .filter(|row| row.iter().any(|value| !value.is_empty()))
+        .collect::<Vec<_>>();
+    let mut groups = match (query.group_by.is_empty(), aggregated) {
+        (true, false) => data.iter().map(|row| vec![*row]).collect::<Vec<_>>(),
+        (true, true) => vec![data],
+        (false, _) => {
+            let mut groups: Vec<Vec<&Vec<String>>> = Vec::new();
+            for row in data {
+                let same_key = |group: &&mut Vec<&Vec<String>>| {
+                    query.group_by.iter().all(|c| group[0][*c] == row[*c])
+                };
+                match groups.iter_mut().find(same_key) {
+                    Some(group) => group.push(row),
+                    None => groups.push(vec![row]),
+                }
+            }
+            groups.sort_by(|a, b| {
+                query
+                    .group_by
+                    .iter()
+                    .map(|c| sort::compare_values(&a[0][*c], &b[0][*c]))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+            groups
+        }
+    };
+    groups.sort_by(|a, b| {
+        query
+            .order_by
+            .iter()
+            .map(|(item, descending)| {
+                sort::compare_keys(&evaluate(*item, a), &evaluate(*item, b), *descending)
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    let limit = query.limit.unwrap_or(groups.len());
+
+    let header = select
+        .iter()
+        .map(|item| match item {
+            Item::Column(c) => headers[*c].clone(),
+            Item::Aggregate(aggregate, c) => format!("{} {}", aggregate, headers[*c]),
+        })
+        .collect();
+    let body = groups
+        .iter()
+        .take(limit)
+        .map(|group| select.iter().map(|item| evaluate(*item, group)).collect());
+    Ok(std::iter::once(header).chain(body).collect())
+}
+
+/// An item's value over a group of rows. Columns are the same throughout a group.
+fn evaluate(item: Item, group: &[&Vec<String>]) -> String {
+    let (aggregate, column) = match item {
+        Item::Column(column) => {
+            return group
+                .first()
+                .map(|row| row[column].clone())
+                .unwrap_or_default()
+        }
+        Item::Aggregate(aggregate, column) => (aggregate, column),
+    };
+    let values = group
+        .iter()
+        .map(|row| row[column].as_str())
+        .filter(|value| !value.is_empty());
+    if aggregate == Aggregate::Count {
+        return values.count().to_string();
+    }
+    let numbers = values
+        .filter_map(|value| value.parse::<f64>().ok())
+        .collect::<Vec<_>>();
+    let result = match aggregate {
+        Aggregate::Sum => Some(numbers.iter().sum()),
+        Aggregate::Avg if numbers.is_empty() => None,
+        Aggregate::Avg => Some(numbers.iter().sum::<f64>() / numbers.len() as f64),
+        Aggregate::Min => numbers.iter().copied().reduce(f64::min),
+        Aggregate::Max => numbers.iter().copied().reduce(f64::max),
+        Aggregate::Count => unreachable!("Counted above"),
+    };
+    result.map(|number| number.to_string()).unwrap_or_default()
+}
+
+fn letter_of(column: usize, first_column: i32) -> String {
+    column_index_to_letter(first_column + column as i32)
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Aggregate::Sum => write!(f, "sum"),
+            Aggregate::Avg => write!(f, "avg"),
+            Aggregate::Count => write!(f, "count"),
+            Aggregate::Min => write!(f, "min"),
+            Aggregate::Max => write!(f, "max"),
+        }
+    }
+}
+
+/// Reads the query text token by token, keywords ignoring case
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+    first_column: i32,
+    columns: usize,
+}
+
+impl Parser {
+    fn new(query: &str, first_column: i32, columns: usize) -> Result<Self, QueryError> {
+        let mut tokens = Vec::new();
+        let mut chars = query.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() => {}
+                '*' | ',' | '(' | ')' => tokens.push(c.to_string()),
+                c if c.is_ascii_alphanumeric() => {
+                    let mut token = c.to_string();
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric()) {
+                        token.push(c);
+                    }
+                    tokens.push(token);
+                }
+                _ => return Err(QueryError::Syntax),
+            }
+        }
+        Ok(Parser {
+            tokens,
+            position: 0,
+            first_column,
+            columns,
+        })
+    }
+
+    fn query(mut self) -> Result<Query, QueryError> {
+        let mut query = Query::default();
+        self.expect("select")?;
+        if !self.eat("*") {
+            query.select = Some(self.list(Parser::item)?);
+        }
+        if self.eat("group") {
+            self.expect("by")?;
+            query.group_by = self.list(Parser::column)?;
+        }
+        if self.eat("order") {
+            self.expect("by")?;
+            query.order_by = self.list(|parser| {
+                let item = parser.item()?;
+                let descending = parser.eat("desc");
+                if !descending {
+                    parser.eat("asc");
+                }
+                Ok((item, descending))
+            })?;
+        }
+        if self.eat("limit") {
+            let limit = self.next().ok_or(QueryError::Syntax)?;
+            query.limit = Some(limit.parse().map_err(|_| QueryError::Syntax)?);
+        }
+        match self.next() {
+            Some(_) => Err(QueryError::Syntax),
+            None => Ok(query),
+        }
+    }
+
+    fn list<T>(
+        &mut self,
+        mut element: impl FnMut(&mut Parser) -> Result<T, QueryError>,
+    ) -> Result<Vec<T>, QueryError> {
+        let mut elements = vec![element(self)?];
+        while self.eat(",") {
+            elements.push(element(self)?);
+        }
+        Ok(elements)
+    }
+
+    fn item(&mut self) -> Result<Item, QueryError> {
+        let aggregate = match self.peek().map(str::to_lowercase).as_deref() {
+            Some("sum") => Aggregate::Sum,
+            Some("avg") => Aggregate::Avg,
+            Some("count") => Aggregate::Count,
+            Some("min") => Aggregate::Min,
+            Some("max") => Aggregate::Max,
+            _ => return self.column().map(Item::Column),
+        };
+        if self.tokens.get(self.position + 1).map(String::as_str) != Some("(") {
+            return self.column().map(Item::Column);
+        }
+        self.position += 2;
+        let column = self.column()?;
+        self.expect(")")?;
+        Ok(Item::Aggregate(aggregate, column))
+    }
+
+    /// A column by its letters, as an index into the range's rows
+    fn column(&mut self) -> Result<usize, QueryError> {
+        let letters = self.next().ok_or(QueryError::Syntax)?;
+        if !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(QueryError::Syntax);
+        }
+        let column = cell_address_to_coords(&format!("{}1", letters))
+            .map(|coords| coords.column - self.first_column)
+            .ok_or(QueryError::Syntax)?;
+        match usize::try_from(column) {
+            Ok(column) if column < self.columns => Ok(column),
+            _ => Err(QueryError::UnknownColumn(letters.to_uppercase())),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Moves past `token` if it's next
+    fn eat(&mut self, token: &str) -> bool {
+        let next = self
+            .peek()
+            .is_some_and(|next| next.eq_ignore_ascii_case(token));
+        if next {
+            self.position += 1;
+        }
+        next
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), QueryError> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(QueryError::Syntax),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::query::*;
+
+    fn table(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|value| value.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn reads_query_calls() {
+        assert_eq!(
+            parse_call(r#"=query(A1:C9, "select A, ""B""")"#),
+            Some(QueryCall {
+                range: "A1:C9".to_string(),
+                query: r#"select A, "B""#.to_string(),
+            })
+        );
+        assert_eq!(parse_call("=SUM(A1,A2)"), None);
+    }
+
+    #[test]
+    fn groups_and_orders() {
+        let rows = table(&[
+            &["Region", "Sales"],
+            &["West", "10"],
+            &["East", "5"],
+            &["West", "7"],
+            &["", ""],
+        ]);
+        assert_eq!(
+            run("select B, sum(C) group by B", 1, &rows),
+            Ok(table(&[
+                &["Region", "sum Sales"],
+                &["East", "5"],
+                &["West", "17"],
+            ]))
+        );
+        assert_eq!(
+            run(
+                "select B, count(C) group by B order by count(C) desc limit 1",
+                1,
+                &rows
+            ),
+            Ok(table(&[&["Region", "count Sales"], &["West", "2"]]))
+        );
+        assert_eq!(
+            run("select B order by C", 1, &rows),
+            Ok(table(&[&["Region"], &["East"], &["West"], &["West"]]))
+        );
+        assert_eq!(
+            run("select B, sum(C)", 1, &rows),
+            Err(QueryError::NotGrouped("B".to_string()))
+        );
+        assert_eq!(
+            run("select D", 1, &rows),
+            Err(QueryError::UnknownColumn("D".to_string()))
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use petgraph::{algo, visit};
+use tracing::info;
+
+use crate::{
+    engine::{
+        csv,
+        parser::{self, FormulaError, TextArgument},
+        query::{self, QueryCall},
+        remote::{self, RemoteData, RemoteFunction},
+    },
+    model::{
+        grid::{Coords, Grid},
+        properties,
+        spill::Spill,
+        view::CellRange,
+        workbook::{CellKey, SheetId},
+    },
+};
+
+impl Grid {
+    /// Recomputes a cell's display value and dependencies, then everything downstream of it
+    pub fn update_cell_display(&mut self, key: CellKey) {
+        let mut cycle: bool = false;
+        info!("Called for {:?}", key);
+        // Timing goes through the browser, so only when someone is looking at it
+        let started_at = self.view.profile.is_some().then(now_ms);
+
+        let Some(content) = self.workbook.get_cell(key).map(|c| c.content.clone()) else {
+            return;
+        };
+        self.workbook.remove_cell_dependencies(key);
+        let previous_spill = self.take_spill(key);
+        let display_value: String;
+        let mut diagnostic = None;
+        if content.starts_with('=') {
+            let reference = content.split_at(1).1;
+            if let Ok(target) = self.workbook.resolve_reference(reference, key.sheet) {
+                display_value = match self.workbook.get_cell_value(target) {
+                    Ok(value) => value.to_string(),
+                    Err(err) => err.to_string(),
+                };
+                self.workbook.cells_dep_graph.add_edge(target, key, ());
+            } else if let Some(call) = query::parse_call(&content) {
+                display_value = match self.run_query(key, &call) {
+                    Ok(values) => self.place_spill(key, values),
+                    Err(e) => e.to_string(),
+                };
+            } else if let Some(name) = properties::parse_info_call(&content) {
+                let value = self.text_argument(key, &name).and_then(|name| {
+                    let value = self.workbook.properties.get(&name);
+                    value.map(str::to_string).ok_or(FormulaError::NotAvailable)
+                });
+                display_value = value.unwrap_or_else(|e| e.to_string());
+            } else if let Some(call) = remote::parse_call(&content) {
+                let text = self
+                    .text_argument(key, &call.url)
+                    .and_then(|url| self.fetched_text(url));
+                display_value = match text {
+                    Ok(text) => match call.function {
+                        RemoteFunction::ImportCsv => {
+                            self.place_spill(key, csv::parse_csv(&text, ','))
+                        }
+                        RemoteFunction::WebService => text,
+                    },
+                    Err(e) => e.to_string(),
+                };
+            } else {
+                let cell_ref_resolver = self.workbook.resolver(key.sheet);
+                let calculated = parser::calculate(&content, &cell_ref_resolver);
+                drop(cell_ref_resolver);
+                // Even when it fails, as it may be for a cell not calculated yet
+                let dep_keys = parser::references(&content)
+                    .iter()
+                    .filter_map(|dep| self.workbook.resolve_reference(dep, key.sheet).ok())
+                    .collect::<Vec<_>>();
+                for dep_key in dep_keys {
+                    self.workbook.cells_dep_graph.add_edge(dep_key, key, ());
+                }
+                display_value = match calculated {
+                    Ok((val, _)) => val.to_string(),
+                    Err(e) => {
+                        if let FormulaError::SyntaxError(d) = &e {
+                            diagnostic = Some(d.clone());
+                        }
+                        e.to_string()
+                    }
+                };
+            }
+            if is_node_in_cycle(&self.workbook.cells_dep_graph, key) {
+                cycle = true;
+            }
+        } else {
+            let spilled = match content.is_empty() {
+                true => self
+                    .workbook
+                    .get_sheet(key.sheet)
+                    .and_then(|sheet| sheet.spilled_value(key.coords)),
+                false => None,
+            };
+            display_value = match spilled {
+                Some((anchor, value)) => {
+                    let anchor = CellKey {
+                        sheet: key.sheet,
+                        coords: anchor,
+                    };
+                    self.workbook.cells_dep_graph.add_edge(anchor, key, ());
+                    value
+                }
+                None => content.clone(),
+            };
+        }
+        let cell = self.workbook.get_mut_cell(key).unwrap();
+        cell.display_value = match cycle {
+            false => display_value,
+            true => FormulaError::CircularReference.to_string(),
+        };
+        cell.diagnostic = diagnostic;
+        let dependants = self.workbook.get_cell_dependants(key);
+        if let Some(started_at) = started_at {
+            self.view.record_recalc(key, now_ms() - started_at);
+        }
+
+        if !cycle {
+            let resolves_cycle = self
+                .view
+                .circular_reference
+                .as_ref()
+                .is_some_and(|path| path.contains(&key));
+            if resolves_cycle {
+                self.view.circular_reference = None;
+            }
+            dependants.into_iter().for_each(|dependant| {
+                self.update_cell_display(dependant);
+            });
+        } else {
+            let members = self.workbook.get_cycle_members(key);
+            members.iter().for_each(|node| {
+                if let Some(cell) = self.workbook.get_mut_cell(*node) {
+                    cell.display_value = FormulaError::CircularReference.to_string();
+                }
+            });
+            self.view.circular_reference = self.workbook.get_cycle_path(key);
+            info!("Cycle members: {:?}", members);
+
+            // Cells downstream of the cycle aren't part of it, they only inherit its error
+            let downstream = members
+                .iter()
+                .flat_map(|member| self.workbook.get_cell_dependants(*member))
+                .filter(|dependant| !members.contains(dependant))
+                .collect::<Vec<_>>();
+            downstream.into_iter().for_each(|dependant| {
+                self.update_cell_display(dependant);
+            });
+        }
+
+        if let Some(previous_spill) = previous_spill {
+            self.clear_spilled(key, previous_spill);
+        }
+        // Contents entered in or cleared from a table's way change whether it can spill
+        let covering = self
+            .workbook
+            .get_sheet(key.sheet)
+            .and_then(|sheet| sheet.spill_covering(key.coords))
+            .map(|(anchor, spill)| (anchor, spill.blocked));
+        if let Some((anchor, blocked)) = covering {
+            if content.is_empty() == blocked {
+                self.update_cell_display(CellKey {
+                    sheet: key.sheet,
+                    coords: anchor,
+                });
+            }
+        }
+    }
+
+    /// Evaluates a QUERY over the display values of its range, which the cell then depends on
+    fn run_query(
+        &mut self,
+        key: CellKey,
+        call: &QueryCall,
+    ) -> Result<Vec<Vec<String>>, FormulaError> {
+        let (first, last) = call
+            .range
+            .split_once(':')
+            .unwrap_or((&call.range, &call.range));
+        let start = self
+            .workbook
+            .resolve_reference(first.trim(), key.sheet)
+            .map_err(|_| FormulaError::InvalidReference)?;
+        // The end is on the start's sheet unless it names one, which has to be the same
+        let end = self
+            .workbook
+            .resolve_reference(last.trim(), start.sheet)
+            .map_err(|_| FormulaError::InvalidReference)?;
+        if end.sheet != start.sheet {
+            return Err(FormulaError::InvalidReference);
+        }
+        let range = CellRange {
+            anchor: start.coords,
+            extent: end.coords,
+        };
+        let sheet = self
+            .workbook
+            .get_sheet(start.sheet)
+            .ok_or(FormulaError::InvalidReference)?;
+        let (top_left, bottom_right) = (range.top_left(), range.bottom_right());
+        let rows = (top_left.row..=bottom_right.row)
+            .map(|row| {
+                (top_left.column..=bottom_right.column)
+                    .map(|column| {
+                        sheet
+                            .cells_map
+                            .get(&Coords { row, column })
+                            .map(|cell| cell.display_value.clone())
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        range.cells().for_each(|coords| {
+            let precedent = CellKey {
+                sheet: start.sheet,
+                coords,
+            };
+            self.workbook.cells_dep_graph.add_edge(precedent, key, ());
+        });
+
+        let spill = Spill {
+            values: query::run(&call.query, top_left.column, &rows)
+                .map_err(|_| FormulaError::InvalidQuery)?,
+            blocked: false,
+        };
+        if start.sheet == key.sheet && spill.range(key.coords).intersects(&range) {
+            return Err(FormulaError::CircularReference);
+        }
+        Ok(spill.values)
+    }
+
+    /// The text an argument stands for in a formula on `sheet`, None if it refers to a cell
+    /// that isn't there
+    pub fn argument_text(&self, sheet: SheetId, argument: &TextArgument) -> Option<String> {
+        match argument {
+            TextArgument::Literal(text) => Some(text.clone()),
+            TextArgument::Reference(reference) => {
+                let target = self.workbook.resolve_reference(reference, sheet).ok()?;
+                let cell = self.workbook.get_cell(target);
+                Some(
+                    cell.map(|cell| cell.display_value.clone())
+                        .unwrap_or_default(),
+                )
+            }
+        }
+    }
+
+    /// The text of a formula's argument, the formula depending on the cell it refers to
+    fn text_argument(
+        &mut self,
+        key: CellKey,
+        argument: &TextArgument,
+    ) -> Result<String, FormulaError> {
+        if let TextArgument::Reference(reference) = argument {
+            if let Ok(target) = self.workbook.resolve_reference(reference, key.sheet) {
+                self.workbook.cells_dep_graph.add_edge(target, key, ());
+            }
+        }
+        self.argument_text(key.sheet, argument)
+            .ok_or(FormulaError::InvalidReference)
+    }
+
+    /// What was fetched from a call's URL, asking for it the first time. Until it's back the
+    /// formula shows `#BUSY!`, and is recalculated once it is.
+    fn fetched_text(&mut self, url: String) -> Result<String, FormulaError> {
+        if url.is_empty() {
+            return Err(FormulaError::NotAvailable);
+        }
+        let data = self
+            .view
+            .remote_data
+            .entry(url)
+            .or_insert(RemoteData::Pending);
+        match data {
+            RemoteData::Pending => Err(FormulaError::Busy),
+            RemoteData::Loaded(text) | RemoteData::Refreshing(text) => Ok(text.clone()),
+            RemoteData::Failed => Err(FormulaError::Connect),
+        }
+    }
+
+    /// Keeps the table a formula returned, spilling it unless cells are in the way, and returns
+    /// what the formula's own cell shows
+    fn place_spill(&mut self, key: CellKey, values: Vec<Vec<String>>) -> String {
+        let mut spill = Spill {
+            values,
+            blocked: false,
+        };
+        let range = spill.range(key.coords);
+        let Some(sheet) = self.workbook.get_sheet(key.sheet) else {
+            return FormulaError::InvalidReference.to_string();
+        };
+        spill.blocked = range.cells().any(|coords| {
+            coords != key.coords
+                && sheet
+                    .cells_map
+                    .get(&coords)
+                    .is_some_and(|cell| !cell.content.is_empty())
+        });
+        let display_value = match spill.blocked {
+            true => FormulaError::Spill.to_string(),
+            false => spill
+                .value_at(key.coords, key.coords)
+                .unwrap_or_default()
+                .to_string(),
+        };
+        // The covered cells update as the formula's dependants
+        if !spill.blocked {
+            range
+                .cells()
+                .filter(|coords| *coords != key.coords)
+                .for_each(|coords| {
+                    let spilled = CellKey {
+                        sheet: key.sheet,
+                        coords,
+                    };
+                    // Created if need be, so there's a cell to show the value
+                    self.workbook.get_mut_cell(spilled);
+                    self.workbook.cells_dep_graph.add_edge(key, spilled, ());
+                });
+        }
+        if let Some(sheet) = self.workbook.get_sheet_mut(key.sheet) {
+            sheet.spills.insert(key.coords, spill);
+        }
+        display_value
+    }
+
+    /// Removes the table a cell returned last time, and its dependants with it
+    fn take_spill(&mut self, key: CellKey) -> Option<Spill> {
+        let spill = self
+            .workbook
+            .get_sheet_mut(key.sheet)?
+            .spills
+            .remove(&key.coords)?;
+        if !spill.blocked {
+            for coords in spill.range(key.coords).cells() {
+                let spilled = CellKey {
+                    sheet: key.sheet,
+                    coords,
+                };
+                self.workbook.cells_dep_graph.remove_edge(key, spilled);
+            }
+        }
+        Some(spill)
+    }
+
+    /// Empties the cells a previous table covered that the formula's new one doesn't, for
+    /// whatever depends on them
+    fn clear_spilled(&mut self, key: CellKey, previous: Spill) {
+        if previous.blocked {
+            return;
+        }
+        let current = self
+            .workbook
+            .get_sheet(key.sheet)
+            .and_then(|sheet| sheet.spills.get(&key.coords))
+            .filter(|spill| !spill.blocked)
+            .map(|spill| spill.range(key.coords));
+        for coords in previous.range(key.coords).cells() {
+            if coords == key.coords || current.is_some_and(|range| range.contains(coords)) {
+                continue;
+            }
+            let spilled = CellKey {
+                sheet: key.sheet,
+                coords,
+            };
+            let Some(cell) = self.workbook.get_mut_cell(spilled) else {
+                continue;
+            };
+            if !cell.content.is_empty() {
+                continue;
+            }
+            cell.display_value = String::new();
+            for dependant in self.workbook.get_cell_dependants(spilled) {
+                self.update_cell_display(dependant);
+            }
+        }
+    }
+}
+
+fn now_ms() -> f64 {
+    web_sys::window()
+        .and_then(|w| w.performance())
+        .map(|p| p.now())
+        .unwrap_or_default()
+}
+
+fn is_node_in_cycle<G>(graph: G, node: G::NodeId) -> bool
+where
+    G: visit::IntoNeighbors + visit::Visitable,
+{
+    let mut space = algo::DfsSpace::new(&graph);
+    for neighbour in graph.neighbors(node) {
+        if algo::has_path_connecting(graph, neighbour, node, Some(&mut space)) {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,77 @@
+use crate::engine::parser::{self, TextArgument};
+
+/// The functions that fetch a URL, which the formula parser doesn't handle since their
+/// results arrive after the formula's been calculated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RemoteFunction {
+    /// `=IMPORTCSV(url)`, the CSV file at the URL spilled as a table
+    ImportCsv,
+    /// `=WEBSERVICE(url)`, the text of the response
+    WebService,
+}
+
+/// The arguments of an `=IMPORTCSV(url)` or `=WEBSERVICE(url)` formula, the URL quoted or in
+/// a cell
+#[derive(Debug, PartialEq)]
+pub struct RemoteCall {
+    pub function: RemoteFunction,
+    pub url: TextArgument,
+}
+
+/// What's been fetched from a URL, kept for every formula asking for it
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoteData {
+    /// Asked for and not back yet
+    Pending,
+    Loaded(String),
+    /// Being fetched again, formulas showing what was fetched before until it's back
+    Refreshing(String),
+    /// The request failed, or the server refused it
+    Failed,
+}
+
+/// Reads a formula as a call fetching a URL, None if it's anything else
+pub fn parse_call(content: &str) -> Option<RemoteCall> {
+    let content = content.trim().strip_prefix('=')?.trim_start();
+    let (name, arguments) = content.split_once('(')?;
+    let function = match name.trim_end().to_uppercase().as_str() {
+        "IMPORTCSV" => RemoteFunction::ImportCsv,
+        "WEBSERVICE" => RemoteFunction::WebService,
+        _ => return None,
+    };
+    let url = parser::parse_text_argument(arguments.trim_end().strip_suffix(')')?)?;
+    Some(RemoteCall { function, url })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::remote::*;
+
+    #[test]
+    fn remote_calls_take_a_quoted_or_referenced_url() {
+        assert_eq!(
+            parse_call(r#"= importcsv( "https://example.com/data.csv" ) "#),
+            Some(RemoteCall {
+                function: RemoteFunction::ImportCsv,
+                url: TextArgument::Literal("https://example.com/data.csv".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_call(r#"=WEBSERVICE("https://example.com/?q=""a""")"#),
+            Some(RemoteCall {
+                function: RemoteFunction::WebService,
+                url: TextArgument::Literal("https://example.com/?q=\"a\"".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_call("=WEBSERVICE(Sheet2!$A1)"),
+            Some(RemoteCall {
+                function: RemoteFunction::WebService,
+                url: TextArgument::Reference("Sheet2!$A1".to_string()),
+            })
+        );
+        assert_eq!(parse_call("=WEBSERVICE(A1 + 1)"), None);
+        assert_eq!(parse_call("=SUM(1, 2)"), None);
+        assert_eq!(parse_call(r#"IMPORTCSV("https://example.com")"#), None);
+    }
+}
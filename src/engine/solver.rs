@@ -0,0 +1,301 @@
+const EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Relation {
+    AtMost,
+    AtLeast,
+    EqualTo,
+}
+
+/// A linear constraint: `coefficients · x` related to `bound`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraint {
+    pub coefficients: Vec<f64>,
+    pub relation: Relation,
+    pub bound: f64,
+}
+
+/// Optimizes `objective · x` over non-negative `x` within the constraints
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearProgram {
+    pub objective: Vec<f64>,
+    pub maximize: bool,
+    pub constraints: Vec<Constraint>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SolveError {
+    /// No values meet every constraint
+    Infeasible,
+    /// The objective improves without limit
+    Unbounded,
+}
+
+/// Solves a linear program with the two-phase simplex method, returning the optimal `x`.
+/// Bland's rule picks pivots, so degenerate problems can't cycle.
+pub fn simplex(program: &LinearProgram) -> Result<Vec<f64>, SolveError> {
+    let variables = program.objective.len();
+    let mut tableau = Tableau::new(program);
+
+    // Phase one drives the artificial variables to zero, finding a feasible start
+    let mut phase_one = vec![0.0; tableau.width()];
+    tableau
+        .artificial
+        .clone()
+        .for_each(|column| phase_one[column] = -1.0);
+    tableau.set_objective(&phase_one);
+    tableau.optimize(tableau.width())?;
+    if tableau.value() < -EPSILON {
+        return Err(SolveError::Infeasible);
+    }
+    tableau.drive_out_artificials();
+
+    let sign = if program.maximize { 1.0 } else { -1.0 };
+    let mut phase_two = vec![0.0; tableau.width()];
+    for (column, coefficient) in program.objective.iter().enumerate() {
+        phase_two[column] = sign * coefficient;
+    }
+    tableau.set_objective(&phase_two);
+    tableau.optimize(tableau.artificial.start)?;
+
+    let mut solution = vec![0.0; variables];
+    for (row, basic) in tableau.basis.iter().enumerate() {
+        if *basic < variables {
+            solution[*basic] = tableau.rows[row][tableau.width()];
+        }
+    }
+    Ok(solution)
+}
+
+/// Constraint rows over the variables, then slack and surplus columns, then artificial ones,
+/// each row ending with its right-hand side. The objective row holds reduced costs.
+struct Tableau {
+    rows: Vec<Vec<f64>>,
+    objective: Vec<f64>,
+    basis: Vec<usize>,
+    artificial: std::ops::Range<usize>,
+}
+
+impl Tableau {
+    fn new(program: &LinearProgram) -> Self {
+        let variables = program.objective.len();
+        // Right-hand sides have to be non-negative, which can flip a relation
+        let constraints = program
+            .constraints
+            .iter()
+            .map(|constraint| match constraint.bound < 0.0 {
+                true => Constraint {
+                    coefficients: constraint.coefficients.iter().map(|c| -c).collect(),
+                    relation: match constraint.relation {
+                        Relation::AtMost => Relation::AtLeast,
+                        Relation::AtLeast => Relation::AtMost,
+                        Relation::EqualTo => Relation::EqualTo,
+                    },
+                    bound: -constraint.bound,
+                },
+                false => constraint.clone(),
+            })
+            .collect::<Vec<_>>();
+        let slacks = constraints
+            .iter()
+            .filter(|constraint| constraint.relation != Relation::EqualTo)
+            .count();
+        let artificials = constraints
+            .iter()
+            .filter(|constraint| constraint.relation != Relation::AtMost)
+            .count();
+        let width = variables + slacks + artificials;
+        let artificial = variables + slacks..width;
+
+        let (mut slack, mut next_artificial) = (variables, artificial.start);
+        let mut rows = Vec::new();
+        let mut basis = Vec::new();
+        for constraint in constraints {
+            let mut row = vec![0.0; width + 1];
+            for (column, coefficient) in constraint.coefficients.iter().enumerate().take(variables)
+            {
+                row[column] = *coefficient;
+            }
+            row[width] = constraint.bound;
+            match constraint.relation {
+                Relation::AtMost => {
+                    row[slack] = 1.0;
+                    basis.push(slack);
+                    slack += 1;
+                }
+                Relation::AtLeast => {
+                    row[slack] = -1.0;
+                    slack += 1;
+                    row[next_artificial] = 1.0;
+                    basis.push(next_artificial);
+                    next_artificial += 1;
+                }
+                Relation::EqualTo => {
+                    row[next_artificial] = 1.0;
+                    basis.push(next_artificial);
+                    next_artificial += 1;
+                }
+            }
+            rows.push(row);
+        }
+        Tableau {
+            rows,
+            objective: vec![0.0; width + 1],
+            basis,
+            artificial,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.objective.len() - 1
+    }
+
+    /// The objective's value at the current basic solution
+    fn value(&self) -> f64 {
+        self.objective[self.width()]
+    }
+
+    /// Starts maximizing `costs · x`, priced out against the current basis
+    fn set_objective(&mut self, costs: &[f64]) {
+        self.objective = costs.iter().map(|cost| -cost).collect();
+        self.objective.push(0.0);
+        for (row, basic) in self.basis.iter().enumerate() {
+            let factor = self.objective[*basic];
+            if factor != 0.0 {
+                for (column, value) in self.rows[row].iter().enumerate() {
+                    self.objective[column] -= factor * value;
+                }
+            }
+        }
+    }
+
+    /// Pivots until no column before `columns` improves the objective
+    fn optimize(&mut self, columns: usize) -> Result<(), SolveError> {
+        let width = self.width();
+        while let Some(entering) = (0..columns).find(|column| self.objective[*column] < -EPSILON) {
+            let leaving = (0..self.rows.len())
+                .filter(|row| self.rows[*row][entering] > EPSILON)
+                .map(|row| (row, self.rows[row][width] / self.rows[row][entering]))
+                .min_by(|(a, ratio_a), (b, ratio_b)| {
+                    ratio_a
+                        .total_cmp(ratio_b)
+                        .then(self.basis[*a].cmp(&self.basis[*b]))
+                })
+                .map(|(row, _)| row)
+                .ok_or(SolveError::Unbounded)?;
+            self.pivot(leaving, entering);
+        }
+        Ok(())
+    }
+
+    /// Swaps artificial variables still in the basis at zero for real ones, where a row has any
+    fn drive_out_artificials(&mut self) {
+        for row in 0..self.rows.len() {
+            if !self.artificial.contains(&self.basis[row]) {
+                continue;
+            }
+            let entering =
+                (0..self.artificial.start).find(|column| self.rows[row][*column].abs() > EPSILON);
+            if let Some(entering) = entering {
+                self.pivot(row, entering);
+            }
+        }
+    }
+
+    fn pivot(&mut self, row: usize, column: usize) {
+        let divisor = self.rows[row][column];
+        self.rows[row]
+            .iter_mut()
+            .for_each(|value| *value /= divisor);
+        let pivot_row = self.rows[row].clone();
+        let eliminate = |target: &mut Vec<f64>| {
+            let factor = target[column];
+            if factor != 0.0 {
+                for (value, pivot) in target.iter_mut().zip(&pivot_row) {
+                    *value -= factor * pivot;
+                }
+            }
+        };
+        for (other, target) in self.rows.iter_mut().enumerate() {
+            if other != row {
+                eliminate(target);
+            }
+        }
+        eliminate(&mut self.objective);
+        self.basis[row] = column;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::solver::*;
+
+    fn constraint(coefficients: &[f64], relation: Relation, bound: f64) -> Constraint {
+        Constraint {
+            coefficients: coefficients.to_vec(),
+            relation,
+            bound,
+        }
+    }
+
+    fn assert_close(solution: Vec<f64>, expected: &[f64]) {
+        assert!(
+            solution
+                .iter()
+                .zip(expected)
+                .all(|(value, expected)| (value - expected).abs() < 1e-6),
+            "{:?} isn't {:?}",
+            solution,
+            expected
+        );
+    }
+
+    #[test]
+    fn maximizes_within_constraints() {
+        // max 3x + 5y, x <= 4, 2y <= 12, 3x + 2y <= 18
+        let program = LinearProgram {
+            objective: vec![3.0, 5.0],
+            maximize: true,
+            constraints: vec![
+                constraint(&[1.0, 0.0], Relation::AtMost, 4.0),
+                constraint(&[0.0, 2.0], Relation::AtMost, 12.0),
+                constraint(&[3.0, 2.0], Relation::AtMost, 18.0),
+            ],
+        };
+        assert_close(simplex(&program).unwrap(), &[2.0, 6.0]);
+    }
+
+    #[test]
+    fn minimizes_with_lower_bounds_and_equalities() {
+        // min 2x + 3y, x + y >= 10, x - y = 2
+        let program = LinearProgram {
+            objective: vec![2.0, 3.0],
+            maximize: false,
+            constraints: vec![
+                constraint(&[1.0, 1.0], Relation::AtLeast, 10.0),
+                constraint(&[1.0, -1.0], Relation::EqualTo, 2.0),
+            ],
+        };
+        assert_close(simplex(&program).unwrap(), &[6.0, 4.0]);
+    }
+
+    #[test]
+    fn reports_infeasible_and_unbounded() {
+        let infeasible = LinearProgram {
+            objective: vec![1.0],
+            maximize: true,
+            constraints: vec![
+                constraint(&[1.0], Relation::AtMost, 1.0),
+                constraint(&[1.0], Relation::AtLeast, 2.0),
+            ],
+        };
+        assert_eq!(simplex(&infeasible), Err(SolveError::Infeasible));
+
+        let unbounded = LinearProgram {
+            objective: vec![1.0, 1.0],
+            maximize: true,
+            constraints: vec![constraint(&[1.0, -1.0], Relation::AtMost, 1.0)],
+        };
+        assert_eq!(simplex(&unbounded), Err(SolveError::Unbounded));
+    }
+}
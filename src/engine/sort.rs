@@ -0,0 +1,42 @@
+use std::cmp::Ordering;
+
+/// Positions of `keys` in sorted order: numbers before text, text ignoring case, blanks at
+/// the end either way. Equal keys keep their order.
+pub fn sorted_order(keys: &[String], descending: bool) -> Vec<usize> {
+    let mut order = (0..keys.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| compare_keys(&keys[*a], &keys[*b], descending));
+    order
+}
+
+/// Compares sort keys the way `sorted_order` orders them
+pub fn compare_keys(a: &str, b: &str, descending: bool) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) if descending => compare_values(b, a),
+        (false, false) => compare_values(a, b),
+    }
+}
+
+/// Compares cell values the way sorting and filtering order them
+pub fn compare_values(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::sort::*;
+
+    #[test]
+    fn numbers_then_text_then_blanks() {
+        let keys = ["pear", "", "10", "Apple", "9"].map(String::from);
+        assert_eq!(sorted_order(&keys, false), [4, 2, 3, 0, 1]);
+        assert_eq!(sorted_order(&keys, true), [0, 3, 2, 4, 1]);
+    }
+}
@@ -0,0 +1,148 @@
+/// The rows of a descriptive statistics summary, in the order `describe` returns them
+pub const STATISTICS: [&str; 9] = [
+    "Count",
+    "Mean",
+    "Median",
+    "Standard deviation",
+    "Minimum",
+    "First quartile",
+    "Third quartile",
+    "Maximum",
+    "Skewness",
+];
+
+/// Summarizes a sample the way `STATISTICS` lists, None for what it's too small for: the
+/// standard deviation takes two values, skewness three and the rest one
+pub fn describe(values: &[f64]) -> [Option<f64>; 9] {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let n = sorted.len() as f64;
+    let mean = (!sorted.is_empty()).then(|| sorted.iter().sum::<f64>() / n);
+    let deviation = mean.filter(|_| n >= 2.0).map(|mean| {
+        let squares = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+        (squares / (n - 1.0)).sqrt()
+    });
+    // Like SKEW, adjusted for the sample
+    let skewness = mean
+        .zip(deviation)
+        .filter(|(_, deviation)| n >= 3.0 && *deviation != 0.0)
+        .map(|(mean, deviation)| {
+            let cubes = sorted
+                .iter()
+                .map(|x| ((x - mean) / deviation).powi(3))
+                .sum::<f64>();
+            n / ((n - 1.0) * (n - 2.0)) * cubes
+        });
+    [
+        Some(n),
+        mean,
+        quantile(&sorted, 0.5),
+        deviation,
+        sorted.first().copied(),
+        quantile(&sorted, 0.25),
+        quantile(&sorted, 0.75),
+        sorted.last().copied(),
+        skewness,
+    ]
+}
+
+/// Interpolates between the sorted values around `p` of the way along, like QUARTILE.INC
+pub fn quantile(sorted: &[f64], p: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let position = p * last as f64;
+    let below = position.floor() as usize;
+    let above = (below + 1).min(last);
+    let fraction = position - below as f64;
+    Some(sorted[below] + (sorted[above] - sorted[below]) * fraction)
+}
+
+const MAX_BINS: usize = 1000;
+
+/// A bin of a histogram, counting the values from `start` up to but not including `end`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+/// Bins values `width` apart from a multiple of the width at or below the smallest. Without
+/// a width, Sturges' rule picks about log2(n) + 1 bins of a round width.
+pub fn histogram(values: &[f64], width: Option<f64>) -> Vec<Bin> {
+    let (Some(min), Some(max)) = (
+        values.iter().copied().reduce(f64::min),
+        values.iter().copied().reduce(f64::max),
+    ) else {
+        return Vec::new();
+    };
+    let width = match width.filter(|width| *width > 0.0) {
+        Some(width) => width,
+        None => {
+            let bins = (values.len() as f64).log2().ceil() + 1.0;
+            round_width((max - min) / bins)
+        }
+    };
+    // Widths too narrow for the spread of values would make too many bins to show
+    let width = width.max((max - min) / MAX_BINS as f64);
+    let start = (min / width).floor() * width;
+    let count = (((max - start) / width).floor() as usize + 1).max(1);
+    let mut bins = (0..count)
+        .map(|i| Bin {
+            start: start + i as f64 * width,
+            end: start + (i + 1) as f64 * width,
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+    for value in values {
+        let index = (((value - start) / width).floor() as usize).min(count - 1);
+        bins[index].count += 1;
+    }
+    bins
+}
+
+/// The nearest width of 1, 2 or 5 times a power of ten at or above `width`
+pub fn round_width(width: f64) -> f64 {
+    if width <= 0.0 || !width.is_finite() {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(width.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|step| *step >= width)
+        .unwrap_or(10.0 * magnitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::statistics::*;
+
+    #[test]
+    fn describes_a_sample() {
+        let summary = describe(&[4.0, 1.0, 3.0, 2.0, 10.0]);
+        assert_eq!(summary[0], Some(5.0));
+        assert_eq!(summary[1], Some(4.0));
+        assert_eq!(summary[2], Some(3.0));
+        assert_eq!(summary[5], Some(2.0));
+        assert_eq!(summary[6], Some(4.0));
+        assert!((summary[3].unwrap() - 3.535534).abs() < 1e-6);
+        assert!((summary[8].unwrap() - 1.697056).abs() < 1e-6);
+
+        let single = describe(&[7.0]);
+        assert_eq!(single[2], Some(7.0));
+        assert_eq!(single[3], None);
+        assert_eq!(describe(&[])[1], None);
+    }
+
+    #[test]
+    fn bins_values() {
+        let values = [1.0, 2.5, 3.0, 7.0, 9.9, 10.0];
+        let counts = |bins: Vec<Bin>| bins.iter().map(|bin| bin.count).collect::<Vec<_>>();
+        assert_eq!(counts(histogram(&values, Some(5.0))), [3, 2, 1]);
+
+        let auto = histogram(&values, None);
+        assert_eq!((auto[0].start, auto[0].end), (0.0, 5.0));
+        assert_eq!(counts(auto), [3, 2, 1]);
+        assert!(histogram(&[], None).is_empty());
+    }
+}
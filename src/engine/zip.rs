@@ -0,0 +1,161 @@
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+/// 1980-01-01, the earliest date ZIP has
+const DOS_DATE: u16 = 0x21;
+
+/// How an entry's data is stored
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    Stored,
+    /// Raw DEFLATE, as `DecompressionStream("deflate-raw")` reads
+    Deflated,
+}
+
+pub struct ZipEntry<'a> {
+    pub compression: Compression,
+    pub data: &'a [u8],
+}
+
+/// A ZIP archive of the files uncompressed, in order, as OpenDocument needs its `mimetype`
+/// first and stored
+pub fn write_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+        let fields = |bytes: &mut Vec<u8>| {
+            put_u16(bytes, 20); // Version needed, 2.0
+            put_u16(bytes, 0); // Flags
+            put_u16(bytes, 0); // Stored
+            put_u16(bytes, 0); // Time
+            put_u16(bytes, DOS_DATE);
+            put_u32(bytes, crc);
+            put_u32(bytes, data.len() as u32);
+            put_u32(bytes, data.len() as u32);
+            put_u16(bytes, name.len() as u16);
+            put_u16(bytes, 0); // Extra field length
+        };
+
+        put_u32(&mut archive, LOCAL_HEADER);
+        fields(&mut archive);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        put_u32(&mut central_directory, CENTRAL_HEADER);
+        put_u16(&mut central_directory, 20); // Version made by
+        fields(&mut central_directory);
+        put_u16(&mut central_directory, 0); // Comment length
+        put_u16(&mut central_directory, 0); // Disk
+        put_u16(&mut central_directory, 0); // Internal attributes
+        put_u32(&mut central_directory, 0); // External attributes
+        put_u32(&mut central_directory, offset);
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&central_directory);
+    put_u32(&mut archive, END_OF_CENTRAL_DIRECTORY);
+    put_u16(&mut archive, 0); // Disk
+    put_u16(&mut archive, 0); // Disk with the directory
+    put_u16(&mut archive, files.len() as u16);
+    put_u16(&mut archive, files.len() as u16);
+    put_u32(&mut archive, central_directory.len() as u32);
+    put_u32(&mut archive, directory_offset);
+    put_u16(&mut archive, 0); // Comment length
+    archive
+}
+
+/// The data of the entry with a name, found through the central directory, or None if
+/// there's no such entry or it's compressed some other way. Deflated data is left for the
+/// browser to inflate.
+pub fn find_entry<'a>(archive: &'a [u8], name: &str) -> Option<ZipEntry<'a>> {
+    // The end record is last, before a comment of up to 64 KiB
+    let end = (0..archive.len().saturating_sub(21))
+        .rev()
+        .take(0x10000 + 22)
+        .find(|i| read_u32(archive, *i) == Some(END_OF_CENTRAL_DIRECTORY))?;
+    let count = read_u16(archive, end + 10)?;
+    let mut at = read_u32(archive, end + 16)? as usize;
+    for _ in 0..count {
+        if read_u32(archive, at)? != CENTRAL_HEADER {
+            return None;
+        }
+        let method = read_u16(archive, at + 10)?;
+        let compressed_size = read_u32(archive, at + 20)? as usize;
+        let name_length = read_u16(archive, at + 28)? as usize;
+        let extra_length = read_u16(archive, at + 30)? as usize;
+        let comment_length = read_u16(archive, at + 32)? as usize;
+        let local_offset = read_u32(archive, at + 42)? as usize;
+        let entry_name = archive.get(at + 46..at + 46 + name_length)?;
+        if entry_name == name.as_bytes() {
+            let local_name_length = read_u16(archive, local_offset + 26)? as usize;
+            let local_extra_length = read_u16(archive, local_offset + 28)? as usize;
+            let start = local_offset + 30 + local_name_length + local_extra_length;
+            let compression = match method {
+                0 => Compression::Stored,
+                8 => Compression::Deflated,
+                _ => return None,
+            };
+            return Some(ZipEntry {
+                compression,
+                data: archive.get(start..start + compressed_size)?,
+            });
+        }
+        at += 46 + name_length + extra_length + comment_length;
+    }
+    None
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::zip::*;
+
+    #[test]
+    fn written_entries_are_found_again() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        let archive = write_zip(&[
+            ("mimetype", b"text/plain".as_slice()),
+            ("a/b.xml", b"<b/>".as_slice()),
+        ]);
+        let entry = find_entry(&archive, "a/b.xml").unwrap();
+        assert_eq!(entry.compression, Compression::Stored);
+        assert_eq!(entry.data, b"<b/>");
+        assert_eq!(
+            find_entry(&archive, "mimetype").unwrap().data,
+            b"text/plain"
+        );
+        assert!(find_entry(&archive, "missing").is_none());
+    }
+}
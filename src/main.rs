@@ -1,13 +1,31 @@
 use dioxus::prelude::*;
 
 use crate::{
-    components::{grid::GridDisplay, header::Header},
+    components::{
+        autosave::Autosave,
+        csv_import::ImportProgress,
+        cycle_banner::{CycleBanner, ProtectionBanner},
+        dialog::{Dialog, DialogHost},
+        files::{use_unsaved_changes_warning, FileDropOverlay},
+        grid::GridDisplay,
+        header::Header,
+        offline::use_service_worker,
+        presence::use_presence,
+        read_only::use_read_only_parameter,
+        remote_data::use_remote_data,
+        search::SearchBar,
+        sheet_tabs::SheetTabs,
+        side_panel::{Panel, SidePanel},
+        validation::ValidationAlertBanner,
+    },
     model::grid::Grid,
 };
 
 mod components;
 mod engine;
 mod model;
+#[cfg(feature = "server")]
+mod server;
 
 static MAIN_CSS: Asset = asset!("/assets/main.css");
 static COLORSCHEME: Asset = asset!("/assets/colorscheme.css");
@@ -19,12 +37,18 @@ const CELL_COLUMNS: usize = 26;
 const CELL_ROWS: usize = 100;
 
 fn main() {
+    // Native builds log through Dioxus' default logger
+    #[cfg(not(any(feature = "desktop", feature = "server")))]
     tracing_wasm::set_as_global_default_with_config(
         tracing_wasm::WASMLayerConfigBuilder::new()
             .set_max_level(tracing::Level::INFO)
             .build(),
     );
+    #[cfg(not(feature = "server"))]
     dioxus::launch(app);
+    // Serves the app along with the API workbooks are stored behind
+    #[cfg(feature = "server")]
+    dioxus::serve(|| async move { Ok(dioxus::server::router(app).merge(server::api_routes())) });
 }
 
 #[component]
@@ -38,15 +62,44 @@ fn app() -> Element {
         )
     });
     let scroll_container = use_signal(|| None);
+    let side_panel = use_signal(|| None::<Panel>);
+    let dialog = use_signal(|| None::<Dialog>);
+    let mut dropping = use_signal(|| false);
+    use_unsaved_changes_warning(grid);
+    use_remote_data(grid);
+    use_service_worker();
+    use_presence(grid);
+    use_read_only_parameter(grid);
 
     rsx! {
         document::Title { "Spreadsheet" }
         document::Link { rel: "icon", href: FAVICON }
+        // Served from the site's root, as is the service worker, to cover the whole app
+        document::Link { rel: "manifest", href: "/manifest.webmanifest" }
         document::Stylesheet { href: MAIN_CSS }
         document::Stylesheet { href: COLORSCHEME }
         body {
-            Header { grid, scroll_container }
-            GridDisplay { grid, scroll_container }
+            Header { grid, scroll_container, side_panel, dialog }
+            div {
+                class: "workspace",
+                ondragover: move |evt| {
+                    evt.prevent_default();
+                    if !dropping() {
+                        dropping.set(true);
+                    }
+                },
+                GridDisplay { grid, scroll_container, dialog }
+                SidePanel { grid, side_panel, dialog }
+            }
+            SheetTabs { grid, dialog, scroll_container }
+            SearchBar { grid, scroll_container, side_panel }
+            CycleBanner { grid }
+            ImportProgress { grid }
+            Autosave { grid }
+            ValidationAlertBanner { grid }
+            ProtectionBanner { grid }
+            DialogHost { grid, dialog, scroll_container }
+            FileDropOverlay { grid, dialog, dropping }
         }
     }
 }
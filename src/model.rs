@@ -1 +1,30 @@
+pub mod chart;
+pub mod clipboard;
+pub mod command;
+pub mod comment;
+pub mod conditional;
+pub mod data_source;
+pub mod export;
+pub mod file;
+pub mod filter;
+pub mod format;
+pub mod google_sheets;
 pub mod grid;
+pub mod history;
+pub mod import;
+pub mod outline;
+pub mod presence;
+pub mod print;
+pub mod properties;
+pub mod protection;
+pub mod read_only;
+pub mod server;
+pub mod snapshot;
+pub mod solver;
+pub mod spill;
+pub mod table;
+pub mod undo;
+pub mod validation;
+pub mod view;
+pub mod viewport;
+pub mod workbook;
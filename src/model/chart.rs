@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{format::NumberFormat, grid::Coords, view::CellRange, workbook::Sheet};
+
+pub type ChartId = u32;
+
+pub const DEFAULT_CHART_WIDTH: i32 = 480;
+pub const DEFAULT_CHART_HEIGHT: i32 = 300;
+
+/// A chart floating over the grid, drawn from the values of a range as they are now
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Chart {
+    pub id: ChartId,
+    pub kind: ChartKind,
+    pub title: String,
+    pub x_title: String,
+    pub y_title: String,
+    /// Categories (x values for scatter) in the first column, series in the others, possibly
+    /// under a row of names
+    pub range: CellRange,
+    /// The range's columns drawn as series, in order
+    pub series: Vec<ChartSeries>,
+    /// The cell the chart's top-left corner sits on
+    pub anchor: Coords,
+    pub width: i32,
+    pub height: i32,
+    /// The x values' axis on scatter charts, categories being spaced evenly on the others
+    pub x_axis: AxisOptions,
+    pub y_axis: AxisOptions,
+    /// On the right, for series plotted on a different scale
+    pub secondary_axis: AxisOptions,
+}
+
+/// A column of the chart's range drawn as a series
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChartSeries {
+    pub column: i32,
+    /// Plotted against the secondary axis
+    pub secondary: bool,
+    pub trendline: Option<Trendline>,
+}
+
+impl ChartSeries {
+    pub fn new(column: i32) -> Self {
+        ChartSeries {
+            column,
+            secondary: false,
+            trendline: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Trendline {
+    /// The least squares line through the series
+    Linear,
+    /// The mean of each value and those before it, this many in all
+    MovingAverage(usize),
+}
+
+/// Where a value axis starts and ends, rounded around the values where unset, and how its
+/// labels show
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AxisOptions {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub format: NumberFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartAxis {
+    X,
+    Y,
+    Secondary,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ChartKind {
+    Bar,
+    Line,
+    /// The first series only, a slice per category
+    Pie,
+    Scatter,
+}
+
+pub const CHART_KINDS: [(ChartKind, &str); 4] = [
+    (ChartKind::Bar, "Bar"),
+    (ChartKind::Line, "Line"),
+    (ChartKind::Pie, "Pie"),
+    (ChartKind::Scatter, "Scatter"),
+];
+
+/// What a chart plots, read from its range
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartData {
+    pub categories: Vec<String>,
+    pub series: Vec<Series>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Series {
+    pub name: String,
+    /// One per category, None where the cell isn't a number
+    pub values: Vec<Option<f64>>,
+    pub secondary: bool,
+    pub trendline: Option<Trendline>,
+}
+
+impl Chart {
+    /// A chart of a range, every column but the first a series, placed right of it
+    pub fn new(id: ChartId, kind: ChartKind, range: CellRange) -> Self {
+        let (top_left, bottom_right) = (range.top_left(), range.bottom_right());
+        Chart {
+            id,
+            kind,
+            title: String::new(),
+            x_title: String::new(),
+            y_title: String::new(),
+            range,
+            series: (top_left.column + 1..=bottom_right.column)
+                .map(ChartSeries::new)
+                .collect(),
+            anchor: Coords {
+                row: top_left.row,
+                column: bottom_right.column + 2,
+            },
+            width: DEFAULT_CHART_WIDTH,
+            height: DEFAULT_CHART_HEIGHT,
+            x_axis: AxisOptions::default(),
+            y_axis: AxisOptions::default(),
+            secondary_axis: AxisOptions::default(),
+        }
+    }
+
+    pub fn axis(&self, axis: ChartAxis) -> &AxisOptions {
+        match axis {
+            ChartAxis::X => &self.x_axis,
+            ChartAxis::Y => &self.y_axis,
+            ChartAxis::Secondary => &self.secondary_axis,
+        }
+    }
+    pub fn axis_mut(&mut self, axis: ChartAxis) -> &mut AxisOptions {
+        match axis {
+            ChartAxis::X => &mut self.x_axis,
+            ChartAxis::Y => &mut self.y_axis,
+            ChartAxis::Secondary => &mut self.secondary_axis,
+        }
+    }
+}
+
+impl Sheet {
+    pub fn get_chart(&self, id: ChartId) -> Option<&Chart> {
+        self.charts.iter().find(|chart| chart.id == id)
+    }
+
+    /// The values a chart plots. The first row names the series if none of it is a number.
+    pub fn chart_data(&self, chart: &Chart) -> ChartData {
+        let (top_left, bottom_right) = (chart.range.top_left(), chart.range.bottom_right());
+        let value = |row: i32, column: i32| {
+            self.cells_map
+                .get(&Coords { row, column })
+                .map(|cell| cell.display_value.clone())
+                .unwrap_or_default()
+        };
+        let has_names = (top_left.column..=bottom_right.column)
+            .all(|column| value(top_left.row, column).parse::<f64>().is_err());
+        let first_row = top_left.row + has_names as i32;
+        let rows = (first_row..=bottom_right.row)
+            .filter(|row| !self.is_row_hidden(*row))
+            .collect::<Vec<_>>();
+        ChartData {
+            categories: rows
+                .iter()
+                .map(|row| value(*row, top_left.column))
+                .collect(),
+            series: chart
+                .series
+                .iter()
+                .filter(|series| (top_left.column..=bottom_right.column).contains(&series.column))
+                .map(|series| Series {
+                    name: match has_names {
+                        true => value(top_left.row, series.column),
+                        false => format!("Series {}", series.column - top_left.column),
+                    },
+                    values: rows
+                        .iter()
+                        .map(|row| value(*row, series.column).parse().ok())
+                        .collect(),
+                    secondary: series.secondary,
+                    trendline: series.trendline,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{chart::*, command::GridCommand, grid::Grid};
+
+    #[test]
+    fn charts_read_their_range_as_it_recalculates() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let contents = [
+            (0, 0, "Month"),
+            (0, 1, "Sales"),
+            (1, 0, "Jan"),
+            (1, 1, "10"),
+            (2, 0, "Feb"),
+            (2, 1, "n/a"),
+        ]
+        .map(|(row, column, content)| (Coords { row, column }, content.to_string()));
+        grid.apply(GridCommand::SetCellContents(contents.to_vec()));
+        grid.apply(GridCommand::SelectCell(Coords { row: 1, column: 1 }));
+        grid.apply(GridCommand::CreateChart(ChartKind::Bar));
+
+        let chart = grid.sheet().charts[0].clone();
+        assert_eq!(chart.series, [ChartSeries::new(1)]);
+        assert_eq!(chart.anchor, Coords { row: 0, column: 3 });
+        let data = grid.sheet().chart_data(&chart);
+        assert_eq!(data.categories, ["Jan", "Feb"]);
+        assert_eq!(data.series[0].name, "Sales");
+        assert_eq!(data.series[0].values, [Some(10.0), None]);
+
+        grid.apply(GridCommand::SetCellContents(vec![(
+            Coords { row: 2, column: 1 },
+            "=B2*2".to_string(),
+        )]));
+        let data = grid.sheet().chart_data(&chart);
+        assert_eq!(data.series[0].values, [Some(10.0), Some(20.0)]);
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{
+    engine::csv,
+    model::{format::CellFormat, grid::Coords, workbook::CellKey},
+};
+
+/// Cells copied with Ctrl+C, keyed by their offset from the copied range's top-left cell
+#[derive(Clone, Debug)]
+pub struct Clipboard {
+    /// Top-left cell of the copied range, which pasted references are shifted relative to
+    pub source: CellKey,
+    pub rows: i32,
+    pub columns: i32,
+    pub contents: Vec<(Coords, String)>,
+    /// Display values, which is what other applications get
+    pub values: Vec<(Coords, String)>,
+    pub formats: Vec<(Coords, CellFormat)>,
+    /// Cut cells move when pasted, so they can only be pasted once
+    pub cut: bool,
+}
+
+impl Clipboard {
+    fn value_rows(&self) -> Vec<Vec<String>> {
+        let mut rows = vec![vec![String::new(); self.columns as usize]; self.rows as usize];
+        for (offset, value) in &self.values {
+            rows[offset.row as usize][offset.column as usize] = value.clone();
+        }
+        rows
+    }
+
+    /// Tab separated values, quoted the way Excel does when a value holds a tab, newline or quote
+    pub fn to_tsv(&self) -> String {
+        csv::write_tsv(&self.value_rows())
+    }
+
+    pub fn to_html(&self) -> String {
+        to_html_table(&self.value_rows())
+    }
+
+    /// Whether text read back from the system clipboard is what this copy put there
+    pub fn matches_text(&self, text: &str) -> bool {
+        let tsv = normalize_newlines(&self.to_tsv());
+        normalize_newlines(text).trim_end_matches('\n') == tsv.trim_end_matches('\n')
+    }
+}
+
+/// What Paste Special takes from the copied cells
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PasteContent {
+    /// Contents as typed and formats, as pasting does
+    All,
+    /// Contents as typed, with relative references shifted, leaving the formats under them
+    Formulas,
+    /// Computed results, frozen, leaving the formats under them
+    Values,
+    /// Fonts, colors, alignment and number formats, leaving the contents under them
+    Formats,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PasteOptions {
+    pub content: PasteContent,
+    /// Empty copied cells leave what's under them alone
+    pub skip_blanks: bool,
+    /// Copied rows become columns
+    pub transpose: bool,
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        PasteOptions {
+            content: PasteContent::All,
+            skip_blanks: false,
+            transpose: false,
+        }
+    }
+}
+
+/// An HTML table of rows of text, which other spreadsheets paste as cells
+pub fn to_html_table(rows: &[Vec<String>]) -> String {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let cells = row
+                .iter()
+                .map(|value| format!("<td>{}</td>", escape_html(value)))
+                .collect::<String>();
+            format!("<tr>{}</tr>", cells)
+        })
+        .collect::<String>();
+    format!("<table>{}</table>", rows)
+}
+
+/// A Markdown table of rows of text, the first row as its header. Columns of numbers are
+/// aligned right.
+pub fn to_markdown_table(rows: &[Vec<String>]) -> String {
+    let Some((header, body)) = rows.split_first() else {
+        return String::new();
+    };
+    let markdown_row = |row: &Vec<String>| {
+        let cells = row
+            .iter()
+            .map(|value| format!(" {} |", escape_markdown(value)))
+            .collect::<String>();
+        format!("|{}\n", cells)
+    };
+    let alignments = (0..header.len())
+        .map(|column| {
+            let values = body
+                .iter()
+                .filter_map(|row| row.get(column))
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>();
+            let is_numeric =
+                !values.is_empty() && values.iter().all(|value| value.parse::<f64>().is_ok());
+            match is_numeric {
+                true => " ---: |",
+                false => " --- |",
+            }
+        })
+        .collect::<String>();
+    let mut markdown = markdown_row(header);
+    markdown.push_str(&format!("|{}\n", alignments));
+    for row in body {
+        markdown.push_str(&markdown_row(row));
+    }
+    markdown
+}
+
+/// Rows of cells pasted from another application, preferring its HTML table over plain text
+pub fn parse_external(text: &str, html: Option<&str>) -> Vec<Vec<String>> {
+    html.and_then(parse_html_table)
+        .unwrap_or_else(|| parse_tsv(text))
+}
+
+/// Rows of tab separated values, at least one even if the text is empty
+pub fn parse_tsv(text: &str) -> Vec<Vec<String>> {
+    let rows = csv::parse_csv(&normalize_newlines(text), '\t');
+    match rows.is_empty() {
+        true => vec![vec![String::new()]],
+        false => rows,
+    }
+}
+
+/// Cell texts of the first `<table>` in an HTML fragment, or None if it has no table
+pub fn parse_html_table(html: &str) -> Option<Vec<Vec<String>>> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<table")?;
+    let end = lower[start..]
+        .find("</table")
+        .map_or(html.len(), |end| start + end);
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut cell: Option<String> = None;
+    let mut i = start;
+    while i < end {
+        if html.as_bytes()[i] == b'<' {
+            let tag_end = lower[i..].find('>').map_or(end, |e| i + e + 1);
+            let tag = lower[i + 1..tag_end.saturating_sub(1).max(i + 1)].trim_start();
+            let name = tag
+                .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .find(|part| !part.is_empty())
+                .unwrap_or_default();
+            let closing = tag.starts_with('/');
+            match (name, closing) {
+                ("tr", false) => rows.push(Vec::new()),
+                ("td" | "th", false) => cell = Some(String::new()),
+                ("td" | "th", true) => {
+                    if let (Some(text), Some(row)) = (cell.take(), rows.last_mut()) {
+                        row.push(decode_html(text.trim()));
+                    }
+                }
+                ("br", _) => {
+                    if let Some(text) = cell.as_mut() {
+                        text.push('\n');
+                    }
+                }
+                _ => {}
+            }
+            i = tag_end;
+        } else {
+            let text_end = lower[i..end].find('<').map_or(end, |e| i + e);
+            if let Some(text) = cell.as_mut() {
+                // Whitespace in HTML source is layout, not content
+                let words = html[i..text_end].split_whitespace().collect::<Vec<_>>();
+                if !words.is_empty() {
+                    if !text.is_empty() && !text.ends_with('\n') {
+                        text.push(' ');
+                    }
+                    text.push_str(&words.join(" "));
+                }
+            }
+            i = text_end;
+        }
+    }
+    rows.retain(|row| !row.is_empty());
+    Some(rows)
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('\n', "<br>")
+}
+
+fn decode_html(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::clipboard::*;
+
+    #[test]
+    fn tsv_round_trip() {
+        let text = "a\t\"b\tc\"\r\n\"say \"\"hi\"\"\"\t\r\n";
+        assert_eq!(
+            parse_tsv(text),
+            vec![vec!["a", "b\tc"], vec!["say \"hi\"", ""]]
+        );
+    }
+
+    #[test]
+    fn html_table_from_spreadsheet() {
+        let html = r#"<meta charset="utf-8"><google-sheets-html-origin><table xmlns="http://www.w3.org/1999/xhtml" cellspacing="0" border="0"><colgroup><col width="100"/></colgroup><tbody>
+            <tr style="height:21px;"><td style="overflow:hidden;">1</td><td>Fish &amp; chips</td></tr>
+            <tr><td><span>two<br>lines</span></td><td></td></tr>
+        </tbody></table>"#;
+        assert_eq!(
+            parse_external("ignored", Some(html)),
+            vec![vec!["1", "Fish & chips"], vec!["two\nlines", ""]]
+        );
+        assert_eq!(
+            parse_external("x\ty", Some("<b>no table</b>")),
+            vec![vec!["x", "y"]]
+        );
+    }
+
+    #[test]
+    fn markdown_table_escapes_pipes_and_aligns_numbers() {
+        let rows = vec![
+            vec!["Item".to_string(), "Price".to_string()],
+            vec!["Fish | chips".to_string(), "4.5".to_string()],
+            vec!["two\nlines".to_string(), "".to_string()],
+        ];
+        assert_eq!(
+            to_markdown_table(&rows),
+            "| Item | Price |\n| --- | ---: |\n| Fish \\| chips | 4.5 |\n| two<br>lines |  |\n"
+        );
+        assert_eq!(to_markdown_table(&[]), "");
+    }
+}
@@ -0,0 +1,2964 @@
+use std::collections::HashMap;
+
+use crate::{
+    engine::{
+        cleaning::Cleaning,
+        csv::ColumnType,
+        editing, fill,
+        ods::OdsTable,
+        remote::{self, RemoteData},
+        sort, statistics,
+    },
+    model::{
+        chart::{Chart, ChartId, ChartKind},
+        clipboard::{Clipboard, PasteContent, PasteOptions},
+        comment::Comment,
+        conditional::{ConditionalFormat, Rule},
+        file::{SessionFile, SheetFile, WorkbookFile},
+        filter::{AutoFilter, ColumnFilter},
+        format::{
+            decimals_of, ColorTarget, FontStyle, HorizontalAlign, NumberFormat, VerticalAlign,
+            MAX_DECIMALS,
+        },
+        grid::{coords_to_cell_address, Cell, Coords, Grid, SearchStep},
+        import::ImportRows,
+        outline::{Outline, OutlineDirection},
+        presence::{Collaboration, Presence},
+        print::PrintSettings,
+        properties::DocumentProperties,
+        protection::ProtectedRange,
+        solver::SolverProblem,
+        table::{Table, TableStyle},
+        validation::{AlertStyle, Validation, ValidationAlert, ValidationRule},
+        view::{CellRange, Selection},
+        workbook::{CellKey, SheetId},
+    },
+};
+
+/// Every change to a `Grid` goes through one of these, applied with `Grid::apply`.
+/// Plain `Coords` refer to the active sheet.
+pub enum GridCommand {
+    SelectCell(Coords),
+    MoveSelection(MoveDirection),
+    /// Jumps to the edge of the data that way (Ctrl+Arrow)
+    JumpSelection(MoveDirection),
+    /// Moves the current cell by as many rows as fit in `height` pixels (Page Up/Down)
+    MovePage {
+        down: bool,
+        height: i32,
+    },
+    /// Goes to the bottom-right corner of the cells in use (Ctrl+End)
+    GoToLastCell,
+    /// Adds a range starting at a cell to the selection (Ctrl+click)
+    AddToSelection(Coords),
+    /// Stretches the active range from the current cell to a cell (Shift+click, mouse drag)
+    ExtendSelection(Coords),
+    /// Moves the far corner of the selection one cell (Shift+Arrow)
+    ExtendSelectionTowards(MoveDirection),
+    /// Moves the far corner of the selection to the edge of the data (Ctrl+Shift+Arrow)
+    ExtendSelectionToEdge(MoveDirection),
+    /// Selects a whole column, as clicking its header does
+    SelectColumn(i32),
+    /// Selects a whole row, as clicking its header does
+    SelectRow(i32),
+    /// Selects the data around the current cell, then the whole sheet (Ctrl+A)
+    SelectAll,
+    /// Widens the active range to whole columns (Ctrl+Space)
+    SelectEntireColumns,
+    /// Widens the active range to whole rows (Shift+Space)
+    SelectEntireRows,
+    /// Selects a cell on any sheet, switching to it
+    GoToCell(CellKey),
+    /// Selects a range on any sheet, switching to it
+    GoToRange {
+        sheet: SheetId,
+        range: CellRange,
+    },
+    /// Selects scattered cells, as Go To Special does. Does nothing given none.
+    SelectCells(Vec<Coords>),
+
+    /// Enters edit mode on the current cell, optionally replacing its content (type-to-edit)
+    StartEditing {
+        initial_content: Option<String>,
+    },
+    /// Updates a cell's content while it's being edited, without recalculating
+    EditContent {
+        coords: Coords,
+        content: String,
+    },
+    /// Leaves edit mode and recalculates the edited cell
+    CommitEdit(Coords),
+    /// Leaves edit mode restoring the content from before editing started
+    CancelEdit(Coords),
+    /// Content set programmatically (e.g. import), recalculated immediately
+    SetCellContent {
+        coords: Coords,
+        content: String,
+    },
+    /// Sets many cells at once, recalculating only after all of them are in place
+    SetCellContents(Vec<(Coords, String)>),
+    SetFormulaBarEditing(bool),
+    /// Empties every selected cell and recalculates whatever depended on them (Delete)
+    ClearContents,
+
+    /// Takes back the last change to the workbook, as one step however many cells it touched
+    Undo,
+    /// Makes the last undone change again
+    Redo,
+
+    /// Turns a font style on for every selected cell, or off if the current cell has it
+    /// (Ctrl+B, Ctrl+I, Ctrl+U)
+    ToggleFontStyle(FontStyle),
+    /// Sets the font of every selected cell, None going back to the grid's
+    SetFontFamily(Option<String>),
+    SetFontSize(Option<u32>),
+    /// Shows the selected cells' numbers a way
+    SetNumberFormat(NumberFormat),
+    /// Shows one more (or fewer) decimal place in every selected cell, counting from the
+    /// places its value has if it isn't formatted yet
+    ChangeDecimals(i8),
+    /// Colors the text or background of every selected cell, None going back to the theme's
+    SetColor {
+        target: ColorTarget,
+        color: Option<String>,
+    },
+    /// Adds a rule drawing the active range of the selection by its values
+    AddConditionalFormat(Rule),
+    /// Removes the rules covering any of the active range of the selection
+    ClearConditionalFormats,
+    /// Makes the active range of the selection a table with its first row as the header,
+    /// or the data region around the current cell if only it is selected
+    CreateTable(TableStyle),
+    /// Restyles the table around the current cell
+    SetTableStyle(TableStyle),
+    /// Adds a totals row under the table around the current cell, summing its number
+    /// columns, or removes the one it has
+    ToggleTotalsRow,
+    /// Turns the table around the current cell back into plain cells, keeping their values
+    RemoveTable,
+    /// Turns filtering on for the table or data region around the current cell, or off,
+    /// showing every row again
+    ToggleAutoFilter,
+    /// Filters the rows by a column's values, None clearing its filter
+    SetColumnFilter {
+        column: i32,
+        filter: Option<ColumnFilter>,
+    },
+    /// Sets what can be entered in the active range of the selection, replacing the rules
+    /// of any cells in it
+    SetValidation {
+        rule: ValidationRule,
+        alert: AlertStyle,
+        error_message: Option<String>,
+        prompt: Option<String>,
+    },
+    /// Removes the rules covering any of the active range of the selection
+    ClearValidation,
+    /// Tidies the text of every selected cell, leaving formulas alone
+    CleanData(Cleaning),
+    /// Fills a linear problem's variable cells with the best values there are, keeping how
+    /// it went for the Solver dialog
+    Solve(SolverProblem),
+    /// Writes count, mean, median, spread, quartiles and skewness of each column of `input`
+    /// as a table starting at `output`. `labels` takes the first row as column names.
+    DescribeStatistics {
+        input: CellRange,
+        output: Coords,
+        labels: bool,
+    },
+    /// Writes how many of the numbers in `input` fall in each bin `bin_width` wide (picked
+    /// if None) as a table starting at `output`, with a bar for each
+    Histogram {
+        input: CellRange,
+        output: Coords,
+        bin_width: Option<f64>,
+    },
+    /// Adds a chart of the active range of the selection, or of the data region around the
+    /// current cell if only it is selected
+    CreateChart(ChartKind),
+    /// Replaces the chart with the same id, as the chart dialog or dragging it changed it
+    SetChart(Chart),
+    DeleteChart(ChartId),
+    /// Groups the rows or columns of the active range of the selection one level deeper
+    Group(OutlineDirection),
+    /// Takes the rows or columns of the active range of the selection out of their innermost
+    /// group
+    Ungroup(OutlineDirection),
+    /// Removes every row or column group, showing what they hid
+    ClearOutline(OutlineDirection),
+    /// Collapses the group at a level starting at a row or column, or expands it
+    ToggleOutlineGroup {
+        direction: OutlineDirection,
+        level: u8,
+        start: i32,
+    },
+    /// Collapses the groups at a level and deeper, expanding the ones above
+    ShowOutlineLevel {
+        direction: OutlineDirection,
+        level: u8,
+    },
+    /// Inserts a row under each run of rows of `range` with the same value in `key_column`,
+    /// totalling `columns` with SUBTOTAL's `function`, then a grand total row, and outlines
+    /// the runs so they collapse to their totals. Rows below move down, taking references
+    /// to them along.
+    Subtotals {
+        range: CellRange,
+        key_column: i32,
+        function: u8,
+        columns: Vec<i32>,
+    },
+    /// Aligns every selected cell's value sideways, None going back to aligning by value
+    SetHorizontalAlign(Option<HorizontalAlign>),
+    SetVerticalAlign(VerticalAlign),
+    /// Turns the text of every selected cell, clamping the rotation to ±90 degrees
+    SetTextOrientation {
+        rotation: i16,
+        vertical: bool,
+    },
+    /// Locks or unlocks every selected cell, which only matters once the sheet is protected
+    SetCellsLocked(bool),
+
+    /// Copies the active range of the selection
+    Copy,
+    /// Like `Copy`, but pasting moves the cells along with references to them
+    Cut,
+    /// Pastes the copied cells at the current cell, shifting relative references in formulas
+    Paste,
+    /// Pastes copied cells picking what to take from them. Cut cells are copied, not moved.
+    PasteSpecial(PasteOptions),
+    /// Pastes rows of cells from another application at the current cell
+    PasteRows(Vec<Vec<String>>),
+    /// Starts writing the rows of a CSV file with their top-left at `at`, on a new sheet if
+    /// `new_sheet`, writing the first batch. A header row is kept as it is and made bold,
+    /// the fields below taken as their column's type. Rows past the sheet's last are left out.
+    ImportCsv {
+        rows: ImportRows,
+        at: Coords,
+        new_sheet: bool,
+        header: bool,
+        types: Vec<ColumnType>,
+    },
+    /// Writes the next batch of rows of the import, recalculating after the last
+    ContinueImport,
+    /// Adds each table as a new sheet named after it, if the name's free, and shows the first
+    ImportSheets(Vec<OdsTable>),
+    /// Replaces the workbook with a saved one, as it was saved
+    OpenWorkbook(WorkbookFile),
+    /// Adds each sheet as a new one, named as it was if the name's free, and shows the first
+    AddSheets(Vec<SheetFile>),
+    /// Goes back to the sheet and selections of an earlier session
+    RestoreSession(SessionFile),
+    /// Goes back to an earlier version of the workbook, which is a change to save unlike
+    /// opening one
+    RestoreVersion(WorkbookFile),
+    /// Replaces the workbook with an empty one of a single sheet
+    NewWorkbook,
+    /// Notes that the workbook as it is has been saved
+    MarkSaved,
+    /// Shares where this session is with whoever else works on a stored workbook, or stops
+    /// for None
+    SetCollaboration(Option<Collaboration>),
+    /// Shows where the others working on the shared workbook are
+    SetCollaborators(Vec<Presence>),
+    /// Keeps what was fetched from a URL, None if it couldn't be, and recalculates the
+    /// formulas waiting on it
+    ReceiveRemoteData {
+        url: String,
+        text: Option<String>,
+    },
+    /// Fetches a URL's data again, every URL's for None
+    RefreshData(Option<String>),
+    /// Has a URL's data fetched again every so many minutes, or only when asked for None
+    SetRefreshInterval {
+        url: String,
+        minutes: Option<u32>,
+    },
+    /// Adds a comment to a cell's thread, starting it if there's none
+    AddComment {
+        coords: Coords,
+        comment: Comment,
+    },
+    SetCommentResolved {
+        coords: Coords,
+        resolved: bool,
+    },
+    /// Removes a cell's comment along with its replies
+    DeleteCommentThread(Coords),
+    /// Sets the workbook's title, author, description and custom properties
+    SetProperties(DocumentProperties),
+    /// Sets how the active sheet prints
+    SetPrintSettings(PrintSettings),
+    /// Protects the active sheet so that only its unlocked cells can be changed, or lifts it
+    SetSheetProtection(bool),
+    /// Makes the active range editable or not while the sheet is protected, replacing the
+    /// ranges it overlaps
+    AddProtectedRange {
+        editable: bool,
+        description: String,
+    },
+    /// Removes one of the active sheet's protected ranges, by index
+    RemoveProtectedRange(usize),
+    /// Stops the import, keeping the rows written so far
+    CancelImport,
+    /// Continues the active range up to a cell, as dragging the fill handle there does
+    FillTo(Coords),
+    /// Moves the active range's cells so its top-left lands on a cell, taking references to
+    /// them along, as dragging the range's border there does
+    MoveRange(Coords),
+    /// Reorders the rows of the range `get_sort_range` picks by their values in a column,
+    /// keeping each row's cells together. Formulas that move shift their relative
+    /// references like copied ones.
+    SortRange {
+        column: i32,
+        descending: bool,
+    },
+    /// Fills the current column's empty cells the way the filled ones transform the data
+    /// beside them
+    FlashFill,
+    ClearClipboard,
+
+    SetSearchQuery(Option<String>),
+    Search(SearchStep),
+
+    TracePrecedents,
+    TraceDependants,
+    ClearTraceArrows,
+    DismissCircularReference,
+    DismissValidationAlert,
+    DismissProtectionMessage,
+    /// Lets the workbook only be looked at and copied from, or edited again
+    SetReadOnly(bool),
+    /// Draws the grid bigger or smaller, in percent
+    SetZoom(u16),
+    /// Puts back what the cell of a warning's entry held before it
+    TakeBackEntry,
+
+    WatchCell(CellKey),
+    UnwatchCell(CellKey),
+
+    /// Freezes the rows above and columns left of the current cell
+    FreezePanes,
+    UnfreezePanes,
+    /// Resizes a column, or every selected column if it's one of them
+    ResizeColumn {
+        column: usize,
+        width: i32,
+    },
+    /// Resizes a row, or every selected row if it's one of them
+    ResizeRow {
+        row: usize,
+        height: i32,
+    },
+
+    SetProfiling(bool),
+    ResetProfile,
+
+    /// Appends a new sheet and switches to it
+    AddSheet,
+    ActivateSheet(SheetId),
+    RenameSheet {
+        sheet: SheetId,
+        name: String,
+    },
+    MoveSheet {
+        sheet: SheetId,
+        to_index: usize,
+    },
+    /// Deletes a sheet, replacing references to it with `#REF!` or the referenced values
+    DeleteSheet {
+        sheet: SheetId,
+        replace_with_values: bool,
+    },
+}
+
+impl GridCommand {
+    /// Whether the command takes the current cell somewhere else
+    fn moves_current_cell(&self) -> bool {
+        matches!(
+            self,
+            GridCommand::SelectCell(_)
+                | GridCommand::MoveSelection(_)
+                | GridCommand::JumpSelection(_)
+                | GridCommand::MovePage { .. }
+                | GridCommand::GoToLastCell
+                | GridCommand::AddToSelection(_)
+                | GridCommand::GoToCell(_)
+                | GridCommand::GoToRange { .. }
+                | GridCommand::SelectCells(_)
+                | GridCommand::SelectColumn(_)
+                | GridCommand::SelectRow(_)
+                | GridCommand::SelectAll
+        )
+    }
+
+    /// Whether the command can change what's saved of the workbook, rather than only how it's
+    /// looked at
+    pub fn changes_document(&self) -> bool {
+        !matches!(
+            self,
+            GridCommand::SelectCell(_)
+                | GridCommand::MoveSelection(_)
+                | GridCommand::JumpSelection(_)
+                | GridCommand::MovePage { .. }
+                | GridCommand::GoToLastCell
+                | GridCommand::AddToSelection(_)
+                | GridCommand::ExtendSelection(_)
+                | GridCommand::ExtendSelectionTowards(_)
+                | GridCommand::ExtendSelectionToEdge(_)
+                | GridCommand::SelectColumn(_)
+                | GridCommand::SelectRow(_)
+                | GridCommand::SelectAll
+                | GridCommand::SelectEntireColumns
+                | GridCommand::SelectEntireRows
+                | GridCommand::GoToCell(_)
+                | GridCommand::GoToRange { .. }
+                | GridCommand::SelectCells(_)
+                | GridCommand::StartEditing { .. }
+                | GridCommand::EditContent { .. }
+                | GridCommand::CancelEdit(_)
+                | GridCommand::SetFormulaBarEditing(_)
+                | GridCommand::Copy
+                | GridCommand::Cut
+                | GridCommand::ClearClipboard
+                | GridCommand::CancelImport
+                | GridCommand::RestoreSession(_)
+                | GridCommand::OpenWorkbook(_)
+                | GridCommand::NewWorkbook
+                | GridCommand::MarkSaved
+                | GridCommand::SetCollaboration(_)
+                | GridCommand::SetCollaborators(_)
+                | GridCommand::ReceiveRemoteData { .. }
+                | GridCommand::RefreshData(_)
+                | GridCommand::SetSearchQuery(_)
+                | GridCommand::Search(_)
+                | GridCommand::TracePrecedents
+                | GridCommand::TraceDependants
+                | GridCommand::ClearTraceArrows
+                | GridCommand::DismissCircularReference
+                | GridCommand::DismissValidationAlert
+                | GridCommand::DismissProtectionMessage
+                | GridCommand::SetReadOnly(_)
+                | GridCommand::SetZoom(_)
+                | GridCommand::WatchCell(_)
+                | GridCommand::UnwatchCell(_)
+                | GridCommand::SetProfiling(_)
+                | GridCommand::ResetProfile
+                | GridCommand::ActivateSheet(_)
+        )
+    }
+}
+
+pub enum MoveDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Grid {
+    pub fn apply(&mut self, command: GridCommand) {
+        // Leaving a cell being edited commits it, as Enter would. Otherwise the editor moves
+        // along and what was typed never gets calculated.
+        if self.view.is_editing_cell && command.moves_current_cell() {
+            self.apply(GridCommand::CommitEdit(self.current_cell()));
+        }
+        if self.view.read_only && command.edits() {
+            return;
+        }
+        if let Some(message) = self.protection_refusal(&command) {
+            self.view.protection_message = Some(message);
+            return;
+        }
+        if command.changes_document() {
+            self.view.has_unsaved_changes = true;
+        }
+        // A change made while an import is being written waits for the rest of it, so the
+        // import stays a step of its own
+        if command.is_undoable() && !matches!(command, GridCommand::ContinueImport) {
+            self.complete_import();
+        }
+        // What a command applies while carrying itself out is part of its own step
+        if !command.is_undoable() || self.view.undo_history.is_recording() {
+            self.run_command(command);
+            return;
+        }
+        self.view.undo_history.start(self.workbook_before(&command));
+        self.run_command(command);
+        // An import's step goes on over its batches, until finish_import ends it
+        if self.view.import.is_none() {
+            self.view.undo_history.finish(&self.workbook);
+        }
+    }
+
+    fn run_command(&mut self, command: GridCommand) {
+        match command {
+            GridCommand::SelectCell(coords) => self.view.select(coords),
+            GridCommand::MoveSelection(direction) => match direction {
+                MoveDirection::Up => self.current_cell_up_one(),
+                MoveDirection::Down => self.current_cell_down_one(),
+                MoveDirection::Left => self.current_cell_left_one(),
+                MoveDirection::Right => self.current_cell_right_one(),
+            },
+            GridCommand::JumpSelection(direction) => {
+                let destination = self.get_data_edge(self.current_cell(), &direction);
+                self.view.select(destination);
+            }
+            GridCommand::MovePage { down, height } => {
+                let mut coords = self.current_cell();
+                coords.row = self.get_page_row(coords.row, height, down);
+                self.view.select(coords);
+            }
+            GridCommand::GoToLastCell => self.view.select(self.get_used_range_end()),
+            GridCommand::AddToSelection(coords) => self.view.add_to_selection(coords),
+            GridCommand::ExtendSelection(coords) => self.view.extend_selection(coords),
+            GridCommand::ExtendSelectionTowards(direction) => {
+                self.extend_selection_towards(direction)
+            }
+            GridCommand::ExtendSelectionToEdge(direction) => {
+                let extent = self.view.selection().active_range().extent;
+                let destination = self.get_data_edge(extent, &direction);
+                self.view.extend_selection(destination);
+            }
+            GridCommand::SelectColumn(column) => {
+                self.view.select(Coords { row: 0, column });
+                self.select_entire(true);
+            }
+            GridCommand::SelectRow(row) => {
+                self.view.select(Coords { row, column: 0 });
+                self.select_entire(false);
+            }
+            GridCommand::SelectAll => self.select_all(),
+            GridCommand::SelectEntireColumns => self.select_entire(true),
+            GridCommand::SelectEntireRows => self.select_entire(false),
+            GridCommand::GoToCell(key) => {
+                if self.workbook.get_sheet(key.sheet).is_some() {
+                    self.view.active_sheet = key.sheet;
+                    self.view.select(key.coords);
+                }
+            }
+
+            GridCommand::GoToRange { sheet, range } => {
+                if self.workbook.get_sheet(sheet).is_some() {
+                    self.view.active_sheet = sheet;
+                    self.view.select(range.anchor);
+                    self.view.extend_selection(range.extent);
+                }
+            }
+            GridCommand::SelectCells(cells) => {
+                if let Some(selection) = Selection::of_cells(&cells) {
+                    self.view
+                        .selections
+                        .insert(self.view.active_sheet, selection);
+                }
+            }
+
+            GridCommand::StartEditing { initial_content } => {
+                self.view.previous_content = self.get_current_cell_content();
+                if let Some(content) = initial_content {
+                    self.get_mut_current_cell().content = content;
+                }
+                self.view.is_editing_cell = true;
+            }
+            GridCommand::EditContent { coords, content } => {
+                let cell = self.get_mut_cell(coords);
+                cell.content = content;
+                cell.diagnostic = None;
+            }
+            GridCommand::CommitEdit(coords) => {
+                self.view.is_editing_cell = false;
+                self.update_cell_display(self.key(coords));
+                self.check_entry(coords);
+                self.record_edit(coords, self.view.previous_content.clone());
+            }
+            GridCommand::CancelEdit(coords) => {
+                let previous_content = self.view.previous_content.clone();
+                self.get_mut_cell(coords).content = previous_content;
+                self.view.is_editing_cell = false;
+                self.update_cell_display(self.key(coords));
+            }
+            GridCommand::SetCellContent { coords, content } => {
+                let old = std::mem::replace(&mut self.get_mut_cell(coords).content, content);
+                self.record_edit(coords, old);
+                self.update_cell_display(self.key(coords));
+            }
+            GridCommand::SetCellContents(contents) => {
+                for (coords, content) in &contents {
+                    let old =
+                        std::mem::replace(&mut self.get_mut_cell(*coords).content, content.clone());
+                    self.record_edit(*coords, old);
+                }
+                for (coords, _) in contents {
+                    self.update_cell_display(self.key(coords));
+                }
+            }
+            GridCommand::ClearContents => {
+                let cleared = self
+                    .view
+                    .selection()
+                    .cells()
+                    .into_iter()
+                    .filter(|coords| self.sheet().cells_map.contains_key(coords))
+                    .map(|coords| (coords, String::new()))
+                    .collect();
+                self.apply(GridCommand::SetCellContents(cleared));
+            }
+            GridCommand::Undo => self.undo(),
+            GridCommand::Redo => self.redo(),
+            GridCommand::SetFormulaBarEditing(editing) => {
+                if editing {
+                    self.view.previous_content = self.get_current_cell_content();
+                }
+                self.view.is_editing_formula_bar = editing;
+            }
+
+            GridCommand::ToggleFontStyle(style) => {
+                let on = !self
+                    .get_current_cell()
+                    .is_some_and(|cell| cell.format.has(style));
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords).format.set(style, on);
+                }
+            }
+
+            GridCommand::SetFontFamily(family) => {
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords).format.font_family = family.clone();
+                }
+            }
+            GridCommand::SetFontSize(size) => {
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords).format.font_size = size;
+                }
+            }
+            GridCommand::SetNumberFormat(number_format) => {
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords).format.number_format = number_format.clone();
+                }
+            }
+            GridCommand::ChangeDecimals(change) => {
+                for coords in self.view.selection().cells() {
+                    let cell = self.get_mut_cell(coords);
+                    let format = cell.format.number_format.clone();
+                    let decimals = format
+                        .decimals()
+                        .unwrap_or_else(|| decimals_of(&cell.display_value));
+                    let decimals = (decimals as i8 + change).clamp(0, MAX_DECIMALS as i8) as u8;
+                    cell.format.number_format = format.with_decimals(decimals);
+                }
+            }
+            GridCommand::SetColor { target, color } => {
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords)
+                        .format
+                        .set_color(target, color.clone());
+                }
+            }
+            GridCommand::AddConditionalFormat(rule) => {
+                let range = self.view.selection().active_range();
+                self.sheet_mut()
+                    .conditional_formats
+                    .push(ConditionalFormat { range, rule });
+            }
+            GridCommand::ClearConditionalFormats => {
+                let range = self.view.selection().active_range();
+                self.sheet_mut()
+                    .conditional_formats
+                    .retain(|format| !format.range.intersects(&range));
+            }
+            GridCommand::CreateTable(style) => {
+                let selection = self.view.selection();
+                let range = match selection.is_single_cell() {
+                    true => match self.get_data_region(self.current_cell()) {
+                        Some(region) => region,
+                        None => return,
+                    },
+                    false => selection.active_range(),
+                };
+                let overlaps = self
+                    .sheet()
+                    .tables
+                    .iter()
+                    .any(|table| table.range.intersects(&range));
+                if overlaps {
+                    return;
+                }
+                self.sheet_mut().tables.push(Table {
+                    range: CellRange {
+                        anchor: range.top_left(),
+                        extent: range.bottom_right(),
+                    },
+                    style,
+                    totals_row: false,
+                });
+            }
+            GridCommand::SetTableStyle(style) => {
+                if let Some(index) = self.sheet().table_at(self.current_cell()) {
+                    self.sheet_mut().tables[index].style = style;
+                }
+            }
+            GridCommand::ToggleTotalsRow => self.toggle_totals_row(),
+            GridCommand::RemoveTable => {
+                if let Some(index) = self.sheet().table_at(self.current_cell()) {
+                    self.sheet_mut().tables.remove(index);
+                }
+            }
+            GridCommand::SetValidation {
+                rule,
+                alert,
+                error_message,
+                prompt,
+            } => {
+                let range = self.view.selection().active_range();
+                let validations = &mut self.sheet_mut().validations;
+                validations.retain(|validation| !validation.range.intersects(&range));
+                validations.push(Validation {
+                    range: CellRange {
+                        anchor: range.top_left(),
+                        extent: range.bottom_right(),
+                    },
+                    rule,
+                    alert,
+                    error_message,
+                    prompt,
+                });
+            }
+            GridCommand::ClearValidation => {
+                let range = self.view.selection().active_range();
+                self.sheet_mut()
+                    .validations
+                    .retain(|validation| !validation.range.intersects(&range));
+            }
+            GridCommand::CleanData(cleaning) => {
+                let contents = self
+                    .view
+                    .selection()
+                    .cells()
+                    .into_iter()
+                    .filter_map(|coords| {
+                        let content = self.sheet().get_cell_content(coords);
+                        let cleaned = cleaning.apply(&content);
+                        (!content.starts_with('=') && cleaned != content)
+                            .then_some((coords, cleaned))
+                    })
+                    .collect();
+                self.apply(GridCommand::SetCellContents(contents));
+            }
+            GridCommand::ToggleAutoFilter => self.toggle_auto_filter(),
+            GridCommand::SetColumnFilter { column, filter } => {
+                let Some(auto_filter) = &mut self.sheet_mut().auto_filter else {
+                    return;
+                };
+                match filter {
+                    Some(filter) => auto_filter.filters.insert(column, filter),
+                    None => auto_filter.filters.remove(&column),
+                };
+                self.refilter();
+            }
+            GridCommand::SetHorizontalAlign(align) => {
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords).format.horizontal_align = align;
+                }
+            }
+            GridCommand::SetVerticalAlign(align) => {
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords).format.vertical_align = align;
+                }
+            }
+            GridCommand::SetTextOrientation { rotation, vertical } => {
+                for coords in self.view.selection().cells() {
+                    let format = &mut self.get_mut_cell(coords).format;
+                    format.rotation = rotation.clamp(-90, 90);
+                    format.vertical_text = vertical;
+                }
+            }
+            GridCommand::SetCellsLocked(locked) => {
+                for coords in self.view.selection().cells() {
+                    self.get_mut_cell(coords).format.unlocked = !locked;
+                }
+            }
+
+            GridCommand::Copy => self.view.clipboard = Some(self.copy_selection(false)),
+            GridCommand::Cut => self.view.clipboard = Some(self.copy_selection(true)),
+            GridCommand::Paste => self.paste(None),
+            GridCommand::PasteSpecial(options) => self.paste(Some(options)),
+            GridCommand::PasteRows(rows) => self.paste_rows(rows),
+            GridCommand::ImportCsv {
+                rows,
+                at,
+                new_sheet,
+                header,
+                types,
+            } => {
+                if new_sheet {
+                    self.apply(GridCommand::AddSheet);
+                }
+                self.start_import(rows, at, header, types);
+            }
+            GridCommand::ContinueImport => self.continue_import(),
+            GridCommand::ImportSheets(tables) => self.import_sheets(tables),
+            GridCommand::OpenWorkbook(file) => self.open_workbook(file),
+            GridCommand::AddSheets(sheets) => self.add_sheets(sheets),
+            GridCommand::ReceiveRemoteData { url, text } => self.receive_remote_data(url, text),
+            GridCommand::RefreshData(url) => self.refresh_data(url),
+            GridCommand::SetRefreshInterval { url, minutes } => match minutes {
+                Some(minutes) => {
+                    self.workbook.refresh_intervals.insert(url, minutes);
+                }
+                None => {
+                    self.workbook.refresh_intervals.remove(&url);
+                }
+            },
+            GridCommand::AddComment { coords, comment } => self.add_comment(coords, comment),
+            GridCommand::SetCommentResolved { coords, resolved } => {
+                let comments = &mut self.sheet_mut().comments;
+                if let Some(thread) = comments.iter_mut().find(|thread| thread.cell == coords) {
+                    thread.resolved = resolved;
+                }
+            }
+            GridCommand::DeleteCommentThread(coords) => {
+                self.sheet_mut()
+                    .comments
+                    .retain(|thread| thread.cell != coords);
+            }
+            GridCommand::SetProperties(properties) => self.set_properties(properties),
+            GridCommand::SetPrintSettings(settings) => self.sheet_mut().print_settings = settings,
+            GridCommand::AddProtectedRange {
+                editable,
+                description,
+            } => {
+                let range = self.view.selection().active_range();
+                let ranges = &mut self.sheet_mut().protected_ranges;
+                ranges.retain(|protected| !protected.range.intersects(&range));
+                ranges.push(ProtectedRange {
+                    range: CellRange {
+                        anchor: range.top_left(),
+                        extent: range.bottom_right(),
+                    },
+                    editable,
+                    description: description.trim().to_string(),
+                });
+            }
+            GridCommand::RemoveProtectedRange(index) => {
+                let ranges = &mut self.sheet_mut().protected_ranges;
+                if index < ranges.len() {
+                    ranges.remove(index);
+                }
+            }
+            GridCommand::SetSheetProtection(protected) => {
+                self.sheet_mut().protected = protected;
+                self.view.protection_message = None;
+            }
+            GridCommand::RestoreSession(session) => self.restore_session(session),
+            GridCommand::RestoreVersion(file) => {
+                let collaboration = self.view.collaboration.take();
+                self.open_workbook(file);
+                self.view.collaboration = collaboration;
+                self.view.has_unsaved_changes = true;
+            }
+            GridCommand::NewWorkbook => self.new_workbook(),
+            GridCommand::MarkSaved => self.view.has_unsaved_changes = false,
+            GridCommand::SetCollaboration(collaboration) => {
+                self.view.collaborators.clear();
+                self.view.collaboration = collaboration;
+            }
+            GridCommand::SetCollaborators(collaborators) => {
+                if self.view.collaboration.is_some() {
+                    self.view.collaborators = collaborators;
+                }
+            }
+            GridCommand::CancelImport => self.cancel_import(),
+            GridCommand::FillTo(target) => self.fill_to(target),
+            GridCommand::MoveRange(target) => self.move_range(target),
+            GridCommand::SortRange { column, descending } => self.sort_range(column, descending),
+            GridCommand::FlashFill => {
+                let contents = self.get_flash_fill();
+                self.apply(GridCommand::SetCellContents(contents));
+            }
+            GridCommand::ClearClipboard => self.view.clipboard = None,
+
+            GridCommand::SetSearchQuery(query) => {
+                let is_update = query.is_some() && self.view.search_query.is_some();
+                self.view.search_query = query;
+                if is_update {
+                    self.search_step(SearchStep::Stay);
+                }
+            }
+            GridCommand::Search(step) => {
+                self.search_step(step);
+            }
+
+            GridCommand::TracePrecedents => self.trace_precedents(),
+            GridCommand::TraceDependants => self.trace_dependants(),
+            GridCommand::ClearTraceArrows => self.view.trace_arrows.clear(),
+            GridCommand::DismissCircularReference => self.view.circular_reference = None,
+            GridCommand::DismissValidationAlert => self.view.validation_alert = None,
+            GridCommand::DismissProtectionMessage => self.view.protection_message = None,
+            GridCommand::SetReadOnly(read_only) => {
+                if read_only && self.view.is_editing_cell {
+                    self.apply(GridCommand::CommitEdit(self.current_cell()));
+                }
+                self.view.read_only = read_only;
+            }
+            GridCommand::SetZoom(zoom) => self.view.set_zoom(zoom),
+            GridCommand::Solve(problem) => {
+                self.view.solver_outcome = Some(self.solve(&problem));
+            }
+            GridCommand::DescribeStatistics {
+                input,
+                output,
+                labels,
+            } => self.describe_statistics(input, output, labels),
+            GridCommand::Histogram {
+                input,
+                output,
+                bin_width,
+            } => self.histogram(input, output, bin_width),
+            GridCommand::CreateChart(kind) => {
+                let selection = self.view.selection();
+                let range = match selection.is_single_cell() {
+                    true => match self.get_data_region(self.current_cell()) {
+                        Some(region) => region,
+                        None => return,
+                    },
+                    false => selection.active_range(),
+                };
+                let sheet = self.sheet_mut();
+                let id = sheet
+                    .charts
+                    .iter()
+                    .map(|chart| chart.id + 1)
+                    .max()
+                    .unwrap_or(0);
+                sheet.charts.push(Chart::new(id, kind, range));
+            }
+            GridCommand::SetChart(chart) => {
+                let sheet = self.sheet_mut();
+                if let Some(existing) = sheet.charts.iter_mut().find(|c| c.id == chart.id) {
+                    *existing = chart;
+                }
+            }
+            GridCommand::DeleteChart(id) => self.sheet_mut().charts.retain(|chart| chart.id != id),
+            GridCommand::Group(direction) => {
+                let (start, end) = self.selected_span(direction);
+                self.sheet_mut().outline_mut(direction).group(start, end);
+            }
+            GridCommand::Ungroup(direction) => {
+                let (start, end) = self.selected_span(direction);
+                self.sheet_mut().outline_mut(direction).ungroup(start, end);
+                self.recalculate_subtotals();
+            }
+            GridCommand::ClearOutline(direction) => {
+                self.sheet_mut().outline_mut(direction).clear();
+                self.recalculate_subtotals();
+            }
+            GridCommand::ToggleOutlineGroup {
+                direction,
+                level,
+                start,
+            } => {
+                self.sheet_mut().outline_mut(direction).toggle(level, start);
+                self.recalculate_subtotals();
+            }
+            GridCommand::ShowOutlineLevel { direction, level } => {
+                self.sheet_mut().outline_mut(direction).show_level(level);
+                self.recalculate_subtotals();
+            }
+            GridCommand::Subtotals {
+                range,
+                key_column,
+                function,
+                columns,
+            } => self.subtotals(range, key_column, function, &columns),
+            GridCommand::TakeBackEntry => {
+                if let Some(alert) = self.view.validation_alert.take() {
+                    if let Some(cell) = self.workbook.get_mut_cell(alert.key) {
+                        let old = std::mem::replace(&mut cell.content, alert.previous_content);
+                        if alert.key.sheet == self.view.active_sheet {
+                            self.record_edit(alert.key.coords, old);
+                        }
+                    }
+                    self.update_cell_display(alert.key);
+                }
+            }
+
+            GridCommand::WatchCell(key) => self.view.watch_cell(key),
+            GridCommand::UnwatchCell(key) => self.view.watched_cells.retain(|k| *k != key),
+
+            GridCommand::FreezePanes => self.view.freeze_panes(self.current_cell()),
+            GridCommand::UnfreezePanes => {
+                self.view.frozen_panes.remove(&self.view.active_sheet);
+            }
+            GridCommand::ResizeColumn { column, width } => {
+                let mut columns = self.get_selected_columns();
+                if !columns.contains(&(column as i32)) {
+                    columns = vec![column as i32];
+                }
+                for column in columns {
+                    self.sheet_mut().set_column_width(column as usize, width);
+                }
+            }
+            GridCommand::ResizeRow { row, height } => {
+                let mut rows = self.get_selected_rows();
+                if !rows.contains(&(row as i32)) {
+                    rows = vec![row as i32];
+                }
+                for row in rows {
+                    self.sheet_mut().set_row_height(row as usize, height);
+                }
+            }
+
+            GridCommand::SetProfiling(enabled) => {
+                self.view.profile = enabled.then(HashMap::new);
+            }
+            GridCommand::ResetProfile => {
+                if let Some(profile) = self.view.profile.as_mut() {
+                    profile.clear();
+                }
+            }
+
+            GridCommand::AddSheet => {
+                let sheet = self.workbook.add_sheet();
+                self.view.active_sheet = sheet;
+                self.recalculate_references_to(sheet);
+            }
+            GridCommand::ActivateSheet(sheet) => {
+                if self.workbook.get_sheet(sheet).is_some() {
+                    self.view.active_sheet = sheet;
+                }
+            }
+            GridCommand::RenameSheet { sheet, name } => {
+                if self.workbook.rename_sheet(sheet, &name) {
+                    // Formulas that referenced the new name before it existed resolve now
+                    self.recalculate_references_to(sheet);
+                }
+            }
+            GridCommand::MoveSheet { sheet, to_index } => self.workbook.move_sheet(sheet, to_index),
+            GridCommand::DeleteSheet {
+                sheet,
+                replace_with_values,
+            } => {
+                let Some(index) = self.workbook.sheets.iter().position(|s| s.id == sheet) else {
+                    return;
+                };
+                if self.workbook.sheets.len() == 1 {
+                    return;
+                }
+                let rewritten = self.workbook.delete_sheet(sheet, replace_with_values);
+                if self.view.active_sheet == sheet {
+                    let next = index.min(self.workbook.sheets.len() - 1);
+                    self.view.active_sheet = self.workbook.sheets[next].id;
+                }
+                self.view.forget_sheet(sheet);
+                for key in rewritten {
+                    self.update_cell_display(key);
+                }
+            }
+        }
+        // Tables take in what's typed under them once it's entered
+        if !self.view.is_editing_cell {
+            self.sheet_mut().sync_tables();
+        }
+    }
+
+    fn get_mut_cell(&mut self, coords: Coords) -> &mut Cell {
+        self.sheet_mut()
+            .cells_map
+            .entry(coords)
+            .or_insert(Cell::new())
+    }
+
+    /// Checks an entry just made in a cell of the active sheet against its rule, turning it
+    /// away if the rule says so, and raises the alert if it breaks it
+    fn check_entry(&mut self, coords: Coords) {
+        self.view.validation_alert = None;
+        let Some(validation) = self.sheet().validation_at(coords).cloned() else {
+            return;
+        };
+        if self.is_valid(coords) {
+            return;
+        }
+        let key = self.key(coords);
+        let previous_content = self.view.previous_content.clone();
+        if validation.alert == AlertStyle::Stop {
+            self.get_mut_cell(coords).content = previous_content.clone();
+            self.update_cell_display(key);
+        }
+        let message = validation.error_message.unwrap_or_else(|| {
+            format!(
+                "That value isn't allowed in {}",
+                coords_to_cell_address(coords)
+            )
+        });
+        self.view.validation_alert = Some(ValidationAlert {
+            key,
+            style: validation.alert,
+            message,
+            previous_content,
+        });
+    }
+
+    fn toggle_auto_filter(&mut self) {
+        if self.sheet().auto_filter.is_some() {
+            self.sheet_mut().auto_filter = None;
+            self.refilter();
+            return;
+        }
+        let current = self.current_cell();
+        let range = match self.sheet().table_at(current) {
+            Some(index) => self.sheet().tables[index].range,
+            None => match self.get_data_region(current) {
+                Some(region) => CellRange {
+                    anchor: region.top_left(),
+                    extent: region.bottom_right(),
+                },
+                None => return,
+            },
+        };
+        self.sheet_mut().auto_filter = Some(AutoFilter::new(range));
+    }
+
+    /// Hides the rows the filters don't match, then recalculates the SUBTOTALs that might
+    /// have skipped other rows before
+    fn refilter(&mut self) {
+        self.sheet_mut().apply_auto_filter();
+        self.recalculate_subtotals();
+    }
+
+    /// Recalculates the SUBTOTALs, after rows were hidden or shown
+    fn recalculate_subtotals(&mut self) {
+        let subtotals = self
+            .workbook
+            .sheets
+            .iter()
+            .flat_map(|sheet| {
+                sheet
+                    .cells_map
+                    .iter()
+                    .filter(|(_, cell)| cell.content.to_uppercase().contains("SUBTOTAL("))
+                    .map(|(coords, _)| CellKey {
+                        sheet: sheet.id,
+                        coords: *coords,
+                    })
+            })
+            .collect::<Vec<_>>();
+        for key in subtotals {
+            self.update_cell_display(key);
+        }
+    }
+
+    fn toggle_totals_row(&mut self) {
+        let Some(index) = self.sheet().table_at(self.current_cell()) else {
+            return;
+        };
+        let table = self.sheet().tables[index].clone();
+        let (top_left, bottom_right) = (table.range.top_left(), table.range.bottom_right());
+        if table.totals_row {
+            let cleared = table
+                .columns()
+                .map(|column| Coords {
+                    row: bottom_right.row,
+                    column,
+                })
+                .filter(|coords| self.sheet().cells_map.contains_key(coords))
+                .map(|coords| (coords, String::new()))
+                .collect();
+            let table = &mut self.sheet_mut().tables[index];
+            table.totals_row = false;
+            table.range.extent.row -= 1;
+            self.apply(GridCommand::SetCellContents(cleared));
+            return;
+        }
+
+        let row = bottom_right.row + 1;
+        let blocked = row >= self.sheet().row_heights.len() as i32
+            || table.columns().any(|column| {
+                let coords = Coords { row, column };
+                !self.sheet().get_cell_content(coords).is_empty()
+                    || self.sheet().table_at(coords).is_some()
+            });
+        if blocked {
+            return;
+        }
+        let data_rows = table.data_rows();
+        let totals = table
+            .columns()
+            .map(|column| {
+                let value = |row: i32| {
+                    self.sheet()
+                        .cells_map
+                        .get(&Coords { row, column })
+                        .map(|cell| cell.display_value.clone())
+                        .unwrap_or_default()
+                };
+                let is_number = |row: i32| value(row).parse::<f64>().is_ok();
+                let content = if column == top_left.column {
+                    "Total".to_string()
+                } else if data_rows.clone().any(is_number) {
+                    // Formulas have no ranges, so the column's cells are listed. Text would
+                    // make the sum an error, blanks count as nothing until filled in.
+                    let cells = data_rows
+                        .clone()
+                        .filter(|row| is_number(*row) || value(*row).is_empty())
+                        .map(|row| coords_to_cell_address(Coords { row, column }))
+                        .collect::<Vec<_>>();
+                    format!("=SUBTOTAL(109,{})", cells.join(","))
+                } else {
+                    String::new()
+                };
+                (Coords { row, column }, content)
+            })
+            .filter(|(_, content)| !content.is_empty())
+            .collect();
+        let table = &mut self.sheet_mut().tables[index];
+        table.totals_row = true;
+        table.range.extent.row = row;
+        self.apply(GridCommand::SetCellContents(totals));
+    }
+
+    fn copy_selection(&self, cut: bool) -> Clipboard {
+        let range = self.view.selection().active_range();
+        let top_left = range.top_left();
+        let bottom_right = range.bottom_right();
+        let mut contents = Vec::new();
+        let mut values = Vec::new();
+        let mut formats = Vec::new();
+        for coords in range.cells() {
+            let offset = Coords {
+                row: coords.row - top_left.row,
+                column: coords.column - top_left.column,
+            };
+            let cell = self.sheet().cells_map.get(&coords);
+            contents.push((offset, cell.map(|c| c.content.clone()).unwrap_or_default()));
+            values.push((
+                offset,
+                cell.map(|c| c.display_value.clone()).unwrap_or_default(),
+            ));
+            formats.push((offset, cell.map(|c| c.format.clone()).unwrap_or_default()));
+        }
+        Clipboard {
+            source: self.key(top_left),
+            rows: bottom_right.row - top_left.row + 1,
+            columns: bottom_right.column - top_left.column + 1,
+            contents,
+            values,
+            formats,
+            cut,
+        }
+    }
+
+    fn paste(&mut self, options: Option<PasteOptions>) {
+        let Some(clipboard) = self.view.clipboard.clone() else {
+            return;
+        };
+        let target = self.view.selection().active_range().top_left();
+        let (row_count, column_count) = (
+            self.sheet().row_heights.len() as i32,
+            self.sheet().column_widths.len() as i32,
+        );
+        if clipboard.cut && options.is_none() {
+            let rows = clipboard.rows.min(row_count - target.row);
+            let columns = clipboard.columns.min(column_count - target.column);
+            let to = self.key(target);
+            for key in self
+                .workbook
+                .move_cells(clipboard.source, rows, columns, to)
+            {
+                self.update_cell_display(key);
+            }
+            self.view.clipboard = None;
+        } else {
+            self.paste_copied(&clipboard, target, options.unwrap_or_default());
+        }
+
+        let (rows, columns) = match options.is_some_and(|options| options.transpose) {
+            true => (clipboard.columns, clipboard.rows),
+            false => (clipboard.rows, clipboard.columns),
+        };
+        self.view.select(target);
+        self.view.extend_selection(Coords {
+            row: (target.row + rows - 1).min(row_count - 1),
+            column: (target.column + columns - 1).min(column_count - 1),
+        });
+    }
+
+    fn paste_copied(&mut self, clipboard: &Clipboard, target: Coords, options: PasteOptions) {
+        let (row_count, column_count) = (
+            self.sheet().row_heights.len() as i32,
+            self.sheet().column_widths.len() as i32,
+        );
+        let source = clipboard.source.coords;
+        let cells = clipboard
+            .contents
+            .iter()
+            .zip(&clipboard.values)
+            .zip(&clipboard.formats)
+            .filter(|(((_, content), _), _)| !(options.skip_blanks && content.is_empty()))
+            .map(|(((offset, content), (_, value)), (_, format))| {
+                let placed = match options.transpose {
+                    true => Coords {
+                        row: offset.column,
+                        column: offset.row,
+                    },
+                    false => *offset,
+                };
+                let coords = Coords {
+                    row: target.row + placed.row,
+                    column: target.column + placed.column,
+                };
+                let content = match options.content {
+                    PasteContent::Values => value.clone(),
+                    PasteContent::All | PasteContent::Formulas if content.starts_with('=') => {
+                        let from = Coords {
+                            row: source.row + offset.row,
+                            column: source.column + offset.column,
+                        };
+                        match options.transpose {
+                            true => editing::transpose_references(content, from, coords),
+                            false => editing::shift_references(
+                                content,
+                                coords.row - from.row,
+                                coords.column - from.column,
+                            ),
+                        }
+                    }
+                    PasteContent::All | PasteContent::Formulas | PasteContent::Formats => {
+                        content.clone()
+                    }
+                };
+                (coords, content, format)
+            })
+            .filter(|(coords, _, _)| coords.row < row_count && coords.column < column_count)
+            .collect::<Vec<_>>();
+        if matches!(options.content, PasteContent::All | PasteContent::Formats) {
+            for (coords, _, format) in &cells {
+                self.get_mut_cell(*coords).format = (*format).clone();
+            }
+        }
+        if options.content != PasteContent::Formats {
+            let contents = cells
+                .into_iter()
+                .map(|(coords, content, _)| (coords, content))
+                .collect();
+            self.apply(GridCommand::SetCellContents(contents));
+        }
+    }
+
+    fn paste_rows(&mut self, rows: Vec<Vec<String>>) {
+        let target = self.view.selection().active_range().top_left();
+        let (row_count, column_count) = (
+            self.sheet().row_heights.len() as i32,
+            self.sheet().column_widths.len() as i32,
+        );
+        let mut extent = target;
+        let mut contents = Vec::new();
+        for (row_offset, row) in rows.into_iter().enumerate() {
+            for (column_offset, content) in row.into_iter().enumerate() {
+                let coords = Coords {
+                    row: target.row + row_offset as i32,
+                    column: target.column + column_offset as i32,
+                };
+                if coords.row < row_count && coords.column < column_count {
+                    extent.row = extent.row.max(coords.row);
+                    extent.column = extent.column.max(coords.column);
+                    contents.push((coords, content));
+                }
+            }
+        }
+        self.apply(GridCommand::SetCellContents(contents));
+
+        self.view.select(target);
+        self.view.extend_selection(extent);
+    }
+
+    fn receive_remote_data(&mut self, url: String, text: Option<String>) {
+        let data = match text {
+            Some(text) => RemoteData::Loaded(text),
+            None => RemoteData::Failed,
+        };
+        self.view.remote_data.insert(url.clone(), data);
+        let waiting = self
+            .workbook
+            .sheets
+            .iter()
+            .flat_map(|sheet| {
+                sheet
+                    .cells_map
+                    .iter()
+                    .filter(|(_, cell)| {
+                        remote::parse_call(&cell.content).is_some_and(|call| {
+                            self.argument_text(sheet.id, &call.url).as_deref() == Some(&url)
+                        })
+                    })
+                    .map(|(coords, _)| CellKey {
+                        sheet: sheet.id,
+                        coords: *coords,
+                    })
+            })
+            .collect::<Vec<_>>();
+        for key in waiting {
+            self.update_cell_display(key);
+        }
+    }
+
+    fn import_sheets(&mut self, tables: Vec<OdsTable>) {
+        let mut first = None;
+        for table in tables {
+            self.apply(GridCommand::AddSheet);
+            let sheet = self.view.active_sheet;
+            first.get_or_insert(sheet);
+            self.apply(GridCommand::RenameSheet {
+                sheet,
+                name: table.name,
+            });
+            let (row_count, column_count) = (
+                self.sheet().row_heights.len() as i32,
+                self.sheet().column_widths.len() as i32,
+            );
+            let mut contents = Vec::new();
+            for (row, fields) in table.rows.into_iter().enumerate() {
+                for (column, content) in fields.into_iter().enumerate() {
+                    let coords = Coords {
+                        row: row as i32,
+                        column: column as i32,
+                    };
+                    if !content.is_empty() && coords.row < row_count && coords.column < column_count
+                    {
+                        contents.push((coords, content));
+                    }
+                }
+            }
+            self.apply(GridCommand::SetCellContents(contents));
+        }
+        if let Some(sheet) = first {
+            self.apply(GridCommand::ActivateSheet(sheet));
+        }
+    }
+
+    fn move_range(&mut self, target: Coords) {
+        let source = self.view.selection().active_range();
+        let (top_left, bottom_right) = (source.top_left(), source.bottom_right());
+        let destination = self.get_move_range(target);
+        if destination.anchor == top_left {
+            return;
+        }
+        let (from, to) = (self.key(top_left), self.key(destination.anchor));
+        let rows = bottom_right.row - top_left.row + 1;
+        let columns = bottom_right.column - top_left.column + 1;
+        for key in self.workbook.move_cells(from, rows, columns, to) {
+            self.update_cell_display(key);
+        }
+        self.view.select(destination.anchor);
+        self.view.extend_selection(destination.extent);
+    }
+
+    fn describe_statistics(&mut self, input: CellRange, output: Coords, labels: bool) {
+        let (top_left, bottom_right) = (input.top_left(), input.bottom_right());
+        let first_row = if labels {
+            top_left.row + 1
+        } else {
+            top_left.row
+        };
+        let at = |row: i32, column: i32| Coords {
+            row: output.row + row,
+            column: output.column + column,
+        };
+        let mut contents = statistics::STATISTICS
+            .iter()
+            .enumerate()
+            .map(|(i, label)| (at(i as i32 + 1, 0), label.to_string()))
+            .collect::<Vec<_>>();
+        for (i, column) in (top_left.column..=bottom_right.column).enumerate() {
+            let display_value = |row: i32| {
+                self.sheet()
+                    .cells_map
+                    .get(&Coords { row, column })
+                    .map(|cell| cell.display_value.clone())
+                    .unwrap_or_default()
+            };
+            let name = match labels {
+                true => display_value(top_left.row),
+                false => format!("Column {}", i + 1),
+            };
+            let values = (first_row..=bottom_right.row)
+                .filter_map(|row| display_value(row).parse::<f64>().ok())
+                .collect::<Vec<_>>();
+            contents.push((at(0, i as i32 + 1), name));
+            for (j, statistic) in statistics::describe(&values).into_iter().enumerate() {
+                let value = match statistic {
+                    Some(value) => value.to_string(),
+                    None => "#DIV/0!".to_string(),
+                };
+                contents.push((at(j as i32 + 1, i as i32 + 1), value));
+            }
+        }
+        self.apply(GridCommand::SetCellContents(contents));
+    }
+
+    fn histogram(&mut self, input: CellRange, output: Coords, bin_width: Option<f64>) {
+        const BAR_LENGTH: usize = 20;
+        let values = input
+            .cells()
+            .filter_map(|coords| {
+                let cell = self.sheet().cells_map.get(&coords)?;
+                cell.display_value.parse::<f64>().ok()
+            })
+            .collect::<Vec<_>>();
+        let bins = statistics::histogram(&values, bin_width);
+        let most = bins.iter().map(|bin| bin.count).max().unwrap_or_default();
+        let row = |row: usize, values: [String; 4]| {
+            values.into_iter().enumerate().map(move |(column, value)| {
+                let coords = Coords {
+                    row: output.row + row as i32,
+                    column: output.column + column as i32,
+                };
+                (coords, value)
+            })
+        };
+        let header = ["From", "To", "Frequency", "Histogram"].map(String::from);
+        let mut contents = row(0, header).collect::<Vec<_>>();
+        for (i, bin) in bins.iter().enumerate() {
+            let bar = "█".repeat((bin.count * BAR_LENGTH).div_ceil(most.max(1)));
+            let values = [
+                bin.start.to_string(),
+                bin.end.to_string(),
+                bin.count.to_string(),
+                bar,
+            ];
+            contents.extend(row(i + 1, values));
+        }
+        self.apply(GridCommand::SetCellContents(contents));
+    }
+
+    fn subtotals(&mut self, range: CellRange, key_column: i32, function: u8, columns: &[i32]) {
+        let (top_left, bottom_right) = (range.top_left(), range.bottom_right());
+        let display_value = |grid: &Grid, row: i32, column: i32| {
+            grid.sheet()
+                .cells_map
+                .get(&Coords { row, column })
+                .map(|cell| cell.display_value.clone())
+                .unwrap_or_default()
+        };
+        // Each run's key and first and last rows
+        let mut runs: Vec<(String, i32, i32)> = Vec::new();
+        for row in top_left.row..=bottom_right.row {
+            let key = display_value(self, row, key_column);
+            match runs.last_mut() {
+                Some((last, _, end)) if *last == key => *end = row,
+                _ => runs.push((key, row, row)),
+            }
+        }
+        let inserted = runs.len() as i32 + 1;
+        let (row_count, column_count) = (
+            self.sheet().row_heights.len() as i32,
+            self.sheet().column_widths.len() as i32,
+        );
+        let last_row = self
+            .sheet()
+            .cells_map
+            .keys()
+            .map(|coords| coords.row)
+            .max()
+            .unwrap_or_default()
+            .max(bottom_right.row);
+        if last_row + inserted >= row_count || self.sheet().table_at(top_left).is_some() {
+            return;
+        }
+
+        // Bottom up, so each block lands on rows the one before vacated
+        let mut blocks = vec![(bottom_right.row + 1, last_row, inserted)];
+        blocks.extend(
+            runs.iter()
+                .enumerate()
+                .rev()
+                .map(|(i, (_, start, end))| (*start, *end, i as i32)),
+        );
+        for (first, last, offset) in blocks {
+            if first > last || offset == 0 {
+                continue;
+            }
+            let from = self.key(Coords {
+                row: first,
+                column: 0,
+            });
+            let to = self.key(Coords {
+                row: first + offset,
+                column: 0,
+            });
+            for key in self
+                .workbook
+                .move_cells(from, last - first + 1, column_count, to)
+            {
+                self.update_cell_display(key);
+            }
+        }
+
+        // Formulas have no ranges, so the cells are listed. Text would make the total an
+        // error, blanks count as nothing until filled in.
+        let total = |grid: &Grid, rows: &[i32], column: i32| {
+            let cells = rows
+                .iter()
+                .filter(|row| {
+                    let value = display_value(grid, **row, column);
+                    value.is_empty() || value.parse::<f64>().is_ok()
+                })
+                .map(|row| coords_to_cell_address(Coords { row: *row, column }))
+                .collect::<Vec<_>>();
+            format!("=SUBTOTAL({},{})", function, cells.join(","))
+        };
+        let mut contents = Vec::new();
+        let mut data_rows = Vec::new();
+        let mut outline = Outline::default();
+        let grand_total_row = bottom_right.row + inserted;
+        outline.group(top_left.row, grand_total_row - 1);
+        for (i, (key, start, end)) in runs.iter().enumerate() {
+            let rows = (start + i as i32..=end + i as i32).collect::<Vec<_>>();
+            let row = end + i as i32 + 1;
+            contents.push((
+                Coords {
+                    row,
+                    column: key_column,
+                },
+                format!("{} Total", key),
+            ));
+            for column in columns {
+                contents.push((
+                    Coords {
+                        row,
+                        column: *column,
+                    },
+                    total(self, &rows, *column),
+                ));
+            }
+            outline.group(rows[0], row - 1);
+            data_rows.extend(rows);
+        }
+        contents.push((
+            Coords {
+                row: grand_total_row,
+                column: key_column,
+            },
+            "Grand Total".to_string(),
+        ));
+        for column in columns {
+            let coords = Coords {
+                row: grand_total_row,
+                column: *column,
+            };
+            contents.push((coords, total(self, &data_rows, *column)));
+        }
+        self.sheet_mut().row_outline = outline;
+        self.apply(GridCommand::SetCellContents(contents));
+    }
+
+    fn sort_range(&mut self, column: i32, descending: bool) {
+        let Some(range) = self.get_sort_range() else {
+            return;
+        };
+        let (top_left, bottom_right) = (range.top_left(), range.bottom_right());
+        if !(top_left.column..=bottom_right.column).contains(&column) {
+            return;
+        }
+        let rows = (top_left.row..=bottom_right.row).collect::<Vec<_>>();
+        let keys = rows
+            .iter()
+            .map(|row| {
+                self.sheet()
+                    .cells_map
+                    .get(&Coords { row: *row, column })
+                    .map(|cell| cell.display_value.clone())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let order = sort::sorted_order(&keys, descending);
+
+        let mut taken = HashMap::new();
+        for coords in range.cells() {
+            if let Some(cell) = self.sheet_mut().cells_map.remove(&coords) {
+                taken.insert(coords, cell);
+            }
+        }
+        let had_cell = taken.keys().copied().collect::<Vec<_>>();
+        for (new_index, old_index) in order.into_iter().enumerate() {
+            let (from_row, to_row) = (rows[old_index], rows[new_index]);
+            for column in top_left.column..=bottom_right.column {
+                let Some(mut cell) = taken.remove(&Coords {
+                    row: from_row,
+                    column,
+                }) else {
+                    continue;
+                };
+                if cell.content.starts_with('=') {
+                    cell.content = editing::shift_references(&cell.content, to_row - from_row, 0);
+                }
+                let to = Coords {
+                    row: to_row,
+                    column,
+                };
+                self.sheet_mut().cells_map.insert(to, cell);
+            }
+        }
+        // Cells left empty recalculate too, for whatever depended on what was there
+        for coords in had_cell {
+            self.get_mut_cell(coords);
+        }
+        for coords in range.cells() {
+            if self.sheet().cells_map.contains_key(&coords) {
+                self.update_cell_display(self.key(coords));
+            }
+        }
+    }
+
+    fn fill_to(&mut self, target: Coords) {
+        let Some((range, direction)) = self.get_fill_range(target) else {
+            return;
+        };
+        let source = self.view.selection().active_range();
+        let (top_left, bottom_right) = (source.top_left(), source.bottom_right());
+        let (filled_top_left, filled_bottom_right) = (range.top_left(), range.bottom_right());
+
+        // Each column (filling up or down) or row (left or right) continues on its own,
+        // both seeds and targets listed in the direction of the fill
+        let lines = match direction {
+            MoveDirection::Down | MoveDirection::Up => (top_left.column..=bottom_right.column)
+                .map(|column| {
+                    let seeds = (top_left.row..=bottom_right.row)
+                        .map(|row| Coords { row, column })
+                        .collect::<Vec<_>>();
+                    let targets = (filled_top_left.row..=filled_bottom_right.row)
+                        .map(|row| Coords { row, column })
+                        .collect::<Vec<_>>();
+                    (seeds, targets)
+                })
+                .collect::<Vec<_>>(),
+            MoveDirection::Right | MoveDirection::Left => (top_left.row..=bottom_right.row)
+                .map(|row| {
+                    let seeds = (top_left.column..=bottom_right.column)
+                        .map(|column| Coords { row, column })
+                        .collect::<Vec<_>>();
+                    let targets = (filled_top_left.column..=filled_bottom_right.column)
+                        .map(|column| Coords { row, column })
+                        .collect::<Vec<_>>();
+                    (seeds, targets)
+                })
+                .collect::<Vec<_>>(),
+        };
+        let step = match direction {
+            MoveDirection::Down => (1, 0),
+            MoveDirection::Up => (-1, 0),
+            MoveDirection::Right => (0, 1),
+            MoveDirection::Left => (0, -1),
+        };
+        let backwards = matches!(direction, MoveDirection::Up | MoveDirection::Left);
+
+        let mut contents = Vec::new();
+        for (mut seeds, mut targets) in lines {
+            if backwards {
+                seeds.reverse();
+                targets.reverse();
+            }
+            let seed_contents = seeds
+                .iter()
+                .map(|coords| self.sheet().get_cell_content(*coords))
+                .collect::<Vec<_>>();
+            let filled = fill::extend(&seed_contents, targets.len(), step);
+            contents.extend(targets.into_iter().zip(filled));
+        }
+        self.apply(GridCommand::SetCellContents(contents));
+
+        // The selection grows over the filled cells, keeping its anchor
+        let anchor = source.anchor;
+        let corners = [top_left, bottom_right, filled_top_left, filled_bottom_right];
+        let (min_row, max_row) = (
+            corners.iter().map(|c| c.row).min().unwrap_or(anchor.row),
+            corners.iter().map(|c| c.row).max().unwrap_or(anchor.row),
+        );
+        let (min_column, max_column) = (
+            corners
+                .iter()
+                .map(|c| c.column)
+                .min()
+                .unwrap_or(anchor.column),
+            corners
+                .iter()
+                .map(|c| c.column)
+                .max()
+                .unwrap_or(anchor.column),
+        );
+        self.view.extend_selection(Coords {
+            row: if anchor.row == min_row {
+                max_row
+            } else {
+                min_row
+            },
+            column: if anchor.column == min_column {
+                max_column
+            } else {
+                min_column
+            },
+        });
+    }
+
+    /// The first and last row or column of the active range of the selection
+    fn selected_span(&self, direction: OutlineDirection) -> (i32, i32) {
+        let range = self.view.selection().active_range();
+        match direction {
+            OutlineDirection::Rows => (range.top_left().row, range.bottom_right().row),
+            OutlineDirection::Columns => (range.top_left().column, range.bottom_right().column),
+        }
+    }
+
+    fn recalculate_references_to(&mut self, sheet: SheetId) {
+        for key in self.workbook.get_inbound_references(sheet) {
+            self.update_cell_display(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        engine::{csv, solver::Relation},
+        model::{
+            command::*,
+            filter::Condition,
+            grid::{cell_address_to_coords, SpecialCells},
+            solver::{SolverConstraint, SolverOutcome},
+            workbook::MIN_ROW_HEIGHT,
+        },
+    };
+
+    fn coords(address: &str) -> Coords {
+        cell_address_to_coords(address).unwrap()
+    }
+
+    fn set(grid: &mut Grid, address: &str, content: &str) {
+        grid.apply(GridCommand::SetCellContent {
+            coords: coords(address),
+            content: content.to_string(),
+        });
+    }
+
+    fn display_value(grid: &Grid, address: &str) -> String {
+        grid.sheet().cells_map[&coords(address)].display_value.clone()
+    }
+
+    #[test]
+    fn set_cell_contents_recalculates_after_all_are_set() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, a2, a3] = ["A1", "A2", "A3"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "=a2 * 2".to_string()),
+            (a2, "=a3 + 1".to_string()),
+            (a3, "4".to_string()),
+        ]));
+        assert_eq!(display_value(&grid, "A1"), "10");
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: a3,
+            content: "=a1".to_string(),
+        });
+        assert_eq!(display_value(&grid, "A1"), "#REF!");
+        let path = [a3, a2, a1, a3].map(|coords| grid.key(coords)).to_vec();
+        assert_eq!(grid.view.circular_reference, Some(path));
+    }
+
+    #[test]
+    fn clearing_contents_recalculates_dependants() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, a2, b1] = ["A1", "A2", "B1"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "2".to_string()),
+            (a2, "=a1 * 3".to_string()),
+            (b1, "=a2 + 1".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(a2));
+        grid.apply(GridCommand::ClearContents);
+
+        assert_eq!(display_value(&grid, "A2"), "");
+        assert_eq!(display_value(&grid, "B1"), "1");
+        // A2 no longer depends on A1
+        assert!(!grid
+            .workbook
+            .cells_dep_graph
+            .contains_edge(grid.key(a1), grid.key(a2)));
+    }
+
+    #[test]
+    fn selecting_another_cell_commits_the_edit() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, b1] = ["A1", "B1"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContent {
+            coords: b1,
+            content: "=a1 + 1".to_string(),
+        });
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some("é".to_string()),
+        });
+        grid.apply(GridCommand::EditContent {
+            coords: a1,
+            content: "41".to_string(),
+        });
+        grid.apply(GridCommand::SelectCell(b1));
+
+        assert!(!grid.view.is_editing_cell);
+        assert_eq!(display_value(&grid, "A1"), "41");
+        assert_eq!(display_value(&grid, "B1"), "42");
+    }
+
+    #[test]
+    fn font_styles_toggle_from_the_current_cell() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, a2] = ["A1", "A2"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SelectCell(a2));
+        grid.apply(GridCommand::ToggleFontStyle(FontStyle::Bold));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(a2));
+
+        grid.apply(GridCommand::ToggleFontStyle(FontStyle::Bold));
+        assert!(grid.sheet().cells_map[&a1].format.bold);
+        assert!(grid.sheet().cells_map[&a2].format.bold);
+        grid.apply(GridCommand::ToggleFontStyle(FontStyle::Bold));
+        assert!(!grid.sheet().cells_map[&a2].format.bold);
+    }
+
+    #[test]
+    fn decimals_change_from_the_shown_value() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "=0.126".to_string(),
+        });
+        grid.apply(GridCommand::ChangeDecimals(-1));
+        let format = &grid.sheet().cells_map[&a1].format.number_format;
+        assert_eq!(*format, NumberFormat::Number(2));
+        assert_eq!(format.apply(&display_value(&grid, "A1")), "0.13");
+        // The value itself keeps its precision
+        assert_eq!(display_value(&grid, "A1"), "0.126");
+    }
+
+    #[test]
+    fn color_scales_follow_the_values() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        for (address, content) in [("A1", "0"), ("A2", "5"), ("A3", "10")] {
+            set(&mut grid, address, content);
+        }
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("A1").unwrap(),
+        ));
+        grid.apply(GridCommand::ExtendSelection(
+            cell_address_to_coords("A3").unwrap(),
+        ));
+        grid.apply(GridCommand::AddConditionalFormat(Rule::ColorScale {
+            min_color: "#ffffff".to_string(),
+            mid_color: None,
+            max_color: "#000000".to_string(),
+        }));
+        let background = |grid: &Grid, address: &str| {
+            grid.sheet().conditional_styles()[&cell_address_to_coords(address).unwrap()]
+                .background_color
+                .clone()
+        };
+        assert_eq!(background(&grid, "A2").as_deref(), Some("#808080"));
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: cell_address_to_coords("A3").unwrap(),
+            content: "20".to_string(),
+        });
+        assert_eq!(background(&grid, "A2").as_deref(), Some("#bfbfbf"));
+
+        grid.apply(GridCommand::ClearConditionalFormats);
+        assert!(grid.sheet().conditional_styles().is_empty());
+    }
+
+    #[test]
+    fn tables_grow_and_total() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let contents = [("A1", "Item"), ("B1", "Cost"), ("A2", "Tea"), ("B2", "3")];
+        for (address, content) in contents {
+            set(&mut grid, address, content);
+        }
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("A1").unwrap(),
+        ));
+        grid.apply(GridCommand::CreateTable(TableStyle::Blue));
+        let table_end = |grid: &Grid| grid.sheet().tables[0].range.bottom_right();
+        assert_eq!(table_end(&grid), cell_address_to_coords("B2").unwrap());
+
+        // Typing under the table takes the row in
+        grid.apply(GridCommand::SetCellContent {
+            coords: cell_address_to_coords("B3").unwrap(),
+            content: "4".to_string(),
+        });
+        assert_eq!(table_end(&grid), cell_address_to_coords("B3").unwrap());
+
+        grid.apply(GridCommand::ToggleTotalsRow);
+        assert_eq!(table_end(&grid), cell_address_to_coords("B4").unwrap());
+        assert_eq!(display_value(&grid, "A4"), "Total");
+        assert_eq!(display_value(&grid, "B4"), "7");
+
+        grid.apply(GridCommand::ToggleTotalsRow);
+        assert_eq!(table_end(&grid), cell_address_to_coords("B3").unwrap());
+        assert_eq!(display_value(&grid, "B4"), "");
+    }
+
+    #[test]
+    fn sorting_keeps_rows_together() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let contents = [
+            ("A1", "Name"),
+            ("B1", "Score"),
+            ("A2", "Bo"),
+            ("B2", "7"),
+            ("C2", "=B2*2"),
+            ("A3", "Al"),
+            ("B3", "3"),
+            ("C3", "=B3*2"),
+        ];
+        for (address, content) in contents {
+            set(&mut grid, address, content);
+        }
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("B2").unwrap(),
+        ));
+        grid.apply(GridCommand::SortRange {
+            column: 1,
+            descending: false,
+        });
+        // The header stays, the rows swap with their formulas following them
+        assert_eq!(display_value(&grid, "A1"), "Name");
+        assert_eq!(display_value(&grid, "A2"), "Al");
+        assert_eq!(display_value(&grid, "C2"), "6");
+        assert_eq!(
+            grid.sheet()
+                .get_cell_content(cell_address_to_coords("C3").unwrap()),
+            "=B3*2"
+        );
+        assert_eq!(display_value(&grid, "A3"), "Bo");
+
+        // The whole sort is one step back
+        grid.apply(GridCommand::Undo);
+        assert_eq!(display_value(&grid, "A2"), "Bo");
+        assert_eq!(display_value(&grid, "C2"), "14");
+        assert_eq!(display_value(&grid, "A3"), "Al");
+        grid.apply(GridCommand::Redo);
+        assert_eq!(display_value(&grid, "A2"), "Al");
+        assert_eq!(display_value(&grid, "C3"), "14");
+    }
+
+    #[test]
+    fn filters_hide_rows_from_subtotals() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let contents = [
+            ("A1", "Fruit"),
+            ("B1", "Count"),
+            ("A2", "Apple"),
+            ("B2", "3"),
+            ("A3", "Pear"),
+            ("B3", "8"),
+            ("A4", "Plum"),
+            ("B4", "12"),
+            ("D1", "=SUBTOTAL(9,B2,B3,B4)"),
+        ];
+        for (address, content) in contents {
+            set(&mut grid, address, content);
+        }
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("A2").unwrap(),
+        ));
+        grid.apply(GridCommand::ToggleAutoFilter);
+        grid.apply(GridCommand::SetColumnFilter {
+            column: 1,
+            filter: Some(ColumnFilter::Condition(Condition::GreaterThan(5.0))),
+        });
+        assert!(grid.sheet().is_row_hidden(1));
+        assert!(!grid.sheet().is_row_hidden(2));
+        assert_eq!(display_value(&grid, "D1"), "20");
+
+        grid.apply(GridCommand::SetColumnFilter {
+            column: 0,
+            filter: Some(ColumnFilter::Values(["Plum".to_string()].into())),
+        });
+        assert_eq!(display_value(&grid, "D1"), "12");
+
+        grid.apply(GridCommand::ToggleAutoFilter);
+        assert!(!grid.sheet().is_row_hidden(1));
+        assert_eq!(display_value(&grid, "D1"), "23");
+
+        // Collapsed groups are only left out by 101 to 109
+        grid.apply(GridCommand::SetCellContent {
+            coords: cell_address_to_coords("E1").unwrap(),
+            content: "=SUBTOTAL(109,B2,B3,B4)".to_string(),
+        });
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("A3").unwrap(),
+        ));
+        grid.apply(GridCommand::ExtendSelection(
+            cell_address_to_coords("A4").unwrap(),
+        ));
+        grid.apply(GridCommand::Group(OutlineDirection::Rows));
+        grid.apply(GridCommand::ToggleOutlineGroup {
+            direction: OutlineDirection::Rows,
+            level: 1,
+            start: 2,
+        });
+        assert!(grid.sheet().is_row_hidden(2));
+        assert_eq!(display_value(&grid, "D1"), "23");
+        assert_eq!(display_value(&grid, "E1"), "3");
+        grid.apply(GridCommand::ClearOutline(OutlineDirection::Rows));
+        assert_eq!(display_value(&grid, "E1"), "23");
+    }
+
+    #[test]
+    fn validation_alerts_by_style() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SelectCell(a1));
+        let set_alert = |grid: &mut Grid, alert: AlertStyle| {
+            grid.apply(GridCommand::SetValidation {
+                rule: ValidationRule::Number {
+                    min: Some(0.0),
+                    max: Some(10.0),
+                },
+                alert,
+                error_message: Some("From 0 to 10".to_string()),
+                prompt: None,
+            });
+        };
+        let enter = |grid: &mut Grid, content: &str| {
+            grid.apply(GridCommand::StartEditing {
+                initial_content: Some(content.to_string()),
+            });
+            grid.apply(GridCommand::CommitEdit(a1));
+        };
+        set_alert(&mut grid, AlertStyle::Stop);
+        enter(&mut grid, "5");
+        enter(&mut grid, "50");
+        assert_eq!(display_value(&grid, "A1"), "5");
+        let alert = grid.view.validation_alert.clone().unwrap();
+        assert_eq!(alert.message, "From 0 to 10");
+
+        set_alert(&mut grid, AlertStyle::Warning);
+        enter(&mut grid, "60");
+        assert_eq!(display_value(&grid, "A1"), "60");
+        assert!(grid.get_invalid_cells().contains(&a1));
+        grid.apply(GridCommand::TakeBackEntry);
+        assert_eq!(display_value(&grid, "A1"), "5");
+        assert!(grid.view.validation_alert.is_none());
+
+        grid.apply(GridCommand::SetValidation {
+            rule: ValidationRule::Formula("=A1-7".to_string()),
+            alert: AlertStyle::Information,
+            error_message: None,
+            prompt: None,
+        });
+        enter(&mut grid, "7");
+        assert_eq!(display_value(&grid, "A1"), "7");
+        assert_eq!(
+            grid.view
+                .validation_alert
+                .map(|alert| alert.message)
+                .as_deref(),
+            Some("That value isn't allowed in A1")
+        );
+    }
+
+    #[test]
+    fn cleaning_skips_formulas() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        for (address, content) in [("A1", " 1,200 "), ("A2", "=A1*2"), ("A3", "n/a")] {
+            set(&mut grid, address, content);
+        }
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("A1").unwrap(),
+        ));
+        grid.apply(GridCommand::ExtendSelection(
+            cell_address_to_coords("A3").unwrap(),
+        ));
+        grid.apply(GridCommand::CleanData(Cleaning::TextToNumbers));
+        assert_eq!(display_value(&grid, "A1"), "1200");
+        assert_eq!(display_value(&grid, "A2"), "2400");
+        assert_eq!(display_value(&grid, "A3"), "n/a");
+
+        // Each clean is one batch to undo
+        grid.apply(GridCommand::CleanData(Cleaning::UpperCase));
+        assert_eq!(display_value(&grid, "A3"), "N/A");
+        grid.apply(GridCommand::Undo);
+        assert_eq!(display_value(&grid, "A3"), "n/a");
+        assert_eq!(display_value(&grid, "A1"), "1200");
+        grid.apply(GridCommand::Undo);
+        assert_eq!(
+            grid.sheet()
+                .get_cell_content(cell_address_to_coords("A1").unwrap()),
+            " 1,200 "
+        );
+        assert_eq!(display_value(&grid, "A3"), "n/a");
+    }
+
+    #[test]
+    fn queries_spill_until_blocked() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let data = [
+            ("A1", "Region"),
+            ("B1", "Sales"),
+            ("A2", "West"),
+            ("B2", "10"),
+            ("A3", "East"),
+            ("B3", "5"),
+            ("A4", "West"),
+            ("B4", "7"),
+            ("D1", r#"=QUERY(A1:B4, "select A, sum(B) group by A")"#),
+            ("F1", "=E3*2"),
+        ];
+        for (address, content) in data {
+            set(&mut grid, address, content);
+        }
+        assert_eq!(display_value(&grid, "D1"), "Region");
+        assert_eq!(display_value(&grid, "E1"), "sum Sales");
+        assert_eq!(display_value(&grid, "D2"), "East");
+        assert_eq!(display_value(&grid, "F1"), "34");
+
+        set(&mut grid, "B2", "20");
+        assert_eq!(display_value(&grid, "E3"), "27");
+        assert_eq!(display_value(&grid, "F1"), "54");
+
+        set(&mut grid, "D3", "in the way");
+        assert_eq!(display_value(&grid, "D1"), "#SPILL!");
+        assert_eq!(display_value(&grid, "E3"), "");
+        assert_eq!(display_value(&grid, "F1"), "0");
+
+        set(&mut grid, "D3", "");
+        assert_eq!(display_value(&grid, "D3"), "West");
+        assert_eq!(display_value(&grid, "F1"), "54");
+    }
+
+    #[test]
+    fn queries_read_ranges_on_other_sheets() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let first = grid.view.active_sheet;
+        grid.apply(GridCommand::AddSheet);
+        for (address, content) in [("A1", "Name"), ("A2", "x"), ("A3", "y")] {
+            set(&mut grid, address, content);
+        }
+        grid.apply(GridCommand::AddSheet);
+        grid.apply(GridCommand::ActivateSheet(first));
+
+        set(&mut grid, "A1", r#"=QUERY(Sheet2!A1:A3, "select A")"#);
+        assert_eq!(display_value(&grid, "A3"), "y");
+        set(
+            &mut grid,
+            "A1",
+            r#"=QUERY(Sheet2!A1:Sheet2!A3, "select A")"#,
+        );
+        assert_eq!(display_value(&grid, "A3"), "y");
+        set(
+            &mut grid,
+            "A1",
+            r#"=QUERY(Sheet2!A1:Sheet3!A3, "select A")"#,
+        );
+        assert_eq!(display_value(&grid, "A1"), "#REF!");
+    }
+
+    #[test]
+    fn remote_formulas_wait_for_their_data() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let url = "https://example.com/data.csv";
+        set(&mut grid, "A1", &format!("=IMPORTCSV(\"{}\")", url));
+        set(&mut grid, "D1", &format!("=WEBSERVICE(\"{}\")", url));
+        set(&mut grid, "E1", "=B2*2");
+        assert_eq!(display_value(&grid, "A1"), "#BUSY!");
+        assert_eq!(display_value(&grid, "D1"), "#BUSY!");
+        assert_eq!(grid.view.pending_urls(), vec![url.to_string()]);
+
+        grid.apply(GridCommand::ReceiveRemoteData {
+            url: url.to_string(),
+            text: Some("Item,Price\r\nTea,4\r\n".to_string()),
+        });
+        assert!(grid.view.pending_urls().is_empty());
+        assert_eq!(display_value(&grid, "A1"), "Item");
+        assert_eq!(display_value(&grid, "B2"), "4");
+        assert_eq!(display_value(&grid, "D1"), "Item,Price\r\nTea,4\r\n");
+        assert_eq!(display_value(&grid, "E1"), "8");
+
+        let missing = "https://example.com/missing.csv";
+        set(&mut grid, "A5", &format!("=IMPORTCSV(\"{}\")", missing));
+        grid.apply(GridCommand::ReceiveRemoteData {
+            url: missing.to_string(),
+            text: None,
+        });
+        assert_eq!(display_value(&grid, "A5"), "#CONNECT!");
+    }
+
+    #[test]
+    fn remote_formulas_take_their_url_from_a_cell() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        set(&mut grid, "B1", "=WEBSERVICE(A1)");
+        assert_eq!(display_value(&grid, "B1"), "#N/A");
+        assert!(grid.view.pending_urls().is_empty());
+
+        let url = "https://example.com/status";
+        set(&mut grid, "A1", url);
+        assert_eq!(display_value(&grid, "B1"), "#BUSY!");
+        assert_eq!(grid.view.pending_urls(), vec![url.to_string()]);
+
+        grid.apply(GridCommand::ReceiveRemoteData {
+            url: url.to_string(),
+            text: Some("up".to_string()),
+        });
+        assert_eq!(display_value(&grid, "B1"), "up");
+    }
+
+    #[test]
+    fn solver_fills_in_the_optimum() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::SetCellContents(vec![
+            (coords("B1"), "=3*A1+5*A2".to_string()),
+            (coords("B2"), "=2*A2".to_string()),
+            (coords("B3"), "=3*A1+2*A2".to_string()),
+            (coords("C1"), "=A1^2".to_string()),
+        ]));
+        let constraint = |address: &str, bound: f64| SolverConstraint {
+            cell: coords(address),
+            relation: Relation::AtMost,
+            bound,
+        };
+        let mut problem = SolverProblem {
+            objective: coords("B1"),
+            maximize: true,
+            variables: CellRange {
+                anchor: coords("A1"),
+                extent: coords("A2"),
+            },
+            constraints: vec![
+                constraint("A1", 4.0),
+                constraint("B2", 12.0),
+                constraint("B3", 18.0),
+            ],
+        };
+        grid.apply(GridCommand::Solve(problem.clone()));
+        assert_eq!(
+            grid.view.solver_outcome,
+            Some(SolverOutcome::Solved { objective: 36.0 })
+        );
+        assert_eq!(display_value(&grid, "A1"), "2");
+        assert_eq!(display_value(&grid, "A2"), "6");
+
+        problem.objective = coords("C1");
+        grid.apply(GridCommand::Solve(problem));
+        assert_eq!(grid.view.solver_outcome, Some(SolverOutcome::NotLinear));
+        assert_eq!(display_value(&grid, "A1"), "2");
+    }
+
+    #[test]
+    fn statistics_summarize_each_column() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let contents = [("A1", "Price"), ("A2", "3"), ("A3", "n/a"), ("A4", "5")]
+            .map(|(address, content)| (coords(address), content.to_string()));
+        grid.apply(GridCommand::SetCellContents(contents.to_vec()));
+        grid.apply(GridCommand::DescribeStatistics {
+            input: CellRange {
+                anchor: coords("A1"),
+                extent: coords("A4"),
+            },
+            output: coords("C1"),
+            labels: true,
+        });
+        assert_eq!(display_value(&grid, "D1"), "Price");
+        assert_eq!(display_value(&grid, "C2"), "Count");
+        assert_eq!(display_value(&grid, "D2"), "2");
+        assert_eq!(display_value(&grid, "D3"), "4");
+        assert_eq!(display_value(&grid, "D10"), "#DIV/0!");
+    }
+
+    #[test]
+    fn histogram_counts_per_bin() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let contents = [("A1", "Score"), ("A2", "1"), ("A3", "4"), ("A4", "12")]
+            .map(|(address, content)| (coords(address), content.to_string()));
+        grid.apply(GridCommand::SetCellContents(contents.to_vec()));
+        grid.apply(GridCommand::Histogram {
+            input: CellRange {
+                anchor: coords("A1"),
+                extent: coords("A4"),
+            },
+            output: coords("C1"),
+            bin_width: Some(5.0),
+        });
+        assert_eq!(display_value(&grid, "C2"), "0");
+        assert_eq!(display_value(&grid, "E2"), "2");
+        assert_eq!(display_value(&grid, "E3"), "0");
+        assert_eq!(display_value(&grid, "E4"), "1");
+        assert_eq!(display_value(&grid, "F2"), "█".repeat(20));
+        assert_eq!(display_value(&grid, "F4"), "█".repeat(10));
+    }
+
+    #[test]
+    fn collapsed_groups_are_skipped_over() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::SelectCell(coords("A2")));
+        grid.apply(GridCommand::ExtendSelection(coords("A4")));
+        grid.apply(GridCommand::Group(OutlineDirection::Rows));
+        grid.apply(GridCommand::ToggleOutlineGroup {
+            direction: OutlineDirection::Rows,
+            level: 1,
+            start: 1,
+        });
+        assert!(grid.sheet().is_row_hidden(2));
+        assert_eq!(grid.get_cell_rect(coords("A5")).y, 25 + 25);
+
+        grid.apply(GridCommand::SelectCell(coords("A1")));
+        grid.apply(GridCommand::MoveSelection(MoveDirection::Down));
+        assert_eq!(grid.current_cell(), coords("A5"));
+    }
+
+    #[test]
+    fn subtotals_total_each_run_and_outline_them() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let contents = [
+            ("A1", "Region"),
+            ("B1", "Sales"),
+            ("A2", "East"),
+            ("B2", "3"),
+            ("A3", "East"),
+            ("B3", "4"),
+            ("A4", "West"),
+            ("B4", "10"),
+            ("A6", "Below"),
+            ("B6", "=B4*2"),
+        ];
+        for (address, content) in contents {
+            set(&mut grid, address, content);
+        }
+        grid.apply(GridCommand::Subtotals {
+            range: CellRange {
+                anchor: coords("A2"),
+                extent: coords("B4"),
+            },
+            key_column: 0,
+            function: 9,
+            columns: vec![1],
+        });
+        assert_eq!(display_value(&grid, "A4"), "East Total");
+        assert_eq!(display_value(&grid, "B4"), "7");
+        assert_eq!(display_value(&grid, "B5"), "10");
+        assert_eq!(display_value(&grid, "B6"), "10");
+        assert_eq!(display_value(&grid, "A7"), "Grand Total");
+        assert_eq!(display_value(&grid, "B7"), "17");
+        // The rows below move down with the reference to the row that moved
+        assert_eq!(grid.sheet().get_cell_content(coords("B9")), "=B5*2");
+
+        grid.apply(GridCommand::ShowOutlineLevel {
+            direction: OutlineDirection::Rows,
+            level: 2,
+        });
+        assert!(grid.sheet().is_row_hidden(1) && grid.sheet().is_row_hidden(4));
+        assert!(!grid.sheet().is_row_hidden(3) && !grid.sheet().is_row_hidden(5));
+    }
+
+    #[test]
+    fn csv_imports_into_a_new_sheet_by_column_type() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let first_sheet = grid.view.active_sheet;
+        let rows = csv::parse_csv("Code,Price\n007,\"1,200\"\n", ',');
+        grid.apply(GridCommand::ImportCsv {
+            rows: rows.into(),
+            at: coords("B2"),
+            new_sheet: true,
+            header: true,
+            types: vec![ColumnType::Text, ColumnType::Number],
+        });
+        assert_ne!(grid.view.active_sheet, first_sheet);
+        assert_eq!(display_value(&grid, "B3"), "007");
+        assert_eq!(display_value(&grid, "C3"), "1200");
+        let format = |address: &str| grid.sheet().cells_map[&coords(address)].format.clone();
+        assert!(format("C2").bold);
+        assert_eq!(format("B3").horizontal_align, Some(HorizontalAlign::Left));
+        assert_eq!(format("C3").horizontal_align, None);
+    }
+
+    #[test]
+    fn imported_sheets_keep_their_names_and_formulas() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let first_sheet = grid.view.active_sheet;
+        let row = |fields: &[&str]| fields.iter().map(|field| field.to_string()).collect();
+        grid.apply(GridCommand::ImportSheets(vec![
+            OdsTable {
+                name: "Summary".to_string(),
+                rows: vec![row(&["=Data!A1*2"])],
+            },
+            OdsTable {
+                name: "Data".to_string(),
+                rows: vec![row(&["21"])],
+            },
+        ]));
+        assert_eq!(grid.workbook.sheets.len(), 3);
+        assert_ne!(grid.view.active_sheet, first_sheet);
+        assert_eq!(grid.sheet().name, "Summary");
+        assert_eq!(display_value(&grid, "A1"), "42");
+    }
+
+    #[test]
+    fn cancel_edit_restores_previous_content() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "5".to_string(),
+        });
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some("7".to_string()),
+        });
+        assert_eq!(grid.get_current_cell_content(), "7");
+        grid.apply(GridCommand::CancelEdit(a1));
+        assert_eq!(display_value(&grid, "A1"), "5");
+        assert!(!grid.view.is_editing_cell);
+    }
+
+    #[test]
+    fn resizing_clamps_to_minimum_size() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::ResizeColumn {
+            column: 1,
+            width: 140,
+        });
+        grid.apply(GridCommand::ResizeRow { row: 2, height: 3 });
+        assert_eq!(grid.sheet().column_widths[1], 140);
+        assert_eq!(grid.sheet().row_heights[2], MIN_ROW_HEIGHT);
+
+        // Out of range indices are ignored
+        grid.apply(GridCommand::ResizeColumn {
+            column: 26,
+            width: 140,
+        });
+        assert_eq!(grid.sheet().column_widths.len(), 26);
+    }
+
+    #[test]
+    fn shift_arrows_extend_selection_from_anchor() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let b2 = cell_address_to_coords("B2").unwrap();
+        grid.apply(GridCommand::SelectCell(b2));
+        grid.apply(GridCommand::ExtendSelectionTowards(MoveDirection::Down));
+        grid.apply(GridCommand::ExtendSelectionTowards(MoveDirection::Left));
+        grid.apply(GridCommand::ExtendSelectionTowards(MoveDirection::Left));
+
+        let range = grid.view.selection().active_range();
+        assert_eq!(grid.current_cell(), b2);
+        assert_eq!(range.top_left(), cell_address_to_coords("A2").unwrap());
+        assert_eq!(range.bottom_right(), cell_address_to_coords("B3").unwrap());
+        assert_eq!(grid.get_selection_address(), "A2:B3");
+
+        grid.apply(GridCommand::MoveSelection(MoveDirection::Right));
+        assert!(grid.view.selection().is_single_cell());
+        assert_eq!(grid.get_current_cell_address(), "C2");
+    }
+
+    #[test]
+    fn whole_columns_and_rows_select_and_resize_together() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::SelectColumn(1));
+        grid.apply(GridCommand::ExtendSelection(Coords { row: 99, column: 2 }));
+        assert_eq!(grid.get_selection_address(), "B1:C100");
+        assert_eq!(grid.get_selected_columns(), vec![1, 2]);
+
+        grid.apply(GridCommand::ResizeColumn {
+            column: 2,
+            width: 40,
+        });
+        assert_eq!(grid.sheet().column_widths[1..4].to_vec(), vec![40, 40, 90]);
+
+        grid.apply(GridCommand::SelectCell(Coords { row: 4, column: 3 }));
+        grid.apply(GridCommand::SelectEntireRows);
+        assert_eq!(grid.get_selection_address(), "A5:Z5");
+        assert_eq!(grid.get_selected_rows(), vec![4]);
+        assert!(grid.get_selected_columns().is_empty());
+    }
+
+    #[test]
+    fn select_all_takes_the_data_region_then_the_sheet() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let cells =
+            ["B2", "C3", "D3", "F3"].map(|a| (cell_address_to_coords(a).unwrap(), "1".to_string()));
+        grid.apply(GridCommand::SetCellContents(cells.to_vec()));
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("B2").unwrap(),
+        ));
+
+        grid.apply(GridCommand::SelectAll);
+        assert_eq!(grid.get_selection_address(), "B2:D3");
+        grid.apply(GridCommand::SelectAll);
+        assert_eq!(grid.get_selection_address(), "A1:Z100");
+    }
+
+    #[test]
+    fn ctrl_arrows_jump_between_data_edges() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let cells =
+            ["A2", "A3", "A4", "A8"].map(|a| (cell_address_to_coords(a).unwrap(), "1".to_string()));
+        grid.apply(GridCommand::SetCellContents(cells.to_vec()));
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("A1").unwrap(),
+        ));
+
+        let mut stops = Vec::new();
+        for _ in 0..4 {
+            grid.apply(GridCommand::JumpSelection(MoveDirection::Down));
+            stops.push(grid.get_current_cell_address());
+        }
+        assert_eq!(stops, ["A2", "A4", "A8", "A100"]);
+
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("A2").unwrap(),
+        ));
+        grid.apply(GridCommand::ExtendSelectionToEdge(MoveDirection::Down));
+        assert_eq!(grid.get_selection_address(), "A2:A4");
+        grid.apply(GridCommand::ExtendSelectionToEdge(MoveDirection::Right));
+        assert_eq!(grid.get_selection_address(), "A2:Z4");
+    }
+
+    #[test]
+    fn paging_and_last_cell() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::MovePage {
+            down: true,
+            height: 260,
+        });
+        assert_eq!(grid.get_current_cell_address(), "A11");
+        grid.apply(GridCommand::MovePage {
+            down: false,
+            height: 10_000,
+        });
+        assert_eq!(grid.get_current_cell_address(), "A1");
+
+        let cells = ["C9", "E4"].map(|a| (cell_address_to_coords(a).unwrap(), "1".to_string()));
+        grid.apply(GridCommand::SetCellContents(cells.to_vec()));
+        grid.apply(GridCommand::GoToLastCell);
+        assert_eq!(grid.get_current_cell_address(), "E9");
+    }
+
+    #[test]
+    fn go_to_special_selects_cells_of_a_kind() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let cells = [("A1", "1"), ("B1", "=a1"), ("C1", "=1/0"), ("A2", "x")]
+            .map(|(a, content)| (cell_address_to_coords(a).unwrap(), content.to_string()));
+        grid.apply(GridCommand::SetCellContents(cells.to_vec()));
+
+        let formulas = grid.get_special_cells(SpecialCells::Formulas);
+        grid.apply(GridCommand::SelectCells(formulas));
+        assert_eq!(grid.get_selection_address(), "B1:C1");
+        let errors = grid.get_special_cells(SpecialCells::Errors);
+        assert_eq!(errors, [cell_address_to_coords("C1").unwrap()]);
+
+        let (sheet, range) = grid.resolve_range("A1:C2").unwrap();
+        grid.apply(GridCommand::GoToRange { sheet, range });
+        let blanks = grid.get_special_cells(SpecialCells::Blanks);
+        grid.apply(GridCommand::SelectCells(blanks));
+        assert_eq!(grid.get_selection_address(), "B2:C2");
+    }
+
+    #[test]
+    fn ctrl_click_accumulates_disjoint_ranges() {
+        let [a1, a2, c1, c2] = ["A1", "A2", "C1", "C2"].map(|a| cell_address_to_coords(a).unwrap());
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(a2));
+        grid.apply(GridCommand::AddToSelection(c1));
+        grid.apply(GridCommand::ExtendSelection(c2));
+        // Overlapping ranges still count each cell once
+        grid.apply(GridCommand::AddToSelection(a2));
+
+        assert_eq!(grid.get_selection_address(), "A1:A2,C1:C2,A2");
+        assert_eq!(grid.current_cell(), a2);
+        assert_eq!(grid.view.selection().cells(), vec![a1, c1, a2, c2]);
+    }
+
+    #[test]
+    fn paste_shifts_relative_references() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, b1, c1, d3] = ["A1", "B1", "C1", "D3"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "2".to_string()),
+            (b1, "3".to_string()),
+            (c1, "=a1 * $b$1".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(c1));
+        grid.apply(GridCommand::Copy);
+        grid.apply(GridCommand::SelectCell(d3));
+        grid.apply(GridCommand::Paste);
+        assert_eq!(grid.get_current_cell_content(), "=B3 * $b$1");
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: cell_address_to_coords("B3").unwrap(),
+            content: "5".to_string(),
+        });
+        assert_eq!(display_value(&grid, "D3"), "15");
+    }
+
+    #[test]
+    fn pasted_rows_fill_from_current_cell() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("Y1").unwrap(),
+        ));
+        grid.apply(GridCommand::PasteRows(vec![
+            vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            vec!["=y1 + z1".to_string()],
+        ]));
+        assert_eq!(display_value(&grid, "Y2"), "3");
+        // The column past the sheet's edge is dropped
+        assert_eq!(grid.get_selection_address(), "Y1:Z2");
+    }
+
+    #[test]
+    fn pasting_a_cut_moves_cells_once() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, b1, c1] = ["A1", "B1", "C1"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "4".to_string()),
+            (b1, "=a1 + 1".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::Cut);
+        grid.apply(GridCommand::SelectCell(c1));
+        grid.apply(GridCommand::Paste);
+
+        assert_eq!(grid.sheet().get_cell_content(b1), "=C1 + 1");
+        assert_eq!(display_value(&grid, "B1"), "5");
+        assert_eq!(display_value(&grid, "A1"), "");
+        assert!(grid.view.clipboard.is_none());
+    }
+
+    #[test]
+    fn paste_special_values_and_transpose() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, b1, a3] = ["A1", "B1", "A3"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "2".to_string()),
+            (b1, "=a1 * 3".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(b1));
+        grid.apply(GridCommand::Copy);
+
+        grid.apply(GridCommand::SelectCell(a3));
+        grid.apply(GridCommand::PasteSpecial(PasteOptions {
+            transpose: true,
+            ..Default::default()
+        }));
+        assert_eq!(grid.get_selection_address(), "A3:A4");
+        let a4 = cell_address_to_coords("A4").unwrap();
+        assert_eq!(grid.sheet().get_cell_content(a4), "=A3 * 3");
+
+        grid.apply(GridCommand::SelectCell(
+            cell_address_to_coords("C1").unwrap(),
+        ));
+        grid.apply(GridCommand::PasteSpecial(PasteOptions {
+            content: PasteContent::Values,
+            ..Default::default()
+        }));
+        let d1 = cell_address_to_coords("D1").unwrap();
+        assert_eq!(grid.sheet().get_cell_content(d1), "6");
+    }
+
+    #[test]
+    fn pasting_takes_the_copied_formats_along() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, b1, c1] = ["A1", "B1", "C1"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "2".to_string()),
+            (c1, "kept".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ToggleFontStyle(FontStyle::Bold));
+        grid.apply(GridCommand::Copy);
+
+        grid.apply(GridCommand::SelectCell(b1));
+        grid.apply(GridCommand::Paste);
+        assert_eq!(grid.sheet().get_cell_content(b1), "2");
+        assert!(grid.sheet().cells_map[&b1].format.bold);
+
+        grid.apply(GridCommand::SelectCell(c1));
+        grid.apply(GridCommand::PasteSpecial(PasteOptions {
+            content: PasteContent::Formats,
+            ..Default::default()
+        }));
+        assert_eq!(grid.sheet().get_cell_content(c1), "kept");
+        assert!(grid.sheet().cells_map[&c1].format.bold);
+    }
+
+    #[test]
+    fn pasting_formulas_leaves_the_formats_alone() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, b1, c1] = ["A1", "B1", "C1"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "2".to_string()),
+            (b1, "=a1 * 3".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(b1));
+        grid.apply(GridCommand::ToggleFontStyle(FontStyle::Bold));
+        grid.apply(GridCommand::Copy);
+        grid.apply(GridCommand::SelectCell(c1));
+        grid.apply(GridCommand::ToggleFontStyle(FontStyle::Italic));
+
+        grid.apply(GridCommand::PasteSpecial(PasteOptions {
+            content: PasteContent::Formulas,
+            ..Default::default()
+        }));
+        assert_eq!(grid.sheet().get_cell_content(c1), "=B1 * 3");
+        let format = &grid.sheet().cells_map[&c1].format;
+        assert!(format.italic && !format.bold);
+    }
+
+    #[test]
+    fn fill_handle_continues_formulas_and_constants() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, b1, b2, b4] = ["A1", "B1", "B2", "B4"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "2".to_string()),
+            (b1, "=a1 * 2".to_string()),
+            (b2, "x".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(b1));
+        grid.apply(GridCommand::ExtendSelection(b2));
+        grid.apply(GridCommand::FillTo(b4));
+
+        assert_eq!(
+            grid.sheet()
+                .get_cell_content(cell_address_to_coords("B3").unwrap()),
+            "=a3 * 2"
+        );
+        assert_eq!(grid.sheet().get_cell_content(b4), "x");
+        assert_eq!(grid.get_selection_address(), "B1:B4");
+        assert_eq!(grid.current_cell(), b1);
+    }
+
+    #[test]
+    fn dragged_range_moves_within_the_sheet() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, a2, c1] = ["A1", "A2", "C1"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "1".to_string()),
+            (a2, "2".to_string()),
+            (c1, "=a1 + a2".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(a2));
+        grid.apply(GridCommand::MoveRange(
+            cell_address_to_coords("B99").unwrap(),
+        ));
+
+        assert_eq!(grid.get_selection_address(), "B99:B100");
+        assert_eq!(grid.sheet().get_cell_content(c1), "=B99 + B100");
+        assert_eq!(display_value(&grid, "C1"), "3");
+        assert_eq!(grid.sheet().get_cell_content(a1), "");
+    }
+
+    #[test]
+    fn dragged_range_replaces_the_cells_it_lands_on() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, a2, c1, c2, d1] =
+            ["A1", "A2", "C1", "C2", "D1"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "1".to_string()),
+            (c1, "x".to_string()),
+            (c2, "y".to_string()),
+            (d1, "=c2".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(a2));
+        grid.apply(GridCommand::MoveRange(c1));
+
+        assert_eq!(grid.sheet().get_cell_content(c1), "1");
+        // The empty cell of the block empties the one it lands on
+        assert_eq!(grid.sheet().get_cell_content(c2), "");
+        assert_eq!(display_value(&grid, "C2"), "");
+        assert_eq!(display_value(&grid, "D1"), "0");
+    }
+
+    #[test]
+    fn dragged_range_replaces_a_cell_referencing_it() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, c5] = ["A1", "C5"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "7".to_string()),
+            (c5, "=a1".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::MoveRange(c5));
+
+        assert_eq!(grid.sheet().get_cell_content(c5), "7");
+        assert_eq!(display_value(&grid, "C5"), "7");
+        assert!(grid.view.circular_reference.is_none());
+    }
+
+    #[test]
+    fn flash_fill_learns_from_the_first_cells() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, a2, a3, b1, b2, b3] =
+            ["A1", "A2", "A3", "B1", "B2", "B3"].map(|a| cell_address_to_coords(a).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "Doe, John".to_string()),
+            (a2, "Smith, Jane".to_string()),
+            (a3, "Roe, Richard".to_string()),
+            (b1, "John".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(b2));
+        assert_eq!(
+            grid.get_flash_fill(),
+            vec![(b2, "Jane".to_string()), (b3, "Richard".to_string())]
+        );
+
+        grid.apply(GridCommand::FlashFill);
+        assert_eq!(display_value(&grid, "B3"), "Richard");
+    }
+
+    #[test]
+    fn frozen_panes_are_per_sheet() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let c3 = cell_address_to_coords("C3").unwrap();
+        grid.apply(GridCommand::SelectCell(c3));
+        grid.apply(GridCommand::FreezePanes);
+        assert_eq!(grid.view.frozen_panes(), Some(c3));
+
+        grid.apply(GridCommand::AddSheet);
+        assert_eq!(grid.view.frozen_panes(), None);
+        // Freezing at A1 leaves nothing to freeze
+        grid.apply(GridCommand::FreezePanes);
+        assert_eq!(grid.view.frozen_panes(), None);
+    }
+
+    #[test]
+    fn cross_sheet_references_follow_sheet_changes() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let first = grid.view.active_sheet;
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "=Sheet2!A1 + 1".to_string(),
+        });
+        assert_eq!(display_value(&grid, "A1"), "#REF!");
+
+        grid.apply(GridCommand::AddSheet);
+        let second = grid.view.active_sheet;
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "2".to_string(),
+        });
+        grid.apply(GridCommand::ActivateSheet(first));
+        assert_eq!(display_value(&grid, "A1"), "3");
+
+        grid.apply(GridCommand::RenameSheet {
+            sheet: second,
+            name: "Inputs".to_string(),
+        });
+        assert_eq!(grid.get_current_cell_content(), "=Inputs!A1 + 1");
+
+        grid.apply(GridCommand::DeleteSheet {
+            sheet: second,
+            replace_with_values: false,
+        });
+        assert_eq!(grid.get_current_cell_content(), "=#REF! + 1");
+        assert_eq!(display_value(&grid, "A1"), "#REF!");
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::date,
+    model::{
+        grid::{Coords, Grid},
+        workbook::{CellKey, Sheet},
+    },
+};
+
+/// A comment on a cell, and the replies to it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommentThread {
+    pub cell: Coords,
+    /// The comment that started the thread first, then the replies in order
+    pub comments: Vec<Comment>,
+    /// Resolved threads are kept but no longer flagged on the grid
+    #[serde(default)]
+    pub resolved: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    pub author: String,
+    /// When it was written, in seconds since 1970-01-01
+    pub timestamp: u64,
+    pub text: String,
+}
+
+impl Comment {
+    /// When it was written, as a date and a UTC time
+    pub fn written_at(&self) -> String {
+        date::format_timestamp(self.timestamp)
+    }
+}
+
+impl Sheet {
+    pub fn comment_thread_at(&self, coords: Coords) -> Option<&CommentThread> {
+        self.comments.iter().find(|thread| thread.cell == coords)
+    }
+    /// Whether a cell has a thread that's still open
+    pub fn has_open_comments(&self, coords: Coords) -> bool {
+        self.comment_thread_at(coords)
+            .is_some_and(|thread| !thread.resolved)
+    }
+}
+
+impl Grid {
+    /// Adds a comment to a cell of the active sheet, starting its thread or replying in it.
+    /// Replying to a resolved thread opens it again.
+    pub fn add_comment(&mut self, coords: Coords, comment: Comment) {
+        if comment.text.trim().is_empty() {
+            return;
+        }
+        let comments = &mut self.sheet_mut().comments;
+        match comments.iter_mut().find(|thread| thread.cell == coords) {
+            Some(thread) => {
+                thread.comments.push(comment);
+                thread.resolved = false;
+            }
+            None => comments.push(CommentThread {
+                cell: coords,
+                comments: vec![comment],
+                resolved: false,
+            }),
+        }
+    }
+
+    /// The threads of every sheet still open, sheet by sheet, row by row
+    pub fn get_open_comment_threads(&self) -> Vec<(CellKey, &CommentThread)> {
+        self.workbook
+            .sheets
+            .iter()
+            .flat_map(|sheet| {
+                let mut threads = sheet
+                    .comments
+                    .iter()
+                    .filter(|thread| !thread.resolved)
+                    .map(|thread| {
+                        let key = CellKey {
+                            sheet: sheet.id,
+                            coords: thread.cell,
+                        };
+                        (key, thread)
+                    })
+                    .collect::<Vec<_>>();
+                threads.sort_by_key(|(key, _)| key.coords);
+                threads
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{command::GridCommand, comment::*, file::read_workbook};
+
+    fn comment(author: &str, text: &str) -> Comment {
+        Comment {
+            author: author.to_string(),
+            timestamp: 1_700_000_000,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn replies_reopen_resolved_threads() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let b2 = Coords { row: 1, column: 1 };
+        grid.apply(GridCommand::AddComment {
+            coords: b2,
+            comment: comment("Ada", "Is this total right?"),
+        });
+        grid.apply(GridCommand::AddComment {
+            coords: Coords { row: 0, column: 0 },
+            comment: comment("Ada", "  "),
+        });
+        assert_eq!(grid.get_open_comment_threads().len(), 1);
+
+        grid.apply(GridCommand::SetCommentResolved {
+            coords: b2,
+            resolved: true,
+        });
+        assert!(!grid.sheet().has_open_comments(b2));
+        assert!(grid.get_open_comment_threads().is_empty());
+
+        grid.apply(GridCommand::AddComment {
+            coords: b2,
+            comment: comment("Grace", "Not quite, see row 4"),
+        });
+        let thread = grid.sheet().comment_thread_at(b2).unwrap();
+        assert!(!thread.resolved);
+        assert_eq!(thread.comments.len(), 2);
+        assert_eq!(thread.comments[1].author, "Grace");
+        assert_eq!(thread.comments[1].written_at(), "2023-11-14 22:13 UTC");
+
+        let saved = read_workbook(&grid.save_workbook()).unwrap();
+        assert_eq!(saved.sheets[0].comments, grid.sheet().comments);
+
+        grid.apply(GridCommand::DeleteCommentThread(b2));
+        assert!(grid.sheet().comment_thread_at(b2).is_none());
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{grid::Coords, view::CellRange, workbook::Sheet};
+
+/// A rule drawing the cells of a range by their values. The drawing is worked out from the
+/// values every time, so it follows them as they change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConditionalFormat {
+    pub range: CellRange,
+    pub rule: Rule,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Rule {
+    /// Backgrounds blended between `#rrggbb` colors at the range's lowest, middle (if
+    /// given) and highest numbers
+    ColorScale {
+        min_color: String,
+        mid_color: Option<String>,
+        max_color: String,
+    },
+    /// A bar across the cell, as long as the number is far from the range's lowest to its
+    /// highest
+    DataBar { color: String, show_value: bool },
+    /// One of three icons by where the number falls against two thresholds, as numbers or
+    /// as percentiles (0 to 100) of the range
+    IconSet {
+        icons: IconSet,
+        thresholds: [f64; 2],
+        percentiles: bool,
+    },
+}
+
+/// Icons from the lowest to the highest numbers
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum IconSet {
+    Arrows,
+    TrafficLights,
+    Flags,
+}
+
+/// How the rules covering a cell draw it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConditionalStyle {
+    pub background_color: Option<String>,
+    /// The bar's color and how much of the cell's width it takes, 0 to 1
+    pub data_bar: Option<(String, f64)>,
+    pub hide_value: bool,
+    /// The set and which of its icons, 0 for the lowest
+    pub icon: Option<(IconSet, usize)>,
+}
+
+impl ConditionalStyle {
+    /// Inline CSS declarations, drawn over the cell's own format
+    pub fn to_style(&self) -> String {
+        let mut style = String::new();
+        if let Some(color) = &self.background_color {
+            style.push_str(&format!("background-color: {};", color));
+        }
+        if let Some((color, length)) = &self.data_bar {
+            let percent = length * 100.0;
+            style.push_str(&format!(
+                "background-image: linear-gradient(to right, {color} {percent:.1}%, transparent {percent:.1}%);"
+            ));
+        }
+        style
+    }
+}
+
+impl Sheet {
+    /// The styles the sheet's rules give its cells, later rules drawing over earlier ones
+    pub fn conditional_styles(&self) -> HashMap<Coords, ConditionalStyle> {
+        let mut styles: HashMap<Coords, ConditionalStyle> = HashMap::new();
+        for format in &self.conditional_formats {
+            let numbers = format
+                .range
+                .cells()
+                .filter_map(|coords| Some((coords, self.number_at(coords)?)))
+                .collect::<Vec<_>>();
+            let Some((min, max)) = bounds(numbers.iter().map(|(_, number)| *number)) else {
+                continue;
+            };
+            let mut sorted = numbers
+                .iter()
+                .map(|(_, number)| *number)
+                .collect::<Vec<_>>();
+            sorted.sort_by(f64::total_cmp);
+            for (coords, number) in numbers {
+                // Where the number sits between the lowest and highest, 0 to 1
+                let position = match max > min {
+                    true => (number - min) / (max - min),
+                    false => 0.5,
+                };
+                let style = styles.entry(coords).or_default();
+                match &format.rule {
+                    Rule::ColorScale {
+                        min_color,
+                        mid_color,
+                        max_color,
+                    } => {
+                        style.background_color =
+                            color_scale(min_color, mid_color.as_deref(), max_color, position);
+                    }
+                    Rule::DataBar { color, show_value } => {
+                        style.data_bar = Some((color.clone(), position));
+                        style.hide_value = !show_value;
+                    }
+                    Rule::IconSet {
+                        icons,
+                        thresholds,
+                        percentiles,
+                    } => {
+                        let measure = match percentiles {
+                            true => percentile_of(number, &sorted),
+                            false => number,
+                        };
+                        let level = thresholds.iter().filter(|t| measure >= **t).count();
+                        style.icon = Some((*icons, level));
+                    }
+                }
+            }
+        }
+        styles
+    }
+
+    fn number_at(&self, coords: Coords) -> Option<f64> {
+        let value = &self.cells_map.get(&coords)?.display_value;
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+    }
+}
+
+fn bounds(numbers: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+    numbers.fold(None, |bounds, number| match bounds {
+        None => Some((number, number)),
+        Some((min, max)) => Some((min.min(number), max.max(number))),
+    })
+}
+
+/// The share of the other numbers below `number` as a percentage, like PERCENTRANK.INC
+fn percentile_of(number: f64, sorted: &[f64]) -> f64 {
+    if sorted.len() < 2 {
+        return 100.0;
+    }
+    let below = sorted.partition_point(|other| *other < number);
+    below as f64 / (sorted.len() - 1) as f64 * 100.0
+}
+
+/// The color `position` of the way along the scale, the middle color sitting halfway
+fn color_scale(min: &str, mid: Option<&str>, max: &str, position: f64) -> Option<String> {
+    match mid {
+        Some(mid) if position < 0.5 => blend(min, mid, position * 2.0),
+        Some(mid) => blend(mid, max, (position - 0.5) * 2.0),
+        None => blend(min, max, position),
+    }
+}
+
+fn blend(from: &str, to: &str, amount: f64) -> Option<String> {
+    let (from, to) = (rgb(from)?, rgb(to)?);
+    let channel = |i: usize| {
+        let (from, to) = (from[i] as f64, to[i] as f64);
+        (from + (to - from) * amount.clamp(0.0, 1.0)).round() as u8
+    };
+    Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(0),
+        channel(1),
+        channel(2)
+    ))
+}
+
+fn rgb(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::conditional::*;
+
+    #[test]
+    fn color_scales_blend_between_stops() {
+        assert_eq!(
+            color_scale("#000000", None, "#ffffff", 0.5).as_deref(),
+            Some("#808080")
+        );
+        assert_eq!(
+            color_scale("#ff0000", Some("#ffff00"), "#00ff00", 0.25).as_deref(),
+            Some("#ff8000")
+        );
+        assert_eq!(
+            color_scale("#ff0000", Some("#ffff00"), "#00ff00", 1.0).as_deref(),
+            Some("#00ff00")
+        );
+    }
+
+    #[test]
+    fn icons_by_percentile() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile_of(1.0, &sorted), 0.0);
+        assert_eq!(percentile_of(3.0, &sorted), 50.0);
+        assert_eq!(percentile_of(5.0, &sorted), 100.0);
+    }
+
+    #[test]
+    fn data_bars_draw_as_a_gradient() {
+        let style = ConditionalStyle {
+            data_bar: Some(("#638ec6".to_string(), 0.25)),
+            ..Default::default()
+        };
+        assert_eq!(
+            style.to_style(),
+            "background-image: linear-gradient(to right, #638ec6 25.0%, transparent 25.0%);"
+        );
+    }
+}
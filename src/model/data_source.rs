@@ -0,0 +1,127 @@
+use crate::{
+    engine::remote::{self, RemoteData},
+    model::{grid::Grid, view::CellRange, workbook::CellKey},
+};
+
+/// Minutes a source can be refreshed every
+pub const REFRESH_INTERVALS: [u32; 6] = [1, 5, 15, 30, 60, 240];
+
+/// A URL formulas like IMPORTCSV fetch from, with the cells its data fills
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataSource {
+    pub url: String,
+    /// Each formula fetching it, with the range its table covers
+    pub ranges: Vec<(CellKey, CellRange)>,
+    /// None until it's been asked for
+    pub data: Option<RemoteData>,
+    /// Minutes between refreshes, None to refresh only when asked
+    pub refresh_interval: Option<u32>,
+}
+
+impl Grid {
+    /// The URLs formulas fetch from, in order
+    pub fn data_sources(&self) -> Vec<DataSource> {
+        let mut sources: Vec<DataSource> = Vec::new();
+        for sheet in &self.workbook.sheets {
+            for (coords, cell) in &sheet.cells_map {
+                let Some(call) = remote::parse_call(&cell.content) else {
+                    continue;
+                };
+                let Some(url) = self
+                    .argument_text(sheet.id, &call.url)
+                    .filter(|url| !url.is_empty())
+                else {
+                    continue;
+                };
+                let range = match sheet.spills.get(coords) {
+                    Some(spill) if !spill.blocked => spill.range(*coords),
+                    _ => CellRange::cell(*coords),
+                };
+                let key = CellKey {
+                    sheet: sheet.id,
+                    coords: *coords,
+                };
+                match sources.iter_mut().find(|source| source.url == url) {
+                    Some(source) => source.ranges.push((key, range)),
+                    None => {
+                        let refresh_interval = self.workbook.refresh_intervals.get(&url);
+                        sources.push(DataSource {
+                            data: self.view.remote_data.get(&url).cloned(),
+                            refresh_interval: refresh_interval.copied(),
+                            url,
+                            ranges: vec![(key, range)],
+                        });
+                    }
+                }
+            }
+        }
+        for source in &mut sources {
+            source
+                .ranges
+                .sort_by_key(|(key, _)| (key.sheet, key.coords.row, key.coords.column));
+        }
+        sources.sort_by(|a, b| a.url.cmp(&b.url));
+        sources
+    }
+
+    /// Fetches a URL's data again, or every URL's, keeping what formulas show until it's back
+    pub fn refresh_data(&mut self, url: Option<String>) {
+        for (source, data) in self.view.remote_data.iter_mut() {
+            if url.as_ref().is_some_and(|url| url != source) {
+                continue;
+            }
+            *data = match std::mem::replace(data, RemoteData::Pending) {
+                RemoteData::Loaded(text) | RemoteData::Refreshing(text) => {
+                    RemoteData::Refreshing(text)
+                }
+                RemoteData::Pending | RemoteData::Failed => RemoteData::Pending,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{command::GridCommand, data_source::*, grid::cell_address_to_coords};
+
+    #[test]
+    fn refreshing_keeps_data_until_the_new_data_is_back() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let coords = |address: &str| cell_address_to_coords(address).unwrap();
+        let url = "https://example.com/rates.csv";
+        grid.apply(GridCommand::SetCellContents(vec![
+            (coords("A1"), format!("=IMPORTCSV(\"{}\")", url)),
+            (coords("D1"), "=B1*10".to_string()),
+        ]));
+        grid.apply(GridCommand::ReceiveRemoteData {
+            url: url.to_string(),
+            text: Some("EUR,1\nUSD,2\n".to_string()),
+        });
+        let sources = grid.data_sources();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(
+            sources[0].ranges[0].1,
+            CellRange {
+                anchor: coords("A1"),
+                extent: coords("B2"),
+            }
+        );
+        assert_eq!(grid.sheet().cells_map[&coords("D1")].display_value, "10");
+
+        grid.apply(GridCommand::SetRefreshInterval {
+            url: url.to_string(),
+            minutes: Some(5),
+        });
+        grid.apply(GridCommand::RefreshData(None));
+        assert_eq!(grid.view.pending_urls(), vec![url.to_string()]);
+        assert_eq!(grid.sheet().cells_map[&coords("D1")].display_value, "10");
+        assert_eq!(grid.data_sources()[0].refresh_interval, Some(5));
+
+        grid.apply(GridCommand::ReceiveRemoteData {
+            url: url.to_string(),
+            text: Some("EUR,3\n".to_string()),
+        });
+        assert_eq!(grid.sheet().cells_map[&coords("D1")].display_value, "30");
+        assert_eq!(grid.sheet().cells_map[&coords("A2")].display_value, "");
+    }
+}
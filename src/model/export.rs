@@ -0,0 +1,267 @@
+use crate::{
+    engine::ods::OdsSheet,
+    model::{
+        clipboard::escape_html,
+        format::DEFAULT_FONT_FAMILY,
+        grid::{Coords, Grid},
+        view::CellRange,
+        workbook::Sheet,
+    },
+};
+
+/// Grid lines and padding of the cells of an HTML export
+const HTML_CELL_STYLE: &str = "border: 1px solid #d0d0d0; padding: 2px 5px; white-space: pre;";
+
+/// What an export writes for each cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportContent {
+    /// Computed results, for other applications
+    Values,
+    /// Contents as typed, for importing back into the app
+    Formulas,
+}
+
+impl Grid {
+    /// Rows of the cells in use on the active sheet, from A1
+    pub fn export_rows(&self, content: ExportContent) -> Vec<Vec<String>> {
+        let range = CellRange {
+            anchor: Coords { row: 0, column: 0 },
+            extent: self.get_used_range_end(),
+        };
+        self.export_range(range, content)
+    }
+
+    /// Rows of the selected cells. Rows and columns between the ranges of a selection are
+    /// left out, and cells of the rows and columns kept that aren't selected are blank.
+    pub fn export_selection(&self, content: ExportContent) -> Vec<Vec<String>> {
+        let selection = self.view.selection();
+        let (rows, columns) = self.export_lines(true);
+        rows.iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        let coords = Coords {
+                            row: *row,
+                            column: *column,
+                        };
+                        match selection.contains(coords) {
+                            true => self.export_text(coords, content),
+                            false => String::new(),
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// A table of the computed values as they're shown, each cell styled with its format,
+    /// for pasting into emails and documents. Takes the cells in use, or the selected ones
+    /// like `export_selection`.
+    pub fn export_html(&self, selection_only: bool) -> String {
+        let (rows, columns) = self.export_lines(selection_only);
+        self.html_table(&rows, &columns, selection_only)
+    }
+
+    /// A styled table of the given rows and columns of the active sheet, leaving cells outside
+    /// the selection blank if `selection_only`
+    pub fn html_table(&self, rows: &[i32], columns: &[i32], selection_only: bool) -> String {
+        let selection = self.view.selection();
+        let sheet = self.sheet();
+        let widths = columns
+            .iter()
+            .map(|column| {
+                let width = sheet.column_widths[*column as usize];
+                format!(r#"<col style="width: {}px;">"#, width)
+            })
+            .collect::<String>();
+        let rows = rows
+            .iter()
+            .map(|row| {
+                let height = sheet.row_heights[*row as usize];
+                let cells = columns
+                    .iter()
+                    .map(|column| {
+                        let coords = Coords {
+                            row: *row,
+                            column: *column,
+                        };
+                        let cell = sheet
+                            .cells_map
+                            .get(&coords)
+                            .filter(|_| !selection_only || selection.contains(coords));
+                        let (style, shown) = match cell {
+                            Some(cell) => (
+                                cell.format.table_cell_style(&cell.display_value),
+                                cell.format.number_format.apply(&cell.display_value),
+                            ),
+                            None => Default::default(),
+                        };
+                        format!(
+                            r#"<td style="{}{}">{}</td>"#,
+                            HTML_CELL_STYLE,
+                            style,
+                            escape_html(&shown)
+                        )
+                    })
+                    .collect::<String>();
+                format!(r#"<tr style="height: {}px;">{}</tr>"#, height, cells)
+            })
+            .collect::<String>();
+        format!(
+            r#"<table style="border-collapse: collapse; font-family: {};">{}{}</table>"#,
+            DEFAULT_FONT_FAMILY.replace('"', "'"),
+            widths,
+            rows
+        )
+    }
+
+    /// The rows and columns an export takes in, those of the cells in use from A1 or those of
+    /// the selected cells, in order
+    fn export_lines(&self, selection_only: bool) -> (Vec<i32>, Vec<i32>) {
+        if !selection_only {
+            let end = self.get_used_range_end();
+            return ((0..=end.row).collect(), (0..=end.column).collect());
+        }
+        let cells = self.view.selection().cells();
+        let mut rows = cells.iter().map(|coords| coords.row).collect::<Vec<_>>();
+        let mut columns = cells.iter().map(|coords| coords.column).collect::<Vec<_>>();
+        for lines in [&mut rows, &mut columns] {
+            lines.sort();
+            lines.dedup();
+        }
+        (rows, columns)
+    }
+
+    pub fn export_range(&self, range: CellRange, content: ExportContent) -> Vec<Vec<String>> {
+        let (top_left, bottom_right) = (range.top_left(), range.bottom_right());
+        (top_left.row..=bottom_right.row)
+            .map(|row| {
+                (top_left.column..=bottom_right.column)
+                    .map(|column| self.export_text(Coords { row, column }, content))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Every sheet's cells in use, from A1, each cell's content along with its value
+    pub fn export_sheets(&self) -> Vec<OdsSheet> {
+        self.workbook
+            .sheets
+            .iter()
+            .map(|sheet| OdsSheet {
+                name: sheet.name.clone(),
+                rows: sheet_rows(sheet),
+            })
+            .collect()
+    }
+
+    fn export_text(&self, coords: Coords, content: ExportContent) -> String {
+        let cell = self.sheet().cells_map.get(&coords);
+        let text = cell.map(|cell| match content {
+            ExportContent::Values => &cell.display_value,
+            ExportContent::Formulas => &cell.content,
+        });
+        text.cloned().unwrap_or_default()
+    }
+}
+
+fn sheet_rows(sheet: &Sheet) -> Vec<Vec<(String, String)>> {
+    let cells = sheet
+        .cells_map
+        .iter()
+        .filter(|(_, cell)| !cell.content.is_empty());
+    let Some(end) = cells
+        .clone()
+        .map(|(coords, _)| *coords)
+        .reduce(|end, coords| Coords {
+            row: end.row.max(coords.row),
+            column: end.column.max(coords.column),
+        })
+    else {
+        return Vec::new();
+    };
+    (0..=end.row)
+        .map(|row| {
+            (0..=end.column)
+                .map(
+                    |column| match sheet.cells_map.get(&Coords { row, column }) {
+                        Some(cell) => (cell.content.clone(), cell.display_value.clone()),
+                        None => Default::default(),
+                    },
+                )
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{
+        command::GridCommand,
+        export::*,
+        format::{FontStyle, NumberFormat},
+        grid::cell_address_to_coords,
+    };
+
+    #[test]
+    fn exports_values_or_formulas() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        for (address, content) in [("A1", "2"), ("B2", "=A1*3")] {
+            grid.apply(GridCommand::SetCellContent {
+                coords: cell_address_to_coords(address).unwrap(),
+                content: content.to_string(),
+            });
+        }
+        assert_eq!(
+            grid.export_rows(ExportContent::Values),
+            [vec!["2", ""], vec!["", "6"]]
+        );
+        assert_eq!(
+            grid.export_rows(ExportContent::Formulas),
+            [vec!["2", ""], vec!["", "=A1*3"]]
+        );
+    }
+
+    #[test]
+    fn selections_export_without_the_gaps_between_ranges() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let coords = |address: &str| cell_address_to_coords(address).unwrap();
+        for (address, content) in [("A1", "1"), ("C1", "3"), ("A3", "7"), ("C3", "9")] {
+            grid.apply(GridCommand::SetCellContent {
+                coords: coords(address),
+                content: content.to_string(),
+            });
+        }
+        grid.apply(GridCommand::SelectCell(coords("A1")));
+        grid.apply(GridCommand::AddToSelection(coords("C3")));
+        assert_eq!(
+            grid.export_selection(ExportContent::Values),
+            [vec!["1", ""], vec!["", "9"]]
+        );
+    }
+
+    #[test]
+    fn html_exports_show_formatted_values_in_styled_cells() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let coords = |address: &str| cell_address_to_coords(address).unwrap();
+        for (address, content) in [("A1", "Total <net>"), ("B1", "=2*3")] {
+            grid.apply(GridCommand::SetCellContent {
+                coords: coords(address),
+                content: content.to_string(),
+            });
+        }
+        grid.apply(GridCommand::SelectCell(coords("A1")));
+        grid.apply(GridCommand::ToggleFontStyle(FontStyle::Bold));
+        grid.apply(GridCommand::SelectCell(coords("B1")));
+        grid.apply(GridCommand::SetNumberFormat(NumberFormat::Currency(2)));
+
+        let html = grid.export_html(false);
+        assert_eq!(html.matches("<td").count(), 2);
+        assert!(html.contains("font-weight: bold;text-align: left;"));
+        assert!(html.contains("Total &lt;net&gt;</td>"));
+        assert!(html.contains("text-align: right; vertical-align: middle;\">$6.00</td>"));
+        // Only the selected cell
+        assert_eq!(grid.export_html(true).matches("<td").count(), 1);
+    }
+}
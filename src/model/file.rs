@@ -0,0 +1,631 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    chart::Chart,
+    command::GridCommand,
+    comment::CommentThread,
+    conditional::ConditionalFormat,
+    filter::AutoFilter,
+    format::CellFormat,
+    grid::{Cell, Coords, Grid},
+    history::CellHistory,
+    outline::Outline,
+    print::PrintSettings,
+    properties::DocumentProperties,
+    protection::ProtectedRange,
+    table::Table,
+    validation::Validation,
+    view::Selection,
+};
+
+pub const FILE_EXTENSION: &str = "rsheet";
+
+/// The version of the format files are written in
+pub const FILE_VERSION: u32 = 1;
+
+/// A workbook as saved to a `.rsheet` file, in JSON. Keeps what CSV loses: formulas, formats,
+/// sizes, sheets, frozen panes, outlines, conditional formats, tables, filters, charts,
+/// validation rules, comments, edit history, print settings and document properties.
+///
+/// Fields added in later versions are skipped by earlier ones and default when missing, so a
+/// version can read files from newer ones as long as they don't need a change it doesn't know
+/// about, like a new kind of rule. Files record the oldest version that can read them for that.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkbookFile {
+    /// The version that wrote the file
+    pub version: u32,
+    /// The oldest version that can read the file
+    pub readable_from: u32,
+    /// Index of the sheet shown on opening
+    #[serde(default)]
+    pub active_sheet: usize,
+    pub sheets: Vec<SheetFile>,
+    /// Minutes between refreshes of the URLs formulas fetch from, for those on a schedule
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub refresh_intervals: BTreeMap<String, u32>,
+    /// Title, author, description and custom properties
+    #[serde(default, skip_serializing_if = "DocumentProperties::is_empty")]
+    pub properties: DocumentProperties,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SheetFile {
+    pub name: String,
+    /// Cells with content or a format, by position
+    pub cells: Vec<CellFile>,
+    pub column_widths: Vec<i32>,
+    pub row_heights: Vec<i32>,
+    pub frozen_panes: Option<Coords>,
+    pub row_outline: Outline,
+    pub column_outline: Outline,
+    pub conditional_formats: Vec<ConditionalFormat>,
+    pub tables: Vec<Table>,
+    pub auto_filter: Option<AutoFilter>,
+    pub charts: Vec<Chart>,
+    pub validations: Vec<Validation>,
+    pub comments: Vec<CommentThread>,
+    pub edit_history: Vec<CellHistory>,
+    pub print_settings: PrintSettings,
+    pub protected: bool,
+    pub protected_ranges: Vec<ProtectedRange>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellFile {
+    pub row: i32,
+    pub column: i32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "is_default_format")]
+    pub format: CellFormat,
+}
+
+fn is_default_format(format: &CellFormat) -> bool {
+    *format == CellFormat::default()
+}
+
+/// Where someone was in a workbook, to take them back there when it's reopened
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionFile {
+    pub active_sheet: usize,
+    /// Each sheet's selection, by index, None for sheets that were never selected in
+    pub selections: Vec<Option<Selection>>,
+    /// How far the grid was scrolled left and down, in pixels
+    pub scroll: (i32, i32),
+    /// How big the grid was drawn, in percent, 0 for sessions from before it could zoom
+    pub zoom: u16,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FileError {
+    /// Not JSON, or not shaped like a workbook
+    Invalid(String),
+    /// Written by a version that needs a newer one to read it
+    TooNew(u32),
+    NoSheets,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Invalid(error) => write!(f, "Not a workbook file: {}", error),
+            FileError::TooNew(version) => write!(
+                f,
+                "The file needs version {} of the format, this app reads up to {}",
+                version, FILE_VERSION
+            ),
+            FileError::NoSheets => write!(f, "The file has no sheets"),
+        }
+    }
+}
+
+/// A workbook file's text, checked to be one this version can read
+pub fn read_workbook(text: &str) -> Result<WorkbookFile, FileError> {
+    let file: WorkbookFile =
+        serde_json::from_str(text).map_err(|error| FileError::Invalid(error.to_string()))?;
+    if file.readable_from > FILE_VERSION {
+        return Err(FileError::TooNew(file.readable_from));
+    }
+    if file.sheets.is_empty() {
+        return Err(FileError::NoSheets);
+    }
+    Ok(file)
+}
+
+impl Grid {
+    /// The workbook as `.rsheet` text
+    pub fn save_workbook(&self) -> String {
+        serde_json::to_string(&self.workbook_file()).expect("workbooks serialize")
+    }
+
+    pub fn workbook_file(&self) -> WorkbookFile {
+        let sheets = self
+            .workbook
+            .sheets
+            .iter()
+            .map(|sheet| {
+                let mut cells = sheet
+                    .cells_map
+                    .iter()
+                    .filter(|(_, cell)| {
+                        !cell.content.is_empty() || !is_default_format(&cell.format)
+                    })
+                    .map(|(coords, cell)| CellFile {
+                        row: coords.row,
+                        column: coords.column,
+                        content: cell.content.clone(),
+                        format: cell.format.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                cells.sort_by_key(|cell| (cell.row, cell.column));
+                let mut edit_history = sheet
+                    .edit_history
+                    .iter()
+                    .map(|(coords, edits)| CellHistory {
+                        cell: *coords,
+                        edits: edits.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                edit_history.sort_by_key(|history| history.cell);
+                SheetFile {
+                    name: sheet.name.clone(),
+                    cells,
+                    column_widths: sheet.column_widths.clone(),
+                    row_heights: sheet.row_heights.clone(),
+                    frozen_panes: self.view.frozen_panes.get(&sheet.id).copied(),
+                    conditional_formats: sheet.conditional_formats.clone(),
+                    tables: sheet.tables.clone(),
+                    row_outline: sheet.row_outline.clone(),
+                    column_outline: sheet.column_outline.clone(),
+                    auto_filter: sheet.auto_filter.clone(),
+                    charts: sheet.charts.clone(),
+                    validations: sheet.validations.clone(),
+                    comments: sheet.comments.clone(),
+                    edit_history,
+                    print_settings: sheet.print_settings.clone(),
+                    protected: sheet.protected,
+                    protected_ranges: sheet.protected_ranges.clone(),
+                }
+            })
+            .collect();
+        let active_sheet = self
+            .workbook
+            .sheets
+            .iter()
+            .position(|sheet| sheet.id == self.view.active_sheet)
+            .unwrap_or_default();
+        WorkbookFile {
+            version: FILE_VERSION,
+            readable_from: 1,
+            active_sheet,
+            sheets,
+            refresh_intervals: self.workbook.refresh_intervals.clone(),
+            properties: self.workbook.properties.clone(),
+        }
+    }
+
+    /// The sheet shown, the selections and the zoom, the scroll being left for the page to
+    /// fill in
+    pub fn session_file(&self) -> SessionFile {
+        let sheets = &self.workbook.sheets;
+        SessionFile {
+            active_sheet: sheets
+                .iter()
+                .position(|sheet| sheet.id == self.view.active_sheet)
+                .unwrap_or_default(),
+            selections: sheets
+                .iter()
+                .map(|sheet| self.view.selections.get(&sheet.id).cloned())
+                .collect(),
+            scroll: (0, 0),
+            zoom: self.view.zoom,
+        }
+    }
+
+    /// Shows the sheet, selects the cells and zooms as a session had, skipping selections
+    /// that no longer fit their sheet
+    pub fn restore_session(&mut self, session: SessionFile) {
+        let ids = self
+            .workbook
+            .sheets
+            .iter()
+            .map(|sheet| sheet.id)
+            .collect::<Vec<_>>();
+        for (id, selection) in ids.iter().zip(session.selections) {
+            let Some(selection) = selection else {
+                continue;
+            };
+            let sheet = self.workbook.get_sheet(*id).expect("sheet exists");
+            let (row_count, column_count) = (
+                sheet.row_heights.len() as i32,
+                sheet.column_widths.len() as i32,
+            );
+            let fits = |coords: Coords| {
+                (0..row_count).contains(&coords.row) && (0..column_count).contains(&coords.column)
+            };
+            let is_valid = !selection.ranges.is_empty()
+                && selection
+                    .ranges
+                    .iter()
+                    .all(|range| fits(range.anchor) && fits(range.extent));
+            if is_valid {
+                self.view.selections.insert(*id, selection);
+            }
+        }
+        if let Some(id) = ids.get(session.active_sheet) {
+            self.view.active_sheet = *id;
+        }
+        if session.zoom != 0 {
+            self.view.set_zoom(session.zoom);
+        }
+    }
+
+    /// Replaces the workbook and everything about viewing it with an empty one's
+    pub fn new_workbook(&mut self) {
+        let read_only = self.view.read_only;
+        *self = self.empty_grid();
+        self.view.read_only = read_only;
+    }
+
+    /// Replaces the workbook and everything about viewing it with a file's
+    pub fn open_workbook(&mut self, file: WorkbookFile) {
+        let mut grid = self.empty_grid();
+        for _ in 1..file.sheets.len() {
+            grid.workbook.add_sheet();
+        }
+        let ids = grid
+            .workbook
+            .sheets
+            .iter()
+            .map(|sheet| sheet.id)
+            .collect::<Vec<_>>();
+        // Set rather than renamed, as the sheets are still empty and renaming refuses a name
+        // another new sheet has by default, like `Sheet1` for the second of `[Sheet2, Sheet1]`
+        for (sheet, sheet_file) in grid.workbook.sheets.iter_mut().zip(&file.sheets) {
+            sheet.name = sheet_file.name.clone();
+        }
+
+        grid.workbook.refresh_intervals = file.refresh_intervals;
+        // Before the cells, for the formulas showing them
+        grid.workbook.properties = file.properties;
+        for (id, sheet_file) in ids.iter().zip(file.sheets) {
+            grid.view.active_sheet = *id;
+            grid.load_sheet(sheet_file);
+        }
+        grid.view.active_sheet = ids.get(file.active_sheet).copied().unwrap_or(ids[0]);
+        grid.view.has_unsaved_changes = false;
+        // Only once loaded, as loading sets the cells
+        grid.view.read_only = self.view.read_only;
+        *self = grid;
+    }
+
+    /// Adds each sheet as a new one, named as it was if the name's free, and shows the first
+    pub fn add_sheets(&mut self, sheets: Vec<SheetFile>) {
+        let mut first = None;
+        for sheet_file in sheets {
+            self.apply(GridCommand::AddSheet);
+            let sheet = self.view.active_sheet;
+            first.get_or_insert(sheet);
+            self.apply(GridCommand::RenameSheet {
+                sheet,
+                name: sheet_file.name.clone(),
+            });
+            self.load_sheet(sheet_file);
+        }
+        if let Some(sheet) = first {
+            self.apply(GridCommand::ActivateSheet(sheet));
+        }
+    }
+
+    /// Fills the active sheet, still empty, with a saved one's cells, sizes, rules and charts.
+    /// Cells and charts beyond the sheet's size are left out.
+    fn load_sheet(&mut self, sheet_file: SheetFile) {
+        if let Some(frozen) = sheet_file.frozen_panes {
+            self.view
+                .frozen_panes
+                .insert(self.view.active_sheet, frozen);
+        }
+        let (row_count, column_count) = (
+            self.sheet().row_heights.len() as i32,
+            self.sheet().column_widths.len() as i32,
+        );
+        let sheet = self.sheet_mut();
+        for (width, saved) in sheet.column_widths.iter_mut().zip(sheet_file.column_widths) {
+            *width = saved;
+        }
+        for (height, saved) in sheet.row_heights.iter_mut().zip(sheet_file.row_heights) {
+            *height = saved;
+        }
+        sheet.row_outline = sheet_file.row_outline;
+        sheet.column_outline = sheet_file.column_outline;
+        sheet.conditional_formats = sheet_file.conditional_formats;
+        sheet.tables = sheet_file.tables;
+        sheet.auto_filter = sheet_file.auto_filter;
+        sheet.charts = sheet_file
+            .charts
+            .into_iter()
+            .filter(|chart| chart.anchor.row < row_count && chart.anchor.column < column_count)
+            .collect();
+        sheet.validations = sheet_file.validations;
+        sheet.protected_ranges = sheet_file.protected_ranges;
+        sheet.comments = sheet_file
+            .comments
+            .into_iter()
+            .filter(|thread| thread.cell.row < row_count && thread.cell.column < column_count)
+            .collect();
+        let edit_history = sheet_file
+            .edit_history
+            .into_iter()
+            .filter(|history| history.cell.row < row_count && history.cell.column < column_count)
+            .map(|history| (history.cell, history.edits))
+            .collect();
+        sheet.print_settings = sheet_file.print_settings;
+
+        let mut contents = Vec::new();
+        for cell in sheet_file.cells {
+            let coords = Coords {
+                row: cell.row,
+                column: cell.column,
+            };
+            if coords.row >= row_count || coords.column >= column_count {
+                continue;
+            }
+            sheet
+                .cells_map
+                .entry(coords)
+                .or_insert_with(Cell::new)
+                .format = cell.format;
+            if !cell.content.is_empty() {
+                contents.push((coords, cell.content));
+            }
+        }
+        self.apply(GridCommand::SetCellContents(contents));
+        // Loading isn't an edit, the history is the saved one
+        self.sheet_mut().edit_history = edit_history;
+        // Protected once its cells are in
+        self.sheet_mut().protected = sheet_file.protected;
+    }
+
+    /// A grid of one empty sheet, of the same size as this one
+    fn empty_grid(&self) -> Grid {
+        Grid::new(
+            self.view.base_header_column_width,
+            self.view.base_header_row_height,
+            self.sheet().column_widths.len(),
+            self.sheet().row_heights.len(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{
+        chart::ChartKind,
+        conditional::Rule,
+        file::*,
+        filter::{ColumnFilter, Condition},
+        format::NumberFormat,
+        grid::cell_address_to_coords,
+        outline::OutlineDirection,
+        table::TableStyle,
+        validation::{AlertStyle, ValidationRule},
+        view::CellRange,
+    };
+
+    #[test]
+    fn saved_workbooks_open_the_same() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let coords = |address: &str| cell_address_to_coords(address).unwrap();
+        grid.apply(GridCommand::SetCellContent {
+            coords: coords("A1"),
+            content: "=Prices!B2*2".to_string(),
+        });
+        grid.apply(GridCommand::SelectCell(coords("A1")));
+        grid.apply(GridCommand::SetNumberFormat(NumberFormat::Currency(2)));
+        grid.apply(GridCommand::ResizeColumn {
+            column: 0,
+            width: 140,
+        });
+        grid.apply(GridCommand::SelectCell(coords("C5")));
+        grid.apply(GridCommand::ExtendSelection(coords("D7")));
+        for direction in [OutlineDirection::Rows, OutlineDirection::Columns] {
+            grid.apply(GridCommand::Group(direction));
+        }
+        grid.apply(GridCommand::ToggleOutlineGroup {
+            direction: OutlineDirection::Rows,
+            level: 1,
+            start: 4,
+        });
+        grid.apply(GridCommand::AddSheet);
+        let sheet = grid.view.active_sheet;
+        grid.apply(GridCommand::RenameSheet {
+            sheet,
+            name: "Prices".to_string(),
+        });
+        grid.apply(GridCommand::SetCellContent {
+            coords: coords("B2"),
+            content: "21".to_string(),
+        });
+        grid.view.frozen_panes.insert(sheet, coords("A2"));
+        grid.sheet_mut().validations.push(Validation {
+            range: CellRange::cell(coords("B2")),
+            rule: ValidationRule::Number {
+                min: Some(0.0),
+                max: None,
+            },
+            alert: AlertStyle::Stop,
+            error_message: None,
+            prompt: None,
+        });
+        for (address, content) in [
+            ("A1", "Item"),
+            ("B1", "Price"),
+            ("A2", "Tea"),
+            ("A3", "Jam"),
+        ] {
+            grid.apply(GridCommand::SetCellContent {
+                coords: coords(address),
+                content: content.to_string(),
+            });
+        }
+        let range = CellRange {
+            anchor: coords("A1"),
+            extent: coords("B3"),
+        };
+        grid.sheet_mut()
+            .conditional_formats
+            .push(ConditionalFormat {
+                range,
+                rule: Rule::DataBar {
+                    color: "#638ec6".to_string(),
+                    show_value: true,
+                },
+            });
+        grid.sheet_mut().tables.push(Table {
+            range,
+            style: TableStyle::Green,
+            totals_row: false,
+        });
+        grid.sheet_mut()
+            .charts
+            .push(Chart::new(1, ChartKind::Bar, range));
+        grid.apply(GridCommand::SelectCell(coords("A1")));
+        grid.apply(GridCommand::ToggleAutoFilter);
+        grid.apply(GridCommand::SetColumnFilter {
+            column: 1,
+            filter: Some(ColumnFilter::Condition(Condition::LessThan(20.0))),
+        });
+        assert!(grid.sheet().is_row_hidden(1));
+
+        let file = read_workbook(&grid.save_workbook()).unwrap();
+        assert_eq!(file, grid.workbook_file());
+        let mut opened = Grid::new(90, 25, 26, 100);
+        opened.apply(GridCommand::OpenWorkbook(file));
+        assert_eq!(opened.workbook_file(), grid.workbook_file());
+        assert_eq!(opened.sheet().name, "Prices");
+        assert!(opened.sheet().is_row_hidden(1));
+        assert_eq!(opened.sheet().tables.len(), 1);
+        assert_eq!(opened.sheet().charts[0].kind, ChartKind::Bar);
+        let first = opened.workbook.sheets[0].id;
+        opened.apply(GridCommand::ActivateSheet(first));
+        let a1 = &opened.sheet().cells_map[&coords("A1")];
+        assert_eq!(a1.display_value, "42");
+        assert_eq!(a1.format.number_format.apply(&a1.display_value), "$42.00");
+        assert_eq!(opened.sheet().column_widths[0], 140);
+        assert!((4..=6).all(|row| opened.sheet().is_row_hidden(row)));
+        assert_eq!(opened.sheet().column_outline.level(2), 1);
+        assert!(!opened.sheet().is_column_hidden(2));
+    }
+
+    #[test]
+    fn sheets_named_like_new_ones_open_in_their_order() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::AddSheet);
+        let [first, second] = [0, 1].map(|index| grid.workbook.sheets[index].id);
+        grid.apply(GridCommand::RenameSheet {
+            sheet: first,
+            name: "Temp".to_string(),
+        });
+        grid.apply(GridCommand::RenameSheet {
+            sheet: second,
+            name: "Sheet1".to_string(),
+        });
+        grid.apply(GridCommand::RenameSheet {
+            sheet: first,
+            name: "Sheet2".to_string(),
+        });
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "5".to_string(),
+        });
+        grid.apply(GridCommand::ActivateSheet(first));
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "=Sheet1!A1*2".to_string(),
+        });
+
+        let mut opened = Grid::new(90, 25, 26, 100);
+        opened.apply(GridCommand::OpenWorkbook(grid.workbook_file()));
+        let names = opened
+            .workbook
+            .sheets
+            .iter()
+            .map(|sheet| sheet.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Sheet2", "Sheet1"]);
+        let first = opened.workbook.sheets[0].id;
+        opened.apply(GridCommand::ActivateSheet(first));
+        assert_eq!(opened.sheet().cells_map[&a1].display_value, "10");
+        assert_eq!(opened.workbook_file(), grid.workbook_file());
+    }
+
+    #[test]
+    fn sessions_restore_the_sheet_and_selections_that_fit() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let coords = |address: &str| cell_address_to_coords(address).unwrap();
+        grid.apply(GridCommand::SelectCell(coords("B2")));
+        grid.apply(GridCommand::ExtendSelection(coords("C4")));
+        grid.apply(GridCommand::AddSheet);
+        grid.apply(GridCommand::SetZoom(125));
+        let mut session = grid.session_file();
+        assert_eq!(session.active_sheet, 1);
+        session.selections[1] = Some(Selection::cell(coords("A500")));
+
+        let mut reopened = Grid::new(90, 25, 26, 100);
+        reopened.apply(GridCommand::AddSheet);
+        reopened.apply(GridCommand::RestoreSession(session));
+        assert_eq!(reopened.current_cell(), coords("A1"));
+        assert_eq!(reopened.view.zoom, 125);
+        let first = reopened.workbook.sheets[0].id;
+        reopened.apply(GridCommand::ActivateSheet(first));
+        assert_eq!(
+            reopened.view.selection().active_range(),
+            CellRange {
+                anchor: coords("B2"),
+                extent: coords("C4"),
+            }
+        );
+
+        // Sessions kept before zooming keep the grid as it's drawn
+        let older: SessionFile = serde_json::from_str(r#"{"active_sheet":0}"#).unwrap();
+        reopened.apply(GridCommand::RestoreSession(older));
+        assert_eq!(reopened.view.zoom, 125);
+        reopened.apply(GridCommand::SetZoom(1000));
+        assert_eq!(reopened.view.zoom, 200);
+    }
+
+    #[test]
+    fn changes_stay_unsaved_until_saved_or_replaced() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SelectCell(a1));
+        assert!(!grid.view.has_unsaved_changes);
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "1".to_string(),
+        });
+        assert!(grid.view.has_unsaved_changes);
+        grid.apply(GridCommand::MarkSaved);
+        assert!(!grid.view.has_unsaved_changes);
+
+        grid.apply(GridCommand::AddSheet);
+        grid.apply(GridCommand::NewWorkbook);
+        assert!(!grid.view.has_unsaved_changes);
+        assert_eq!(grid.workbook.sheets.len(), 1);
+        assert!(grid.sheet().cells_map.is_empty());
+    }
+
+    #[test]
+    fn files_from_newer_versions_read_unless_they_need_one() {
+        let newer = r#"{"version":3,"readable_from":1,"sheets":[{"name":"S","sparklines":[]}]}"#;
+        assert_eq!(read_workbook(newer).unwrap().sheets[0].name, "S");
+        let incompatible = r#"{"version":3,"readable_from":2,"sheets":[{"name":"S"}]}"#;
+        assert_eq!(read_workbook(incompatible), Err(FileError::TooNew(2)));
+        assert!(matches!(read_workbook("a,b"), Err(FileError::Invalid(_))));
+    }
+}
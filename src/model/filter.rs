@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::sort,
+    model::{grid::Coords, view::CellRange, workbook::Sheet},
+};
+
+/// Filtering on a block of data: dropdowns on its header row pick which of the rows below
+/// show. Rows are hidden when a filter is set, not as values change afterwards.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AutoFilter {
+    /// From the header's first cell to the last row's last cell
+    pub range: CellRange,
+    /// The filter on each column that has one
+    pub filters: BTreeMap<i32, ColumnFilter>,
+    /// Rows the filters hid when last applied
+    pub hidden_rows: BTreeSet<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColumnFilter {
+    /// Only rows whose value is one of these show, blank being ""
+    Values(BTreeSet<String>),
+    Condition(Condition),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    GreaterThan(f64),
+    LessThan(f64),
+    /// Ignoring case
+    Contains(String),
+}
+
+impl ColumnFilter {
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            ColumnFilter::Values(values) => values.contains(value),
+            ColumnFilter::Condition(Condition::GreaterThan(bound)) => {
+                value.parse::<f64>().is_ok_and(|number| number > *bound)
+            }
+            ColumnFilter::Condition(Condition::LessThan(bound)) => {
+                value.parse::<f64>().is_ok_and(|number| number < *bound)
+            }
+            ColumnFilter::Condition(Condition::Contains(text)) => {
+                value.to_lowercase().contains(&text.to_lowercase())
+            }
+        }
+    }
+}
+
+impl AutoFilter {
+    pub fn new(range: CellRange) -> Self {
+        AutoFilter {
+            range,
+            filters: BTreeMap::new(),
+            hidden_rows: BTreeSet::new(),
+        }
+    }
+
+    pub fn header_row(&self) -> i32 {
+        self.range.top_left().row
+    }
+
+    pub fn data_rows(&self) -> std::ops::RangeInclusive<i32> {
+        self.header_row() + 1..=self.range.bottom_right().row
+    }
+
+    pub fn columns(&self) -> std::ops::RangeInclusive<i32> {
+        self.range.top_left().column..=self.range.bottom_right().column
+    }
+}
+
+impl Sheet {
+    /// Whether a filter or a collapsed group hides a row
+    pub fn is_row_hidden(&self, row: i32) -> bool {
+        self.is_row_filtered(row) || self.row_outline.is_hidden(row)
+    }
+    pub fn is_row_filtered(&self, row: i32) -> bool {
+        self.auto_filter
+            .as_ref()
+            .is_some_and(|filter| filter.hidden_rows.contains(&row))
+    }
+
+    /// The height a row takes up on screen, none if it's hidden
+    pub fn displayed_row_height(&self, row: usize) -> i32 {
+        match self.is_row_hidden(row as i32) {
+            true => 0,
+            false => self.row_heights.get(row).copied().unwrap_or_default(),
+        }
+    }
+
+    /// The different values in a filtered column's data rows, sorted, for its checklist
+    pub fn filter_values(&self, column: i32) -> Vec<String> {
+        let Some(filter) = &self.auto_filter else {
+            return Vec::new();
+        };
+        let values = filter
+            .data_rows()
+            .map(|row| self.display_value_at(Coords { row, column }))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        sort::sorted_order(&values, false)
+            .into_iter()
+            .map(|index| values[index].clone())
+            .collect()
+    }
+
+    /// Hides the data rows some column's filter doesn't match
+    pub fn apply_auto_filter(&mut self) {
+        let Some(filter) = &self.auto_filter else {
+            return;
+        };
+        let hidden_rows = filter
+            .data_rows()
+            .filter(|row| {
+                filter.filters.iter().any(|(column, column_filter)| {
+                    let value = self.display_value_at(Coords {
+                        row: *row,
+                        column: *column,
+                    });
+                    !column_filter.matches(&value)
+                })
+            })
+            .collect();
+        if let Some(filter) = &mut self.auto_filter {
+            filter.hidden_rows = hidden_rows;
+        }
+    }
+
+    fn display_value_at(&self, coords: Coords) -> String {
+        self.cells_map
+            .get(&coords)
+            .map(|cell| cell.display_value.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::filter::*;
+
+    #[test]
+    fn column_filters_match() {
+        let values = ColumnFilter::Values(["Apple".to_string(), String::new()].into());
+        assert!(values.matches("Apple"));
+        assert!(values.matches(""));
+        assert!(!values.matches("apple"));
+
+        let greater = ColumnFilter::Condition(Condition::GreaterThan(10.0));
+        assert!(greater.matches("10.5"));
+        assert!(!greater.matches("10"));
+        assert!(!greater.matches("eleven"));
+
+        let contains = ColumnFilter::Condition(Condition::Contains("PP".to_string()));
+        assert!(contains.matches("Apple"));
+        assert!(!contains.matches("Pear"));
+    }
+}
@@ -0,0 +1,444 @@
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{
+    date::{serial_of, DateFormat},
+    number_format::FormatCode,
+};
+
+/// How a cell's value is drawn. Part of the document, kept with the cell when it moves.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CellFormat {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// None for the grid's font
+    pub font_family: Option<String>,
+    /// In pixels, None for the grid's size
+    pub font_size: Option<u32>,
+    /// `#rrggbb`, None for the theme's color
+    pub text_color: Option<String>,
+    pub background_color: Option<String>,
+    pub number_format: NumberFormat,
+    /// None to go by the value, numbers right and text left
+    pub horizontal_align: Option<HorizontalAlign>,
+    pub vertical_align: VerticalAlign,
+    /// Degrees counterclockwise, -90 to 90
+    pub rotation: i16,
+    /// Letters stacked top to bottom, instead of any rotation
+    pub vertical_text: bool,
+    /// Can be edited while the sheet is protected
+    pub unlocked: bool,
+}
+
+/// How numbers are shown. Only the drawing changes, the value keeps its full precision.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum NumberFormat {
+    /// As calculated
+    #[default]
+    General,
+    /// Thousands separated, with a number of decimal places
+    Number(u8),
+    Currency(u8),
+    /// Multiplied by 100 with a percent sign
+    Percent(u8),
+    /// Numbers as date serials, ISO date text as the date it names
+    Date(DateFormat),
+    /// An Excel-style format code like `#,##0.00;[Red](#,##0.00)`
+    Custom(String),
+}
+
+pub const MAX_DECIMALS: u8 = 10;
+
+/// The grid's font, as in main.css
+pub const DEFAULT_FONT_FAMILY: &str = "\"Noto Sans\", Cantarell, sans-serif";
+pub const DEFAULT_FONT_SIZE: u32 = 11;
+
+pub const FONT_FAMILIES: [&str; 7] = [
+    "Arial",
+    "Calibri",
+    "Courier New",
+    "Georgia",
+    "Times New Roman",
+    "Trebuchet MS",
+    "Verdana",
+];
+pub const FONT_SIZES: [u32; 12] = [8, 9, 10, 11, 12, 14, 16, 18, 20, 24, 28, 36];
+
+/// Colors offered before resorting to typing a hex code
+pub const PALETTE: [&str; 16] = [
+    "#000000", "#595959", "#a6a6a6", "#ffffff", "#c00000", "#ff0000", "#ffc000", "#ffff00",
+    "#92d050", "#00b050", "#00b0f0", "#0070c0", "#002060", "#7030a0", "#f4b6c2", "#fce4d6",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontStyle {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HorizontalAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum VerticalAlign {
+    Top,
+    #[default]
+    Middle,
+    Bottom,
+}
+
+impl NumberFormat {
+    pub fn decimals(&self) -> Option<u8> {
+        match *self {
+            NumberFormat::General | NumberFormat::Date(_) | NumberFormat::Custom(_) => None,
+            NumberFormat::Number(decimals)
+            | NumberFormat::Currency(decimals)
+            | NumberFormat::Percent(decimals) => Some(decimals),
+        }
+    }
+
+    /// The same format with other decimal places, General becoming Number. Dates have none
+    /// and custom codes spell out their own, so both stay as they are.
+    pub fn with_decimals(self, decimals: u8) -> Self {
+        let decimals = decimals.min(MAX_DECIMALS);
+        match self {
+            NumberFormat::Date(format) => NumberFormat::Date(format),
+            NumberFormat::Custom(code) => NumberFormat::Custom(code),
+            NumberFormat::General | NumberFormat::Number(_) => NumberFormat::Number(decimals),
+            NumberFormat::Currency(_) => NumberFormat::Currency(decimals),
+            NumberFormat::Percent(_) => NumberFormat::Percent(decimals),
+        }
+    }
+
+    /// How a value shows, text and errors passing through as they are unless a custom code
+    /// has a section for text
+    pub fn apply(&self, value: &str) -> String {
+        let number = value.parse::<f64>();
+        match (self, number) {
+            // Codes that don't parse are refused when set, this only covers older documents
+            (NumberFormat::Custom(code), _) => FormatCode::parse(code)
+                .map(|code| code.format(value).text)
+                .unwrap_or_else(|| value.to_string()),
+            (NumberFormat::Date(format), _) => {
+                format.apply(value).unwrap_or_else(|| value.to_string())
+            }
+            (_, Err(_)) | (NumberFormat::General, _) => value.to_string(),
+            (NumberFormat::Number(decimals), Ok(number)) => group_thousands(number, *decimals),
+            (NumberFormat::Currency(decimals), Ok(number)) => {
+                let sign = if number < 0.0 { "-" } else { "" };
+                format!("{}${}", sign, group_thousands(number.abs(), *decimals))
+            }
+            (NumberFormat::Percent(decimals), Ok(number)) => {
+                format!("{:.*}%", *decimals as usize, number * 100.0)
+            }
+        }
+    }
+
+    /// The color a custom code's section gives the value, like `[Red]` for negatives
+    pub fn color(&self, value: &str) -> Option<&'static str> {
+        match self {
+            NumberFormat::Custom(code) => FormatCode::parse(code)?.format(value).color,
+            _ => None,
+        }
+    }
+}
+
+/// Decimal places a value is shown with, as a starting point for changing them
+pub fn decimals_of(value: &str) -> u8 {
+    match value.parse::<f64>() {
+        Ok(_) => value.split_once('.').map_or(0, |(_, fraction)| {
+            fraction.len().min(MAX_DECIMALS as usize) as u8
+        }),
+        Err(_) => 0,
+    }
+}
+
+fn group_thousands(number: f64, decimals: u8) -> String {
+    let text = format!("{:.*}", decimals as usize, number.abs());
+    let (integer, fraction) = match text.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (text.as_str(), None),
+    };
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+    // Rounding can leave nothing negative to show, like -0.001 to 0.00
+    let is_zero = text.chars().all(|c| c == '0' || c == '.');
+    match number < 0.0 && !is_zero {
+        true => format!("-{}", grouped),
+        false => grouped,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorTarget {
+    Text,
+    Background,
+}
+
+impl CellFormat {
+    pub fn has(&self, style: FontStyle) -> bool {
+        match style {
+            FontStyle::Bold => self.bold,
+            FontStyle::Italic => self.italic,
+            FontStyle::Underline => self.underline,
+            FontStyle::Strikethrough => self.strikethrough,
+        }
+    }
+
+    pub fn set(&mut self, style: FontStyle, on: bool) {
+        match style {
+            FontStyle::Bold => self.bold = on,
+            FontStyle::Italic => self.italic = on,
+            FontStyle::Underline => self.underline = on,
+            FontStyle::Strikethrough => self.strikethrough = on,
+        }
+    }
+
+    pub fn color(&self, target: ColorTarget) -> Option<&str> {
+        match target {
+            ColorTarget::Text => self.text_color.as_deref(),
+            ColorTarget::Background => self.background_color.as_deref(),
+        }
+    }
+
+    pub fn set_color(&mut self, target: ColorTarget, color: Option<String>) {
+        match target {
+            ColorTarget::Text => self.text_color = color,
+            ColorTarget::Background => self.background_color = color,
+        }
+    }
+
+    /// Where the value lines up across its cell, going by the value unless set
+    pub fn horizontal_align_of(&self, value: &str) -> HorizontalAlign {
+        let is_number = match self.number_format {
+            NumberFormat::Date(_) => serial_of(value).is_some(),
+            _ => value.parse::<f64>().is_ok_and(f64::is_finite),
+        };
+        match self.horizontal_align {
+            Some(align) => align,
+            // Turned text reads best centered under or over its column
+            None if self.rotation != 0 || self.vertical_text => HorizontalAlign::Center,
+            None if is_number => HorizontalAlign::Right,
+            None => HorizontalAlign::Left,
+        }
+    }
+
+    /// Inline CSS placing the value in its flexbox cell
+    pub fn alignment_style(&self, value: &str) -> String {
+        let justify = match self.horizontal_align_of(value) {
+            HorizontalAlign::Left => "flex-start",
+            HorizontalAlign::Center => "center",
+            HorizontalAlign::Right => "flex-end",
+        };
+        let align = match self.vertical_align {
+            VerticalAlign::Top => "flex-start",
+            VerticalAlign::Middle => "center",
+            VerticalAlign::Bottom => "flex-end",
+        };
+        format!("justify-content: {}; align-items: {};", justify, align)
+    }
+
+    /// Inline CSS drawing the format and placing the value in a `<td>`, for HTML outside
+    /// the grid
+    pub fn table_cell_style(&self, value: &str) -> String {
+        let text_align = match self.horizontal_align_of(value) {
+            HorizontalAlign::Left => "left",
+            HorizontalAlign::Center => "center",
+            HorizontalAlign::Right => "right",
+        };
+        let vertical_align = match self.vertical_align {
+            VerticalAlign::Top => "top",
+            VerticalAlign::Middle => "middle",
+            VerticalAlign::Bottom => "bottom",
+        };
+        format!(
+            "{}text-align: {}; vertical-align: {};",
+            self.to_style(),
+            text_align,
+            vertical_align
+        )
+    }
+
+    /// The CSS `font` the value is drawn in, for measuring it
+    pub fn font(&self) -> String {
+        let family = match &self.font_family {
+            Some(family) => format!("\"{}\"", family),
+            None => DEFAULT_FONT_FAMILY.to_string(),
+        };
+        format!(
+            "{}{}{}px {}",
+            if self.italic { "italic " } else { "" },
+            if self.bold { "bold " } else { "" },
+            self.font_size.unwrap_or(DEFAULT_FONT_SIZE),
+            family
+        )
+    }
+
+    /// Inline CSS turning the text element inside the cell
+    pub fn text_style(&self) -> String {
+        if self.vertical_text {
+            "flex-shrink: 0; writing-mode: vertical-rl; text-orientation: upright;".to_string()
+        } else if self.rotation != 0 {
+            format!("flex-shrink: 0; transform: rotate({}deg);", -self.rotation)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Inline CSS declarations drawing the format
+    pub fn to_style(&self) -> String {
+        let mut style = String::new();
+        if let Some(family) = &self.font_family {
+            style.push_str(&format!("font-family: \"{}\";", family));
+        }
+        if let Some(size) = self.font_size {
+            style.push_str(&format!("font-size: {}px;", size));
+        }
+        if self.bold {
+            style.push_str("font-weight: bold;");
+        }
+        if self.italic {
+            style.push_str("font-style: italic;");
+        }
+        let decorations = [
+            (self.underline, "underline"),
+            (self.strikethrough, "line-through"),
+        ]
+        .into_iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, decoration)| decoration)
+        .collect::<Vec<_>>();
+        if !decorations.is_empty() {
+            style.push_str(&format!("text-decoration: {};", decorations.join(" ")));
+        }
+        if let Some(color) = &self.text_color {
+            style.push_str(&format!("color: {};", color));
+        }
+        if let Some(color) = &self.background_color {
+            style.push_str(&format!("background-color: {};", color));
+        }
+        style
+    }
+}
+
+/// `#rrggbb` from a hex color typed as `#rgb`, `rgb`, `#rrggbb` or `rrggbb`
+pub fn normalize_hex_color(text: &str) -> Option<String> {
+    let hex = text.trim().trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+    Some(format!("#{}", hex.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::format::*;
+
+    #[test]
+    fn style_combines_decorations() {
+        let mut format = CellFormat::default();
+        assert_eq!(format.to_style(), "");
+        format.set(FontStyle::Bold, true);
+        format.set(FontStyle::Underline, true);
+        format.set(FontStyle::Strikethrough, true);
+        assert_eq!(
+            format.to_style(),
+            "font-weight: bold;text-decoration: underline line-through;"
+        );
+
+        format.font_family = Some("Times New Roman".to_string());
+        format.font_size = Some(14);
+        assert_eq!(format.font(), "bold 14px \"Times New Roman\"");
+    }
+
+    #[test]
+    fn alignment_goes_by_value_unless_set() {
+        let mut format = CellFormat::default();
+        assert_eq!(
+            format.alignment_style("12"),
+            "justify-content: flex-end; align-items: center;"
+        );
+        assert_eq!(
+            format.alignment_style("total"),
+            "justify-content: flex-start; align-items: center;"
+        );
+        format.horizontal_align = Some(HorizontalAlign::Center);
+        format.vertical_align = VerticalAlign::Top;
+        assert_eq!(
+            format.alignment_style("12"),
+            "justify-content: center; align-items: flex-start;"
+        );
+    }
+
+    #[test]
+    fn turned_text_is_centered() {
+        let mut format = CellFormat {
+            rotation: 45,
+            ..Default::default()
+        };
+        assert_eq!(
+            format.text_style(),
+            "flex-shrink: 0; transform: rotate(-45deg);"
+        );
+        assert!(format
+            .alignment_style("Header")
+            .starts_with("justify-content: center;"));
+        format.vertical_text = true;
+        assert!(format.text_style().contains("writing-mode: vertical-rl;"));
+    }
+
+    #[test]
+    fn number_formats() {
+        assert_eq!(NumberFormat::General.apply("1234.5"), "1234.5");
+        assert_eq!(NumberFormat::Number(2).apply("1234567.891"), "1,234,567.89");
+        assert_eq!(NumberFormat::Number(0).apply("-999.6"), "-1,000");
+        assert_eq!(NumberFormat::Number(2).apply("-0.001"), "0.00");
+        assert_eq!(NumberFormat::Currency(2).apply("-1234.5"), "-$1,234.50");
+        assert_eq!(NumberFormat::Percent(1).apply("0.1234"), "12.3%");
+        assert_eq!(NumberFormat::Percent(1).apply("#DIV/0!"), "#DIV/0!");
+        assert_eq!(decimals_of("3.125"), 3);
+        assert_eq!(
+            NumberFormat::General.with_decimals(4),
+            NumberFormat::Number(4)
+        );
+
+        let date = NumberFormat::Date(DateFormat::Iso);
+        assert_eq!(date.apply("45356"), "2024-03-05");
+        assert_eq!(date.apply("#REF!"), "#REF!");
+
+        let custom = NumberFormat::Custom("0.0;[Red]-0.0".to_string());
+        assert_eq!(custom.apply("-2.26"), "-2.3");
+        assert_eq!(custom.color("-2.26"), Some("#ff0000"));
+        assert_eq!(custom.color("2.25"), None);
+    }
+
+    #[test]
+    fn hex_colors_normalize() {
+        assert_eq!(normalize_hex_color(" #F0a ").as_deref(), Some("#ff00aa"));
+        assert_eq!(normalize_hex_color("00B050").as_deref(), Some("#00b050"));
+        assert_eq!(normalize_hex_color("#12345"), None);
+        assert_eq!(normalize_hex_color("red"), None);
+    }
+}
@@ -0,0 +1,468 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::{
+    engine::{csv, date::DateFormat, number_format::FormatCode},
+    model::{
+        file::{CellFile, SheetFile},
+        format::{CellFormat, HorizontalAlign, NumberFormat, VerticalAlign},
+    },
+};
+
+const SHEETS_URL: &str = "https://docs.google.com/spreadsheets";
+const API_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+/// The parts of each cell the Sheets API is asked for, to keep responses small
+const API_FIELDS: &str = "sheets(properties(sheetId,title),data(startRow,startColumn,rowData(values(userEnteredValue,userEnteredFormat(numberFormat,backgroundColor,horizontalAlignment,verticalAlignment,textFormat)))))";
+
+/// The name given to a sheet downloaded as CSV, which doesn't carry its title
+pub const CSV_SHEET_NAME: &str = "Google Sheet";
+
+#[derive(Debug, PartialEq)]
+pub enum GoogleSheetsError {
+    /// Not a link to a spreadsheet
+    InvalidLink,
+    /// A link to a sheet published to the web, which the Sheets API can't read
+    PublishedLink,
+    /// What the Sheets API said went wrong, like a key that isn't valid
+    Api(String),
+    /// A response that isn't shaped like a spreadsheet
+    Invalid(String),
+    NoSheets,
+}
+
+impl fmt::Display for GoogleSheetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoogleSheetsError::InvalidLink => write!(f, "Not a link to a Google Sheets spreadsheet"),
+            GoogleSheetsError::PublishedLink => write!(
+                f,
+                "Links to published sheets can't be read with an API key, use the spreadsheet's own link"
+            ),
+            GoogleSheetsError::Api(message) => write!(f, "Google Sheets: {}", message),
+            GoogleSheetsError::Invalid(error) => write!(f, "Not a spreadsheet: {}", error),
+            GoogleSheetsError::NoSheets => write!(f, "The spreadsheet has no sheets"),
+        }
+    }
+}
+
+/// A spreadsheet's id in a Google Sheets link, and whether it's the id of its published copy
+fn spreadsheet_id(link: &str) -> Option<(&str, bool)> {
+    let path = &link[link.find("/spreadsheets/d/")? + "/spreadsheets/d/".len()..];
+    let (path, published) = match path.strip_prefix("e/") {
+        Some(path) => (path, true),
+        None => (path, false),
+    };
+    let id = path.split(['/', '?', '#']).next().unwrap_or_default();
+    let is_valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    is_valid.then_some((id, published))
+}
+
+/// The `gid` of the sheet a link points to, in its query or fragment
+pub fn sheet_gid(link: &str) -> Option<i64> {
+    let start = link.find("gid=")? + "gid=".len();
+    let digits = link[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+/// Where to download a sheet as CSV, for a sheet published to the web or a spreadsheet
+/// shared with anyone who has the link. The sheet in the link's `gid` is downloaded, the first
+/// one without it.
+pub fn csv_url(link: &str) -> Result<String, GoogleSheetsError> {
+    let (id, published) = spreadsheet_id(link.trim()).ok_or(GoogleSheetsError::InvalidLink)?;
+    let gid = sheet_gid(link)
+        .map(|gid| format!("&gid={}", gid))
+        .unwrap_or_default();
+    Ok(match published {
+        true => format!("{}/d/e/{}/pub?output=csv{}", SHEETS_URL, id, gid),
+        false => format!("{}/d/{}/export?format=csv{}", SHEETS_URL, id, gid),
+    })
+}
+
+/// Where the Sheets API gives a spreadsheet's values and formats
+pub fn api_url(link: &str, key: &str) -> Result<String, GoogleSheetsError> {
+    match spreadsheet_id(link.trim()) {
+        Some((id, false)) => Ok(format!(
+            "{}/{}?includeGridData=true&fields={}&key={}",
+            API_URL,
+            id,
+            API_FIELDS,
+            key.trim()
+        )),
+        Some((_, true)) => Err(GoogleSheetsError::PublishedLink),
+        None => Err(GoogleSheetsError::InvalidLink),
+    }
+}
+
+/// A sheet of the values in a CSV download
+pub fn read_csv(text: &str) -> SheetFile {
+    let cells = csv::parse_csv(text, ',')
+        .into_iter()
+        .enumerate()
+        .flat_map(|(row, fields)| {
+            fields
+                .into_iter()
+                .enumerate()
+                .filter(|(_, content)| !content.is_empty())
+                .map(move |(column, content)| CellFile {
+                    row: row as i32,
+                    column: column as i32,
+                    content,
+                    format: CellFormat::default(),
+                })
+        })
+        .collect();
+    SheetFile {
+        name: CSV_SHEET_NAME.to_string(),
+        cells,
+        ..SheetFile::default()
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    error: ApiErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ApiErrorDetail {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Spreadsheet {
+    #[serde(default)]
+    sheets: Vec<Sheet>,
+}
+
+#[derive(Deserialize)]
+struct Sheet {
+    properties: SheetProperties,
+    #[serde(default)]
+    data: Vec<GridData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SheetProperties {
+    #[serde(default)]
+    sheet_id: i64,
+    #[serde(default)]
+    title: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GridData {
+    #[serde(default)]
+    start_row: i32,
+    #[serde(default)]
+    start_column: i32,
+    #[serde(default)]
+    row_data: Vec<RowData>,
+}
+
+#[derive(Deserialize)]
+struct RowData {
+    #[serde(default)]
+    values: Vec<CellData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CellData {
+    user_entered_value: Option<ExtendedValue>,
+    user_entered_format: Option<ApiFormat>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtendedValue {
+    number_value: Option<f64>,
+    string_value: Option<String>,
+    bool_value: Option<bool>,
+    formula_value: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiFormat {
+    number_format: Option<ApiNumberFormat>,
+    background_color: Option<Color>,
+    horizontal_alignment: Option<String>,
+    vertical_alignment: Option<String>,
+    text_format: Option<TextFormat>,
+}
+
+#[derive(Deserialize)]
+struct ApiNumberFormat {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    pattern: String,
+}
+
+/// Channels from 0 to 1, left out when 0
+#[derive(Deserialize)]
+struct Color {
+    #[serde(default)]
+    red: f64,
+    #[serde(default)]
+    green: f64,
+    #[serde(default)]
+    blue: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TextFormat {
+    foreground_color: Option<Color>,
+    font_family: Option<String>,
+    font_size: Option<u32>,
+    #[serde(default)]
+    bold: bool,
+    #[serde(default)]
+    italic: bool,
+    #[serde(default)]
+    strikethrough: bool,
+    #[serde(default)]
+    underline: bool,
+}
+
+/// The sheets of a Sheets API response with their values, formulas and formats, only the
+/// one in `gid` if it's given
+pub fn read_api_response(
+    text: &str,
+    gid: Option<i64>,
+) -> Result<Vec<SheetFile>, GoogleSheetsError> {
+    if let Ok(ApiError { error }) = serde_json::from_str(text) {
+        return Err(GoogleSheetsError::Api(error.message));
+    }
+    let spreadsheet: Spreadsheet = serde_json::from_str(text)
+        .map_err(|error| GoogleSheetsError::Invalid(error.to_string()))?;
+    let sheets = spreadsheet
+        .sheets
+        .into_iter()
+        .filter(|sheet| gid.is_none_or(|gid| sheet.properties.sheet_id == gid))
+        .map(read_sheet)
+        .collect::<Vec<_>>();
+    match sheets.is_empty() {
+        true => Err(GoogleSheetsError::NoSheets),
+        false => Ok(sheets),
+    }
+}
+
+fn read_sheet(sheet: Sheet) -> SheetFile {
+    let mut cells = Vec::new();
+    for data in sheet.data {
+        for (row, row_data) in data.row_data.into_iter().enumerate() {
+            for (column, cell) in row_data.values.into_iter().enumerate() {
+                let content = cell.user_entered_value.map(content_of).unwrap_or_default();
+                let format = cell.user_entered_format.map(format_of).unwrap_or_default();
+                if !content.is_empty() || format != CellFormat::default() {
+                    cells.push(CellFile {
+                        row: data.start_row + row as i32,
+                        column: data.start_column + column as i32,
+                        content,
+                        format,
+                    });
+                }
+            }
+        }
+    }
+    SheetFile {
+        name: sheet.properties.title,
+        cells,
+        ..SheetFile::default()
+    }
+}
+
+fn content_of(value: ExtendedValue) -> String {
+    if let Some(formula) = value.formula_value {
+        formula
+    } else if let Some(number) = value.number_value {
+        number.to_string()
+    } else if let Some(boolean) = value.bool_value {
+        boolean.to_string().to_uppercase()
+    } else {
+        value.string_value.unwrap_or_default()
+    }
+}
+
+fn format_of(format: ApiFormat) -> CellFormat {
+    let mut cell_format = CellFormat {
+        number_format: format
+            .number_format
+            .map(number_format_of)
+            .unwrap_or_default(),
+        // White is how Sheets shows cells without a background
+        background_color: format
+            .background_color
+            .map(hex_color)
+            .filter(|color| color != "#ffffff"),
+        horizontal_align: match format.horizontal_alignment.as_deref() {
+            Some("LEFT") => Some(HorizontalAlign::Left),
+            Some("CENTER") => Some(HorizontalAlign::Center),
+            Some("RIGHT") => Some(HorizontalAlign::Right),
+            _ => None,
+        },
+        vertical_align: match format.vertical_alignment.as_deref() {
+            Some("TOP") => VerticalAlign::Top,
+            Some("BOTTOM") => VerticalAlign::Bottom,
+            _ => VerticalAlign::Middle,
+        },
+        ..CellFormat::default()
+    };
+    if let Some(text) = format.text_format {
+        cell_format.bold = text.bold;
+        cell_format.italic = text.italic;
+        cell_format.underline = text.underline;
+        cell_format.strikethrough = text.strikethrough;
+        cell_format.font_family = text.font_family;
+        cell_format.font_size = text.font_size;
+        // Black is how Sheets shows text without a color, which the theme may not
+        cell_format.text_color = text
+            .foreground_color
+            .map(hex_color)
+            .filter(|color| color != "#000000");
+    }
+    cell_format
+}
+
+/// The closest number format, the pattern itself where it's one format codes can show
+fn number_format_of(format: ApiNumberFormat) -> NumberFormat {
+    match format.kind.as_str() {
+        "DATE" | "DATE_TIME" => NumberFormat::Date(DateFormat::Short),
+        "TIME" => NumberFormat::Date(DateFormat::Time),
+        "NUMBER" | "CURRENCY" | "PERCENT" | "SCIENTIFIC"
+            if FormatCode::parse(&format.pattern).is_some() && !format.pattern.is_empty() =>
+        {
+            NumberFormat::Custom(format.pattern)
+        }
+        "NUMBER" => NumberFormat::Number(2),
+        "CURRENCY" => NumberFormat::Currency(2),
+        "PERCENT" => NumberFormat::Percent(2),
+        _ => NumberFormat::General,
+    }
+}
+
+fn hex_color(color: Color) -> String {
+    let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red),
+        channel(color.green),
+        channel(color.blue)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::google_sheets::*;
+
+    #[test]
+    fn links_point_to_csv_downloads_and_the_api() {
+        let shared = "https://docs.google.com/spreadsheets/d/1AbC-d_9/edit#gid=42";
+        assert_eq!(
+            csv_url(shared).unwrap(),
+            "https://docs.google.com/spreadsheets/d/1AbC-d_9/export?format=csv&gid=42"
+        );
+        let published = "https://docs.google.com/spreadsheets/d/e/2PACX-1vQ/pubhtml";
+        assert_eq!(
+            csv_url(published).unwrap(),
+            "https://docs.google.com/spreadsheets/d/e/2PACX-1vQ/pub?output=csv"
+        );
+        assert!(api_url(shared, " key ")
+            .unwrap()
+            .starts_with("https://sheets.googleapis.com/v4/spreadsheets/1AbC-d_9?"));
+        assert!(api_url(shared, "key").unwrap().ends_with("&key=key"));
+        assert_eq!(
+            api_url(published, "key"),
+            Err(GoogleSheetsError::PublishedLink)
+        );
+        assert_eq!(
+            csv_url("https://example.com/sheet.csv"),
+            Err(GoogleSheetsError::InvalidLink)
+        );
+    }
+
+    #[test]
+    fn api_responses_keep_values_formulas_and_formats() {
+        let text = r##"{"sheets": [
+            {"properties": {"sheetId": 0, "title": "Prices"}, "data": [{"rowData": [
+                {"values": [
+                    {"userEnteredValue": {"stringValue": "Item"},
+                     "userEnteredFormat": {"textFormat": {"bold": true, "foregroundColor": {}},
+                                           "backgroundColor": {"red": 1, "green": 1, "blue": 1}}},
+                    {"userEnteredValue": {"numberValue": 4.5},
+                     "userEnteredFormat": {"numberFormat": {"type": "CURRENCY", "pattern": "#,##0.00"},
+                                           "horizontalAlignment": "CENTER",
+                                           "backgroundColor": {"red": 1, "green": 0.5}}}
+                ]},
+                {"values": [{"userEnteredValue": {"boolValue": true}},
+                            {"userEnteredValue": {"formulaValue": "=B1*2"}}]}
+            ]}]},
+            {"properties": {"sheetId": 7, "title": "Other"}}
+        ]}"##;
+        let sheets = read_api_response(text, None).unwrap();
+        assert_eq!(sheets.len(), 2);
+        let prices = &sheets[0];
+        assert_eq!(prices.name, "Prices");
+        let contents = prices
+            .cells
+            .iter()
+            .map(|cell| (cell.row, cell.column, cell.content.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                (0, 0, "Item"),
+                (0, 1, "4.5"),
+                (1, 0, "TRUE"),
+                (1, 1, "=B1*2")
+            ]
+        );
+        let (item, price) = (&prices.cells[0].format, &prices.cells[1].format);
+        assert!(item.bold);
+        assert_eq!(
+            (item.text_color.clone(), item.background_color.clone()),
+            (None, None)
+        );
+        assert_eq!(
+            price.number_format,
+            NumberFormat::Custom("#,##0.00".to_string())
+        );
+        assert_eq!(price.horizontal_align, Some(HorizontalAlign::Center));
+        assert_eq!(price.background_color.as_deref(), Some("#ff8000"));
+
+        let other = read_api_response(text, Some(7)).unwrap();
+        assert_eq!(other.len(), 1);
+        assert_eq!(other[0].name, "Other");
+        assert_eq!(
+            read_api_response(
+                r#"{"error": {"code": 400, "message": "API key not valid."}}"#,
+                None
+            ),
+            Err(GoogleSheetsError::Api("API key not valid.".to_string()))
+        );
+    }
+
+    #[test]
+    fn csv_downloads_are_one_sheet_of_values() {
+        let sheet = read_csv("a,\"b,c\"\n,1\n");
+        assert_eq!(sheet.name, CSV_SHEET_NAME);
+        let contents = sheet
+            .cells
+            .iter()
+            .map(|cell| (cell.row, cell.column, cell.content.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(contents, vec![(0, 0, "a"), (0, 1, "b,c"), (1, 1, "1")]);
+    }
+}
@@ -1,9 +1,15 @@
-use std::{collections::HashMap, fmt};
+use std::{collections::BTreeSet, fmt};
 
-use petgraph::{
-    prelude::GraphMap,
-    Directed,
-    Direction::{Incoming, Outgoing},
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{flash_fill, parser::ParseDiagnostic},
+    model::{
+        command::MoveDirection,
+        format::CellFormat,
+        view::{CellRange, ViewState},
+        workbook::{CellKey, Sheet, SheetId, Workbook},
+    },
 };
 
 pub fn column_index_to_letter(column: i32) -> String {
@@ -24,9 +30,21 @@ fn column_letter_to_index(column: &str) -> i32 {
     result
 }
 
+pub fn coords_to_cell_address(coords: Coords) -> String {
+    format!(
+        "{}{}",
+        column_index_to_letter(coords.column),
+        coords.row + 1
+    )
+}
+
+/// Absolute markers (`$A$1`) don't change which cell an address points to
 pub fn cell_address_to_coords(address: &str) -> Option<Coords> {
+    let address = &address.replace('$', "");
     let col_end = address.find(|c: char| c.is_numeric())?;
-    if col_end == 0 {
+    // Up to XFD, so formulas like `SUBTOTAL(9,A1)` aren't read as a column
+    let letters = &address[..col_end];
+    if col_end == 0 || col_end > 3 || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
         return None;
     }
     let col = column_letter_to_index(&address[..col_end]);
@@ -42,6 +60,7 @@ pub enum GetCellValueError {
     InvalidAddress,
     InvalidValue(String),
     CircularReference,
+    UnknownSheet,
 }
 
 impl fmt::Display for GetCellValueError {
@@ -50,22 +69,15 @@ impl fmt::Display for GetCellValueError {
             GetCellValueError::InvalidAddress => write!(f, "Invalid address"),
             GetCellValueError::InvalidValue(value) => write!(f, "{}", value),
             GetCellValueError::CircularReference => write!(f, "#REF!"),
+            GetCellValueError::UnknownSheet => write!(f, "#REF!"),
         }
     }
 }
 
+/// The open workbook and the view onto it. Every change to either goes through `Grid::apply`.
 pub struct Grid {
-    pub cells_map: HashMap<Coords, Cell>,
-    pub cells_dep_graph: GraphMap<Coords, (), Directed>,
-
-    pub current_cell: Coords,
-    pub previous_content: String,
-    pub is_editing_cell: bool,
-
-    pub base_header_column_width: i32,
-    pub base_header_row_height: i32,
-    pub column_widths: Vec<i32>,
-    pub row_heights: Vec<i32>,
+    pub workbook: Workbook,
+    pub view: ViewState,
 }
 
 impl Grid {
@@ -75,102 +87,774 @@ impl Grid {
         column_count: usize,
         row_count: usize,
     ) -> Self {
-        Grid {
-            cells_map: HashMap::new(),
-            cells_dep_graph: GraphMap::new(),
-
-            current_cell: Coords { row: 0, column: 0 },
-            previous_content: String::new(),
-            is_editing_cell: false,
-
+        let workbook = Workbook::new(
             base_header_column_width,
             base_header_row_height,
-            column_widths: vec![base_header_column_width; column_count],
-            row_heights: vec![base_header_row_height; row_count],
-        }
+            column_count,
+            row_count,
+        );
+        let view = ViewState::new(
+            workbook.sheets[0].id,
+            base_header_column_width,
+            base_header_row_height,
+        );
+        Grid { workbook, view }
     }
 
-    pub fn get_cell_value_by_address(&self, address: &str) -> Result<f64, GetCellValueError> {
-        let coords = cell_address_to_coords(address).ok_or(GetCellValueError::InvalidAddress)?;
-        match self.cells_map.get(&coords) {
-            Some(cell) => match cell.display_value.parse() {
-                Ok(value) => Ok(value),
-                Err(_) => {
-                    if cell.content.is_empty() {
-                        return Ok(0.0);
-                    }
-                    match cell.display_value == "#REF!" {
-                        true => Err(GetCellValueError::CircularReference),
-                        false => Err(GetCellValueError::InvalidValue(cell.display_value.clone())),
-                    }
-                }
-            },
-            None => Ok(0.0),
+    pub fn sheet(&self) -> &Sheet {
+        self.workbook
+            .get_sheet(self.view.active_sheet)
+            .expect("active sheet exists")
+    }
+    pub fn sheet_mut(&mut self) -> &mut Sheet {
+        self.workbook
+            .get_sheet_mut(self.view.active_sheet)
+            .expect("active sheet exists")
+    }
+    /// Key of a cell on the active sheet
+    pub fn key(&self, coords: Coords) -> CellKey {
+        CellKey {
+            sheet: self.view.active_sheet,
+            coords,
         }
     }
+    pub fn current_cell(&self) -> Coords {
+        self.view.current_cell()
+    }
+    pub fn current_key(&self) -> CellKey {
+        self.key(self.current_cell())
+    }
+    /// Cell a typed address like `B3` or `Sheet2!B3` points to
+    pub fn resolve_address(&self, address: &str) -> Option<CellKey> {
+        self.workbook
+            .resolve_reference(address.trim(), self.view.active_sheet)
+            .ok()
+    }
+    /// Resolves `A1`, `A1:B3` or either with a sheet prefix, as typed into Go To
+    pub fn resolve_range(&self, text: &str) -> Option<(SheetId, CellRange)> {
+        let (first, last) = match text.split_once(':') {
+            Some((first, last)) => (first, Some(last)),
+            None => (text, None),
+        };
+        let anchor = self.resolve_address(first)?;
+        let extent = match last {
+            Some(last) => cell_address_to_coords(last.trim())?,
+            None => anchor.coords,
+        };
+        Some((
+            anchor.sheet,
+            CellRange {
+                anchor: anchor.coords,
+                extent,
+            },
+        ))
+    }
+    /// Address of `key`, prefixed with its sheet if that isn't the active one
+    pub fn get_cell_reference(&self, key: CellKey) -> String {
+        self.workbook.format_reference(key, self.view.active_sheet)
+    }
 
+    pub fn get_current_cell(&self) -> Option<&Cell> {
+        self.sheet().cells_map.get(&self.current_cell())
+    }
     pub fn get_mut_current_cell(&mut self) -> &mut Cell {
-        self.cells_map
-            .entry(self.current_cell)
+        let coords = self.current_cell();
+        self.sheet_mut()
+            .cells_map
+            .entry(coords)
             .or_insert(Cell::new())
     }
     pub fn get_current_cell_address(&self) -> String {
-        format!(
-            "{}{}",
-            column_index_to_letter(self.current_cell.column),
-            self.current_cell.row + 1
-        )
+        coords_to_cell_address(self.current_cell())
     }
     pub fn get_current_cell_content(&self) -> String {
-        self.cells_map
-            .get(&self.current_cell)
-            .map(|c| c.content.clone())
-            .unwrap_or_default()
+        self.sheet().get_cell_content(self.current_cell())
+    }
+    /// `A1:C3` for a range, just the cell's address for a single cell, ranges joined with commas
+    pub fn get_selection_address(&self) -> String {
+        self.view
+            .selection()
+            .ranges
+            .iter()
+            .map(|range| match range.is_single_cell() {
+                true => coords_to_cell_address(range.anchor),
+                false => format!(
+                    "{}:{}",
+                    coords_to_cell_address(range.top_left()),
+                    coords_to_cell_address(range.bottom_right())
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
     }
 
+    /// Moves up to the nearest row a filter or collapsed group isn't hiding
     pub fn current_cell_up_one(&mut self) {
-        if self.current_cell.row > 0 {
-            self.current_cell.row -= 1;
+        let mut coords = self.current_cell();
+        if let Some(row) = (0..coords.row)
+            .rev()
+            .find(|row| !self.sheet().is_row_hidden(*row))
+        {
+            coords.row = row;
+            self.view.select(coords);
         }
     }
+    /// Moves down to the nearest row a filter or collapsed group isn't hiding
     pub fn current_cell_down_one(&mut self) {
-        if self.current_cell.row < (self.row_heights.len() - 1) as i32 {
-            self.current_cell.row += 1;
+        let mut coords = self.current_cell();
+        let last_row = self.sheet().row_heights.len() as i32 - 1;
+        if let Some(row) = (coords.row + 1..=last_row).find(|row| !self.sheet().is_row_hidden(*row))
+        {
+            coords.row = row;
+            self.view.select(coords);
         }
     }
+    /// Moves left to the nearest column a collapsed group isn't hiding
     pub fn current_cell_left_one(&mut self) {
-        if self.current_cell.column > 0 {
-            self.current_cell.column -= 1;
+        let mut coords = self.current_cell();
+        if let Some(column) = (0..coords.column)
+            .rev()
+            .find(|column| !self.sheet().is_column_hidden(*column))
+        {
+            coords.column = column;
+            self.view.select(coords);
         }
     }
+    /// Moves right to the nearest column a collapsed group isn't hiding
     pub fn current_cell_right_one(&mut self) {
-        if self.current_cell.column < (self.column_widths.len() - 1) as i32 {
-            self.current_cell.column += 1;
+        let mut coords = self.current_cell();
+        let last_column = self.sheet().column_widths.len() as i32 - 1;
+        if let Some(column) =
+            (coords.column + 1..=last_column).find(|column| !self.sheet().is_column_hidden(*column))
+        {
+            coords.column = column;
+            self.view.select(coords);
         }
     }
 
-    pub fn remove_cell_dependencies(&mut self, coords: Coords) {
-        self.cells_dep_graph
-            .edges_directed(coords, Incoming)
-            .map(|(a, b, _)| (a, b))
-            .collect::<Vec<_>>()
+    /// Moves the active range's extent one cell, keeping it inside the sheet
+    pub fn extend_selection_towards(&mut self, direction: MoveDirection) {
+        let mut extent = self.view.selection().active_range().extent;
+        match direction {
+            MoveDirection::Up if extent.row > 0 => extent.row -= 1,
+            MoveDirection::Down if extent.row < (self.sheet().row_heights.len() - 1) as i32 => {
+                extent.row += 1
+            }
+            MoveDirection::Left if extent.column > 0 => extent.column -= 1,
+            MoveDirection::Right
+                if extent.column < (self.sheet().column_widths.len() - 1) as i32 =>
+            {
+                extent.column += 1
+            }
+            _ => return,
+        }
+        self.view.extend_selection(extent);
+    }
+
+    /// Widens the active range to the whole of its columns (Ctrl+Space) or rows (Shift+Space)
+    pub fn select_entire(&mut self, columns: bool) {
+        let CellRange {
+            mut anchor,
+            mut extent,
+        } = self.view.selection().active_range();
+        match columns {
+            true => {
+                anchor.row = 0;
+                extent.row = self.sheet().row_heights.len() as i32 - 1;
+            }
+            false => {
+                anchor.column = 0;
+                extent.column = self.sheet().column_widths.len() as i32 - 1;
+            }
+        }
+        self.view.select(anchor);
+        self.view.extend_selection(extent);
+    }
+
+    /// The block of data around a cell: the smallest rectangle containing it with no filled
+    /// cells touching its outside, even diagonally. None if the cell is alone and empty.
+    pub fn get_data_region(&self, coords: Coords) -> Option<CellRange> {
+        let filled = self
+            .sheet()
+            .cells_map
+            .iter()
+            .filter(|(_, cell)| !cell.content.is_empty())
+            .map(|(coords, _)| *coords)
+            .collect::<Vec<_>>();
+        let (mut top_left, mut bottom_right) = (coords, coords);
+        loop {
+            let touching = filled.iter().filter(|c| {
+                (top_left.row - 1..=bottom_right.row + 1).contains(&c.row)
+                    && (top_left.column - 1..=bottom_right.column + 1).contains(&c.column)
+            });
+            let (mut new_top_left, mut new_bottom_right) = (top_left, bottom_right);
+            for c in touching {
+                new_top_left.row = new_top_left.row.min(c.row);
+                new_top_left.column = new_top_left.column.min(c.column);
+                new_bottom_right.row = new_bottom_right.row.max(c.row);
+                new_bottom_right.column = new_bottom_right.column.max(c.column);
+            }
+            if (new_top_left, new_bottom_right) == (top_left, bottom_right) {
+                break;
+            }
+            (top_left, bottom_right) = (new_top_left, new_bottom_right);
+        }
+        if top_left == bottom_right && !filled.contains(&coords) {
+            return None;
+        }
+        Some(CellRange {
+            anchor: top_left,
+            extent: bottom_right,
+        })
+    }
+
+    /// Selects the data region around the current cell, or the whole sheet if that's
+    /// already selected (Ctrl+A, pressed once or twice)
+    pub fn select_all(&mut self) {
+        let selection = self.view.selection();
+        let region = self
+            .get_data_region(self.current_cell())
+            .filter(|region| selection.ranges != [*region]);
+        let range = region.unwrap_or(CellRange {
+            anchor: Coords { row: 0, column: 0 },
+            extent: Coords {
+                row: self.sheet().row_heights.len() as i32 - 1,
+                column: self.sheet().column_widths.len() as i32 - 1,
+            },
+        });
+        self.view.select(range.anchor);
+        self.view.extend_selection(range.extent);
+    }
+
+    /// Columns selected from top to bottom, as clicking their headers selects them
+    pub fn get_selected_columns(&self) -> Vec<i32> {
+        let last_row = self.sheet().row_heights.len() as i32 - 1;
+        let mut columns = self
+            .view
+            .selection()
+            .ranges
+            .iter()
+            .filter(|range| range.top_left().row == 0 && range.bottom_right().row == last_row)
+            .flat_map(|range| range.top_left().column..=range.bottom_right().column)
+            .collect::<Vec<_>>();
+        columns.sort();
+        columns.dedup();
+        columns
+    }
+
+    /// Rows selected from end to end, as clicking their headers selects them
+    pub fn get_selected_rows(&self) -> Vec<i32> {
+        let last_column = self.sheet().column_widths.len() as i32 - 1;
+        let mut rows = self
+            .view
+            .selection()
+            .ranges
+            .iter()
+            .filter(|range| {
+                range.top_left().column == 0 && range.bottom_right().column == last_column
+            })
+            .flat_map(|range| range.top_left().row..=range.bottom_right().row)
+            .collect::<Vec<_>>();
+        rows.sort();
+        rows.dedup();
+        rows
+    }
+
+    /// Where Ctrl+Arrow goes from a cell: the end of the block of filled cells it's in, the
+    /// start of the next block, or the edge of the sheet if there's no more data that way
+    pub fn get_data_edge(&self, from: Coords, direction: &MoveDirection) -> Coords {
+        let (along_rows, step) = match direction {
+            MoveDirection::Up => (true, -1),
+            MoveDirection::Down => (true, 1),
+            MoveDirection::Left => (false, -1),
+            MoveDirection::Right => (false, 1),
+        };
+        let (position, last) = match along_rows {
+            true => (from.row, self.sheet().row_heights.len() as i32 - 1),
+            false => (from.column, self.sheet().column_widths.len() as i32 - 1),
+        };
+        // Only the filled cells on the line being travelled
+        let filled = self
+            .sheet()
+            .cells_map
+            .iter()
+            .filter(|(_, cell)| !cell.content.is_empty())
+            .filter_map(|(coords, _)| match along_rows {
+                true => (coords.column == from.column).then_some(coords.row),
+                false => (coords.row == from.row).then_some(coords.column),
+            })
+            .collect::<BTreeSet<_>>();
+
+        let destination = if filled.contains(&position) && filled.contains(&(position + step)) {
+            let mut end = position;
+            while filled.contains(&(end + step)) {
+                end += step;
+            }
+            end
+        } else {
+            let next = match step > 0 {
+                true => filled.range(position + 1..).next(),
+                false => filled.range(..position).next_back(),
+            };
+            next.copied().unwrap_or(if step > 0 { last } else { 0 })
+        };
+        let destination = destination.clamp(0, last);
+        match along_rows {
+            true => Coords {
+                row: destination,
+                column: from.column,
+            },
+            false => Coords {
+                row: from.row,
+                column: destination,
+            },
+        }
+    }
+
+    /// The row a page up or down from `row` lands on, a page being as many rows as fit in
+    /// `height` pixels but at least one
+    pub fn get_page_row(&self, row: i32, height: i32, down: bool) -> i32 {
+        let last = self.sheet().row_heights.len() as i32 - 1;
+        let step = if down { 1 } else { -1 };
+        let (mut target, mut filled) = (row, 0);
+        while (0..=last).contains(&(target + step)) {
+            filled += self.sheet().row_heights[(target + step) as usize];
+            if filled > height && target != row {
+                break;
+            }
+            target += step;
+        }
+        target
+    }
+
+    /// Bottom-right corner of the cells in use, where Ctrl+End goes
+    pub fn get_used_range_end(&self) -> Coords {
+        self.sheet()
+            .cells_map
+            .iter()
+            .filter(|(_, cell)| !cell.content.is_empty())
+            .fold(Coords { row: 0, column: 0 }, |end, (coords, _)| Coords {
+                row: end.row.max(coords.row),
+                column: end.column.max(coords.column),
+            })
+    }
+
+    /// Cells of a kind within the selection, or within the cells in use if a single cell is
+    /// selected (Go To Special)
+    pub fn get_special_cells(&self, kind: SpecialCells) -> Vec<Coords> {
+        let selection = self.view.selection();
+        let cells = match selection.is_single_cell() {
+            true => CellRange {
+                anchor: Coords { row: 0, column: 0 },
+                extent: self.get_used_range_end(),
+            }
+            .cells()
+            .collect(),
+            false => selection.cells(),
+        };
+        cells
+            .into_iter()
+            .filter(|coords| {
+                let cell = self.sheet().cells_map.get(coords);
+                let content = cell.map(|c| c.content.as_str()).unwrap_or_default();
+                let is_formula = content.starts_with('=');
+                match kind {
+                    SpecialCells::Formulas => is_formula,
+                    SpecialCells::Constants => !is_formula && !content.is_empty(),
+                    SpecialCells::Errors => {
+                        is_formula && cell.is_some_and(|c| is_error_value(&c.display_value))
+                    }
+                    SpecialCells::Blanks => content.is_empty(),
+                }
+            })
+            .collect()
+    }
+
+    /// Cells the fill handle dragged to `target` would fill, and which way, if it's outside
+    /// the active range. The fill runs along whichever axis the target is further out on.
+    pub fn get_fill_range(&self, target: Coords) -> Option<(CellRange, MoveDirection)> {
+        let source = self.view.selection().active_range();
+        let (top_left, bottom_right) = (source.top_left(), source.bottom_right());
+        let candidates = [
+            (target.row - bottom_right.row, MoveDirection::Down),
+            (top_left.row - target.row, MoveDirection::Up),
+            (target.column - bottom_right.column, MoveDirection::Right),
+            (top_left.column - target.column, MoveDirection::Left),
+        ];
+        let (distance, direction) = candidates
             .into_iter()
-            .for_each(|(a, b)| {
-                self.cells_dep_graph.remove_edge(a, b);
+            .max_by_key(|(distance, _)| *distance)?;
+        if distance <= 0 {
+            return None;
+        }
+        let (anchor, extent) = match direction {
+            MoveDirection::Down => (
+                Coords {
+                    row: bottom_right.row + 1,
+                    column: top_left.column,
+                },
+                Coords {
+                    row: target.row,
+                    column: bottom_right.column,
+                },
+            ),
+            MoveDirection::Up => (
+                Coords {
+                    row: target.row,
+                    column: top_left.column,
+                },
+                Coords {
+                    row: top_left.row - 1,
+                    column: bottom_right.column,
+                },
+            ),
+            MoveDirection::Right => (
+                Coords {
+                    row: top_left.row,
+                    column: bottom_right.column + 1,
+                },
+                Coords {
+                    row: bottom_right.row,
+                    column: target.column,
+                },
+            ),
+            MoveDirection::Left => (
+                Coords {
+                    row: top_left.row,
+                    column: target.column,
+                },
+                Coords {
+                    row: bottom_right.row,
+                    column: top_left.column - 1,
+                },
+            ),
+        };
+        Some((CellRange { anchor, extent }, direction))
+    }
+
+    /// Where the active range lands when moved so its top-left is at `target`, shifted back
+    /// inside the sheet if it would hang off an edge
+    pub fn get_move_range(&self, target: Coords) -> CellRange {
+        let source = self.view.selection().active_range();
+        let (top_left, bottom_right) = (source.top_left(), source.bottom_right());
+        let (rows, columns) = (
+            bottom_right.row - top_left.row,
+            bottom_right.column - top_left.column,
+        );
+        let anchor = Coords {
+            row: target
+                .row
+                .clamp(0, self.sheet().row_heights.len() as i32 - 1 - rows),
+            column: target
+                .column
+                .clamp(0, self.sheet().column_widths.len() as i32 - 1 - columns),
+        };
+        CellRange {
+            anchor,
+            extent: Coords {
+                row: anchor.row + rows,
+                column: anchor.column + columns,
+            },
+        }
+    }
+
+    /// The rows sorting reorders: a table's data rows around the current cell, the active
+    /// range if several cells are selected, or else the data region below a header row of
+    /// text. Columns of formulas often have no header, so blank header cells are allowed.
+    pub fn get_sort_range(&self) -> Option<CellRange> {
+        let current = self.current_cell();
+        if let Some(index) = self.sheet().table_at(current) {
+            let table = &self.sheet().tables[index];
+            let (top_left, bottom_right) = (table.range.top_left(), table.range.bottom_right());
+            let rows = table.data_rows();
+            return (!rows.is_empty()).then_some(CellRange {
+                anchor: Coords {
+                    row: *rows.start(),
+                    column: top_left.column,
+                },
+                extent: Coords {
+                    row: *rows.end(),
+                    column: bottom_right.column,
+                },
             });
+        }
+        let selection = self.view.selection();
+        if !selection.is_single_cell() {
+            return Some(selection.active_range());
+        }
+        let region = self.get_data_region(current)?;
+        let (top_left, bottom_right) = (region.top_left(), region.bottom_right());
+        let header = (top_left.column..=bottom_right.column)
+            .filter_map(|column| {
+                self.sheet().cells_map.get(&Coords {
+                    row: top_left.row,
+                    column,
+                })
+            })
+            .map(|cell| cell.display_value.as_str())
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>();
+        let has_header =
+            !header.is_empty() && header.iter().all(|value| value.parse::<f64>().is_err());
+        let first = top_left.row + has_header as i32;
+        (first <= bottom_right.row).then_some(CellRange {
+            anchor: Coords {
+                row: first,
+                column: top_left.column,
+            },
+            extent: bottom_right,
+        })
     }
-    pub fn get_cell_dependants(&self, coords: Coords) -> Vec<Coords> {
-        self.cells_dep_graph
-            .edges_directed(coords, Outgoing)
-            .map(|(_, b, _)| b)
-            .collect::<Vec<_>>()
+
+    /// What flash fill would put in the empty cells of the current column, learning from the
+    /// filled ones beside the same block of data in the column to the left (or right)
+    pub fn get_flash_fill(&self) -> Vec<(Coords, String)> {
+        let sheet = self.sheet();
+        let current = self.current_cell();
+        let (row_count, column_count) = (
+            sheet.row_heights.len() as i32,
+            sheet.column_widths.len() as i32,
+        );
+        let value = |row: i32, column: i32| {
+            sheet
+                .cells_map
+                .get(&Coords { row, column })
+                .map(|cell| cell.display_value.clone())
+                .unwrap_or_default()
+        };
+        let Some(source_column) =
+            [current.column - 1, current.column + 1]
+                .into_iter()
+                .find(|&column| {
+                    (0..column_count).contains(&column) && !value(current.row, column).is_empty()
+                })
+        else {
+            return Vec::new();
+        };
+        let is_data =
+            |row: i32| (0..row_count).contains(&row) && !value(row, source_column).is_empty();
+        let (mut top, mut bottom) = (current.row, current.row);
+        while is_data(top - 1) {
+            top -= 1;
+        }
+        while is_data(bottom + 1) {
+            bottom += 1;
+        }
+
+        let mut examples = Vec::new();
+        let mut targets = Vec::new();
+        for row in top..=bottom {
+            let coords = Coords {
+                row,
+                column: current.column,
+            };
+            let content = sheet.get_cell_content(coords);
+            match content.is_empty() {
+                true => targets.push((coords, value(row, source_column))),
+                false => examples.push((value(row, source_column), content)),
+            }
+        }
+        let Some(pattern) = flash_fill::infer(&examples) else {
+            return Vec::new();
+        };
+        targets
+            .into_iter()
+            .filter_map(|(coords, source)| Some((coords, pattern.apply(&source)?)))
+            .collect()
+    }
+
+    pub fn is_search_match(&self, cell: &Cell) -> bool {
+        match &self.view.search_query {
+            Some(query) => cell_matches(cell, query),
+            _ => false,
+        }
+    }
+    /// Cells matching a query on every sheet, in sheet order and row-major order within each
+    pub fn find_all(&self, query: &str) -> Vec<CellKey> {
+        self.workbook
+            .sheets
+            .iter()
+            .flat_map(|sheet| {
+                let mut matches = sheet
+                    .cells_map
+                    .iter()
+                    .filter(|(_, cell)| cell_matches(cell, query))
+                    .map(|(coords, _)| CellKey {
+                        sheet: sheet.id,
+                        coords: *coords,
+                    })
+                    .collect::<Vec<_>>();
+                matches.sort_by_key(|key| key.coords);
+                matches
+            })
+            .collect()
+    }
+    /// Matching cells of the active sheet in row-major order
+    pub fn get_search_matches(&self) -> Vec<Coords> {
+        let mut matches = self
+            .sheet()
+            .cells_map
+            .iter()
+            .filter(|(_, cell)| self.is_search_match(cell))
+            .map(|(coords, _)| *coords)
+            .collect::<Vec<_>>();
+        matches.sort();
+        matches
+    }
+    /// Moves the current cell to a search match, wrapping around the grid.
+    /// Returns false if nothing matches.
+    pub fn search_step(&mut self, step: SearchStep) -> bool {
+        let matches = self.get_search_matches();
+        let current = self.current_cell();
+        let next = match step {
+            SearchStep::Stay => matches.iter().find(|c| **c >= current),
+            SearchStep::Forward => matches.iter().find(|c| **c > current),
+            SearchStep::Backward => matches.iter().rev().find(|c| **c < current),
+        };
+        let wrapped = match step {
+            SearchStep::Backward => matches.last(),
+            _ => matches.first(),
+        };
+        match next.or(wrapped) {
+            Some(coords) => {
+                self.view.select(*coords);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn trace_precedents(&mut self) {
+        let key = self.current_key();
+        for precedent in self.workbook.get_cell_precedents(key) {
+            self.view.add_trace_arrow(precedent, key);
+        }
+    }
+    pub fn trace_dependants(&mut self) {
+        let key = self.current_key();
+        for dependant in self.workbook.get_cell_dependants(key) {
+            self.view.add_trace_arrow(key, dependant);
+        }
+    }
+
+    /// Offsets at which a cell sticks while the grid scrolls, if it's in a frozen row or column
+    pub fn get_frozen_offsets(&self, coords: Coords) -> (Option<i32>, Option<i32>) {
+        let Some(frozen) = self.view.frozen_panes() else {
+            return (None, None);
+        };
+        let rect = self.get_cell_rect(coords);
+        (
+            (coords.column < frozen.column).then_some(rect.x),
+            (coords.row < frozen.row).then_some(rect.y),
+        )
+    }
+
+    /// Position and size of a cell in pixels, relative to the grid's top-left corner
+    pub fn get_cell_rect(&self, coords: Coords) -> CellRect {
+        let sheet = self.sheet();
+        let (base_width, base_height) = (
+            self.view.base_header_column_width,
+            self.view.base_header_row_height,
+        );
+        let column = coords.column.max(0) as usize;
+        let row = coords.row.max(0) as usize;
+        CellRect {
+            x: base_width
+                + (0..column)
+                    .map(|column| sheet.displayed_column_width(column))
+                    .sum::<i32>(),
+            y: base_height
+                + (0..row)
+                    .map(|row| sheet.displayed_row_height(row))
+                    .sum::<i32>(),
+            width: match column < sheet.column_widths.len() {
+                true => sheet.displayed_column_width(column),
+                false => base_width,
+            },
+            height: match row < sheet.row_heights.len() {
+                true => sheet.displayed_row_height(row),
+                false => base_height,
+            },
+        }
+    }
+
+    /// The cell under a point relative to the grid's top-left corner, the nearest one if the
+    /// point is over the headers or past the last cell
+    pub fn get_cell_at(&self, x: i32, y: i32) -> Coords {
+        let sheet = self.sheet();
+        let index_at = |offset: i32, sizes: &mut dyn Iterator<Item = i32>| {
+            let mut end = 0;
+            let mut last = 0;
+            for (index, size) in sizes.enumerate() {
+                end += size;
+                last = index;
+                if offset < end {
+                    break;
+                }
+            }
+            last as i32
+        };
+        Coords {
+            row: index_at(
+                y - self.view.base_header_row_height,
+                &mut (0..sheet.row_heights.len()).map(|row| sheet.displayed_row_height(row)),
+            ),
+            column: index_at(
+                x - self.view.base_header_column_width,
+                &mut (0..sheet.column_widths.len())
+                    .map(|column| sheet.displayed_column_width(column)),
+            ),
+        }
     }
 }
 
-#[derive(Debug)]
+pub struct CellRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl CellRect {
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SpecialCells {
+    Formulas,
+    Constants,
+    Errors,
+    Blanks,
+}
+
+/// Whether a cell's value contains a search query, ignoring case
+fn cell_matches(cell: &Cell, query: &str) -> bool {
+    !query.is_empty()
+        && cell
+            .display_value
+            .to_lowercase()
+            .contains(&query.to_lowercase())
+}
+
+/// Whether a value is one of the errors formulas evaluate to, like `#REF!` or `#NAME?`
+pub fn is_error_value(value: &str) -> bool {
+    value.starts_with('#') && (value.ends_with('!') || value.ends_with('?'))
+}
+
+pub enum SearchStep {
+    Stay,
+    Forward,
+    Backward,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Cell {
     pub content: String,
     pub display_value: String,
+    pub diagnostic: Option<ParseDiagnostic>,
+    pub format: CellFormat,
 }
 
 impl Cell {
@@ -178,12 +862,101 @@ impl Cell {
         Cell {
             content: String::new(),
             display_value: String::new(),
+            diagnostic: None,
+            format: CellFormat::default(),
         }
     }
 }
 
-#[derive(Hash, Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Hash, Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Coords {
     pub row: i32,
     pub column: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::grid::*;
+
+    fn grid_with(cells: &[(&str, &str)]) -> Grid {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        for (address, value) in cells {
+            let mut cell = Cell::new();
+            cell.content = value.to_string();
+            cell.display_value = value.to_string();
+            grid.sheet_mut()
+                .cells_map
+                .insert(cell_address_to_coords(address).unwrap(), cell);
+        }
+        grid
+    }
+
+    #[test]
+    fn addresses_are_column_letters_then_a_row() {
+        assert_eq!(
+            cell_address_to_coords("$AB$12"),
+            Some(Coords {
+                row: 11,
+                column: 27
+            })
+        );
+        assert_eq!(cell_address_to_coords("SUBTOTAL(9,A1)"), None);
+        assert_eq!(cell_address_to_coords("A(1"), None);
+        assert_eq!(cell_address_to_coords("12"), None);
+    }
+
+    #[test]
+    fn search_wraps_around() {
+        let mut grid = grid_with(&[("B1", "apple"), ("A3", "Pineapple"), ("C2", "pear")]);
+        grid.view.search_query = Some("APPLE".to_string());
+        assert_eq!(
+            grid.get_search_matches(),
+            vec![
+                cell_address_to_coords("B1").unwrap(),
+                cell_address_to_coords("A3").unwrap()
+            ]
+        );
+
+        assert!(grid.search_step(SearchStep::Forward));
+        assert_eq!(grid.get_current_cell_address(), "B1");
+        assert!(grid.search_step(SearchStep::Forward));
+        assert_eq!(grid.get_current_cell_address(), "A3");
+        assert!(grid.search_step(SearchStep::Forward));
+        assert_eq!(grid.get_current_cell_address(), "B1");
+        assert!(grid.search_step(SearchStep::Backward));
+        assert_eq!(grid.get_current_cell_address(), "A3");
+        assert!(grid.search_step(SearchStep::Stay));
+        assert_eq!(grid.get_current_cell_address(), "A3");
+
+        grid.view.search_query = Some("kiwi".to_string());
+        assert!(!grid.search_step(SearchStep::Forward));
+    }
+
+    #[test]
+    fn find_all_searches_every_sheet() {
+        let mut grid = grid_with(&[("B2", "apple"), ("A3", "pear"), ("A1", "Apple pie")]);
+        let first = grid.view.active_sheet;
+        let second = grid.workbook.add_sheet();
+        grid.view.active_sheet = second;
+        let mut cell = Cell::new();
+        cell.display_value = "crab apple".to_string();
+        grid.sheet_mut()
+            .cells_map
+            .insert(cell_address_to_coords("C1").unwrap(), cell);
+
+        let found = grid
+            .find_all("apple")
+            .into_iter()
+            .map(|key| (key.sheet, coords_to_cell_address(key.coords)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (first, "A1".to_string()),
+                (first, "B2".to_string()),
+                (second, "C1".to_string())
+            ]
+        );
+        assert!(grid.find_all("").is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::date,
+    model::grid::{Coords, Grid},
+};
+
+/// The most edits kept for a cell, older ones are dropped
+pub const MAX_CELL_EDITS: usize = 50;
+
+/// A change to a cell's content
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellEdit {
+    pub old: String,
+    pub new: String,
+    /// When it was made, in seconds since 1970-01-01
+    pub timestamp: u64,
+}
+
+/// The changes made to a cell, oldest first, as saved with its sheet
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellHistory {
+    pub cell: Coords,
+    pub edits: Vec<CellEdit>,
+}
+
+impl Grid {
+    /// Notes a change to a cell of the active sheet, if its content did change
+    pub fn record_edit(&mut self, coords: Coords, old: String) {
+        let new = self.sheet().get_cell_content(coords);
+        if new == old {
+            return;
+        }
+        let edits = self.sheet_mut().edit_history.entry(coords).or_default();
+        edits.push(CellEdit {
+            old,
+            new,
+            timestamp: date::now_seconds(),
+        });
+        if edits.len() > MAX_CELL_EDITS {
+            edits.remove(0);
+        }
+    }
+
+    /// The changes made to a cell of the active sheet, latest first
+    pub fn get_edit_history(&self, coords: Coords) -> Vec<CellEdit> {
+        let mut edits = self
+            .sheet()
+            .edit_history
+            .get(&coords)
+            .cloned()
+            .unwrap_or_default();
+        edits.reverse();
+        edits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{command::GridCommand, file::read_workbook, history::*};
+
+    #[test]
+    fn edits_are_recorded_per_cell() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let a1 = Coords { row: 0, column: 0 };
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some("1".to_string()),
+        });
+        grid.apply(GridCommand::CommitEdit(a1));
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "2".to_string(),
+        });
+        // Setting what's already there isn't a change
+        grid.apply(GridCommand::SetCellContents(vec![(a1, "2".to_string())]));
+        grid.apply(GridCommand::ClearContents);
+
+        let history = grid
+            .get_edit_history(a1)
+            .into_iter()
+            .map(|edit| (edit.old, edit.new))
+            .collect::<Vec<_>>();
+        let expected =
+            [("2", ""), ("1", "2"), ("", "1")].map(|(old, new)| (old.to_string(), new.to_string()));
+        assert_eq!(history, expected);
+        assert!(grid
+            .get_edit_history(Coords { row: 1, column: 0 })
+            .is_empty());
+
+        let mut reopened = Grid::new(90, 25, 5, 5);
+        reopened.open_workbook(read_workbook(&grid.save_workbook()).unwrap());
+        assert_eq!(reopened.get_edit_history(a1), grid.get_edit_history(a1));
+    }
+}
@@ -0,0 +1,410 @@
+use crate::{
+    engine::csv::{self, ColumnType, CsvRecords},
+    model::{
+        format::HorizontalAlign,
+        grid::{Coords, Grid},
+        workbook::{CellKey, SheetId},
+    },
+};
+
+/// Rows written at a time, between which the page gets to draw and take input. A quarter of
+/// a sheet of the usual size.
+pub const IMPORT_BATCH_ROWS: usize = 25;
+
+/// The rows an import writes: CSV text parsed a batch at a time, or rows read already
+#[derive(Clone, Debug)]
+pub enum ImportRows {
+    Csv(CsvRecords),
+    Read(std::vec::IntoIter<Vec<String>>),
+}
+
+impl From<CsvRecords> for ImportRows {
+    fn from(records: CsvRecords) -> Self {
+        ImportRows::Csv(records)
+    }
+}
+
+impl From<Vec<Vec<String>>> for ImportRows {
+    fn from(rows: Vec<Vec<String>>) -> Self {
+        ImportRows::Read(rows.into_iter())
+    }
+}
+
+impl Iterator for ImportRows {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        match self {
+            ImportRows::Csv(records) => records.next(),
+            ImportRows::Read(rows) => rows.next(),
+        }
+    }
+}
+
+/// A CSV file being written into a sheet a batch of rows at a time, recalculating only
+/// once it's all in
+#[derive(Clone, Debug)]
+pub struct CsvImport {
+    pub sheet: SheetId,
+    rows: ImportRows,
+    /// The rows there's room for under `at`, past which the import stops
+    row_limit: usize,
+    at: Coords,
+    header: bool,
+    types: Vec<ColumnType>,
+    /// The first row not written yet
+    next_row: usize,
+    /// The most fields a row written had
+    column_count: usize,
+    /// Whether the rows ran out
+    done: bool,
+    /// Cells written whose display waits for the end: formulas, cells that held formulas,
+    /// and cells other formulas depend on
+    deferred: Vec<CellKey>,
+}
+
+impl CsvImport {
+    pub fn new(
+        sheet: SheetId,
+        rows: ImportRows,
+        row_limit: usize,
+        at: Coords,
+        header: bool,
+        types: Vec<ColumnType>,
+    ) -> Self {
+        CsvImport {
+            sheet,
+            rows,
+            row_limit,
+            at,
+            header,
+            types,
+            next_row: 0,
+            column_count: 0,
+            done: false,
+            deferred: Vec::new(),
+        }
+    }
+
+    pub fn rows_written(&self) -> usize {
+        self.next_row
+    }
+
+    /// How far the import has got, from 0 to 1: through the file, or through the rows
+    /// there's room for if that's further
+    pub fn progress(&self) -> f64 {
+        let read = match &self.rows {
+            ImportRows::Csv(records) => records.progress(),
+            ImportRows::Read(rows) => self.next_row as f64 / (self.next_row + rows.len()) as f64,
+        };
+        read.max(self.next_row as f64 / self.row_limit as f64)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done || self.next_row >= self.row_limit
+    }
+
+    fn column_type(&self, column: usize) -> ColumnType {
+        self.types.get(column).copied().unwrap_or(ColumnType::Text)
+    }
+}
+
+impl Grid {
+    /// Starts writing rows at a cell of the active sheet, with their first batch
+    pub fn start_import(
+        &mut self,
+        rows: ImportRows,
+        at: Coords,
+        header: bool,
+        types: Vec<ColumnType>,
+    ) {
+        let row_limit = (self.sheet().row_heights.len() as i32 - at.row).max(0) as usize;
+        let sheet = self.view.active_sheet;
+        self.view.import = Some(CsvImport::new(sheet, rows, row_limit, at, header, types));
+        self.continue_import();
+    }
+
+    /// Writes the next batch of rows of the import, finishing it after the last or once it
+    /// reaches the sheet's last row
+    pub fn continue_import(&mut self) {
+        let Some(mut import) = self.view.import.take() else {
+            return;
+        };
+        let Some(sheet) = self.workbook.get_sheet(import.sheet) else {
+            self.view.undo_history.finish(&self.workbook);
+            return;
+        };
+        let column_count = sheet.column_widths.len() as i32;
+        let end = (import.next_row + IMPORT_BATCH_ROWS).min(import.row_limit);
+        for i in import.next_row..end {
+            let Some(fields) = import.rows.next() else {
+                import.done = true;
+                break;
+            };
+            import.next_row = i + 1;
+            import.column_count = import.column_count.max(fields.len());
+            let is_header = import.header && i == 0;
+            for (column, field) in fields.into_iter().enumerate() {
+                let coords = Coords {
+                    row: import.at.row + i as i32,
+                    column: import.at.column + column as i32,
+                };
+                if coords.column >= column_count {
+                    break;
+                }
+                let column_type = import.column_type(column);
+                let content = match is_header {
+                    true => field,
+                    false => csv::convert_field(&field, column_type),
+                };
+                let key = CellKey {
+                    sheet: import.sheet,
+                    coords,
+                };
+                let depended_on = !self.workbook.get_cell_dependants(key).is_empty();
+                let Some(cell) = self.workbook.get_mut_cell(key) else {
+                    continue;
+                };
+                let was_formula = cell.content.starts_with('=');
+                if is_header {
+                    cell.format.bold = true;
+                } else if column_type == ColumnType::Text {
+                    cell.format.horizontal_align = Some(HorizontalAlign::Left);
+                }
+                // Empty cells may show what a formula spills into them
+                if was_formula || depended_on || content.is_empty() || content.starts_with('=') {
+                    import.deferred.push(key);
+                } else {
+                    cell.display_value = content.clone();
+                    cell.diagnostic = None;
+                }
+                cell.content = content;
+            }
+        }
+
+        match import.is_done() {
+            true => self.finish_import(import),
+            false => self.view.import = Some(import),
+        }
+    }
+
+    /// Writes all that's left of the import, no more than the rows its sheet has room for
+    pub fn complete_import(&mut self) {
+        while self.view.import.is_some() {
+            self.continue_import();
+        }
+    }
+
+    /// Stops the import where it got to, keeping the rows written so far
+    pub fn cancel_import(&mut self) {
+        if let Some(import) = self.view.import.take() {
+            self.finish_import(import);
+        }
+    }
+
+    fn finish_import(&mut self, import: CsvImport) {
+        for key in import.deferred {
+            self.update_cell_display(key);
+        }
+        self.view.undo_history.finish(&self.workbook);
+        if import.next_row == 0 || self.view.active_sheet != import.sheet {
+            return;
+        }
+        let sheet = self.sheet();
+        let extent = Coords {
+            row: import.at.row + import.next_row as i32 - 1,
+            column: (import.at.column + import.column_count.max(1) as i32 - 1)
+                .min(sheet.column_widths.len() as i32 - 1),
+        };
+        self.view.select(import.at);
+        self.view.extend_selection(extent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{command::GridCommand, grid::cell_address_to_coords, import::*};
+
+    fn display_value(grid: &Grid, address: &str) -> String {
+        let coords = cell_address_to_coords(address).unwrap();
+        grid.sheet().cells_map[&coords].display_value.clone()
+    }
+
+    #[test]
+    fn big_imports_recalculate_once_written() {
+        let mut grid = Grid::new(90, 25, 26, 2000);
+        grid.apply(GridCommand::SetCellContent {
+            coords: cell_address_to_coords("C1").unwrap(),
+            content: "=A1+A1200".to_string(),
+        });
+        let rows = (1..=1200)
+            .map(|n| vec![n.to_string(), "=A1*2".to_string()])
+            .collect::<Vec<_>>();
+        grid.apply(GridCommand::ImportCsv {
+            rows: rows.into(),
+            at: Coords { row: 0, column: 0 },
+            new_sheet: false,
+            header: false,
+            types: vec![ColumnType::Number, ColumnType::Text],
+        });
+        let import = grid.view.import.as_ref().unwrap();
+        assert_eq!(import.rows_written(), IMPORT_BATCH_ROWS);
+        assert_eq!(display_value(&grid, "A2"), "2");
+        // Formulas wait for the rest
+        assert_eq!(display_value(&grid, "C1"), "0");
+
+        while grid.view.import.is_some() {
+            grid.apply(GridCommand::ContinueImport);
+        }
+        assert_eq!(display_value(&grid, "B5"), "2");
+        assert_eq!(display_value(&grid, "C1"), "1201");
+    }
+
+    #[test]
+    fn imports_are_undone_in_one_step() {
+        let mut grid = Grid::new(90, 25, 26, 2000);
+        let [a1, a1200] = ["A1", "A1200"].map(|address| cell_address_to_coords(address).unwrap());
+        grid.apply(GridCommand::SetCellContent {
+            coords: cell_address_to_coords("C1").unwrap(),
+            content: "=A1+A1200".to_string(),
+        });
+        let rows = (1..=1200).map(|n| vec![n.to_string()]).collect::<Vec<_>>();
+        grid.apply(GridCommand::ImportCsv {
+            rows: rows.into(),
+            at: a1,
+            new_sheet: false,
+            header: false,
+            types: vec![ColumnType::Number],
+        });
+        // Nothing to go back to half way
+        grid.apply(GridCommand::Undo);
+        assert!(grid.view.import.is_some());
+        while grid.view.import.is_some() {
+            grid.apply(GridCommand::ContinueImport);
+        }
+        assert_eq!(display_value(&grid, "C1"), "1201");
+
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.sheet().get_cell_content(a1), "");
+        assert_eq!(grid.sheet().get_cell_content(a1200), "");
+        assert_eq!(display_value(&grid, "C1"), "0");
+        grid.apply(GridCommand::Redo);
+        assert_eq!(grid.sheet().get_cell_content(a1200), "1200");
+        assert_eq!(display_value(&grid, "C1"), "1201");
+        grid.apply(GridCommand::Undo);
+        grid.apply(GridCommand::Undo);
+        assert!(!grid.view.undo_history.can_undo());
+    }
+
+    #[test]
+    fn changes_during_an_import_are_steps_after_it() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let [a1, a80, d1] =
+            ["A1", "A80", "D1"].map(|address| cell_address_to_coords(address).unwrap());
+        let rows = (1..=80).map(|n| vec![n.to_string()]).collect::<Vec<_>>();
+        grid.apply(GridCommand::ImportCsv {
+            rows: rows.into(),
+            at: a1,
+            new_sheet: false,
+            header: false,
+            types: vec![ColumnType::Number],
+        });
+        grid.apply(GridCommand::SetCellContent {
+            coords: d1,
+            content: "x".to_string(),
+        });
+        assert!(grid.view.import.is_none());
+        assert_eq!(grid.sheet().get_cell_content(a80), "80");
+
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.sheet().get_cell_content(d1), "");
+        assert_eq!(grid.sheet().get_cell_content(a80), "80");
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.sheet().get_cell_content(a1), "");
+    }
+
+    #[test]
+    fn deleting_the_sheet_being_imported_to_keeps_undo_going() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        let first = grid.view.active_sheet;
+        let rows = (1..=80).map(|n| vec![n.to_string()]).collect::<Vec<_>>();
+        grid.apply(GridCommand::ImportCsv {
+            rows: rows.into(),
+            at: a1,
+            new_sheet: true,
+            header: false,
+            types: vec![ColumnType::Number],
+        });
+        let second = grid.view.active_sheet;
+        assert!(grid.view.import.is_some());
+        grid.apply(GridCommand::DeleteSheet {
+            sheet: second,
+            replace_with_values: false,
+        });
+        grid.apply(GridCommand::ContinueImport);
+        assert_eq!(grid.view.active_sheet, first);
+        assert!(!grid.view.undo_history.is_recording());
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "1".to_string(),
+        });
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.sheet().get_cell_content(a1), "");
+    }
+
+    #[test]
+    fn cancelled_imports_keep_what_was_written() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let rows = (0..80).map(|n| vec![n.to_string()]).collect::<Vec<_>>();
+        grid.apply(GridCommand::ImportCsv {
+            rows: rows.into(),
+            at: Coords { row: 0, column: 0 },
+            new_sheet: false,
+            header: false,
+            types: vec![ColumnType::Number],
+        });
+        grid.apply(GridCommand::CancelImport);
+        assert!(grid.view.import.is_none());
+        let last = Coords {
+            row: IMPORT_BATCH_ROWS as i32 - 1,
+            column: 0,
+        };
+        assert_eq!(
+            grid.sheet().cells_map[&last].display_value,
+            last.row.to_string()
+        );
+        assert!(!grid.sheet().cells_map.contains_key(&Coords {
+            row: last.row + 1,
+            column: 0
+        }));
+        assert_eq!(grid.view.selection().active_range().bottom_right(), last);
+    }
+
+    #[test]
+    fn imports_stop_at_the_last_row() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let text = (1..=1000).map(|n| format!("{n},x\n")).collect::<String>();
+        grid.apply(GridCommand::ImportCsv {
+            rows: CsvRecords::new(text, ',').into(),
+            at: cell_address_to_coords("A61").unwrap(),
+            new_sheet: false,
+            header: false,
+            types: vec![ColumnType::Number, ColumnType::Text],
+        });
+        // Batches split the sheet
+        assert_eq!(
+            grid.view.import.as_ref().unwrap().rows_written(),
+            IMPORT_BATCH_ROWS
+        );
+        // The sheet's 40 rows under A61 fill up, and the rest of the file is left unread
+        grid.apply(GridCommand::ContinueImport);
+        assert!(grid.view.import.is_none());
+        assert_eq!(display_value(&grid, "A100"), "40");
+        assert_eq!(
+            grid.view.selection().active_range().bottom_right(),
+            cell_address_to_coords("B100").unwrap()
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::workbook::Sheet;
+
+/// How deep groups can nest, as in Excel
+pub const MAX_OUTLINE_LEVEL: u8 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutlineDirection {
+    Rows,
+    Columns,
+}
+
+/// Groups of rows or columns that collapse out of sight, nested by level. Level 1 groups
+/// are the outermost.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Outline {
+    /// The level of each row or column that's grouped
+    levels: BTreeMap<i32, u8>,
+    /// The groups collapsed, by level and first row or column
+    collapsed: BTreeSet<(u8, i32)>,
+    /// What the collapsed groups hide, kept as they change
+    hidden: BTreeSet<i32>,
+}
+
+/// A run of rows or columns at a level or deeper
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutlineGroup {
+    pub level: u8,
+    pub start: i32,
+    pub end: i32,
+}
+
+impl Outline {
+    pub fn level(&self, index: i32) -> u8 {
+        self.levels.get(&index).copied().unwrap_or(0)
+    }
+
+    pub fn max_level(&self) -> u8 {
+        self.levels.values().copied().max().unwrap_or(0)
+    }
+
+    pub fn is_hidden(&self, index: i32) -> bool {
+        self.hidden.contains(&index)
+    }
+
+    pub fn is_collapsed(&self, group: &OutlineGroup) -> bool {
+        self.collapsed.contains(&(group.level, group.start))
+    }
+
+    /// Every group, outer ones before those nested in them
+    pub fn groups(&self) -> Vec<OutlineGroup> {
+        let mut groups = Vec::new();
+        for level in 1..=self.max_level() {
+            let mut current: Option<OutlineGroup> = None;
+            for (index, _) in self.levels.iter().filter(|(_, l)| **l >= level) {
+                if let Some(group) = current.as_mut().filter(|group| group.end + 1 == *index) {
+                    group.end = *index;
+                    continue;
+                }
+                groups.extend(current.replace(OutlineGroup {
+                    level,
+                    start: *index,
+                    end: *index,
+                }));
+            }
+            groups.extend(current);
+        }
+        groups
+    }
+
+    /// The groups whose +/- button goes on a header: the one just past each group, or the one
+    /// just before if the group runs to the last of `count`
+    pub fn groups_toggled_at(&self, index: i32, count: i32) -> Vec<OutlineGroup> {
+        self.groups()
+            .into_iter()
+            .filter(|group| match group.end + 1 < count {
+                true => group.end + 1 == index,
+                false => group.start - 1 == index,
+            })
+            .collect()
+    }
+
+    /// Groups the rows or columns one level deeper, up to `MAX_OUTLINE_LEVEL`
+    pub fn group(&mut self, start: i32, end: i32) {
+        for index in start..=end {
+            let level = self.levels.entry(index).or_insert(0);
+            *level = (*level + 1).min(MAX_OUTLINE_LEVEL);
+        }
+        self.refresh();
+    }
+
+    /// Takes the rows or columns out of their innermost group
+    pub fn ungroup(&mut self, start: i32, end: i32) {
+        for index in start..=end {
+            if let Some(level) = self.levels.get_mut(&index) {
+                *level -= 1;
+                if *level == 0 {
+                    self.levels.remove(&index);
+                }
+            }
+        }
+        self.refresh();
+    }
+
+    pub fn clear(&mut self) {
+        *self = Outline::default();
+    }
+
+    /// Collapses the group at a level starting at a row or column, or expands it. Groups
+    /// inside it stay as they were.
+    pub fn toggle(&mut self, level: u8, start: i32) {
+        if !self.collapsed.remove(&(level, start)) {
+            self.collapsed.insert((level, start));
+        }
+        self.refresh();
+    }
+
+    /// Shows the groups above a level, collapsing the rest. Past the deepest level, every
+    /// group is expanded.
+    pub fn show_level(&mut self, level: u8) {
+        self.collapsed = self
+            .groups()
+            .iter()
+            .filter(|group| group.level >= level)
+            .map(|group| (group.level, group.start))
+            .collect();
+        self.refresh();
+    }
+
+    /// Forgets collapsed groups that changed shape and works out what's hidden
+    fn refresh(&mut self) {
+        let groups = self.groups();
+        self.collapsed.retain(|(level, start)| {
+            groups
+                .iter()
+                .any(|group| group.level == *level && group.start == *start)
+        });
+        self.hidden = groups
+            .iter()
+            .filter(|group| self.is_collapsed(group))
+            .flat_map(|group| group.start..=group.end)
+            .collect();
+    }
+}
+
+impl Sheet {
+    pub fn outline(&self, direction: OutlineDirection) -> &Outline {
+        match direction {
+            OutlineDirection::Rows => &self.row_outline,
+            OutlineDirection::Columns => &self.column_outline,
+        }
+    }
+    pub fn outline_mut(&mut self, direction: OutlineDirection) -> &mut Outline {
+        match direction {
+            OutlineDirection::Rows => &mut self.row_outline,
+            OutlineDirection::Columns => &mut self.column_outline,
+        }
+    }
+
+    pub fn is_column_hidden(&self, column: i32) -> bool {
+        self.column_outline.is_hidden(column)
+    }
+
+    /// The width a column takes up on screen, none if it's hidden
+    pub fn displayed_column_width(&self, column: usize) -> i32 {
+        match self.is_column_hidden(column as i32) {
+            true => 0,
+            false => self.column_widths.get(column).copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::outline::*;
+
+    #[test]
+    fn nested_groups_collapse_separately() {
+        let mut outline = Outline::default();
+        outline.group(2, 9);
+        outline.group(3, 5);
+        assert_eq!(outline.max_level(), 2);
+        let groups = outline.groups();
+        assert_eq!(
+            groups,
+            [
+                OutlineGroup {
+                    level: 1,
+                    start: 2,
+                    end: 9
+                },
+                OutlineGroup {
+                    level: 2,
+                    start: 3,
+                    end: 5
+                },
+            ]
+        );
+
+        outline.toggle(2, 3);
+        assert!(outline.is_hidden(4) && !outline.is_hidden(6));
+        outline.toggle(1, 2);
+        assert!(outline.is_hidden(9));
+        // The inner group stays collapsed when the outer one opens
+        outline.toggle(1, 2);
+        assert!(outline.is_hidden(4) && !outline.is_hidden(9));
+
+        outline.show_level(3);
+        assert!(!outline.is_hidden(4));
+        outline.show_level(1);
+        assert!((2..=9).all(|index| outline.is_hidden(index)));
+
+        // Groups that change shape open up
+        outline.ungroup(2, 9);
+        assert_eq!(outline.max_level(), 1);
+        assert!(!outline.is_hidden(4));
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{grid::Grid, server::ServerConnection, view::CellRange};
+
+/// Distinct colors collaborators' cursors are drawn in
+pub const COLLABORATOR_COLORS: usize = 6;
+
+/// Seconds a collaborator stays shown after they were last heard from
+#[cfg(feature = "server")]
+pub const PRESENCE_TIMEOUT_SECS: u64 = 15;
+
+/// A workbook on a server being worked on along with whoever else has it open
+#[derive(Clone, Debug, PartialEq)]
+pub struct Collaboration {
+    pub connection: ServerConnection,
+    /// The name the workbook is stored under
+    pub workbook: String,
+    /// Tells this session apart from others of the same name
+    pub id: String,
+}
+
+/// Where someone working on a shared workbook is: their selection on a sheet
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Presence {
+    pub id: String,
+    pub name: String,
+    /// The sheet's name, as ids differ between sessions
+    pub sheet: String,
+    pub ranges: Vec<CellRange>,
+}
+
+impl Presence {
+    /// The same collaborator keeps the same color in every session
+    pub fn color(&self) -> usize {
+        let hash = self.id.bytes().fold(0usize, |hash, byte| {
+            hash.wrapping_mul(31).wrapping_add(byte as usize)
+        });
+        hash % COLLABORATOR_COLORS
+    }
+}
+
+impl Grid {
+    /// Where this session is, for collaborators to be shown, None if nothing's shared
+    pub fn presence(&self) -> Option<Presence> {
+        let collaboration = self.view.collaboration.as_ref()?;
+        Some(Presence {
+            id: collaboration.id.clone(),
+            name: collaboration.connection.user_name.clone(),
+            sheet: self.sheet().name.clone(),
+            ranges: self.view.selection().ranges,
+        })
+    }
+
+    /// Collaborators on the active sheet
+    pub fn collaborators_on_sheet(&self) -> Vec<&Presence> {
+        let sheet = &self.sheet().name;
+        self.view
+            .collaborators
+            .iter()
+            .filter(|presence| presence.sheet == *sheet)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{grid::Coords, presence::*, server::ServerConnection};
+
+    #[test]
+    fn collaborators_are_shown_on_their_sheet() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        assert_eq!(grid.presence(), None);
+
+        grid.view.collaboration = Some(Collaboration {
+            connection: ServerConnection {
+                user_name: "Ada".to_string(),
+                ..Default::default()
+            },
+            workbook: "Budget".to_string(),
+            id: "a1".to_string(),
+        });
+        grid.view.select(Coords { row: 2, column: 1 });
+        let presence = grid.presence().unwrap();
+        assert_eq!(presence.name, "Ada");
+        assert_eq!(presence.sheet, grid.sheet().name);
+        assert_eq!(
+            presence.ranges,
+            vec![CellRange::cell(Coords { row: 2, column: 1 })]
+        );
+
+        let elsewhere = Presence {
+            sheet: "Elsewhere".to_string(),
+            ..presence.clone()
+        };
+        grid.view.collaborators = vec![presence.clone(), elsewhere];
+        assert_eq!(grid.collaborators_on_sheet(), vec![&presence]);
+    }
+}
@@ -0,0 +1,438 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    clipboard::escape_html,
+    grid::{Coords, Grid},
+    view::CellRange,
+};
+
+/// CSS pixels in a millimeter, at the 96 pixels an inch browsers print at
+const PIXELS_PER_MM: f64 = 96.0 / 25.4;
+
+/// Height of a page's header or footer line, taken from the space for cells when it has text
+const HEADER_FOOTER_HEIGHT: f64 = 24.0;
+
+/// What a header or footer token is replaced with, the page number, the number of pages, the
+/// date printed on and the sheet's name
+pub const HEADER_FOOTER_TOKENS: [&str; 4] = ["&[Page]", "&[Pages]", "&[Date]", "&[Sheet]"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A3,
+    Letter,
+    Legal,
+}
+
+pub const PAGE_SIZES: [(PageSize, &str); 4] = [
+    (PageSize::A4, "A4"),
+    (PageSize::A3, "A3"),
+    (PageSize::Letter, "Letter"),
+    (PageSize::Legal, "Legal"),
+];
+
+impl PageSize {
+    /// Width and height in portrait, in millimeters
+    fn dimensions(&self) -> (f64, f64) {
+        match self {
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::A3 => (297.0, 420.0),
+            PageSize::Letter => (215.9, 279.4),
+            PageSize::Legal => (215.9, 355.6),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// How much the cells are shrunk to fit fewer pages. They're never enlarged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Scaling {
+    #[default]
+    ActualSize,
+    /// Every column on one page across
+    FitWidth,
+    /// Everything on one page
+    FitPage,
+}
+
+pub const SCALINGS: [(Scaling, &str); 3] = [
+    (Scaling::ActualSize, "Actual size"),
+    (Scaling::FitWidth, "Fit all columns on one page"),
+    (Scaling::FitPage, "Fit sheet on one page"),
+];
+
+/// Page margins in millimeters
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Margins {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Margins {
+            top: 19.0,
+            right: 18.0,
+            bottom: 19.0,
+            left: 18.0,
+        }
+    }
+}
+
+/// Text at the left, center and right of the top or bottom of every page, with tokens like
+/// `&[Page]` filled in for each
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeaderFooter {
+    pub left: String,
+    pub center: String,
+    pub right: String,
+}
+
+impl HeaderFooter {
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty() && self.center.is_empty() && self.right.is_empty()
+    }
+
+    /// The three parts as printed on a page
+    fn fill(&self, page: usize, pages: usize, date: &str, sheet: &str) -> [String; 3] {
+        let values = [
+            page.to_string(),
+            pages.to_string(),
+            date.to_string(),
+            sheet.to_string(),
+        ];
+        [&self.left, &self.center, &self.right].map(|text| {
+            let mut text = text.clone();
+            for (token, value) in HEADER_FOOTER_TOKENS.iter().zip(&values) {
+                text = text.replace(token, value);
+            }
+            text
+        })
+    }
+}
+
+/// How a sheet's cells are laid out on printed pages
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrintSettings {
+    pub page_size: PageSize,
+    pub orientation: Orientation,
+    pub margins: Margins,
+    pub scaling: Scaling,
+    /// Rows from the top of what's printed, printed again at the top of every page
+    pub repeat_rows: u32,
+    /// The range printed, the cells in use from A1 if None
+    pub print_area: Option<CellRange>,
+    pub header: HeaderFooter,
+    pub footer: HeaderFooter,
+}
+
+impl PrintSettings {
+    /// Width and height of the page as it's turned, in millimeters
+    pub fn page_dimensions(&self) -> (f64, f64) {
+        let (width, height) = self.page_size.dimensions();
+        match self.orientation {
+            Orientation::Portrait => (width, height),
+            Orientation::Landscape => (height, width),
+        }
+    }
+
+    /// Width and height of the area within the margins, in CSS pixels
+    pub fn printable_size(&self) -> (f64, f64) {
+        let (width, height) = self.page_dimensions();
+        let margins = &self.margins;
+        (
+            (width - margins.left - margins.right).max(1.0) * PIXELS_PER_MM,
+            (height - margins.top - margins.bottom).max(1.0) * PIXELS_PER_MM,
+        )
+    }
+
+    /// Width and height of the area for cells, within the margins and between the header and
+    /// footer, in CSS pixels
+    fn cells_size(&self) -> (f64, f64) {
+        let (width, mut height) = self.printable_size();
+        for header_footer in [&self.header, &self.footer] {
+            if !header_footer.is_empty() {
+                height -= HEADER_FOOTER_HEIGHT;
+            }
+        }
+        (width, height.max(1.0))
+    }
+}
+
+/// The rows and columns printed on a page, in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintPage {
+    pub rows: Vec<i32>,
+    pub columns: Vec<i32>,
+}
+
+/// The pages a sheet prints on, down then across, and how much its cells are shrunk on them
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintLayout {
+    pub pages: Vec<PrintPage>,
+    pub scale: f64,
+}
+
+impl Grid {
+    /// Paginates the active sheet's print area, or its cells in use from A1, leaving out
+    /// hidden rows and columns
+    pub fn print_layout(&self, settings: &PrintSettings) -> PrintLayout {
+        let sheet = self.sheet();
+        let area = settings.print_area.unwrap_or(CellRange {
+            anchor: Coords { row: 0, column: 0 },
+            extent: self.get_used_range_end(),
+        });
+        let (top_left, bottom_right) = (area.top_left(), area.bottom_right());
+        let rows = (top_left.row..=bottom_right.row)
+            .filter(|row| !sheet.is_row_hidden(*row))
+            .filter_map(|row| Some((row, *sheet.row_heights.get(row as usize)? as f64)))
+            .collect::<Vec<_>>();
+        let columns = (top_left.column..=bottom_right.column)
+            .filter(|column| !sheet.is_column_hidden(*column))
+            .filter_map(|column| Some((column, *sheet.column_widths.get(column as usize)? as f64)))
+            .collect::<Vec<_>>();
+
+        let (width, height) = settings.cells_size();
+        let total = |lines: &[(i32, f64)]| lines.iter().map(|(_, size)| size).sum::<f64>();
+        let (total_width, total_height) = (total(&columns), total(&rows));
+        let scale = match settings.scaling {
+            Scaling::ActualSize => 1.0,
+            Scaling::FitWidth => width / total_width,
+            Scaling::FitPage => (width / total_width).min(height / total_height),
+        }
+        .min(1.0);
+        let scaled = |lines: Vec<(i32, f64)>| {
+            lines
+                .into_iter()
+                .map(|(line, size)| (line, size * scale))
+                .collect::<Vec<_>>()
+        };
+        let (rows, columns) = (scaled(rows), scaled(columns));
+
+        // Header rows taller than a page aren't repeated
+        let mut repeated = rows
+            .iter()
+            .take_while(|(row, _)| ((*row - top_left.row) as u32) < settings.repeat_rows)
+            .copied()
+            .collect::<Vec<_>>();
+        if total(&repeated) >= height {
+            repeated.clear();
+        }
+        let row_bands = paginate(&rows, height, height - total(&repeated));
+        let column_bands = paginate(&columns, width, width);
+
+        let pages = column_bands
+            .iter()
+            .flat_map(|columns| {
+                row_bands.iter().enumerate().map(|(index, rows)| {
+                    let header = match index {
+                        0 => &[][..],
+                        _ => &repeated[..],
+                    };
+                    PrintPage {
+                        rows: header
+                            .iter()
+                            .map(|(row, _)| *row)
+                            .chain(rows.clone())
+                            .collect(),
+                        columns: columns.clone(),
+                    }
+                })
+            })
+            .collect();
+        PrintLayout { pages, scale }
+    }
+
+    /// A document of the active sheet's pages, for the browser to print or save as a PDF, with
+    /// `date` for the `&[Date]` of headers and footers. On screen the pages are shown at half
+    /// size with their margins, as a preview.
+    pub fn print_html(&self, settings: &PrintSettings, date: &str) -> String {
+        let layout = self.print_layout(settings);
+        let (page_width, page_height) = settings.page_dimensions();
+        let margins = &settings.margins;
+        let margin = format!(
+            "{}mm {}mm {}mm {}mm",
+            margins.top, margins.right, margins.bottom, margins.left
+        );
+        let sheet = self.sheet();
+        let page_count = layout.pages.len();
+        let header_footer = |class: &str, header_footer: &HeaderFooter, page: usize| {
+            if header_footer.is_empty() {
+                return String::new();
+            }
+            let parts = header_footer
+                .fill(page, page_count, date, &sheet.name)
+                .map(|part| format!("<span>{}</span>", escape_html(&part)));
+            format!(r#"<div class="{}">{}</div>"#, class, parts.concat())
+        };
+        let pages = layout
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(index, page)| {
+                let width = page
+                    .columns
+                    .iter()
+                    .map(|column| sheet.column_widths[*column as usize])
+                    .sum::<i32>();
+                format!(
+                    r#"<div class="page">{}<div style="zoom: {}; width: {}px;">{}</div>{}</div>"#,
+                    header_footer("page-header", &settings.header, index + 1),
+                    layout.scale,
+                    width,
+                    self.html_table(&page.rows, &page.columns, false),
+                    header_footer("page-footer", &settings.footer, index + 1),
+                )
+            })
+            .collect::<String>();
+        format!(
+            r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>{}</title><style>
+@page {{ size: {}mm {}mm; margin: {}; }}
+body {{ margin: 0; }}
+table {{ table-layout: fixed; width: 100%; }}
+td {{ overflow: hidden; }}
+.page {{ display: flex; flex-direction: column; overflow: hidden; }}
+.page-header, .page-footer {{ display: flex; align-items: center; height: {}px; font: 12px sans-serif; }}
+.page-header span, .page-footer span {{ flex: 1; white-space: pre; }}
+.page-header span:nth-child(2), .page-footer span:nth-child(2) {{ text-align: center; }}
+.page-header span:last-child, .page-footer span:last-child {{ text-align: right; }}
+.page-footer {{ margin-top: auto; }}
+@media print {{
+.page {{ height: calc({}mm - 1px); break-after: page; }}
+.page:last-child {{ break-after: auto; }}
+}}
+@media screen {{
+body {{ zoom: 0.5; padding: 16px; background: #d8d8d8; }}
+.page {{ box-sizing: border-box; width: {}mm; height: {}mm; padding: {}; margin: 0 auto 16px; background: white; box-shadow: 0 1px 4px rgba(0, 0, 0, 0.3); }}
+}}
+</style></head><body>{}</body></html>"#,
+            escape_html(&sheet.name),
+            page_width,
+            page_height,
+            margin,
+            HEADER_FOOTER_HEIGHT,
+            page_height - margins.top - margins.bottom,
+            page_width,
+            page_height,
+            margin,
+            pages
+        )
+    }
+}
+
+/// Splits lines into runs that fit the space of a page, the first page having `first` and the
+/// others `rest`. A line bigger than a page gets one to itself.
+fn paginate(lines: &[(i32, f64)], first: f64, rest: f64) -> Vec<Vec<i32>> {
+    let mut pages: Vec<Vec<i32>> = Vec::new();
+    let mut used = 0.0;
+    for (line, size) in lines {
+        let space = match pages.len() {
+            0 | 1 => first,
+            _ => rest,
+        };
+        match pages.last_mut() {
+            // Rounding shouldn't push a line that fits onto the next page
+            Some(page) if used + size <= space + 0.01 => page.push(*line),
+            _ => {
+                pages.push(vec![*line]);
+                used = 0.0;
+            }
+        }
+        used += size;
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{command::GridCommand, grid::cell_address_to_coords, print::*};
+
+    #[test]
+    fn pages_go_down_then_across_repeating_header_rows() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        grid.apply(GridCommand::SetCellContents(vec![(
+            cell_address_to_coords("J60").unwrap(),
+            "end".to_string(),
+        )]));
+        let mut settings = PrintSettings {
+            repeat_rows: 1,
+            ..PrintSettings::default()
+        };
+        // A4 portrait fits 7 columns of 90px and 39 rows of 25px
+        let layout = grid.print_layout(&settings);
+        assert_eq!(layout.scale, 1.0);
+        assert_eq!(layout.pages.len(), 4);
+        assert_eq!(layout.pages[0].rows, (0..39).collect::<Vec<_>>());
+        assert_eq!(layout.pages[0].columns, (0..7).collect::<Vec<_>>());
+        assert_eq!(
+            layout.pages[1].rows,
+            std::iter::once(0).chain(39..60).collect::<Vec<_>>()
+        );
+        assert_eq!(layout.pages[2].columns, (7..10).collect::<Vec<_>>());
+
+        settings.scaling = Scaling::FitWidth;
+        let layout = grid.print_layout(&settings);
+        assert_eq!(layout.pages.len(), 2);
+        assert_eq!(layout.pages[1].columns, (0..10).collect::<Vec<_>>());
+
+        settings.scaling = Scaling::FitPage;
+        assert_eq!(grid.print_layout(&settings).pages.len(), 1);
+
+        settings.scaling = Scaling::ActualSize;
+        settings.orientation = Orientation::Landscape;
+        // 26 rows on the first page, then 25 under the header row
+        assert_eq!(grid.print_layout(&settings).pages.len(), 3);
+        let html = grid.print_html(&settings, "2026-10-16");
+        assert!(html.contains("size: 297mm 210mm"));
+        assert_eq!(html.matches(r#"class="page""#).count(), 3);
+    }
+
+    #[test]
+    fn print_areas_and_headers_and_footers() {
+        let mut grid = Grid::new(90, 25, 26, 100);
+        let coords = |address: &str| cell_address_to_coords(address).unwrap();
+        grid.apply(GridCommand::SetCellContents(vec![(
+            coords("J60"),
+            "end".to_string(),
+        )]));
+        let mut settings = PrintSettings {
+            print_area: Some(CellRange {
+                anchor: coords("C5"),
+                extent: coords("B2"),
+            }),
+            ..PrintSettings::default()
+        };
+        assert_eq!(
+            grid.print_layout(&settings).pages,
+            vec![PrintPage {
+                rows: vec![1, 2, 3, 4],
+                columns: vec![1, 2],
+            }]
+        );
+
+        // The header and footer take a line each from the 39 rows a page has
+        settings.print_area = Some(CellRange {
+            anchor: coords("A1"),
+            extent: coords("A60"),
+        });
+        settings.header.center = "&[Sheet]".to_string();
+        settings.footer.right = "Page &[Page] of &[Pages], &[Date]".to_string();
+        let layout = grid.print_layout(&settings);
+        assert_eq!(layout.pages.len(), 2);
+        assert_eq!(layout.pages[0].rows.len(), 37);
+        let html = grid.print_html(&settings, "2026-10-16");
+        assert!(html.contains("<span>Sheet1</span>"));
+        assert!(html.contains("<span>Page 2 of 2, 2026-10-16</span>"));
+    }
+}
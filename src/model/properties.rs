@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::parser::{self, TextArgument},
+    model::{grid::Grid, workbook::CellKey},
+};
+
+/// What a workbook is about and who wrote it, saved and exported with it
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentProperties {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    /// Any other properties, by name
+    pub custom: BTreeMap<String, String>,
+}
+
+impl DocumentProperties {
+    pub fn is_empty(&self) -> bool {
+        *self == DocumentProperties::default()
+    }
+
+    /// A property by name, ignoring case: `title`, `author`, `description` or a custom one
+    pub fn get(&self, name: &str) -> Option<&str> {
+        match name.to_lowercase().as_str() {
+            "title" => Some(&self.title),
+            "author" => Some(&self.author),
+            "description" => Some(&self.description),
+            name => self
+                .custom
+                .iter()
+                .find(|(custom, _)| custom.to_lowercase() == name)
+                .map(|(_, value)| value.as_str()),
+        }
+    }
+}
+
+/// Reads a formula as `INFO("name")` or `INFO(A1)`, returning the name of the property it asks
+/// for or the cell holding it. None if it's anything else.
+pub fn parse_info_call(content: &str) -> Option<TextArgument> {
+    let content = content.trim().strip_prefix('=')?.trim_start();
+    let (name, arguments) = content.split_once('(')?;
+    if !name.trim_end().eq_ignore_ascii_case("INFO") {
+        return None;
+    }
+    parser::parse_text_argument(arguments.trim_end().strip_suffix(')')?)
+}
+
+impl Grid {
+    /// Sets the workbook's properties and recalculates the formulas showing them
+    pub fn set_properties(&mut self, properties: DocumentProperties) {
+        self.workbook.properties = properties;
+        let showing = self
+            .workbook
+            .sheets
+            .iter()
+            .flat_map(|sheet| {
+                sheet
+                    .cells_map
+                    .iter()
+                    .filter(|(_, cell)| parse_info_call(&cell.content).is_some())
+                    .map(|(coords, _)| CellKey {
+                        sheet: sheet.id,
+                        coords: *coords,
+                    })
+            })
+            .collect::<Vec<_>>();
+        for key in showing {
+            self.update_cell_display(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{
+        command::GridCommand,
+        file::read_workbook,
+        grid::{cell_address_to_coords, Grid},
+        properties::*,
+    };
+
+    #[test]
+    fn info_shows_the_workbooks_properties() {
+        assert_eq!(
+            parse_info_call(r#"= info( "Title" )"#),
+            Some(TextArgument::Literal("Title".to_string()))
+        );
+        assert_eq!(
+            parse_info_call("=INFO(A1)"),
+            Some(TextArgument::Reference("A1".to_string()))
+        );
+        assert_eq!(parse_info_call("=INFO(A1 + 1)"), None);
+        assert_eq!(parse_info_call(r#"=INFORM("title")"#), None);
+
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let (a1, a2) = (
+            cell_address_to_coords("A1").unwrap(),
+            cell_address_to_coords("A2").unwrap(),
+        );
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, r#"=INFO("title")"#.to_string()),
+            (a2, r#"=INFO("Cost center")"#.to_string()),
+        ]));
+        assert_eq!(grid.sheet().cells_map[&a2].display_value, "#N/A");
+
+        let mut properties = DocumentProperties {
+            title: "Budget".to_string(),
+            ..Default::default()
+        };
+        properties
+            .custom
+            .insert("Cost Center".to_string(), "R&D".to_string());
+        grid.apply(GridCommand::SetProperties(properties.clone()));
+        assert_eq!(grid.sheet().cells_map[&a1].display_value, "Budget");
+        assert_eq!(grid.sheet().cells_map[&a2].display_value, "R&D");
+
+        let mut reopened = Grid::new(90, 25, 5, 5);
+        reopened.open_workbook(read_workbook(&grid.save_workbook()).unwrap());
+        assert_eq!(reopened.workbook.properties, properties);
+        assert_eq!(reopened.sheet().cells_map[&a1].display_value, "Budget");
+    }
+
+    #[test]
+    fn info_follows_the_cell_naming_the_property() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let [a1, b1, c1] =
+            ["A1", "B1", "C1"].map(|address| cell_address_to_coords(address).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "author".to_string()),
+            (b1, "=INFO(A1)".to_string()),
+            (c1, "=B1".to_string()),
+        ]));
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "");
+
+        grid.apply(GridCommand::SetProperties(DocumentProperties {
+            title: "Budget".to_string(),
+            author: "Ada".to_string(),
+            ..Default::default()
+        }));
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "Ada");
+        assert_eq!(grid.sheet().cells_map[&c1].display_value, "Ada");
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "title".to_string(),
+        });
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "Budget");
+        assert_eq!(grid.sheet().cells_map[&c1].display_value, "Budget");
+    }
+}
@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    command::GridCommand,
+    grid::{coords_to_cell_address, Coords, Grid},
+    view::CellRange,
+    workbook::Sheet,
+};
+
+const LOCKED_MESSAGE: &str = "Locked cells can't be changed while the sheet is protected";
+
+/// Cells that can be edited on the protected sheet, or can't, whatever their own lock says
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProtectedRange {
+    pub range: CellRange,
+    /// Open to edits while the sheet is protected, rather than kept from them
+    pub editable: bool,
+    /// Shown when an edit is turned away, e.g. what to ask for to change the range
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+impl ProtectedRange {
+    pub fn address(&self) -> String {
+        format!(
+            "{}:{}",
+            coords_to_cell_address(self.range.top_left()),
+            coords_to_cell_address(self.range.bottom_right())
+        )
+    }
+}
+
+impl Sheet {
+    /// The range deciding whether a cell is locked, the latest one if ranges overlap
+    pub fn protected_range_at(&self, coords: Coords) -> Option<&ProtectedRange> {
+        self.protected_ranges
+            .iter()
+            .rev()
+            .find(|protected| protected.range.contains(coords))
+    }
+
+    /// Whether a cell can't be edited while the sheet is protected. Cells are locked unless
+    /// unlocked in their format or in an editable range.
+    pub fn is_locked(&self, coords: Coords) -> bool {
+        match self.protected_range_at(coords) {
+            Some(protected) => !protected.editable,
+            None => self
+                .cells_map
+                .get(&coords)
+                .is_none_or(|cell| !cell.format.unlocked),
+        }
+    }
+
+    /// Why a locked cell can't be edited, with its range's description if it has one
+    fn locked_message(&self, coords: Coords) -> String {
+        match self.protected_range_at(coords) {
+            Some(protected) if !protected.description.is_empty() => {
+                format!(
+                    "{} is protected: {}",
+                    protected.address(),
+                    protected.description
+                )
+            }
+            _ => LOCKED_MESSAGE.to_string(),
+        }
+    }
+}
+
+impl Grid {
+    /// Why a command can't go ahead on the active sheet as it's protected, or None if it can:
+    /// only its unlocked cells can be edited. Anything else changing the sheet is turned away.
+    /// Pasting a cut is also turned away if the sheet it was cut from is protected.
+    pub fn protection_refusal(&self, command: &GridCommand) -> Option<String> {
+        if matches!(command, GridCommand::Paste | GridCommand::PasteSpecial(_)) {
+            if let Some(message) = self.cut_source_refusal() {
+                return Some(message);
+            }
+        }
+        if !self.sheet().protected {
+            return None;
+        }
+        let cells = match command {
+            GridCommand::StartEditing { .. } | GridCommand::SetFormulaBarEditing(true) => {
+                vec![self.current_cell()]
+            }
+            GridCommand::EditContent { coords, .. }
+            | GridCommand::CommitEdit(coords)
+            | GridCommand::SetCellContent { coords, .. } => vec![*coords],
+            GridCommand::SetCellContents(contents) => {
+                contents.iter().map(|(coords, _)| *coords).collect()
+            }
+            GridCommand::ClearContents
+            | GridCommand::ToggleFontStyle(_)
+            | GridCommand::SetFontFamily(_)
+            | GridCommand::SetFontSize(_)
+            | GridCommand::SetNumberFormat(_)
+            | GridCommand::ChangeDecimals(_)
+            | GridCommand::SetColor { .. }
+            | GridCommand::SetHorizontalAlign(_)
+            | GridCommand::SetVerticalAlign(_)
+            | GridCommand::SetTextOrientation { .. }
+            | GridCommand::CleanData(_) => self.view.selection().cells(),
+            GridCommand::Paste => self.paste_target(false),
+            GridCommand::PasteSpecial(options) => self.paste_target(options.transpose),
+            GridCommand::TakeBackEntry => self
+                .view
+                .validation_alert
+                .iter()
+                .filter(|alert| alert.key.sheet == self.view.active_sheet)
+                .map(|alert| alert.key.coords)
+                .collect(),
+            // Rows pasted in are set as contents, which is checked then
+            GridCommand::PasteRows(_)
+            // The workbook, other sheets, and what's only kept along with the sheet
+            | GridCommand::ImportSheets(_)
+            | GridCommand::ImportCsv { new_sheet: true, .. }
+            | GridCommand::ContinueImport
+            | GridCommand::OpenWorkbook(_)
+            | GridCommand::AddSheets(_)
+            | GridCommand::RestoreVersion(_)
+            | GridCommand::NewWorkbook
+            | GridCommand::AddSheet
+            | GridCommand::RenameSheet { .. }
+            | GridCommand::MoveSheet { .. }
+            | GridCommand::SetProperties(_)
+            | GridCommand::SetRefreshInterval { .. }
+            | GridCommand::SetPrintSettings(_)
+            | GridCommand::AddComment { .. }
+            | GridCommand::SetCommentResolved { .. }
+            | GridCommand::DeleteCommentThread(_)
+            | GridCommand::SetSheetProtection(_)
+            // Puts the workbook back as it was, protection and all
+            | GridCommand::Undo
+            | GridCommand::Redo => return None,
+            command => {
+                return command
+                    .changes_document()
+                    .then(|| LOCKED_MESSAGE.to_string())
+            }
+        };
+        cells
+            .into_iter()
+            .find(|coords| self.sheet().is_locked(*coords))
+            .map(|coords| self.sheet().locked_message(coords))
+    }
+
+    /// The cells pasting would write to
+    fn paste_target(&self, transpose: bool) -> Vec<Coords> {
+        let Some(clipboard) = &self.view.clipboard else {
+            return Vec::new();
+        };
+        let target = self.view.selection().active_range().top_left();
+        let (rows, columns) = match transpose {
+            true => (clipboard.columns, clipboard.rows),
+            false => (clipboard.rows, clipboard.columns),
+        };
+        (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| Coords {
+                    row: target.row + row,
+                    column: target.column + column,
+                })
+            })
+            .collect()
+    }
+
+    /// Why the cells cut can't be emptied, being locked on the sheet they were cut from,
+    /// whichever sheet they're pasted on
+    fn cut_source_refusal(&self) -> Option<String> {
+        let clipboard = self
+            .view
+            .clipboard
+            .as_ref()
+            .filter(|clipboard| clipboard.cut)?;
+        let sheet = self
+            .workbook
+            .get_sheet(clipboard.source.sheet)
+            .filter(|sheet| sheet.protected)?;
+        let source = clipboard.source.coords;
+        (0..clipboard.rows)
+            .flat_map(|row| {
+                (0..clipboard.columns).map(move |column| Coords {
+                    row: source.row + row,
+                    column: source.column + column,
+                })
+            })
+            .find(|coords| sheet.is_locked(*coords))
+            .map(|coords| sheet.locked_message(coords))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{grid::cell_address_to_coords, protection::*};
+
+    #[test]
+    fn protected_sheets_only_take_edits_to_unlocked_cells() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let (a1, b1) = (
+            cell_address_to_coords("A1").unwrap(),
+            cell_address_to_coords("B1").unwrap(),
+        );
+        grid.apply(GridCommand::SelectCell(b1));
+        grid.apply(GridCommand::SetCellsLocked(false));
+        grid.apply(GridCommand::SetSheetProtection(true));
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "1".to_string(),
+        });
+        assert_eq!(grid.sheet().get_cell_content(a1), "");
+        assert!(grid.view.protection_message.is_some());
+
+        grid.apply(GridCommand::DismissProtectionMessage);
+        grid.apply(GridCommand::SetCellContent {
+            coords: b1,
+            content: "2".to_string(),
+        });
+        assert_eq!(grid.sheet().get_cell_content(b1), "2");
+        assert!(grid.view.protection_message.is_none());
+
+        // Locking is part of what protection keeps as it is
+        grid.apply(GridCommand::SetCellsLocked(true));
+        assert!(!grid.sheet().is_locked(b1));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some("x".to_string()),
+        });
+        assert!(!grid.view.is_editing_cell);
+
+        grid.apply(GridCommand::SetSheetProtection(false));
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some("x".to_string()),
+        });
+        assert!(grid.view.is_editing_cell);
+    }
+
+    #[test]
+    fn unlocked_cells_can_be_typed_into_on_protected_sheets() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let b1 = cell_address_to_coords("B1").unwrap();
+        grid.apply(GridCommand::SelectCell(b1));
+        grid.apply(GridCommand::SetCellsLocked(false));
+        grid.apply(GridCommand::SetSheetProtection(true));
+
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some("4".to_string()),
+        });
+        grid.apply(GridCommand::EditContent {
+            coords: b1,
+            content: "42".to_string(),
+        });
+        grid.apply(GridCommand::CommitEdit(b1));
+        assert!(!grid.view.is_editing_cell);
+        assert!(grid.view.protection_message.is_none());
+        assert_eq!(grid.sheet().get_cell_content(b1), "42");
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "42");
+    }
+
+    #[test]
+    fn protected_ranges_override_the_cells_locks() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let (a1, b2, c3) = (
+            cell_address_to_coords("A1").unwrap(),
+            cell_address_to_coords("B2").unwrap(),
+            cell_address_to_coords("C3").unwrap(),
+        );
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(b2));
+        grid.apply(GridCommand::AddProtectedRange {
+            editable: true,
+            description: "Inputs".to_string(),
+        });
+        grid.apply(GridCommand::SelectCell(c3));
+        grid.apply(GridCommand::SetCellsLocked(false));
+        grid.apply(GridCommand::AddProtectedRange {
+            editable: false,
+            description: "Ask finance to change totals".to_string(),
+        });
+        grid.apply(GridCommand::SetSheetProtection(true));
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: b2,
+            content: "1".to_string(),
+        });
+        assert_eq!(grid.sheet().get_cell_content(b2), "1");
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: c3,
+            content: "2".to_string(),
+        });
+        assert_eq!(grid.sheet().get_cell_content(c3), "");
+        assert_eq!(
+            grid.view.protection_message.as_deref(),
+            Some("C3:C3 is protected: Ask finance to change totals")
+        );
+
+        // Ranges can't be changed while the sheet is protected
+        grid.apply(GridCommand::RemoveProtectedRange(1));
+        assert_eq!(grid.sheet().protected_ranges.len(), 2);
+    }
+
+    #[test]
+    fn cuts_from_protected_sheets_cant_be_pasted_elsewhere() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        let first = grid.view.active_sheet;
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "1".to_string(),
+        });
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::Cut);
+        grid.apply(GridCommand::SetSheetProtection(true));
+        grid.apply(GridCommand::AddSheet);
+
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::Paste);
+        assert!(grid.view.protection_message.is_some());
+        assert_eq!(grid.sheet().get_cell_content(a1), "");
+        grid.apply(GridCommand::ActivateSheet(first));
+        assert_eq!(grid.sheet().get_cell_content(a1), "1");
+    }
+}
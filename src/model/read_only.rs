@@ -0,0 +1,59 @@
+use crate::model::command::GridCommand;
+
+impl GridCommand {
+    /// Whether the command edits the workbook, which viewing it read-only turns away.
+    /// Selecting, moving around, searching and copying all still go ahead.
+    pub fn edits(&self) -> bool {
+        self.changes_document()
+            || matches!(
+                self,
+                GridCommand::StartEditing { .. }
+                    | GridCommand::EditContent { .. }
+                    | GridCommand::SetFormulaBarEditing(true)
+                    | GridCommand::Cut
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{
+        command::MoveDirection,
+        grid::{cell_address_to_coords, Grid},
+        read_only::*,
+    };
+
+    #[test]
+    fn read_only_grids_only_take_commands_that_dont_edit() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "1".to_string(),
+        });
+        let file = grid.workbook_file();
+        grid.apply(GridCommand::SetReadOnly(true));
+
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "2".to_string(),
+        });
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some("3".to_string()),
+        });
+        grid.apply(GridCommand::Cut);
+        assert_eq!(grid.sheet().get_cell_content(a1), "1");
+        assert!(!grid.view.is_editing_cell);
+        assert!(grid.view.clipboard.is_none());
+
+        grid.apply(GridCommand::MoveSelection(MoveDirection::Down));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::Copy);
+        assert!(grid.view.clipboard.is_some());
+
+        // Opening another workbook keeps it read-only
+        grid.apply(GridCommand::OpenWorkbook(file));
+        assert!(grid.view.read_only);
+        assert_eq!(grid.sheet().get_cell_content(a1), "1");
+    }
+}
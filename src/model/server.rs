@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use crate::model::file::FILE_EXTENSION;
+
+/// Path of the REST API workbooks are kept behind on the server
+pub const WORKBOOKS_API: &str = "/api/workbooks";
+
+/// Path of where collaborators on a stored workbook tell each other where they are
+pub const PRESENCE_API: &str = "/api/presence";
+
+/// The longest name a workbook can be stored under
+const MAX_NAME_LENGTH: usize = 100;
+
+/// A server workbooks are stored on, and the token it's been given to allow access
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConnection {
+    /// Its address, blank for the one the app is served from
+    pub url: String,
+    pub token: String,
+    /// What collaborators see this session as
+    pub user_name: String,
+}
+
+impl ServerConnection {
+    /// The URL of the list of workbooks, or of the one with a name
+    pub fn api_url(&self, name: Option<&str>) -> String {
+        let base = format!("{}{}", self.url.trim().trim_end_matches('/'), WORKBOOKS_API);
+        match name {
+            Some(name) => format!("{}/{}", base, encode_path_segment(name)),
+            None => base,
+        }
+    }
+    /// The URL collaborators on a stored workbook send their presence to
+    pub fn presence_url(&self, name: &str) -> String {
+        format!(
+            "{}{}/{}",
+            self.url.trim().trim_end_matches('/'),
+            PRESENCE_API,
+            encode_path_segment(name)
+        )
+    }
+}
+
+/// A workbook as the server lists it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkbookEntry {
+    pub name: String,
+    /// When it was last saved, in seconds since 1970-01-01
+    pub modified: u64,
+}
+
+/// Whether a workbook can be stored under a name: not blank, not too long, and without
+/// characters that would take its file out of the server's directory or that file systems
+/// refuse
+pub fn is_valid_workbook_name(name: &str) -> bool {
+    !name.trim().is_empty()
+        && name.trim() == name
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && !name.starts_with('.')
+        && !name
+            .chars()
+            .any(|c| c.is_control() || r#"\/:*?"<>|"#.contains(c))
+}
+
+/// The name of the file a workbook is kept in on the server
+#[cfg(feature = "server")]
+pub fn workbook_file_name(name: &str) -> String {
+    format!("{}.{}", name, FILE_EXTENSION)
+}
+
+/// Percent-encodes everything but unreserved characters
+fn encode_path_segment(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::server::*;
+
+    #[test]
+    fn workbook_names_stay_in_the_servers_directory() {
+        assert!(is_valid_workbook_name("Budget 2026"));
+        assert!(is_valid_workbook_name("Café"));
+        assert!(!is_valid_workbook_name(""));
+        assert!(!is_valid_workbook_name(" Budget"));
+        assert!(!is_valid_workbook_name("../secrets"));
+        assert!(!is_valid_workbook_name("a/b"));
+        assert!(!is_valid_workbook_name(".hidden"));
+        assert!(!is_valid_workbook_name(&"a".repeat(101)));
+    }
+
+    #[test]
+    fn api_urls_encode_the_workbook_name() {
+        let connection = ServerConnection {
+            url: "https://sheets.example.com/".to_string(),
+            token: String::new(),
+            user_name: String::new(),
+        };
+        assert_eq!(
+            connection.api_url(None),
+            "https://sheets.example.com/api/workbooks"
+        );
+        assert_eq!(
+            connection.api_url(Some("Q1 & Q2 café")),
+            "https://sheets.example.com/api/workbooks/Q1%20%26%20Q2%20caf%C3%A9"
+        );
+        assert_eq!(
+            ServerConnection::default().api_url(Some("a")),
+            "/api/workbooks/a"
+        );
+        assert_eq!(
+            connection.presence_url("Q1"),
+            "https://sheets.example.com/api/presence/Q1"
+        );
+    }
+}
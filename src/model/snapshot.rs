@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    file::WorkbookFile,
+    grid::{coords_to_cell_address, Coords},
+};
+
+/// The workbook as it was at some point, kept under a name to go back to or compare with
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    /// When it was taken, in seconds since 1970-01-01
+    pub timestamp: u64,
+    /// The workbook, as saved to a file
+    pub workbook: String,
+}
+
+/// A cell whose content differs between two versions of a workbook
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellChange {
+    pub sheet: String,
+    pub coords: Coords,
+    /// Blank if the cell was empty, or its sheet didn't exist
+    pub old: String,
+    pub new: String,
+}
+
+impl CellChange {
+    pub fn address(&self) -> String {
+        coords_to_cell_address(self.coords)
+    }
+    pub fn kind(&self) -> ChangeKind {
+        match (self.old.is_empty(), self.new.is_empty()) {
+            (true, _) => ChangeKind::Added,
+            (_, true) => ChangeKind::Removed,
+            _ => ChangeKind::Changed,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// The cells whose content differs from one version to the other, sheet by sheet as they're
+/// ordered in the newer one, then those only the older one has. Sheets are matched by name.
+pub fn diff_workbooks(old: &WorkbookFile, new: &WorkbookFile) -> Vec<CellChange> {
+    let contents = |file: &WorkbookFile, name: &str| {
+        file.sheets
+            .iter()
+            .find(|sheet| sheet.name == name)
+            .map(|sheet| {
+                sheet
+                    .cells
+                    .iter()
+                    .filter(|cell| !cell.content.is_empty())
+                    .map(|cell| {
+                        let coords = Coords {
+                            row: cell.row,
+                            column: cell.column,
+                        };
+                        (coords, cell.content.clone())
+                    })
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default()
+    };
+    let mut names = new
+        .sheets
+        .iter()
+        .map(|sheet| sheet.name.clone())
+        .collect::<Vec<_>>();
+    for sheet in &old.sheets {
+        if !names.contains(&sheet.name) {
+            names.push(sheet.name.clone());
+        }
+    }
+
+    let mut changes = Vec::new();
+    for name in names {
+        let (mut before, after) = (contents(old, &name), contents(new, &name));
+        let mut sheet_changes = Vec::new();
+        for (coords, content) in after {
+            let previous = before.remove(&coords).unwrap_or_default();
+            if previous != content {
+                sheet_changes.push((coords, previous, content));
+            }
+        }
+        for (coords, content) in before {
+            sheet_changes.push((coords, content, String::new()));
+        }
+        sheet_changes.sort_by_key(|(coords, _, _)| *coords);
+        changes.extend(
+            sheet_changes
+                .into_iter()
+                .map(|(coords, old, new)| CellChange {
+                    sheet: name.clone(),
+                    coords,
+                    old,
+                    new,
+                }),
+        );
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{command::GridCommand, grid::Grid, snapshot::*};
+
+    #[test]
+    fn diffs_list_added_removed_and_changed_cells() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let cell = |row, column| Coords { row, column };
+        grid.apply(GridCommand::SetCellContents(vec![
+            (cell(0, 0), "1".to_string()),
+            (cell(1, 0), "2".to_string()),
+            (cell(2, 0), "=A1+A2".to_string()),
+        ]));
+        let old = grid.workbook_file();
+
+        grid.apply(GridCommand::SetCellContents(vec![
+            (cell(1, 0), String::new()),
+            (cell(2, 0), "=A1*2".to_string()),
+            (cell(0, 1), "Total".to_string()),
+        ]));
+        let new = grid.workbook_file();
+
+        let changes = diff_workbooks(&old, &new);
+        let listed = changes
+            .iter()
+            .map(|change| (change.address(), change.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listed,
+            vec![
+                ("B1".to_string(), ChangeKind::Added),
+                ("A2".to_string(), ChangeKind::Removed),
+                ("A3".to_string(), ChangeKind::Changed),
+            ]
+        );
+        assert_eq!(changes[2].old, "=A1+A2");
+        assert!(diff_workbooks(&new, &new).is_empty());
+    }
+}
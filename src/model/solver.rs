@@ -0,0 +1,147 @@
+use crate::{
+    engine::solver::{self, Constraint, LinearProgram, Relation, SolveError},
+    model::{
+        command::GridCommand,
+        grid::{Coords, Grid},
+        view::CellRange,
+    },
+};
+
+/// What the Solver dialog asks for: values of the variable cells that make the objective cell
+/// as large or small as it can be while every constraint holds. Variables stay non-negative.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolverProblem {
+    pub objective: Coords,
+    pub maximize: bool,
+    pub variables: CellRange,
+    pub constraints: Vec<SolverConstraint>,
+}
+
+/// A cell's value kept at most, at least or exactly at a bound
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolverConstraint {
+    pub cell: Coords,
+    pub relation: Relation,
+    pub bound: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolverOutcome {
+    /// The variable cells hold the solution
+    Solved {
+        objective: f64,
+    },
+    Infeasible,
+    Unbounded,
+    /// The objective or a constraint doesn't change in proportion to the variables
+    NotLinear,
+    /// A cell the problem reads holds an error or text
+    InvalidValue,
+}
+
+impl Grid {
+    /// Solves a linear problem on the active sheet, writing the solution into the variable
+    /// cells. They're left as they were if there isn't one.
+    ///
+    /// The model is read off the formulas by setting the variables to zero and then to one at
+    /// a time, and checked against the grid at the solution.
+    pub fn solve(&mut self, problem: &SolverProblem) -> SolverOutcome {
+        let variables = problem.variables.cells().collect::<Vec<_>>();
+        let originals = variables
+            .iter()
+            .map(|coords| (*coords, self.sheet().get_cell_content(*coords)))
+            .collect::<Vec<_>>();
+
+        let outcome = self.find_solution(problem, &variables);
+        if !matches!(outcome, SolverOutcome::Solved { .. }) {
+            self.apply(GridCommand::SetCellContents(originals));
+        }
+        outcome
+    }
+
+    fn find_solution(&mut self, problem: &SolverProblem, variables: &[Coords]) -> SolverOutcome {
+        let Some(base) = self.evaluate_at(problem, variables, &vec![0.0; variables.len()]) else {
+            return SolverOutcome::InvalidValue;
+        };
+        // Column i holds how much each value moves per unit of variable i
+        let mut slopes = Vec::new();
+        for i in 0..variables.len() {
+            let mut unit = vec![0.0; variables.len()];
+            unit[i] = 1.0;
+            let Some(values) = self.evaluate_at(problem, variables, &unit) else {
+                return SolverOutcome::InvalidValue;
+            };
+            slopes.push(
+                values
+                    .iter()
+                    .zip(&base)
+                    .map(|(value, base)| value - base)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        let row = |index: usize| slopes.iter().map(|slope| slope[index]).collect::<Vec<_>>();
+        let program = LinearProgram {
+            objective: row(0),
+            maximize: problem.maximize,
+            constraints: problem
+                .constraints
+                .iter()
+                .enumerate()
+                .map(|(i, constraint)| Constraint {
+                    coefficients: row(i + 1),
+                    relation: constraint.relation,
+                    bound: constraint.bound - base[i + 1],
+                })
+                .collect(),
+        };
+
+        let solution = match solver::simplex(&program) {
+            Ok(solution) => solution
+                .into_iter()
+                .map(|value| (value * 1e9).round() / 1e9)
+                .collect::<Vec<_>>(),
+            Err(SolveError::Infeasible) => return SolverOutcome::Infeasible,
+            Err(SolveError::Unbounded) => return SolverOutcome::Unbounded,
+        };
+        let Some(values) = self.evaluate_at(problem, variables, &solution) else {
+            return SolverOutcome::InvalidValue;
+        };
+        let predicted = (0..values.len()).map(|index| {
+            base[index]
+                + row(index)
+                    .iter()
+                    .zip(&solution)
+                    .map(|(slope, value)| slope * value)
+                    .sum::<f64>()
+        });
+        let linear = values
+            .iter()
+            .zip(predicted)
+            .all(|(value, predicted)| (value - predicted).abs() <= 1e-6 * (1.0 + value.abs()));
+        match linear {
+            true => SolverOutcome::Solved {
+                objective: values[0],
+            },
+            false => SolverOutcome::NotLinear,
+        }
+    }
+
+    /// The objective's value then each constraint cell's, with the variables set to `values`
+    fn evaluate_at(
+        &mut self,
+        problem: &SolverProblem,
+        variables: &[Coords],
+        values: &[f64],
+    ) -> Option<Vec<f64>> {
+        let contents = variables
+            .iter()
+            .zip(values)
+            .map(|(coords, value)| (*coords, value.to_string()))
+            .collect();
+        self.apply(GridCommand::SetCellContents(contents));
+        std::iter::once(problem.objective)
+            .chain(problem.constraints.iter().map(|constraint| constraint.cell))
+            .map(|coords| self.workbook.get_cell_value(self.key(coords)).ok())
+            .collect()
+    }
+}
@@ -0,0 +1,51 @@
+use crate::model::{grid::Coords, view::CellRange, workbook::Sheet};
+
+/// The table a formula like QUERY returns, shown from its cell rightward and down
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spill {
+    pub values: Vec<Vec<String>>,
+    /// Whether cells with contents are in the way, leaving only `#SPILL!` in the formula's cell
+    pub blocked: bool,
+}
+
+impl Spill {
+    /// The cells the table covers when it spills from `anchor`
+    pub fn range(&self, anchor: Coords) -> CellRange {
+        let columns = self.values.iter().map(Vec::len).max().unwrap_or(1).max(1);
+        CellRange {
+            anchor,
+            extent: Coords {
+                row: anchor.row + self.values.len().max(1) as i32 - 1,
+                column: anchor.column + columns as i32 - 1,
+            },
+        }
+    }
+
+    pub fn value_at(&self, anchor: Coords, coords: Coords) -> Option<&str> {
+        self.values
+            .get((coords.row - anchor.row) as usize)?
+            .get((coords.column - anchor.column) as usize)
+            .map(String::as_str)
+    }
+}
+
+impl Sheet {
+    /// The formula cell whose table would cover `coords`, with its spill. A formula's own cell
+    /// isn't covered.
+    pub fn spill_covering(&self, coords: Coords) -> Option<(Coords, &Spill)> {
+        self.spills
+            .iter()
+            .find(|(anchor, spill)| **anchor != coords && spill.range(**anchor).contains(coords))
+            .map(|(anchor, spill)| (*anchor, spill))
+    }
+
+    /// What a spill shows in an empty cell, with the formula cell it comes from
+    pub fn spilled_value(&self, coords: Coords) -> Option<(Coords, String)> {
+        let (anchor, spill) = self.spill_covering(coords)?;
+        if spill.blocked {
+            return None;
+        }
+        let value = spill.value_at(anchor, coords).unwrap_or_default();
+        Some((anchor, value.to_string()))
+    }
+}
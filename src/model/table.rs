@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{grid::Coords, view::CellRange, workbook::Sheet};
+
+/// A range drawn as a table: a header row, banded data rows and possibly a totals row
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    /// From the header's first cell to the last row's last cell
+    pub range: CellRange,
+    pub style: TableStyle,
+    /// The last row holds totals rather than data
+    pub totals_row: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TableStyle {
+    Blue,
+    Green,
+    Orange,
+    Gray,
+}
+
+pub const TABLE_STYLES: [TableStyle; 4] = [
+    TableStyle::Blue,
+    TableStyle::Green,
+    TableStyle::Orange,
+    TableStyle::Gray,
+];
+
+impl TableStyle {
+    /// The header's background, also ruling off the totals row
+    pub fn accent(self) -> &'static str {
+        match self {
+            TableStyle::Blue => "#4472c4",
+            TableStyle::Green => "#70ad47",
+            TableStyle::Orange => "#ed7d31",
+            TableStyle::Gray => "#7f7f7f",
+        }
+    }
+
+    /// Translucent, so the bands read on light and dark themes alike
+    pub fn band(self) -> &'static str {
+        match self {
+            TableStyle::Blue => "rgba(68, 114, 196, 0.18)",
+            TableStyle::Green => "rgba(112, 173, 71, 0.18)",
+            TableStyle::Orange => "rgba(237, 125, 49, 0.18)",
+            TableStyle::Gray => "rgba(127, 127, 127, 0.18)",
+        }
+    }
+}
+
+/// How a table draws one of its cells, under the cell's own format
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableCellStyle {
+    pub background_color: Option<&'static str>,
+    pub text_color: Option<&'static str>,
+    pub bold: bool,
+    /// A line over the cell, above the totals row
+    pub top_rule: Option<&'static str>,
+}
+
+impl TableCellStyle {
+    pub fn to_style(&self) -> String {
+        let mut style = String::new();
+        if let Some(color) = self.background_color {
+            style.push_str(&format!("background-color: {};", color));
+        }
+        if let Some(color) = self.text_color {
+            style.push_str(&format!("color: {};", color));
+        }
+        if self.bold {
+            style.push_str("font-weight: bold;");
+        }
+        if let Some(color) = self.top_rule {
+            style.push_str(&format!("box-shadow: inset 0 2px 0 {};", color));
+        }
+        style
+    }
+}
+
+impl Table {
+    pub fn header_row(&self) -> i32 {
+        self.range.top_left().row
+    }
+
+    /// The rows between the header and the totals row
+    pub fn data_rows(&self) -> std::ops::RangeInclusive<i32> {
+        let last = self.range.bottom_right().row - self.totals_row as i32;
+        self.header_row() + 1..=last
+    }
+
+    pub fn columns(&self) -> std::ops::RangeInclusive<i32> {
+        self.range.top_left().column..=self.range.bottom_right().column
+    }
+}
+
+impl Sheet {
+    pub fn table_at(&self, coords: Coords) -> Option<usize> {
+        self.tables
+            .iter()
+            .position(|table| table.range.contains(coords))
+    }
+
+    pub fn table_styles(&self) -> HashMap<Coords, TableCellStyle> {
+        let mut styles = HashMap::new();
+        for table in &self.tables {
+            let style = table.style;
+            for coords in table.range.cells() {
+                let cell_style = if coords.row == table.header_row() {
+                    TableCellStyle {
+                        background_color: Some(style.accent()),
+                        text_color: Some("#ffffff"),
+                        bold: true,
+                        top_rule: None,
+                    }
+                } else if table.data_rows().contains(&coords.row) {
+                    // Every other row from the first data row is shaded
+                    let banded = (coords.row - table.header_row()) % 2 == 1;
+                    TableCellStyle {
+                        background_color: banded.then_some(style.band()),
+                        ..Default::default()
+                    }
+                } else {
+                    TableCellStyle {
+                        bold: true,
+                        top_rule: Some(style.accent()),
+                        ..Default::default()
+                    }
+                };
+                styles.insert(coords, cell_style);
+            }
+        }
+        styles
+    }
+
+    /// Grows tables over values entered in the row just below them, and shrinks them when
+    /// their last rows are emptied, down to one data row. Tables with a totals row have
+    /// nowhere to grow into, so they keep their size.
+    pub fn sync_tables(&mut self) {
+        for index in 0..self.tables.len() {
+            let table = &self.tables[index];
+            if table.totals_row {
+                continue;
+            }
+            let columns = table.columns();
+            let mut last = table.range.bottom_right().row;
+            while last + 1 < self.row_heights.len() as i32
+                && self.row_has_values(last + 1, columns.clone())
+                && !self.row_in_another_table(index, last + 1, columns.clone())
+            {
+                last += 1;
+            }
+            while last > table.header_row() + 1 && !self.row_has_values(last, columns.clone()) {
+                last -= 1;
+            }
+            let table = &mut self.tables[index];
+            table.range = CellRange {
+                anchor: table.range.top_left(),
+                extent: Coords {
+                    row: last,
+                    column: table.range.bottom_right().column,
+                },
+            };
+        }
+    }
+
+    fn row_in_another_table(
+        &self,
+        index: usize,
+        row: i32,
+        columns: std::ops::RangeInclusive<i32>,
+    ) -> bool {
+        columns
+            .into_iter()
+            .filter_map(|column| self.table_at(Coords { row, column }))
+            .any(|other| other != index)
+    }
+
+    fn row_has_values(&self, row: i32, columns: std::ops::RangeInclusive<i32>) -> bool {
+        columns.into_iter().any(|column| {
+            self.cells_map
+                .get(&Coords { row, column })
+                .is_some_and(|cell| !cell.content.is_empty())
+        })
+    }
+}
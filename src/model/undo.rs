@@ -0,0 +1,263 @@
+use crate::{
+    engine::remote,
+    model::{
+        command::GridCommand,
+        grid::Grid,
+        workbook::{CellKey, Workbook},
+    },
+};
+
+/// How many changes back undo can go
+const MAX_UNDO_STEPS: usize = 100;
+
+/// The workbook as it was before each change, to undo them, and after each undone one, to
+/// redo them. Oldest first.
+#[derive(Default)]
+pub struct UndoHistory {
+    undo: Vec<Workbook>,
+    redo: Vec<Workbook>,
+    /// The workbook before the change being made, so the commands it applies aren't steps of
+    /// their own
+    before: Option<Workbook>,
+}
+
+impl UndoHistory {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.before.is_some()
+    }
+
+    /// Holds on to the workbook as it was before the change starting now
+    pub fn start(&mut self, before: Workbook) {
+        self.before = Some(before);
+    }
+
+    /// Keeps the workbook from before the change as a step to undo, which can't be redone past
+    /// anymore. Nothing's kept if the change left the workbook as it was.
+    pub fn finish(&mut self, after: &Workbook) {
+        let Some(before) = self.before.take() else {
+            return;
+        };
+        if is_same_document(&before, after) {
+            return;
+        }
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+        self.redo.clear();
+    }
+}
+
+/// Whether two workbooks hold the same, leaving out the dependencies their cells make
+fn is_same_document(a: &Workbook, b: &Workbook) -> bool {
+    a.sheets == b.sheets
+        && a.properties == b.properties
+        && a.refresh_intervals == b.refresh_intervals
+}
+
+impl GridCommand {
+    /// Whether the command is a step of its own to undo. Anything that changes the document,
+    /// but undoing and redoing themselves.
+    pub fn is_undoable(&self) -> bool {
+        self.changes_document() && !matches!(self, GridCommand::Undo | GridCommand::Redo)
+    }
+}
+
+impl Grid {
+    /// Does nothing while an import is being written, its rows being all one step
+    pub fn undo(&mut self) {
+        if self.view.import.is_some() {
+            return;
+        }
+        if let Some(before) = self.view.undo_history.undo.pop() {
+            let after = self.restore(before);
+            self.view.undo_history.redo.push(after);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.view.import.is_some() {
+            return;
+        }
+        if let Some(after) = self.view.undo_history.redo.pop() {
+            let before = self.restore(after);
+            self.view.undo_history.undo.push(before);
+        }
+    }
+
+    /// The workbook as it was before a command. What's typed goes into the cell as it's
+    /// typed, so a committed edit's cell held what it did when editing started.
+    pub fn workbook_before(&self, command: &GridCommand) -> Workbook {
+        let mut workbook = self.workbook.clone();
+        if let GridCommand::CommitEdit(coords) = command {
+            let cell = workbook
+                .get_sheet_mut(self.view.active_sheet)
+                .and_then(|sheet| sheet.cells_map.get_mut(coords));
+            if let Some(cell) = cell {
+                cell.content = self.view.previous_content.clone();
+            }
+        }
+        workbook
+    }
+
+    /// Swaps in a kept workbook, returning the one it replaces
+    fn restore(&mut self, workbook: Workbook) -> Workbook {
+        let replaced = std::mem::replace(&mut self.workbook, workbook);
+        if self.workbook.get_sheet(self.view.active_sheet).is_none() {
+            self.view.active_sheet = self.workbook.sheets[0].id;
+        }
+        self.view.trace_arrows.clear();
+        self.view.circular_reference = None;
+        // What was fetched may have come in since the workbook was kept
+        let fetching = self
+            .workbook
+            .sheets
+            .iter()
+            .flat_map(|sheet| {
+                sheet
+                    .cells_map
+                    .iter()
+                    .filter(|(_, cell)| remote::parse_call(&cell.content).is_some())
+                    .map(|(coords, _)| CellKey {
+                        sheet: sheet.id,
+                        coords: *coords,
+                    })
+            })
+            .collect::<Vec<_>>();
+        for key in fetching {
+            self.update_cell_display(key);
+        }
+        replaced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{
+        grid::{cell_address_to_coords, Grid},
+        undo::*,
+    };
+
+    #[test]
+    fn undo_takes_back_a_change_and_redo_makes_it_again() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let (a1, b1) = (
+            cell_address_to_coords("A1").unwrap(),
+            cell_address_to_coords("B1").unwrap(),
+        );
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "1".to_string(),
+        });
+        grid.apply(GridCommand::SetCellContent {
+            coords: b1,
+            content: "=A1*2".to_string(),
+        });
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "5".to_string(),
+        });
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "10");
+
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.sheet().get_cell_content(a1), "1");
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "2");
+        grid.apply(GridCommand::Redo);
+        assert_eq!(grid.sheet().get_cell_content(a1), "5");
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "10");
+
+        // Dependencies come back with the cells
+        grid.apply(GridCommand::Undo);
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "3".to_string(),
+        });
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "6");
+        // A new change can't be redone past
+        assert!(!grid.view.undo_history.can_redo());
+    }
+
+    #[test]
+    fn changes_that_change_nothing_arent_steps() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "1".to_string(),
+        });
+        grid.apply(GridCommand::Undo);
+        assert!(!grid.view.undo_history.can_undo());
+        assert!(grid.view.undo_history.can_redo());
+
+        // Editing and committing the same content
+        grid.apply(GridCommand::StartEditing {
+            initial_content: None,
+        });
+        grid.apply(GridCommand::CommitEdit(a1));
+        assert!(!grid.view.undo_history.can_undo());
+        assert!(grid.view.undo_history.can_redo());
+    }
+
+    #[test]
+    fn clearing_a_selection_is_one_step() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let [a1, a2, b1] =
+            ["A1", "A2", "B1"].map(|address| cell_address_to_coords(address).unwrap());
+        grid.apply(GridCommand::SetCellContents(vec![
+            (a1, "1".to_string()),
+            (a2, "2".to_string()),
+            (b1, "=A1+A2".to_string()),
+        ]));
+        grid.apply(GridCommand::SelectCell(a1));
+        grid.apply(GridCommand::ExtendSelection(a2));
+        grid.apply(GridCommand::ClearContents);
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "0");
+
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.sheet().get_cell_content(a1), "1");
+        assert_eq!(grid.sheet().get_cell_content(a2), "2");
+        assert_eq!(grid.sheet().cells_map[&b1].display_value, "3");
+        assert!(grid.view.undo_history.can_undo());
+    }
+
+    #[test]
+    fn backspace_and_typing_is_one_step() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let a1 = cell_address_to_coords("A1").unwrap();
+        grid.apply(GridCommand::SetCellContent {
+            coords: a1,
+            content: "old".to_string(),
+        });
+        grid.apply(GridCommand::StartEditing {
+            initial_content: Some(String::new()),
+        });
+        grid.apply(GridCommand::EditContent {
+            coords: a1,
+            content: "new".to_string(),
+        });
+        grid.apply(GridCommand::CommitEdit(a1));
+        assert_eq!(grid.sheet().get_cell_content(a1), "new");
+
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.sheet().get_cell_content(a1), "old");
+    }
+
+    #[test]
+    fn undoing_an_added_sheet_goes_back_to_one_still_there() {
+        let mut grid = Grid::new(90, 25, 5, 5);
+        let first = grid.view.active_sheet;
+        grid.apply(GridCommand::AddSheet);
+        assert_ne!(grid.view.active_sheet, first);
+
+        grid.apply(GridCommand::Undo);
+        assert_eq!(grid.workbook.sheets.len(), 1);
+        assert_eq!(grid.view.active_sheet, first);
+    }
+}
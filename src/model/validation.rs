@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{date, editing, parser},
+    model::{
+        grid::{Coords, Grid},
+        view::CellRange,
+        workbook::{CellKey, Sheet},
+    },
+};
+
+/// A rule for what can be entered in the cells of a range. Blank cells always pass.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Validation {
+    pub range: CellRange,
+    pub rule: ValidationRule,
+    pub alert: AlertStyle,
+    /// Said instead of the default message when an entry breaks the rule
+    pub error_message: Option<String>,
+    /// Shown by a cell of the range while it's selected
+    pub prompt: Option<String>,
+}
+
+/// What happens to an entry breaking its rule. Kept entries are flagged on the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AlertStyle {
+    /// Turned away
+    Stop,
+    /// Kept unless taken back
+    Warning,
+    /// Kept, the message only tells
+    Information,
+}
+
+/// The message about an entry that broke its rule, until it's dismissed
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationAlert {
+    pub key: CellKey,
+    pub style: AlertStyle,
+    pub message: String,
+    /// What the cell held before, to take a warned entry back to
+    pub previous_content: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ValidationRule {
+    /// One of these values, ignoring case. The cell gets a dropdown to pick them from.
+    List(Vec<String>),
+    /// A number within the bounds
+    Number { min: Option<f64>, max: Option<f64> },
+    /// A date within the bounds, as serials
+    Date {
+        start: Option<f64>,
+        end: Option<f64>,
+    },
+    /// A formula written for the range's top-left cell, which holds when it isn't 0. Other
+    /// cells shift its relative references like a copy would.
+    Formula(String),
+}
+
+impl ValidationRule {
+    /// Whether a value passes the rule, for every kind but `Formula`
+    fn allows(&self, value: &str) -> bool {
+        let within = |number: f64, min: &Option<f64>, max: &Option<f64>| {
+            min.is_none_or(|min| number >= min) && max.is_none_or(|max| number <= max)
+        };
+        match self {
+            ValidationRule::List(values) => values
+                .iter()
+                .any(|allowed| allowed.trim().to_lowercase() == value.trim().to_lowercase()),
+            ValidationRule::Number { min, max } => value
+                .parse::<f64>()
+                .is_ok_and(|number| within(number, min, max)),
+            ValidationRule::Date { start, end } => {
+                date::serial_of(value).is_some_and(|serial| within(serial, start, end))
+            }
+            ValidationRule::Formula(_) => true,
+        }
+    }
+}
+
+impl Sheet {
+    /// The rule for a cell, the latest one if ranges overlap
+    pub fn validation_at(&self, coords: Coords) -> Option<&Validation> {
+        self.validations
+            .iter()
+            .rev()
+            .find(|validation| validation.range.contains(coords))
+    }
+}
+
+impl Grid {
+    /// Whether a cell of the active sheet holds what its rule allows
+    pub fn is_valid(&self, coords: Coords) -> bool {
+        let sheet = self.sheet();
+        let (Some(validation), Some(cell)) =
+            (sheet.validation_at(coords), sheet.cells_map.get(&coords))
+        else {
+            return true;
+        };
+        if cell.content.is_empty() {
+            return true;
+        }
+        let ValidationRule::Formula(formula) = &validation.rule else {
+            return validation.rule.allows(&cell.display_value);
+        };
+        let origin = validation.range.top_left();
+        let formula = editing::shift_references(
+            formula,
+            coords.row - origin.row,
+            coords.column - origin.column,
+        );
+        let resolver = self.workbook.resolver(self.view.active_sheet);
+        parser::calculate(&formula, &resolver).is_ok_and(|(value, _)| value != 0.0)
+    }
+
+    /// The cells of the active sheet breaking their rules, flagged on the grid
+    pub fn get_invalid_cells(&self) -> HashSet<Coords> {
+        self.sheet()
+            .validations
+            .iter()
+            .flat_map(|validation| validation.range.cells())
+            .filter(|coords| !self.is_valid(*coords))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::validation::*;
+
+    #[test]
+    fn rules_allow_values() {
+        let list = ValidationRule::List(vec!["Yes".to_string(), "No".to_string()]);
+        assert!(list.allows("yes"));
+        assert!(!list.allows("Maybe"));
+
+        let number = ValidationRule::Number {
+            min: Some(1.0),
+            max: Some(10.0),
+        };
+        assert!(number.allows("10"));
+        assert!(!number.allows("10.5"));
+        assert!(!number.allows("ten"));
+
+        let date = ValidationRule::Date {
+            start: date::serial_of("2024-01-01"),
+            end: None,
+        };
+        assert!(date.allows("2024-03-01"));
+        assert!(!date.allows("2023-12-31"));
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::remote::RemoteData,
+    model::{
+        clipboard::Clipboard,
+        grid::Coords,
+        import::CsvImport,
+        presence::{Collaboration, Presence},
+        solver::SolverOutcome,
+        undo::UndoHistory,
+        validation::ValidationAlert,
+        workbook::{CellKey, SheetId},
+    },
+};
+
+/// The zoom levels offered, in percent, from the smallest to the largest
+pub const ZOOM_LEVELS: [u16; 8] = [50, 75, 90, 100, 110, 125, 150, 200];
+
+/// How the workbook is being looked at and worked on: which sheet and cell are selected,
+/// edit, search and auditing state. None of it belongs to the document.
+pub struct ViewState {
+    pub active_sheet: SheetId,
+    /// Selection of each sheet, so switching back to a sheet returns to it
+    pub selections: HashMap<SheetId, Selection>,
+    /// First scrolling cell of each sheet with frozen panes,
+    /// the rows above and columns left of it stay in place
+    pub frozen_panes: HashMap<SheetId, Coords>,
+    /// How big the grid is drawn, in percent
+    pub zoom: u16,
+
+    pub previous_content: String,
+    pub is_editing_cell: bool,
+    pub is_editing_formula_bar: bool,
+    pub search_query: Option<String>,
+    pub trace_arrows: Vec<(CellKey, CellKey)>,
+    pub circular_reference: Option<Vec<CellKey>>,
+    pub validation_alert: Option<ValidationAlert>,
+    /// Why the last change was turned away by the sheet's protection
+    pub protection_message: Option<String>,
+    /// Only looking at and copying from the workbook, with nothing editable
+    pub read_only: bool,
+    /// How the last Solver run went
+    pub solver_outcome: Option<SolverOutcome>,
+    pub watched_cells: Vec<CellKey>,
+    pub clipboard: Option<Clipboard>,
+    /// Changes that can be undone and redone
+    pub undo_history: UndoHistory,
+    /// Per-cell recalculation stats, only collected while profiling is on
+    pub profile: Option<HashMap<CellKey, RecalcStats>>,
+    /// A CSV file still being written in
+    pub import: Option<CsvImport>,
+    /// Whether the workbook changed since it was started, opened or last saved
+    pub has_unsaved_changes: bool,
+    /// What formulas like IMPORTCSV fetched, by URL
+    pub remote_data: HashMap<String, RemoteData>,
+    /// The stored workbook shared with others working on it, if any
+    pub collaboration: Option<Collaboration>,
+    /// Where the others working on the shared workbook are
+    pub collaborators: Vec<Presence>,
+
+    pub base_header_column_width: i32,
+    pub base_header_row_height: i32,
+}
+
+impl ViewState {
+    pub fn new(
+        active_sheet: SheetId,
+        base_header_column_width: i32,
+        base_header_row_height: i32,
+    ) -> Self {
+        ViewState {
+            active_sheet,
+            selections: HashMap::new(),
+            frozen_panes: HashMap::new(),
+            zoom: 100,
+
+            previous_content: String::new(),
+            is_editing_cell: false,
+            is_editing_formula_bar: false,
+            search_query: None,
+            trace_arrows: Vec::new(),
+            circular_reference: None,
+            validation_alert: None,
+            protection_message: None,
+            read_only: false,
+            solver_outcome: None,
+            watched_cells: Vec::new(),
+            clipboard: None,
+            undo_history: UndoHistory::default(),
+            profile: None,
+            import: None,
+            has_unsaved_changes: false,
+            remote_data: HashMap::new(),
+            collaboration: None,
+            collaborators: Vec::new(),
+
+            base_header_column_width,
+            base_header_row_height,
+        }
+    }
+
+    pub fn selection(&self) -> Selection {
+        self.selections
+            .get(&self.active_sheet)
+            .cloned()
+            .unwrap_or_else(|| Selection::cell(Coords { row: 0, column: 0 }))
+    }
+    pub fn current_cell(&self) -> Coords {
+        self.selection().active_range().anchor
+    }
+    /// What the grid's sizes are multiplied by on screen
+    pub fn zoom_factor(&self) -> f64 {
+        self.zoom as f64 / 100.0
+    }
+    /// Zooms to a level, kept within the smallest and largest offered
+    pub fn set_zoom(&mut self, zoom: u16) {
+        self.zoom = zoom.clamp(ZOOM_LEVELS[0], ZOOM_LEVELS[ZOOM_LEVELS.len() - 1]);
+    }
+    /// URLs formulas asked for whose data hasn't come back yet, or is being fetched again
+    pub fn pending_urls(&self) -> Vec<String> {
+        let mut urls = self
+            .remote_data
+            .iter()
+            .filter(|(_, data)| matches!(data, RemoteData::Pending | RemoteData::Refreshing(_)))
+            .map(|(url, _)| url.clone())
+            .collect::<Vec<_>>();
+        urls.sort();
+        urls
+    }
+    /// Selects a single cell
+    pub fn select(&mut self, coords: Coords) {
+        self.selections
+            .insert(self.active_sheet, Selection::cell(coords));
+    }
+    /// Starts another range at `coords`, keeping the ones already selected (Ctrl+click)
+    pub fn add_to_selection(&mut self, coords: Coords) {
+        let mut selection = self.selection();
+        selection.ranges.push(CellRange::cell(coords));
+        self.selections.insert(self.active_sheet, selection);
+    }
+    /// Stretches the active range from its anchor to `coords`
+    pub fn extend_selection(&mut self, coords: Coords) {
+        let mut selection = self.selection();
+        if let Some(range) = selection.ranges.last_mut() {
+            range.extent = coords;
+        }
+        self.selections.insert(self.active_sheet, selection);
+    }
+
+    /// Frozen panes of the active sheet, if any
+    pub fn frozen_panes(&self) -> Option<Coords> {
+        self.frozen_panes.get(&self.active_sheet).copied()
+    }
+    /// Freezes the rows above and columns left of `coords`, unfreezing if that's none of them
+    pub fn freeze_panes(&mut self, coords: Coords) {
+        if coords.row > 0 || coords.column > 0 {
+            self.frozen_panes.insert(self.active_sheet, coords);
+        } else {
+            self.frozen_panes.remove(&self.active_sheet);
+        }
+    }
+
+    pub fn watch_cell(&mut self, key: CellKey) {
+        if !self.watched_cells.contains(&key) {
+            self.watched_cells.push(key);
+        }
+    }
+
+    pub fn add_trace_arrow(&mut self, from: CellKey, to: CellKey) {
+        if !self.trace_arrows.contains(&(from, to)) {
+            self.trace_arrows.push((from, to));
+        }
+    }
+
+    pub fn record_recalc(&mut self, key: CellKey, elapsed_ms: f64) {
+        if let Some(profile) = self.profile.as_mut() {
+            let stats = profile.entry(key).or_default();
+            stats.count += 1;
+            stats.total_ms += elapsed_ms;
+        }
+    }
+    /// Profiled cells, slowest first
+    pub fn get_recalc_hotspots(&self) -> Vec<(CellKey, RecalcStats)> {
+        let mut hotspots = self
+            .profile
+            .iter()
+            .flatten()
+            .map(|(key, stats)| (*key, *stats))
+            .collect::<Vec<_>>();
+        hotspots.sort_by(|(_, a), (_, b)| b.total_ms.total_cmp(&a.total_ms));
+        hotspots
+    }
+
+    /// Drops everything pointing into a sheet that no longer exists
+    pub fn forget_sheet(&mut self, sheet: SheetId) {
+        self.selections.remove(&sheet);
+        self.frozen_panes.remove(&sheet);
+        self.watched_cells.retain(|key| key.sheet != sheet);
+        self.trace_arrows
+            .retain(|(from, to)| from.sheet != sheet && to.sheet != sheet);
+        if let Some(profile) = self.profile.as_mut() {
+            profile.retain(|key, _| key.sheet != sheet);
+        }
+        if self
+            .clipboard
+            .as_ref()
+            .is_some_and(|clipboard| clipboard.source.sheet == sheet)
+        {
+            self.clipboard = None;
+        }
+        let cycle_on_sheet = self
+            .circular_reference
+            .as_ref()
+            .is_some_and(|path| path.iter().any(|key| key.sheet == sheet));
+        if cycle_on_sheet {
+            self.circular_reference = None;
+        }
+    }
+}
+
+/// One or more ranges, possibly disjoint. The last one is active, its anchor is the current cell.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Selection {
+    pub ranges: Vec<CellRange>,
+}
+
+impl Selection {
+    pub fn cell(coords: Coords) -> Self {
+        Selection {
+            ranges: vec![CellRange::cell(coords)],
+        }
+    }
+
+    /// Scattered cells, each row's runs of neighbouring cells as one range, the first cell
+    /// being the current one. None if there are no cells.
+    pub fn of_cells(cells: &[Coords]) -> Option<Self> {
+        let mut cells = cells.to_vec();
+        cells.sort();
+        cells.dedup();
+        let mut ranges: Vec<CellRange> = Vec::new();
+        for coords in cells {
+            match ranges.last_mut() {
+                Some(range)
+                    if range.extent.row == coords.row
+                        && range.extent.column + 1 == coords.column =>
+                {
+                    range.extent = coords
+                }
+                _ => ranges.push(CellRange::cell(coords)),
+            }
+        }
+        // The active range is the last one
+        if ranges.is_empty() {
+            return None;
+        }
+        ranges.rotate_left(1);
+        Some(Selection { ranges })
+    }
+
+    pub fn active_range(&self) -> CellRange {
+        *self
+            .ranges
+            .last()
+            .expect("a selection has at least one range")
+    }
+    pub fn is_single_cell(&self) -> bool {
+        self.ranges.len() == 1 && self.active_range().is_single_cell()
+    }
+    pub fn contains(&self, coords: Coords) -> bool {
+        self.ranges.iter().any(|range| range.contains(coords))
+    }
+    /// Every selected cell once, in row-major order, however the ranges overlap.
+    /// This is what actions on the selection operate over.
+    pub fn cells(&self) -> Vec<Coords> {
+        let mut cells = self
+            .ranges
+            .iter()
+            .flat_map(|range| range.cells())
+            .collect::<Vec<_>>();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+}
+
+/// A rectangle of cells spanned from the anchor to the extent
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellRange {
+    pub anchor: Coords,
+    pub extent: Coords,
+}
+
+impl CellRange {
+    pub fn cell(coords: Coords) -> Self {
+        CellRange {
+            anchor: coords,
+            extent: coords,
+        }
+    }
+
+    pub fn top_left(&self) -> Coords {
+        Coords {
+            row: self.anchor.row.min(self.extent.row),
+            column: self.anchor.column.min(self.extent.column),
+        }
+    }
+    pub fn bottom_right(&self) -> Coords {
+        Coords {
+            row: self.anchor.row.max(self.extent.row),
+            column: self.anchor.column.max(self.extent.column),
+        }
+    }
+    pub fn is_single_cell(&self) -> bool {
+        self.anchor == self.extent
+    }
+    pub fn contains(&self, coords: Coords) -> bool {
+        let (top_left, bottom_right) = (self.top_left(), self.bottom_right());
+        (top_left.row..=bottom_right.row).contains(&coords.row)
+            && (top_left.column..=bottom_right.column).contains(&coords.column)
+    }
+    pub fn intersects(&self, other: &CellRange) -> bool {
+        let (top_left, bottom_right) = (self.top_left(), self.bottom_right());
+        let (other_top_left, other_bottom_right) = (other.top_left(), other.bottom_right());
+        top_left.row <= other_bottom_right.row
+            && other_top_left.row <= bottom_right.row
+            && top_left.column <= other_bottom_right.column
+            && other_top_left.column <= bottom_right.column
+    }
+    pub fn cells(&self) -> impl Iterator<Item = Coords> {
+        let (top_left, bottom_right) = (self.top_left(), self.bottom_right());
+        (top_left.row..=bottom_right.row).flat_map(move |row| {
+            (top_left.column..=bottom_right.column).map(move |column| Coords { row, column })
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecalcStats {
+    pub count: u32,
+    pub total_ms: f64,
+}
@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+use crate::model::grid::Grid;
+
+/// Rows and columns drawn past each edge of the viewport, so that scrolling a little doesn't
+/// show blank cells before they're drawn
+pub const OVERSCAN: usize = 4;
+
+/// The part of the grid scrolled into view, in pixels from its top-left corner
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Viewport {
+    pub left: i32,
+    pub top: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// The rows and columns worth drawing for a viewport
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisibleCells {
+    pub rows: Range<usize>,
+    pub columns: Range<usize>,
+}
+
+impl Grid {
+    /// The rows and columns at least partly inside the viewport, and `OVERSCAN` more past
+    /// each edge. Frozen panes aren't counted, they're always in view.
+    pub fn visible_cells(&self, viewport: Viewport) -> VisibleCells {
+        VisibleCells {
+            rows: self.visible_rows(viewport),
+            columns: self.visible_columns(viewport),
+        }
+    }
+
+    /// The rows worth drawing for a viewport, their headers included
+    pub fn visible_rows(&self, viewport: Viewport) -> Range<usize> {
+        let sheet = self.sheet();
+        visible_span(
+            self.view.base_header_row_height,
+            (0..sheet.row_heights.len()).map(|row| sheet.displayed_row_height(row)),
+            viewport.top,
+            viewport.height,
+        )
+    }
+
+    /// The columns worth drawing for a viewport, their headers included
+    pub fn visible_columns(&self, viewport: Viewport) -> Range<usize> {
+        let sheet = self.sheet();
+        visible_span(
+            self.view.base_header_column_width,
+            (0..sheet.column_widths.len()).map(|column| sheet.displayed_column_width(column)),
+            viewport.left,
+            viewport.width,
+        )
+    }
+}
+
+/// Indices of the sizes laid end to end from `start` that overlap `from..from + length`,
+/// widened by the overscan
+fn visible_span(
+    start: i32,
+    sizes: impl Iterator<Item = i32>,
+    from: i32,
+    length: i32,
+) -> Range<usize> {
+    let mut position = start;
+    let mut span: Option<Range<usize>> = None;
+    let mut count = 0;
+    for (index, size) in sizes.enumerate() {
+        if position + size > from && position < from + length {
+            span = Some(span.map_or(index, |span| span.start)..index + 1);
+        }
+        position += size;
+        count = index + 1;
+    }
+    let span = span.unwrap_or(0..0);
+    span.start.saturating_sub(OVERSCAN)..(span.end + OVERSCAN).min(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{grid::Coords, viewport::*};
+
+    #[test]
+    fn visible_cells_are_those_in_view_and_a_margin() {
+        let grid = Grid::new(90, 25, 26, 100);
+        let (width, height) = (grid.sheet().column_widths[0], grid.sheet().row_heights[0]);
+        let rect = grid.get_cell_rect(Coords {
+            row: 50,
+            column: 10,
+        });
+        let visible = grid.visible_cells(Viewport {
+            left: rect.x,
+            top: rect.y,
+            width: 3 * width,
+            height: 10 * height,
+        });
+        assert_eq!(visible.rows, 50 - OVERSCAN..60 + OVERSCAN);
+        assert_eq!(visible.columns, 10 - OVERSCAN..13 + OVERSCAN);
+
+        // Up to the sheet's last row, from its first column
+        let rect = grid.get_cell_rect(Coords { row: 95, column: 0 });
+        let visible = grid.visible_cells(Viewport {
+            left: rect.x,
+            top: rect.y,
+            width: 3 * width,
+            height: 10 * height,
+        });
+        assert_eq!(visible.rows, 95 - OVERSCAN..100);
+        assert_eq!(visible.columns, 0..3 + OVERSCAN);
+    }
+}
@@ -0,0 +1,759 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+
+use petgraph::{
+    prelude::GraphMap,
+    Directed, Direction,
+    Direction::{Incoming, Outgoing},
+};
+
+use crate::{
+    engine::{
+        editing::{self, ReferenceSpan},
+        parser::CellResolver,
+    },
+    model::{
+        chart::Chart,
+        comment::CommentThread,
+        conditional::ConditionalFormat,
+        filter::AutoFilter,
+        grid::{cell_address_to_coords, coords_to_cell_address, Cell, Coords, GetCellValueError},
+        history::CellEdit,
+        outline::Outline,
+        print::PrintSettings,
+        properties::DocumentProperties,
+        protection::ProtectedRange,
+        spill::Spill,
+        table::Table,
+        validation::Validation,
+    },
+};
+
+pub type SheetId = u32;
+
+/// A cell anywhere in the workbook
+#[derive(Hash, Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct CellKey {
+    pub sheet: SheetId,
+    pub coords: Coords,
+}
+
+pub const MIN_COLUMN_WIDTH: i32 = 20;
+pub const MIN_ROW_HEIGHT: i32 = 15;
+
+#[derive(Clone, PartialEq)]
+pub struct Sheet {
+    pub id: SheetId,
+    pub name: String,
+    pub cells_map: HashMap<Coords, Cell>,
+    pub column_widths: Vec<i32>,
+    pub row_heights: Vec<i32>,
+    pub conditional_formats: Vec<ConditionalFormat>,
+    pub tables: Vec<Table>,
+    pub auto_filter: Option<AutoFilter>,
+    pub validations: Vec<Validation>,
+    pub comments: Vec<CommentThread>,
+    /// Changes made to each cell's content, oldest first
+    pub edit_history: HashMap<Coords, Vec<CellEdit>>,
+    /// The tables formulas return, by the formula's cell
+    pub spills: HashMap<Coords, Spill>,
+    pub charts: Vec<Chart>,
+    pub row_outline: Outline,
+    pub column_outline: Outline,
+    pub print_settings: PrintSettings,
+    /// Whether only unlocked cells can be edited
+    pub protected: bool,
+    /// Ranges locked or unlocked together while the sheet is protected
+    pub protected_ranges: Vec<ProtectedRange>,
+}
+
+impl Sheet {
+    pub fn get_cell_content(&self, coords: Coords) -> String {
+        self.cells_map
+            .get(&coords)
+            .map(|c| c.content.clone())
+            .unwrap_or_default()
+    }
+
+    /// Widths below `MIN_COLUMN_WIDTH` are clamped to it
+    pub fn set_column_width(&mut self, column: usize, width: i32) {
+        if let Some(w) = self.column_widths.get_mut(column) {
+            *w = width.max(MIN_COLUMN_WIDTH);
+        }
+    }
+    /// Heights below `MIN_ROW_HEIGHT` are clamped to it
+    pub fn set_row_height(&mut self, row: usize, height: i32) {
+        if let Some(h) = self.row_heights.get_mut(row) {
+            *h = height.max(MIN_ROW_HEIGHT);
+        }
+    }
+}
+
+struct SheetResolver<'a> {
+    workbook: &'a Workbook,
+    from: SheetId,
+}
+
+impl CellResolver for SheetResolver<'_> {
+    fn value(&self, reference: &str) -> Result<f64, GetCellValueError> {
+        self.workbook
+            .get_cell_value_by_reference(reference, self.from)
+    }
+
+    fn is_filtered(&self, reference: &str) -> bool {
+        let Ok(key) = self.workbook.resolve_reference(reference, self.from) else {
+            return false;
+        };
+        self.workbook
+            .get_sheet(key.sheet)
+            .is_some_and(|sheet| sheet.is_row_filtered(key.coords.row))
+    }
+
+    fn is_hidden(&self, reference: &str) -> bool {
+        let Ok(key) = self.workbook.resolve_reference(reference, self.from) else {
+            return false;
+        };
+        self.workbook
+            .get_sheet(key.sheet)
+            .is_some_and(|sheet| sheet.row_outline.is_hidden(key.coords.row))
+    }
+}
+
+/// The sheets of a document and the dependencies between their cells, which can cross sheets
+#[derive(Clone)]
+pub struct Workbook {
+    pub sheets: Vec<Sheet>,
+    pub cells_dep_graph: GraphMap<CellKey, (), Directed>,
+    /// Minutes between refreshes of the URLs formulas fetch from, for those on a schedule
+    pub refresh_intervals: BTreeMap<String, u32>,
+    pub properties: DocumentProperties,
+
+    next_sheet_id: SheetId,
+    column_width: i32,
+    row_height: i32,
+    column_count: usize,
+    row_count: usize,
+}
+
+impl Workbook {
+    pub fn new(column_width: i32, row_height: i32, column_count: usize, row_count: usize) -> Self {
+        let mut workbook = Workbook {
+            sheets: Vec::new(),
+            cells_dep_graph: GraphMap::new(),
+            refresh_intervals: BTreeMap::new(),
+            properties: DocumentProperties::default(),
+
+            next_sheet_id: 0,
+            column_width,
+            row_height,
+            column_count,
+            row_count,
+        };
+        workbook.add_sheet();
+        workbook
+    }
+
+    /// Appends an empty sheet named after the first free "SheetN"
+    pub fn add_sheet(&mut self) -> SheetId {
+        let id = self.next_sheet_id;
+        self.next_sheet_id += 1;
+        let name = (self.sheets.len() + 1..)
+            .map(|n| format!("Sheet{}", n))
+            .find(|name| self.get_sheet_by_name(name).is_none())
+            .unwrap();
+        self.sheets.push(Sheet {
+            id,
+            name,
+            cells_map: HashMap::new(),
+            column_widths: vec![self.column_width; self.column_count],
+            row_heights: vec![self.row_height; self.row_count],
+            conditional_formats: Vec::new(),
+            tables: Vec::new(),
+            auto_filter: None,
+            validations: Vec::new(),
+            comments: Vec::new(),
+            edit_history: HashMap::new(),
+            spills: HashMap::new(),
+            charts: Vec::new(),
+            row_outline: Outline::default(),
+            column_outline: Outline::default(),
+            print_settings: PrintSettings::default(),
+            protected: false,
+            protected_ranges: Vec::new(),
+        });
+        id
+    }
+
+    pub fn get_sheet(&self, id: SheetId) -> Option<&Sheet> {
+        self.sheets.iter().find(|sheet| sheet.id == id)
+    }
+    pub fn get_sheet_mut(&mut self, id: SheetId) -> Option<&mut Sheet> {
+        self.sheets.iter_mut().find(|sheet| sheet.id == id)
+    }
+    /// Sheet names are case insensitive, like in references
+    pub fn get_sheet_by_name(&self, name: &str) -> Option<&Sheet> {
+        self.sheets
+            .iter()
+            .find(|sheet| sheet.name.to_lowercase() == name.to_lowercase())
+    }
+
+    /// Names can't be blank, taken by another sheet or contain characters that would end a
+    /// quoted sheet reference
+    pub fn is_valid_sheet_name(&self, name: &str, sheet: SheetId) -> bool {
+        let name = name.trim();
+        !name.is_empty()
+            && !name.contains(['\'', '!'])
+            && self
+                .get_sheet_by_name(name)
+                .is_none_or(|other| other.id == sheet)
+    }
+
+    /// Renames a sheet and rewrites the references to it in every formula.
+    /// Returns false, changing nothing, if the name isn't valid.
+    pub fn rename_sheet(&mut self, id: SheetId, name: &str) -> bool {
+        let name = name.trim();
+        if !self.is_valid_sheet_name(name, id) {
+            return false;
+        }
+        let Some(sheet) = self.get_sheet_mut(id) else {
+            return false;
+        };
+        let old_name = std::mem::replace(&mut sheet.name, name.to_string());
+        let prefix = format!("{}!", quote_sheet_name(name));
+        for cell in self
+            .sheets
+            .iter_mut()
+            .flat_map(|sheet| sheet.cells_map.values_mut())
+        {
+            let content = &cell.content;
+            let renamed = editing::replace_references(content, |span| {
+                refers_to(span, &old_name)
+                    .then(|| format!("{}{}", prefix, &content[span.cell_start..span.end]))
+            });
+            cell.content = renamed;
+        }
+        true
+    }
+
+    pub fn move_sheet(&mut self, id: SheetId, to_index: usize) {
+        if let Some(index) = self.sheets.iter().position(|sheet| sheet.id == id) {
+            let sheet = self.sheets.remove(index);
+            let to_index = to_index.min(self.sheets.len());
+            self.sheets.insert(to_index, sheet);
+        }
+    }
+
+    /// Cells on other sheets whose formulas reference sheet `id`
+    pub fn get_inbound_references(&self, id: SheetId) -> Vec<CellKey> {
+        let Some(name) = self.get_sheet(id).map(|sheet| sheet.name.as_str()) else {
+            return Vec::new();
+        };
+        let mut inbound = self
+            .sheets
+            .iter()
+            .filter(|sheet| sheet.id != id)
+            .flat_map(|sheet| {
+                sheet
+                    .cells_map
+                    .iter()
+                    .filter(|(_, cell)| {
+                        editing::find_references(&cell.content)
+                            .iter()
+                            .any(|span| refers_to(span, name))
+                    })
+                    .map(|(coords, _)| CellKey {
+                        sheet: sheet.id,
+                        coords: *coords,
+                    })
+            })
+            .collect::<Vec<_>>();
+        inbound.sort();
+        inbound
+    }
+
+    /// Removes a sheet, rewriting references to it in other sheets' formulas with `#REF!`, or
+    /// with the referenced cell's current value if `replace_with_values` is set (ranges always
+    /// become `#REF!`). Returns the rewritten cells, which still need recalculating.
+    /// The last remaining sheet can't be deleted.
+    pub fn delete_sheet(&mut self, id: SheetId, replace_with_values: bool) -> Vec<CellKey> {
+        let Some(index) = self.sheets.iter().position(|sheet| sheet.id == id) else {
+            return Vec::new();
+        };
+        if self.sheets.len() == 1 {
+            return Vec::new();
+        }
+        let name = self.sheets[index].name.clone();
+        let inbound = self.get_inbound_references(id);
+        for key in &inbound {
+            let Some(content) = self.get_cell(*key).map(|cell| cell.content.clone()) else {
+                continue;
+            };
+            let rewritten = editing::replace_references(&content, |span| {
+                if !refers_to(span, &name) {
+                    return None;
+                }
+                let value = match replace_with_values && !span.is_range() {
+                    true => cell_address_to_coords(&span.first)
+                        .and_then(|coords| self.get_cell_value(CellKey { sheet: id, coords }).ok()),
+                    false => None,
+                };
+                Some(match value {
+                    Some(value) if value < 0.0 => format!("({})", value),
+                    Some(value) => value.to_string(),
+                    None => "#REF!".to_string(),
+                })
+            });
+            if let Some(cell) = self.get_mut_cell(*key) {
+                cell.content = rewritten;
+            }
+        }
+
+        self.sheets.remove(index);
+        let nodes = self
+            .cells_dep_graph
+            .nodes()
+            .filter(|node| node.sheet == id)
+            .collect::<Vec<_>>();
+        nodes.into_iter().for_each(|node| {
+            self.cells_dep_graph.remove_node(node);
+        });
+        inbound
+    }
+
+    /// Moves the `rows` x `columns` block of cells at `from` so its top-left cell lands on `to`,
+    /// possibly on another sheet. References anywhere to the moved cells follow them, formulas
+    /// in the block keep pointing where they did. Returns the cells to recalculate.
+    pub fn move_cells(
+        &mut self,
+        from: CellKey,
+        rows: i32,
+        columns: i32,
+        to: CellKey,
+    ) -> Vec<CellKey> {
+        let in_block = |key: CellKey| {
+            key.sheet == from.sheet
+                && (from.coords.row..from.coords.row + rows).contains(&key.coords.row)
+                && (from.coords.column..from.coords.column + columns).contains(&key.coords.column)
+        };
+        let (row_offset, column_offset) = (
+            to.coords.row - from.coords.row,
+            to.coords.column - from.coords.column,
+        );
+        let moved_key = |key: CellKey| match in_block(key) {
+            true => CellKey {
+                sheet: to.sheet,
+                coords: Coords {
+                    row: key.coords.row + row_offset,
+                    column: key.coords.column + column_offset,
+                },
+            },
+            false => key,
+        };
+
+        let mut rewritten = HashMap::new();
+        for sheet in &self.sheets {
+            for (coords, cell) in &sheet.cells_map {
+                let key = CellKey {
+                    sheet: sheet.id,
+                    coords: *coords,
+                };
+                let destination = moved_key(key).sheet;
+                let content = &cell.content;
+                let updated = editing::replace_references(content, |span| {
+                    let referenced = match &span.sheet {
+                        Some(name) => self.get_sheet_by_name(name)?.id,
+                        None => key.sheet,
+                    };
+                    let endpoints = [&span.first, &span.last].map(|address| {
+                        cell_address_to_coords(address).map(|coords| CellKey {
+                            sheet: referenced,
+                            coords,
+                        })
+                    });
+                    let follows = endpoints.iter().all(|key| key.is_some_and(&in_block));
+                    let (sheet, cells) = match follows {
+                        true => (
+                            to.sheet,
+                            editing::shift_span(span, row_offset, column_offset, false)?,
+                        ),
+                        false if referenced != destination && span.sheet.is_none() => {
+                            (referenced, content[span.cell_start..span.end].to_string())
+                        }
+                        false => return None,
+                    };
+                    if span.sheet.is_none() && sheet == destination {
+                        return Some(cells);
+                    }
+                    let name = quote_sheet_name(&self.get_sheet(sheet)?.name);
+                    Some(format!("{}!{}", name, cells))
+                });
+                if updated != *content {
+                    rewritten.insert(key, updated);
+                }
+            }
+        }
+
+        let moved = self
+            .sheets
+            .iter_mut()
+            .filter(|sheet| sheet.id == from.sheet)
+            .flat_map(|sheet| {
+                let keys = sheet
+                    .cells_map
+                    .keys()
+                    .filter(|coords| {
+                        in_block(CellKey {
+                            sheet: sheet.id,
+                            coords: **coords,
+                        })
+                    })
+                    .copied()
+                    .collect::<Vec<_>>();
+                keys.into_iter()
+                    .filter_map(|coords| sheet.cells_map.remove_entry(&coords))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let moved = moved
+            .into_iter()
+            .map(|(coords, mut cell)| {
+                let key = CellKey {
+                    sheet: from.sheet,
+                    coords,
+                };
+                if let Some(content) = rewritten.remove(&key) {
+                    cell.content = content;
+                }
+                (moved_key(key), cell)
+            })
+            .collect::<Vec<_>>();
+
+        // Whatever the block lands on is replaced, under its empty cells too, so its old
+        // formulas aren't rewritten back over the moved cells
+        let mut changed = Vec::new();
+        for row in to.coords.row..to.coords.row + rows {
+            for column in to.coords.column..to.coords.column + columns {
+                let key = CellKey {
+                    sheet: to.sheet,
+                    coords: Coords { row, column },
+                };
+                rewritten.remove(&key);
+                if let Some(sheet) = self.get_sheet_mut(key.sheet) {
+                    if let Some(cell) = sheet.cells_map.get_mut(&key.coords) {
+                        *cell = Cell::new();
+                    }
+                    changed.push(key);
+                }
+            }
+        }
+        for (destination, cell) in moved {
+            if let Some(sheet) = self.get_sheet_mut(destination.sheet) {
+                sheet.cells_map.insert(destination.coords, cell);
+            }
+        }
+        // Vacated cells recalculate as empty for whatever still depends on them
+        for row in from.coords.row..from.coords.row + rows {
+            for column in from.coords.column..from.coords.column + columns {
+                let key = CellKey {
+                    sheet: from.sheet,
+                    coords: Coords { row, column },
+                };
+                if !changed.contains(&key) {
+                    if let Some(cell) = self.get_mut_cell(key) {
+                        *cell = Cell::new();
+                    }
+                    changed.push(key);
+                }
+            }
+        }
+        for (key, content) in rewritten {
+            if let Some(cell) = self.get_mut_cell(key) {
+                cell.content = content;
+                changed.push(key);
+            }
+        }
+        changed
+    }
+
+    pub fn get_cell(&self, key: CellKey) -> Option<&Cell> {
+        self.get_sheet(key.sheet)?.cells_map.get(&key.coords)
+    }
+    /// The cell at `key`, created empty if it doesn't exist yet. None if the sheet doesn't exist.
+    pub fn get_mut_cell(&mut self, key: CellKey) -> Option<&mut Cell> {
+        let sheet = self.get_sheet_mut(key.sheet)?;
+        Some(sheet.cells_map.entry(key.coords).or_insert(Cell::new()))
+    }
+
+    pub fn get_cell_value(&self, key: CellKey) -> Result<f64, GetCellValueError> {
+        let sheet = self
+            .get_sheet(key.sheet)
+            .ok_or(GetCellValueError::UnknownSheet)?;
+        match sheet.cells_map.get(&key.coords) {
+            Some(cell) => match cell.display_value.parse() {
+                Ok(value) => Ok(value),
+                Err(_) => {
+                    if cell.content.is_empty() {
+                        return Ok(0.0);
+                    }
+                    match cell.display_value == "#REF!" {
+                        true => Err(GetCellValueError::CircularReference),
+                        false => Err(GetCellValueError::InvalidValue(cell.display_value.clone())),
+                    }
+                }
+            },
+            None => Ok(0.0),
+        }
+    }
+    /// Resolves a reference like `A1`, `Sheet2!A1` or `'My sheet'!A1` as written on sheet `from`
+    pub fn resolve_reference(
+        &self,
+        reference: &str,
+        from: SheetId,
+    ) -> Result<CellKey, GetCellValueError> {
+        let (sheet, address) = match reference.rsplit_once('!') {
+            Some((name, address)) => {
+                let sheet = self
+                    .get_sheet_by_name(name.trim_matches('\''))
+                    .ok_or(GetCellValueError::UnknownSheet)?;
+                (sheet.id, address)
+            }
+            None => (from, reference),
+        };
+        let coords = cell_address_to_coords(address).ok_or(GetCellValueError::InvalidAddress)?;
+        Ok(CellKey { sheet, coords })
+    }
+    pub fn get_cell_value_by_reference(
+        &self,
+        reference: &str,
+        from: SheetId,
+    ) -> Result<f64, GetCellValueError> {
+        self.get_cell_value(self.resolve_reference(reference, from)?)
+    }
+    /// Resolves the references of a formula on sheet `from`
+    pub fn resolver(&self, from: SheetId) -> impl CellResolver + '_ {
+        SheetResolver {
+            workbook: self,
+            from,
+        }
+    }
+    /// How `key` would be written in a formula on sheet `from`
+    pub fn format_reference(&self, key: CellKey, from: SheetId) -> String {
+        let address = coords_to_cell_address(key.coords);
+        match self.get_sheet(key.sheet) {
+            Some(sheet) if key.sheet != from => {
+                format!("{}!{}", quote_sheet_name(&sheet.name), address)
+            }
+            _ => address,
+        }
+    }
+
+    pub fn remove_cell_dependencies(&mut self, key: CellKey) {
+        self.cells_dep_graph
+            .edges_directed(key, Incoming)
+            .map(|(a, b, _)| (a, b))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .for_each(|(a, b)| {
+                self.cells_dep_graph.remove_edge(a, b);
+            });
+    }
+    pub fn get_cell_dependants(&self, key: CellKey) -> Vec<CellKey> {
+        self.cells_dep_graph
+            .edges_directed(key, Outgoing)
+            .map(|(_, b, _)| b)
+            .collect::<Vec<_>>()
+    }
+    pub fn get_cell_precedents(&self, key: CellKey) -> Vec<CellKey> {
+        self.cells_dep_graph
+            .edges_directed(key, Incoming)
+            .map(|(a, _, _)| a)
+            .collect::<Vec<_>>()
+    }
+    pub fn get_transitive_precedents(&self, key: CellKey) -> Vec<CellKey> {
+        self.get_transitive_neighbours(key, Incoming)
+    }
+    pub fn get_transitive_dependants(&self, key: CellKey) -> Vec<CellKey> {
+        self.get_transitive_neighbours(key, Outgoing)
+    }
+    fn get_transitive_neighbours(&self, key: CellKey, direction: Direction) -> Vec<CellKey> {
+        let mut visited: Vec<CellKey> = Vec::new();
+        let mut queue = VecDeque::from([key]);
+        while let Some(node) = queue.pop_front() {
+            for neighbour in self.cells_dep_graph.neighbors_directed(node, direction) {
+                if neighbour != key && !visited.contains(&neighbour) {
+                    visited.push(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        visited.sort();
+        visited
+    }
+    /// Cells that both feed into and depend on `key`, including itself
+    pub fn get_cycle_members(&self, key: CellKey) -> Vec<CellKey> {
+        let precedents = self.get_transitive_precedents(key);
+        let mut members = self
+            .get_transitive_dependants(key)
+            .into_iter()
+            .filter(|dependant| precedents.contains(dependant))
+            .collect::<Vec<_>>();
+        if !members.contains(&key) {
+            members.push(key);
+        }
+        members
+    }
+    /// Shortest chain of references from `key` back to itself, starting and ending with `key`
+    pub fn get_cycle_path(&self, key: CellKey) -> Option<Vec<CellKey>> {
+        let mut parents: HashMap<CellKey, CellKey> = HashMap::new();
+        let mut queue = VecDeque::from([key]);
+        while let Some(node) = queue.pop_front() {
+            for neighbour in self.cells_dep_graph.neighbors_directed(node, Outgoing) {
+                if neighbour == key {
+                    let mut path = vec![key];
+                    let mut current = node;
+                    while current != key {
+                        path.push(current);
+                        current = parents[&current];
+                    }
+                    path.push(key);
+                    path.reverse();
+                    return Some(path);
+                }
+                if let Entry::Vacant(entry) = parents.entry(neighbour) {
+                    entry.insert(node);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Sheet names that aren't plain identifiers have to be quoted in references
+pub fn quote_sheet_name(name: &str) -> String {
+    let is_plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    match is_plain {
+        true => name.to_string(),
+        false => format!("'{}'", name),
+    }
+}
+
+fn refers_to(span: &ReferenceSpan, sheet_name: &str) -> bool {
+    span.sheet
+        .as_ref()
+        .is_some_and(|name| name.to_lowercase() == sheet_name.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::workbook::*;
+
+    fn key(sheet: SheetId, address: &str) -> CellKey {
+        CellKey {
+            sheet,
+            coords: cell_address_to_coords(address).unwrap(),
+        }
+    }
+
+    fn set_cell(workbook: &mut Workbook, key: CellKey, content: &str) {
+        let cell = workbook.get_mut_cell(key).unwrap();
+        cell.content = content.to_string();
+        cell.display_value = content.to_string();
+    }
+
+    #[test]
+    fn rename_rewrites_references() {
+        let mut workbook = Workbook::new(90, 25, 26, 100);
+        let first = workbook.sheets[0].id;
+        let second = workbook.add_sheet();
+        set_cell(&mut workbook, key(first, "A1"), "=sheet2!a1 + b1");
+
+        assert!(!workbook.rename_sheet(second, "sheet1"));
+        assert!(!workbook.rename_sheet(second, "it's"));
+        assert!(workbook.rename_sheet(second, "Q1 totals"));
+        assert_eq!(
+            workbook.get_cell(key(first, "A1")).unwrap().content,
+            "='Q1 totals'!a1 + b1"
+        );
+        assert_eq!(
+            workbook.format_reference(key(second, "B2"), first),
+            "'Q1 totals'!B2"
+        );
+    }
+
+    #[test]
+    fn delete_rewrites_inbound_references() {
+        let mut workbook = Workbook::new(90, 25, 26, 100);
+        let first = workbook.sheets[0].id;
+        let second = workbook.add_sheet();
+        set_cell(&mut workbook, key(second, "A1"), "-4");
+        set_cell(&mut workbook, key(first, "A1"), "=Sheet2!A1 * 2");
+        set_cell(&mut workbook, key(first, "A2"), "=Sheet2!A1:B2 + Sheet2!A1");
+        workbook
+            .cells_dep_graph
+            .add_edge(key(second, "A1"), key(first, "A1"), ());
+
+        assert_eq!(
+            workbook.get_inbound_references(second),
+            vec![key(first, "A1"), key(first, "A2")]
+        );
+        workbook.delete_sheet(second, true);
+        assert_eq!(
+            workbook.get_cell(key(first, "A1")).unwrap().content,
+            "=(-4) * 2"
+        );
+        assert_eq!(
+            workbook.get_cell(key(first, "A2")).unwrap().content,
+            "=#REF! + (-4)"
+        );
+        assert_eq!(workbook.cells_dep_graph.node_count(), 1);
+        assert!(workbook.delete_sheet(first, false).is_empty());
+        assert_eq!(workbook.sheets.len(), 1);
+    }
+
+    #[test]
+    fn moved_cells_take_references_along() {
+        let mut workbook = Workbook::new(90, 25, 26, 100);
+        let sheet1 = workbook.sheets[0].id;
+        let sheet2 = workbook.add_sheet();
+        set_cell(&mut workbook, key(sheet1, "A1"), "1");
+        set_cell(&mut workbook, key(sheet1, "A2"), "=A1*2");
+        set_cell(&mut workbook, key(sheet1, "B1"), "=sum(A1:A2) + B2");
+        set_cell(&mut workbook, key(sheet1, "B2"), "=A1+$A$2+sum(A2:A3)");
+        set_cell(&mut workbook, key(sheet2, "A1"), "=Sheet1!A2");
+
+        workbook.move_cells(key(sheet1, "A1"), 2, 1, key(sheet1, "C5"));
+        let content = |workbook: &Workbook, k| workbook.get_cell(k).unwrap().content.clone();
+        assert_eq!(content(&workbook, key(sheet1, "C5")), "1");
+        assert_eq!(content(&workbook, key(sheet1, "C6")), "=C5*2");
+        assert_eq!(content(&workbook, key(sheet1, "B1")), "=sum(C5:C6) + B2");
+        // Ranges only partly moved stay put
+        assert_eq!(content(&workbook, key(sheet1, "B2")), "=C5+$C$6+sum(A2:A3)");
+        assert_eq!(content(&workbook, key(sheet2, "A1")), "=Sheet1!C6");
+        assert_eq!(content(&workbook, key(sheet1, "A1")), "");
+
+        workbook.move_cells(key(sheet1, "B1"), 1, 1, key(sheet2, "B1"));
+        assert_eq!(
+            content(&workbook, key(sheet2, "B1")),
+            "=sum(Sheet1!C5:C6) + Sheet1!B2"
+        );
+    }
+
+    #[test]
+    fn cycle_path_excludes_downstream_cells() {
+        let mut workbook = Workbook::new(90, 25, 26, 100);
+        let sheet = workbook.sheets[0].id;
+        let [a1, b1, c1, d1] = ["A1", "B1", "C1", "D1"].map(|a| key(sheet, a));
+        workbook.cells_dep_graph.add_edge(a1, b1, ());
+        workbook.cells_dep_graph.add_edge(b1, c1, ());
+        workbook.cells_dep_graph.add_edge(c1, a1, ());
+        workbook.cells_dep_graph.add_edge(c1, d1, ());
+
+        assert_eq!(workbook.get_cycle_path(b1), Some(vec![b1, c1, a1, b1]));
+        let mut members = workbook.get_cycle_members(a1);
+        members.sort();
+        assert_eq!(members, vec![a1, b1, c1]);
+        assert_eq!(workbook.get_cycle_path(d1), None);
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::Path,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    routing::{get, put},
+    Json, Router,
+};
+
+use crate::model::{
+    file::{read_workbook, FILE_EXTENSION},
+    presence::{Presence, PRESENCE_TIMEOUT_SECS},
+    server::{
+        is_valid_workbook_name, workbook_file_name, WorkbookEntry, PRESENCE_API, WORKBOOKS_API,
+    },
+};
+
+/// Who's working on each workbook, by name, and when each was last heard from
+type PresenceMap = HashMap<String, Vec<(Presence, Instant)>>;
+
+static PRESENCES: Mutex<Option<PresenceMap>> = Mutex::new(None);
+
+/// The directory workbooks are stored in, `SPREADSHEET_DIR` or `workbooks`
+fn workbooks_dir() -> PathBuf {
+    std::env::var("SPREADSHEET_DIR")
+        .unwrap_or_else(|_| "workbooks".to_string())
+        .into()
+}
+
+/// Lets through requests with the `SPREADSHEET_TOKEN` as their bearer token. Without one set,
+/// the API stays closed.
+fn authorize(headers: &HeaderMap) -> Result<(), StatusCode> {
+    let token = std::env::var("SPREADSHEET_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let given = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    match tokens_match(given.as_bytes(), token.as_bytes()) {
+        true => Ok(()),
+        false => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// Compares every byte whichever one differs, so how long a wrong guess takes doesn't tell
+/// how much of it was right
+fn tokens_match(given: &[u8], token: &[u8]) -> bool {
+    given.len() == token.len()
+        && given
+            .iter()
+            .zip(token)
+            .fold(0, |differences, (a, b)| differences | (a ^ b))
+            == 0
+}
+
+fn workbook_path(name: &str) -> Result<PathBuf, StatusCode> {
+    match is_valid_workbook_name(name) {
+        true => Ok(workbooks_dir().join(workbook_file_name(name))),
+        false => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// The REST API workbooks are listed, loaded, saved and deleted through:
+/// `GET /api/workbooks`, and `GET`, `PUT` and `DELETE` of `/api/workbooks/{name}`. Those
+/// working on one `PUT` their presence to `/api/presence/{name}`, getting back the others'.
+pub fn api_routes() -> Router {
+    Router::new()
+        .route(WORKBOOKS_API, get(list_workbooks))
+        .route(
+            &format!("{}/{{name}}", WORKBOOKS_API),
+            get(load_workbook)
+                .put(save_workbook)
+                .delete(delete_workbook),
+        )
+        .route(&format!("{}/{{name}}", PRESENCE_API), put(update_presence))
+}
+
+/// The stored workbooks, last saved first
+async fn list_workbooks(headers: HeaderMap) -> Result<Json<Vec<WorkbookEntry>>, StatusCode> {
+    authorize(&headers)?;
+    let mut workbooks = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(workbooks_dir()).await else {
+        return Ok(Json(workbooks));
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = file_name.strip_suffix(&format!(".{}", FILE_EXTENSION)) else {
+            continue;
+        };
+        let modified = entry
+            .metadata()
+            .await
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        workbooks.push(WorkbookEntry {
+            name: name.to_string(),
+            modified,
+        });
+    }
+    workbooks.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(Json(workbooks))
+}
+
+async fn load_workbook(Path(name): Path<String>, headers: HeaderMap) -> Result<String, StatusCode> {
+    authorize(&headers)?;
+    tokio::fs::read_to_string(workbook_path(&name)?)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)
+}
+
+/// Stores a workbook under a name, over any stored under it before. Anything that doesn't
+/// read as a workbook is refused.
+async fn save_workbook(
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<StatusCode, StatusCode> {
+    authorize(&headers)?;
+    let path = workbook_path(&name)?;
+    if read_workbook(&body).is_err() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let written = match tokio::fs::create_dir_all(workbooks_dir()).await {
+        Ok(()) => tokio::fs::write(path, body).await,
+        Err(e) => Err(e),
+    };
+    match written {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn delete_workbook(
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    authorize(&headers)?;
+    tokio::fs::remove_file(workbook_path(&name)?)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Keeps where someone working on a workbook is, answering with where everyone else who's
+/// been heard from lately is. Presences are only kept in memory.
+async fn update_presence(
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(presence): Json<Presence>,
+) -> Result<Json<Vec<Presence>>, StatusCode> {
+    authorize(&headers)?;
+    if !is_valid_workbook_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let now = Instant::now();
+    let timeout = Duration::from_secs(PRESENCE_TIMEOUT_SECS);
+    let mut presences = PRESENCES.lock().unwrap();
+    let workbooks = presences.get_or_insert_with(HashMap::new);
+    for present in workbooks.values_mut() {
+        present.retain(|(_, seen)| now.duration_since(*seen) < timeout);
+    }
+    workbooks.retain(|_, present| !present.is_empty());
+
+    let present = workbooks.entry(name).or_default();
+    let others = present
+        .iter()
+        .filter(|(other, _)| other.id != presence.id)
+        .map(|(other, _)| other.clone())
+        .collect();
+    present.retain(|(other, _)| other.id != presence.id);
+    present.push((presence, now));
+    Ok(Json(others))
+}